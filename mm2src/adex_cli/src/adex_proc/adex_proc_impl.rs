@@ -9,6 +9,7 @@ use super::response_handler::ResponseHandler;
 use super::OrderbookConfig;
 use crate::activation_scheme_db::get_activation_scheme;
 use crate::adex_config::AdexConfig;
+use crate::rpc_data::{ExportTradeHistoryRequest, ExportTradeHistoryResponse, MmRpcV2Response};
 use crate::transport::Transport;
 use crate::{error_anyhow, error_bail, warn_anyhow};
 
@@ -133,6 +134,21 @@ impl<T: Transport, P: ResponseHandler, C: AdexConfig + 'static> AdexProc<'_, '_,
         request_legacy!(buy, Mm2RpcResult<SellBuyResponse>, self, on_buy_response)
     }
 
+    pub(crate) async fn export_trade_history(&self, request: ExportTradeHistoryRequest) -> Result<()> {
+        info!("Exporting trade history ...");
+        let export_trade_history = Command::builder()
+            .userpass(self.get_rpc_password()?)
+            .method(Method::ExportTradeHistory)
+            .v2_params(request)
+            .build();
+        request_legacy!(
+            export_trade_history,
+            MmRpcV2Response<ExportTradeHistoryResponse>,
+            self,
+            on_export_trade_history_response
+        )
+    }
+
     pub(crate) async fn send_stop(&self) -> Result<()> {
         info!("Sending stop command");
         let stop_command = Command::<Dummy>::builder()
@@ -9,11 +9,21 @@ where
     #[serde(flatten, skip_serializing_if = "Option::is_none")]
     flatten_data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    mmrpc: Option<MmRpcVersion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     method: Option<Method>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     userpass: Option<String>,
 }
 
+#[derive(Serialize, Clone, Copy)]
+pub(super) enum MmRpcVersion {
+    #[serde(rename = "2.0")]
+    V2,
+}
+
 #[derive(Serialize, Clone, Display)]
 #[serde(rename_all = "lowercase")]
 pub(super) enum Method {
@@ -27,6 +37,8 @@ pub(super) enum Method {
     GetOrderbook,
     Sell,
     Buy,
+    #[serde(rename = "export_trade_history")]
+    ExportTradeHistory,
 }
 
 #[derive(Serialize, Clone, Copy, Display)]
@@ -43,6 +55,7 @@ pub(super) struct CommandBuilder<T> {
     userpass: Option<String>,
     method: Option<Method>,
     flatten_data: Option<T>,
+    params: Option<T>,
 }
 
 impl<T> CommandBuilder<T>
@@ -54,6 +67,7 @@ where
             userpass: None,
             method: None,
             flatten_data: None,
+            params: None,
         }
     }
 
@@ -72,10 +86,19 @@ where
         self
     }
 
+    /// Sets the params of the `mmrpc: 2.0` request.
+    pub(super) fn v2_params(&mut self, params: T) -> &mut Self {
+        self.params = Some(params);
+        self
+    }
+
     pub(super) fn build(&mut self) -> Command<T> {
+        let params = self.params.take();
         Command {
             userpass: self.userpass.take(),
+            mmrpc: params.as_ref().map(|_| MmRpcVersion::V2),
             method: self.method.take(),
+            params,
             flatten_data: self.flatten_data.take(),
         }
     }
@@ -17,6 +17,7 @@ use std::io::Write;
 use super::OrderbookConfig;
 use crate::adex_config::AdexConfig;
 use crate::error_anyhow;
+use crate::rpc_data::{ExportTradeHistoryResponse, MmRpcV2Response};
 use common::{write_safe::io::WriteSafeIO, write_safe_io, writeln_safe_io};
 
 pub(crate) trait ResponseHandler {
@@ -35,6 +36,7 @@ pub(crate) trait ResponseHandler {
     fn on_sell_response(&self, response: &Mm2RpcResult<SellBuyResponse>) -> Result<()>;
     fn on_buy_response(&self, response: &Mm2RpcResult<SellBuyResponse>) -> Result<()>;
     fn on_stop_response(&self, response: &Mm2RpcResult<Status>) -> Result<()>;
+    fn on_export_trade_history_response(&self, response: &MmRpcV2Response<ExportTradeHistoryResponse>) -> Result<()>;
 }
 
 pub(crate) struct ResponseHandlerImpl<'a> {
@@ -190,6 +192,19 @@ impl ResponseHandler for ResponseHandlerImpl<'_> {
         writeln_safe_io!(self.writer.borrow_mut(), "Service stopped: {}", response.result);
         Ok(())
     }
+
+    fn on_export_trade_history_response(&self, response: &MmRpcV2Response<ExportTradeHistoryResponse>) -> Result<()> {
+        let mut writer = self.writer.borrow_mut();
+        match &response.result.csv {
+            Some(csv) => write_safe_io!(writer, "{}", csv),
+            None => {
+                let report = serde_json::to_string_pretty(&response.result.report)
+                    .map_err(|error| error_anyhow!("Failed to format trade history: {error}"))?;
+                writeln_safe_io!(writer, "{}", report)
+            },
+        }
+        Ok(())
+    }
 }
 
 struct SimpleCliTable<'a> {
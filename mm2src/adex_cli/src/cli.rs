@@ -11,6 +11,7 @@ use uuid::Uuid;
 
 use crate::adex_config::{get_config, set_config, AdexConfig};
 use crate::adex_proc::{AdexProc, OrderbookConfig, ResponseHandler};
+use crate::rpc_data::{ExportTradeHistoryRequest, TradeHistoryFormat};
 use crate::scenarios::{get_status, init, start_process, stop_process};
 use crate::transport::SlurpTransport;
 
@@ -72,6 +73,11 @@ enum Command {
         #[command(flatten)]
         order_args: BuyOrderCli,
     },
+    #[command(about = "Exports finished swaps with fees, USD valuation and realized PnL per coin")]
+    ExportTradeHistory {
+        #[command(flatten)]
+        export_args: ExportTradeHistoryArgs,
+    },
 }
 
 #[derive(Subcommand)]
@@ -148,6 +154,10 @@ impl Cli {
             Command::Buy {
                 order_args: BuyOrderCli { order_cli },
             } => proc.buy(SellBuyRequest::from(order_cli)).await?,
+            Command::ExportTradeHistory { export_args } => {
+                proc.export_trade_history(ExportTradeHistoryRequest::from(export_args))
+                    .await?
+            },
         }
         Ok(())
     }
@@ -209,6 +219,36 @@ impl From<&OrderbookCliArgs> for OrderbookConfig {
     }
 }
 
+#[derive(Args, Debug)]
+struct ExportTradeHistoryArgs {
+    #[arg(long, help = "Only swaps where this coin was sold")]
+    my_coin: Option<String>,
+    #[arg(long, help = "Only swaps where this coin was bought")]
+    other_coin: Option<String>,
+    #[arg(long, help = "Only swaps started at or after this unix timestamp in seconds")]
+    from_timestamp: Option<u64>,
+    #[arg(long, help = "Only swaps started at or before this unix timestamp in seconds")]
+    to_timestamp: Option<u64>,
+    #[arg(long, help = "Print trades as CSV instead of JSON")]
+    csv: bool,
+}
+
+impl From<&mut ExportTradeHistoryArgs> for ExportTradeHistoryRequest {
+    fn from(value: &mut ExportTradeHistoryArgs) -> Self {
+        ExportTradeHistoryRequest {
+            my_coin: value.my_coin.take(),
+            other_coin: value.other_coin.take(),
+            from_timestamp: value.from_timestamp,
+            to_timestamp: value.to_timestamp,
+            format: if value.csv {
+                TradeHistoryFormat::Csv
+            } else {
+                TradeHistoryFormat::Json
+            },
+        }
+    }
+}
+
 #[derive(Args, Serialize, Debug)]
 struct OrderCli {
     #[arg(help = "Base currency of a pair")]
@@ -234,6 +274,12 @@ struct OrderCli {
           action = ArgAction::Append,
           help="The created order is matched using a set of publics to select specific nodes (ignored if uuids not empty)")]
     match_publics: Vec<H256Json>,
+    #[arg(
+        long,
+        help = "Only the makers with at least this reputation score from 0 to 1 are matched",
+        value_parser=parse_mm_number
+    )]
+    min_maker_reputation: Option<MmNumber>,
     #[arg(
         long,
         help = "Number of required blockchain confirmations for base coin atomic swap transaction"
@@ -303,6 +349,7 @@ impl From<&mut OrderCli> for SellBuyRequest {
             gui: None,
             dest_pub_key: H256Json::default(),
             match_by,
+            min_maker_reputation: take(&mut value.min_maker_reputation),
             order_type: value.order_type.into(),
             base_confs: value.base_confs,
             base_nota: value.base_nota,
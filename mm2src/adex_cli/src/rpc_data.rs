@@ -5,6 +5,7 @@
 
 use mm2_rpc::data::legacy::{ElectrumProtocol, GasStationPricePolicy, UtxoMergeParams};
 use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "method", rename_all = "lowercase")]
@@ -69,3 +70,37 @@ pub(super) struct Server {
     #[serde(default)]
     disable_cert_verification: bool,
 }
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TradeHistoryFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ExportTradeHistoryRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) my_coin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) other_coin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) from_timestamp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) to_timestamp: Option<u64>,
+    pub(crate) format: TradeHistoryFormat,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MmRpcV2Response<T> {
+    pub(crate) result: T,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExportTradeHistoryResponse {
+    #[serde(default)]
+    pub(crate) csv: Option<String>,
+    /// The rest of the report: the trades, the realized PnL per coin and the unpriced trades.
+    #[serde(flatten)]
+    pub(crate) report: Json,
+}
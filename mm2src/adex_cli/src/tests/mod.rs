@@ -171,6 +171,51 @@ async fn test_buy_morty_for_rick() {
     assert_eq!("Buy order uuid: 4685e133-dfb3-4b31-8d4c-0ffa79933c8e\n", result);
 }
 
+#[tokio::test]
+async fn test_export_trade_history_csv() {
+    tokio::spawn(fake_mm2_server(
+        7792,
+        include_bytes!("http_mock_data/export_trade_history_csv.http"),
+    ));
+    tokio::time::sleep(Duration::from_millis(FAKE_SERVER_WARMUP_TIMEOUT_MS)).await;
+    let mut buffer: Vec<u8> = vec![];
+    let response_handler = ResponseHandlerImpl {
+        writer: (&mut buffer as &mut dyn Write).into(),
+    };
+    let config = AdexConfigImpl::new("dummy", "http://127.0.0.1:7792");
+    let args = vec!["adex-cli", "export-trade-history", "--my-coin", "RICK", "--csv"];
+    Cli::execute(args.iter().map(|arg| arg.to_string()), &config, &response_handler)
+        .await
+        .unwrap();
+
+    let result = String::from_utf8(buffer).unwrap();
+    assert_eq!(TRADE_HISTORY_CSV, result);
+}
+
+#[tokio::test]
+async fn test_export_trade_history_json() {
+    tokio::spawn(fake_mm2_server(
+        7793,
+        include_bytes!("http_mock_data/export_trade_history.http"),
+    ));
+    tokio::time::sleep(Duration::from_millis(FAKE_SERVER_WARMUP_TIMEOUT_MS)).await;
+    let mut buffer: Vec<u8> = vec![];
+    let response_handler = ResponseHandlerImpl {
+        writer: (&mut buffer as &mut dyn Write).into(),
+    };
+    let config = AdexConfigImpl::new("dummy", "http://127.0.0.1:7793");
+    let args = vec!["adex-cli", "export-trade-history", "--from-timestamp", "1700000000"];
+    Cli::execute(args.iter().map(|arg| arg.to_string()), &config, &response_handler)
+        .await
+        .unwrap();
+
+    let result = String::from_utf8(buffer).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(report["format"], "json");
+    assert_eq!(report["found_records"], 0);
+    assert!(report.get("csv").is_none());
+}
+
 async fn fake_mm2_server(port: u16, predefined_response: &'static [u8]) {
     let server = TcpListener::bind(("0.0.0.0", port))
         .await
@@ -253,3 +298,8 @@ unspendable_balance: 0
 required_confirmations: 3
 requires_notarization: No
 ";
+
+const TRADE_HISTORY_CSV: &str = "uuid,swap_type,started_at,finished_at,my_coin,my_amount,other_coin,other_amount,\
+my_coin_usd_price,other_coin_usd_price,my_amount_usd,other_amount_usd,dex_fee_coin,dex_fee_amount,miner_fees
+5b3c6d1e-6a8c-4a4b-9c53-6f6a0a6f8d3e,Taker,1700000000,1700000600,RICK,2,MORTY,1,1,2,2,2,RICK,0.0026,0.00001 RICK;0.00001 MORTY
+";
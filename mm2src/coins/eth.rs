@@ -82,22 +82,27 @@ cfg_wasm32! {
 
 use super::watcher_common::{validate_watcher_reward, REWARD_GAS_AMOUNT};
 use super::{coin_conf, lp_coinfind_or_err, AsyncMutex, BalanceError, BalanceFut, CheckIfMyPaymentSentArgs,
-            CoinBalance, CoinFutSpawner, CoinProtocol, CoinTransportMetrics, CoinsContext, ConfirmPaymentInput,
-            EthValidateFeeArgs, FeeApproxStage, FoundSwapTxSpend, HistorySyncState, IguanaPrivKey, MakerSwapTakerCoin,
-            MarketCoinOps, MmCoin, MmCoinEnum, MyAddressError, MyWalletAddress, NegotiateSwapContractAddrErr,
-            NumConversError, NumConversResult, PaymentInstructionArgs, PaymentInstructions, PaymentInstructionsErr,
-            PrivKeyBuildPolicy, PrivKeyPolicyNotAllowed, RawTransactionError, RawTransactionFut,
-            RawTransactionRequest, RawTransactionRes, RawTransactionResult, RefundError, RefundPaymentArgs,
-            RefundResult, RewardTarget, RpcClientType, RpcTransportEventHandler, RpcTransportEventHandlerShared,
-            SearchForSwapTxSpendInput, SendMakerPaymentSpendPreimageInput, SendPaymentArgs, SignatureError,
-            SignatureResult, SpendPaymentArgs, SwapOps, TakerSwapMakerCoin, TradeFee, TradePreimageError,
-            TradePreimageFut, TradePreimageResult, TradePreimageValue, Transaction, TransactionDetails,
-            TransactionEnum, TransactionErr, TransactionFut, TransactionType, TxMarshalingErr,
-            UnexpectedDerivationMethod, ValidateAddressResult, ValidateFeeArgs, ValidateInstructionsErr,
-            ValidateOtherPubKeyErr, ValidatePaymentError, ValidatePaymentFut, ValidatePaymentInput, VerificationError,
+            CoinAssocTypes, CoinBalance, CoinFutSpawner, CoinProtocol, CoinTransportMetrics, CoinsContext,
+            ConfirmPaymentInput, DeriveHtlcResult, EthValidateFeeArgs, FeeApproxStage, FoundSwapTxSpend,
+            GenPreimageResult, GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs, HistorySyncState, IguanaPrivKey,
+            MakerSwapTakerCoin, MarketCoinOps, MmCoin, MmCoinEnum, MyAddressError, MyWalletAddress,
+            NegotiateSwapContractAddrErr, NumConversError, NumConversResult, PaymentInstructionArgs,
+            PaymentInstructions, PaymentInstructionsErr, PrivKeyBuildPolicy, PrivKeyPolicyNotAllowed,
+            RawTransactionError, RawTransactionFut, RawTransactionRequest, RawTransactionRes, RawTransactionResult,
+            RefundError, RefundFundingSecretArgs, RefundPaymentArgs, RefundResult, RewardTarget, RpcClientType,
+            RpcTransportEventHandler, RpcTransportEventHandlerShared, SearchForSwapTxSpendInput,
+            SendMakerPaymentSpendPreimageInput, SendPaymentArgs, SendTakerFundingArgs, SignatureError,
+            SignatureResult, SignedBatchTx, SpendPaymentArgs, SwapOps, SwapOpsV2, TakerSwapMakerCoin, ToBytes,
+            TradeFee, TradePreimageError, TradePreimageFut, TradePreimageResult, TradePreimageValue, Transaction,
+            TransactionDetails, TransactionEnum, TransactionErr, TransactionFut, TransactionType, TxMarshalingErr,
+            TxPreimageWithSig, UnexpectedDerivationMethod, ValidateAddressResult, ValidateFeeArgs,
+            ValidateInstructionsErr, ValidateOtherPubKeyErr, ValidatePaymentError, ValidatePaymentFut,
+            ValidatePaymentInput, ValidateTakerFundingArgs, ValidateTakerFundingResult,
+            ValidateTakerFundingSpendPreimageResult, ValidateTakerPaymentSpendPreimageResult, VerificationError,
             VerificationResult, WaitForHTLCTxSpendArgs, WatcherOps, WatcherReward, WatcherRewardError,
             WatcherSearchForSwapTxSpendInput, WatcherValidatePaymentInput, WatcherValidateTakerFeeInput,
-            WithdrawError, WithdrawFee, WithdrawFut, WithdrawRequest, WithdrawResult, EARLY_CONFIRMATION_ERR_LOG,
+            WithdrawError, WithdrawFee, WithdrawFut, WithdrawManyDetails, WithdrawManyFut, WithdrawManyRequest,
+            WithdrawManyResult, WithdrawRequest, WithdrawResult, EARLY_CONFIRMATION_ERR_LOG,
             INVALID_CONTRACT_ADDRESS_ERR_LOG, INVALID_PAYMENT_STATE_ERR_LOG, INVALID_RECEIVER_ERR_LOG,
             INVALID_SENDER_ERR_LOG, INVALID_SWAP_ID_ERR_LOG};
 pub use rlp;
@@ -114,6 +119,10 @@ mod nonce;
 use crate::{PrivKeyPolicy, TransactionResult, WithdrawFrom};
 use nonce::ParityNonce;
 
+#[path = "eth/eth_swap_v2.rs"] mod eth_swap_v2;
+
+#[path = "eth/eth_balance_events.rs"] mod eth_balance_events;
+
 /// https://github.com/artemii235/etomic-swap/blob/master/contracts/EtomicSwap.sol
 /// Dev chain (195.201.137.5:8565) contract address: 0x83965C539899cC0F918552e5A26915de40ee8852
 /// Ropsten: https://ropsten.etherscan.io/address/0x7bc1bbdd6a0a722fc9bffc49c921b685ecb84b94
@@ -125,6 +134,8 @@ const ERC20_ABI: &str = include_str!("eth/erc20_abi.json");
 const ERC721_ABI: &str = include_str!("eth/erc721_abi.json");
 /// https://github.com/ethereum/EIPs/blob/master/EIPS/eip-1155.md
 const ERC1155_ABI: &str = include_str!("eth/erc1155_abi.json");
+/// Taker swap contract of the upgraded swap protocol (swap v2), keeping taker funding and taker payment.
+const TAKER_SWAP_V2_ABI: &str = include_str!("eth/taker_swap_v2_abi.json");
 /// Payment states from etomic swap smart contract: https://github.com/artemii235/etomic-swap/blob/master/contracts/EtomicSwap.sol#L5
 pub enum PaymentState {
     Uninitialized,
@@ -167,6 +178,7 @@ lazy_static! {
     pub static ref ERC20_CONTRACT: Contract = Contract::load(ERC20_ABI.as_bytes()).unwrap();
     pub static ref ERC721_CONTRACT: Contract = Contract::load(ERC721_ABI.as_bytes()).unwrap();
     pub static ref ERC1155_CONTRACT: Contract = Contract::load(ERC1155_ABI.as_bytes()).unwrap();
+    pub static ref TAKER_SWAP_V2: Contract = Contract::load(TAKER_SWAP_V2_ABI.as_bytes()).unwrap();
 }
 
 pub type Web3RpcFut<T> = Box<dyn Future<Item = T, Error = MmError<Web3RpcError>> + Send>;
@@ -421,6 +433,8 @@ pub struct EthCoinImpl {
     sign_message_prefix: Option<String>,
     swap_contract_address: Address,
     fallback_swap_contract: Option<Address>,
+    /// The taker swap contract of the upgraded swap protocol (swap v2).
+    swap_v2_contract: Option<Address>,
     contract_supports_watchers: bool,
     pub(crate) web3: Web3<Web3Transport>,
     /// The separate web3 instances kept to get nonce, will replace the web3 completely soon
@@ -705,12 +719,13 @@ async fn get_tx_hex_by_hash_impl(coin: EthCoin, tx_hash: H256) -> RawTransaction
     })
 }
 
-async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
-    let to_addr = coin
-        .address_from_str(&req.to)
-        .map_to_mm(WithdrawError::InvalidAddress)?;
-    let (my_balance, my_address, key_pair) = match req.from {
-        Some(WithdrawFrom::HDWalletAddress(ref path_to_address)) => {
+/// Returns the balance, the address and the key pair of the withdrawal sender.
+async fn get_withdraw_sender(
+    coin: &EthCoin,
+    from: Option<&WithdrawFrom>,
+) -> MmResult<(U256, Address, KeyPair), WithdrawError> {
+    match from {
+        Some(WithdrawFrom::HDWalletAddress(path_to_address)) => {
             let raw_priv_key = coin
                 .priv_key_policy
                 .hd_wallet_derived_priv_key_or_err(path_to_address)?;
@@ -718,19 +733,26 @@ async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
                 .map_to_mm(|e| WithdrawError::InternalError(e.to_string()))?;
             let address = key_pair.address();
             let balance = coin.address_balance(address).compat().await?;
-            (balance, address, key_pair)
+            Ok((balance, address, key_pair))
         },
         Some(WithdrawFrom::AddressId(_)) | Some(WithdrawFrom::DerivationPath { .. }) => {
-            return MmError::err(WithdrawError::UnexpectedFromAddress(
+            MmError::err(WithdrawError::UnexpectedFromAddress(
                 "Withdraw from 'AddressId' or 'DerivationPath' is not supported yet for EVM!".to_string(),
             ))
         },
-        None => (
+        None => Ok((
             coin.my_balance().compat().await?,
             coin.my_address,
             coin.priv_key_policy.activated_key_or_err()?.clone(),
-        ),
-    };
+        )),
+    }
+}
+
+async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
+    let to_addr = coin
+        .address_from_str(&req.to)
+        .map_to_mm(WithdrawError::InvalidAddress)?;
+    let (my_balance, my_address, key_pair) = get_withdraw_sender(&coin, req.from.as_ref()).await?;
     let my_balance_dec = u256_to_big_decimal(my_balance, coin.decimals)?;
 
     let (mut wei_amount, dec_amount) = if req.max {
@@ -872,6 +894,153 @@ async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
     })
 }
 
+/// Pays to the outputs with one transaction per output since an EVM transaction can't have several recipients.
+/// The transactions are signed with the sequential nonces starting from the current nonce of the address,
+/// but none of them is broadcasted, so they are returned in [`WithdrawManyDetails::batch_txs`].
+/// The returned details sum up all of the transactions and contain the hex and the hash of the first one.
+async fn withdraw_many_impl(coin: EthCoin, req: WithdrawManyRequest) -> WithdrawManyResult {
+    req.ensure_no_max_outputs()?;
+    let (my_balance, my_address, key_pair) = get_withdraw_sender(&coin, req.from.as_ref()).await?;
+
+    struct OutputTx {
+        eth_value: U256,
+        data: Vec<u8>,
+        call_addr: Address,
+        gas: U256,
+    }
+
+    let mut output_txs = Vec::with_capacity(req.outputs.len());
+    let mut to = Vec::with_capacity(req.outputs.len());
+    let mut total_wei_amount = U256::zero();
+    let mut received_wei_amount = U256::zero();
+    let mut total_gas = U256::zero();
+    let mut gas_price = None;
+    for output in req.outputs.iter() {
+        let to_addr = coin
+            .address_from_str(&output.to)
+            .map_to_mm(WithdrawError::InvalidAddress)?;
+        let wei_amount = wei_from_big_decimal(&output.amount, coin.decimals)?;
+        let (eth_value, data, call_addr) = match &coin.coin_type {
+            EthCoinType::Eth => (wei_amount, vec![], to_addr),
+            EthCoinType::Erc20 { token_addr, .. } => {
+                let function = ERC20_CONTRACT.function("transfer")?;
+                let data = function.encode_input(&[Token::Address(to_addr), Token::Uint(wei_amount)])?;
+                (0.into(), data, *token_addr)
+            },
+        };
+        let (gas, output_gas_price) =
+            get_eth_gas_details(&coin, req.fee.clone(), eth_value, data.clone().into(), call_addr, false).await?;
+        // All of the transactions are paid with the same gas price, so the total fee is known in advance.
+        gas_price.get_or_insert(output_gas_price);
+
+        total_wei_amount += wei_amount;
+        if to_addr == my_address {
+            received_wei_amount += wei_amount;
+        }
+        total_gas += gas;
+        to.push(checksum_address(&format!("{:#02x}", to_addr)));
+        output_txs.push(OutputTx {
+            eth_value,
+            data,
+            call_addr,
+            gas,
+        });
+    }
+    let gas_price = gas_price.unwrap_or_default();
+
+    let mut total_spent = total_wei_amount;
+    if coin.coin_type == EthCoinType::Eth {
+        total_spent += total_gas * gas_price;
+    }
+    if total_spent > my_balance {
+        return MmError::err(WithdrawError::NotSufficientBalance {
+            coin: coin.ticker.clone(),
+            available: u256_to_big_decimal(my_balance, coin.decimals)?,
+            required: u256_to_big_decimal(total_spent, coin.decimals)?,
+        });
+    }
+
+    match coin.priv_key_policy {
+        EthPrivKeyPolicy::Iguana(_) | EthPrivKeyPolicy::HDWallet { .. } => (),
+        EthPrivKeyPolicy::Trezor => {
+            return MmError::err(WithdrawError::UnsupportedError(
+                "Trezor is not supported for EVM yet!".to_string(),
+            ))
+        },
+        #[cfg(target_arch = "wasm32")]
+        EthPrivKeyPolicy::Metamask(_) => {
+            return MmError::err(WithdrawError::UnsupportedError(
+                "Batch withdrawal is not supported with MetaMask".to_string(),
+            ))
+        },
+    }
+
+    let fee_coin = match &coin.coin_type {
+        EthCoinType::Eth => coin.ticker(),
+        EthCoinType::Erc20 { platform, .. } => platform.as_str(),
+    };
+
+    // Todo: nonce_lock is still global for all addresses but this needs to be per address
+    let _nonce_lock = coin.nonce_lock.lock().await;
+    let (mut nonce, _) = get_addr_nonce(my_address, coin.web3_instances.clone())
+        .compat()
+        .timeout_secs(30.)
+        .await?
+        .map_to_mm(WithdrawError::Transport)?;
+
+    let mut batch_txs = Vec::with_capacity(output_txs.len());
+    for output_tx in output_txs {
+        let tx = UnSignedEthTx {
+            nonce,
+            value: output_tx.eth_value,
+            action: Action::Call(output_tx.call_addr),
+            data: output_tx.data,
+            gas: output_tx.gas,
+            gas_price,
+        };
+        let signed = tx.sign(key_pair.secret(), coin.chain_id);
+        let tx_hash_bytes = BytesJson::from(signed.hash.0.to_vec());
+        batch_txs.push(SignedBatchTx {
+            nonce: nonce.as_u64(),
+            tx_hex: BytesJson::from(rlp::encode(&signed).to_vec()),
+            tx_hash: format!("{:02x}", tx_hash_bytes),
+        });
+        nonce += U256::one();
+    }
+    // The outputs aren't empty, so at least one transaction is signed.
+    let first_tx = batch_txs
+        .first()
+        .cloned()
+        .or_mm_err(|| WithdrawError::InternalError("No transactions are signed".to_owned()))?;
+
+    let total_amount = u256_to_big_decimal(total_wei_amount, coin.decimals)?;
+    let received_by_me = u256_to_big_decimal(received_wei_amount, coin.decimals)?;
+    let fee_details = EthTxFeeDetails::new(total_gas, gas_price, fee_coin)?;
+    let mut spent_by_me = total_amount.clone();
+    if coin.coin_type == EthCoinType::Eth {
+        spent_by_me += &fee_details.total_fee;
+    }
+    let details = TransactionDetails {
+        to,
+        from: vec![checksum_address(&format!("{:#02x}", my_address))],
+        total_amount,
+        my_balance_change: &received_by_me - &spent_by_me,
+        spent_by_me,
+        received_by_me,
+        tx_hex: first_tx.tx_hex,
+        tx_hash: first_tx.tx_hash,
+        block_height: 0,
+        fee_details: Some(fee_details.into()),
+        coin: coin.ticker.clone(),
+        internal_id: vec![].into(),
+        timestamp: now_sec(),
+        kmd_rewards: None,
+        transaction_type: Default::default(),
+        memo: None,
+    };
+    Ok(WithdrawManyDetails { details, batch_txs })
+}
+
 /// `withdraw_erc1155` function returns details of `ERC-1155` transaction including tx hex,
 /// which should be sent to`send_raw_transaction` RPC to broadcast the transaction.
 pub async fn withdraw_erc1155(ctx: MmArc, withdraw_type: WithdrawErc1155) -> WithdrawNftResult {
@@ -1246,6 +1415,10 @@ impl SwapOps for EthCoin {
         watcher_reward: bool,
     ) -> Result<Vec<u8>, String> {
         let unverified: UnverifiedTransaction = try_s!(rlp::decode(spend_tx));
+        if let Some(secret) = eth_swap_v2::extract_secret_from_taker_payment_spend(&unverified.data) {
+            return secret;
+        }
+
         let function_name = get_function_name("receiverSpend", watcher_reward);
         let function = try_s!(SWAP_CONTRACT.function(&function_name));
 
@@ -1310,33 +1483,33 @@ impl SwapOps for EthCoin {
     }
 
     #[inline]
-    fn derive_htlc_key_pair(&self, _swap_unique_data: &[u8]) -> keys::KeyPair {
+    fn derive_htlc_key_pair(&self, _swap_unique_data: &[u8]) -> DeriveHtlcResult<keys::KeyPair> {
         match self.priv_key_policy {
             EthPrivKeyPolicy::Iguana(ref key_pair)
             | EthPrivKeyPolicy::HDWallet {
                 activated_key: ref key_pair,
                 ..
-            } => key_pair_from_secret(key_pair.secret().as_bytes()).expect("valid key"),
-            EthPrivKeyPolicy::Trezor => todo!(),
+            } => Ok(key_pair_from_secret(key_pair.secret().as_bytes()).expect("valid key")),
+            EthPrivKeyPolicy::Trezor => MmError::err(UnexpectedDerivationMethod::Trezor),
             #[cfg(target_arch = "wasm32")]
             EthPrivKeyPolicy::Metamask(_) => todo!(),
         }
     }
 
     #[inline]
-    fn derive_htlc_pubkey(&self, _swap_unique_data: &[u8]) -> Vec<u8> {
+    fn derive_htlc_pubkey(&self, _swap_unique_data: &[u8]) -> DeriveHtlcResult<Vec<u8>> {
         match self.priv_key_policy {
             EthPrivKeyPolicy::Iguana(ref key_pair)
             | EthPrivKeyPolicy::HDWallet {
                 activated_key: ref key_pair,
                 ..
-            } => key_pair_from_secret(key_pair.secret().as_bytes())
+            } => Ok(key_pair_from_secret(key_pair.secret().as_bytes())
                 .expect("valid key")
                 .public_slice()
-                .to_vec(),
-            EthPrivKeyPolicy::Trezor => todo!(),
+                .to_vec()),
+            EthPrivKeyPolicy::Trezor => MmError::err(UnexpectedDerivationMethod::Trezor),
             #[cfg(target_arch = "wasm32")]
-            EthPrivKeyPolicy::Metamask(ref metamask_policy) => metamask_policy.public_key.as_bytes().to_vec(),
+            EthPrivKeyPolicy::Metamask(ref metamask_policy) => Ok(metamask_policy.public_key.as_bytes().to_vec()),
         }
     }
 
@@ -1415,6 +1588,175 @@ impl MakerSwapTakerCoin for EthCoin {
     async fn on_maker_payment_refund_success(&self, _taker_payment: &[u8]) -> RefundResult<()> { Ok(()) }
 }
 
+impl ToBytes for SignedEthTx {
+    fn to_bytes(&self) -> Vec<u8> { self.tx_hex() }
+}
+
+impl ToBytes for Public {
+    /// Serializes the public key in the uncompressed SEC1 format (with `0x04` prefix).
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(65);
+        bytes.push(4);
+        bytes.extend_from_slice(self.as_bytes());
+        bytes
+    }
+}
+
+impl ToBytes for Signature {
+    fn to_bytes(&self) -> Vec<u8> { self.to_vec() }
+}
+
+#[derive(Debug, Display)]
+pub enum EthAssocTypesError {
+    InvalidHexString(String),
+    TxParseError(String),
+    ParseSignatureError(String),
+}
+
+impl CoinAssocTypes for EthCoin {
+    type Pubkey = Public;
+    type PubkeyParseError = MmError<EthAssocTypesError>;
+    type Tx = SignedEthTx;
+    type TxParseError = MmError<EthAssocTypesError>;
+    type Preimage = SignedEthTx;
+    type PreimageParseError = MmError<EthAssocTypesError>;
+    type Sig = Signature;
+    type SigParseError = MmError<EthAssocTypesError>;
+
+    fn parse_pubkey(&self, pubkey: &[u8]) -> Result<Self::Pubkey, Self::PubkeyParseError> {
+        let pubkey =
+            PublicKey::from_slice(pubkey).map_to_mm(|e| EthAssocTypesError::InvalidHexString(e.to_string()))?;
+        Ok(Public::from_slice(&pubkey.serialize_uncompressed()[1..65]))
+    }
+
+    fn parse_tx(&self, tx: &[u8]) -> Result<Self::Tx, Self::TxParseError> {
+        signed_eth_tx_from_bytes(tx).map_to_mm(EthAssocTypesError::TxParseError)
+    }
+
+    /// There are no transaction preimages in the EVM swap v2, so the preimage is the transaction itself.
+    fn parse_preimage(&self, tx: &[u8]) -> Result<Self::Preimage, Self::PreimageParseError> { self.parse_tx(tx) }
+
+    fn parse_signature(&self, sig: &[u8]) -> Result<Self::Sig, Self::SigParseError> {
+        if sig.len() != 65 {
+            return MmError::err(EthAssocTypesError::ParseSignatureError(format!(
+                "Signature slice is not 65 bytes long, got {}",
+                sig.len()
+            )));
+        }
+        let mut arr = [0; 65];
+        arr.copy_from_slice(sig);
+        Ok(Signature::from(arr))
+    }
+}
+
+/// The taker funding and the taker payment are kept by the taker swap v2 contract,
+/// see [`eth_swap_v2`] module for the details.
+#[async_trait]
+impl SwapOpsV2 for EthCoin {
+    async fn send_taker_funding(&self, args: SendTakerFundingArgs<'_>) -> Result<Self::Tx, TransactionErr> {
+        self.send_taker_funding_impl(args).await
+    }
+
+    async fn validate_taker_funding(&self, args: ValidateTakerFundingArgs<'_, Self>) -> ValidateTakerFundingResult {
+        self.validate_taker_funding_impl(args).await
+    }
+
+    async fn refund_taker_funding_timelock(&self, args: RefundPaymentArgs<'_>) -> TransactionResult {
+        self.refund_taker_funding_timelock_impl(args.payment_tx)
+            .await
+            .map(TransactionEnum::from)
+    }
+
+    async fn refund_taker_funding_secret(
+        &self,
+        args: RefundFundingSecretArgs<'_, Self>,
+    ) -> Result<Self::Tx, TransactionErr> {
+        self.refund_taker_funding_secret_impl(args).await
+    }
+
+    /// Funding tx is approved by taker itself, so the "preimage" is just the funding tx.
+    async fn gen_taker_funding_spend_preimage(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_, Self>,
+        _swap_unique_data: &[u8],
+    ) -> GenPreimageResult<Self> {
+        Ok(TxPreimageWithSig {
+            preimage: args.funding_tx.clone(),
+            signature: args.funding_tx.signature(),
+        })
+    }
+
+    async fn validate_taker_funding_spend_preimage(
+        &self,
+        gen_args: &GenTakerFundingSpendArgs<'_, Self>,
+        preimage: &TxPreimageWithSig<Self>,
+    ) -> ValidateTakerFundingSpendPreimageResult {
+        self.validate_taker_funding_spend_preimage_impl(gen_args, preimage)
+    }
+
+    async fn sign_and_send_taker_funding_spend(
+        &self,
+        _preimage: &TxPreimageWithSig<Self>,
+        args: &GenTakerFundingSpendArgs<'_, Self>,
+        _swap_unique_data: &[u8],
+    ) -> Result<Self::Tx, TransactionErr> {
+        self.taker_payment_approve(args.funding_tx).await
+    }
+
+    async fn refund_combined_taker_payment(&self, args: RefundPaymentArgs<'_>) -> TransactionResult {
+        self.refund_taker_payment_timelock_impl(args.payment_tx)
+            .await
+            .map(TransactionEnum::from)
+    }
+
+    /// Maker spends the approved taker payment directly, so the "preimage" is just the taker payment tx.
+    async fn gen_taker_payment_spend_preimage(
+        &self,
+        args: &GenTakerPaymentSpendArgs<'_, Self>,
+        _swap_unique_data: &[u8],
+    ) -> GenPreimageResult<Self> {
+        Ok(TxPreimageWithSig {
+            preimage: args.taker_tx.clone(),
+            signature: args.taker_tx.signature(),
+        })
+    }
+
+    async fn validate_taker_payment_spend_preimage(
+        &self,
+        gen_args: &GenTakerPaymentSpendArgs<'_, Self>,
+        preimage: &TxPreimageWithSig<Self>,
+    ) -> ValidateTakerPaymentSpendPreimageResult {
+        self.validate_taker_payment_spend_preimage_impl(gen_args, preimage)
+    }
+
+    async fn sign_and_broadcast_taker_payment_spend(
+        &self,
+        _preimage: &TxPreimageWithSig<Self>,
+        gen_args: &GenTakerPaymentSpendArgs<'_, Self>,
+        secret: &[u8],
+        _swap_unique_data: &[u8],
+    ) -> TransactionResult {
+        self.spend_taker_payment_impl(gen_args.taker_tx, secret)
+            .await
+            .map(TransactionEnum::from)
+    }
+
+    fn derive_htlc_pubkey_v2(&self, _swap_unique_data: &[u8]) -> DeriveHtlcResult<Self::Pubkey> {
+        match self.priv_key_policy {
+            EthPrivKeyPolicy::Iguana(ref key_pair)
+            | EthPrivKeyPolicy::HDWallet {
+                activated_key: ref key_pair,
+                ..
+            } => Ok(*key_pair.public()),
+            EthPrivKeyPolicy::Trezor => MmError::err(UnexpectedDerivationMethod::Trezor),
+            #[cfg(target_arch = "wasm32")]
+            EthPrivKeyPolicy::Metamask(ref metamask_policy) => Ok(Public::from_slice(
+                &metamask_policy.public_key_uncompressed.as_bytes()[1..],
+            )),
+        }
+    }
+}
+
 #[async_trait]
 impl WatcherOps for EthCoin {
     fn send_maker_payment_spend_preimage(&self, input: SendMakerPaymentSpendPreimageInput) -> TransactionFut {
@@ -2260,6 +2602,20 @@ impl MarketCoinOps for EthCoin {
         let unverified: UnverifiedTransaction = try_tx_fus!(rlp::decode(args.tx_bytes));
         let tx = try_tx_fus!(SignedEthTx::new(unverified));
 
+        // Taker payment of the swap v2 is kept by the separate contract and is spent in a different way.
+        if matches!(tx.action, Call(address) if Some(address) == self.swap_v2_contract) {
+            let coin = self.clone();
+            let from_block = args.from_block;
+            let wait_until = args.wait_until;
+            let check_every = args.check_every;
+            let fut = async move {
+                coin.wait_for_taker_payment_spend_impl(&tx, from_block, wait_until, check_every)
+                    .await
+                    .map(TransactionEnum::from)
+            };
+            return Box::new(fut.boxed().compat());
+        }
+
         let swap_contract_address = match args.swap_contract_address {
             Some(addr) => try_tx_fus!(addr.try_to_address()),
             None => match tx.action {
@@ -4737,6 +5093,13 @@ impl EthTxFeeDetails {
 impl MmCoin for EthCoin {
     fn is_asset_chain(&self) -> bool { false }
 
+    /// The swaps aren't supported with Trezor yet, so the orders are rejected before the swap is started.
+    fn wallet_only(&self, ctx: &MmArc) -> bool {
+        let coin_conf = coin_conf(ctx, self.ticker());
+        let wallet_only_conf = coin_conf["wallet_only"].as_bool().unwrap_or(false);
+        wallet_only_conf || matches!(self.priv_key_policy, EthPrivKeyPolicy::Trezor)
+    }
+
     fn spawner(&self) -> CoinFutSpawner { CoinFutSpawner::new(&self.abortable_system) }
 
     fn get_raw_transaction(&self, req: RawTransactionRequest) -> RawTransactionFut {
@@ -4763,6 +5126,10 @@ impl MmCoin for EthCoin {
         Box::new(Box::pin(withdraw_impl(self.clone(), req)).compat())
     }
 
+    fn withdraw_many(&self, req: WithdrawManyRequest) -> WithdrawManyFut {
+        Box::new(Box::pin(withdraw_many_impl(self.clone(), req)).compat())
+    }
+
     fn decimals(&self) -> u8 { self.decimals }
 
     fn convert_to_address(&self, from: &str, to_address_format: Json) -> Result<String, String> {
@@ -5412,6 +5779,10 @@ pub async fn eth_coin_from_conf_and_request(
             return ERR!("fallback_swap_contract can't be zero address");
         }
     }
+    let swap_v2_contract: Option<Address> = try_s!(json::from_value(req["swap_v2_contract_address"].clone()));
+    if swap_v2_contract == Some(Address::default()) {
+        return ERR!("swap_v2_contract_address can't be zero address");
+    }
     let contract_supports_watchers = req["contract_supports_watchers"].as_bool().unwrap_or_default();
 
     let path_to_address = try_s!(json::from_value::<Option<StandardHDCoinAddress>>(
@@ -5508,6 +5879,7 @@ pub async fn eth_coin_from_conf_and_request(
         sign_message_prefix,
         swap_contract_address,
         fallback_swap_contract,
+        swap_v2_contract,
         contract_supports_watchers,
         decimals,
         ticker: ticker.into(),
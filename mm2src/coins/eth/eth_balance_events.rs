@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use common::{executor::{AbortSettings, SpawnAbortable, Timer},
+             log};
+use futures::channel::oneshot::{self, Receiver, Sender};
+use futures::compat::Future01CompatExt;
+use mm2_core::mm_ctx::MmArc;
+use mm2_event_stream::{behaviour::{EventBehaviour, EventInitStatus},
+                       Event, EventStreamConfiguration};
+use std::collections::HashMap;
+
+use super::{EthCoin, EthCoinType};
+use crate::{CoinBalance, MarketCoinOps, MmCoin};
+
+impl EthCoin {
+    /// Returns the balances of the platform coin and all its activated ERC20 tokens, keyed by ticker.
+    async fn all_balances(&self) -> Result<HashMap<String, CoinBalance>, String> {
+        let mut balances = HashMap::new();
+        let platform_balance = MarketCoinOps::my_balance(self)
+            .compat()
+            .await
+            .map_err(|e| e.to_string())?;
+        balances.insert(self.ticker.clone(), platform_balance);
+
+        if let EthCoinType::Eth = self.coin_type {
+            let token_balances = self.get_tokens_balance_list().await.map_err(|e| e.to_string())?;
+            balances.extend(token_balances);
+        }
+
+        Ok(balances)
+    }
+}
+
+#[async_trait]
+impl EventBehaviour for EthCoin {
+    const EVENT_NAME: &'static str = "COIN_BALANCE";
+
+    async fn handle(self, interval: f64, tx: oneshot::Sender<EventInitStatus>) {
+        let ctx = match MmArc::from_weak(&self.ctx) {
+            Some(ctx) => ctx,
+            None => {
+                let msg = "MM context must have been initialized already.";
+                log::error!("{} event for {} is not started: {}", Self::EVENT_NAME, self.ticker, msg);
+                tx.send(EventInitStatus::Failed(msg.to_owned())).ok();
+                return;
+            },
+        };
+
+        let mut current_balances: HashMap<String, CoinBalance> = HashMap::new();
+
+        tx.send(EventInitStatus::Success)
+            .expect("Receiver is dropped, which should never happen.");
+
+        // Web3 HTTP transports don't support subscriptions, so the balances are polled.
+        loop {
+            match self.all_balances().await {
+                Ok(balances) => {
+                    for (ticker, balance) in balances {
+                        if current_balances.get(&ticker) == Some(&balance) {
+                            continue;
+                        }
+
+                        let payload = json!({
+                            "ticker": ticker,
+                            "balance": { "spendable": balance.spendable, "unspendable": balance.unspendable }
+                        });
+
+                        ctx.stream_channel_controller
+                            .broadcast(Event::new(Self::EVENT_NAME.to_string(), payload.to_string()))
+                            .await;
+
+                        current_balances.insert(ticker, balance);
+                    }
+                },
+                Err(e) => log::error!("Failed to fetch {} balances: {}", self.ticker, e),
+            }
+
+            Timer::sleep(interval).await;
+        }
+    }
+
+    async fn spawn_if_active(self, config: &EventStreamConfiguration) -> EventInitStatus {
+        if let Some(event) = config.get_event(Self::EVENT_NAME) {
+            log::info!(
+                "{} event is activated for {} with {} seconds interval.",
+                Self::EVENT_NAME,
+                self.ticker,
+                event.stream_interval_seconds
+            );
+
+            let (tx, rx): (Sender<EventInitStatus>, Receiver<EventInitStatus>) = oneshot::channel();
+            let fut = self.clone().handle(event.stream_interval_seconds, tx);
+            let settings =
+                AbortSettings::info_on_abort(format!("{} event is stopped for {}.", Self::EVENT_NAME, self.ticker));
+            self.spawner().spawn_with_settings(fut, settings);
+
+            rx.await.unwrap_or_else(|e| {
+                EventInitStatus::Failed(format!("Event initialization status must be received: {}", e))
+            })
+        } else {
+            EventInitStatus::Inactive
+        }
+    }
+}
@@ -0,0 +1,1019 @@
+//! EVM implementation of the [Trading Protocol Upgrade](https://github.com/KomodoPlatform/komodo-defi-framework/issues/1895) operations.
+//!
+//! Both taker funding and taker payment are kept by the taker swap v2 contract:
+//! * taker funding is sent using `ethTakerPayment`/`erc20TakerPayment` call,
+//! * funding "spend" to the taker payment is done by taker itself using `takerPaymentApprove` call,
+//! * maker spends the approved taker payment revealing the secret using `spendTakerPayment` call,
+//! * taker refunds the funding using `refundTakerFundingTimelock` or `refundTakerPaymentSecret` call
+//!   and the approved payment using `refundTakerPaymentTimelock` call.
+//!
+//! So unlike UTXO coins, there are no transaction preimages to be exchanged between maker and taker:
+//! the funding and the approval transactions are sent instead and validated against the negotiated parameters.
+
+use super::{addr_from_raw_pubkey, decode_contract_call, signed_eth_tx_from_bytes, signed_tx_from_web3_tx,
+            wei_from_big_decimal, EthCoin, EthCoinType, SignedEthTx, ETH_GAS, TAKER_SWAP_V2};
+use crate::{GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs, MarketCoinOps, RefundFundingSecretArgs,
+            SendTakerFundingArgs, TransactionErr, TxPreimageWithSig, ValidateTakerFundingArgs,
+            ValidateTakerFundingError, ValidateTakerFundingResult, ValidateTakerFundingSpendPreimageError,
+            ValidateTakerFundingSpendPreimageResult, ValidateTakerPaymentSpendPreimageError,
+            ValidateTakerPaymentSpendPreimageResult};
+use bitcrypto::ripemd160;
+use common::executor::Timer;
+use common::log::error;
+use common::now_sec;
+use ethabi::Token;
+use ethcore_transaction::Action;
+use ethereum_types::{Address, U256};
+use ethkey::public_to_address;
+use futures::compat::Future01CompatExt;
+use mm2_err_handle::prelude::*;
+use std::convert::TryFrom;
+use web3::types::{BlockNumber, FilterBuilder, Log, TransactionId};
+
+/// How long to wait for the ERC20 allowance to be updated after `approve` transaction is sent.
+const ALLOWANCE_UPDATE_TIMEOUT_SEC: u64 = 600;
+
+/// Taker payment states of the taker swap v2 contract.
+#[allow(dead_code)]
+pub(super) enum TakerPaymentStateV2 {
+    Uninitialized,
+    PaymentSent,
+    TakerApproved,
+    MakerSpent,
+    TakerRefunded,
+}
+
+/// Taker payment parameters decoded from the contract call sent by taker.
+struct TakerPaymentCallArgs {
+    id: Vec<u8>,
+    /// Trading amount including the premium.
+    amount: U256,
+    dex_fee: U256,
+    maker: Address,
+    taker: Address,
+    taker_secret_hash: Vec<u8>,
+    maker_secret_hash: Vec<u8>,
+    /// Zero address for ETH.
+    token_address: Address,
+    /// Pre-approve and payment lock times, present only for the funding calls.
+    lock_times: Option<(U256, U256)>,
+}
+
+/// The contract keeps 20 bytes hashes, which are `ripemd160(sha256(secret))`.
+/// SHA256 hash of the secret is converted the same way to be compatible.
+fn secret_hash_to_contract_format(secret_hash: &[u8]) -> Vec<u8> {
+    if secret_hash.len() == 32 {
+        ripemd160(secret_hash).to_vec()
+    } else {
+        secret_hash.to_vec()
+    }
+}
+
+fn token_to_bytes(tokens: &[Token], index: usize) -> Result<Vec<u8>, String> {
+    match tokens.get(index) {
+        Some(Token::FixedBytes(bytes)) => Ok(bytes.clone()),
+        token => ERR!("Expected Token::FixedBytes at {}, got {:?}", index, token),
+    }
+}
+
+fn token_to_uint(tokens: &[Token], index: usize) -> Result<U256, String> {
+    match tokens.get(index) {
+        Some(Token::Uint(number)) => Ok(*number),
+        token => ERR!("Expected Token::Uint at {}, got {:?}", index, token),
+    }
+}
+
+fn token_to_address(tokens: &[Token], index: usize) -> Result<Address, String> {
+    match tokens.get(index) {
+        Some(Token::Address(address)) => Ok(*address),
+        token => ERR!("Expected Token::Address at {}, got {:?}", index, token),
+    }
+}
+
+/// Decodes taker payment parameters from `ethTakerPayment`, `erc20TakerPayment` or `takerPaymentApprove` call.
+fn decode_taker_payment_call(tx: &SignedEthTx) -> Result<TakerPaymentCallArgs, String> {
+    let selector = match tx.data.get(0..4) {
+        Some(selector) => selector,
+        None => return ERR!("Tx {:02x} data is too short to be a contract call", tx.hash),
+    };
+
+    let eth_payment = try_s!(TAKER_SWAP_V2.function("ethTakerPayment"));
+    let erc20_payment = try_s!(TAKER_SWAP_V2.function("erc20TakerPayment"));
+    let approve = try_s!(TAKER_SWAP_V2.function("takerPaymentApprove"));
+
+    if selector == eth_payment.short_signature() {
+        let decoded = try_s!(decode_contract_call(eth_payment, &tx.data));
+        let dex_fee = try_s!(token_to_uint(&decoded, 1));
+        let amount = try_s!(tx.value.checked_sub(dex_fee).ok_or_else(|| ERRL!(
+            "Tx value {} is less than dex fee {}",
+            tx.value,
+            dex_fee
+        )));
+        Ok(TakerPaymentCallArgs {
+            id: try_s!(token_to_bytes(&decoded, 0)),
+            amount,
+            dex_fee,
+            maker: try_s!(token_to_address(&decoded, 2)),
+            taker: tx.sender(),
+            taker_secret_hash: try_s!(token_to_bytes(&decoded, 3)),
+            maker_secret_hash: try_s!(token_to_bytes(&decoded, 4)),
+            token_address: Address::default(),
+            lock_times: Some((try_s!(token_to_uint(&decoded, 5)), try_s!(token_to_uint(&decoded, 6)))),
+        })
+    } else if selector == erc20_payment.short_signature() {
+        let decoded = try_s!(decode_contract_call(erc20_payment, &tx.data));
+        Ok(TakerPaymentCallArgs {
+            id: try_s!(token_to_bytes(&decoded, 0)),
+            amount: try_s!(token_to_uint(&decoded, 1)),
+            dex_fee: try_s!(token_to_uint(&decoded, 2)),
+            maker: try_s!(token_to_address(&decoded, 4)),
+            taker: tx.sender(),
+            taker_secret_hash: try_s!(token_to_bytes(&decoded, 5)),
+            maker_secret_hash: try_s!(token_to_bytes(&decoded, 6)),
+            token_address: try_s!(token_to_address(&decoded, 3)),
+            lock_times: Some((try_s!(token_to_uint(&decoded, 7)), try_s!(token_to_uint(&decoded, 8)))),
+        })
+    } else if selector == approve.short_signature() {
+        let decoded = try_s!(decode_contract_call(approve, &tx.data));
+        Ok(TakerPaymentCallArgs {
+            id: try_s!(token_to_bytes(&decoded, 0)),
+            amount: try_s!(token_to_uint(&decoded, 1)),
+            dex_fee: try_s!(token_to_uint(&decoded, 2)),
+            maker: try_s!(token_to_address(&decoded, 3)),
+            taker: tx.sender(),
+            taker_secret_hash: try_s!(token_to_bytes(&decoded, 4)),
+            maker_secret_hash: try_s!(token_to_bytes(&decoded, 5)),
+            token_address: try_s!(token_to_address(&decoded, 6)),
+            lock_times: None,
+        })
+    } else {
+        ERR!("Tx {:02x} is not a taker swap v2 payment call", tx.hash)
+    }
+}
+
+/// The contract call arguments used to refund the taker funding or the taker payment after the lock time.
+fn taker_refund_timelock_tokens(call_args: TakerPaymentCallArgs) -> [Token; 7] {
+    [
+        Token::FixedBytes(call_args.id),
+        Token::Uint(call_args.amount),
+        Token::Uint(call_args.dex_fee),
+        Token::Address(call_args.maker),
+        Token::FixedBytes(call_args.taker_secret_hash),
+        Token::FixedBytes(call_args.maker_secret_hash),
+        Token::Address(call_args.token_address),
+    ]
+}
+
+/// Taker payment parameters expected by the counterparty, the `None` fields aren't checked.
+struct ExpectedTakerPayment {
+    id: Option<Vec<u8>>,
+    maker: Address,
+    taker: Address,
+    taker_secret_hash: Option<Vec<u8>>,
+    maker_secret_hash: Vec<u8>,
+    token_address: Address,
+}
+
+impl ExpectedTakerPayment {
+    fn check(&self, call_args: &TakerPaymentCallArgs) -> Result<(), String> {
+        if let Some(ref id) = self.id {
+            if call_args.id != *id {
+                return ERR!("Invalid swap id {:?}, expected {:?}", call_args.id, id);
+            }
+        }
+        if call_args.maker != self.maker {
+            return ERR!("Invalid maker address {:?}, expected {:?}", call_args.maker, self.maker);
+        }
+        if call_args.taker != self.taker {
+            return ERR!("Invalid taker address {:?}, expected {:?}", call_args.taker, self.taker);
+        }
+        if let Some(ref taker_secret_hash) = self.taker_secret_hash {
+            if call_args.taker_secret_hash != *taker_secret_hash {
+                return ERR!(
+                    "Invalid taker secret hash {:?}, expected {:?}",
+                    call_args.taker_secret_hash,
+                    taker_secret_hash
+                );
+            }
+        }
+        if call_args.maker_secret_hash != self.maker_secret_hash {
+            return ERR!(
+                "Invalid maker secret hash {:?}, expected {:?}",
+                call_args.maker_secret_hash,
+                self.maker_secret_hash
+            );
+        }
+        if call_args.token_address != self.token_address {
+            return ERR!(
+                "Invalid token address {:?}, expected {:?}",
+                call_args.token_address,
+                self.token_address
+            );
+        }
+        Ok(())
+    }
+}
+
+impl EthCoin {
+    fn swap_v2_contract_or_err(&self) -> Result<Address, String> {
+        self.swap_v2_contract
+            .ok_or_else(|| ERRL!("swap_v2_contract_address is not set for {}", self.ticker))
+    }
+
+    fn token_address_for_swap_v2(&self) -> Address {
+        match self.coin_type {
+            EthCoinType::Eth => Address::default(),
+            EthCoinType::Erc20 { token_addr, .. } => token_addr,
+        }
+    }
+
+    /// Requests the state of the taker payment from the taker swap v2 contract.
+    async fn taker_payment_state_v2(&self, swap_contract: Address, id: Vec<u8>) -> Result<U256, String> {
+        let function = try_s!(TAKER_SWAP_V2.function("takerPayments"));
+        let data = try_s!(function.encode_input(&[Token::FixedBytes(id)]));
+        let bytes = try_s!(self.call_request(swap_contract, None, Some(data.into())).await);
+        let decoded = try_s!(function.decode_output(&bytes.0));
+        token_to_uint(&decoded, 3)
+    }
+
+    /// Gets the taker swap v2 contract `event_name` events from `from_block` to `to_block`.
+    async fn taker_swap_v2_events(
+        &self,
+        swap_contract: Address,
+        event_name: &str,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Log>, String> {
+        let contract_event = try_s!(TAKER_SWAP_V2.event(event_name));
+        let filter = FilterBuilder::default()
+            .topics(Some(vec![contract_event.signature()]), None, None, None)
+            .from_block(BlockNumber::Number(from_block.into()))
+            .to_block(BlockNumber::Number(to_block.into()))
+            .address(vec![swap_contract])
+            .build();
+        self.web3.eth().logs(filter).await.map_err(|e| ERRL!("{}", e))
+    }
+
+    async fn call_taker_swap_v2(&self, function_name: &str, tokens: &[Token]) -> Result<SignedEthTx, TransactionErr> {
+        let swap_contract = try_tx_s!(self.swap_v2_contract_or_err());
+        let function = try_tx_s!(TAKER_SWAP_V2.function(function_name));
+        let data = try_tx_s!(function.encode_input(tokens));
+        self.sign_and_send_transaction(0.into(), Action::Call(swap_contract), data, U256::from(ETH_GAS))
+            .compat()
+            .await
+    }
+
+    /// Makes sure the taker swap v2 contract is allowed to transfer at least `amount` of ERC20 token.
+    async fn ensure_swap_v2_allowance(&self, swap_contract: Address, amount: U256) -> Result<(), TransactionErr> {
+        let allowed = try_tx_s!(self.allowance(swap_contract).compat().await);
+        if allowed >= amount {
+            return Ok(());
+        }
+
+        let approved = self.approve(swap_contract, U256::max_value()).compat().await?;
+        // make sure the approve tx is confirmed by making sure that the allowed value has been updated
+        let wait_until = now_sec() + ALLOWANCE_UPDATE_TIMEOUT_SEC;
+        if let Err(e) = self
+            .wait_for_required_allowance(swap_contract, amount, wait_until)
+            .compat()
+            .await
+        {
+            return TX_PLAIN_ERR!(
+                "Allowed value was not updated in time after sending approve transaction {:02x}: {}",
+                approved.hash,
+                e
+            );
+        }
+        Ok(())
+    }
+
+    pub(super) async fn send_taker_funding_impl(
+        &self,
+        args: SendTakerFundingArgs<'_>,
+    ) -> Result<SignedEthTx, TransactionErr> {
+        let swap_contract = try_tx_s!(self.swap_v2_contract_or_err());
+        let receiver = try_tx_s!(addr_from_raw_pubkey(args.maker_pub));
+        let dex_fee = try_tx_s!(wei_from_big_decimal(&args.dex_fee_amount, self.decimals));
+        let amount = try_tx_s!(wei_from_big_decimal(
+            &(&args.trading_amount + &args.premium_amount),
+            self.decimals
+        ));
+        let pre_approve_lock_time = try_tx_s!(u32::try_from(args.time_lock));
+        let payment_lock_time = try_tx_s!(u32::try_from(args.payment_time_lock));
+        let id = self.etomic_swap_id(pre_approve_lock_time, args.taker_secret_hash);
+        let taker_secret_hash = secret_hash_to_contract_format(args.taker_secret_hash);
+        let maker_secret_hash = secret_hash_to_contract_format(args.maker_secret_hash);
+
+        match self.coin_type {
+            EthCoinType::Eth => {
+                let function = try_tx_s!(TAKER_SWAP_V2.function("ethTakerPayment"));
+                let data = try_tx_s!(function.encode_input(&[
+                    Token::FixedBytes(id),
+                    Token::Uint(dex_fee),
+                    Token::Address(receiver),
+                    Token::FixedBytes(taker_secret_hash),
+                    Token::FixedBytes(maker_secret_hash),
+                    Token::Uint(pre_approve_lock_time.into()),
+                    Token::Uint(payment_lock_time.into()),
+                ]));
+                self.sign_and_send_transaction(amount + dex_fee, Action::Call(swap_contract), data, U256::from(ETH_GAS))
+                    .compat()
+                    .await
+            },
+            EthCoinType::Erc20 { token_addr, .. } => {
+                self.ensure_swap_v2_allowance(swap_contract, amount + dex_fee).await?;
+                let function = try_tx_s!(TAKER_SWAP_V2.function("erc20TakerPayment"));
+                let data = try_tx_s!(function.encode_input(&[
+                    Token::FixedBytes(id),
+                    Token::Uint(amount),
+                    Token::Uint(dex_fee),
+                    Token::Address(token_addr),
+                    Token::Address(receiver),
+                    Token::FixedBytes(taker_secret_hash),
+                    Token::FixedBytes(maker_secret_hash),
+                    Token::Uint(pre_approve_lock_time.into()),
+                    Token::Uint(payment_lock_time.into()),
+                ]));
+                self.sign_and_send_transaction(0.into(), Action::Call(swap_contract), data, U256::from(ETH_GAS))
+                    .compat()
+                    .await
+            },
+        }
+    }
+
+    pub(super) async fn validate_taker_funding_impl(
+        &self,
+        args: ValidateTakerFundingArgs<'_, Self>,
+    ) -> ValidateTakerFundingResult {
+        let swap_contract = self
+            .swap_v2_contract_or_err()
+            .map_to_mm(ValidateTakerFundingError::InternalError)?;
+        let dex_fee = wei_from_big_decimal(&args.dex_fee_amount, self.decimals)?;
+        let amount = wei_from_big_decimal(&(&args.trading_amount + &args.premium_amount), self.decimals)?;
+        let pre_approve_lock_time =
+            u32::try_from(args.time_lock).map_to_mm(|e| ValidateTakerFundingError::LocktimeOverflow(e.to_string()))?;
+        let payment_lock_time = u32::try_from(args.payment_time_lock)
+            .map_to_mm(|e| ValidateTakerFundingError::LocktimeOverflow(e.to_string()))?;
+        let expected_id = self.etomic_swap_id(pre_approve_lock_time, args.taker_secret_hash);
+
+        let tx_from_rpc = self
+            .web3
+            .eth()
+            .transaction(TransactionId::Hash(args.funding_tx.hash))
+            .await
+            .map_to_mm(|e| ValidateTakerFundingError::Rpc(e.to_string()))?
+            .or_mm_err(|| {
+                ValidateTakerFundingError::WrongPaymentTx(format!(
+                    "Didn't find provided tx {:?} on ETH node",
+                    args.funding_tx.hash
+                ))
+            })?;
+
+        let expected_sender = public_to_address(args.other_pub);
+        if tx_from_rpc.from != Some(expected_sender) {
+            return MmError::err(ValidateTakerFundingError::WrongPaymentTx(format!(
+                "Funding tx {:?} was sent from wrong address, expected {:?}",
+                tx_from_rpc, expected_sender
+            )));
+        }
+        if tx_from_rpc.to != Some(swap_contract) {
+            return MmError::err(ValidateTakerFundingError::WrongPaymentTx(format!(
+                "Funding tx {:?} was sent to wrong address, expected {:?}",
+                tx_from_rpc, swap_contract
+            )));
+        }
+
+        let funding_tx = signed_tx_from_web3_tx(tx_from_rpc).map_to_mm(ValidateTakerFundingError::WrongPaymentTx)?;
+        let call_args = decode_taker_payment_call(&funding_tx).map_to_mm(ValidateTakerFundingError::WrongPaymentTx)?;
+
+        if call_args.id != expected_id {
+            return MmError::err(ValidateTakerFundingError::WrongPaymentTx(format!(
+                "Invalid swap id {:?}, expected {:?}",
+                call_args.id, expected_id
+            )));
+        }
+        if call_args.maker != self.my_address {
+            return MmError::err(ValidateTakerFundingError::WrongPaymentTx(format!(
+                "Funding tx receiver {:?} is invalid, expected {:?}",
+                call_args.maker, self.my_address
+            )));
+        }
+        if call_args.amount != amount || call_args.dex_fee != dex_fee {
+            return MmError::err(ValidateTakerFundingError::InvalidDestinationOrAmount(format!(
+                "Funding tx amount {} and dex fee {} are invalid, expected {} and {}",
+                call_args.amount, call_args.dex_fee, amount, dex_fee
+            )));
+        }
+        if call_args.token_address != self.token_address_for_swap_v2() {
+            return MmError::err(ValidateTakerFundingError::WrongPaymentTx(format!(
+                "Funding tx token address {:?} is invalid, expected {:?}",
+                call_args.token_address,
+                self.token_address_for_swap_v2()
+            )));
+        }
+        if call_args.taker_secret_hash != secret_hash_to_contract_format(args.taker_secret_hash)
+            || call_args.maker_secret_hash != secret_hash_to_contract_format(args.maker_secret_hash)
+        {
+            return MmError::err(ValidateTakerFundingError::WrongPaymentTx(format!(
+                "Funding tx secret hashes {:?} and {:?} are invalid",
+                call_args.taker_secret_hash, call_args.maker_secret_hash
+            )));
+        }
+        let expected_lock_times = Some((U256::from(pre_approve_lock_time), U256::from(payment_lock_time)));
+        if call_args.lock_times != expected_lock_times {
+            return MmError::err(ValidateTakerFundingError::WrongPaymentTx(format!(
+                "Funding tx lock times {:?} are invalid, expected {:?}",
+                call_args.lock_times, expected_lock_times
+            )));
+        }
+
+        let state = self
+            .taker_payment_state_v2(swap_contract, call_args.id)
+            .await
+            .map_to_mm(ValidateTakerFundingError::Rpc)?;
+        if state != U256::from(TakerPaymentStateV2::PaymentSent as u8) {
+            return MmError::err(ValidateTakerFundingError::UnexpectedPaymentState(format!(
+                "Taker payment state is not PaymentSent, got {}",
+                state
+            )));
+        }
+        Ok(())
+    }
+
+    /// Approves the taker funding to become the taker payment, which can be spent by maker.
+    pub(super) async fn taker_payment_approve(&self, funding_tx: &SignedEthTx) -> Result<SignedEthTx, TransactionErr> {
+        let swap_contract = try_tx_s!(self.swap_v2_contract_or_err());
+        let call_args = try_tx_s!(decode_taker_payment_call(funding_tx));
+        let state = try_tx_s!(self.taker_payment_state_v2(swap_contract, call_args.id.clone()).await);
+        if state != U256::from(TakerPaymentStateV2::PaymentSent as u8) {
+            return TX_PLAIN_ERR!("Taker payment state is not PaymentSent, got {}", state);
+        }
+
+        self.call_taker_swap_v2("takerPaymentApprove", &[
+            Token::FixedBytes(call_args.id),
+            Token::Uint(call_args.amount),
+            Token::Uint(call_args.dex_fee),
+            Token::Address(call_args.maker),
+            Token::FixedBytes(call_args.taker_secret_hash),
+            Token::FixedBytes(call_args.maker_secret_hash),
+            Token::Address(call_args.token_address),
+        ])
+        .await
+    }
+
+    /// Refunds the taker funding, which hasn't been approved, after the pre-approve lock time expires.
+    pub(super) async fn refund_taker_funding_timelock_impl(
+        &self,
+        funding_tx: &[u8],
+    ) -> Result<SignedEthTx, TransactionErr> {
+        let swap_contract = try_tx_s!(self.swap_v2_contract_or_err());
+        let funding_tx = try_tx_s!(signed_eth_tx_from_bytes(funding_tx));
+        let call_args = try_tx_s!(decode_taker_payment_call(&funding_tx));
+        let state = try_tx_s!(self.taker_payment_state_v2(swap_contract, call_args.id.clone()).await);
+        if state != U256::from(TakerPaymentStateV2::PaymentSent as u8) {
+            return TX_PLAIN_ERR!("Taker funding state is not PaymentSent, got {}", state);
+        }
+
+        self.call_taker_swap_v2("refundTakerFundingTimelock", &taker_refund_timelock_tokens(call_args))
+            .await
+    }
+
+    /// Refunds the approved taker payment after the payment lock time expires.
+    pub(super) async fn refund_taker_payment_timelock_impl(
+        &self,
+        payment_tx: &[u8],
+    ) -> Result<SignedEthTx, TransactionErr> {
+        let swap_contract = try_tx_s!(self.swap_v2_contract_or_err());
+        let payment_tx = try_tx_s!(signed_eth_tx_from_bytes(payment_tx));
+        let call_args = try_tx_s!(decode_taker_payment_call(&payment_tx));
+        let state = try_tx_s!(self.taker_payment_state_v2(swap_contract, call_args.id.clone()).await);
+        if state != U256::from(TakerPaymentStateV2::TakerApproved as u8) {
+            return TX_PLAIN_ERR!("Taker payment state is not TakerApproved, got {}", state);
+        }
+
+        self.call_taker_swap_v2("refundTakerPaymentTimelock", &taker_refund_timelock_tokens(call_args))
+            .await
+    }
+
+    /// Makes sure the funding "preimage" received from taker is the validated funding tx
+    /// locked with the negotiated parameters.
+    pub(super) fn validate_taker_funding_spend_preimage_impl(
+        &self,
+        gen_args: &GenTakerFundingSpendArgs<'_, Self>,
+        preimage: &TxPreimageWithSig<Self>,
+    ) -> ValidateTakerFundingSpendPreimageResult {
+        let funding_tx = &preimage.preimage;
+        if funding_tx.hash != gen_args.funding_tx.hash {
+            return MmError::err(ValidateTakerFundingSpendPreimageError::InvalidPreimage(format!(
+                "Preimage {:02x} is not the funding tx {:02x}",
+                funding_tx.hash, gen_args.funding_tx.hash
+            )));
+        }
+        if preimage.signature != funding_tx.signature() {
+            return MmError::err(ValidateTakerFundingSpendPreimageError::InvalidMakerSignature);
+        }
+
+        let pre_approve_lock_time = u32::try_from(gen_args.funding_time_lock)
+            .map_to_mm(|e| ValidateTakerFundingSpendPreimageError::LocktimeOverflow(e.to_string()))?;
+        let payment_lock_time = u32::try_from(gen_args.taker_payment_time_lock)
+            .map_to_mm(|e| ValidateTakerFundingSpendPreimageError::LocktimeOverflow(e.to_string()))?;
+        let call_args =
+            decode_taker_payment_call(funding_tx).map_to_mm(ValidateTakerFundingSpendPreimageError::InvalidPreimage)?;
+
+        let expected = ExpectedTakerPayment {
+            id: Some(self.etomic_swap_id(pre_approve_lock_time, gen_args.taker_secret_hash)),
+            maker: public_to_address(gen_args.maker_pub),
+            taker: public_to_address(gen_args.taker_pub),
+            taker_secret_hash: Some(secret_hash_to_contract_format(gen_args.taker_secret_hash)),
+            maker_secret_hash: secret_hash_to_contract_format(gen_args.maker_secret_hash),
+            token_address: self.token_address_for_swap_v2(),
+        };
+        expected
+            .check(&call_args)
+            .map_to_mm(ValidateTakerFundingSpendPreimageError::InvalidPreimage)?;
+
+        let expected_lock_times = Some((U256::from(pre_approve_lock_time), U256::from(payment_lock_time)));
+        if call_args.lock_times != expected_lock_times {
+            return MmError::err(ValidateTakerFundingSpendPreimageError::InvalidPreimage(format!(
+                "Funding tx lock times {:?} are invalid, expected {:?}",
+                call_args.lock_times, expected_lock_times
+            )));
+        }
+        Ok(())
+    }
+
+    /// Makes sure the taker payment "preimage" received from taker is the approval of the taker payment
+    /// with the negotiated parameters, so maker can spend it.
+    pub(super) fn validate_taker_payment_spend_preimage_impl(
+        &self,
+        gen_args: &GenTakerPaymentSpendArgs<'_, Self>,
+        preimage: &TxPreimageWithSig<Self>,
+    ) -> ValidateTakerPaymentSpendPreimageResult {
+        let payment_tx = &preimage.preimage;
+        if payment_tx.hash != gen_args.taker_tx.hash {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidPreimage(format!(
+                "Preimage {:02x} is not the taker payment {:02x}",
+                payment_tx.hash, gen_args.taker_tx.hash
+            )));
+        }
+        if preimage.signature != payment_tx.signature() {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidTakerSignature);
+        }
+
+        let call_args =
+            decode_taker_payment_call(payment_tx).map_to_mm(ValidateTakerPaymentSpendPreimageError::InvalidPreimage)?;
+        if call_args.lock_times.is_some() {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidPreimage(format!(
+                "Tx {:02x} is the taker funding, which isn't approved yet",
+                payment_tx.hash
+            )));
+        }
+
+        let expected = ExpectedTakerPayment {
+            id: None,
+            maker: public_to_address(gen_args.maker_pub),
+            taker: public_to_address(gen_args.taker_pub),
+            taker_secret_hash: None,
+            maker_secret_hash: secret_hash_to_contract_format(gen_args.secret_hash),
+            token_address: self.token_address_for_swap_v2(),
+        };
+        expected
+            .check(&call_args)
+            .map_to_mm(ValidateTakerPaymentSpendPreimageError::InvalidPreimage)?;
+
+        let amount = wei_from_big_decimal(&(&gen_args.trading_amount + &gen_args.premium_amount), self.decimals)
+            .mm_err(|e| ValidateTakerPaymentSpendPreimageError::TxGenError(e.to_string()))?;
+        let dex_fee = wei_from_big_decimal(&gen_args.dex_fee_amount, self.decimals)
+            .mm_err(|e| ValidateTakerPaymentSpendPreimageError::TxGenError(e.to_string()))?;
+        // Taker may pay the premium, which isn't known to maker.
+        if call_args.amount < amount || call_args.dex_fee != dex_fee {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidPreimage(format!(
+                "Taker payment amount {} and dex fee {} are invalid, expected at least {} and {}",
+                call_args.amount, call_args.dex_fee, amount, dex_fee
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reclaims taker funding immediately revealing the taker secret.
+    pub(super) async fn refund_taker_funding_secret_impl(
+        &self,
+        args: RefundFundingSecretArgs<'_, Self>,
+    ) -> Result<SignedEthTx, TransactionErr> {
+        let call_args = try_tx_s!(decode_taker_payment_call(args.funding_tx));
+
+        self.call_taker_swap_v2("refundTakerPaymentSecret", &[
+            Token::FixedBytes(call_args.id),
+            Token::Uint(call_args.amount),
+            Token::Uint(call_args.dex_fee),
+            Token::Address(call_args.maker),
+            Token::FixedBytes(args.taker_secret.to_vec()),
+            Token::FixedBytes(call_args.maker_secret_hash),
+            Token::Address(call_args.token_address),
+        ])
+        .await
+    }
+
+    /// Spends the approved taker payment on maker's side revealing the maker secret.
+    pub(super) async fn spend_taker_payment_impl(
+        &self,
+        taker_payment: &SignedEthTx,
+        secret: &[u8],
+    ) -> Result<SignedEthTx, TransactionErr> {
+        let swap_contract = try_tx_s!(self.swap_v2_contract_or_err());
+        let call_args = try_tx_s!(decode_taker_payment_call(taker_payment));
+        let state = try_tx_s!(self.taker_payment_state_v2(swap_contract, call_args.id.clone()).await);
+        if state != U256::from(TakerPaymentStateV2::TakerApproved as u8) {
+            return TX_PLAIN_ERR!("Taker payment state is not TakerApproved, got {}", state);
+        }
+
+        self.call_taker_swap_v2("spendTakerPayment", &[
+            Token::FixedBytes(call_args.id),
+            Token::Uint(call_args.amount),
+            Token::Uint(call_args.dex_fee),
+            Token::Address(call_args.taker),
+            Token::FixedBytes(call_args.taker_secret_hash),
+            Token::FixedBytes(secret.to_vec()),
+            Token::Address(call_args.token_address),
+        ])
+        .await
+    }
+
+    /// Waits until maker spends the approved taker payment and returns the spending transaction.
+    pub(super) async fn wait_for_taker_payment_spend_impl(
+        &self,
+        taker_payment: &SignedEthTx,
+        from_block: u64,
+        wait_until: u64,
+        check_every: f64,
+    ) -> Result<SignedEthTx, TransactionErr> {
+        let swap_contract = try_tx_s!(self.swap_v2_contract_or_err());
+        let call_args = try_tx_s!(decode_taker_payment_call(taker_payment));
+        loop {
+            if now_sec() > wait_until {
+                return TX_PLAIN_ERR!(
+                    "Waited too long until {} for taker payment {:02x} to be spent",
+                    wait_until,
+                    taker_payment.hash
+                );
+            }
+
+            match self.taker_payment_state_v2(swap_contract, call_args.id.clone()).await {
+                Ok(state) if state == U256::from(TakerPaymentStateV2::MakerSpent as u8) => (),
+                Ok(_) => {
+                    Timer::sleep(check_every).await;
+                    continue;
+                },
+                Err(e) => {
+                    error!("Error getting taker payment state: {}", e);
+                    Timer::sleep(check_every).await;
+                    continue;
+                },
+            }
+
+            let current_block = match self.current_block().compat().await {
+                Ok(b) => b,
+                Err(e) => {
+                    error!("Error getting block number: {}", e);
+                    Timer::sleep(check_every).await;
+                    continue;
+                },
+            };
+            let events = match self
+                .taker_swap_v2_events(swap_contract, "TakerPaymentSpent", from_block, current_block)
+                .await
+            {
+                Ok(events) => events,
+                Err(e) => {
+                    error!("Error getting taker payment spend events: {}", e);
+                    Timer::sleep(check_every).await;
+                    continue;
+                },
+            };
+
+            let found = events
+                .iter()
+                .find(|event| event.data.0.get(..32) == Some(call_args.id.as_slice()))
+                .and_then(|event| event.transaction_hash);
+            if let Some(tx_hash) = found {
+                match self.web3.eth().transaction(TransactionId::Hash(tx_hash)).await {
+                    Ok(Some(tx)) => return signed_tx_from_web3_tx(tx).map_err(TransactionErr::Plain),
+                    Ok(None) => error!("Tx {:02x} not found yet", tx_hash),
+                    Err(e) => error!("Get tx {:02x} error: {}", tx_hash, e),
+                }
+            }
+            Timer::sleep(check_every).await;
+        }
+    }
+}
+
+/// Extracts the maker secret from `spendTakerPayment` call.
+/// Returns `None` if the transaction is not a taker swap v2 spend.
+pub(super) fn extract_secret_from_taker_payment_spend(call_data: &[u8]) -> Option<Result<Vec<u8>, String>> {
+    let function = TAKER_SWAP_V2.function("spendTakerPayment").ok()?;
+    if call_data.get(0..4) != Some(&function.short_signature()[..]) {
+        return None;
+    }
+
+    let secret = decode_contract_call(function, call_data)
+        .map_err(|e| ERRL!("{}", e))
+        .and_then(|tokens| token_to_bytes(&tokens, 5));
+    Some(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eth::eth_tests::eth_coin_from_keypair;
+    use crate::eth::UnSignedEthTx;
+    use ethkey::{Generator, KeyPair, Random};
+    use mm2_number::BigDecimal;
+    use mm2_test_helpers::for_tests::ETH_DEV_NODES;
+    use std::str::FromStr;
+
+    const FUNDING_LOCK_TIME: u32 = 1_700_000_000;
+    const PAYMENT_LOCK_TIME: u32 = 1_700_010_000;
+
+    fn swap_contract() -> Address { Address::from_str("0x9130b257d37a52e52f21054c4da3450c72f595ce").unwrap() }
+
+    fn send_call(sender: &KeyPair, value: U256, function_name: &str, tokens: &[Token]) -> SignedEthTx {
+        let data = TAKER_SWAP_V2
+            .function(function_name)
+            .unwrap()
+            .encode_input(tokens)
+            .unwrap();
+        let tx = UnSignedEthTx {
+            nonce: 0.into(),
+            gas_price: 1.into(),
+            gas: ETH_GAS.into(),
+            action: Action::Call(swap_contract()),
+            value,
+            data,
+        };
+        tx.sign(sender.secret(), None)
+    }
+
+    struct SwapKeys {
+        maker: KeyPair,
+        taker: KeyPair,
+        taker_secret_hash: Vec<u8>,
+        maker_secret_hash: Vec<u8>,
+    }
+
+    impl SwapKeys {
+        fn new() -> SwapKeys {
+            SwapKeys {
+                maker: Random.generate().unwrap(),
+                taker: Random.generate().unwrap(),
+                taker_secret_hash: vec![1; 20],
+                maker_secret_hash: vec![2; 20],
+            }
+        }
+
+        fn maker_coin(&self) -> EthCoin {
+            eth_coin_from_keypair(EthCoinType::Eth, ETH_DEV_NODES, None, self.maker.clone()).1
+        }
+
+        fn eth_funding(&self, coin: &EthCoin, amount: U256, dex_fee: U256) -> SignedEthTx {
+            send_call(&self.taker, amount + dex_fee, "ethTakerPayment", &[
+                Token::FixedBytes(coin.etomic_swap_id(FUNDING_LOCK_TIME, &self.taker_secret_hash)),
+                Token::Uint(dex_fee),
+                Token::Address(self.maker.address()),
+                Token::FixedBytes(self.taker_secret_hash.clone()),
+                Token::FixedBytes(self.maker_secret_hash.clone()),
+                Token::Uint(FUNDING_LOCK_TIME.into()),
+                Token::Uint(PAYMENT_LOCK_TIME.into()),
+            ])
+        }
+
+        fn approve(&self, coin: &EthCoin, amount: U256, dex_fee: U256) -> SignedEthTx {
+            send_call(&self.taker, 0.into(), "takerPaymentApprove", &[
+                Token::FixedBytes(coin.etomic_swap_id(FUNDING_LOCK_TIME, &self.taker_secret_hash)),
+                Token::Uint(amount),
+                Token::Uint(dex_fee),
+                Token::Address(self.maker.address()),
+                Token::FixedBytes(self.taker_secret_hash.clone()),
+                Token::FixedBytes(self.maker_secret_hash.clone()),
+                Token::Address(Address::default()),
+            ])
+        }
+
+        fn funding_spend_args<'a>(&'a self, funding_tx: &'a SignedEthTx) -> GenTakerFundingSpendArgs<'a, EthCoin> {
+            GenTakerFundingSpendArgs {
+                funding_tx,
+                maker_pub: self.maker.public(),
+                taker_pub: self.taker.public(),
+                funding_time_lock: FUNDING_LOCK_TIME as u64,
+                taker_secret_hash: &self.taker_secret_hash,
+                taker_payment_time_lock: PAYMENT_LOCK_TIME as u64,
+                maker_secret_hash: &self.maker_secret_hash,
+            }
+        }
+
+        fn payment_spend_args<'a>(&'a self, taker_tx: &'a SignedEthTx) -> GenTakerPaymentSpendArgs<'a, EthCoin> {
+            GenTakerPaymentSpendArgs {
+                taker_tx,
+                time_lock: PAYMENT_LOCK_TIME as u64,
+                secret_hash: &self.maker_secret_hash,
+                maker_pub: self.maker.public(),
+                taker_pub: self.taker.public(),
+                dex_fee_pub: &[],
+                dex_fee_amount: BigDecimal::from_str("0.01").unwrap(),
+                premium_amount: BigDecimal::default(),
+                trading_amount: BigDecimal::from(1),
+            }
+        }
+    }
+
+    fn preimage_of(tx: &SignedEthTx) -> TxPreimageWithSig<EthCoin> {
+        TxPreimageWithSig {
+            preimage: tx.clone(),
+            signature: tx.signature(),
+        }
+    }
+
+    fn one_eth() -> U256 { U256::exp10(18) }
+
+    fn dex_fee() -> U256 { U256::exp10(16) }
+
+    #[test]
+    fn test_secret_hash_to_contract_format() {
+        let dhash160 = vec![1; 20];
+        assert_eq!(secret_hash_to_contract_format(&dhash160), dhash160);
+
+        let sha256 = vec![1; 32];
+        assert_eq!(secret_hash_to_contract_format(&sha256), ripemd160(&sha256).to_vec());
+    }
+
+    #[test]
+    fn test_decode_taker_payment_calls() {
+        let keys = SwapKeys::new();
+        let coin = keys.maker_coin();
+
+        let funding = keys.eth_funding(&coin, one_eth(), dex_fee());
+        let call_args = decode_taker_payment_call(&funding).unwrap();
+        assert_eq!(call_args.amount, one_eth());
+        assert_eq!(call_args.dex_fee, dex_fee());
+        assert_eq!(call_args.maker, keys.maker.address());
+        assert_eq!(call_args.taker, keys.taker.address());
+        assert_eq!(call_args.taker_secret_hash, keys.taker_secret_hash);
+        assert_eq!(call_args.maker_secret_hash, keys.maker_secret_hash);
+        assert_eq!(call_args.token_address, Address::default());
+        assert_eq!(
+            call_args.lock_times,
+            Some((FUNDING_LOCK_TIME.into(), PAYMENT_LOCK_TIME.into()))
+        );
+
+        let token_address = Address::from_str("0x2b294f029fde858b2c62184e8390591755521d8e").unwrap();
+        let erc20_funding = send_call(&keys.taker, 0.into(), "erc20TakerPayment", &[
+            Token::FixedBytes(vec![3; 32]),
+            Token::Uint(one_eth()),
+            Token::Uint(dex_fee()),
+            Token::Address(token_address),
+            Token::Address(keys.maker.address()),
+            Token::FixedBytes(keys.taker_secret_hash.clone()),
+            Token::FixedBytes(keys.maker_secret_hash.clone()),
+            Token::Uint(FUNDING_LOCK_TIME.into()),
+            Token::Uint(PAYMENT_LOCK_TIME.into()),
+        ]);
+        let call_args = decode_taker_payment_call(&erc20_funding).unwrap();
+        assert_eq!(call_args.id, vec![3; 32]);
+        assert_eq!(call_args.amount, one_eth());
+        assert_eq!(call_args.token_address, token_address);
+        assert_eq!(call_args.maker, keys.maker.address());
+
+        let approve = keys.approve(&coin, one_eth(), dex_fee());
+        let call_args = decode_taker_payment_call(&approve).unwrap();
+        assert_eq!(call_args.amount, one_eth());
+        assert_eq!(call_args.lock_times, None);
+
+        // The refund call passes the same parameters the funding was locked with.
+        let refund_tokens = taker_refund_timelock_tokens(decode_taker_payment_call(&funding).unwrap());
+        let refund = send_call(&keys.taker, 0.into(), "refundTakerFundingTimelock", &refund_tokens);
+        let function = TAKER_SWAP_V2.function("refundTakerFundingTimelock").unwrap();
+        assert_eq!(refund.data[0..4], function.short_signature());
+        assert_eq!(
+            decode_contract_call(function, &refund.data).unwrap(),
+            refund_tokens.to_vec()
+        );
+
+        let not_a_swap_call = send_call(&keys.taker, 0.into(), "takerPayments", &[Token::FixedBytes(vec![
+            0;
+            32
+        ])]);
+        assert!(decode_taker_payment_call(&not_a_swap_call).is_err());
+    }
+
+    #[test]
+    fn test_validate_taker_funding_spend_preimage() {
+        let keys = SwapKeys::new();
+        let coin = keys.maker_coin();
+        let funding = keys.eth_funding(&coin, one_eth(), dex_fee());
+        let gen_args = keys.funding_spend_args(&funding);
+        coin.validate_taker_funding_spend_preimage_impl(&gen_args, &preimage_of(&funding))
+            .unwrap();
+
+        // The preimage must be the funding tx itself.
+        let other_funding = keys.eth_funding(&coin, one_eth() * 2, dex_fee());
+        let err = coin
+            .validate_taker_funding_spend_preimage_impl(&gen_args, &preimage_of(&other_funding))
+            .unwrap_err();
+        assert!(matches!(
+            err.get_inner(),
+            ValidateTakerFundingSpendPreimageError::InvalidPreimage(_)
+        ));
+
+        let mut preimage = preimage_of(&funding);
+        preimage.signature = other_funding.signature();
+        let err = coin
+            .validate_taker_funding_spend_preimage_impl(&gen_args, &preimage)
+            .unwrap_err();
+        assert!(matches!(
+            err.get_inner(),
+            ValidateTakerFundingSpendPreimageError::InvalidMakerSignature
+        ));
+
+        let other_secret_hash = vec![5; 20];
+        let mut gen_args = keys.funding_spend_args(&funding);
+        gen_args.maker_secret_hash = &other_secret_hash;
+        coin.validate_taker_funding_spend_preimage_impl(&gen_args, &preimage_of(&funding))
+            .unwrap_err();
+
+        let mut gen_args = keys.funding_spend_args(&funding);
+        gen_args.taker_payment_time_lock += 1;
+        coin.validate_taker_funding_spend_preimage_impl(&gen_args, &preimage_of(&funding))
+            .unwrap_err();
+
+        let other_maker = Random.generate().unwrap();
+        let mut gen_args = keys.funding_spend_args(&funding);
+        gen_args.maker_pub = other_maker.public();
+        coin.validate_taker_funding_spend_preimage_impl(&gen_args, &preimage_of(&funding))
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_validate_taker_payment_spend_preimage() {
+        let keys = SwapKeys::new();
+        let coin = keys.maker_coin();
+        let approve = keys.approve(&coin, one_eth(), dex_fee());
+        let gen_args = keys.payment_spend_args(&approve);
+        coin.validate_taker_payment_spend_preimage_impl(&gen_args, &preimage_of(&approve))
+            .unwrap();
+
+        // The taker payment must be approved.
+        let funding = keys.eth_funding(&coin, one_eth(), dex_fee());
+        let gen_args = keys.payment_spend_args(&funding);
+        let err = coin
+            .validate_taker_payment_spend_preimage_impl(&gen_args, &preimage_of(&funding))
+            .unwrap_err();
+        assert!(matches!(
+            err.get_inner(),
+            ValidateTakerPaymentSpendPreimageError::InvalidPreimage(_)
+        ));
+
+        let less_than_expected = keys.approve(&coin, one_eth() - 1, dex_fee());
+        let gen_args = keys.payment_spend_args(&less_than_expected);
+        coin.validate_taker_payment_spend_preimage_impl(&gen_args, &preimage_of(&less_than_expected))
+            .unwrap_err();
+
+        let wrong_dex_fee = keys.approve(&coin, one_eth(), dex_fee() + 1);
+        let gen_args = keys.payment_spend_args(&wrong_dex_fee);
+        coin.validate_taker_payment_spend_preimage_impl(&gen_args, &preimage_of(&wrong_dex_fee))
+            .unwrap_err();
+
+        let other_secret_hash = vec![5; 20];
+        let mut gen_args = keys.payment_spend_args(&approve);
+        gen_args.secret_hash = &other_secret_hash;
+        coin.validate_taker_payment_spend_preimage_impl(&gen_args, &preimage_of(&approve))
+            .unwrap_err();
+
+        let other_taker = Random.generate().unwrap();
+        let mut gen_args = keys.payment_spend_args(&approve);
+        gen_args.taker_pub = other_taker.public();
+        coin.validate_taker_payment_spend_preimage_impl(&gen_args, &preimage_of(&approve))
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_extract_secret_from_taker_payment_spend() {
+        let keys = SwapKeys::new();
+        let secret = vec![7; 32];
+        let spend = send_call(&keys.maker, 0.into(), "spendTakerPayment", &[
+            Token::FixedBytes(vec![3; 32]),
+            Token::Uint(one_eth()),
+            Token::Uint(dex_fee()),
+            Token::Address(keys.taker.address()),
+            Token::FixedBytes(keys.taker_secret_hash.clone()),
+            Token::FixedBytes(secret.clone()),
+            Token::Address(Address::default()),
+        ]);
+        assert_eq!(
+            extract_secret_from_taker_payment_spend(&spend.data).unwrap().unwrap(),
+            secret
+        );
+
+        let coin = keys.maker_coin();
+        let funding = keys.eth_funding(&coin, one_eth(), dex_fee());
+        assert!(extract_secret_from_taker_payment_spend(&funding.data).is_none());
+    }
+}
@@ -1,5 +1,5 @@
 use super::*;
-use crate::{DexFee, IguanaPrivKey};
+use crate::{DexFee, IguanaPrivKey, WithdrawOutput};
 use common::{block_on, now_sec, wait_until_sec};
 use crypto::privkey::key_pair_from_seed;
 use ethkey::{Generator, Random};
@@ -99,7 +99,7 @@ fn random_eth_coin_for_test(
     eth_coin_from_keypair(coin_type, urls, fallback_swap_contract, key_pair)
 }
 
-fn eth_coin_from_keypair(
+pub(super) fn eth_coin_from_keypair(
     coin_type: EthCoinType,
     urls: &[&str],
     fallback_swap_contract: Option<Address>,
@@ -140,6 +140,7 @@ fn eth_coin_from_keypair(
         priv_key_policy: key_pair.into(),
         swap_contract_address: Address::from_str(ETH_DEV_SWAP_CONTRACT).unwrap(),
         fallback_swap_contract,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         ticker,
         web3_instances: vec![Web3Instance {
@@ -323,6 +324,7 @@ fn send_and_refund_erc20_payment() {
         priv_key_policy: key_pair.into(),
         swap_contract_address: Address::from_str(ETH_DEV_SWAP_CONTRACT).unwrap(),
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         web3_instances: vec![Web3Instance {
             web3: web3.clone(),
@@ -409,6 +411,7 @@ fn send_and_refund_eth_payment() {
         priv_key_policy: key_pair.into(),
         swap_contract_address: Address::from_str(ETH_DEV_SWAP_CONTRACT).unwrap(),
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         web3_instances: vec![Web3Instance {
             web3: web3.clone(),
@@ -507,6 +510,7 @@ fn test_nonce_several_urls() {
         priv_key_policy: key_pair.into(),
         swap_contract_address: Address::from_str(ETH_DEV_SWAP_CONTRACT).unwrap(),
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         web3_instances: vec![
             Web3Instance {
@@ -573,6 +577,7 @@ fn test_wait_for_payment_spend_timeout() {
         priv_key_policy: key_pair.into(),
         swap_contract_address: Address::from_str(ETH_DEV_SWAP_CONTRACT).unwrap(),
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         ticker: "ETH".into(),
         web3_instances: vec![Web3Instance {
@@ -643,6 +648,7 @@ fn test_search_for_swap_tx_spend_was_spent() {
         priv_key_policy: key_pair.into(),
         swap_contract_address,
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         ticker: "ETH".into(),
         web3_instances: vec![Web3Instance {
@@ -754,6 +760,7 @@ fn test_search_for_swap_tx_spend_was_refunded() {
         priv_key_policy: key_pair.into(),
         swap_contract_address,
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         ticker: "BAT".into(),
         web3_instances: vec![Web3Instance {
@@ -830,6 +837,7 @@ fn test_withdraw_impl_manual_fee() {
             gas_price: 1.into(),
         }),
         memo: None,
+        inputs: None,
     };
     coin.my_balance().wait().unwrap();
 
@@ -874,6 +882,7 @@ fn test_withdraw_impl_fee_details() {
             gas_price: 1.into(),
         }),
         memo: None,
+        inputs: None,
     };
     coin.my_balance().wait().unwrap();
 
@@ -890,6 +899,106 @@ fn test_withdraw_impl_fee_details() {
     assert_eq!(expected, tx_details.fee_details);
 }
 
+#[test]
+fn test_withdraw_many_impl_signs_in_nonce_order() {
+    let (_ctx, coin) = eth_coin_for_test(EthCoinType::Eth, &["http://dummy.dummy"], None);
+    EthCoin::my_balance.mock_safe(|_| {
+        let balance = wei_from_big_decimal(&10.into(), 18).unwrap();
+        MockResult::Return(Box::new(futures01::future::ok(balance)))
+    });
+    get_addr_nonce.mock_safe(|_, _| MockResult::Return(Box::new(futures01::future::ok((5.into(), vec![])))));
+    EthCoin::send_raw_tx_bytes.mock_safe(|_, _| panic!("No transaction should be broadcasted"));
+
+    let my_address = checksum_address(&format!("{:#02x}", coin.my_address));
+    let to = "0x7Bc1bBDD6A0a722fC9bffC49c921B685ECB84b94".to_string();
+    let withdraw_req = WithdrawManyRequest {
+        coin: "ETH".to_string(),
+        from: None,
+        outputs: vec![
+            WithdrawOutput {
+                to: to.clone(),
+                amount: 1.into(),
+                max: false,
+            },
+            WithdrawOutput {
+                to: my_address.clone(),
+                amount: 2.into(),
+                max: false,
+            },
+        ],
+        fee: Some(WithdrawFee::EthGas {
+            gas: ETH_GAS,
+            gas_price: 1.into(),
+        }),
+        memo: None,
+        inputs: None,
+    };
+    let WithdrawManyDetails {
+        details: tx_details,
+        batch_txs,
+    } = block_on(withdraw_many_impl(coin, withdraw_req)).unwrap();
+
+    let nonces: Vec<_> = batch_txs.iter().map(|tx| tx.nonce).collect();
+    assert_eq!(nonces, vec![5, 6]);
+    for batch_tx in batch_txs.iter() {
+        let signed = signed_eth_tx_from_bytes(&batch_tx.tx_hex).unwrap();
+        assert_eq!(signed.nonce, U256::from(batch_tx.nonce));
+        assert_eq!(format!("{:02x}", signed.hash), batch_tx.tx_hash);
+    }
+    // The details contain the first transaction.
+    assert_eq!(tx_details.tx_hex, batch_txs[0].tx_hex);
+    assert_eq!(tx_details.tx_hash, batch_txs[0].tx_hash);
+    assert_eq!(tx_details.to, vec![to, my_address]);
+    assert_eq!(tx_details.total_amount, BigDecimal::from(3));
+    assert_eq!(tx_details.received_by_me, BigDecimal::from(2));
+    assert_eq!(tx_details.spent_by_me, "3.0003".parse::<BigDecimal>().unwrap());
+    assert_eq!(tx_details.my_balance_change, "-1.0003".parse::<BigDecimal>().unwrap());
+    let expected_fee = EthTxFeeDetails {
+        coin: "ETH".into(),
+        gas_price: "0.000000001".parse().unwrap(),
+        gas: ETH_GAS * 2,
+        total_fee: "0.0003".parse().unwrap(),
+    };
+    assert_eq!(tx_details.fee_details, Some(expected_fee.into()));
+}
+
+#[test]
+fn test_withdraw_many_impl_not_sufficient_balance() {
+    let (_ctx, coin) = eth_coin_for_test(EthCoinType::Eth, &["http://dummy.dummy"], None);
+    EthCoin::my_balance.mock_safe(|_| {
+        let balance = wei_from_big_decimal(&2.into(), 18).unwrap();
+        MockResult::Return(Box::new(futures01::future::ok(balance)))
+    });
+    EthCoin::send_raw_tx_bytes.mock_safe(|_, _| panic!("No transaction should be broadcasted"));
+
+    let output = WithdrawOutput {
+        to: "0x7Bc1bBDD6A0a722fC9bffC49c921B685ECB84b94".to_string(),
+        amount: 1.into(),
+        max: false,
+    };
+    let withdraw_req = WithdrawManyRequest {
+        coin: "ETH".to_string(),
+        from: None,
+        outputs: vec![output.clone(), output],
+        fee: Some(WithdrawFee::EthGas {
+            gas: ETH_GAS,
+            gas_price: 1.into(),
+        }),
+        memo: None,
+        inputs: None,
+    };
+    // The fees of both transactions are required on top of the amounts.
+    let error = block_on(withdraw_many_impl(coin, withdraw_req))
+        .unwrap_err()
+        .into_inner();
+    match error {
+        WithdrawError::NotSufficientBalance { required, .. } => {
+            assert_eq!(required, "2.0003".parse::<BigDecimal>().unwrap())
+        },
+        e => panic!("Unexpected error {:?}", e),
+    }
+}
+
 #[test]
 #[cfg(not(target_arch = "wasm32"))]
 fn test_nonce_lock() {
@@ -1433,6 +1542,7 @@ fn test_message_hash() {
         priv_key_policy: key_pair.into(),
         swap_contract_address: Address::from_str(ETH_DEV_SWAP_CONTRACT).unwrap(),
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         web3_instances: vec![Web3Instance {
             web3: web3.clone(),
@@ -1478,6 +1588,7 @@ fn test_sign_verify_message() {
         priv_key_policy: key_pair.into(),
         swap_contract_address: Address::from_str(ETH_DEV_SWAP_CONTRACT).unwrap(),
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         web3_instances: vec![Web3Instance {
             web3: web3.clone(),
@@ -1534,6 +1645,7 @@ fn test_eth_extract_secret() {
         priv_key_policy: key_pair.into(),
         swap_contract_address,
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         ticker: "ETH".into(),
         web3_instances: vec![Web3Instance {
@@ -32,6 +32,7 @@ async fn test_send() {
         priv_key_policy: key_pair.into(),
         swap_contract_address: Address::from_str(ETH_DEV_SWAP_CONTRACT).unwrap(),
         fallback_swap_contract: None,
+        swap_v2_contract: None,
         contract_supports_watchers: false,
         web3_instances: vec![Web3Instance {
             web3: web3.clone(),
@@ -28,6 +28,8 @@ pub enum EthActivationV2Error {
     #[display(fmt = "Error deserializing 'derivation_path': {}", _0)]
     ErrorDeserializingDerivationPath(String),
     PrivKeyPolicyNotAllowed(PrivKeyPolicyNotAllowed),
+    #[display(fmt = "Failed to initialize the balance streaming: {}", _0)]
+    BalanceStreamInitError(String),
     #[cfg(target_arch = "wasm32")]
     #[from_trait(WithMetamaskRpcError::metamask_rpc_error)]
     #[display(fmt = "{}", _0)]
@@ -89,6 +91,8 @@ pub struct EthActivationV2Request {
     pub rpc_mode: EthRpcMode,
     pub swap_contract_address: Address,
     pub fallback_swap_contract: Option<Address>,
+    /// Address of the taker swap contract used by the upgraded swap protocol (swap v2).
+    pub swap_v2_contract_address: Option<Address>,
     #[serde(default)]
     pub contract_supports_watchers: bool,
     pub gas_station_url: Option<String>,
@@ -200,6 +204,7 @@ impl EthCoin {
             sign_message_prefix: self.sign_message_prefix.clone(),
             swap_contract_address: self.swap_contract_address,
             fallback_swap_contract: self.fallback_swap_contract,
+            swap_v2_contract: self.swap_v2_contract,
             contract_supports_watchers: self.contract_supports_watchers,
             decimals,
             ticker,
@@ -247,6 +252,13 @@ pub async fn eth_coin_from_conf_and_request_v2(
         }
     }
 
+    if req.swap_v2_contract_address == Some(Address::default()) {
+        return Err(EthActivationV2Error::InvalidSwapContractAddr(
+            "swap_v2_contract_address can't be zero address".to_string(),
+        )
+        .into());
+    }
+
     let (my_address, priv_key_policy) =
         build_address_and_priv_key_policy(conf, priv_key_policy, &req.path_to_address).await?;
     let my_address_str = checksum_address(&format!("{:02x}", my_address));
@@ -307,6 +319,7 @@ pub async fn eth_coin_from_conf_and_request_v2(
         sign_message_prefix,
         swap_contract_address: req.swap_contract_address,
         fallback_swap_contract: req.fallback_swap_contract,
+        swap_v2_contract: req.swap_v2_contract_address,
         contract_supports_watchers: req.contract_supports_watchers,
         decimals: ETH_DECIMALS,
         ticker,
@@ -101,6 +101,7 @@ cfg_wasm32! {
     use hd_wallet_storage::HDWalletDb;
     use mm2_db::indexed_db::{ConstructibleDb, DbLocked, SharedDb};
     use tx_history_storage::wasm::{clear_tx_history, load_tx_history, save_tx_history, TxHistoryDb};
+    use utxo::frozen_outpoints_storage::FrozenOutPointsDb;
     pub type TxHistoryDbLocked<'a> = DbLocked<'a, TxHistoryDb>;
 }
 
@@ -276,6 +277,7 @@ pub use solana::{SolanaActivationParams, SolanaCoin, SolanaFeeDetails};
 
 pub mod utxo;
 use utxo::bch::{bch_coin_with_policy, BchActivationRequest, BchCoin};
+use utxo::frozen_outpoints_storage::load_frozen_outpoints;
 use utxo::qtum::{self, qtum_coin_with_policy, Qrc20AddressError, QtumCoin, QtumDelegationOps, QtumDelegationRequest,
                  QtumStakingInfosDetails, ScriptHashTypeNotSupported};
 use utxo::rpc_clients::UtxoRpcError;
@@ -284,7 +286,7 @@ use utxo::slp::{slp_addr_from_pubkey_str, SlpFeeDetails};
 use utxo::utxo_common::big_decimal_from_sat_unsigned;
 use utxo::utxo_standard::{utxo_standard_coin_with_policy, UtxoStandardCoin};
 use utxo::UtxoActivationParams;
-use utxo::{BlockchainNetwork, GenerateTxError, UtxoFeeDetails, UtxoTx};
+use utxo::{BlockchainNetwork, FrozenOutPoints, GenerateTxError, UtxoFeeDetails, UtxoOutPoint, UtxoTx};
 
 pub mod nft;
 use nft::nft_errors::GetNftInfoError;
@@ -298,12 +300,15 @@ pub type BalanceResult<T> = Result<T, MmError<BalanceError>>;
 pub type BalanceFut<T> = Box<dyn Future<Item = T, Error = MmError<BalanceError>> + Send>;
 pub type NonZeroBalanceFut<T> = Box<dyn Future<Item = T, Error = MmError<GetNonZeroBalance>> + Send>;
 pub type NumConversResult<T> = Result<T, MmError<NumConversError>>;
+pub type DeriveHtlcResult<T> = Result<T, MmError<UnexpectedDerivationMethod>>;
 pub type StakingInfosResult = Result<StakingInfos, MmError<StakingInfosError>>;
 pub type StakingInfosFut = Box<dyn Future<Item = StakingInfos, Error = MmError<StakingInfosError>> + Send>;
 pub type DelegationResult = Result<TransactionDetails, MmError<DelegationError>>;
 pub type DelegationFut = Box<dyn Future<Item = TransactionDetails, Error = MmError<DelegationError>> + Send>;
 pub type WithdrawResult = Result<TransactionDetails, MmError<WithdrawError>>;
 pub type WithdrawFut = Box<dyn Future<Item = TransactionDetails, Error = MmError<WithdrawError>> + Send>;
+pub type WithdrawManyResult = Result<WithdrawManyDetails, MmError<WithdrawError>>;
+pub type WithdrawManyFut = Box<dyn Future<Item = WithdrawManyDetails, Error = MmError<WithdrawError>> + Send>;
 pub type TradePreimageResult<T> = Result<T, MmError<TradePreimageError>>;
 pub type TradePreimageFut<T> = Box<dyn Future<Item = T, Error = MmError<TradePreimageError>> + Send>;
 pub type CoinFindResult<T> = Result<T, MmError<CoinFindError>>;
@@ -972,11 +977,11 @@ pub trait SwapOps {
     ) -> Result<Option<BytesJson>, MmError<NegotiateSwapContractAddrErr>>;
 
     /// Consider using [`SwapOps::derive_htlc_pubkey`] if you need the public key only.
-    /// Some coins may not have a private key.
-    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> KeyPair;
+    /// Some coins may not have a private key, e.g. if they are activated with a hardware wallet.
+    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<KeyPair>;
 
     /// Derives an HTLC key-pair and returns a public key corresponding to that key.
-    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> Vec<u8>;
+    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Vec<u8>>;
 
     fn validate_other_pubkey(&self, raw_pubkey: &[u8]) -> MmResult<(), ValidateOtherPubKeyErr>;
 
@@ -1089,6 +1094,10 @@ pub struct SendTakerFundingArgs<'a> {
     pub time_lock: u64,
     /// The hash of the secret generated by taker, this needs to be revealed for immediate refund
     pub taker_secret_hash: &'a [u8],
+    /// The hash of the secret generated by maker, used by coins locking the taker payment on funding (EVM)
+    pub maker_secret_hash: &'a [u8],
+    /// Timelock of the taker payment, used by coins locking the taker payment on funding (EVM)
+    pub payment_time_lock: u64,
     /// Maker's pubkey
     pub maker_pub: &'a [u8],
     /// DEX fee amount
@@ -1139,6 +1148,10 @@ pub struct ValidateTakerFundingArgs<'a, Coin: CoinAssocTypes + ?Sized> {
     pub time_lock: u64,
     /// The hash of the secret generated by taker
     pub taker_secret_hash: &'a [u8],
+    /// The hash of the secret generated by maker
+    pub maker_secret_hash: &'a [u8],
+    /// Timelock of the taker payment
+    pub payment_time_lock: u64,
     /// Taker's pubkey
     pub other_pub: &'a Coin::Pubkey,
     /// DEX fee amount
@@ -1216,6 +1229,10 @@ impl From<UtxoSignWithKeyPairError> for TxGenError {
     fn from(err: UtxoSignWithKeyPairError) -> Self { TxGenError::Signing(err.to_string()) }
 }
 
+impl From<UnexpectedDerivationMethod> for TxGenError {
+    fn from(err: UnexpectedDerivationMethod) -> Self { TxGenError::Signing(err.to_string()) }
+}
+
 /// Enum covering error cases that can happen during taker funding validation.
 #[derive(Debug, Display)]
 pub enum ValidateTakerFundingError {
@@ -1232,6 +1249,12 @@ pub enum ValidateTakerFundingError {
     TxLacksOfOutputs,
     /// Input payment timelock overflows the type used by specific coin.
     LocktimeOverflow(String),
+    /// Funding transaction doesn't match the expected contract call.
+    WrongPaymentTx(String),
+    /// Funding is not in the expected state on-chain (e.g. it's already spent or refunded).
+    UnexpectedPaymentState(String),
+    /// Internal error, e.g. coin is not configured to support the swap protocol.
+    InternalError(String),
 }
 
 impl From<NumConversError> for ValidateTakerFundingError {
@@ -1242,6 +1265,10 @@ impl From<UtxoRpcError> for ValidateTakerFundingError {
     fn from(err: UtxoRpcError) -> Self { ValidateTakerFundingError::Rpc(err.to_string()) }
 }
 
+impl From<UnexpectedDerivationMethod> for ValidateTakerFundingError {
+    fn from(err: UnexpectedDerivationMethod) -> Self { ValidateTakerFundingError::InternalError(err.to_string()) }
+}
+
 /// Enum covering error cases that can happen during taker funding spend preimage validation.
 #[derive(Debug, Display)]
 pub enum ValidateTakerFundingSpendPreimageError {
@@ -1290,6 +1317,8 @@ pub enum ValidateTakerPaymentSpendPreimageError {
     TxGenError(String),
     /// Input payment timelock overflows the type used by specific coin.
     LocktimeOverflow(String),
+    /// Coin's RPC error
+    Rpc(String),
 }
 
 impl From<UtxoSignWithKeyPairError> for ValidateTakerPaymentSpendPreimageError {
@@ -1396,7 +1425,7 @@ pub trait SwapOpsV2: CoinAssocTypes + Send + Sync + 'static {
     ) -> TransactionResult;
 
     /// Derives an HTLC key-pair and returns a public key corresponding to that key.
-    fn derive_htlc_pubkey_v2(&self, swap_unique_data: &[u8]) -> Self::Pubkey;
+    fn derive_htlc_pubkey_v2(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Self::Pubkey>;
 }
 
 /// Operations that coins have independently from the MarketMaker.
@@ -1509,7 +1538,7 @@ pub trait GetWithdrawSenderAddress {
 
     async fn get_withdraw_sender_address(
         &self,
-        req: &WithdrawRequest,
+        from: Option<&WithdrawFrom>,
     ) -> MmResult<WithdrawSenderAddress<Self::Address, Self::Pubkey>, WithdrawError>;
 }
 
@@ -1537,6 +1566,9 @@ pub struct WithdrawRequest {
     max: bool,
     fee: Option<WithdrawFee>,
     memo: Option<String>,
+    /// The outputs to spend instead of the automatically selected ones.
+    /// Currently, this field is supported by UTXO coins **only**.
+    inputs: Option<Vec<UtxoOutPoint>>,
     /// Currently, this flag is used by ETH/ERC20 coins activated with MetaMask **only**.
     #[cfg(target_arch = "wasm32")]
     #[serde(default)]
@@ -1591,12 +1623,66 @@ impl WithdrawRequest {
             max: true,
             fee: None,
             memo: None,
+            inputs: None,
             #[cfg(target_arch = "wasm32")]
             broadcast: false,
         }
     }
 }
 
+#[derive(Clone, Deserialize)]
+pub struct WithdrawOutput {
+    to: String,
+    #[serde(default)]
+    amount: BigDecimal,
+    /// Send the rest of the balance to this output. Currently, this flag is supported by UTXO coins **only**.
+    #[serde(default)]
+    max: bool,
+}
+
+/// The batch withdrawal paying to several outputs at once.
+#[derive(Clone, Deserialize)]
+pub struct WithdrawManyRequest {
+    coin: String,
+    from: Option<WithdrawFrom>,
+    outputs: Vec<WithdrawOutput>,
+    fee: Option<WithdrawFee>,
+    memo: Option<String>,
+    /// The outputs to spend instead of the automatically selected ones.
+    /// Currently, this field is supported by UTXO coins **only**.
+    inputs: Option<Vec<UtxoOutPoint>>,
+}
+
+impl WithdrawManyRequest {
+    #[allow(clippy::result_large_err)]
+    fn ensure_no_max_outputs(&self) -> Result<(), MmError<WithdrawError>> {
+        if self.outputs.iter().any(|output| output.max) {
+            return MmError::err(WithdrawError::UnsupportedError(format!(
+                "'max' outputs are not supported by {} batch withdrawal",
+                self.coin
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl From<WithdrawRequest> for WithdrawManyRequest {
+    fn from(req: WithdrawRequest) -> Self {
+        WithdrawManyRequest {
+            coin: req.coin,
+            from: req.from,
+            outputs: vec![WithdrawOutput {
+                to: req.to,
+                amount: req.amount,
+                max: req.max,
+            }],
+            fee: req.fee,
+            memo: req.memo,
+            inputs: req.inputs,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum StakingInfosDetails {
@@ -1775,6 +1861,38 @@ pub struct TransactionDetails {
     memo: Option<String>,
 }
 
+/// A signed transaction of the batch withdrawal paying to one of the outputs.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SignedBatchTx {
+    pub nonce: u64,
+    /// Raw bytes of signed transaction, this should be sent as is to `send_raw_transaction_bytes` RPC to broadcast the transaction
+    pub tx_hex: BytesJson,
+    /// Transaction hash in hexadecimal format
+    pub tx_hash: String,
+}
+
+/// Batch withdrawal details
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WithdrawManyDetails {
+    /// The details summing up all of the outputs.
+    /// If the outputs are paid with several transactions, `tx_hex` and `tx_hash` are the ones of the first transaction.
+    #[serde(flatten)]
+    pub details: TransactionDetails,
+    /// The signed transactions of the coins that can't pay to several outputs within one transaction, e.g. ETH.
+    /// None of them is broadcasted, they have to be sent in the nonce order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub batch_txs: Vec<SignedBatchTx>,
+}
+
+impl From<TransactionDetails> for WithdrawManyDetails {
+    fn from(details: TransactionDetails) -> Self {
+        WithdrawManyDetails {
+            details,
+            batch_txs: Vec::new(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct BlockHeightAndTime {
     height: u64,
@@ -2245,6 +2363,21 @@ pub enum WithdrawError {
     CoinDoesntSupportInitWithdraw {
         coin: String,
     },
+    #[display(fmt = "'{}' coin doesn't support batch withdrawal", coin)]
+    CoinDoesntSupportWithdrawMany {
+        coin: String,
+    },
+    #[display(fmt = "Withdraw request should contain at least one output")]
+    EmptyOutputs,
+    #[display(
+        fmt = "Input {}:{} is not spendable: it's either spent, immature or frozen",
+        tx_hash,
+        index
+    )]
+    InputNotSpendable {
+        tx_hash: H256Json,
+        index: u32,
+    },
     #[display(
         fmt = "Not enough {} to withdraw: available {}, required at least {}",
         coin,
@@ -2348,6 +2481,9 @@ impl HttpStatusCode for WithdrawError {
             WithdrawError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
             WithdrawError::Timeout(_) => StatusCode::REQUEST_TIMEOUT,
             WithdrawError::CoinDoesntSupportInitWithdraw { .. }
+            | WithdrawError::CoinDoesntSupportWithdrawMany { .. }
+            | WithdrawError::EmptyOutputs
+            | WithdrawError::InputNotSpendable { .. }
             | WithdrawError::NotSufficientBalance { .. }
             | WithdrawError::NotSufficientPlatformBalanceForFee { .. }
             | WithdrawError::ZeroBalanceToWithdrawMax
@@ -2597,6 +2733,14 @@ pub trait MmCoin:
 
     fn withdraw(&self, req: WithdrawRequest) -> WithdrawFut;
 
+    /// Generates the transaction paying to all of the `req.outputs`.
+    /// The coins that can't pay to several outputs within one transaction sign one transaction per output
+    /// and return them in [`WithdrawManyDetails::batch_txs`] along with the details summing them up.
+    fn withdraw_many(&self, req: WithdrawManyRequest) -> WithdrawManyFut {
+        let error = WithdrawError::CoinDoesntSupportWithdrawMany { coin: req.coin };
+        Box::new(futures01::future::err(MmError::new(error)))
+    }
+
     fn get_raw_transaction(&self, req: RawTransactionRequest) -> RawTransactionFut;
 
     fn get_tx_hex_by_hash(&self, tx_hash: Vec<u8>) -> RawTransactionFut;
@@ -3028,10 +3172,15 @@ pub struct CoinsContext {
     platform_coin_tokens: PaMutex<HashMap<String, HashSet<String>>>,
     scan_addresses_manager: ScanAddressesTaskManagerShared,
     withdraw_task_manager: WithdrawTaskManagerShared,
+    /// A map from a currency ticker symbol to the outputs frozen by the user.
+    /// The frozen outputs are never selected automatically, so they can't be spent by swaps.
+    frozen_outpoints: PaMutex<HashMap<String, FrozenOutPoints>>,
     #[cfg(target_arch = "wasm32")]
     tx_history_db: SharedDb<TxHistoryDb>,
     #[cfg(target_arch = "wasm32")]
     hd_wallet_db: SharedDb<HDWalletDb>,
+    #[cfg(target_arch = "wasm32")]
+    frozen_outpoints_db: SharedDb<FrozenOutPointsDb>,
 }
 
 #[derive(Debug)]
@@ -3052,10 +3201,13 @@ impl CoinsContext {
                 get_new_address_manager: GetNewAddressTaskManager::new_shared(),
                 scan_addresses_manager: ScanAddressesTaskManager::new_shared(),
                 withdraw_task_manager: WithdrawTaskManager::new_shared(),
+                frozen_outpoints: PaMutex::new(HashMap::new()),
                 #[cfg(target_arch = "wasm32")]
                 tx_history_db: ConstructibleDb::new(ctx).into_shared(),
                 #[cfg(target_arch = "wasm32")]
                 hd_wallet_db: ConstructibleDb::new_shared_db(ctx).into_shared(),
+                #[cfg(target_arch = "wasm32")]
+                frozen_outpoints_db: ConstructibleDb::new(ctx).into_shared(),
             })
         })))
     }
@@ -3174,6 +3326,28 @@ impl CoinsContext {
             .error_log_with_msg(&format!("Error aborting coin({ticker}) futures"));
     }
 
+    /// Returns the outputs of the `ticker` coin frozen by the user.
+    pub fn frozen_outpoints(&self, ticker: &str) -> FrozenOutPoints {
+        let frozen_outpoints = self.frozen_outpoints.lock();
+        frozen_outpoints.get(ticker).cloned().unwrap_or_default()
+    }
+
+    /// Replaces the outputs of the `ticker` coin frozen by the user.
+    /// Note the outputs are not persisted, see [`utxo::frozen_outpoints_storage::save_frozen_outpoints`].
+    pub fn set_frozen_outpoints(&self, ticker: &str, frozen_outpoints: FrozenOutPoints) {
+        let mut frozen = self.frozen_outpoints.lock();
+        frozen.insert(ticker.to_owned(), frozen_outpoints);
+    }
+
+    /// Returns the total value of the `ticker` coin outputs frozen by the user.
+    pub fn frozen_amount(&self, ticker: &str) -> BigDecimal {
+        let frozen_outpoints = self.frozen_outpoints.lock();
+        frozen_outpoints
+            .get(ticker)
+            .map(|outpoints| outpoints.values().fold(BigDecimal::from(0), |sum, value| sum + value))
+            .unwrap_or_default()
+    }
+
     #[cfg(target_arch = "wasm32")]
     async fn tx_history_db(&self) -> TxHistoryResult<TxHistoryDbLocked<'_>> {
         Ok(self.tx_history_db.get_or_initialize().await?)
@@ -3700,6 +3874,14 @@ pub async fn lp_register_coin(
     let RegisterCoinParams { ticker } = params;
     let cctx = CoinsContext::from_ctx(ctx).map_to_mm(RegisterCoinError::Internal)?;
 
+    // The outputs frozen by the user must stay excluded from the coin selection after a restart.
+    if let MmCoinEnum::UtxoCoin(_) | MmCoinEnum::QtumCoin(_) = coin {
+        let frozen_outpoints = load_frozen_outpoints(ctx, &ticker)
+            .await
+            .mm_err(|e| RegisterCoinError::Internal(e.to_string()))?;
+        cctx.set_frozen_outpoints(&ticker, frozen_outpoints);
+    }
+
     // TODO AP: locking the coins list during the entire initialization prevents different coins from being
     // activated concurrently which results in long activation time: https://github.com/KomodoPlatform/atomicDEX/issues/24
     // So I'm leaving the possibility of race condition intentionally in favor of faster concurrent activation.
@@ -3848,6 +4030,14 @@ pub async fn withdraw(ctx: MmArc, req: WithdrawRequest) -> WithdrawResult {
     coin.withdraw(req).compat().await
 }
 
+pub async fn withdraw_many(ctx: MmArc, req: WithdrawManyRequest) -> WithdrawManyResult {
+    if req.outputs.is_empty() {
+        return MmError::err(WithdrawError::EmptyOutputs);
+    }
+    let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
+    coin.withdraw_many(req).compat().await
+}
+
 pub async fn get_raw_transaction(ctx: MmArc, req: RawTransactionRequest) -> RawTransactionResult {
     let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
     coin.get_raw_transaction(req).compat().await
@@ -6,6 +6,7 @@ use mm2_net::transport::SlurpError;
 use mm2_number::bigdecimal_custom::CheckedDivision;
 use mm2_number::{BigDecimal, MmNumber};
 use num_traits::CheckedDiv;
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 #[cfg(feature = "run-docker-tests")] use std::str::FromStr;
 use std::str::Utf8Error;
@@ -90,6 +91,14 @@ impl Default for Provider {
     fn default() -> Self { Provider::Unknown }
 }
 
+/// USD price of a coin.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UsdPrice {
+    pub price: MmNumber,
+    /// `None` if the source doesn't report it, e.g. if the price is set manually.
+    pub last_updated_timestamp: Option<u64>,
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct RateInfos {
     #[allow(dead_code)]
@@ -142,6 +151,21 @@ impl TickerInfosRegistry {
         self.get_infos(base).zip(self.get_infos(rel))
     }
 
+    /// Returns the USD prices of the tickers having a known provider and a valid timestamp.
+    pub fn usd_prices(&self) -> HashMap<String, UsdPrice> {
+        self.0
+            .iter()
+            .filter(|(_, infos)| infos.price_provider != Provider::Unknown && infos.last_updated_timestamp != 0)
+            .map(|(ticker, infos)| {
+                let price = UsdPrice {
+                    price: infos.last_price.clone(),
+                    last_updated_timestamp: Some(infos.last_updated_timestamp),
+                };
+                (ticker.clone(), price)
+            })
+            .collect()
+    }
+
     pub fn get_cex_rates(&self, base: &str, rel: &str) -> Option<RateInfos> {
         match self.get_infos_pair(base, rel) {
             Some((base_price_infos, rel_price_infos)) => {
@@ -186,29 +210,37 @@ impl TickerInfosRegistry {
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-async fn process_price_request(price_url: &str) -> Result<TickerInfosRegistry, MmError<PriceServiceRequestError>> {
+async fn fetch_price_json<T: DeserializeOwned>(price_url: &str) -> Result<T, MmError<PriceServiceRequestError>> {
     debug!("Fetching price from: {}", price_url);
     let (status, headers, body) = mm2_net::native_http::slurp_url(price_url).await?;
     let (status_code, body, _) = (status, std::str::from_utf8(&body)?.trim().into(), headers);
     if status_code != StatusCode::OK {
         return MmError::err(PriceServiceRequestError::HttpProcessError(body));
     }
-    let model: HashMap<String, TickerInfos> = serde_json::from_str(&body)?;
-    Ok(TickerInfosRegistry(model))
+    Ok(serde_json::from_str(&body)?)
 }
 
 #[cfg(target_arch = "wasm32")]
-async fn process_price_request(price_url: &str) -> Result<TickerInfosRegistry, MmError<PriceServiceRequestError>> {
+async fn fetch_price_json<T: DeserializeOwned>(price_url: &str) -> Result<T, MmError<PriceServiceRequestError>> {
     debug!("Fetching price from: {}", price_url);
     let (status, headers, body) = mm2_net::wasm_http::slurp_url(price_url).await?;
     let (status_code, body, _) = (status, std::str::from_utf8(&body)?.trim().into(), headers);
     if status_code != StatusCode::OK {
         return MmError::err(PriceServiceRequestError::HttpProcessError(body));
     }
-    let model: HashMap<String, TickerInfos> = serde_json::from_str(&body)?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+async fn process_price_request(price_url: &str) -> Result<TickerInfosRegistry, MmError<PriceServiceRequestError>> {
+    let model: HashMap<String, TickerInfos> = fetch_price_json(price_url).await?;
     Ok(TickerInfosRegistry(model))
 }
 
+/// Fetches the USD prices in the [`UsdPrice`] format, e.g. `{"KMD": {"price": "0.3", "last_updated_timestamp": 1700000000}}`.
+pub async fn fetch_usd_prices(price_url: &str) -> Result<HashMap<String, UsdPrice>, MmError<PriceServiceRequestError>> {
+    fetch_price_json(price_url).await
+}
+
 pub async fn fetch_price_tickers(price_url: &str) -> Result<TickerInfosRegistry, MmError<PriceServiceRequestError>> {
     let model = process_price_request(price_url).await?;
     debug!("price registry size: {}", model.0.len());
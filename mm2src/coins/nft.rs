@@ -1,4 +1,6 @@
 use mm2_core::mm_ctx::MmArc;
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::RpcMethodRegistry;
 use mm2_err_handle::prelude::{MmError, MmResult};
 use url::Url;
 
@@ -59,6 +61,40 @@ const BLOCKLIST_SCAN: &str = "scan";
 /// of the generated transaction meant for transferring the NFT. On failure, it details the encountered error.
 pub type WithdrawNftResult = Result<TransactionNftDetails, MmError<WithdrawError>>;
 
+/// Registers the NFT mmrpc 2.0 methods.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "get_nft_list",
+        "Lists the NFTs of the node addresses.",
+        mmrpc_handler!(get_nft_list),
+    );
+    registry.register(
+        "get_nft_metadata",
+        "Returns the metadata of the NFT.",
+        mmrpc_handler!(get_nft_metadata),
+    );
+    registry.register(
+        "get_nft_transfers",
+        "Lists the NFT transfers of the node addresses.",
+        mmrpc_handler!(get_nft_transfers),
+    );
+    registry.register(
+        "refresh_nft_metadata",
+        "Refreshes the cached metadata of the NFT.",
+        mmrpc_handler!(refresh_nft_metadata),
+    );
+    registry.register(
+        "update_nft",
+        "Updates the cached NFT list and transfers.",
+        mmrpc_handler!(update_nft),
+    );
+    registry.register(
+        "withdraw_nft",
+        "Generates a transaction sending the NFT to the given address.",
+        mmrpc_handler!(withdraw_nft),
+    );
+}
+
 /// Fetches a list of user-owned NFTs across specified chains.
 ///
 /// The function aggregates NFTs based on provided chains, supports pagination, and
@@ -82,6 +82,7 @@ fn test_withdraw_to_p2sh_address_should_fail() {
         max: false,
         fee: None,
         memo: None,
+        inputs: None,
     };
     let err = coin.withdraw(req).wait().unwrap_err().into_inner();
     let expect = WithdrawError::InvalidAddress("QRC20 can be sent to P2PKH addresses only".to_owned());
@@ -121,6 +122,7 @@ fn test_withdraw_impl_fee_details() {
             gas_price: 40,
         }),
         memo: None,
+        inputs: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
 
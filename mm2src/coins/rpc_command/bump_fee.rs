@@ -0,0 +1,546 @@
+//! Fee bumping of the stuck UTXO transactions sent from the wallet address.
+//!
+//! * RBF replaces the transaction with the one spending the same inputs and paying the same recipients,
+//!   but with a higher fee deducted from the change. The original transaction should signal BIP125 replaceability,
+//!   see the `signal_rbf` coin config. According to the BIP125 rule 4, the replacement should pay
+//!   for its own bandwidth at least the relay fee rate of the node on top of the fee of the original transaction.
+//! * CPFP spends the change output of the transaction with a child transaction paying the fee
+//!   for both of them, so the miners are incentivized to mine the parent. It's the only option for the swap payments,
+//!   since they never signal replaceability: replacing them changes their hashes.
+//!
+//! The resulting transaction is broadcasted and recorded to [`RecentlySpentOutPoints`].
+//! The outputs of the replaced transaction are purged from it,
+//! so the following transactions don't spend the outputs of the transaction that won't be mined.
+
+use crate::utxo::rpc_clients::{UnspentInfo, UtxoRpcError};
+use crate::utxo::utxo_common::{big_decimal_from_sat, big_decimal_from_sat_unsigned, tx_size_in_v_bytes, UtxoTxBuilder};
+use crate::utxo::{output_script, sat_from_big_decimal, ActualTxFee, Address, BroadcastTxErr, FeePolicy,
+                  GenerateTxError, GetUtxoListOps, RecentlySpentOutPointsGuard, UtxoAddressFormat, UtxoCommonOps,
+                  UtxoFeeDetails, UtxoTx, UtxoTxBroadcastOps, UTXO_LOCK};
+use crate::{lp_coinfind_or_err, CoinFindError, MmCoinEnum, NumConversError, PrivKeyPolicyNotAllowed,
+            TransactionDetails, UnexpectedDerivationMethod, WithdrawFee};
+use chain::constants::{SEQUENCE_FINAL, SEQUENCE_RBF};
+use chain::{OutPoint, TransactionOutput};
+use common::{now_sec, HttpStatusCode, StatusCode};
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use keys::bytes::Bytes;
+use keys::Type as ScriptType;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use rpc::v1::types::{ToTxHash, H256 as H256Json};
+use script::{Builder, SignatureVersion, TransactionInputSigner, UnsignedTransactionInput};
+use serialization::{deserialize, serialize, serialize_with_flags, SERIALIZE_TRANSACTION_WITNESS};
+use std::collections::HashSet;
+use utxo_signer::with_key_pair::{sign_tx, UtxoSignWithKeyPairError};
+
+const KILO_BYTE: u64 = 1000;
+
+pub type BumpFeeResult<T> = Result<T, MmError<BumpFeeError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum BumpFeeError {
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Requested coin: {}; is not supported for this action.", _0)]
+    NotSupportedCoin(String),
+    #[display(fmt = "Invalid fee policy: {}", _0)]
+    InvalidFeePolicy(String),
+    #[display(fmt = "Transaction {} is already confirmed", tx_hash)]
+    TxAlreadyConfirmed { tx_hash: H256Json },
+    #[display(fmt = "Transaction can't be replaced: {}", _0)]
+    TxNotReplaceable(String),
+    #[display(fmt = "Transaction {} has no unspent output to the wallet address", tx_hash)]
+    NoChangeOutput { tx_hash: H256Json },
+    #[display(
+        fmt = "The new fee {} should be higher than the current fee {}",
+        new_fee,
+        current_fee
+    )]
+    FeeNotIncreased {
+        current_fee: BigDecimal,
+        new_fee: BigDecimal,
+    },
+    #[display(
+        fmt = "The new fee {} is too low to replace the transaction, required at least {}",
+        new_fee,
+        required_fee
+    )]
+    ReplacementFeeTooLow {
+        new_fee: BigDecimal,
+        required_fee: BigDecimal,
+    },
+    #[display(
+        fmt = "Not enough {} to bump the fee: available {}, required at least {}",
+        coin,
+        available,
+        required
+    )]
+    NotSufficientBalance {
+        coin: String,
+        available: BigDecimal,
+        required: BigDecimal,
+    },
+    #[display(fmt = "Transport error: {}", _0)]
+    Transport(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for BumpFeeError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BumpFeeError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            BumpFeeError::NotSupportedCoin(_)
+            | BumpFeeError::InvalidFeePolicy(_)
+            | BumpFeeError::TxAlreadyConfirmed { .. }
+            | BumpFeeError::TxNotReplaceable(_)
+            | BumpFeeError::NoChangeOutput { .. }
+            | BumpFeeError::FeeNotIncreased { .. }
+            | BumpFeeError::ReplacementFeeTooLow { .. }
+            | BumpFeeError::NotSufficientBalance { .. } => StatusCode::BAD_REQUEST,
+            BumpFeeError::Transport(_) | BumpFeeError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<CoinFindError> for BumpFeeError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => BumpFeeError::NoSuchCoin { coin },
+        }
+    }
+}
+
+impl From<UtxoRpcError> for BumpFeeError {
+    fn from(e: UtxoRpcError) -> Self {
+        match e {
+            UtxoRpcError::Internal(internal) => BumpFeeError::Internal(internal),
+            rpc => BumpFeeError::Transport(rpc.to_string()),
+        }
+    }
+}
+
+impl From<BroadcastTxErr> for BumpFeeError {
+    fn from(e: BroadcastTxErr) -> Self {
+        match e {
+            BroadcastTxErr::Rpc(rpc) => BumpFeeError::from(rpc),
+            BroadcastTxErr::Other(other) => BumpFeeError::Internal(other),
+        }
+    }
+}
+
+impl From<UnexpectedDerivationMethod> for BumpFeeError {
+    fn from(e: UnexpectedDerivationMethod) -> Self { BumpFeeError::NotSupportedCoin(e.to_string()) }
+}
+
+impl From<PrivKeyPolicyNotAllowed> for BumpFeeError {
+    fn from(e: PrivKeyPolicyNotAllowed) -> Self { BumpFeeError::NotSupportedCoin(e.to_string()) }
+}
+
+impl From<UtxoSignWithKeyPairError> for BumpFeeError {
+    fn from(e: UtxoSignWithKeyPairError) -> Self { BumpFeeError::Internal(format!("Error signing: {}", e)) }
+}
+
+impl From<NumConversError> for BumpFeeError {
+    fn from(e: NumConversError) -> Self { BumpFeeError::Internal(e.to_string()) }
+}
+
+impl From<serialization::Error> for BumpFeeError {
+    fn from(e: serialization::Error) -> Self { BumpFeeError::Internal(format!("Error deserializing tx: {:?}", e)) }
+}
+
+impl BumpFeeError {
+    fn from_generate_tx_error(gen_tx_err: GenerateTxError, coin: String, decimals: u8) -> BumpFeeError {
+        match gen_tx_err {
+            GenerateTxError::EmptyUtxoSet { required } => BumpFeeError::NotSufficientBalance {
+                coin,
+                available: BigDecimal::from(0),
+                required: big_decimal_from_sat_unsigned(required, decimals),
+            },
+            GenerateTxError::NotEnoughUtxos { sum_utxos, required } => BumpFeeError::NotSufficientBalance {
+                coin,
+                available: big_decimal_from_sat_unsigned(sum_utxos, decimals),
+                required: big_decimal_from_sat_unsigned(required, decimals),
+            },
+            GenerateTxError::Transport(e) => BumpFeeError::Transport(e),
+            e => BumpFeeError::Internal(e.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum BumpFeeMethod {
+    /// Replace the transaction with the one paying a higher fee.
+    Rbf,
+    /// Spend the change output of the transaction with a child transaction paying the fee for both of them.
+    Cpfp,
+}
+
+#[derive(Deserialize)]
+pub struct BumpFeeRequest {
+    coin: String,
+    tx_hash: H256Json,
+    method: BumpFeeMethod,
+    /// The new fee rate. `UtxoFixed` and `UtxoPerKbyte` are supported only.
+    /// When CPFP is used, the rate applies to the parent and the child transactions together.
+    fee: WithdrawFee,
+}
+
+/// Bumps the fee of the unconfirmed transaction sent from the wallet address and broadcasts the result.
+pub async fn bump_fee(ctx: MmArc, req: BumpFeeRequest) -> BumpFeeResult<TransactionDetails> {
+    match lp_coinfind_or_err(&ctx, &req.coin).await? {
+        MmCoinEnum::UtxoCoin(utxo) => bump_fee_impl(&utxo, req).await,
+        MmCoinEnum::QtumCoin(qtum) => bump_fee_impl(&qtum, req).await,
+        _ => MmError::err(BumpFeeError::NotSupportedCoin(req.coin)),
+    }
+}
+
+async fn bump_fee_impl<T>(coin: &T, req: BumpFeeRequest) -> BumpFeeResult<TransactionDetails>
+where
+    T: UtxoCommonOps + GetUtxoListOps,
+{
+    let decimals = coin.as_ref().decimals;
+    let actual_tx_fee = match req.fee {
+        WithdrawFee::UtxoFixed { ref amount } => ActualTxFee::FixedPerKb(sat_from_big_decimal(amount, decimals)?),
+        WithdrawFee::UtxoPerKbyte { ref amount } => ActualTxFee::Dynamic(sat_from_big_decimal(amount, decimals)?),
+        ref fee_policy => {
+            let error = format!(
+                "Expected 'UtxoFixed' or 'UtxoPerKbyte' fee types, found {:?}",
+                fee_policy
+            );
+            return MmError::err(BumpFeeError::InvalidFeePolicy(error));
+        },
+    };
+
+    let verbose_tx = coin
+        .as_ref()
+        .rpc_client
+        .get_verbose_transaction(&req.tx_hash)
+        .compat()
+        .await?;
+    if verbose_tx.confirmations > 0 {
+        return MmError::err(BumpFeeError::TxAlreadyConfirmed { tx_hash: req.tx_hash });
+    }
+    let tx: UtxoTx = deserialize(verbose_tx.hex.as_slice())?;
+
+    let my_address = coin.as_ref().derivation_method.single_addr_or_err()?.clone();
+    let _utxo_lock = UTXO_LOCK.lock().await;
+    let (unspents, recently_spent) = coin.get_unspent_ordered_list(&my_address).await?;
+
+    let bump_fee_tx = BumpFeeTx {
+        coin,
+        my_address,
+        tx,
+        actual_tx_fee,
+    };
+    match req.method {
+        BumpFeeMethod::Rbf => bump_fee_tx.replace_by_fee(unspents, recently_spent).await,
+        BumpFeeMethod::Cpfp => bump_fee_tx.child_pays_for_parent(unspents, recently_spent).await,
+    }
+}
+
+struct BumpFeeTx<'a, T> {
+    coin: &'a T,
+    my_address: Address,
+    /// The transaction which fee is bumped.
+    tx: UtxoTx,
+    actual_tx_fee: ActualTxFee,
+}
+
+impl<'a, T> BumpFeeTx<'a, T>
+where
+    T: UtxoCommonOps + GetUtxoListOps,
+{
+    async fn replace_by_fee(
+        self,
+        unspents: Vec<UnspentInfo>,
+        recently_spent: RecentlySpentOutPointsGuard<'_>,
+    ) -> BumpFeeResult<TransactionDetails> {
+        let ticker = self.coin.as_ref().conf.ticker.clone();
+        let decimals = self.coin.as_ref().decimals;
+
+        if !self.tx.inputs.iter().any(|input| input.sequence <= SEQUENCE_RBF) {
+            let error = "the transaction doesn't signal BIP125 replaceability".to_owned();
+            return MmError::err(BumpFeeError::TxNotReplaceable(error));
+        }
+
+        let inputs = self.my_spent_outputs().await?;
+        let current_fee = self.tx_fee(&inputs)?;
+
+        // The change is added by the builder last, so it's recalculated from scratch.
+        let my_script_pubkey = self.my_script_pubkey();
+        let mut outputs = self.tx.outputs.clone();
+        if outputs.len() > 1 && outputs.last().map(|output| &output.script_pubkey) == Some(&my_script_pubkey) {
+            outputs.pop();
+        }
+
+        // The outputs of the replaced transaction can't be spent by its replacement.
+        let tx_hash = self.tx.hash();
+        let replaced_inputs: HashSet<OutPoint> = inputs.iter().map(|input| input.outpoint).collect();
+        let available_inputs = unspents
+            .into_iter()
+            .filter(|unspent| unspent.outpoint.hash != tx_hash && !replaced_inputs.contains(&unspent.outpoint));
+
+        let (unsigned, data) = UtxoTxBuilder::new(self.coin)
+            .with_from_address(self.my_address.clone())
+            .add_required_inputs(inputs)
+            .fund_from_required_inputs()
+            .replaceable()
+            .add_available_inputs(available_inputs)
+            .add_outputs(outputs)
+            .with_fee_policy(FeePolicy::SendExact)
+            .with_fee(self.actual_tx_fee)
+            .build()
+            .await
+            .mm_err(|e| BumpFeeError::from_generate_tx_error(e, ticker.clone(), decimals))?;
+
+        let new_fee = data.fee_amount + data.unused_change;
+        if new_fee <= current_fee {
+            return MmError::err(BumpFeeError::FeeNotIncreased {
+                current_fee: big_decimal_from_sat_unsigned(current_fee, decimals),
+                new_fee: big_decimal_from_sat_unsigned(new_fee, decimals),
+            });
+        }
+
+        // BIP125 rule 4: the replacement pays for its own relay on top of the replaced transaction fee.
+        let relay_fee = self
+            .coin
+            .as_ref()
+            .rpc_client
+            .get_relay_fee()
+            .compat()
+            .await
+            .map_to_mm(UtxoRpcError::from)?;
+        let relay_fee_per_kb = sat_from_big_decimal(&relay_fee, decimals)?;
+        let new_v_size = tx_size_in_v_bytes(&self.my_address.addr_format, &UtxoTx::from(unsigned.clone())) as u64;
+        let required_fee = current_fee + relay_fee_per_kb * new_v_size / KILO_BYTE;
+        if new_fee < required_fee {
+            return MmError::err(BumpFeeError::ReplacementFeeTooLow {
+                new_fee: big_decimal_from_sat_unsigned(new_fee, decimals),
+                required_fee: big_decimal_from_sat_unsigned(required_fee, decimals),
+            });
+        }
+
+        self.sign_and_broadcast(unsigned, new_fee, BumpFeeMethod::Rbf, recently_spent)
+            .await
+    }
+
+    async fn child_pays_for_parent(
+        self,
+        unspents: Vec<UnspentInfo>,
+        recently_spent: RecentlySpentOutPointsGuard<'_>,
+    ) -> BumpFeeResult<TransactionDetails> {
+        let ticker = self.coin.as_ref().conf.ticker.clone();
+        let decimals = self.coin.as_ref().decimals;
+        let tx_hash = self.tx.hash();
+
+        let parent_inputs = self.my_spent_outputs().await?;
+        let parent_fee = self.tx_fee(&parent_inputs)?;
+
+        // Spend the largest unspent output of the parent transaction paying to the wallet address.
+        let my_script_pubkey = self.my_script_pubkey();
+        let change = unspents
+            .into_iter()
+            .filter(|unspent| unspent.outpoint.hash == tx_hash)
+            .filter(|unspent| {
+                self.tx
+                    .outputs
+                    .get(unspent.outpoint.index as usize)
+                    .map(|output| output.script_pubkey == my_script_pubkey)
+                    .unwrap_or(false)
+            })
+            .max_by_key(|unspent| unspent.value)
+            .or_mm_err(|| BumpFeeError::NoChangeOutput {
+                tx_hash: tx_hash.reversed().into(),
+            })?;
+
+        let mut unsigned = self.coin.as_ref().transaction_preimage();
+        unsigned.inputs.push(UnsignedTransactionInput {
+            previous_output: change.outpoint,
+            sequence: if self.coin.as_ref().conf.signal_rbf {
+                SEQUENCE_RBF
+            } else {
+                SEQUENCE_FINAL
+            },
+            amount: change.value,
+            witness: Vec::new(),
+        });
+        unsigned.outputs.push(TransactionOutput {
+            value: change.value,
+            script_pubkey: my_script_pubkey,
+        });
+
+        // The package of the parent and the child should pay the fee according to the requested rate.
+        let addr_format = &self.my_address.addr_format;
+        let parent_v_size = tx_v_size(&self.tx);
+        let child_v_size = tx_size_in_v_bytes(addr_format, &UtxoTx::from(unsigned.clone())) as u64;
+        let package_fee = match self.actual_tx_fee {
+            ActualTxFee::Dynamic(fee_per_kb) => fee_per_kb * (parent_v_size + child_v_size) / KILO_BYTE,
+            ActualTxFee::FixedPerKb(fee_per_kb) => {
+                fee_per_kb * ((parent_v_size + child_v_size + KILO_BYTE - 1) / KILO_BYTE)
+            },
+        };
+        if package_fee <= parent_fee {
+            return MmError::err(BumpFeeError::FeeNotIncreased {
+                current_fee: big_decimal_from_sat_unsigned(parent_fee, decimals),
+                new_fee: big_decimal_from_sat_unsigned(package_fee, decimals),
+            });
+        }
+        let child_fee = package_fee - parent_fee;
+
+        let required = child_fee + self.coin.as_ref().dust_amount;
+        if change.value < required {
+            return MmError::err(BumpFeeError::NotSufficientBalance {
+                coin: ticker,
+                available: big_decimal_from_sat_unsigned(change.value, decimals),
+                required: big_decimal_from_sat_unsigned(required, decimals),
+            });
+        }
+        unsigned.outputs[0].value -= child_fee;
+
+        self.sign_and_broadcast(unsigned, child_fee, BumpFeeMethod::Cpfp, recently_spent)
+            .await
+    }
+
+    fn my_script_pubkey(&self) -> Bytes { output_script(&self.my_address, ScriptType::P2PKH).to_bytes() }
+
+    /// Returns the outputs spent by [`BumpFeeTx::tx`].
+    /// All of them should belong to the wallet address, otherwise the transaction can't be rebuilt.
+    async fn my_spent_outputs(&self) -> BumpFeeResult<Vec<UnspentInfo>> {
+        let my_script_pubkey = self.my_script_pubkey();
+        let mut spent_outputs = Vec::with_capacity(self.tx.inputs.len());
+        for input in self.tx.inputs.iter() {
+            let prev_hash = input.previous_output.hash.reversed().into();
+            let prev_tx_bytes = self
+                .coin
+                .as_ref()
+                .rpc_client
+                .get_transaction_bytes(&prev_hash)
+                .compat()
+                .await?;
+            let prev_tx: UtxoTx = deserialize(prev_tx_bytes.as_slice())?;
+            let prev_output = prev_tx
+                .outputs
+                .get(input.previous_output.index as usize)
+                .or_mm_err(|| {
+                    let error = format!("{}:{} output doesn't exist", prev_hash, input.previous_output.index);
+                    BumpFeeError::Internal(error)
+                })?;
+            if prev_output.script_pubkey != my_script_pubkey {
+                let error = format!(
+                    "the input {}:{} doesn't belong to the wallet address",
+                    prev_hash, input.previous_output.index
+                );
+                return MmError::err(BumpFeeError::TxNotReplaceable(error));
+            }
+            spent_outputs.push(UnspentInfo {
+                outpoint: input.previous_output,
+                value: prev_output.value,
+                height: None,
+            });
+        }
+        Ok(spent_outputs)
+    }
+
+    fn tx_fee(&self, spent_outputs: &[UnspentInfo]) -> BumpFeeResult<u64> {
+        let sum_inputs = spent_outputs.iter().fold(0, |sum, input| sum + input.value);
+        let sum_outputs = self.tx.outputs.iter().fold(0, |sum, output| sum + output.value);
+        sum_inputs
+            .checked_sub(sum_outputs)
+            .or_mm_err(|| BumpFeeError::Internal("Transaction outputs exceed its inputs".to_owned()))
+    }
+
+    async fn sign_and_broadcast(
+        &self,
+        unsigned: TransactionInputSigner,
+        fee_amount: u64,
+        method: BumpFeeMethod,
+        mut recently_spent: RecentlySpentOutPointsGuard<'_>,
+    ) -> BumpFeeResult<TransactionDetails> {
+        let coin = self.coin.as_ref();
+        let ticker = coin.conf.ticker.clone();
+        let decimals = coin.decimals;
+        let key_pair = coin.priv_key_policy.activated_key_or_err()?;
+
+        let spent_by_me = unsigned.inputs.iter().fold(0, |sum, input| sum + input.amount);
+        let my_script_pubkey = self.my_script_pubkey();
+        let received_by_me = unsigned
+            .outputs
+            .iter()
+            .filter(|output| output.script_pubkey == my_script_pubkey)
+            .fold(0, |sum, output| sum + output.value);
+        let spent_outputs = unsigned
+            .inputs
+            .iter()
+            .map(|input| UnspentInfo {
+                outpoint: input.previous_output,
+                value: input.amount,
+                height: None,
+            })
+            .collect();
+
+        let signature_version = match self.my_address.addr_format {
+            UtxoAddressFormat::Segwit => SignatureVersion::WitnessV0,
+            _ => coin.conf.signature_version,
+        };
+        let prev_script = Builder::build_p2pkh(&self.my_address.hash);
+        let signed = sign_tx(unsigned, key_pair, prev_script, signature_version, coin.conf.fork_id)?;
+
+        self.coin.broadcast_tx(&signed).await?;
+        if let BumpFeeMethod::Rbf = method {
+            recently_spent.remove_outputs_of(&self.tx.hash());
+        }
+        recently_spent.add_spent(spent_outputs, signed.hash(), signed.outputs.clone());
+
+        let mut to = Vec::with_capacity(signed.outputs.len());
+        for output in signed.outputs.iter() {
+            let addresses = self
+                .coin
+                .addresses_from_script(&output.script_pubkey.clone().into())
+                .map_to_mm(BumpFeeError::Internal)?;
+            for address in addresses {
+                to.push(address.display_address().map_to_mm(BumpFeeError::Internal)?);
+            }
+        }
+        let tx_hex = match self.my_address.addr_format {
+            UtxoAddressFormat::Segwit => serialize_with_flags(&signed, SERIALIZE_TRANSACTION_WITNESS).into(),
+            _ => serialize(&signed).into(),
+        };
+        let fee_details = UtxoFeeDetails {
+            coin: Some(ticker.clone()),
+            amount: big_decimal_from_sat_unsigned(fee_amount, decimals),
+        };
+        Ok(TransactionDetails {
+            from: vec![self.my_address.display_address().map_to_mm(BumpFeeError::Internal)?],
+            to,
+            total_amount: big_decimal_from_sat_unsigned(spent_by_me, decimals),
+            spent_by_me: big_decimal_from_sat_unsigned(spent_by_me, decimals),
+            received_by_me: big_decimal_from_sat_unsigned(received_by_me, decimals),
+            my_balance_change: big_decimal_from_sat(received_by_me as i64 - spent_by_me as i64, decimals),
+            tx_hash: signed.hash().reversed().to_vec().to_tx_hash(),
+            tx_hex,
+            fee_details: Some(fee_details.into()),
+            block_height: 0,
+            coin: ticker,
+            internal_id: vec![].into(),
+            timestamp: now_sec(),
+            kmd_rewards: None,
+            transaction_type: Default::default(),
+            memo: None,
+        })
+    }
+}
+
+/// Returns the virtual size of the signed transaction.
+fn tx_v_size(tx: &UtxoTx) -> u64 {
+    let base_size = serialize(tx).len() as u64;
+    if !tx.has_witness() {
+        return base_size;
+    }
+    let total_size = serialize_with_flags(tx, SERIALIZE_TRANSACTION_WITNESS).len() as u64;
+    // https://github.com/bitcoin/bips/blob/master/bip-0141.mediawiki#transaction-size-calculations
+    (base_size * 3 + total_size + 3) / 4
+}
@@ -0,0 +1,441 @@
+//! On-demand consolidation of the small UTXOs of the wallet addresses.
+//!
+//! Unlike the background merge loop configured with `UtxoMergeParams`, the consolidation
+//! * selects the outputs by value, so the large outputs aren't respent needlessly;
+//! * can be limited by the fee rate, so the outputs are merged when the blocks are cheap;
+//! * skips the outputs that cost more to spend than their value;
+//! * keeps the outputs covering the amount locked by the active swaps untouched,
+//!   so the swap payments don't depend on the unconfirmed consolidation transaction;
+//! * handles the HD wallet addresses besides the activated one.
+
+use crate::utxo::rpc_clients::{UnspentInfo, UtxoRpcError};
+use crate::utxo::utxo_common::{big_decimal_from_sat, big_decimal_from_sat_unsigned, UtxoTxBuilder};
+use crate::utxo::{output_script, sat_from_big_decimal, ActualTxFee, Address, BroadcastTxErr, FeePolicy,
+                  GenerateTxError, GetUtxoListOps, UtxoAddressFormat, UtxoCommonOps, UtxoFeeDetails, UtxoOutPoint,
+                  UtxoTxBroadcastOps, UtxoTxGenerationOps, UTXO_LOCK};
+use crate::{lp_coinfind_or_err, CoinFindError, MmCoinEnum, NumConversError, PrivKeyPolicyNotAllowed,
+            TransactionDetails, UnexpectedDerivationMethod};
+use chain::TransactionOutput;
+use common::{now_sec, HttpStatusCode, StatusCode};
+use crypto::StandardHDCoinAddress;
+use derive_more::Display;
+use keys::{KeyPair, Private, Type as ScriptType};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use rpc::v1::types::ToTxHash;
+use script::{Builder, SignatureVersion};
+use serialization::{serialize, serialize_with_flags, SERIALIZE_TRANSACTION_WITNESS};
+use utxo_signer::with_key_pair::{sign_tx, UtxoSignWithKeyPairError};
+
+const KILO_BYTE: u64 = 1000;
+/// The size of the signed P2PKH input.
+const P2PKH_INPUT_SIZE: u64 = 148;
+/// The virtual size of the signed P2WPKH input.
+const P2WPKH_INPUT_V_SIZE: u64 = 68;
+
+pub type ConsolidateUtxosResult<T> = Result<T, MmError<ConsolidateUtxosError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum ConsolidateUtxosError {
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Requested coin: {}; is not supported for this action.", _0)]
+    NotSupportedCoin(String),
+    #[display(fmt = "Invalid request: {}", _0)]
+    InvalidRequest(String),
+    #[display(
+        fmt = "The current fee per kbyte {} exceeds the maximum {}",
+        fee_per_kbyte,
+        max_fee_per_kbyte
+    )]
+    FeeRateTooHigh {
+        fee_per_kbyte: BigDecimal,
+        max_fee_per_kbyte: BigDecimal,
+    },
+    #[display(fmt = "Transport error: {}", _0)]
+    Transport(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for ConsolidateUtxosError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ConsolidateUtxosError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            ConsolidateUtxosError::NotSupportedCoin(_)
+            | ConsolidateUtxosError::InvalidRequest(_)
+            | ConsolidateUtxosError::FeeRateTooHigh { .. } => StatusCode::BAD_REQUEST,
+            ConsolidateUtxosError::Transport(_) | ConsolidateUtxosError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+impl From<CoinFindError> for ConsolidateUtxosError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => ConsolidateUtxosError::NoSuchCoin { coin },
+        }
+    }
+}
+
+impl From<UtxoRpcError> for ConsolidateUtxosError {
+    fn from(e: UtxoRpcError) -> Self {
+        match e {
+            UtxoRpcError::Internal(internal) => ConsolidateUtxosError::Internal(internal),
+            rpc => ConsolidateUtxosError::Transport(rpc.to_string()),
+        }
+    }
+}
+
+impl From<BroadcastTxErr> for ConsolidateUtxosError {
+    fn from(e: BroadcastTxErr) -> Self {
+        match e {
+            BroadcastTxErr::Rpc(rpc) => ConsolidateUtxosError::from(rpc),
+            BroadcastTxErr::Other(other) => ConsolidateUtxosError::Internal(other),
+        }
+    }
+}
+
+impl From<GenerateTxError> for ConsolidateUtxosError {
+    fn from(e: GenerateTxError) -> Self {
+        match e {
+            GenerateTxError::Transport(transport) => ConsolidateUtxosError::Transport(transport),
+            e => ConsolidateUtxosError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<UnexpectedDerivationMethod> for ConsolidateUtxosError {
+    fn from(e: UnexpectedDerivationMethod) -> Self { ConsolidateUtxosError::NotSupportedCoin(e.to_string()) }
+}
+
+impl From<PrivKeyPolicyNotAllowed> for ConsolidateUtxosError {
+    fn from(e: PrivKeyPolicyNotAllowed) -> Self { ConsolidateUtxosError::NotSupportedCoin(e.to_string()) }
+}
+
+impl From<UtxoSignWithKeyPairError> for ConsolidateUtxosError {
+    fn from(e: UtxoSignWithKeyPairError) -> Self { ConsolidateUtxosError::Internal(format!("Error signing: {}", e)) }
+}
+
+impl From<NumConversError> for ConsolidateUtxosError {
+    fn from(e: NumConversError) -> Self { ConsolidateUtxosError::InvalidRequest(e.to_string()) }
+}
+
+fn default_merge_at() -> usize { 2 }
+
+fn default_max_merge_at_once() -> usize { 100 }
+
+#[derive(Deserialize)]
+pub struct ConsolidateUtxosRequest {
+    pub coin: String,
+    /// The HD wallet addresses which outputs are consolidated. The activated address is used if empty.
+    #[serde(default)]
+    addresses: Vec<StandardHDCoinAddress>,
+    /// Only the outputs with the value not greater than this one are merged. All the outputs are merged if not set.
+    max_output_value: Option<BigDecimal>,
+    /// The outputs of an address are merged only if there are at least `merge_at` of them.
+    #[serde(default = "default_merge_at")]
+    merge_at: usize,
+    /// The maximum number of the outputs merged by one transaction.
+    #[serde(default = "default_max_merge_at_once")]
+    max_merge_at_once: usize,
+    /// The consolidation fails with [`ConsolidateUtxosError::FeeRateTooHigh`]
+    /// if the current fee per kbyte is higher than this one.
+    max_fee_per_kbyte: Option<BigDecimal>,
+    /// Whether to merge the outputs that cost more to spend than their value.
+    #[serde(default)]
+    include_dust: bool,
+    /// Whether to return the signed transactions without broadcasting them.
+    #[serde(default)]
+    preview: bool,
+}
+
+#[derive(Serialize)]
+pub struct AddressConsolidation {
+    address: String,
+    /// The merged outputs. Empty if there are less than `merge_at` outputs to merge.
+    merged: Vec<UtxoOutPoint>,
+    /// The number of the outputs skipped since they cost more to spend than their value.
+    dust_skipped: usize,
+    /// The number of the outputs kept for the active swaps.
+    reserved_for_swaps: usize,
+    /// The consolidation transaction. Not broadcasted if `preview` is requested.
+    tx: Option<TransactionDetails>,
+}
+
+#[derive(Serialize)]
+pub struct ConsolidateUtxosResponse {
+    /// The fee per kbyte the consolidation transactions pay.
+    fee_per_kbyte: BigDecimal,
+    consolidations: Vec<AddressConsolidation>,
+}
+
+/// Merges the outputs of the wallet addresses.
+/// The largest outputs of the activated address covering `swaps_locked_amount` are never merged.
+pub async fn consolidate_utxos(
+    ctx: MmArc,
+    req: ConsolidateUtxosRequest,
+    swaps_locked_amount: BigDecimal,
+) -> ConsolidateUtxosResult<ConsolidateUtxosResponse> {
+    match lp_coinfind_or_err(&ctx, &req.coin).await? {
+        MmCoinEnum::UtxoCoin(utxo) => consolidate_utxos_impl(&utxo, req, swaps_locked_amount).await,
+        MmCoinEnum::QtumCoin(qtum) => consolidate_utxos_impl(&qtum, req, swaps_locked_amount).await,
+        _ => MmError::err(ConsolidateUtxosError::NotSupportedCoin(req.coin)),
+    }
+}
+
+async fn consolidate_utxos_impl<T>(
+    coin: &T,
+    req: ConsolidateUtxosRequest,
+    swaps_locked_amount: BigDecimal,
+) -> ConsolidateUtxosResult<ConsolidateUtxosResponse>
+where
+    T: UtxoCommonOps + GetUtxoListOps,
+{
+    if req.merge_at < 2 || req.max_merge_at_once < req.merge_at {
+        let error = format!(
+            "'merge_at' should be at least 2 and not greater than 'max_merge_at_once', found {} and {}",
+            req.merge_at, req.max_merge_at_once
+        );
+        return MmError::err(ConsolidateUtxosError::InvalidRequest(error));
+    }
+
+    let decimals = coin.as_ref().decimals;
+    let max_output_value = match req.max_output_value {
+        Some(ref max_output_value) => sat_from_big_decimal(max_output_value, decimals)?,
+        None => u64::MAX,
+    };
+    let swaps_locked_amount = sat_from_big_decimal(&swaps_locked_amount, decimals)?;
+
+    let actual_tx_fee = coin.get_tx_fee().await?;
+    let fee_per_kbyte = match actual_tx_fee {
+        ActualTxFee::Dynamic(fee_per_kb) | ActualTxFee::FixedPerKb(fee_per_kb) => fee_per_kb,
+    };
+    if let Some(ref max_fee_per_kbyte) = req.max_fee_per_kbyte {
+        if fee_per_kbyte > sat_from_big_decimal(max_fee_per_kbyte, decimals)? {
+            return MmError::err(ConsolidateUtxosError::FeeRateTooHigh {
+                fee_per_kbyte: big_decimal_from_sat_unsigned(fee_per_kbyte, decimals),
+                max_fee_per_kbyte: max_fee_per_kbyte.clone(),
+            });
+        }
+    }
+
+    let key_pairs = if req.addresses.is_empty() {
+        vec![activated_key_pair(coin)?]
+    } else {
+        req.addresses
+            .iter()
+            .map(|hd_address| hd_address_key_pair(coin, hd_address))
+            .collect::<ConsolidateUtxosResult<_>>()?
+    };
+
+    let _utxo_lock = UTXO_LOCK.lock().await;
+    let mut consolidations = Vec::with_capacity(key_pairs.len());
+    for (key_pair, address) in key_pairs {
+        let (mut unspents, mut recently_spent) = coin.get_unspent_ordered_list(&address).await?;
+        unspents.sort_unstable_by(|x, y| y.value.cmp(&x.value));
+
+        // The swaps spend the outputs of the activated address only, and they don't support the HD wallets yet.
+        let reserved_for_swaps = if coin.as_ref().derivation_method.single_addr() == Some(&address) {
+            reserve_outputs(&unspents, swaps_locked_amount)
+        } else {
+            0
+        };
+
+        let input_spend_fee = input_spend_fee(&actual_tx_fee, &address.addr_format);
+        let mut dust_skipped = 0;
+        let mut to_merge: Vec<UnspentInfo> = unspents
+            .into_iter()
+            .skip(reserved_for_swaps)
+            .filter(|unspent| unspent.value <= max_output_value)
+            .filter(|unspent| {
+                let is_dust = unspent.value <= input_spend_fee;
+                if is_dust && !req.include_dust {
+                    dust_skipped += 1;
+                    return false;
+                }
+                true
+            })
+            .collect();
+        // Merge the smallest outputs first.
+        to_merge.reverse();
+        to_merge.truncate(req.max_merge_at_once);
+
+        let mut consolidation = AddressConsolidation {
+            address: address.display_address().map_to_mm(ConsolidateUtxosError::Internal)?,
+            merged: Vec::new(),
+            dust_skipped,
+            reserved_for_swaps,
+            tx: None,
+        };
+        if to_merge.len() < req.merge_at {
+            consolidations.push(consolidation);
+            continue;
+        }
+
+        let value = to_merge.iter().fold(0, |sum, unspent| sum + unspent.value);
+        let script_pubkey = output_script(&address, ScriptType::P2PKH).to_bytes();
+        let output = TransactionOutput { value, script_pubkey };
+        let (unsigned, data) = UtxoTxBuilder::new(coin)
+            .with_from_address(address.clone())
+            .add_required_inputs(to_merge.clone())
+            .fund_from_required_inputs()
+            .replaceable()
+            .add_outputs(vec![output])
+            .with_fee_policy(FeePolicy::DeductFromOutput(0))
+            .with_fee(actual_tx_fee)
+            .build()
+            .await?;
+
+        let signature_version = match address.addr_format {
+            UtxoAddressFormat::Segwit => SignatureVersion::WitnessV0,
+            _ => coin.as_ref().conf.signature_version,
+        };
+        let prev_script = Builder::build_p2pkh(&address.hash);
+        let signed = sign_tx(
+            unsigned,
+            &key_pair,
+            prev_script,
+            signature_version,
+            coin.as_ref().conf.fork_id,
+        )?;
+        if !req.preview {
+            coin.broadcast_tx(&signed).await?;
+            recently_spent.add_spent(to_merge.clone(), signed.hash(), signed.outputs.clone());
+        }
+
+        let fee_amount = data.fee_amount + data.unused_change;
+        let tx_hex = match address.addr_format {
+            UtxoAddressFormat::Segwit => serialize_with_flags(&signed, SERIALIZE_TRANSACTION_WITNESS).into(),
+            _ => serialize(&signed).into(),
+        };
+        let ticker = coin.as_ref().conf.ticker.clone();
+        let fee_details = UtxoFeeDetails {
+            coin: Some(ticker.clone()),
+            amount: big_decimal_from_sat_unsigned(fee_amount, decimals),
+        };
+        consolidation.tx = Some(TransactionDetails {
+            from: vec![consolidation.address.clone()],
+            to: vec![consolidation.address.clone()],
+            total_amount: big_decimal_from_sat_unsigned(data.spent_by_me, decimals),
+            spent_by_me: big_decimal_from_sat_unsigned(data.spent_by_me, decimals),
+            received_by_me: big_decimal_from_sat_unsigned(data.received_by_me, decimals),
+            my_balance_change: big_decimal_from_sat(data.received_by_me as i64 - data.spent_by_me as i64, decimals),
+            tx_hash: signed.hash().reversed().to_vec().to_tx_hash(),
+            tx_hex,
+            fee_details: Some(fee_details.into()),
+            block_height: 0,
+            coin: ticker,
+            internal_id: vec![].into(),
+            timestamp: now_sec(),
+            kmd_rewards: None,
+            transaction_type: Default::default(),
+            memo: None,
+        });
+        consolidation.merged = to_merge.into_iter().map(|unspent| unspent.outpoint.into()).collect();
+        consolidations.push(consolidation);
+    }
+
+    Ok(ConsolidateUtxosResponse {
+        fee_per_kbyte: big_decimal_from_sat_unsigned(fee_per_kbyte, decimals),
+        consolidations,
+    })
+}
+
+fn activated_key_pair<T: UtxoCommonOps>(coin: &T) -> ConsolidateUtxosResult<(KeyPair, Address)> {
+    let key_pair = *coin.as_ref().priv_key_policy.activated_key_or_err()?;
+    let address = match coin.as_ref().derivation_method.single_addr() {
+        Some(my_address) => my_address.clone(),
+        // The activated key of the HD wallet is derived from the enabled address path.
+        None => coin.address_from_pubkey(key_pair.public()),
+    };
+    Ok((key_pair, address))
+}
+
+fn hd_address_key_pair<T: UtxoCommonOps>(
+    coin: &T,
+    hd_address: &StandardHDCoinAddress,
+) -> ConsolidateUtxosResult<(KeyPair, Address)> {
+    let conf = &coin.as_ref().conf;
+    let secret = coin
+        .as_ref()
+        .priv_key_policy
+        .hd_wallet_derived_priv_key_or_err(hd_address)?;
+    let private = Private {
+        prefix: conf.wif_prefix,
+        secret,
+        compressed: true,
+        checksum_type: conf.checksum_type,
+    };
+    let key_pair = KeyPair::from_private(private).map_to_mm(|e| ConsolidateUtxosError::Internal(e.to_string()))?;
+    let address = coin.address_from_pubkey(key_pair.public());
+    Ok((key_pair, address))
+}
+
+/// Returns the number of the first `unspents` covering the `locked_amount`.
+/// The `unspents` are expected to be ordered from the largest to the smallest.
+fn reserve_outputs(unspents: &[UnspentInfo], locked_amount: u64) -> usize {
+    let mut reserved_amount = 0;
+    unspents
+        .iter()
+        .take_while(|unspent| {
+            if reserved_amount >= locked_amount {
+                return false;
+            }
+            reserved_amount += unspent.value;
+            true
+        })
+        .count()
+}
+
+/// Returns the fee paid for spending an output of the `addr_format` address.
+/// The fixed fee is paid for every started kbyte like [`UtxoTxBuilder`] does.
+fn input_spend_fee(actual_tx_fee: &ActualTxFee, addr_format: &UtxoAddressFormat) -> u64 {
+    let input_size = match addr_format {
+        UtxoAddressFormat::Segwit => P2WPKH_INPUT_V_SIZE,
+        _ => P2PKH_INPUT_SIZE,
+    };
+    match actual_tx_fee {
+        ActualTxFee::Dynamic(fee_per_kb) => fee_per_kb * input_size / KILO_BYTE,
+        ActualTxFee::FixedPerKb(fee_per_kb) => fee_per_kb * ((input_size + KILO_BYTE - 1) / KILO_BYTE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain::OutPoint;
+
+    fn unspent(value: u64) -> UnspentInfo {
+        UnspentInfo {
+            outpoint: OutPoint::default(),
+            value,
+            height: None,
+        }
+    }
+
+    #[test]
+    fn test_reserve_outputs() {
+        let unspents = vec![unspent(500), unspent(300), unspent(100), unspent(50)];
+        assert_eq!(reserve_outputs(&unspents, 0), 0);
+        assert_eq!(reserve_outputs(&unspents, 500), 1);
+        assert_eq!(reserve_outputs(&unspents, 501), 2);
+        assert_eq!(reserve_outputs(&unspents, 900), 3);
+        assert_eq!(reserve_outputs(&unspents, 10000), 4);
+    }
+
+    #[test]
+    fn test_input_spend_fee() {
+        let standard = UtxoAddressFormat::Standard;
+        let segwit = UtxoAddressFormat::Segwit;
+        assert_eq!(input_spend_fee(&ActualTxFee::Dynamic(1000), &standard), 148);
+        assert_eq!(input_spend_fee(&ActualTxFee::Dynamic(1000), &segwit), 68);
+        // The input starts a new kbyte.
+        assert_eq!(input_spend_fee(&ActualTxFee::FixedPerKb(1000), &standard), 1000);
+        assert_eq!(input_spend_fee(&ActualTxFee::FixedPerKb(1000), &segwit), 1000);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{lp_coinfind_or_err, CoinsContext, MmCoinEnum, WithdrawError};
-use crate::{TransactionDetails, WithdrawRequest};
+use crate::{TransactionDetails, WithdrawManyRequest, WithdrawRequest};
 use async_trait::async_trait;
 use common::SuccessResponse;
 use crypto::hw_rpc_task::{HwRpcTaskAwaitingStatus, HwRpcTaskUserAction, HwRpcTaskUserActionRequest};
@@ -38,7 +38,31 @@ pub async fn init_withdraw(ctx: MmArc, request: WithdrawRequest) -> WithdrawInit
     let task = WithdrawTask {
         ctx: ctx.clone(),
         coin,
-        request,
+        request: WithdrawTaskRequest::Withdraw(request),
+    };
+    let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(WithdrawError::InternalError)?;
+    let task_id = WithdrawTaskManager::spawn_rpc_task(&coins_ctx.withdraw_task_manager, &spawner, task)?;
+    Ok(InitWithdrawResponse { task_id })
+}
+
+/// Starts the task generating a transaction that pays to all of the `request.outputs`.
+/// The task is managed by the same [`WithdrawTaskManager`] as the `init_withdraw` ones.
+pub async fn init_withdraw_many(ctx: MmArc, request: WithdrawManyRequest) -> WithdrawInitResult<InitWithdrawResponse> {
+    if request.outputs.is_empty() {
+        return MmError::err(WithdrawError::EmptyOutputs);
+    }
+    let coin = lp_coinfind_or_err(&ctx, &request.coin).await?;
+    // ZCoin shielded outputs aren't supported by the batch withdrawal.
+    if !matches!(coin, MmCoinEnum::UtxoCoin(_) | MmCoinEnum::QtumCoin(_)) {
+        return MmError::err(WithdrawError::CoinDoesntSupportWithdrawMany {
+            coin: coin.ticker().to_owned(),
+        });
+    }
+    let spawner = coin.spawner();
+    let task = WithdrawTask {
+        ctx: ctx.clone(),
+        coin,
+        request: WithdrawTaskRequest::WithdrawMany(request),
     };
     let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(WithdrawError::InternalError)?;
     let task_id = WithdrawTaskManager::spawn_rpc_task(&coins_ctx.withdraw_task_manager, &spawner, task)?;
@@ -105,10 +129,26 @@ pub trait InitWithdrawCoin {
     ) -> Result<TransactionDetails, MmError<WithdrawError>>;
 }
 
+#[async_trait]
+pub trait InitWithdrawManyCoin {
+    async fn init_withdraw_many(
+        &self,
+        ctx: MmArc,
+        req: WithdrawManyRequest,
+        task_handle: &WithdrawTaskHandle,
+    ) -> Result<TransactionDetails, MmError<WithdrawError>>;
+}
+
+#[derive(Clone)]
+enum WithdrawTaskRequest {
+    Withdraw(WithdrawRequest),
+    WithdrawMany(WithdrawManyRequest),
+}
+
 pub struct WithdrawTask {
     ctx: MmArc,
     coin: MmCoinEnum,
-    request: WithdrawRequest,
+    request: WithdrawTaskRequest,
 }
 
 impl RpcTaskTypes for WithdrawTask {
@@ -128,12 +168,26 @@ impl RpcTask for WithdrawTask {
 
     async fn run(&mut self, task_handle: &WithdrawTaskHandle) -> Result<Self::Item, MmError<Self::Error>> {
         let ctx = self.ctx.clone();
-        let request = self.request.clone();
-        match self.coin {
-            MmCoinEnum::UtxoCoin(ref standard_utxo) => standard_utxo.init_withdraw(ctx, request, task_handle).await,
-            MmCoinEnum::QtumCoin(ref qtum) => qtum.init_withdraw(ctx, request, task_handle).await,
+        match (&self.coin, self.request.clone()) {
+            (MmCoinEnum::UtxoCoin(standard_utxo), WithdrawTaskRequest::Withdraw(request)) => {
+                standard_utxo.init_withdraw(ctx, request, task_handle).await
+            },
+            (MmCoinEnum::UtxoCoin(standard_utxo), WithdrawTaskRequest::WithdrawMany(request)) => {
+                standard_utxo.init_withdraw_many(ctx, request, task_handle).await
+            },
+            (MmCoinEnum::QtumCoin(qtum), WithdrawTaskRequest::Withdraw(request)) => {
+                qtum.init_withdraw(ctx, request, task_handle).await
+            },
+            (MmCoinEnum::QtumCoin(qtum), WithdrawTaskRequest::WithdrawMany(request)) => {
+                qtum.init_withdraw_many(ctx, request, task_handle).await
+            },
             #[cfg(not(target_arch = "wasm32"))]
-            MmCoinEnum::ZCoin(ref z) => z.init_withdraw(ctx, request, task_handle).await,
+            (MmCoinEnum::ZCoin(z), WithdrawTaskRequest::Withdraw(request)) => {
+                z.init_withdraw(ctx, request, task_handle).await
+            },
+            (_, WithdrawTaskRequest::WithdrawMany(_)) => MmError::err(WithdrawError::CoinDoesntSupportWithdrawMany {
+                coin: self.coin.ticker().to_owned(),
+            }),
             _ => MmError::err(WithdrawError::CoinDoesntSupportInitWithdraw {
                 coin: self.coin.ticker().to_owned(),
             }),
@@ -11,6 +11,9 @@ mod send_payment;
 mod trusted_nodes;
 mod update_channel;
 
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::RpcMethodRegistry;
+
 pub mod channels {
     pub use super::close_channel::*;
     pub use super::get_channel_details::*;
@@ -31,3 +34,82 @@ pub mod payments {
     pub use super::list_payments_by_filter::*;
     pub use super::send_payment::*;
 }
+
+/// Registers the `lightning::*` mmrpc 2.0 methods.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "lightning::channels::close_channel",
+        "Closes the Lightning channel.",
+        mmrpc_handler!(channels::close_channel),
+    );
+    registry.register(
+        "lightning::channels::get_channel_details",
+        "Returns the details of the Lightning channel.",
+        mmrpc_handler!(channels::get_channel_details),
+    );
+    registry.register(
+        "lightning::channels::get_claimable_balances",
+        "Returns the balances claimable from the closed Lightning channels.",
+        mmrpc_handler!(channels::get_claimable_balances),
+    );
+    registry.register(
+        "lightning::channels::list_closed_channels_by_filter",
+        "Lists the closed Lightning channels by the given filter.",
+        mmrpc_handler!(channels::list_closed_channels_by_filter),
+    );
+    registry.register(
+        "lightning::channels::list_open_channels_by_filter",
+        "Lists the open Lightning channels by the given filter.",
+        mmrpc_handler!(channels::list_open_channels_by_filter),
+    );
+    registry.register(
+        "lightning::channels::open_channel",
+        "Opens a Lightning channel with the given node.",
+        mmrpc_handler!(channels::open_channel),
+    );
+    registry.register(
+        "lightning::channels::update_channel",
+        "Updates the config of the Lightning channel.",
+        mmrpc_handler!(channels::update_channel),
+    );
+    registry.register(
+        "lightning::nodes::add_trusted_node",
+        "Adds the Lightning node to the trusted ones.",
+        mmrpc_handler!(nodes::add_trusted_node),
+    );
+    registry.register(
+        "lightning::nodes::connect_to_node",
+        "Connects to the Lightning node.",
+        mmrpc_handler!(nodes::connect_to_node),
+    );
+    registry.register(
+        "lightning::nodes::list_trusted_nodes",
+        "Lists the trusted Lightning nodes.",
+        mmrpc_handler!(nodes::list_trusted_nodes),
+    );
+    registry.register(
+        "lightning::nodes::remove_trusted_node",
+        "Removes the Lightning node from the trusted ones.",
+        mmrpc_handler!(nodes::remove_trusted_node),
+    );
+    registry.register(
+        "lightning::payments::generate_invoice",
+        "Generates a Lightning invoice.",
+        mmrpc_handler!(payments::generate_invoice),
+    );
+    registry.register(
+        "lightning::payments::get_payment_details",
+        "Returns the details of the Lightning payment.",
+        mmrpc_handler!(payments::get_payment_details),
+    );
+    registry.register(
+        "lightning::payments::list_payments_by_filter",
+        "Lists the Lightning payments by the given filter.",
+        mmrpc_handler!(payments::list_payments_by_filter),
+    );
+    registry.register(
+        "lightning::payments::send_payment",
+        "Sends a Lightning payment.",
+        mmrpc_handler!(payments::send_payment),
+    );
+}
@@ -1,4 +1,6 @@
 pub mod account_balance;
+pub mod bump_fee;
+pub mod consolidate_utxos;
 pub mod get_current_mtp;
 pub mod get_enabled_coins;
 pub mod get_new_address;
@@ -8,4 +10,260 @@ pub mod init_create_account;
 pub mod init_scan_for_new_addresses;
 pub mod init_withdraw;
 #[cfg(not(target_arch = "wasm32"))] pub mod lightning;
+pub mod psbt;
 pub mod tendermint;
+pub mod utxo_coin_control;
+
+use crate::my_tx_history_v2::my_tx_history_v2_rpc;
+use crate::{add_delegation, get_my_address, get_raw_transaction, get_staking_infos, remove_delegation, sign_message,
+            verify_message, withdraw, withdraw_many};
+use account_balance::account_balance;
+use bump_fee::bump_fee;
+use get_current_mtp::get_current_mtp_rpc;
+use get_enabled_coins::get_enabled_coins;
+use get_new_address::{cancel_get_new_address, get_new_address, init_get_new_address, init_get_new_address_status,
+                      init_get_new_address_user_action};
+use init_account_balance::{cancel_account_balance, init_account_balance, init_account_balance_status};
+use init_create_account::{cancel_create_new_account, init_create_new_account, init_create_new_account_status,
+                          init_create_new_account_user_action};
+use init_scan_for_new_addresses::{cancel_scan_for_new_addresses, init_scan_for_new_addresses,
+                                  init_scan_for_new_addresses_status};
+use init_withdraw::{cancel_withdraw, init_withdraw, init_withdraw_many, withdraw_status, withdraw_user_action};
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::RpcMethodRegistry;
+use psbt::{create_psbt, finalize_psbt, sign_psbt};
+use tendermint::{ibc_chains, ibc_transfer_channels, ibc_withdraw};
+use utxo_coin_control::{freeze_outpoints, list_unspent, prune_frozen_outpoints, unfreeze_outpoints};
+
+/// Registers the coins mmrpc 2.0 methods including the Lightning ones.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "account_balance",
+        "Returns the balance of the given HD wallet account.",
+        mmrpc_handler!(account_balance),
+    );
+    registry.register(
+        "add_delegation",
+        "Delegates the coins for staking.",
+        mmrpc_handler!(add_delegation),
+    );
+    registry.register(
+        "bump_fee",
+        "Bumps the fee of the unconfirmed UTXO transaction with RBF or CPFP and broadcasts the result.",
+        mmrpc_handler!(bump_fee),
+    );
+    registry.register(
+        "create_psbt",
+        "Generates the unsigned UTXO withdrawal transaction as PSBT.",
+        mmrpc_handler!(create_psbt),
+    );
+    registry.register(
+        "finalize_psbt",
+        "Extracts the signed transaction from PSBT to be broadcasted with send_raw_transaction.",
+        mmrpc_handler!(finalize_psbt),
+    );
+    registry.register(
+        "freeze_outpoints",
+        "Excludes the given outputs of the UTXO coin from the automatic coin selection.",
+        mmrpc_handler!(freeze_outpoints),
+    );
+    registry.register(
+        "get_current_mtp",
+        "Returns the current median time past of the UTXO coin.",
+        mmrpc_handler!(get_current_mtp_rpc),
+    );
+    registry.register(
+        "get_enabled_coins",
+        "Lists the enabled coins.",
+        mmrpc_handler!(get_enabled_coins),
+    );
+    registry.register(
+        "get_my_address",
+        "Returns the address of the coin derived from the node key.",
+        mmrpc_handler!(get_my_address),
+    );
+    registry.register(
+        "get_new_address",
+        "Generates a new address of the HD wallet account.",
+        mmrpc_handler!(get_new_address),
+    );
+    registry.register(
+        "get_raw_transaction",
+        "Returns the raw transaction by its hash.",
+        mmrpc_handler!(get_raw_transaction),
+    );
+    registry.register(
+        "get_staking_infos",
+        "Returns the staking information of the coin.",
+        mmrpc_handler!(get_staking_infos),
+    );
+    registry.register(
+        "ibc_chains",
+        "Lists the chains available for the IBC transfers.",
+        mmrpc_handler!(ibc_chains),
+    );
+    registry.register(
+        "ibc_transfer_channels",
+        "Lists the IBC channels between the given chains.",
+        mmrpc_handler!(ibc_transfer_channels),
+    );
+    registry.register(
+        "ibc_withdraw",
+        "Generates an IBC transfer transaction.",
+        mmrpc_handler!(ibc_withdraw),
+    );
+    registry.register(
+        "list_unspent",
+        "Lists the unspent outputs of the UTXO coin along with their confirmations and frozen status.",
+        mmrpc_handler!(list_unspent),
+    );
+    registry.register(
+        "my_tx_history",
+        "Returns the transaction history of the coin.",
+        mmrpc_handler!(my_tx_history_v2_rpc),
+    );
+    registry.register(
+        "prune_frozen_outpoints",
+        "Forgets the frozen outputs of the UTXO coin that are not unspent anymore.",
+        mmrpc_handler!(prune_frozen_outpoints),
+    );
+    registry.register(
+        "remove_delegation",
+        "Removes the staking delegation.",
+        mmrpc_handler!(remove_delegation),
+    );
+    registry.register(
+        "sign_message",
+        "Signs the message with the coin private key.",
+        mmrpc_handler!(sign_message),
+    );
+    registry.register(
+        "sign_psbt",
+        "Signs the PSBT inputs spending the outputs of the UTXO coin address.",
+        mmrpc_handler!(sign_psbt),
+    );
+    registry.register(
+        "unfreeze_outpoints",
+        "Returns the given outputs of the UTXO coin to the automatic coin selection.",
+        mmrpc_handler!(unfreeze_outpoints),
+    );
+    registry.register(
+        "verify_message",
+        "Verifies the message signature.",
+        mmrpc_handler!(verify_message),
+    );
+    registry.register(
+        "withdraw",
+        "Generates a transaction sending the coins to the given address.",
+        mmrpc_handler!(withdraw),
+    );
+    registry.register(
+        "withdraw_many",
+        "Generates the transactions sending the coins to several addresses at once.",
+        mmrpc_handler!(withdraw_many),
+    );
+    #[cfg(not(target_arch = "wasm32"))]
+    registry.register(
+        "z_coin_tx_history",
+        "Returns the transaction history of the Z coin.",
+        mmrpc_handler!(crate::my_tx_history_v2::z_coin_tx_history_rpc),
+    );
+    registry.register(
+        "task::account_balance::cancel",
+        "Cancels the HD account balance task.",
+        mmrpc_handler!(cancel_account_balance),
+    );
+    registry.register(
+        "task::account_balance::init",
+        "Starts the task requesting the HD account balance.",
+        mmrpc_handler!(init_account_balance),
+    );
+    registry.register(
+        "task::account_balance::status",
+        "Returns the status of the HD account balance task.",
+        mmrpc_handler!(init_account_balance_status),
+    );
+    registry.register(
+        "task::create_new_account::cancel",
+        "Cancels the HD account creation task.",
+        mmrpc_handler!(cancel_create_new_account),
+    );
+    registry.register(
+        "task::create_new_account::init",
+        "Starts the task creating a new HD account.",
+        mmrpc_handler!(init_create_new_account),
+    );
+    registry.register(
+        "task::create_new_account::status",
+        "Returns the status of the HD account creation task.",
+        mmrpc_handler!(init_create_new_account_status),
+    );
+    registry.register(
+        "task::create_new_account::user_action",
+        "Passes the user action to the HD account creation task.",
+        mmrpc_handler!(init_create_new_account_user_action),
+    );
+    registry.register(
+        "task::get_new_address::cancel",
+        "Cancels the new address generation task.",
+        mmrpc_handler!(cancel_get_new_address),
+    );
+    registry.register(
+        "task::get_new_address::init",
+        "Starts the task generating a new address of the HD account.",
+        mmrpc_handler!(init_get_new_address),
+    );
+    registry.register(
+        "task::get_new_address::status",
+        "Returns the status of the new address generation task.",
+        mmrpc_handler!(init_get_new_address_status),
+    );
+    registry.register(
+        "task::get_new_address::user_action",
+        "Passes the user action to the new address generation task.",
+        mmrpc_handler!(init_get_new_address_user_action),
+    );
+    registry.register(
+        "task::scan_for_new_addresses::cancel",
+        "Cancels the HD account addresses scanning task.",
+        mmrpc_handler!(cancel_scan_for_new_addresses),
+    );
+    registry.register(
+        "task::scan_for_new_addresses::init",
+        "Starts the task scanning the HD account for the used addresses.",
+        mmrpc_handler!(init_scan_for_new_addresses),
+    );
+    registry.register(
+        "task::scan_for_new_addresses::status",
+        "Returns the status of the HD account addresses scanning task.",
+        mmrpc_handler!(init_scan_for_new_addresses_status),
+    );
+    registry.register(
+        "task::withdraw::cancel",
+        "Cancels the withdraw task.",
+        mmrpc_handler!(cancel_withdraw),
+    );
+    registry.register(
+        "task::withdraw::init",
+        "Starts the task generating a withdraw transaction.",
+        mmrpc_handler!(init_withdraw),
+    );
+    registry.register(
+        "task::withdraw_many::init",
+        "Starts the task generating a transaction sending the coins to several addresses at once.",
+        mmrpc_handler!(init_withdraw_many),
+    );
+    registry.register(
+        "task::withdraw::status",
+        "Returns the status of the withdraw task.",
+        mmrpc_handler!(withdraw_status),
+    );
+    registry.register(
+        "task::withdraw::user_action",
+        "Passes the user action to the withdraw task.",
+        mmrpc_handler!(withdraw_user_action),
+    );
+
+    #[cfg(not(target_arch = "wasm32"))]
+    lightning::register_rpc_methods(registry);
+}
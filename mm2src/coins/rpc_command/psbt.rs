@@ -0,0 +1,334 @@
+//! PSBT (BIP-174) based withdrawal from UTXO coins, so the transaction can be signed by another wallet.
+//!
+//! * `create_psbt` generates the unsigned withdrawal transaction with the spent outputs and the sender derivation.
+//! * `sign_psbt` signs the PSBT inputs spending P2PKH and P2WPKH outputs of the wallet address
+//!   and returns the outputs, the amounts and the fee of the transaction, so they can be confirmed before finalizing.
+//! * `finalize_psbt` extracts the signed transaction to be broadcasted with `send_raw_transaction`.
+//!
+//! Only the coins using the plain Bitcoin transaction format are supported.
+
+use crate::utxo::utxo_common::{big_decimal_from_sat, big_decimal_from_sat_unsigned};
+use crate::utxo::utxo_withdraw::{StandardUtxoWithdraw, UtxoWithdraw};
+use crate::utxo::{GetUtxoListOps, UtxoCoinConf, UtxoCommonOps, UtxoFeeDetails};
+use crate::{lp_coinfind_or_err, CoinFindError, MarketCoinOps, MmCoinEnum, PrivKeyPolicyNotAllowed, TxFeeDetails,
+            UnexpectedDerivationMethod, WithdrawError, WithdrawManyRequest};
+use chain::Psbt;
+use common::{HttpStatusCode, StatusCode};
+use crypto::StandardHDCoinAddress;
+use derive_more::Display;
+use keys::{AddressHashEnum, KeyPair, Private};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use rpc::v1::types::{Bytes as BytesJson, H256 as H256Json};
+use script::{Builder, Script};
+use serialization::{deserialize, serialize, serialize_with_flags, SERIALIZE_TRANSACTION_WITNESS};
+use utxo_signer::with_psbt::{self, UtxoSignPsbtError};
+
+pub type PsbtResult<T> = Result<T, MmError<PsbtError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum PsbtError {
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Requested coin: {}; is not supported for this action.", _0)]
+    NotSupportedCoin(String),
+    #[display(fmt = "Invalid PSBT: {}", _0)]
+    InvalidPsbt(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for PsbtError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PsbtError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            PsbtError::NotSupportedCoin(_) | PsbtError::InvalidPsbt(_) => StatusCode::BAD_REQUEST,
+            PsbtError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<CoinFindError> for PsbtError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => PsbtError::NoSuchCoin { coin },
+        }
+    }
+}
+
+impl From<UnexpectedDerivationMethod> for PsbtError {
+    fn from(e: UnexpectedDerivationMethod) -> Self { PsbtError::NotSupportedCoin(e.to_string()) }
+}
+
+impl From<PrivKeyPolicyNotAllowed> for PsbtError {
+    fn from(e: PrivKeyPolicyNotAllowed) -> Self { PsbtError::NotSupportedCoin(e.to_string()) }
+}
+
+impl From<UtxoSignPsbtError> for PsbtError {
+    fn from(e: UtxoSignPsbtError) -> Self {
+        match e {
+            UtxoSignPsbtError::ErrorSigning(signing) => PsbtError::Internal(format!("Error signing: {}", signing)),
+            invalid => PsbtError::InvalidPsbt(invalid.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreatePsbtResponse {
+    /// Base64 encoded PSBT.
+    psbt: String,
+    from: Vec<String>,
+    to: Vec<String>,
+    spent_by_me: BigDecimal,
+    received_by_me: BigDecimal,
+    my_balance_change: BigDecimal,
+    fee_details: TxFeeDetails,
+}
+
+#[derive(Deserialize)]
+pub struct SignPsbtRequest {
+    coin: String,
+    /// Base64 encoded PSBT.
+    psbt: String,
+    /// The HD wallet address signing the inputs. The enabled address is used if not set.
+    #[serde(default)]
+    from: Option<StandardHDCoinAddress>,
+}
+
+#[derive(Serialize)]
+pub struct SignPsbtResponse {
+    /// Base64 encoded PSBT.
+    psbt: String,
+    /// The number of the inputs signed by this request.
+    signed_inputs: usize,
+    /// The addresses of the spent outputs that are known.
+    from: Vec<String>,
+    to: Vec<String>,
+    outputs: Vec<PsbtOutputSummary>,
+    /// The amount of the spent outputs of the signing address.
+    spent_by_me: BigDecimal,
+    received_by_me: BigDecimal,
+    my_balance_change: BigDecimal,
+    /// `None` if the amount of any spent output isn't provided by the PSBT.
+    fee_details: Option<TxFeeDetails>,
+}
+
+#[derive(Serialize)]
+pub struct PsbtOutputSummary {
+    /// Empty if the output script is non-standard.
+    to: Vec<String>,
+    amount: BigDecimal,
+    is_mine: bool,
+}
+
+#[derive(Deserialize)]
+pub struct FinalizePsbtRequest {
+    coin: String,
+    /// Base64 encoded PSBT.
+    psbt: String,
+}
+
+#[derive(Serialize)]
+pub struct FinalizePsbtResponse {
+    tx_hex: BytesJson,
+    tx_hash: H256Json,
+}
+
+/// Generates the unsigned withdrawal transaction as PSBT.
+pub async fn create_psbt(ctx: MmArc, req: WithdrawManyRequest) -> MmResult<CreatePsbtResponse, WithdrawError> {
+    match lp_coinfind_or_err(&ctx, &req.coin).await? {
+        MmCoinEnum::UtxoCoin(utxo) => create_psbt_impl(utxo, req).await,
+        MmCoinEnum::QtumCoin(qtum) => create_psbt_impl(qtum, req).await,
+        _ => MmError::err(WithdrawError::UnsupportedError(format!(
+            "PSBT is not supported by {}",
+            req.coin
+        ))),
+    }
+}
+
+/// Signs the PSBT inputs spending the outputs of the wallet address.
+pub async fn sign_psbt(ctx: MmArc, req: SignPsbtRequest) -> PsbtResult<SignPsbtResponse> {
+    match lp_coinfind_or_err(&ctx, &req.coin).await? {
+        MmCoinEnum::UtxoCoin(utxo) => sign_psbt_impl(&utxo, req),
+        MmCoinEnum::QtumCoin(qtum) => sign_psbt_impl(&qtum, req),
+        _ => MmError::err(PsbtError::NotSupportedCoin(req.coin)),
+    }
+}
+
+/// Finalizes the signed PSBT and returns the transaction to be broadcasted with `send_raw_transaction`.
+pub async fn finalize_psbt(ctx: MmArc, req: FinalizePsbtRequest) -> PsbtResult<FinalizePsbtResponse> {
+    match lp_coinfind_or_err(&ctx, &req.coin).await? {
+        MmCoinEnum::UtxoCoin(utxo) => finalize_psbt_impl(&utxo, req),
+        MmCoinEnum::QtumCoin(qtum) => finalize_psbt_impl(&qtum, req),
+        _ => MmError::err(PsbtError::NotSupportedCoin(req.coin)),
+    }
+}
+
+/// Whether the transactions of the coin can be encoded as PSBT.
+pub(crate) fn is_psbt_supported(conf: &UtxoCoinConf) -> bool {
+    !(conf.overwintered || conf.zcash || conf.is_pos || conf.is_posv)
+}
+
+async fn create_psbt_impl<T>(coin: T, req: WithdrawManyRequest) -> MmResult<CreatePsbtResponse, WithdrawError>
+where
+    T: UtxoCommonOps + GetUtxoListOps + MarketCoinOps,
+{
+    let ticker = coin.as_ref().conf.ticker.clone();
+    let decimals = coin.as_ref().decimals;
+    let to = req.outputs.iter().map(|output| output.to.clone()).collect();
+
+    let withdraw = StandardUtxoWithdraw::new(coin, req)?;
+    let from = withdraw.sender_address_string();
+    let (psbt, data) = withdraw.build_psbt().await?;
+
+    let fee_details = UtxoFeeDetails {
+        coin: Some(ticker),
+        amount: big_decimal_from_sat((data.fee_amount + data.unused_change) as i64, decimals),
+    };
+    Ok(CreatePsbtResponse {
+        psbt: base64::encode(&serialize(&psbt)),
+        from: vec![from],
+        to,
+        spent_by_me: big_decimal_from_sat(data.spent_by_me as i64, decimals),
+        received_by_me: big_decimal_from_sat(data.received_by_me as i64, decimals),
+        my_balance_change: big_decimal_from_sat(data.received_by_me as i64 - data.spent_by_me as i64, decimals),
+        fee_details: fee_details.into(),
+    })
+}
+
+fn sign_psbt_impl<T: UtxoCommonOps>(coin: &T, req: SignPsbtRequest) -> PsbtResult<SignPsbtResponse> {
+    let mut psbt = decode_psbt(coin, &req.psbt)?;
+    let key_pair = match req.from {
+        Some(ref hd_address) => hd_address_key_pair(coin, hd_address)?,
+        None => *coin.as_ref().priv_key_policy.activated_key_or_err()?,
+    };
+
+    let conf = &coin.as_ref().conf;
+    let signed_inputs = with_psbt::sign_psbt(&mut psbt, &key_pair, conf.signature_version, conf.fork_id)?;
+
+    let decimals = coin.as_ref().decimals;
+    // The same scripts as the ones `with_psbt::sign_psbt` signs the inputs for.
+    let address_hash = AddressHashEnum::AddressHash(key_pair.public().address_hash());
+    let my_scripts = [
+        Builder::build_p2pkh(&address_hash),
+        Builder::build_witness_script(&address_hash),
+    ];
+    let is_mine = |script: &Script| my_scripts.contains(script);
+
+    let mut from = Vec::new();
+    let mut input_amount = Some(0);
+    let mut spent_by_me = 0;
+    for index in 0..psbt.inputs.len() {
+        let spent_output = match psbt.spent_output(index) {
+            Some(spent_output) => spent_output,
+            None => {
+                input_amount = None;
+                continue;
+            },
+        };
+        let script = Script::from(spent_output.script_pubkey.clone());
+        if is_mine(&script) {
+            spent_by_me += spent_output.value;
+        }
+        input_amount = input_amount.map(|amount| amount + spent_output.value);
+        from.extend(display_addresses(coin, &script));
+    }
+
+    let mut to = Vec::new();
+    let mut outputs = Vec::with_capacity(psbt.unsigned_tx.outputs.len());
+    let mut output_amount = 0;
+    let mut received_by_me = 0;
+    for output in psbt.unsigned_tx.outputs.iter() {
+        let script = Script::from(output.script_pubkey.clone());
+        let is_my_output = is_mine(&script);
+        if is_my_output {
+            received_by_me += output.value;
+        }
+        output_amount += output.value;
+        let addresses = display_addresses(coin, &script);
+        to.extend(addresses.iter().cloned());
+        outputs.push(PsbtOutputSummary {
+            to: addresses,
+            amount: big_decimal_from_sat_unsigned(output.value, decimals),
+            is_mine: is_my_output,
+        });
+    }
+    from.sort();
+    from.dedup();
+    to.sort();
+    to.dedup();
+
+    let fee_details: Option<TxFeeDetails> = input_amount.map(|input_amount| {
+        UtxoFeeDetails {
+            coin: Some(conf.ticker.clone()),
+            amount: big_decimal_from_sat(input_amount as i64 - output_amount as i64, decimals),
+        }
+        .into()
+    });
+    Ok(SignPsbtResponse {
+        psbt: base64::encode(&serialize(&psbt)),
+        signed_inputs,
+        from,
+        to,
+        outputs,
+        spent_by_me: big_decimal_from_sat_unsigned(spent_by_me, decimals),
+        received_by_me: big_decimal_from_sat_unsigned(received_by_me, decimals),
+        my_balance_change: big_decimal_from_sat(received_by_me as i64 - spent_by_me as i64, decimals),
+        fee_details,
+    })
+}
+
+/// Returns the addresses of the standard `script`, the PSBT created by another wallet may contain any scripts.
+fn display_addresses<T: UtxoCommonOps>(coin: &T, script: &Script) -> Vec<String> {
+    coin.addresses_from_script(script)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|address| address.display_address().ok())
+        .collect()
+}
+
+fn finalize_psbt_impl<T: UtxoCommonOps>(coin: &T, req: FinalizePsbtRequest) -> PsbtResult<FinalizePsbtResponse> {
+    let psbt = decode_psbt(coin, &req.psbt)?;
+    let signed = with_psbt::finalize_psbt(psbt)?;
+    Ok(FinalizePsbtResponse {
+        tx_hex: serialize_with_flags(&signed, SERIALIZE_TRANSACTION_WITNESS).into(),
+        tx_hash: signed.hash().reversed().into(),
+    })
+}
+
+fn decode_psbt<T: UtxoCommonOps>(coin: &T, psbt: &str) -> PsbtResult<Psbt> {
+    if !is_psbt_supported(&coin.as_ref().conf) {
+        return MmError::err(PsbtError::NotSupportedCoin(coin.as_ref().conf.ticker.clone()));
+    }
+
+    let psbt_bytes = base64::decode(psbt).map_to_mm(|e| PsbtError::InvalidPsbt(e.to_string()))?;
+    let mut psbt: Psbt =
+        deserialize(psbt_bytes.as_slice()).map_to_mm(|e| PsbtError::InvalidPsbt(format!("{:?}", e)))?;
+
+    // The transaction hashes depend on the coin hash algorithm.
+    let tx_hash_algo = coin.as_ref().tx_hash_algo;
+    psbt.unsigned_tx.tx_hash_algo = tx_hash_algo;
+    for input in psbt.inputs.iter_mut() {
+        if let Some(ref mut prev_tx) = input.non_witness_utxo {
+            prev_tx.tx_hash_algo = tx_hash_algo;
+        }
+    }
+    Ok(psbt)
+}
+
+fn hd_address_key_pair<T: UtxoCommonOps>(coin: &T, hd_address: &StandardHDCoinAddress) -> PsbtResult<KeyPair> {
+    let conf = &coin.as_ref().conf;
+    let secret = coin
+        .as_ref()
+        .priv_key_policy
+        .hd_wallet_derived_priv_key_or_err(hd_address)?;
+    let private = Private {
+        prefix: conf.wif_prefix,
+        secret,
+        compressed: true,
+        checksum_type: conf.checksum_type,
+    };
+    KeyPair::from_private(private).map_to_mm(|e| PsbtError::Internal(e.to_string()))
+}
@@ -0,0 +1,295 @@
+//! Manual coin control for UTXO coins: listing the spendable outputs and freezing some of them,
+//! so they are never selected automatically to fund swaps or withdrawals.
+//!
+//! BCH isn't supported since its outputs may carry SLP tokens and are selected separately.
+
+use crate::utxo::frozen_outpoints_storage::{save_frozen_outpoints, FrozenOutPointsStorageError};
+use crate::utxo::rpc_clients::UtxoRpcError;
+use crate::utxo::utxo_common::big_decimal_from_sat_unsigned;
+use crate::utxo::utxo_tx_history_v2::{UtxoMyAddressesHistoryError, UtxoTxHistoryOps};
+use crate::utxo::{FrozenOutPoints, GetUtxoMapOps, UtxoCommonOps, UtxoOutPoint};
+use crate::{lp_coinfind_or_err, CoinFindError, CoinsContext, MmCoinEnum};
+use chain::OutPoint;
+use common::{HttpStatusCode, StatusCode};
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use rpc::v1::types::H256 as H256Json;
+use std::collections::{HashMap, HashSet};
+
+pub type CoinControlResult<T> = Result<T, MmError<CoinControlError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum CoinControlError {
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Requested coin: {}; is not supported for this action.", _0)]
+    NotSupportedCoin(String),
+    #[display(fmt = "Output {}:{} is not an unspent output of this wallet", tx_hash, index)]
+    UnknownOutPoint { tx_hash: H256Json, index: u32 },
+    #[display(fmt = "Transport error: {}", _0)]
+    Transport(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for CoinControlError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CoinControlError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            CoinControlError::NotSupportedCoin(_) | CoinControlError::UnknownOutPoint { .. } => StatusCode::BAD_REQUEST,
+            CoinControlError::Transport(_) | CoinControlError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<CoinFindError> for CoinControlError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => CoinControlError::NoSuchCoin { coin },
+        }
+    }
+}
+
+impl From<UtxoRpcError> for CoinControlError {
+    fn from(e: UtxoRpcError) -> Self {
+        match e {
+            UtxoRpcError::Internal(internal) => CoinControlError::Internal(internal),
+            rpc => CoinControlError::Transport(rpc.to_string()),
+        }
+    }
+}
+
+impl From<UtxoMyAddressesHistoryError> for CoinControlError {
+    fn from(e: UtxoMyAddressesHistoryError) -> Self { CoinControlError::Internal(e.to_string()) }
+}
+
+impl From<FrozenOutPointsStorageError> for CoinControlError {
+    fn from(e: FrozenOutPointsStorageError) -> Self { CoinControlError::Internal(e.to_string()) }
+}
+
+#[derive(Deserialize)]
+pub struct ListUnspentRequest {
+    coin: String,
+}
+
+#[derive(Serialize)]
+pub struct UnspentOutput {
+    #[serde(flatten)]
+    outpoint: UtxoOutPoint,
+    address: String,
+    value: BigDecimal,
+    /// The block height the output was mined in. `None` if the output is not mined yet.
+    height: Option<u64>,
+    confirmations: u64,
+    is_frozen: bool,
+}
+
+#[derive(Serialize)]
+pub struct ListUnspentResponse {
+    unspents: Vec<UnspentOutput>,
+}
+
+#[derive(Deserialize)]
+pub struct FreezeOutPointsRequest {
+    coin: String,
+    outpoints: Vec<UtxoOutPoint>,
+}
+
+#[derive(Serialize)]
+pub struct FreezeOutPointsResponse {
+    /// All the outputs of the coin that are frozen after the request.
+    frozen: Vec<UtxoOutPoint>,
+}
+
+#[derive(Deserialize)]
+pub struct PruneFrozenOutPointsRequest {
+    coin: String,
+}
+
+#[derive(Serialize)]
+pub struct PruneFrozenOutPointsResponse {
+    /// The frozen outputs that are not unspent anymore and have been forgotten.
+    pruned: Vec<UtxoOutPoint>,
+    /// All the outputs of the coin that are frozen after the request.
+    frozen: Vec<UtxoOutPoint>,
+}
+
+/// Lists the mature and immature unspent outputs of all the wallet addresses.
+pub async fn list_unspent(ctx: MmArc, req: ListUnspentRequest) -> CoinControlResult<ListUnspentResponse> {
+    match lp_coinfind_or_err(&ctx, &req.coin).await? {
+        MmCoinEnum::UtxoCoin(utxo) => list_unspent_impl(&ctx, &utxo).await,
+        MmCoinEnum::QtumCoin(qtum) => list_unspent_impl(&ctx, &qtum).await,
+        _ => MmError::err(CoinControlError::NotSupportedCoin(req.coin)),
+    }
+}
+
+/// Excludes the given outputs from the automatic coin selection.
+pub async fn freeze_outpoints(ctx: MmArc, req: FreezeOutPointsRequest) -> CoinControlResult<FreezeOutPointsResponse> {
+    match lp_coinfind_or_err(&ctx, &req.coin).await? {
+        MmCoinEnum::UtxoCoin(utxo) => freeze_outpoints_impl(&ctx, &utxo, req.outpoints).await,
+        MmCoinEnum::QtumCoin(qtum) => freeze_outpoints_impl(&ctx, &qtum, req.outpoints).await,
+        _ => MmError::err(CoinControlError::NotSupportedCoin(req.coin)),
+    }
+}
+
+/// Returns the given outputs to the automatic coin selection.
+/// The outputs that are not frozen are ignored.
+pub async fn unfreeze_outpoints(ctx: MmArc, req: FreezeOutPointsRequest) -> CoinControlResult<FreezeOutPointsResponse> {
+    let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
+    match coin {
+        MmCoinEnum::UtxoCoin(_) | MmCoinEnum::QtumCoin(_) => (),
+        _ => return MmError::err(CoinControlError::NotSupportedCoin(req.coin)),
+    }
+
+    let outpoints = req.outpoints;
+    let frozen = update_frozen_outpoints(&ctx, &req.coin, |coin_frozen_outpoints| {
+        for outpoint in outpoints {
+            coin_frozen_outpoints.remove(&OutPoint::from(outpoint));
+        }
+    })
+    .await?;
+    Ok(FreezeOutPointsResponse {
+        frozen: frozen.keys().copied().map(UtxoOutPoint::from).collect(),
+    })
+}
+
+/// Forgets the frozen outputs that are not unspent anymore, e.g. spent manually by a transaction
+/// that was built with explicitly chosen inputs.
+pub async fn prune_frozen_outpoints(
+    ctx: MmArc,
+    req: PruneFrozenOutPointsRequest,
+) -> CoinControlResult<PruneFrozenOutPointsResponse> {
+    match lp_coinfind_or_err(&ctx, &req.coin).await? {
+        MmCoinEnum::UtxoCoin(utxo) => prune_frozen_outpoints_impl(&ctx, &utxo).await,
+        MmCoinEnum::QtumCoin(qtum) => prune_frozen_outpoints_impl(&ctx, &qtum).await,
+        _ => MmError::err(CoinControlError::NotSupportedCoin(req.coin)),
+    }
+}
+
+/// Applies `update` to the outputs of the `ticker` coin frozen by the user and persists the result.
+/// Returns the outputs that are frozen after the update.
+async fn update_frozen_outpoints<F>(ctx: &MmArc, ticker: &str, update: F) -> CoinControlResult<FrozenOutPoints>
+where
+    F: FnOnce(&mut FrozenOutPoints),
+{
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(CoinControlError::Internal)?;
+    let frozen = {
+        let mut frozen_outpoints = coins_ctx.frozen_outpoints.lock();
+        let coin_frozen_outpoints = frozen_outpoints.entry(ticker.to_owned()).or_default();
+        update(coin_frozen_outpoints);
+        coin_frozen_outpoints.clone()
+    };
+    save_frozen_outpoints(ctx, ticker, &frozen).await?;
+    Ok(frozen)
+}
+
+async fn list_unspent_impl<T>(ctx: &MmArc, coin: &T) -> CoinControlResult<ListUnspentResponse>
+where
+    T: UtxoCommonOps + GetUtxoMapOps + UtxoTxHistoryOps,
+{
+    let ticker = coin.as_ref().conf.ticker.clone();
+    let decimals = coin.as_ref().decimals;
+    let addresses = UtxoTxHistoryOps::my_addresses(coin).await?.into_iter().collect();
+    let (unspents_map, _) = coin.get_all_unspent_ordered_map(addresses).await?;
+    let block_count = coin.as_ref().rpc_client.get_block_count().compat().await?;
+
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(CoinControlError::Internal)?;
+    let frozen_outpoints = coins_ctx.frozen_outpoints(&ticker);
+
+    let mut unspents = Vec::new();
+    for (address, address_unspents) in unspents_map {
+        let address = address.display_address().map_to_mm(CoinControlError::Internal)?;
+        for unspent in address_unspents {
+            let is_frozen = frozen_outpoints.contains_key(&unspent.outpoint);
+            let confirmations = match unspent.height {
+                Some(height) => block_count.saturating_sub(height) + 1,
+                None => 0,
+            };
+            unspents.push(UnspentOutput {
+                outpoint: unspent.outpoint.into(),
+                address: address.clone(),
+                value: big_decimal_from_sat_unsigned(unspent.value, decimals),
+                height: unspent.height,
+                confirmations,
+                is_frozen,
+            });
+        }
+    }
+
+    Ok(ListUnspentResponse { unspents })
+}
+
+async fn freeze_outpoints_impl<T>(
+    ctx: &MmArc,
+    coin: &T,
+    outpoints: Vec<UtxoOutPoint>,
+) -> CoinControlResult<FreezeOutPointsResponse>
+where
+    T: UtxoCommonOps + GetUtxoMapOps + UtxoTxHistoryOps,
+{
+    let ticker = coin.as_ref().conf.ticker.clone();
+    let decimals = coin.as_ref().decimals;
+    let addresses = UtxoTxHistoryOps::my_addresses(coin).await?.into_iter().collect();
+    let (unspents_map, _) = coin.get_all_unspent_ordered_map(addresses).await?;
+    let unspent_values: HashMap<OutPoint, u64> = unspents_map
+        .into_values()
+        .flatten()
+        .map(|unspent| (unspent.outpoint, unspent.value))
+        .collect();
+
+    let mut to_freeze = Vec::with_capacity(outpoints.len());
+    for outpoint in outpoints {
+        let value = unspent_values
+            .get(&OutPoint::from(outpoint))
+            .or_mm_err(|| CoinControlError::UnknownOutPoint {
+                tx_hash: outpoint.tx_hash,
+                index: outpoint.index,
+            })?;
+        to_freeze.push((
+            OutPoint::from(outpoint),
+            big_decimal_from_sat_unsigned(*value, decimals),
+        ));
+    }
+
+    let frozen = update_frozen_outpoints(ctx, &ticker, |coin_frozen_outpoints| {
+        coin_frozen_outpoints.extend(to_freeze)
+    })
+    .await?;
+    Ok(FreezeOutPointsResponse {
+        frozen: frozen.keys().copied().map(UtxoOutPoint::from).collect(),
+    })
+}
+
+async fn prune_frozen_outpoints_impl<T>(ctx: &MmArc, coin: &T) -> CoinControlResult<PruneFrozenOutPointsResponse>
+where
+    T: UtxoCommonOps + GetUtxoMapOps + UtxoTxHistoryOps,
+{
+    let ticker = coin.as_ref().conf.ticker.clone();
+    let addresses = UtxoTxHistoryOps::my_addresses(coin).await?.into_iter().collect();
+    let (unspents_map, _) = coin.get_all_unspent_ordered_map(addresses).await?;
+    let unspent_outpoints: HashSet<OutPoint> = unspents_map
+        .into_values()
+        .flatten()
+        .map(|unspent| unspent.outpoint)
+        .collect();
+
+    let mut pruned = Vec::new();
+    let frozen = update_frozen_outpoints(ctx, &ticker, |coin_frozen_outpoints| {
+        coin_frozen_outpoints.retain(|outpoint, _value| {
+            let is_unspent = unspent_outpoints.contains(outpoint);
+            if !is_unspent {
+                pruned.push(UtxoOutPoint::from(*outpoint));
+            }
+            is_unspent
+        })
+    })
+    .await?;
+    Ok(PruneFrozenOutPointsResponse {
+        pruned,
+        frozen: frozen.keys().copied().map(UtxoOutPoint::from).collect(),
+    })
+}
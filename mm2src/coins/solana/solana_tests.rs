@@ -165,6 +165,7 @@ fn solana_transaction_simulations() {
                 max: false,
                 fee: None,
                 memo: None,
+                inputs: None,
             })
             .compat(),
     )
@@ -193,6 +194,7 @@ fn solana_transaction_zero_balance() {
                 max: false,
                 fee: None,
                 memo: None,
+                inputs: None,
             })
             .compat(),
     );
@@ -222,6 +224,7 @@ fn solana_transaction_simulations_not_enough_for_fees() {
                 max: false,
                 fee: None,
                 memo: None,
+                inputs: None,
             })
             .compat(),
     );
@@ -256,6 +259,7 @@ fn solana_transaction_simulations_max() {
                 max: true,
                 fee: None,
                 memo: None,
+                inputs: None,
             })
             .compat(),
     )
@@ -285,6 +289,7 @@ fn solana_test_transactions() {
                 max: false,
                 fee: None,
                 memo: None,
+                inputs: None,
             })
             .compat(),
     )
@@ -15,20 +15,25 @@ use crate::tendermint::ibc::IBC_OUT_SOURCE_PORT;
 use crate::utxo::sat_from_big_decimal;
 use crate::utxo::utxo_common::big_decimal_from_sat;
 use crate::{big_decimal_from_sat_unsigned, BalanceError, BalanceFut, BigDecimal, CheckIfMyPaymentSentArgs,
-            CoinBalance, CoinFutSpawner, ConfirmPaymentInput, DexFee, FeeApproxStage, FoundSwapTxSpend,
-            HistorySyncState, MakerSwapTakerCoin, MarketCoinOps, MmCoin, MmCoinEnum, NegotiateSwapContractAddrErr,
-            PaymentInstructionArgs, PaymentInstructions, PaymentInstructionsErr, PrivKeyBuildPolicy, PrivKeyPolicy,
-            PrivKeyPolicyNotAllowed, RawTransactionError, RawTransactionFut, RawTransactionRequest, RawTransactionRes,
-            RefundError, RefundPaymentArgs, RefundResult, RpcCommonOps, SearchForSwapTxSpendInput,
-            SendMakerPaymentSpendPreimageInput, SendPaymentArgs, SignatureError, SignatureResult, SpendPaymentArgs,
-            SwapOps, TakerSwapMakerCoin, TradeFee, TradePreimageError, TradePreimageFut, TradePreimageResult,
-            TradePreimageValue, TransactionDetails, TransactionEnum, TransactionErr, TransactionFut,
-            TransactionResult, TransactionType, TxFeeDetails, TxMarshalingErr, UnexpectedDerivationMethod,
-            ValidateAddressResult, ValidateFeeArgs, ValidateInstructionsErr, ValidateOtherPubKeyErr,
-            ValidatePaymentFut, ValidatePaymentInput, ValidateWatcherSpendInput, VerificationError,
-            VerificationResult, WaitForHTLCTxSpendArgs, WatcherOps, WatcherReward, WatcherRewardError,
-            WatcherSearchForSwapTxSpendInput, WatcherValidatePaymentInput, WatcherValidateTakerFeeInput,
-            WithdrawError, WithdrawFee, WithdrawFrom, WithdrawFut, WithdrawRequest};
+            CoinBalance, CoinFutSpawner, ConfirmPaymentInput, DeriveHtlcResult, DexFee, FeeApproxStage,
+            FoundSwapTxSpend, HistorySyncState, MakerSwapTakerCoin, MarketCoinOps, MmCoin, MmCoinEnum,
+            NegotiateSwapContractAddrErr, PaymentInstructionArgs, PaymentInstructions, PaymentInstructionsErr,
+            PrivKeyBuildPolicy, PrivKeyPolicy, PrivKeyPolicyNotAllowed, RawTransactionError, RawTransactionFut,
+            RawTransactionRequest, RawTransactionRes, RefundError, RefundPaymentArgs, RefundResult, RpcCommonOps,
+            SearchForSwapTxSpendInput, SendMakerPaymentSpendPreimageInput, SendPaymentArgs, SignatureError,
+            SignatureResult, SpendPaymentArgs, SwapOps, TakerSwapMakerCoin, TradeFee, TradePreimageError,
+            TradePreimageFut, TradePreimageResult, TradePreimageValue, TransactionDetails, TransactionEnum,
+            TransactionErr, TransactionFut, TransactionResult, TransactionType, TxFeeDetails, TxMarshalingErr,
+            UnexpectedDerivationMethod, ValidateAddressResult, ValidateFeeArgs, ValidateInstructionsErr,
+            ValidateOtherPubKeyErr, ValidatePaymentFut, ValidatePaymentInput, ValidateWatcherSpendInput,
+            VerificationError, VerificationResult, WaitForHTLCTxSpendArgs, WatcherOps, WatcherReward,
+            WatcherRewardError, WatcherSearchForSwapTxSpendInput, WatcherValidatePaymentInput,
+            WatcherValidateTakerFeeInput, WithdrawError, WithdrawFee, WithdrawFrom, WithdrawFut, WithdrawManyDetails,
+            WithdrawManyFut, WithdrawManyRequest, WithdrawRequest};
+use crate::{CoinAssocTypes, GenPreimageResult, GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs,
+            RefundFundingSecretArgs, SendTakerFundingArgs, SwapOpsV2, ToBytes, Transaction, TxPreimageWithSig,
+            ValidateTakerFundingArgs, ValidateTakerFundingError, ValidateTakerFundingResult,
+            ValidateTakerFundingSpendPreimageResult, ValidateTakerPaymentSpendPreimageResult};
 use async_std::prelude::FutureExt as AsyncStdFutureExt;
 use async_trait::async_trait;
 use bitcrypto::{dhash160, sha256};
@@ -59,7 +64,7 @@ use futures::{FutureExt, TryFutureExt};
 use futures01::Future;
 use hex::FromHexError;
 use itertools::Itertools;
-use keys::KeyPair;
+use keys::{KeyPair, Public, Signature};
 use mm2_core::mm_ctx::{MmArc, MmWeak};
 use mm2_err_handle::prelude::*;
 use mm2_git::{FileMetadata, GitController, GithubClient, RepositoryOperations, GITHUB_API_URI};
@@ -68,6 +73,7 @@ use parking_lot::Mutex as PaMutex;
 use primitives::hash::H256;
 use prost::{DecodeError, Message};
 use rpc::v1::types::Bytes as BytesJson;
+use secp256k1::Signature as SecpSignature;
 use serde_json::{self as json, Value as Json};
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -77,6 +83,8 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use uuid::Uuid;
 
+#[path = "tendermint_swap_v2.rs"] mod tendermint_swap_v2;
+
 // ABCI Request Paths
 const ABCI_GET_LATEST_BLOCK_PATH: &str = "/cosmos.base.tendermint.v1beta1.Service/GetLatestBlock";
 const ABCI_GET_BLOCK_BY_HEIGHT_PATH: &str = "/cosmos.base.tendermint.v1beta1.Service/GetBlockByHeight";
@@ -328,6 +336,10 @@ impl From<TendermintCoinRpcError> for ValidatePaymentError {
     }
 }
 
+impl From<TendermintCoinRpcError> for ValidateTakerFundingError {
+    fn from(err: TendermintCoinRpcError) -> Self { ValidateTakerFundingError::Rpc(err.to_string()) }
+}
+
 impl From<TendermintCoinRpcError> for TradePreimageError {
     fn from(err: TendermintCoinRpcError) -> Self { TradePreimageError::Transport(err.to_string()) }
 }
@@ -361,6 +373,17 @@ impl crate::Transaction for CosmosTransaction {
     }
 }
 
+impl ToBytes for CosmosTransaction {
+    fn to_bytes(&self) -> Vec<u8> { self.tx_hex() }
+}
+
+#[derive(Debug, Display)]
+pub enum TendermintAssocTypesError {
+    InvalidPubkey(String),
+    TxParseError(String),
+    InvalidSignature(String),
+}
+
 pub(crate) fn account_id_from_privkey(priv_key: &[u8], prefix: &str) -> MmResult<AccountId, TendermintInitErrorKind> {
     let signing_key =
         SigningKey::from_bytes(priv_key).map_to_mm(|e| TendermintInitErrorKind::InvalidPrivKey(e.to_string()))?;
@@ -401,7 +424,7 @@ pub struct AllBalancesResult {
 enum SearchForSwapTxSpendErr {
     Cosmrs(ErrorReport),
     Rpc(TendermintCoinRpcError),
-    TxMessagesEmpty,
+    HtlcNotFound(String),
     ClaimHtlcTxNotFound,
     UnexpectedHtlcState(i32),
     Proto(DecodeError),
@@ -841,7 +864,7 @@ impl TendermintCoin {
         &self,
         account_info: BaseAccount,
         priv_key: &Secp256k1Secret,
-        tx_payload: Any,
+        tx_payloads: Vec<Any>,
         timeout_height: u64,
         memo: String,
     ) -> cosmrs::Result<Vec<u8>> {
@@ -853,7 +876,7 @@ impl TendermintCoin {
         let fee = Fee::from_amount_and_gas(fee_amount, GAS_LIMIT_DEFAULT);
 
         let signkey = SigningKey::from_bytes(priv_key.as_slice())?;
-        let tx_body = tx::Body::new(vec![tx_payload], memo, timeout_height as u32);
+        let tx_body = tx::Body::new(tx_payloads, memo, timeout_height as u32);
         let auth_info = SignerInfo::single_direct(Some(signkey.public_key()), account_info.sequence).auth_info(fee);
         let sign_doc = SignDoc::new(&tx_body, &auth_info, &self.chain_id, account_info.account_number)?;
         sign_doc.sign(&signkey)?.to_bytes()
@@ -894,12 +917,24 @@ impl TendermintCoin {
         fee: Fee,
         timeout_height: u64,
         memo: String,
+    ) -> Result<(String, Raw), TransactionErr> {
+        self.seq_safe_send_raw_msgs(vec![tx_payload], fee, timeout_height, memo)
+            .await
+    }
+
+    /// Sends the transaction carrying all of the `tx_payloads`, retrying on the account sequence mismatch.
+    pub(super) async fn seq_safe_send_raw_msgs(
+        &self,
+        tx_payloads: Vec<Any>,
+        fee: Fee,
+        timeout_height: u64,
+        memo: String,
     ) -> Result<(String, Raw), TransactionErr> {
         let (tx_id, tx_raw) = loop {
-            let tx_raw = try_tx_s!(self.any_to_signed_raw_tx(
+            let tx_raw = try_tx_s!(self.msgs_to_signed_raw_tx(
                 try_tx_s!(self.priv_key_policy.activated_key_or_err()),
                 try_tx_s!(self.account_info(&self.account_id).await),
-                tx_payload.clone(),
+                tx_payloads.clone(),
                 fee.clone(),
                 timeout_height,
                 memo.clone(),
@@ -921,13 +956,25 @@ impl TendermintCoin {
         Ok((tx_id, tx_raw))
     }
 
-    #[allow(deprecated)]
     pub(super) async fn calculate_fee(
         &self,
         msg: Any,
         timeout_height: u64,
         memo: String,
         withdraw_fee: Option<WithdrawFee>,
+    ) -> MmResult<Fee, TendermintCoinRpcError> {
+        self.calculate_fee_for_msgs(vec![msg], timeout_height, memo, withdraw_fee)
+            .await
+    }
+
+    /// Calculates the fee of the transaction carrying all of the `msgs`.
+    #[allow(deprecated)]
+    pub(super) async fn calculate_fee_for_msgs(
+        &self,
+        msgs: Vec<Any>,
+        timeout_height: u64,
+        memo: String,
+        withdraw_fee: Option<WithdrawFee>,
     ) -> MmResult<Fee, TendermintCoinRpcError> {
         let path = AbciPath::from_str(ABCI_SIMULATE_TX_PATH).expect("valid path");
 
@@ -938,7 +985,7 @@ impl TendermintCoin {
                 .gen_simulated_tx(
                     account_info,
                     activated_priv_key,
-                    msg.clone(),
+                    msgs.clone(),
                     timeout_height,
                     memo.clone(),
                 )
@@ -981,7 +1028,9 @@ impl TendermintCoin {
             ))
         })?;
 
-        let (gas_price, gas_limit) = self.gas_info_for_withdraw(&withdraw_fee, GAS_LIMIT_DEFAULT);
+        // Every message consumes about the same amount of gas as the single message transaction.
+        let fallback_gas_limit = GAS_LIMIT_DEFAULT * msgs.len() as u64;
+        let (gas_price, gas_limit) = self.gas_info_for_withdraw(&withdraw_fee, fallback_gas_limit);
 
         let amount = ((gas.gas_used as f64 * 1.5) * gas_price).ceil();
 
@@ -993,7 +1042,6 @@ impl TendermintCoin {
         Ok(Fee::from_amount_and_gas(fee_amount, gas_limit))
     }
 
-    #[allow(deprecated)]
     pub(super) async fn calculate_account_fee_amount_as_u64(
         &self,
         account_id: &AccountId,
@@ -1002,13 +1050,35 @@ impl TendermintCoin {
         timeout_height: u64,
         memo: String,
         withdraw_fee: Option<WithdrawFee>,
+    ) -> MmResult<u64, TendermintCoinRpcError> {
+        self.calculate_account_fee_amount_for_msgs_as_u64(
+            account_id,
+            priv_key,
+            vec![msg],
+            timeout_height,
+            memo,
+            withdraw_fee,
+        )
+        .await
+    }
+
+    /// Calculates the fee of the transaction carrying all of the `msgs`.
+    #[allow(deprecated)]
+    pub(super) async fn calculate_account_fee_amount_for_msgs_as_u64(
+        &self,
+        account_id: &AccountId,
+        priv_key: &Secp256k1Secret,
+        msgs: Vec<Any>,
+        timeout_height: u64,
+        memo: String,
+        withdraw_fee: Option<WithdrawFee>,
     ) -> MmResult<u64, TendermintCoinRpcError> {
         let path = AbciPath::from_str(ABCI_SIMULATE_TX_PATH).expect("valid path");
 
         let (response, raw_response) = loop {
             let account_info = self.account_info(account_id).await?;
             let tx_bytes = self
-                .gen_simulated_tx(account_info, priv_key, msg.clone(), timeout_height, memo.clone())
+                .gen_simulated_tx(account_info, priv_key, msgs.clone(), timeout_height, memo.clone())
                 .map_to_mm(|e| TendermintCoinRpcError::InternalError(format!("{}", e)))?;
 
             let request = AbciRequest::new(
@@ -1170,9 +1240,22 @@ impl TendermintCoin {
         fee: Fee,
         timeout_height: u64,
         memo: String,
+    ) -> cosmrs::Result<Raw> {
+        self.msgs_to_signed_raw_tx(priv_key, account_info, vec![tx_payload], fee, timeout_height, memo)
+    }
+
+    /// Signs the transaction carrying all of the `tx_payloads`.
+    pub(super) fn msgs_to_signed_raw_tx(
+        &self,
+        priv_key: &Secp256k1Secret,
+        account_info: BaseAccount,
+        tx_payloads: Vec<Any>,
+        fee: Fee,
+        timeout_height: u64,
+        memo: String,
     ) -> cosmrs::Result<Raw> {
         let signkey = SigningKey::from_bytes(priv_key.as_slice())?;
-        let tx_body = tx::Body::new(vec![tx_payload], memo, timeout_height as u32);
+        let tx_body = tx::Body::new(tx_payloads, memo, timeout_height as u32);
         let auth_info = SignerInfo::single_direct(Some(signkey.public_key()), account_info.sequence).auth_info(fee);
         let sign_doc = SignDoc::new(&tx_body, &auth_info, &self.chain_id, account_info.account_number)?;
         sign_doc.sign(&signkey)
@@ -1751,13 +1834,8 @@ impl TendermintCoin {
         input: SearchForSwapTxSpendInput<'_>,
     ) -> MmResult<Option<FoundSwapTxSpend>, SearchForSwapTxSpendErr> {
         let tx = cosmrs::Tx::from_bytes(input.tx)?;
-        let first_message = tx
-            .body
-            .messages
-            .first()
-            .or_mm_err(|| SearchForSwapTxSpendErr::TxMessagesEmpty)?;
-        let htlc_proto = CreateHtlcProtoRep::decode(first_message.value.as_slice())?;
-        let htlc = MsgCreateHtlc::try_from(htlc_proto)?;
+        let htlc = tendermint_swap_v2::create_htlc_msg_locked_with(&tx, input.secret_hash)
+            .map_to_mm(SearchForSwapTxSpendErr::HtlcNotFound)?;
         let htlc_id = self.calculate_htlc_id(&htlc.sender, &htlc.to, htlc.amount, input.secret_hash);
 
         let htlc_response = self.query_htlc(htlc_id.clone()).await?;
@@ -1825,6 +1903,38 @@ impl TendermintCoin {
         }
     }
 
+    #[allow(clippy::result_large_err)]
+    fn withdraw_recipient(&self, to: &str) -> MmResult<AccountId, WithdrawError> {
+        let to_address = AccountId::from_str(to).map_to_mm(|e| WithdrawError::InvalidAddress(e.to_string()))?;
+        if to_address.prefix() != self.account_prefix {
+            return MmError::err(WithdrawError::InvalidAddress(format!(
+                "expected {} address prefix",
+                self.account_prefix
+            )));
+        }
+        Ok(to_address)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn withdraw_sender(&self, from: Option<&WithdrawFrom>) -> MmResult<(AccountId, Secp256k1Secret), WithdrawError> {
+        match from {
+            Some(WithdrawFrom::HDWalletAddress(path_to_address)) => {
+                let priv_key = self
+                    .priv_key_policy
+                    .hd_wallet_derived_priv_key_or_err(path_to_address)?;
+                let account_id = account_id_from_privkey(priv_key.as_slice(), &self.account_prefix)
+                    .map_err(|e| WithdrawError::InternalError(e.to_string()))?;
+                Ok((account_id, priv_key))
+            },
+            Some(WithdrawFrom::AddressId(_)) | Some(WithdrawFrom::DerivationPath { .. }) => {
+                MmError::err(WithdrawError::UnexpectedFromAddress(
+                    "Withdraw from 'AddressId' or 'DerivationPath' is not supported yet for Tendermint!".to_string(),
+                ))
+            },
+            None => Ok((self.account_id.clone(), *self.priv_key_policy.activated_key_or_err()?)),
+        }
+    }
+
     pub(crate) fn active_ticker_and_decimals_from_denom(&self, denom: &str) -> Option<(String, u8)> {
         if self.denom.as_ref() == denom {
             return Some((self.ticker.clone(), self.decimals));
@@ -1913,32 +2023,8 @@ impl MmCoin for TendermintCoin {
     fn withdraw(&self, req: WithdrawRequest) -> WithdrawFut {
         let coin = self.clone();
         let fut = async move {
-            let to_address =
-                AccountId::from_str(&req.to).map_to_mm(|e| WithdrawError::InvalidAddress(e.to_string()))?;
-            if to_address.prefix() != coin.account_prefix {
-                return MmError::err(WithdrawError::InvalidAddress(format!(
-                    "expected {} address prefix",
-                    coin.account_prefix
-                )));
-            }
-
-            let (account_id, priv_key) = match req.from {
-                Some(WithdrawFrom::HDWalletAddress(ref path_to_address)) => {
-                    let priv_key = coin
-                        .priv_key_policy
-                        .hd_wallet_derived_priv_key_or_err(path_to_address)?;
-                    let account_id = account_id_from_privkey(priv_key.as_slice(), &coin.account_prefix)
-                        .map_err(|e| WithdrawError::InternalError(e.to_string()))?;
-                    (account_id, priv_key)
-                },
-                Some(WithdrawFrom::AddressId(_)) | Some(WithdrawFrom::DerivationPath { .. }) => {
-                    return MmError::err(WithdrawError::UnexpectedFromAddress(
-                        "Withdraw from 'AddressId' or 'DerivationPath' is not supported yet for Tendermint!"
-                            .to_string(),
-                    ))
-                },
-                None => (coin.account_id.clone(), *coin.priv_key_policy.activated_key_or_err()?),
-            };
+            let to_address = coin.withdraw_recipient(&req.to)?;
+            let (account_id, priv_key) = coin.withdraw_sender(req.from.as_ref())?;
 
             let (balance_denom, balance_dec) = coin
                 .get_balance_as_unsigned_and_decimal(&account_id, &coin.denom, coin.decimals())
@@ -2081,6 +2167,125 @@ impl MmCoin for TendermintCoin {
         Box::new(fut.boxed().compat())
     }
 
+    fn withdraw_many(&self, req: WithdrawManyRequest) -> WithdrawManyFut {
+        let coin = self.clone();
+        let fut = async move {
+            req.ensure_no_max_outputs()?;
+            let (account_id, priv_key) = coin.withdraw_sender(req.from.as_ref())?;
+
+            let mut to = Vec::with_capacity(req.outputs.len());
+            let mut msgs = Vec::with_capacity(req.outputs.len());
+            let mut amount_dec = BigDecimal::default();
+            let mut received_by_me = BigDecimal::default();
+            for output in req.outputs.iter() {
+                let to_address = coin.withdraw_recipient(&output.to)?;
+                if !coin.is_tx_amount_enough(coin.decimals, &output.amount) {
+                    return MmError::err(WithdrawError::AmountTooLow {
+                        amount: output.amount.clone(),
+                        threshold: coin.min_tx_amount(),
+                    });
+                }
+
+                if to_address == account_id {
+                    received_by_me += &output.amount;
+                }
+                amount_dec += &output.amount;
+
+                let msg_send = MsgSend {
+                    from_address: account_id.clone(),
+                    to_address,
+                    amount: vec![Coin {
+                        denom: coin.denom.clone(),
+                        amount: sat_from_big_decimal(&output.amount, coin.decimals)?.into(),
+                    }],
+                }
+                .to_any()
+                .map_to_mm(|e| WithdrawError::InternalError(e.to_string()))?;
+                msgs.push(msg_send);
+                to.push(output.to.clone());
+            }
+
+            let memo = req.memo.unwrap_or_else(|| TX_DEFAULT_MEMO.into());
+            let current_block = coin
+                .current_block()
+                .compat()
+                .await
+                .map_to_mm(WithdrawError::Transport)?;
+            let timeout_height = current_block + TIMEOUT_HEIGHT_DELTA;
+
+            // Every `MsgSend` consumes about the same amount of gas as the single withdrawal.
+            let fallback_gas_limit = GAS_LIMIT_DEFAULT * msgs.len() as u64;
+            let (_, gas_limit) = coin.gas_info_for_withdraw(&req.fee, fallback_gas_limit);
+
+            let fee_amount_u64 = coin
+                .calculate_account_fee_amount_for_msgs_as_u64(
+                    &account_id,
+                    &priv_key,
+                    msgs.clone(),
+                    timeout_height,
+                    memo.clone(),
+                    req.fee,
+                )
+                .await?;
+            let fee_amount_dec = big_decimal_from_sat_unsigned(fee_amount_u64, coin.decimals());
+
+            let (_, balance_dec) = coin
+                .get_balance_as_unsigned_and_decimal(&account_id, &coin.denom, coin.decimals())
+                .await?;
+            let total_amount = &amount_dec + &fee_amount_dec;
+            if balance_dec < total_amount {
+                return MmError::err(WithdrawError::NotSufficientBalance {
+                    coin: coin.ticker.clone(),
+                    available: balance_dec,
+                    required: total_amount,
+                });
+            }
+
+            let fee_amount = Coin {
+                denom: coin.denom.clone(),
+                amount: fee_amount_u64.into(),
+            };
+            let fee = Fee::from_amount_and_gas(fee_amount, gas_limit);
+
+            let account_info = coin.account_info(&account_id).await?;
+            let tx_raw = coin
+                .msgs_to_signed_raw_tx(&priv_key, account_info, msgs, fee, timeout_height, memo.clone())
+                .map_to_mm(|e| WithdrawError::InternalError(e.to_string()))?;
+
+            let tx_bytes = tx_raw
+                .to_bytes()
+                .map_to_mm(|e| WithdrawError::InternalError(e.to_string()))?;
+
+            let hash = sha256(&tx_bytes);
+
+            Ok(TransactionDetails {
+                tx_hash: hex::encode_upper(hash.as_slice()),
+                tx_hex: tx_bytes.into(),
+                from: vec![account_id.to_string()],
+                to,
+                my_balance_change: &received_by_me - &total_amount,
+                spent_by_me: total_amount.clone(),
+                total_amount,
+                received_by_me,
+                block_height: 0,
+                timestamp: 0,
+                fee_details: Some(TxFeeDetails::Tendermint(TendermintFeeDetails {
+                    coin: coin.ticker.clone(),
+                    amount: fee_amount_dec,
+                    uamount: fee_amount_u64,
+                    gas_limit,
+                })),
+                coin: coin.ticker.to_string(),
+                internal_id: hash.to_vec().into(),
+                kmd_rewards: None,
+                transaction_type: TransactionType::default(),
+                memo: Some(memo),
+            }
+            .into())
+        };
+        Box::new(fut.boxed().compat())
+    }
+
     fn get_raw_transaction(&self, mut req: RawTransactionRequest) -> RawTransactionFut {
         let coin = self.clone();
         let fut = async move {
@@ -2341,9 +2546,8 @@ impl MarketCoinOps for TendermintCoin {
 
     fn wait_for_htlc_tx_spend(&self, args: WaitForHTLCTxSpendArgs<'_>) -> TransactionFut {
         let tx = try_tx_fus!(cosmrs::Tx::from_bytes(args.tx_bytes));
-        let first_message = try_tx_fus!(tx.body.messages.first().ok_or("Tx body couldn't be read."));
-        let htlc_proto = try_tx_fus!(CreateHtlcProtoRep::decode(first_message.value.as_slice()));
-        let htlc = try_tx_fus!(MsgCreateHtlc::try_from(htlc_proto));
+        // Swap v2 taker payment claims the funding HTLCs first, so the HTLC is selected by the hash lock.
+        let htlc = try_tx_fus!(tendermint_swap_v2::create_htlc_msg_locked_with(&tx, args.secret_hash));
         let htlc_id = self.calculate_htlc_id(&htlc.sender, &htlc.to, htlc.amount, args.secret_hash);
 
         let events_string = format!("claim_htlc.id='{}'", htlc_id);
@@ -2655,19 +2859,14 @@ impl SwapOps for TendermintCoin {
     }
 
     #[inline]
-    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> KeyPair {
-        key_pair_from_secret(
-            self.priv_key_policy
-                .activated_key_or_err()
-                .expect("valid priv key")
-                .as_ref(),
-        )
-        .expect("valid priv key")
+    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<KeyPair> {
+        let activated_key = self.priv_key_policy.activated_key_or_err()?;
+        Ok(key_pair_from_secret(activated_key.as_ref()).expect("valid priv key"))
     }
 
     #[inline]
-    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> Vec<u8> {
-        self.derive_htlc_key_pair(swap_unique_data).public_slice().to_vec()
+    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Vec<u8>> {
+        Ok(self.derive_htlc_key_pair(swap_unique_data)?.public_slice().to_vec())
     }
 
     fn validate_other_pubkey(&self, raw_pubkey: &[u8]) -> MmResult<(), ValidateOtherPubKeyErr> {
@@ -2721,6 +2920,124 @@ impl MakerSwapTakerCoin for TendermintCoin {
     async fn on_maker_payment_refund_success(&self, _taker_payment: &[u8]) -> RefundResult<()> { Ok(()) }
 }
 
+impl CoinAssocTypes for TendermintCoin {
+    type Pubkey = Public;
+    type PubkeyParseError = MmError<TendermintAssocTypesError>;
+    type Tx = CosmosTransaction;
+    type TxParseError = MmError<TendermintAssocTypesError>;
+    type Preimage = CosmosTransaction;
+    type PreimageParseError = MmError<TendermintAssocTypesError>;
+    type Sig = Signature;
+    type SigParseError = MmError<TendermintAssocTypesError>;
+
+    fn parse_pubkey(&self, pubkey: &[u8]) -> Result<Self::Pubkey, Self::PubkeyParseError> {
+        Public::from_slice(pubkey).map_to_mm(|e| TendermintAssocTypesError::InvalidPubkey(e.to_string()))
+    }
+
+    fn parse_tx(&self, tx: &[u8]) -> Result<Self::Tx, Self::TxParseError> {
+        let data = TxRaw::decode(tx).map_to_mm(|e| TendermintAssocTypesError::TxParseError(e.to_string()))?;
+        Ok(CosmosTransaction { data })
+    }
+
+    /// The preimage is the transaction with the unsigned body, see [`tendermint_swap_v2`].
+    fn parse_preimage(&self, tx: &[u8]) -> Result<Self::Preimage, Self::PreimageParseError> { self.parse_tx(tx) }
+
+    fn parse_signature(&self, sig: &[u8]) -> Result<Self::Sig, Self::SigParseError> {
+        // Preimages are signed with DER encoded secp256k1 signatures of the HTLC keys
+        SecpSignature::from_der(sig).map_to_mm(|e| TendermintAssocTypesError::InvalidSignature(e.to_string()))?;
+        Ok(sig.into())
+    }
+}
+
+/// See [`tendermint_swap_v2`] for the details of the Trading Protocol Upgrade implementation using IRIS HTLC.
+#[async_trait]
+impl SwapOpsV2 for TendermintCoin {
+    async fn send_taker_funding(&self, args: SendTakerFundingArgs<'_>) -> Result<Self::Tx, TransactionErr> {
+        self.send_taker_funding_for_denom(args, self.denom.clone(), self.decimals)
+            .await
+    }
+
+    async fn validate_taker_funding(&self, args: ValidateTakerFundingArgs<'_, Self>) -> ValidateTakerFundingResult {
+        self.validate_taker_funding_for_denom(args, self.denom.clone(), self.decimals)
+            .await
+    }
+
+    async fn refund_taker_funding_timelock(&self, args: RefundPaymentArgs<'_>) -> TransactionResult {
+        self.check_htlc_auto_refunded(args.payment_tx).await
+    }
+
+    async fn refund_taker_funding_secret(
+        &self,
+        args: RefundFundingSecretArgs<'_, Self>,
+    ) -> Result<Self::Tx, TransactionErr> {
+        self.refund_taker_funding_secret_impl(args.funding_tx, args.swap_unique_data)
+            .await
+    }
+
+    async fn gen_taker_funding_spend_preimage(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_, Self>,
+        swap_unique_data: &[u8],
+    ) -> GenPreimageResult<Self> {
+        self.gen_taker_funding_spend_preimage_impl(args, swap_unique_data)
+    }
+
+    async fn validate_taker_funding_spend_preimage(
+        &self,
+        gen_args: &GenTakerFundingSpendArgs<'_, Self>,
+        preimage: &TxPreimageWithSig<Self>,
+    ) -> ValidateTakerFundingSpendPreimageResult {
+        self.validate_taker_funding_spend_preimage_impl(gen_args, preimage)
+    }
+
+    async fn sign_and_send_taker_funding_spend(
+        &self,
+        preimage: &TxPreimageWithSig<Self>,
+        args: &GenTakerFundingSpendArgs<'_, Self>,
+        swap_unique_data: &[u8],
+    ) -> Result<Self::Tx, TransactionErr> {
+        self.sign_and_send_taker_funding_spend_impl(preimage, args, swap_unique_data)
+            .await
+    }
+
+    async fn refund_combined_taker_payment(&self, args: RefundPaymentArgs<'_>) -> TransactionResult {
+        self.check_htlc_auto_refunded(args.payment_tx).await
+    }
+
+    async fn gen_taker_payment_spend_preimage(
+        &self,
+        args: &GenTakerPaymentSpendArgs<'_, Self>,
+        swap_unique_data: &[u8],
+    ) -> GenPreimageResult<Self> {
+        self.gen_taker_payment_spend_preimage_impl(args, swap_unique_data)
+    }
+
+    async fn validate_taker_payment_spend_preimage(
+        &self,
+        gen_args: &GenTakerPaymentSpendArgs<'_, Self>,
+        preimage: &TxPreimageWithSig<Self>,
+    ) -> ValidateTakerPaymentSpendPreimageResult {
+        self.validate_taker_payment_spend_preimage_for_denom(gen_args, preimage, self.denom.clone(), self.decimals)
+            .await
+    }
+
+    async fn sign_and_broadcast_taker_payment_spend(
+        &self,
+        _preimage: &TxPreimageWithSig<Self>,
+        gen_args: &GenTakerPaymentSpendArgs<'_, Self>,
+        secret: &[u8],
+        _swap_unique_data: &[u8],
+    ) -> TransactionResult {
+        self.claim_htlc_for_tx(gen_args.taker_tx, secret)
+            .await
+            .map(TransactionEnum::CosmosTransaction)
+    }
+
+    fn derive_htlc_pubkey_v2(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Self::Pubkey> {
+        Ok(*self.derive_htlc_key_pair(swap_unique_data)?.public())
+    }
+}
+
 #[async_trait]
 impl WatcherOps for TendermintCoin {
     fn create_maker_payment_spend_preimage(
@@ -2838,6 +3155,7 @@ pub fn tendermint_priv_key_policy(
 pub mod tendermint_coin_tests {
     use super::*;
 
+    use crate::WithdrawOutput;
     use common::{block_on, wait_until_ms, DEX_FEE_ADDR_RAW_PUBKEY};
     use cosmrs::proto::cosmos::tx::v1beta1::{GetTxRequest, GetTxResponse, GetTxsEventResponse};
     use crypto::privkey::key_pair_from_seed;
@@ -3628,6 +3946,86 @@ pub mod tendermint_coin_tests {
         assert!(status_code.is_none());
     }
 
+    #[test]
+    fn test_withdraw_many() {
+        let rpc_urls = vec![IRIS_TESTNET_RPC_URL.to_string()];
+
+        let protocol_conf = get_iris_protocol();
+
+        let ctx = mm2_core::mm_ctx::MmCtxBuilder::default().into_mm_arc();
+
+        let conf = TendermintConf {
+            avg_blocktime: AVG_BLOCKTIME,
+            derivation_path: None,
+        };
+
+        let key_pair = key_pair_from_seed(IRIS_TESTNET_HTLC_PAIR1_SEED).unwrap();
+        let priv_key_policy = TendermintPrivKeyPolicy::Iguana(key_pair.private().secret);
+
+        let coin = block_on(TendermintCoin::init(
+            &ctx,
+            "IRIS".to_string(),
+            conf,
+            protocol_conf,
+            rpc_urls,
+            false,
+            priv_key_policy,
+        ))
+        .unwrap();
+
+        let my_address = coin.account_id.to_string();
+        let mut req = WithdrawManyRequest {
+            coin: "IRIS".to_string(),
+            from: None,
+            outputs: vec![
+                WithdrawOutput {
+                    to: IRIS_TESTNET_HTLC_PAIR2_ADDRESS.to_string(),
+                    amount: "0.000001".parse().unwrap(),
+                    max: false,
+                },
+                WithdrawOutput {
+                    to: my_address.clone(),
+                    amount: "0.000002".parse().unwrap(),
+                    max: false,
+                },
+            ],
+            fee: None,
+            memo: Some("withdraw many".to_string()),
+            inputs: None,
+        };
+
+        let withdraw_details = block_on(coin.withdraw_many(req.clone()).compat()).unwrap();
+        assert!(withdraw_details.batch_txs.is_empty());
+        let tx_details = withdraw_details.details;
+        assert_eq!(tx_details.to, vec![
+            IRIS_TESTNET_HTLC_PAIR2_ADDRESS.to_string(),
+            my_address.clone()
+        ]);
+        assert_eq!(tx_details.from, vec![my_address]);
+        assert_eq!(tx_details.received_by_me, "0.000002".parse::<BigDecimal>().unwrap());
+        let fee = match tx_details.fee_details {
+            Some(TxFeeDetails::Tendermint(ref fee_details)) => fee_details.amount.clone(),
+            ref fee_details => panic!("Unexpected fee details {:?}", fee_details),
+        };
+        assert_eq!(
+            tx_details.total_amount,
+            "0.000003".parse::<BigDecimal>().unwrap() + &fee
+        );
+        assert_eq!(
+            tx_details.my_balance_change,
+            "-0.000001".parse::<BigDecimal>().unwrap() - &fee
+        );
+
+        // All of the outputs are paid within one transaction.
+        let tx = cosmrs::Tx::from_bytes(&tx_details.tx_hex).unwrap();
+        assert_eq!(tx.body.messages.len(), 2);
+        assert_eq!(tx.body.memo, "withdraw many");
+
+        req.outputs[0].max = true;
+        let error = block_on(coin.withdraw_many(req).compat()).unwrap_err().into_inner();
+        assert!(matches!(error, WithdrawError::UnsupportedError(_)), "{:?}", error);
+    }
+
     #[test]
     fn test_wait_for_confirmations() {
         const CHECK_INTERVAL: u64 = 2;
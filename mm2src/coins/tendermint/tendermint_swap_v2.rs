@@ -0,0 +1,1055 @@
+//! Trading Protocol Upgrade (swap v2) implementation for Tendermint coins and tokens using IRIS HTLC.
+//!
+//! IRIS HTLC pays the locked amount to its recipient once anyone reveals the secret, and it's refunded to the sender
+//! automatically at the expiration height. It can't be spent to another HTLC, so the swap transactions are:
+//! * Taker funding creates the HTLCs locking the trading volume with premium and the dex fee to the taker's own address.
+//!   They're refunded to taker automatically after the funding time lock expiration, or immediately by claiming them
+//!   with the funding secrets. The funding secrets are derived from the taker's private key and the swap unique data
+//!   instead of the taker secret, so claiming the funding doesn't reveal the taker secret unlocking the maker payment.
+//! * Taker funding spend preimage is generated by maker. It's the unsigned transaction body creating the taker payment
+//!   HTLC to maker locked with the maker secret hash and sending the dex fee, signed by the maker's HTLC key.
+//!   Taker broadcasts the preimage messages together with the claims of the funding HTLCs in a single transaction,
+//!   so the funding is moved to the taker payment atomically.
+//! * Taker payment is claimed by maker revealing the maker secret, or refunded to taker automatically after
+//!   the taker payment time lock expiration.
+//!
+//! # Limitation
+//!
+//! Unlike the UTXO and EVM implementations, taker doesn't reveal the taker secret refunding the funding:
+//! IRIS HTLC has a single hash lock, and claiming the funding with the taker secret during the funding spend would let
+//! maker refund the maker payment and still claim the taker payment. So a taker can refund the funding immediately
+//! after the maker payment is sent, and maker has to wait for the maker payment time lock expiration to refund it.
+//! Until IRIS HTLC supports a refund path revealing the taker secret, new swap v2 of Tendermint taker coins aren't
+//! started, see `is_swap_v2_pair_supported`; this implementation only finishes the swaps that are already running.
+
+use super::{CosmosTransaction, TendermintCoin, TendermintCoinRpcError, MIN_TIME_LOCK, TIMEOUT_HEIGHT_DELTA,
+            TX_DEFAULT_MEMO};
+use crate::tendermint::iris::htlc::{MsgClaimHtlc, MsgCreateHtlc, HTLC_STATE_COMPLETED, HTLC_STATE_OPEN,
+                                    HTLC_STATE_REFUNDED};
+use crate::tendermint::type_urls::CREATE_HTLC_TYPE_URL;
+use crate::utxo::sat_from_big_decimal;
+use crate::{CoinAssocTypes, GenPreimageResult, GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs, MarketCoinOps,
+            SendTakerFundingArgs, SwapOps, Transaction, TransactionEnum, TransactionErr, TransactionResult,
+            TxGenError, TxPreimageWithSig, ValidateTakerFundingArgs, ValidateTakerFundingError,
+            ValidateTakerFundingResult, ValidateTakerFundingSpendPreimageError,
+            ValidateTakerFundingSpendPreimageResult, ValidateTakerPaymentSpendPreimageError,
+            ValidateTakerPaymentSpendPreimageResult};
+use bitcrypto::{dhash160, sha256};
+use common::{now_sec, Future01CompatExt, DEX_FEE_ADDR_RAW_PUBKEY};
+use cosmrs::bank::MsgSend;
+use cosmrs::proto::cosmos::tx::v1beta1::{TxBody, TxRaw};
+use cosmrs::tx::{self, Msg};
+use cosmrs::{AccountId, Any, Coin as CosmosCoin, Denom};
+use keys::{Public, Signature};
+use mm2_err_handle::prelude::*;
+use prost::Message;
+use std::convert::TryFrom;
+
+/// Tag of the funding secret locking the trading volume with premium.
+const FUNDING_PAYMENT_SECRET_TAG: &[u8] = b"taker_funding_payment";
+/// Tag of the funding secret locking the dex fee.
+const FUNDING_DEX_FEE_SECRET_TAG: &[u8] = b"taker_funding_dex_fee";
+
+/// Returns the HTLCs created by the transaction.
+pub(super) fn create_htlc_msgs(tx: &cosmrs::Tx) -> Result<Vec<MsgCreateHtlc>, String> {
+    tx.body
+        .messages
+        .iter()
+        .filter(|msg| msg.type_url == CREATE_HTLC_TYPE_URL)
+        .map(|msg| MsgCreateHtlc::from_any(msg).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Returns the HTLC created by the transaction and locked with the `secret_hash`.
+pub(super) fn create_htlc_msg_locked_with(tx: &cosmrs::Tx, secret_hash: &[u8]) -> Result<MsgCreateHtlc, String> {
+    let hash_lock = hex::encode(secret_hash);
+    create_htlc_msgs(tx)?
+        .into_iter()
+        .find(|htlc| htlc.hash_lock.eq_ignore_ascii_case(&hash_lock))
+        .ok_or_else(|| format!("Tx doesn't create HTLC locked with {}", hash_lock))
+}
+
+/// Wraps the unsigned transaction body carrying the `msgs` into the preimage.
+fn preimage_from_msgs(msgs: Vec<Any>) -> Result<CosmosTransaction, String> {
+    let body_bytes = tx::Body::new(msgs, TX_DEFAULT_MEMO, 0u32)
+        .into_bytes()
+        .map_err(|e| e.to_string())?;
+    Ok(CosmosTransaction {
+        data: TxRaw {
+            body_bytes,
+            auth_info_bytes: Vec::new(),
+            signatures: Vec::new(),
+        },
+    })
+}
+
+/// Returns the messages of the preimage body.
+fn preimage_msgs(preimage: &CosmosTransaction) -> Result<Vec<Any>, String> {
+    let body = TxBody::decode(preimage.data.body_bytes.as_slice()).map_err(|e| e.to_string())?;
+    let body = tx::Body::try_from(body).map_err(|e| e.to_string())?;
+    Ok(body.messages)
+}
+
+fn verify_swap_data_sig(data: &[u8], signature: &Signature, pubkey: &Public) -> Result<bool, String> {
+    pubkey.verify(&sha256(data), signature).map_err(|e| e.to_string())
+}
+
+fn coin_amount_as_u64(coins: &[CosmosCoin], denom: &Denom) -> Result<u64, String> {
+    match coins {
+        [coin] if &coin.denom == denom => coin.amount.to_string().parse().map_err(|e| format!("{:?}", e)),
+        _ => ERR!("Expected the single {} amount, found {:?}", denom, coins),
+    }
+}
+
+impl From<TendermintCoinRpcError> for ValidateTakerPaymentSpendPreimageError {
+    fn from(err: TendermintCoinRpcError) -> Self { ValidateTakerPaymentSpendPreimageError::Rpc(err.to_string()) }
+}
+
+impl TendermintCoin {
+    /// Derives the secret locking the taker funding HTLC, so it can be restored from the private key.
+    fn taker_funding_secret(&self, swap_unique_data: &[u8], tag: &[u8]) -> Result<[u8; 32], String> {
+        let priv_key = self.priv_key_policy.activated_key_or_err().map_err(|e| e.to_string())?;
+        let mut data = priv_key.as_slice().to_vec();
+        data.extend_from_slice(swap_unique_data);
+        data.extend_from_slice(tag);
+        Ok(sha256(&data).take())
+    }
+
+    fn account_id_from_pubkey(&self, pubkey: &[u8]) -> Result<AccountId, String> {
+        AccountId::new(&self.account_prefix, dhash160(pubkey).as_slice()).map_err(|e| e.to_string())
+    }
+
+    fn htlc_id(&self, htlc: &MsgCreateHtlc) -> Result<String, String> {
+        let hash_lock = hex::decode(&htlc.hash_lock).map_err(|e| e.to_string())?;
+        Ok(self.calculate_htlc_id(&htlc.sender, &htlc.to, htlc.amount.clone(), &hash_lock))
+    }
+
+    /// Signs the hash of the swap `data` with the HTLC key.
+    fn sign_swap_data(&self, data: &[u8], swap_unique_data: &[u8]) -> MmResult<Signature, TxGenError> {
+        let key_pair = self.derive_htlc_key_pair(swap_unique_data)?;
+        key_pair
+            .private()
+            .sign(&sha256(data))
+            .map_to_mm(|e| TxGenError::Signing(e.to_string()))
+    }
+
+    async fn send_swap_msgs(&self, msgs: Vec<Any>) -> Result<CosmosTransaction, TransactionErr> {
+        let current_block = try_tx_s!(self.current_block().compat().await);
+        let timeout_height = current_block + TIMEOUT_HEIGHT_DELTA;
+
+        let fee = try_tx_s!(
+            self.calculate_fee_for_msgs(msgs.clone(), timeout_height, TX_DEFAULT_MEMO.to_owned(), None)
+                .await
+        );
+
+        let (_tx_id, tx_raw) = self
+            .seq_safe_send_raw_msgs(msgs, fee, timeout_height, TX_DEFAULT_MEMO.into())
+            .await?;
+
+        Ok(CosmosTransaction { data: tx_raw.into() })
+    }
+
+    /// Generates the taker funding HTLCs: the trading volume with premium and the dex fee, if it's not zero.
+    fn gen_taker_funding_htlcs(
+        &self,
+        args: &SendTakerFundingArgs<'_>,
+        denom: Denom,
+        decimals: u8,
+        time_lock: u64,
+    ) -> Result<Vec<MsgCreateHtlc>, String> {
+        let payment_amount = sat_from_big_decimal(&(&args.trading_amount + &args.premium_amount), decimals)
+            .map_err(|e| e.to_string())?;
+        let dex_fee_amount = sat_from_big_decimal(&args.dex_fee_amount, decimals).map_err(|e| e.to_string())?;
+
+        let mut amounts = vec![(payment_amount, FUNDING_PAYMENT_SECRET_TAG)];
+        if dex_fee_amount > 0 {
+            amounts.push((dex_fee_amount, FUNDING_DEX_FEE_SECRET_TAG));
+        }
+
+        amounts
+            .into_iter()
+            .map(|(amount, tag)| {
+                let secret = self.taker_funding_secret(args.swap_unique_data, tag)?;
+                Ok(MsgCreateHtlc {
+                    sender: self.account_id.clone(),
+                    to: self.account_id.clone(),
+                    receiver_on_other_chain: String::new(),
+                    sender_on_other_chain: String::new(),
+                    amount: vec![CosmosCoin {
+                        denom: denom.clone(),
+                        amount: amount.into(),
+                    }],
+                    hash_lock: hex::encode(sha256(&secret).as_slice()),
+                    timestamp: 0,
+                    time_lock,
+                    transfer: false,
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) async fn send_taker_funding_for_denom(
+        &self,
+        args: SendTakerFundingArgs<'_>,
+        denom: Denom,
+        decimals: u8,
+    ) -> Result<CosmosTransaction, TransactionErr> {
+        let time_lock = self.estimate_blocks_from_duration(args.time_lock.saturating_sub(now_sec()));
+        let htlcs = try_tx_s!(self.gen_taker_funding_htlcs(&args, denom, decimals, time_lock as u64));
+        let msgs = try_tx_s!(htlcs.iter().map(Msg::to_any).collect::<Result<Vec<_>, _>>());
+        self.send_swap_msgs(msgs).await
+    }
+
+    /// Checks the taker funding HTLCs are locked to the taker's address with the expected amounts and time lock.
+    fn check_taker_funding_htlcs<Coin>(
+        &self,
+        htlcs: &[MsgCreateHtlc],
+        args: &ValidateTakerFundingArgs<'_, Coin>,
+        denom: &Denom,
+        decimals: u8,
+    ) -> ValidateTakerFundingResult
+    where
+        Coin: CoinAssocTypes<Tx = CosmosTransaction, Pubkey = Public> + ?Sized,
+    {
+        let taker = self
+            .account_id_from_pubkey(args.other_pub)
+            .map_to_mm(ValidateTakerFundingError::InternalError)?;
+
+        let payment_amount = sat_from_big_decimal(&(&args.trading_amount + &args.premium_amount), decimals)?;
+        let dex_fee_amount = sat_from_big_decimal(&args.dex_fee_amount, decimals)?;
+        let mut expected_amounts = vec![payment_amount];
+        if dex_fee_amount > 0 {
+            expected_amounts.push(dex_fee_amount);
+        }
+
+        if htlcs.len() != expected_amounts.len() {
+            return MmError::err(ValidateTakerFundingError::WrongPaymentTx(format!(
+                "Funding tx must create {} HTLCs, found {}",
+                expected_amounts.len(),
+                htlcs.len()
+            )));
+        }
+
+        // HTLC time lock is set in blocks relative to the creation height, so it must be at least
+        // the number of blocks remaining until the funding lock time.
+        let min_time_lock = self.estimate_blocks_from_duration(args.time_lock.saturating_sub(now_sec()));
+        for (htlc, amount) in htlcs.iter().zip(expected_amounts) {
+            let expected_amount = vec![CosmosCoin {
+                denom: denom.clone(),
+                amount: amount.into(),
+            }];
+            if htlc.sender != taker || htlc.to != taker || htlc.amount != expected_amount {
+                return MmError::err(ValidateTakerFundingError::InvalidDestinationOrAmount(format!(
+                    "Incorrect CreateHtlc message {:?}, expected sender and receiver {} and amount {:?}",
+                    htlc, taker, expected_amount
+                )));
+            }
+
+            if (htlc.time_lock as i64) < min_time_lock {
+                return MmError::err(ValidateTakerFundingError::LocktimeOverflow(format!(
+                    "HTLC time lock {} is less than {} blocks",
+                    htlc.time_lock, min_time_lock
+                )));
+            }
+
+            let hash_lock =
+                hex::decode(&htlc.hash_lock).map_to_mm(|e| ValidateTakerFundingError::WrongPaymentTx(e.to_string()))?;
+            if hash_lock.len() != 32 {
+                return MmError::err(ValidateTakerFundingError::WrongPaymentTx(format!(
+                    "Incorrect hash lock {}",
+                    htlc.hash_lock
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn validate_taker_funding_for_denom<Coin>(
+        &self,
+        args: ValidateTakerFundingArgs<'_, Coin>,
+        denom: Denom,
+        decimals: u8,
+    ) -> ValidateTakerFundingResult
+    where
+        Coin: CoinAssocTypes<Tx = CosmosTransaction, Pubkey = Public> + ?Sized,
+    {
+        let funding_tx_bytes = args.funding_tx.tx_hex();
+        let tx = cosmrs::Tx::from_bytes(&funding_tx_bytes)
+            .map_to_mm(|e| ValidateTakerFundingError::WrongPaymentTx(e.to_string()))?;
+
+        let htlcs = create_htlc_msgs(&tx).map_to_mm(ValidateTakerFundingError::WrongPaymentTx)?;
+        if htlcs.len() != tx.body.messages.len() {
+            return MmError::err(ValidateTakerFundingError::WrongPaymentTx(
+                "Funding tx must have only CreateHtlc messages".into(),
+            ));
+        }
+        self.check_taker_funding_htlcs(&htlcs, &args, &denom, decimals)?;
+
+        let hash = hex::encode_upper(sha256(&funding_tx_bytes).as_slice());
+        let tx_from_rpc = self.request_tx(hash).await?;
+        if funding_tx_bytes != tx_from_rpc.encode_to_vec() {
+            return MmError::err(ValidateTakerFundingError::TxBytesMismatch {
+                from_rpc: tx_from_rpc.encode_to_vec().into(),
+                actual: funding_tx_bytes.into(),
+            });
+        }
+
+        for htlc in htlcs.iter() {
+            let htlc_id = self
+                .htlc_id(htlc)
+                .map_to_mm(ValidateTakerFundingError::WrongPaymentTx)?;
+            let htlc_data = self
+                .query_htlc(htlc_id.clone())
+                .await?
+                .htlc
+                .or_mm_err(|| ValidateTakerFundingError::Rpc(format!("No HTLC data for {}", htlc_id)))?;
+
+            if htlc_data.state != HTLC_STATE_OPEN {
+                return MmError::err(ValidateTakerFundingError::UnexpectedPaymentState(format!(
+                    "HTLC {} state {}",
+                    htlc_id, htlc_data.state
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generates the claims of the taker funding HTLCs. The claimed funds are paid to the taker's address.
+    fn claim_taker_funding_msgs(
+        &self,
+        funding_tx: &CosmosTransaction,
+        swap_unique_data: &[u8],
+    ) -> Result<Vec<Any>, String> {
+        let tx = cosmrs::Tx::from_bytes(&funding_tx.tx_hex()).map_err(|e| e.to_string())?;
+        let secrets = [FUNDING_PAYMENT_SECRET_TAG, FUNDING_DEX_FEE_SECRET_TAG]
+            .iter()
+            .map(|tag| self.taker_funding_secret(swap_unique_data, tag))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let htlcs = create_htlc_msgs(&tx)?;
+        if htlcs.is_empty() {
+            return ERR!("Funding tx doesn't create HTLC");
+        }
+
+        htlcs
+            .iter()
+            .map(|htlc| {
+                if htlc.to != self.account_id {
+                    return ERR!("Funding HTLC {:?} isn't locked to {}", htlc, self.account_id);
+                }
+                let secret = secrets
+                    .iter()
+                    .find(|secret| {
+                        hex::encode(sha256(secret.as_slice()).as_slice()).eq_ignore_ascii_case(&htlc.hash_lock)
+                    })
+                    .ok_or_else(|| format!("Funding HTLC {:?} isn't locked with the funding secret", htlc))?;
+                MsgClaimHtlc {
+                    sender: self.account_id.clone(),
+                    id: self.htlc_id(htlc)?,
+                    secret: hex::encode(secret),
+                }
+                .to_any()
+                .map_err(|e| e.to_string())
+            })
+            .collect()
+    }
+
+    /// Refunds the taker funding immediately claiming its HTLCs back to the taker's address.
+    pub(crate) async fn refund_taker_funding_secret_impl(
+        &self,
+        funding_tx: &CosmosTransaction,
+        swap_unique_data: &[u8],
+    ) -> Result<CosmosTransaction, TransactionErr> {
+        let msgs = try_tx_s!(self.claim_taker_funding_msgs(funding_tx, swap_unique_data));
+        self.send_swap_msgs(msgs).await
+    }
+
+    /// IRIS HTLC is refunded automatically at the expiration height without a transaction,
+    /// so checks that all of the HTLCs created by `htlc_tx` are refunded and returns the dummy refund tx.
+    pub(crate) async fn check_htlc_auto_refunded(&self, htlc_tx: &[u8]) -> TransactionResult {
+        let tx = try_tx_s!(cosmrs::Tx::from_bytes(htlc_tx));
+        let htlcs = try_tx_s!(create_htlc_msgs(&tx));
+        if htlcs.is_empty() {
+            return TX_PLAIN_ERR!("Tx doesn't create HTLC");
+        }
+
+        for htlc in htlcs.iter() {
+            let htlc_id = try_tx_s!(self.htlc_id(htlc));
+            let htlc_response = try_tx_s!(self.query_htlc(htlc_id.clone()).await);
+            let htlc_data = try_tx_s!(htlc_response.htlc.ok_or("HTLC data is missing"));
+            match htlc_data.state {
+                HTLC_STATE_REFUNDED => {},
+                HTLC_STATE_OPEN => {
+                    return TX_PLAIN_ERR!(
+                        "HTLC {} isn't expired yet, it's refunded automatically at the expiration height",
+                        htlc_id
+                    )
+                },
+                HTLC_STATE_COMPLETED => return TX_PLAIN_ERR!("HTLC {} is already claimed", htlc_id),
+                unexpected_state => return TX_PLAIN_ERR!("Unexpected state {} of HTLC {}", unexpected_state, htlc_id),
+            }
+        }
+
+        Ok(TransactionEnum::CosmosTransaction(CosmosTransaction {
+            data: TxRaw::default(),
+        }))
+    }
+
+    /// Generates the messages moving the taker funding to the taker payment HTLC locked to maker with
+    /// the maker secret hash, and the dex fee to the dex fee address.
+    fn gen_taker_funding_spend_msgs<Coin>(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_, Coin>,
+        time_lock: u64,
+    ) -> Result<Vec<Any>, String>
+    where
+        Coin: CoinAssocTypes<Tx = CosmosTransaction, Pubkey = Public> + ?Sized,
+    {
+        let tx = cosmrs::Tx::from_bytes(&args.funding_tx.tx_hex()).map_err(|e| e.to_string())?;
+        let htlcs = create_htlc_msgs(&tx)?;
+        let (payment, dex_fee) = match htlcs.as_slice() {
+            [payment] => (payment, None),
+            [payment, dex_fee] => (payment, Some(dex_fee)),
+            _ => return ERR!("Funding tx must create 1 or 2 HTLCs, found {}", htlcs.len()),
+        };
+
+        let taker = self.account_id_from_pubkey(args.taker_pub)?;
+        let maker = self.account_id_from_pubkey(args.maker_pub)?;
+        let taker_payment = MsgCreateHtlc {
+            sender: taker.clone(),
+            to: maker,
+            receiver_on_other_chain: String::new(),
+            sender_on_other_chain: String::new(),
+            amount: payment.amount.clone(),
+            hash_lock: hex::encode(args.maker_secret_hash),
+            timestamp: 0,
+            time_lock,
+            transfer: false,
+        };
+        let mut msgs = vec![taker_payment.to_any().map_err(|e| e.to_string())?];
+
+        if let Some(dex_fee) = dex_fee {
+            let dex_fee_send = MsgSend {
+                from_address: taker,
+                to_address: self.account_id_from_pubkey(DEX_FEE_ADDR_RAW_PUBKEY.as_slice())?,
+                amount: dex_fee.amount.clone(),
+            };
+            msgs.push(dex_fee_send.to_any().map_err(|e| e.to_string())?);
+        }
+
+        Ok(msgs)
+    }
+
+    pub(crate) fn gen_taker_funding_spend_preimage_impl<Coin>(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_, Coin>,
+        swap_unique_data: &[u8],
+    ) -> GenPreimageResult<Coin>
+    where
+        Coin: CoinAssocTypes<Tx = CosmosTransaction, Pubkey = Public, Preimage = CosmosTransaction, Sig = Signature>
+            + ?Sized,
+    {
+        let time_lock = self.estimate_blocks_from_duration(args.taker_payment_time_lock.saturating_sub(now_sec()));
+        let msgs = self
+            .gen_taker_funding_spend_msgs(args, time_lock as u64)
+            .map_to_mm(TxGenError::Legacy)?;
+        let preimage = preimage_from_msgs(msgs).map_to_mm(TxGenError::Legacy)?;
+        let signature = self.sign_swap_data(&preimage.data.body_bytes, swap_unique_data)?;
+        Ok(TxPreimageWithSig { preimage, signature })
+    }
+
+    pub(crate) fn validate_taker_funding_spend_preimage_impl<Coin>(
+        &self,
+        gen_args: &GenTakerFundingSpendArgs<'_, Coin>,
+        preimage: &TxPreimageWithSig<Coin>,
+    ) -> ValidateTakerFundingSpendPreimageResult
+    where
+        Coin: CoinAssocTypes<Tx = CosmosTransaction, Pubkey = Public, Preimage = CosmosTransaction, Sig = Signature>
+            + ?Sized,
+    {
+        let body_bytes = &preimage.preimage.data.body_bytes;
+        let is_signed_by_maker = verify_swap_data_sig(body_bytes, &preimage.signature, gen_args.maker_pub)
+            .map_to_mm(ValidateTakerFundingSpendPreimageError::SignatureVerificationFailure)?;
+        if !is_signed_by_maker {
+            return MmError::err(ValidateTakerFundingSpendPreimageError::InvalidMakerSignature);
+        }
+
+        let msgs =
+            preimage_msgs(&preimage.preimage).map_to_mm(ValidateTakerFundingSpendPreimageError::InvalidPreimage)?;
+        // The time lock in blocks depends on the time of the preimage generation, so it's checked separately.
+        let time_lock = match msgs.first().map(MsgCreateHtlc::from_any) {
+            Some(Ok(htlc)) => htlc.time_lock,
+            _ => {
+                return MmError::err(ValidateTakerFundingSpendPreimageError::InvalidPreimage(
+                    "Preimage must create the taker payment HTLC first".into(),
+                ))
+            },
+        };
+
+        let expected = self
+            .gen_taker_funding_spend_msgs(gen_args, time_lock)
+            .map_to_mm(ValidateTakerFundingSpendPreimageError::TxGenError)?;
+        if msgs != expected {
+            return MmError::err(ValidateTakerFundingSpendPreimageError::InvalidPreimage(format!(
+                "Preimage messages {:?} don't match the expected {:?}",
+                msgs, expected
+            )));
+        }
+
+        // Taker payment must expire before the maker payment, which is locked until the middle of
+        // the taker payment and the taker funding lock times.
+        let maker_payment_time_lock = gen_args.taker_payment_time_lock
+            + gen_args
+                .funding_time_lock
+                .saturating_sub(gen_args.taker_payment_time_lock)
+                / 2;
+        let max_time_lock = self.estimate_blocks_from_duration(maker_payment_time_lock.saturating_sub(now_sec()));
+        if (time_lock as i64) < MIN_TIME_LOCK || (time_lock as i64) > max_time_lock {
+            return MmError::err(ValidateTakerFundingSpendPreimageError::LocktimeOverflow(format!(
+                "Taker payment time lock {} must be in between {} and {} blocks",
+                time_lock, MIN_TIME_LOCK, max_time_lock
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Broadcasts the funding spend preimage messages together with the claims of the funding HTLCs,
+    /// so the funds claimed to the taker's address are moved to the taker payment within the same transaction.
+    pub(crate) async fn sign_and_send_taker_funding_spend_impl<Coin>(
+        &self,
+        preimage: &TxPreimageWithSig<Coin>,
+        args: &GenTakerFundingSpendArgs<'_, Coin>,
+        swap_unique_data: &[u8],
+    ) -> Result<CosmosTransaction, TransactionErr>
+    where
+        Coin: CoinAssocTypes<Tx = CosmosTransaction, Preimage = CosmosTransaction> + ?Sized,
+    {
+        let mut msgs = try_tx_s!(self.claim_taker_funding_msgs(args.funding_tx, swap_unique_data));
+        msgs.extend(try_tx_s!(preimage_msgs(&preimage.preimage)));
+        self.send_swap_msgs(msgs).await
+    }
+
+    /// Taker payment HTLC is claimed by maker directly, so taker only confirms it with the signature.
+    pub(crate) fn gen_taker_payment_spend_preimage_impl<Coin>(
+        &self,
+        args: &GenTakerPaymentSpendArgs<'_, Coin>,
+        swap_unique_data: &[u8],
+    ) -> GenPreimageResult<Coin>
+    where
+        Coin: CoinAssocTypes<Tx = CosmosTransaction, Preimage = CosmosTransaction, Sig = Signature> + ?Sized,
+    {
+        let signature = self.sign_swap_data(&args.taker_tx.tx_hex(), swap_unique_data)?;
+        Ok(TxPreimageWithSig {
+            preimage: args.taker_tx.clone(),
+            signature,
+        })
+    }
+
+    /// Checks the taker payment spend preimage is signed by taker, and the taker payment pays the dex fee and
+    /// creates HTLC locked to maker with the maker secret hash, the expected amount and time lock.
+    fn check_taker_payment_spend_preimage<Coin>(
+        &self,
+        gen_args: &GenTakerPaymentSpendArgs<'_, Coin>,
+        preimage: &TxPreimageWithSig<Coin>,
+        denom: &Denom,
+        decimals: u8,
+    ) -> MmResult<MsgCreateHtlc, ValidateTakerPaymentSpendPreimageError>
+    where
+        Coin: CoinAssocTypes<Tx = CosmosTransaction, Pubkey = Public, Preimage = CosmosTransaction, Sig = Signature>
+            + ?Sized,
+    {
+        let taker_tx_bytes = gen_args.taker_tx.tx_hex();
+        if preimage.preimage.tx_hex() != taker_tx_bytes {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidPreimage(
+                "Preimage doesn't match the taker payment".into(),
+            ));
+        }
+
+        let is_signed_by_taker = verify_swap_data_sig(&taker_tx_bytes, &preimage.signature, gen_args.taker_pub)
+            .map_to_mm(ValidateTakerPaymentSpendPreimageError::SignatureVerificationFailure)?;
+        if !is_signed_by_taker {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidTakerSignature);
+        }
+
+        let tx = cosmrs::Tx::from_bytes(&taker_tx_bytes)
+            .map_to_mm(|e| ValidateTakerPaymentSpendPreimageError::InvalidPreimage(e.to_string()))?;
+        let htlc = create_htlc_msg_locked_with(&tx, gen_args.secret_hash)
+            .map_to_mm(ValidateTakerPaymentSpendPreimageError::InvalidPreimage)?;
+
+        let taker = self
+            .account_id_from_pubkey(gen_args.taker_pub)
+            .map_to_mm(ValidateTakerPaymentSpendPreimageError::TxGenError)?;
+        let maker = self
+            .account_id_from_pubkey(gen_args.maker_pub)
+            .map_to_mm(ValidateTakerPaymentSpendPreimageError::TxGenError)?;
+        let min_amount = sat_from_big_decimal(&(&gen_args.trading_amount + &gen_args.premium_amount), decimals)
+            .map_to_mm(|e| ValidateTakerPaymentSpendPreimageError::TxGenError(e.to_string()))?;
+        let amount = coin_amount_as_u64(&htlc.amount, denom)
+            .map_to_mm(ValidateTakerPaymentSpendPreimageError::InvalidPreimage)?;
+        if htlc.sender != taker || htlc.to != maker || amount < min_amount {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidPreimage(format!(
+                "Incorrect CreateHtlc message {:?}, expected sender {}, receiver {} and amount at least {}",
+                htlc, taker, maker, min_amount
+            )));
+        }
+
+        // HTLC time lock is set in blocks relative to the creation height, so it must be at least
+        // the number of blocks remaining until the taker payment lock time.
+        let min_time_lock = self.estimate_blocks_from_duration(gen_args.time_lock.saturating_sub(now_sec()));
+        if (htlc.time_lock as i64) < min_time_lock {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::LocktimeOverflow(format!(
+                "HTLC time lock {} is less than {} blocks",
+                htlc.time_lock, min_time_lock
+            )));
+        }
+
+        let dex_fee_amount = sat_from_big_decimal(&gen_args.dex_fee_amount, decimals)
+            .map_to_mm(|e| ValidateTakerPaymentSpendPreimageError::TxGenError(e.to_string()))?;
+        if dex_fee_amount > 0 {
+            let dex_fee_address = self
+                .account_id_from_pubkey(gen_args.dex_fee_pub)
+                .map_to_mm(ValidateTakerPaymentSpendPreimageError::TxGenError)?;
+            let expected_amount = vec![CosmosCoin {
+                denom: denom.clone(),
+                amount: dex_fee_amount.into(),
+            }];
+            let pays_dex_fee = tx
+                .body
+                .messages
+                .iter()
+                .filter_map(|msg| MsgSend::from_any(msg).ok())
+                .any(|send| {
+                    send.from_address == taker && send.to_address == dex_fee_address && send.amount == expected_amount
+                });
+            if !pays_dex_fee {
+                return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidPreimage(format!(
+                    "Taker payment doesn't send {:?} dex fee to {}",
+                    expected_amount, dex_fee_address
+                )));
+            }
+        }
+
+        Ok(htlc)
+    }
+
+    pub(crate) async fn validate_taker_payment_spend_preimage_for_denom<Coin>(
+        &self,
+        gen_args: &GenTakerPaymentSpendArgs<'_, Coin>,
+        preimage: &TxPreimageWithSig<Coin>,
+        denom: Denom,
+        decimals: u8,
+    ) -> ValidateTakerPaymentSpendPreimageResult
+    where
+        Coin: CoinAssocTypes<Tx = CosmosTransaction, Pubkey = Public, Preimage = CosmosTransaction, Sig = Signature>
+            + ?Sized,
+    {
+        let htlc = self.check_taker_payment_spend_preimage(gen_args, preimage, &denom, decimals)?;
+
+        let taker_tx_bytes = gen_args.taker_tx.tx_hex();
+        let hash = hex::encode_upper(sha256(&taker_tx_bytes).as_slice());
+        let tx_from_rpc = self.request_tx(hash).await?;
+        if taker_tx_bytes != tx_from_rpc.encode_to_vec() {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidPreimage(
+                "Taker payment bytes don't match ones received from rpc".into(),
+            ));
+        }
+
+        let htlc_id = self
+            .htlc_id(&htlc)
+            .map_to_mm(ValidateTakerPaymentSpendPreimageError::InvalidPreimage)?;
+        let htlc_data = self
+            .query_htlc(htlc_id.clone())
+            .await?
+            .htlc
+            .or_mm_err(|| ValidateTakerPaymentSpendPreimageError::Rpc(format!("No HTLC data for {}", htlc_id)))?;
+        if htlc_data.state != HTLC_STATE_OPEN {
+            return MmError::err(ValidateTakerPaymentSpendPreimageError::InvalidPreimage(format!(
+                "Unexpected state {} of HTLC {}",
+                htlc_data.state, htlc_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Claims the HTLC created by `htlc_tx` and locked with the hash of the `secret`.
+    pub(crate) async fn claim_htlc_for_tx(
+        &self,
+        htlc_tx: &CosmosTransaction,
+        secret: &[u8],
+    ) -> Result<CosmosTransaction, TransactionErr> {
+        let tx = try_tx_s!(cosmrs::Tx::from_bytes(&htlc_tx.tx_hex()));
+        let htlc = try_tx_s!(create_htlc_msg_locked_with(&tx, sha256(secret).as_slice()));
+        let htlc_id = try_tx_s!(self.htlc_id(&htlc));
+        let claim_htlc_tx = try_tx_s!(self.gen_claim_htlc_tx(htlc_id, secret));
+        self.send_swap_msgs(vec![claim_htlc_tx.msg_payload]).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tendermint::tendermint_coin_tests::{IRIS_TESTNET_HTLC_PAIR1_SEED, IRIS_TESTNET_RPC_URL};
+    use crate::tendermint::{TendermintConf, TendermintPrivKeyPolicy, TendermintProtocolInfo, GAS_LIMIT_DEFAULT};
+    use common::block_on;
+    use cosmrs::proto::cosmos::auth::v1beta1::BaseAccount;
+    use cosmrs::tx::Fee;
+    use crypto::privkey::key_pair_from_seed;
+    use mm2_number::BigDecimal;
+    use std::str::FromStr;
+
+    const TAKER_SEED: &str = IRIS_TESTNET_HTLC_PAIR1_SEED;
+    const MAKER_SEED: &str = "iris test2 seed";
+    const UNIQUE_DATA: &[u8] = &[1; 16];
+
+    fn iris_test_coin(seed: &str) -> TendermintCoin {
+        let ctx = mm2_core::mm_ctx::MmCtxBuilder::default().into_mm_arc();
+        let conf = TendermintConf {
+            avg_blocktime: 5,
+            derivation_path: None,
+        };
+        let protocol_info = TendermintProtocolInfo {
+            decimals: 6,
+            denom: String::from("unyan"),
+            account_prefix: String::from("iaa"),
+            chain_id: String::from("nyancat-9"),
+            gas_price: None,
+            chain_registry_name: None,
+        };
+        let key_pair = key_pair_from_seed(seed).unwrap();
+        let priv_key_policy = TendermintPrivKeyPolicy::Iguana(key_pair.private().secret);
+
+        block_on(TendermintCoin::init(
+            &ctx,
+            "IRIS-TEST".to_string(),
+            conf,
+            protocol_info,
+            vec![IRIS_TESTNET_RPC_URL.to_string()],
+            false,
+            priv_key_policy,
+        ))
+        .unwrap()
+    }
+
+    fn htlc_pubkey(coin: &TendermintCoin) -> Public { *coin.derive_htlc_key_pair(UNIQUE_DATA).unwrap().public() }
+
+    /// Signs the transaction offline, as it would be broadcasted by `coin`.
+    fn signed_tx(coin: &TendermintCoin, msgs: Vec<Any>) -> CosmosTransaction {
+        let fee = Fee::from_amount_and_gas(
+            CosmosCoin {
+                denom: coin.denom.clone(),
+                amount: 0u64.into(),
+            },
+            GAS_LIMIT_DEFAULT,
+        );
+        let priv_key = coin.priv_key_policy.activated_key_or_err().unwrap();
+        let tx_raw = coin
+            .msgs_to_signed_raw_tx(priv_key, BaseAccount::default(), msgs, fee, 100, TX_DEFAULT_MEMO.into())
+            .unwrap();
+        CosmosTransaction { data: tx_raw.into() }
+    }
+
+    fn funding_args(time_lock: u64, dex_fee_amount: BigDecimal) -> SendTakerFundingArgs<'static> {
+        SendTakerFundingArgs {
+            time_lock,
+            taker_secret_hash: &[1; 32],
+            maker_secret_hash: &[2; 32],
+            payment_time_lock: time_lock,
+            maker_pub: &[],
+            dex_fee_amount,
+            premium_amount: BigDecimal::from_str("0.1").unwrap(),
+            trading_amount: BigDecimal::from(1),
+            swap_unique_data: UNIQUE_DATA,
+        }
+    }
+
+    fn funding_tx(taker: &TendermintCoin, funding_time_lock: u64, dex_fee_amount: BigDecimal) -> CosmosTransaction {
+        let args = funding_args(funding_time_lock, dex_fee_amount);
+        let htlcs = taker
+            .gen_taker_funding_htlcs(&args, taker.denom.clone(), taker.decimals, 1000)
+            .unwrap();
+        let msgs = htlcs.iter().map(|htlc| htlc.to_any().unwrap()).collect();
+        signed_tx(taker, msgs)
+    }
+
+    #[test]
+    fn test_taker_funding_htlcs() {
+        let taker = iris_test_coin(TAKER_SEED);
+        let maker = iris_test_coin(MAKER_SEED);
+        let taker_pub = htlc_pubkey(&taker);
+        let time_lock = now_sec() + 3000;
+
+        let args = funding_args(time_lock, BigDecimal::from_str("0.01").unwrap());
+        let mut htlcs = taker
+            .gen_taker_funding_htlcs(&args, taker.denom.clone(), taker.decimals, 1000)
+            .unwrap();
+        assert_eq!(htlcs.len(), 2);
+        for htlc in htlcs.iter() {
+            assert_eq!(htlc.sender, taker.account_id);
+            assert_eq!(htlc.to, taker.account_id);
+            // Claiming the funding mustn't reveal the swap secrets
+            assert_ne!(htlc.hash_lock, hex::encode(args.taker_secret_hash));
+            assert_ne!(htlc.hash_lock, hex::encode(args.maker_secret_hash));
+        }
+        assert_ne!(htlcs[0].hash_lock, htlcs[1].hash_lock);
+
+        let dummy_tx = CosmosTransaction { data: TxRaw::default() };
+        let validate_args = ValidateTakerFundingArgs::<TendermintCoin> {
+            funding_tx: &dummy_tx,
+            time_lock,
+            taker_secret_hash: args.taker_secret_hash,
+            maker_secret_hash: args.maker_secret_hash,
+            payment_time_lock: time_lock,
+            other_pub: &taker_pub,
+            dex_fee_amount: args.dex_fee_amount.clone(),
+            premium_amount: args.premium_amount.clone(),
+            trading_amount: args.trading_amount.clone(),
+            swap_unique_data: UNIQUE_DATA,
+        };
+        maker
+            .check_taker_funding_htlcs(&htlcs, &validate_args, &maker.denom, maker.decimals)
+            .unwrap();
+
+        // The funding HTLC locked to maker could be claimed before the maker payment is sent
+        let mut locked_to_maker = htlcs.clone();
+        locked_to_maker[0].to = maker.account_id.clone();
+        let err = maker
+            .check_taker_funding_htlcs(&locked_to_maker, &validate_args, &maker.denom, maker.decimals)
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(err, ValidateTakerFundingError::InvalidDestinationOrAmount(_)));
+
+        let err = maker
+            .check_taker_funding_htlcs(&htlcs[..1], &validate_args, &maker.denom, maker.decimals)
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(err, ValidateTakerFundingError::WrongPaymentTx(_)));
+
+        htlcs[1].time_lock = 10;
+        let err = maker
+            .check_taker_funding_htlcs(&htlcs, &validate_args, &maker.denom, maker.decimals)
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(err, ValidateTakerFundingError::LocktimeOverflow(_)));
+
+        // Zero dex fee isn't locked
+        let args = funding_args(time_lock, BigDecimal::from(0));
+        let htlcs = taker
+            .gen_taker_funding_htlcs(&args, taker.denom.clone(), taker.decimals, 1000)
+            .unwrap();
+        assert_eq!(htlcs.len(), 1);
+    }
+
+    #[test]
+    fn test_taker_funding_spend() {
+        let taker = iris_test_coin(TAKER_SEED);
+        let maker = iris_test_coin(MAKER_SEED);
+        let taker_pub = htlc_pubkey(&taker);
+        let maker_pub = htlc_pubkey(&maker);
+        let now = now_sec();
+        let funding_tx = funding_tx(&taker, now + 3000, BigDecimal::from_str("0.01").unwrap());
+
+        let gen_args = GenTakerFundingSpendArgs::<TendermintCoin> {
+            funding_tx: &funding_tx,
+            maker_pub: &maker_pub,
+            taker_pub: &taker_pub,
+            funding_time_lock: now + 3000,
+            taker_secret_hash: &[1; 32],
+            taker_payment_time_lock: now + 1000,
+            maker_secret_hash: &[2; 32],
+        };
+        let preimage = maker
+            .gen_taker_funding_spend_preimage_impl(&gen_args, UNIQUE_DATA)
+            .unwrap();
+        taker
+            .validate_taker_funding_spend_preimage_impl(&gen_args, &preimage)
+            .unwrap();
+
+        let msgs = preimage_msgs(&preimage.preimage).unwrap();
+        assert_eq!(msgs.len(), 2);
+        let taker_payment = MsgCreateHtlc::from_any(&msgs[0]).unwrap();
+        assert_eq!(taker_payment.sender, taker.account_id);
+        assert_eq!(taker_payment.to, maker.account_id);
+        assert_eq!(taker_payment.hash_lock, hex::encode([2; 32]));
+        assert_eq!(taker_payment.amount[0].amount.to_string(), "1100000");
+        let dex_fee = MsgSend::from_any(&msgs[1]).unwrap();
+        assert_eq!(dex_fee.from_address, taker.account_id);
+        assert_eq!(dex_fee.amount[0].amount.to_string(), "10000");
+
+        // The funding spend claims the funding HTLCs within the same transaction
+        let claims = taker.claim_taker_funding_msgs(&funding_tx, UNIQUE_DATA).unwrap();
+        assert_eq!(claims.len(), 2);
+        let funding = cosmrs::Tx::from_bytes(&funding_tx.tx_hex()).unwrap();
+        for (claim, htlc) in claims.iter().zip(create_htlc_msgs(&funding).unwrap()) {
+            let claim = MsgClaimHtlc::from_any(claim).unwrap();
+            assert_eq!(claim.id, taker.htlc_id(&htlc).unwrap());
+            let secret = hex::decode(&claim.secret).unwrap();
+            assert_eq!(hex::encode(sha256(&secret).as_slice()), htlc.hash_lock);
+        }
+
+        // Preimage signed by another key
+        let mut invalid = maker
+            .gen_taker_funding_spend_preimage_impl(&gen_args, UNIQUE_DATA)
+            .unwrap();
+        invalid.signature = taker
+            .sign_swap_data(&invalid.preimage.data.body_bytes, UNIQUE_DATA)
+            .unwrap();
+        let err = taker
+            .validate_taker_funding_spend_preimage_impl(&gen_args, &invalid)
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(
+            err,
+            ValidateTakerFundingSpendPreimageError::InvalidMakerSignature
+        ));
+
+        // Preimage paying to another secret hash
+        let other_args = GenTakerFundingSpendArgs::<TendermintCoin> {
+            maker_secret_hash: &[3; 32],
+            ..gen_args
+        };
+        let invalid = maker
+            .gen_taker_funding_spend_preimage_impl(&other_args, UNIQUE_DATA)
+            .unwrap();
+        let err = taker
+            .validate_taker_funding_spend_preimage_impl(&gen_args, &invalid)
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(
+            err,
+            ValidateTakerFundingSpendPreimageError::InvalidPreimage(_)
+        ));
+
+        // Taker payment locked after the maker payment expiration
+        let other_args = GenTakerFundingSpendArgs::<TendermintCoin> {
+            taker_payment_time_lock: now + 5000,
+            ..gen_args
+        };
+        let invalid = maker
+            .gen_taker_funding_spend_preimage_impl(&other_args, UNIQUE_DATA)
+            .unwrap();
+        let err = taker
+            .validate_taker_funding_spend_preimage_impl(&gen_args, &invalid)
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(
+            err,
+            ValidateTakerFundingSpendPreimageError::LocktimeOverflow(_)
+        ));
+    }
+
+    #[test]
+    fn test_taker_payment_spend_preimage() {
+        let taker = iris_test_coin(TAKER_SEED);
+        let maker = iris_test_coin(MAKER_SEED);
+        let taker_pub = htlc_pubkey(&taker);
+        let maker_pub = htlc_pubkey(&maker);
+        let now = now_sec();
+        let funding_tx = funding_tx(&taker, now + 3000, BigDecimal::from_str("0.01").unwrap());
+
+        let funding_spend_args = GenTakerFundingSpendArgs::<TendermintCoin> {
+            funding_tx: &funding_tx,
+            maker_pub: &maker_pub,
+            taker_pub: &taker_pub,
+            funding_time_lock: now + 3000,
+            taker_secret_hash: &[1; 32],
+            taker_payment_time_lock: now + 1000,
+            maker_secret_hash: &[2; 32],
+        };
+        let funding_spend = maker
+            .gen_taker_funding_spend_preimage_impl(&funding_spend_args, UNIQUE_DATA)
+            .unwrap();
+        let mut msgs = taker.claim_taker_funding_msgs(&funding_tx, UNIQUE_DATA).unwrap();
+        msgs.extend(preimage_msgs(&funding_spend.preimage).unwrap());
+        let taker_payment = signed_tx(&taker, msgs);
+
+        let gen_args = |dex_fee_amount: &str, trading_amount: u64| GenTakerPaymentSpendArgs::<TendermintCoin> {
+            taker_tx: &taker_payment,
+            time_lock: now + 1000,
+            secret_hash: &[2; 32],
+            maker_pub: &maker_pub,
+            taker_pub: &taker_pub,
+            dex_fee_pub: &DEX_FEE_ADDR_RAW_PUBKEY,
+            dex_fee_amount: BigDecimal::from_str(dex_fee_amount).unwrap(),
+            premium_amount: Default::default(),
+            trading_amount: BigDecimal::from(trading_amount),
+        };
+        let preimage = taker
+            .gen_taker_payment_spend_preimage_impl(&gen_args("0.01", 1), UNIQUE_DATA)
+            .unwrap();
+        let htlc = maker
+            .check_taker_payment_spend_preimage(&gen_args("0.01", 1), &preimage, &maker.denom, maker.decimals)
+            .unwrap();
+        assert_eq!(htlc.to, maker.account_id);
+
+        let invalid = maker
+            .gen_taker_payment_spend_preimage_impl(&gen_args("0.01", 1), UNIQUE_DATA)
+            .unwrap();
+        let err = maker
+            .check_taker_payment_spend_preimage(&gen_args("0.01", 1), &invalid, &maker.denom, maker.decimals)
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(
+            err,
+            ValidateTakerPaymentSpendPreimageError::InvalidTakerSignature
+        ));
+
+        // Taker payment must pay the dex fee
+        let err = maker
+            .check_taker_payment_spend_preimage(&gen_args("0.02", 1), &preimage, &maker.denom, maker.decimals)
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(
+            err,
+            ValidateTakerPaymentSpendPreimageError::InvalidPreimage(_)
+        ));
+
+        let err = maker
+            .check_taker_payment_spend_preimage(&gen_args("0.01", 2), &preimage, &maker.denom, maker.decimals)
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(
+            err,
+            ValidateTakerPaymentSpendPreimageError::InvalidPreimage(_)
+        ));
+    }
+
+    #[test]
+    fn test_refund_taker_funding_secret_msgs() {
+        let taker = iris_test_coin(TAKER_SEED);
+        let maker = iris_test_coin(MAKER_SEED);
+        let funding_tx = funding_tx(&taker, now_sec() + 3000, BigDecimal::from_str("0.01").unwrap());
+
+        let claims = taker.claim_taker_funding_msgs(&funding_tx, UNIQUE_DATA).unwrap();
+        for claim in claims.iter() {
+            let claim = MsgClaimHtlc::from_any(claim).unwrap();
+            assert_eq!(claim.sender, taker.account_id);
+        }
+
+        // The funding secrets are specific to the swap and to the taker's key
+        assert!(taker.claim_taker_funding_msgs(&funding_tx, &[2; 16]).is_err());
+        assert!(maker.claim_taker_funding_msgs(&funding_tx, UNIQUE_DATA).is_err());
+    }
+
+    #[test]
+    fn test_refund_taker_funding_timelock() {
+        let coin = iris_test_coin(TAKER_SEED);
+
+        // https://nyancat.iobscan.io/#/tx?txHash=BD1A76F43E8E2C7A1104EE363D63455CD50C76F2BFE93B703235F0A973061297
+        let refunded_tx =
+            block_on(coin.request_tx("BD1A76F43E8E2C7A1104EE363D63455CD50C76F2BFE93B703235F0A973061297".into()))
+                .unwrap();
+        let refund = block_on(coin.check_htlc_auto_refunded(&refunded_tx.encode_to_vec())).unwrap();
+        let expected = TransactionEnum::CosmosTransaction(CosmosTransaction { data: TxRaw::default() });
+        assert_eq!(refund, expected);
+
+        // https://nyancat.iobscan.io/#/tx?txHash=2DB382CE3D9953E4A94957B475B0E8A98F5B6DDB32D6BF0F6A765D949CF4A727
+        let claimed_tx =
+            block_on(coin.request_tx("2DB382CE3D9953E4A94957B475B0E8A98F5B6DDB32D6BF0F6A765D949CF4A727".into()))
+                .unwrap();
+        block_on(coin.check_htlc_auto_refunded(&claimed_tx.encode_to_vec())).unwrap_err();
+    }
+}
@@ -3,8 +3,8 @@
 use super::{CoinBalance, HistorySyncState, MarketCoinOps, MmCoin, RawTransactionFut, RawTransactionRequest, SwapOps,
             TradeFee, TransactionEnum, TransactionFut};
 use crate::{coin_errors::MyAddressError, BalanceFut, CanRefundHtlc, CheckIfMyPaymentSentArgs, CoinAssocTypes,
-            CoinFutSpawner, ConfirmPaymentInput, FeeApproxStage, FoundSwapTxSpend, GenPreimageResult,
-            GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs, MakerSwapTakerCoin, MmCoinEnum,
+            CoinFutSpawner, ConfirmPaymentInput, DeriveHtlcResult, FeeApproxStage, FoundSwapTxSpend,
+            GenPreimageResult, GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs, MakerSwapTakerCoin, MmCoinEnum,
             NegotiateSwapContractAddrErr, PaymentInstructionArgs, PaymentInstructions, PaymentInstructionsErr,
             RefundFundingSecretArgs, RefundPaymentArgs, RefundResult, SearchForSwapTxSpendInput,
             SendMakerPaymentSpendPreimageInput, SendPaymentArgs, SendTakerFundingArgs, SignatureResult,
@@ -184,9 +184,9 @@ impl SwapOps for TestCoin {
         unimplemented!()
     }
 
-    fn derive_htlc_key_pair(&self, _swap_unique_data: &[u8]) -> KeyPair { unimplemented!() }
+    fn derive_htlc_key_pair(&self, _swap_unique_data: &[u8]) -> DeriveHtlcResult<KeyPair> { unimplemented!() }
 
-    fn derive_htlc_pubkey(&self, _swap_unique_data: &[u8]) -> Vec<u8> { unimplemented!() }
+    fn derive_htlc_pubkey(&self, _swap_unique_data: &[u8]) -> DeriveHtlcResult<Vec<u8>> { unimplemented!() }
 
     fn can_refund_htlc(&self, locktime: u64) -> Box<dyn Future<Item = CanRefundHtlc, Error = String> + Send + '_> {
         unimplemented!()
@@ -398,9 +398,9 @@ impl ToBytes for TestPubkey {
 pub struct TestTx {}
 
 impl Transaction for TestTx {
-    fn tx_hex(&self) -> Vec<u8> { todo!() }
+    fn tx_hex(&self) -> Vec<u8> { vec![] }
 
-    fn tx_hash(&self) -> BytesJson { todo!() }
+    fn tx_hash(&self) -> BytesJson { BytesJson::default() }
 }
 
 pub struct TestPreimage {}
@@ -425,13 +425,15 @@ impl CoinAssocTypes for TestCoin {
     type Sig = TestSig;
     type SigParseError = String;
 
-    fn parse_pubkey(&self, pubkey: &[u8]) -> Result<Self::Pubkey, Self::PubkeyParseError> { unimplemented!() }
+    fn parse_pubkey(&self, _pubkey: &[u8]) -> Result<Self::Pubkey, Self::PubkeyParseError> { Ok(TestPubkey {}) }
 
-    fn parse_tx(&self, tx: &[u8]) -> Result<Self::Tx, Self::TxParseError> { unimplemented!() }
+    fn parse_tx(&self, _tx: &[u8]) -> Result<Self::Tx, Self::TxParseError> { Ok(TestTx {}) }
 
-    fn parse_preimage(&self, preimage: &[u8]) -> Result<Self::Preimage, Self::PreimageParseError> { todo!() }
+    fn parse_preimage(&self, _preimage: &[u8]) -> Result<Self::Preimage, Self::PreimageParseError> {
+        Ok(TestPreimage {})
+    }
 
-    fn parse_signature(&self, sig: &[u8]) -> Result<Self::Sig, Self::SigParseError> { todo!() }
+    fn parse_signature(&self, _sig: &[u8]) -> Result<Self::Sig, Self::SigParseError> { Ok(TestSig {}) }
 }
 
 #[async_trait]
@@ -505,5 +507,5 @@ impl SwapOpsV2 for TestCoin {
         unimplemented!()
     }
 
-    fn derive_htlc_pubkey_v2(&self, swap_unique_data: &[u8]) -> Self::Pubkey { todo!() }
+    fn derive_htlc_pubkey_v2(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Self::Pubkey> { todo!() }
 }
@@ -27,11 +27,13 @@ pub(crate) mod bchd_grpc;
 #[rustfmt::skip]
 #[path = "utxo/pb.rs"]
 mod bchd_pb;
+pub mod frozen_outpoints_storage;
 pub mod qtum;
 pub mod rpc_clients;
 pub mod slp;
 pub mod spv;
 pub mod swap_proto_v2_scripts;
+mod utxo_balance_events;
 pub mod utxo_block_header_storage;
 pub mod utxo_builder;
 pub mod utxo_common;
@@ -65,7 +67,7 @@ pub use keys::{Address, AddressFormat as UtxoAddressFormat, AddressHashEnum, Key
                Type as ScriptType};
 #[cfg(not(target_arch = "wasm32"))]
 use lightning_invoice::Currency as LightningCurrency;
-use mm2_core::mm_ctx::MmArc;
+use mm2_core::mm_ctx::{MmArc, MmWeak};
 use mm2_err_handle::prelude::*;
 use mm2_metrics::MetricsArc;
 use mm2_number::BigDecimal;
@@ -101,9 +103,9 @@ use self::rpc_clients::{electrum_script_hash, ElectrumClient, ElectrumRpcRequest
                         NativeClient, UnspentInfo, UnspentMap, UtxoRpcClientEnum, UtxoRpcError, UtxoRpcFut,
                         UtxoRpcResult};
 use super::{big_decimal_from_sat_unsigned, BalanceError, BalanceFut, BalanceResult, CoinBalance, CoinFutSpawner,
-            CoinsContext, DerivationMethod, FeeApproxStage, FoundSwapTxSpend, HistorySyncState, KmdRewardsDetails,
-            MarketCoinOps, MmCoin, NumConversError, NumConversResult, PrivKeyActivationPolicy, PrivKeyPolicy,
-            PrivKeyPolicyNotAllowed, RawTransactionFut, RawTransactionRequest, RawTransactionResult,
+            CoinsContext, DerivationMethod, DeriveHtlcResult, FeeApproxStage, FoundSwapTxSpend, HistorySyncState,
+            KmdRewardsDetails, MarketCoinOps, MmCoin, NumConversError, NumConversResult, PrivKeyActivationPolicy,
+            PrivKeyPolicy, PrivKeyPolicyNotAllowed, RawTransactionFut, RawTransactionRequest, RawTransactionResult,
             RpcTransportEventHandler, RpcTransportEventHandlerShared, TradeFee, TradePreimageError, TradePreimageFut,
             TradePreimageResult, Transaction, TransactionDetails, TransactionEnum, TransactionErr,
             UnexpectedDerivationMethod, VerificationError, WithdrawError, WithdrawRequest};
@@ -136,6 +138,8 @@ const KMD_MTP_BLOCK_COUNT: NonZeroU64 = unsafe { NonZeroU64::new_unchecked(11u64
 const DEFAULT_DYNAMIC_FEE_VOLATILITY_PERCENT: f64 = 0.5;
 const DEFAULT_GAP_LIMIT: u32 = 20;
 
+/// The outputs frozen by the user mapped to their values.
+pub type FrozenOutPoints = HashMap<OutPoint, BigDecimal>;
 pub type GenerateTxResult = Result<(TransactionInputSigner, AdditionalTxData), MmError<GenerateTxError>>;
 pub type HistoryUtxoTxMap = HashMap<H256Json, HistoryUtxoTx>;
 pub type MatureUnspentMap = HashMap<Address, MatureUnspentList>;
@@ -306,6 +310,31 @@ impl From<UnspentInfo> for CachedUnspentInfo {
     }
 }
 
+/// The [`OutPoint`] as it's exposed to the RPC users, i.e. with the transaction hash in the display byte order.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct UtxoOutPoint {
+    pub tx_hash: H256Json,
+    pub index: u32,
+}
+
+impl From<OutPoint> for UtxoOutPoint {
+    fn from(outpoint: OutPoint) -> UtxoOutPoint {
+        UtxoOutPoint {
+            tx_hash: outpoint.hash.reversed().into(),
+            index: outpoint.index,
+        }
+    }
+}
+
+impl From<UtxoOutPoint> for OutPoint {
+    fn from(outpoint: UtxoOutPoint) -> OutPoint {
+        OutPoint {
+            hash: outpoint.tx_hash.reversed().into(),
+            index: outpoint.index,
+        }
+    }
+}
+
 impl From<CachedUnspentInfo> for UnspentInfo {
     fn from(cached: CachedUnspentInfo) -> UnspentInfo {
         UnspentInfo {
@@ -385,6 +414,16 @@ impl RecentlySpentOutPoints {
         }
     }
 
+    /// Forgets the outputs of the transaction that is replaced and won't be mined,
+    /// so they are not returned as spendable instead of the inputs it spent.
+    pub fn remove_outputs_of(&mut self, tx_hash: &H256) {
+        for outputs in self.input_to_output_map.values_mut() {
+            outputs.retain(|output| output.outpoint.hash != *tx_hash);
+        }
+        self.output_to_input_map
+            .retain(|output, _inputs| output.outpoint.hash != *tx_hash);
+    }
+
     pub fn replace_spent_outputs_with_cache(&self, mut outputs: HashSet<UnspentInfo>) -> HashSet<UnspentInfo> {
         let mut replacement_unspents = HashSet::new();
         outputs.retain(|unspent| {
@@ -551,6 +590,10 @@ pub struct UtxoCoinConf {
     /// relay fee amount instead of calculated
     /// https://github.com/KomodoPlatform/atomicDEX-API/issues/617
     pub force_min_relay_fee: bool,
+    /// Whether the withdrawals and the other wallet transactions signal BIP125 replaceability,
+    /// so their fee can be bumped later, see [`UtxoTxBuilder::replaceable`].
+    /// The swap transactions never signal it, since replacing them changes their hashes.
+    pub signal_rbf: bool,
     /// Block count for median time past calculation
     pub mtp_block_count: NonZeroU64,
     pub estimate_fee_mode: Option<EstimateFeeMode>,
@@ -610,6 +653,8 @@ pub struct UtxoCoinFields {
     /// This abortable system is used to spawn coin's related futures that should be aborted on coin deactivation
     /// and on [`MmArc::stop`].
     pub abortable_system: AbortableQueue,
+    /// The weak reference to the MM context, used by the background streaming of the coin events.
+    pub ctx: MmWeak,
 }
 
 #[derive(Debug, Display)]
@@ -1060,6 +1105,7 @@ pub trait GetUtxoListOps {
     /// + `RecentlySpentOutPoints` MutexGuard for further interaction (e.g. to add new transaction to it).
     /// The function uses either [`GetUtxoListOps::get_all_unspent_ordered_list`] or [`GetUtxoListOps::get_mature_unspent_ordered_list`]
     /// depending on the coin configuration.
+    /// The outputs frozen by the user are excluded.
     async fn get_unspent_ordered_list(
         &self,
         address: &Address,
@@ -1097,6 +1143,7 @@ pub trait GetUtxoMapOps {
     /// + `RecentlySpentOutPoints` MutexGuard for further interaction (e.g. to add new transaction to it).
     /// The function uses either [`GetUtxoMapOps::get_all_unspent_ordered_map`] or [`GetUtxoMapOps::get_mature_unspent_ordered_map`]
     /// depending on the coin configuration.
+    /// The outputs frozen by the user are excluded.
     async fn get_unspent_ordered_map(
         &self,
         addresses: Vec<Address>,
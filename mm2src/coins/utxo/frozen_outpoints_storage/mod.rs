@@ -0,0 +1,123 @@
+//! Persists the outputs frozen by the user, so they stay excluded from the automatic coin selection after a restart.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod sql_frozen_outpoints_storage;
+#[cfg(not(target_arch = "wasm32"))]
+use sql_frozen_outpoints_storage as storage;
+
+#[cfg(target_arch = "wasm32")] mod wasm_frozen_outpoints_storage;
+#[cfg(target_arch = "wasm32")]
+use wasm_frozen_outpoints_storage as storage;
+#[cfg(target_arch = "wasm32")]
+pub use wasm_frozen_outpoints_storage::FrozenOutPointsDb;
+
+use crate::utxo::{FrozenOutPoints, UtxoOutPoint};
+use chain::OutPoint;
+use derive_more::Display;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use std::str::FromStr;
+
+pub type FrozenOutPointsStorageResult<T> = MmResult<T, FrozenOutPointsStorageError>;
+
+#[derive(Debug, Display)]
+pub enum FrozenOutPointsStorageError {
+    #[display(fmt = "Error deserializing a frozen output: {}", _0)]
+    ErrorDeserializing(String),
+    #[display(fmt = "Error loading the frozen outputs: {}", _0)]
+    ErrorLoading(String),
+    #[display(fmt = "Error saving the frozen outputs: {}", _0)]
+    ErrorSaving(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+/// A frozen output as it's stored in the database.
+struct FrozenOutPointItem {
+    tx_hash: String,
+    index: u32,
+    value: String,
+}
+
+impl FrozenOutPointItem {
+    fn from_frozen(outpoint: &OutPoint, value: &BigDecimal) -> FrozenOutPointItem {
+        FrozenOutPointItem {
+            tx_hash: UtxoOutPoint::from(*outpoint).tx_hash.to_string(),
+            index: outpoint.index,
+            value: value.to_string(),
+        }
+    }
+
+    fn into_frozen(self) -> FrozenOutPointsStorageResult<(OutPoint, BigDecimal)> {
+        let tx_hash = self
+            .tx_hash
+            .parse()
+            .map_to_mm(|e| FrozenOutPointsStorageError::ErrorDeserializing(format!("{:?}", e)))?;
+        let value = BigDecimal::from_str(&self.value)
+            .map_to_mm(|e| FrozenOutPointsStorageError::ErrorDeserializing(e.to_string()))?;
+        let outpoint = UtxoOutPoint {
+            tx_hash,
+            index: self.index,
+        };
+        Ok((OutPoint::from(outpoint), value))
+    }
+}
+
+/// Loads the outputs of the `ticker` coin frozen by the user.
+pub async fn load_frozen_outpoints(ctx: &MmArc, ticker: &str) -> FrozenOutPointsStorageResult<FrozenOutPoints> {
+    storage::load_frozen_outpoints(ctx, ticker.to_owned())
+        .await?
+        .into_iter()
+        .map(FrozenOutPointItem::into_frozen)
+        .collect()
+}
+
+/// Replaces the stored outputs of the `ticker` coin frozen by the user with the given ones.
+pub async fn save_frozen_outpoints(
+    ctx: &MmArc,
+    ticker: &str,
+    frozen_outpoints: &FrozenOutPoints,
+) -> FrozenOutPointsStorageResult<()> {
+    let items = frozen_outpoints
+        .iter()
+        .map(|(outpoint, value)| FrozenOutPointItem::from_frozen(outpoint, value))
+        .collect();
+    storage::save_frozen_outpoints(ctx, ticker.to_owned(), items).await
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use common::block_on;
+    use mm2_test_helpers::for_tests::mm_ctx_with_custom_db;
+    use primitives::hash::H256;
+
+    #[test]
+    fn test_save_load_frozen_outpoints() {
+        let ctx = mm_ctx_with_custom_db();
+        let first = OutPoint {
+            hash: H256::from([1; 32]),
+            index: 0,
+        };
+        let second = OutPoint {
+            hash: H256::from([2; 32]),
+            index: 3,
+        };
+
+        let mut frozen = FrozenOutPoints::new();
+        frozen.insert(first, "0.5".parse().unwrap());
+        frozen.insert(second, "1.00000001".parse().unwrap());
+        block_on(save_frozen_outpoints(&ctx, "RICK", &frozen)).unwrap();
+        block_on(save_frozen_outpoints(&ctx, "MORTY", &frozen)).unwrap();
+        assert_eq!(block_on(load_frozen_outpoints(&ctx, "RICK")).unwrap(), frozen);
+
+        // The stored outputs are replaced, and the other coins aren't affected.
+        frozen.remove(&first);
+        block_on(save_frozen_outpoints(&ctx, "RICK", &frozen)).unwrap();
+        assert_eq!(block_on(load_frozen_outpoints(&ctx, "RICK")).unwrap(), frozen);
+        assert_eq!(block_on(load_frozen_outpoints(&ctx, "MORTY")).unwrap().len(), 2);
+
+        assert!(block_on(load_frozen_outpoints(&ctx, "QTUM")).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use super::{FrozenOutPointItem, FrozenOutPointsStorageError, FrozenOutPointsStorageResult};
+use common::async_blocking;
+use db_common::sqlite::rusqlite::{params, Connection, Error as SqlError, Row};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+
+const CREATE_FROZEN_OUTPOINTS_TABLE: &str = "CREATE TABLE IF NOT EXISTS frozen_outpoints (
+    coin VARCHAR(255) NOT NULL,
+    tx_hash VARCHAR(255) NOT NULL,
+    output_index INTEGER NOT NULL,
+    value VARCHAR(255) NOT NULL,
+    UNIQUE(coin, tx_hash, output_index)
+);";
+
+const SELECT_FROZEN_OUTPOINTS: &str = "SELECT tx_hash, output_index, value FROM frozen_outpoints WHERE coin = ?1;";
+
+const DELETE_FROZEN_OUTPOINTS: &str = "DELETE FROM frozen_outpoints WHERE coin = ?1;";
+
+const INSERT_FROZEN_OUTPOINT: &str =
+    "INSERT INTO frozen_outpoints (coin, tx_hash, output_index, value) VALUES (?1, ?2, ?3, ?4);";
+
+impl From<SqlError> for FrozenOutPointsStorageError {
+    fn from(e: SqlError) -> Self {
+        let error = e.to_string();
+        match e {
+            SqlError::FromSqlConversionFailure(_, _, _)
+            | SqlError::IntegralValueOutOfRange(_, _)
+            | SqlError::InvalidColumnIndex(_)
+            | SqlError::InvalidColumnType(_, _, _) => FrozenOutPointsStorageError::ErrorDeserializing(error),
+            _ => FrozenOutPointsStorageError::Internal(error),
+        }
+    }
+}
+
+fn frozen_outpoint_from_row(row: &Row<'_>) -> Result<FrozenOutPointItem, SqlError> {
+    Ok(FrozenOutPointItem {
+        tx_hash: row.get(0)?,
+        index: row.get(1)?,
+        value: row.get(2)?,
+    })
+}
+
+fn init_table(conn: &Connection) -> FrozenOutPointsStorageResult<()> {
+    conn.execute(CREATE_FROZEN_OUTPOINTS_TABLE, [])?;
+    Ok(())
+}
+
+pub(super) async fn load_frozen_outpoints(
+    ctx: &MmArc,
+    ticker: String,
+) -> FrozenOutPointsStorageResult<Vec<FrozenOutPointItem>> {
+    let ctx = ctx.clone();
+    async_blocking(move || {
+        let conn = ctx.sqlite_conn_opt().or_mm_err(|| {
+            FrozenOutPointsStorageError::Internal("'MmCtx::sqlite_connection' is not initialized".to_owned())
+        })?;
+        init_table(&conn)?;
+
+        let mut statement = conn.prepare(SELECT_FROZEN_OUTPOINTS)?;
+        let items = statement
+            .query_map(params![ticker], frozen_outpoint_from_row)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_to_mm(|e| FrozenOutPointsStorageError::ErrorLoading(e.to_string()))?;
+        Ok(items)
+    })
+    .await
+}
+
+pub(super) async fn save_frozen_outpoints(
+    ctx: &MmArc,
+    ticker: String,
+    items: Vec<FrozenOutPointItem>,
+) -> FrozenOutPointsStorageResult<()> {
+    let ctx = ctx.clone();
+    async_blocking(move || {
+        let mut conn = ctx.sqlite_conn_opt().or_mm_err(|| {
+            FrozenOutPointsStorageError::Internal("'MmCtx::sqlite_connection' is not initialized".to_owned())
+        })?;
+        init_table(&conn)?;
+
+        let sql_transaction = conn.transaction()?;
+        sql_transaction.execute(DELETE_FROZEN_OUTPOINTS, params![ticker])?;
+        for item in items {
+            sql_transaction
+                .execute(INSERT_FROZEN_OUTPOINT, params![
+                    ticker,
+                    item.tx_hash,
+                    item.index,
+                    item.value
+                ])
+                .map_to_mm(|e| FrozenOutPointsStorageError::ErrorSaving(e.to_string()))?;
+        }
+        sql_transaction.commit()?;
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,110 @@
+use super::{FrozenOutPointItem, FrozenOutPointsStorageError, FrozenOutPointsStorageResult};
+use crate::CoinsContext;
+use async_trait::async_trait;
+use mm2_core::mm_ctx::MmArc;
+use mm2_db::indexed_db::{DbIdentifier, DbInstance, DbTransactionError, DbUpgrader, IndexedDb, IndexedDbBuilder,
+                         InitDbError, InitDbResult, OnUpgradeResult, TableSignature};
+use mm2_err_handle::prelude::*;
+
+const DB_VERSION: u32 = 1;
+
+impl From<DbTransactionError> for FrozenOutPointsStorageError {
+    fn from(e: DbTransactionError) -> Self {
+        let desc = e.to_string();
+        match e {
+            DbTransactionError::ErrorDeserializingItem(_) => FrozenOutPointsStorageError::ErrorDeserializing(desc),
+            DbTransactionError::ErrorGettingItems(_) => FrozenOutPointsStorageError::ErrorLoading(desc),
+            DbTransactionError::ErrorUploadingItem(_) | DbTransactionError::ErrorDeletingItems(_) => {
+                FrozenOutPointsStorageError::ErrorSaving(desc)
+            },
+            _ => FrozenOutPointsStorageError::Internal(desc),
+        }
+    }
+}
+
+impl From<InitDbError> for FrozenOutPointsStorageError {
+    fn from(e: InitDbError) -> Self { FrozenOutPointsStorageError::Internal(e.to_string()) }
+}
+
+#[derive(Deserialize, Serialize)]
+struct FrozenOutPointTable {
+    coin: String,
+    tx_hash: String,
+    index: u32,
+    value: String,
+}
+
+impl TableSignature for FrozenOutPointTable {
+    fn table_name() -> &'static str { "frozen_outpoints" }
+
+    fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+        if let (0, 1) = (old_version, new_version) {
+            let table = upgrader.create_table(Self::table_name())?;
+            table.create_index("coin", false)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct FrozenOutPointsDb {
+    inner: IndexedDb,
+}
+
+#[async_trait]
+impl DbInstance for FrozenOutPointsDb {
+    const DB_NAME: &'static str = "frozen_outpoints";
+
+    async fn init(db_id: DbIdentifier) -> InitDbResult<Self> {
+        let inner = IndexedDbBuilder::new(db_id)
+            .with_version(DB_VERSION)
+            .with_table::<FrozenOutPointTable>()
+            .build()
+            .await?;
+        Ok(FrozenOutPointsDb { inner })
+    }
+}
+
+pub(super) async fn load_frozen_outpoints(
+    ctx: &MmArc,
+    ticker: String,
+) -> FrozenOutPointsStorageResult<Vec<FrozenOutPointItem>> {
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(FrozenOutPointsStorageError::Internal)?;
+    let db = coins_ctx.frozen_outpoints_db.get_or_initialize().await?;
+    let transaction = db.inner.transaction().await?;
+    let table = transaction.table::<FrozenOutPointTable>().await?;
+
+    let items = table
+        .get_items("coin", &ticker)
+        .await?
+        .into_iter()
+        .map(|(_item_id, item)| FrozenOutPointItem {
+            tx_hash: item.tx_hash,
+            index: item.index,
+            value: item.value,
+        })
+        .collect();
+    Ok(items)
+}
+
+pub(super) async fn save_frozen_outpoints(
+    ctx: &MmArc,
+    ticker: String,
+    items: Vec<FrozenOutPointItem>,
+) -> FrozenOutPointsStorageResult<()> {
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(FrozenOutPointsStorageError::Internal)?;
+    let db = coins_ctx.frozen_outpoints_db.get_or_initialize().await?;
+    let transaction = db.inner.transaction().await?;
+    let table = transaction.table::<FrozenOutPointTable>().await?;
+
+    table.delete_items_by_index("coin", &ticker).await?;
+    for item in items {
+        let item = FrozenOutPointTable {
+            coin: ticker.clone(),
+            tx_hash: item.tx_hash,
+            index: item.index,
+            value: item.value,
+        };
+        table.add_item(&item).await?;
+    }
+    Ok(())
+}
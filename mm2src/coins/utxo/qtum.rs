@@ -17,7 +17,7 @@ use crate::rpc_command::init_create_account::{self, CreateAccountRpcError, Creat
                                               InitCreateAccountRpcOps};
 use crate::rpc_command::init_scan_for_new_addresses::{self, InitScanAddressesRpcOps, ScanAddressesParams,
                                                       ScanAddressesResponse};
-use crate::rpc_command::init_withdraw::{InitWithdrawCoin, WithdrawTaskHandle};
+use crate::rpc_command::init_withdraw::{InitWithdrawCoin, InitWithdrawManyCoin, WithdrawTaskHandle};
 use crate::tx_history_storage::{GetTxHistoryFilters, WalletId};
 use crate::utxo::utxo_builder::{MergeUtxoArcOps, UtxoCoinBuildError, UtxoCoinBuilder, UtxoCoinBuilderCommonOps,
                                 UtxoFieldsWithGlobalHDBuilder, UtxoFieldsWithHardwareWalletBuilder,
@@ -34,7 +34,8 @@ use crate::{eth, CanRefundHtlc, CheckIfMyPaymentSentArgs, CoinBalance, CoinWithD
             ValidateInstructionsErr, ValidateOtherPubKeyErr, ValidatePaymentError, ValidatePaymentFut,
             ValidatePaymentInput, ValidateWatcherSpendInput, VerificationResult, WaitForHTLCTxSpendArgs, WatcherOps,
             WatcherReward, WatcherRewardError, WatcherSearchForSwapTxSpendInput, WatcherValidatePaymentInput,
-            WatcherValidateTakerFeeInput, WithdrawFut, WithdrawSenderAddress};
+            WatcherValidateTakerFeeInput, WithdrawFrom, WithdrawFut, WithdrawManyDetails, WithdrawManyFut,
+            WithdrawManyRequest, WithdrawSenderAddress};
 use common::executor::{AbortableSystem, AbortedError};
 use crypto::Bip44Chain;
 use ethereum_types::H160;
@@ -657,11 +658,11 @@ impl SwapOps for QtumCoin {
         Ok(None)
     }
 
-    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> KeyPair {
+    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<KeyPair> {
         utxo_common::derive_htlc_key_pair(self.as_ref(), swap_unique_data)
     }
 
-    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> Vec<u8> {
+    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Vec<u8>> {
         utxo_common::derive_htlc_pubkey(self, swap_unique_data)
     }
 
@@ -901,6 +902,11 @@ impl MmCoin for QtumCoin {
         Box::new(utxo_common::withdraw(self.clone(), req).boxed().compat())
     }
 
+    fn withdraw_many(&self, req: WithdrawManyRequest) -> WithdrawManyFut {
+        let fut = utxo_common::withdraw_many(self.clone(), req).map_ok(WithdrawManyDetails::from);
+        Box::new(fut.boxed().compat())
+    }
+
     fn decimals(&self) -> u8 { utxo_common::decimals(&self.utxo_arc) }
 
     /// Check if the `to_address_format` is standard and if the `from` address is standard UTXO address.
@@ -989,9 +995,9 @@ impl GetWithdrawSenderAddress for QtumCoin {
 
     async fn get_withdraw_sender_address(
         &self,
-        req: &WithdrawRequest,
+        from: Option<&WithdrawFrom>,
     ) -> MmResult<WithdrawSenderAddress<Self::Address, Self::Pubkey>, WithdrawError> {
-        utxo_common::get_withdraw_from_address(self, req).await
+        utxo_common::get_withdraw_from_address(self, from).await
     }
 }
 
@@ -1002,6 +1008,18 @@ impl InitWithdrawCoin for QtumCoin {
         ctx: MmArc,
         req: WithdrawRequest,
         task_handle: &WithdrawTaskHandle,
+    ) -> Result<TransactionDetails, MmError<WithdrawError>> {
+        utxo_common::init_withdraw(ctx, self.clone(), req.into(), task_handle).await
+    }
+}
+
+#[async_trait]
+impl InitWithdrawManyCoin for QtumCoin {
+    async fn init_withdraw_many(
+        &self,
+        ctx: MmArc,
+        req: WithdrawManyRequest,
+        task_handle: &WithdrawTaskHandle,
     ) -> Result<TransactionDetails, MmError<WithdrawError>> {
         utxo_common::init_withdraw(ctx, self.clone(), req, task_handle).await
     }
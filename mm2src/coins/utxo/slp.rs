@@ -13,9 +13,9 @@ use crate::utxo::utxo_common::{self, big_decimal_from_sat_unsigned, payment_scri
 use crate::utxo::{generate_and_send_tx, sat_from_big_decimal, ActualTxFee, AdditionalTxData, BroadcastTxErr,
                   FeePolicy, GenerateTxError, RecentlySpentOutPointsGuard, UtxoCoinConf, UtxoCoinFields,
                   UtxoCommonOps, UtxoTx, UtxoTxBroadcastOps, UtxoTxGenerationOps};
-use crate::{BalanceFut, CheckIfMyPaymentSentArgs, CoinBalance, CoinFutSpawner, ConfirmPaymentInput, DexFee,
-            FeeApproxStage, FoundSwapTxSpend, HistorySyncState, MakerSwapTakerCoin, MarketCoinOps, MmCoin, MmCoinEnum,
-            NegotiateSwapContractAddrErr, NumConversError, PaymentInstructionArgs, PaymentInstructions,
+use crate::{BalanceFut, CheckIfMyPaymentSentArgs, CoinBalance, CoinFutSpawner, ConfirmPaymentInput, DeriveHtlcResult,
+            DexFee, FeeApproxStage, FoundSwapTxSpend, HistorySyncState, MakerSwapTakerCoin, MarketCoinOps, MmCoin,
+            MmCoinEnum, NegotiateSwapContractAddrErr, NumConversError, PaymentInstructionArgs, PaymentInstructions,
             PaymentInstructionsErr, PrivKeyPolicyNotAllowed, RawTransactionFut, RawTransactionRequest, RefundError,
             RefundPaymentArgs, RefundResult, SearchForSwapTxSpendInput, SendMakerPaymentSpendPreimageInput,
             SendPaymentArgs, SignatureResult, SpendPaymentArgs, SwapOps, TakerSwapMakerCoin, TradeFee,
@@ -493,7 +493,7 @@ impl SlpToken {
             },
         }
 
-        let htlc_keypair = self.derive_htlc_key_pair(&input.unique_swap_data);
+        let htlc_keypair = self.derive_htlc_key_pair(&input.unique_swap_data)?;
         let first_pub = &Public::from_slice(&input.other_pub)
             .map_to_mm(|err| ValidatePaymentError::InvalidParameter(err.to_string()))?;
         let time_lock = input
@@ -1219,7 +1219,7 @@ impl SwapOps for SlpToken {
         let taker_pub = try_tx_fus!(Public::from_slice(maker_payment_args.other_pubkey));
         let amount = try_tx_fus!(sat_from_big_decimal(&maker_payment_args.amount, self.decimals()));
         let secret_hash = maker_payment_args.secret_hash.to_owned();
-        let maker_htlc_keypair = self.derive_htlc_key_pair(maker_payment_args.swap_unique_data);
+        let maker_htlc_keypair = try_tx_fus!(self.derive_htlc_key_pair(maker_payment_args.swap_unique_data));
         let time_lock = try_tx_fus!(maker_payment_args.time_lock.try_into());
 
         let coin = self.clone();
@@ -1238,7 +1238,7 @@ impl SwapOps for SlpToken {
         let amount = try_tx_fus!(sat_from_big_decimal(&taker_payment_args.amount, self.decimals()));
         let secret_hash = taker_payment_args.secret_hash.to_owned();
 
-        let taker_htlc_keypair = self.derive_htlc_key_pair(taker_payment_args.swap_unique_data);
+        let taker_htlc_keypair = try_tx_fus!(self.derive_htlc_key_pair(taker_payment_args.swap_unique_data));
         let time_lock = try_tx_fus!(taker_payment_args.time_lock.try_into());
 
         let coin = self.clone();
@@ -1257,7 +1257,7 @@ impl SwapOps for SlpToken {
         let taker_pub = try_tx_fus!(Public::from_slice(maker_spends_payment_args.other_pubkey));
         let secret = maker_spends_payment_args.secret.to_owned();
         let secret_hash = maker_spends_payment_args.secret_hash.to_owned();
-        let htlc_keypair = self.derive_htlc_key_pair(maker_spends_payment_args.swap_unique_data);
+        let htlc_keypair = try_tx_fus!(self.derive_htlc_key_pair(maker_spends_payment_args.swap_unique_data));
         let coin = self.clone();
         let time_lock = try_tx_fus!(maker_spends_payment_args.time_lock.try_into());
 
@@ -1276,7 +1276,7 @@ impl SwapOps for SlpToken {
         let maker_pub = try_tx_fus!(Public::from_slice(taker_spends_payment_args.other_pubkey));
         let secret = taker_spends_payment_args.secret.to_owned();
         let secret_hash = taker_spends_payment_args.secret_hash.to_owned();
-        let htlc_keypair = self.derive_htlc_key_pair(taker_spends_payment_args.swap_unique_data);
+        let htlc_keypair = try_tx_fus!(self.derive_htlc_key_pair(taker_spends_payment_args.swap_unique_data));
         let coin = self.clone();
         let time_lock = try_tx_fus!(taker_spends_payment_args.time_lock.try_into());
 
@@ -1294,7 +1294,7 @@ impl SwapOps for SlpToken {
         let tx = taker_refunds_payment_args.payment_tx.to_owned();
         let maker_pub = try_tx_s!(Public::from_slice(taker_refunds_payment_args.other_pubkey));
         let secret_hash = taker_refunds_payment_args.secret_hash.to_owned();
-        let htlc_keypair = self.derive_htlc_key_pair(taker_refunds_payment_args.swap_unique_data);
+        let htlc_keypair = try_tx_s!(self.derive_htlc_key_pair(taker_refunds_payment_args.swap_unique_data));
         let time_lock = try_tx_s!(taker_refunds_payment_args.time_lock.try_into());
 
         let tx = try_tx_s!(
@@ -1308,7 +1308,7 @@ impl SwapOps for SlpToken {
         let tx = maker_refunds_payment_args.payment_tx.to_owned();
         let taker_pub = try_tx_s!(Public::from_slice(maker_refunds_payment_args.other_pubkey));
         let secret_hash = maker_refunds_payment_args.secret_hash.to_owned();
-        let htlc_keypair = self.derive_htlc_key_pair(maker_refunds_payment_args.swap_unique_data);
+        let htlc_keypair = try_tx_s!(self.derive_htlc_key_pair(maker_refunds_payment_args.swap_unique_data));
         let time_lock = try_tx_s!(maker_refunds_payment_args.time_lock.try_into());
 
         let tx = try_tx_s!(
@@ -1416,11 +1416,11 @@ impl SwapOps for SlpToken {
         Ok(None)
     }
 
-    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> KeyPair {
+    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<KeyPair> {
         utxo_common::derive_htlc_key_pair(self.platform_coin.as_ref(), swap_unique_data)
     }
 
-    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> Vec<u8> {
+    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Vec<u8>> {
         utxo_common::derive_htlc_pubkey(self, swap_unique_data)
     }
 
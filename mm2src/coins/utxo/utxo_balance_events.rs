@@ -0,0 +1,186 @@
+use async_trait::async_trait;
+use common::{executor::{AbortSettings, SpawnAbortable, Timer},
+             log};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+use futures::channel::oneshot::{self, Receiver, Sender};
+use futures::compat::Future01CompatExt;
+use futures::{select, FutureExt, StreamExt};
+use keys::Type as ScriptType;
+use mm2_core::mm_ctx::MmArc;
+use mm2_event_stream::{behaviour::{EventBehaviour, EventInitStatus},
+                       Event, EventStreamConfiguration};
+
+use super::bch::BchCoin;
+use super::output_script;
+use super::qtum::QtumCoin;
+use super::rpc_clients::{electrum_script_hash, ElectrumClient, ScripthashNotification, UtxoRpcClientEnum};
+use super::utxo_standard::UtxoStandardCoin;
+use super::utxo_tx_history_v2::UtxoTxHistoryOps;
+use super::UtxoCoinFields;
+use crate::{CoinBalance, MarketCoinOps, MmCoin};
+
+const BALANCE_EVENT_NAME: &str = "COIN_BALANCE";
+
+/// Subscribes to the status changes of every address of the coin.
+/// Electrum servers forget the subscriptions on reconnection, so this is called on every polling tick.
+async fn subscribe_to_addresses<Coin>(coin: &Coin, client: &ElectrumClient) -> Result<(), String>
+where
+    Coin: UtxoTxHistoryOps,
+{
+    let addresses = coin.my_addresses().await.map_err(|e| e.to_string())?;
+    for address in addresses {
+        let scripthash = hex::encode(electrum_script_hash(&output_script(&address, ScriptType::P2PKH)));
+        client
+            .scripthash_subscribe(&scripthash)
+            .compat()
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Waits for either a scripthash notification or the polling interval, whichever comes first.
+async fn wait_for_balance_change(notifications: &mut Option<UnboundedReceiver<ScripthashNotification>>, interval: f64) {
+    match notifications {
+        Some(rx) => {
+            select! {
+                _ = rx.next().fuse() => (),
+                _ = Timer::sleep(interval).fuse() => (),
+            }
+        },
+        None => Timer::sleep(interval).await,
+    }
+}
+
+/// The [`EventBehaviour::handle`] implementation shared by all UTXO coins.
+async fn handle_balance_events<Coin>(coin: Coin, interval: f64, tx: oneshot::Sender<EventInitStatus>)
+where
+    Coin: UtxoTxHistoryOps + AsRef<UtxoCoinFields>,
+{
+    let ctx = match MmArc::from_weak(&coin.as_ref().ctx) {
+        Some(ctx) => ctx,
+        None => {
+            let msg = "MM context must have been initialized already.";
+            log::error!(
+                "{} event for {} is not started: {}",
+                BALANCE_EVENT_NAME,
+                coin.ticker(),
+                msg
+            );
+            tx.send(EventInitStatus::Failed(msg.to_owned())).ok();
+            return;
+        },
+    };
+
+    // Native mode doesn't support subscriptions, so the balance is only polled every `interval` seconds.
+    let electrum_client = match &coin.as_ref().rpc_client {
+        UtxoRpcClientEnum::Electrum(client) => Some(client.clone()),
+        UtxoRpcClientEnum::Native(_) => None,
+    };
+    let mut notifications = electrum_client.as_ref().map(|client| {
+        let (notification_tx, notification_rx) = unbounded();
+        client.set_scripthash_notification_sender(notification_tx);
+        notification_rx
+    });
+
+    let mut current_balance: Option<CoinBalance> = None;
+
+    tx.send(EventInitStatus::Success)
+        .expect("Receiver is dropped, which should never happen.");
+
+    loop {
+        if let Some(client) = &electrum_client {
+            if let Err(e) = subscribe_to_addresses(&coin, client).await {
+                log::error!("Failed to subscribe to {} addresses: {}", coin.ticker(), e);
+            }
+        }
+
+        match coin.my_balance().compat().await {
+            Ok(balance) if current_balance.as_ref() != Some(&balance) => {
+                let payload = json!({
+                    "ticker": coin.ticker(),
+                    "balance": { "spendable": balance.spendable, "unspendable": balance.unspendable }
+                });
+
+                ctx.stream_channel_controller
+                    .broadcast(Event::new(BALANCE_EVENT_NAME.to_string(), payload.to_string()))
+                    .await;
+
+                current_balance = Some(balance);
+            },
+            Ok(_) => (),
+            Err(e) => log::error!("Failed to fetch {} balance: {}", coin.ticker(), e),
+        }
+
+        wait_for_balance_change(&mut notifications, interval).await;
+    }
+}
+
+/// The [`EventBehaviour::spawn_if_active`] implementation shared by all UTXO coins.
+async fn spawn_balance_events_if_active<Coin>(coin: Coin, config: &EventStreamConfiguration) -> EventInitStatus
+where
+    Coin: EventBehaviour + MmCoin + Clone,
+{
+    if let Some(event) = config.get_event(BALANCE_EVENT_NAME) {
+        log::info!(
+            "{} event is activated for {}. Electrum notifications are checked with {} seconds fallback interval.",
+            BALANCE_EVENT_NAME,
+            coin.ticker(),
+            event.stream_interval_seconds
+        );
+
+        let (tx, rx): (Sender<EventInitStatus>, Receiver<EventInitStatus>) = oneshot::channel();
+        let fut = coin.clone().handle(event.stream_interval_seconds, tx);
+        let settings = AbortSettings::info_on_abort(format!(
+            "{} event is stopped for {}.",
+            BALANCE_EVENT_NAME,
+            coin.ticker()
+        ));
+        coin.spawner().spawn_with_settings(fut, settings);
+
+        rx.await
+            .unwrap_or_else(|e| EventInitStatus::Failed(format!("Event initialization status must be received: {}", e)))
+    } else {
+        EventInitStatus::Inactive
+    }
+}
+
+#[async_trait]
+impl EventBehaviour for UtxoStandardCoin {
+    const EVENT_NAME: &'static str = BALANCE_EVENT_NAME;
+
+    async fn handle(self, interval: f64, tx: oneshot::Sender<EventInitStatus>) {
+        handle_balance_events(self, interval, tx).await
+    }
+
+    async fn spawn_if_active(self, config: &EventStreamConfiguration) -> EventInitStatus {
+        spawn_balance_events_if_active(self, config).await
+    }
+}
+
+#[async_trait]
+impl EventBehaviour for QtumCoin {
+    const EVENT_NAME: &'static str = BALANCE_EVENT_NAME;
+
+    async fn handle(self, interval: f64, tx: oneshot::Sender<EventInitStatus>) {
+        handle_balance_events(self, interval, tx).await
+    }
+
+    async fn spawn_if_active(self, config: &EventStreamConfiguration) -> EventInitStatus {
+        spawn_balance_events_if_active(self, config).await
+    }
+}
+
+/// Only the BCH balance is streamed, SLP tokens balances aren't tracked yet.
+#[async_trait]
+impl EventBehaviour for BchCoin {
+    const EVENT_NAME: &'static str = BALANCE_EVENT_NAME;
+
+    async fn handle(self, interval: f64, tx: oneshot::Sender<EventInitStatus>) {
+        handle_balance_events(self, interval, tx).await
+    }
+
+    async fn spawn_if_active(self, config: &EventStreamConfiguration) -> EventInitStatus {
+        spawn_balance_events_if_active(self, config).await
+    }
+}
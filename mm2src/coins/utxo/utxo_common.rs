@@ -26,13 +26,13 @@ use crate::{CanRefundHtlc, CoinBalance, CoinWithDerivationMethod, ConfirmPayment
             ValidateTakerFundingSpendPreimageError, ValidateTakerFundingSpendPreimageResult,
             ValidateTakerPaymentSpendPreimageError, ValidateTakerPaymentSpendPreimageResult,
             ValidateWatcherSpendInput, VerificationError, VerificationResult, WatcherSearchForSwapTxSpendInput,
-            WatcherValidatePaymentInput, WatcherValidateTakerFeeInput, WithdrawFrom, WithdrawResult,
-            WithdrawSenderAddress, EARLY_CONFIRMATION_ERR_LOG, INVALID_RECEIVER_ERR_LOG, INVALID_REFUND_TX_ERR_LOG,
-            INVALID_SCRIPT_ERR_LOG, INVALID_SENDER_ERR_LOG, OLD_TRANSACTION_ERR_LOG};
+            WatcherValidatePaymentInput, WatcherValidateTakerFeeInput, WithdrawFrom, WithdrawManyRequest,
+            WithdrawResult, WithdrawSenderAddress, EARLY_CONFIRMATION_ERR_LOG, INVALID_RECEIVER_ERR_LOG,
+            INVALID_REFUND_TX_ERR_LOG, INVALID_SCRIPT_ERR_LOG, INVALID_SENDER_ERR_LOG, OLD_TRANSACTION_ERR_LOG};
 use crate::{MmCoinEnum, WatcherReward, WatcherRewardError};
 pub use bitcrypto::{dhash160, sha256, ChecksumType};
 use bitcrypto::{dhash256, ripemd160};
-use chain::constants::SEQUENCE_FINAL;
+use chain::constants::{SEQUENCE_FINAL, SEQUENCE_RBF};
 use chain::{OutPoint, TransactionOutput};
 use common::executor::Timer;
 use common::jsonrpc_client::JsonRpcErrorType;
@@ -770,6 +770,11 @@ pub struct UtxoTxBuilder<'a, T: AsRef<UtxoCoinFields> + UtxoTxGenerationOps> {
     tx_fee: u64,
     min_relay_fee: Option<u64>,
     dust: Option<u64>,
+    /// Whether the value of the required inputs is spent to cover the outputs and the fee.
+    /// If not, the outputs and the fee are covered by the available inputs only.
+    fund_from_required_inputs: bool,
+    /// Whether the inputs may signal BIP125 replaceability, see [`UtxoTxBuilder::replaceable`].
+    replaceable: bool,
 }
 
 impl<'a, T: AsRef<UtxoCoinFields> + UtxoTxGenerationOps> UtxoTxBuilder<'a, T> {
@@ -788,6 +793,8 @@ impl<'a, T: AsRef<UtxoCoinFields> + UtxoTxGenerationOps> UtxoTxBuilder<'a, T> {
             tx_fee: 0,
             min_relay_fee: None,
             dust: None,
+            fund_from_required_inputs: false,
+            replaceable: false,
         }
     }
 
@@ -813,6 +820,25 @@ impl<'a, T: AsRef<UtxoCoinFields> + UtxoTxGenerationOps> UtxoTxBuilder<'a, T> {
         self
     }
 
+    /// Spends the value of the required inputs to cover the outputs and the fee,
+    /// so the available inputs are added only if the required ones are not enough.
+    ///
+    /// Not used for the SLP transactions since their required inputs carry the tokens,
+    /// and the token outputs are funded by the available inputs.
+    pub fn fund_from_required_inputs(mut self) -> Self {
+        self.fund_from_required_inputs = true;
+        self
+    }
+
+    /// The inputs signal BIP125 replaceability if it's enabled by the `signal_rbf` coin config,
+    /// so the fee of the transaction can be bumped later.
+    ///
+    /// Must not be used for the swap transactions, since replacing them changes their hashes.
+    pub fn replaceable(mut self) -> Self {
+        self.replaceable = true;
+        self
+    }
+
     /// This function expects that utxos are sorted by amounts in ascending order
     /// Consider sorting before calling this function
     pub fn add_available_inputs(mut self, inputs: impl IntoIterator<Item = UnspentInfo>) -> Self {
@@ -912,6 +938,14 @@ impl<'a, T: AsRef<UtxoCoinFields> + UtxoTxGenerationOps> UtxoTxBuilder<'a, T> {
         }
     }
 
+    fn input_sequence(&self) -> u32 {
+        if self.replaceable && self.coin.as_ref().conf.signal_rbf {
+            SEQUENCE_RBF
+        } else {
+            SEQUENCE_FINAL
+        }
+    }
+
     fn dust(&self) -> u64 {
         match self.dust {
             Some(dust) => dust,
@@ -972,18 +1006,30 @@ impl<'a, T: AsRef<UtxoCoinFields> + UtxoTxGenerationOps> UtxoTxBuilder<'a, T> {
             None
         };
 
+        let sequence = self.input_sequence();
+        for input in self.tx.inputs.iter_mut() {
+            input.sequence = sequence;
+        }
+
+        // The required inputs may already cover the outputs and the fee.
+        let mut is_complete = false;
+        if self.fund_from_required_inputs && !self.tx.inputs.is_empty() {
+            self.sum_inputs += self.tx.inputs.iter().fold(0, |sum, input| sum + input.amount);
+            is_complete = self.update_fee_and_check_completeness(&from.addr_format, &actual_tx_fee);
+        }
         for utxo in self.available_inputs.clone() {
+            if is_complete {
+                break;
+            }
             self.tx.inputs.push(UnsignedTransactionInput {
                 previous_output: utxo.outpoint,
-                sequence: SEQUENCE_FINAL,
+                sequence,
                 amount: utxo.value,
                 witness: vec![],
             });
             self.sum_inputs += utxo.value;
 
-            if self.update_fee_and_check_completeness(&from.addr_format, &actual_tx_fee) {
-                break;
-            }
+            is_complete = self.update_fee_and_check_completeness(&from.addr_format, &actual_tx_fee);
         }
 
         match self.fee_policy {
@@ -1716,7 +1762,7 @@ pub fn send_maker_payment<T>(coin: T, args: SendPaymentArgs) -> TransactionFut
 where
     T: UtxoCommonOps + GetUtxoListOps + SwapOps,
 {
-    let maker_htlc_key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let maker_htlc_key_pair = try_tx_fus!(coin.derive_htlc_key_pair(args.swap_unique_data));
     let SwapPaymentOutputsResult {
         payment_address,
         outputs,
@@ -1753,7 +1799,7 @@ where
         None => args.amount,
     };
 
-    let taker_htlc_key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let taker_htlc_key_pair = try_tx_fus!(coin.derive_htlc_key_pair(args.swap_unique_data));
     let SwapPaymentOutputsResult {
         payment_address,
         outputs,
@@ -1790,7 +1836,7 @@ pub fn send_maker_spends_taker_payment<T: UtxoCommonOps + SwapOps>(coin: T, args
 
     let payment_value = try_tx_fus!(prev_transaction.first_output()).value;
 
-    let key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let key_pair = try_tx_fus!(coin.derive_htlc_key_pair(args.swap_unique_data));
     let script_data = Builder::default()
         .push_data(args.secret)
         .push_opcode(Opcode::OP_0)
@@ -1898,7 +1944,7 @@ pub fn create_maker_payment_spend_preimage<T: UtxoCommonOps + SwapOps>(
     drop_mutability!(prev_transaction);
     let payment_value = try_tx_fus!(prev_transaction.first_output()).value;
 
-    let key_pair = coin.derive_htlc_key_pair(swap_unique_data);
+    let key_pair = try_tx_fus!(coin.derive_htlc_key_pair(swap_unique_data));
 
     let script_data = Builder::default().into_script();
     let redeem_script = payment_script(
@@ -1960,7 +2006,7 @@ pub fn create_taker_payment_refund_preimage<T: UtxoCommonOps + SwapOps>(
     drop_mutability!(prev_transaction);
     let payment_value = try_tx_fus!(prev_transaction.first_output()).value;
 
-    let key_pair = coin.derive_htlc_key_pair(swap_unique_data);
+    let key_pair = try_tx_fus!(coin.derive_htlc_key_pair(swap_unique_data));
     let script_data = Builder::default().push_opcode(Opcode::OP_1).into_script();
     let redeem_script = payment_script(
         time_lock,
@@ -2010,7 +2056,7 @@ pub fn send_taker_spends_maker_payment<T: UtxoCommonOps + SwapOps>(coin: T, args
     drop_mutability!(prev_transaction);
     let payment_value = try_tx_fus!(prev_transaction.first_output()).value;
 
-    let key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let key_pair = try_tx_fus!(coin.derive_htlc_key_pair(args.swap_unique_data));
 
     let script_data = Builder::default()
         .push_data(args.secret)
@@ -2076,7 +2122,7 @@ async fn refund_htlc_payment<T: UtxoCommonOps + SwapOps>(
     let payment_value = try_tx_s!(prev_transaction.first_output()).value;
     let other_public = try_tx_s!(Public::from_slice(args.other_pubkey));
 
-    let key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let key_pair = try_tx_s!(coin.derive_htlc_key_pair(args.swap_unique_data));
     let script_data = Builder::default().push_opcode(Opcode::OP_1).into_script();
     let time_lock = try_tx_s!(args.time_lock.try_into());
 
@@ -2471,7 +2517,7 @@ pub fn validate_maker_payment<T: UtxoCommonOps + SwapOps>(
     let mut tx: UtxoTx = try_f!(deserialize(input.payment_tx.as_slice()));
     tx.tx_hash_algo = coin.as_ref().tx_hash_algo;
 
-    let htlc_keypair = coin.derive_htlc_key_pair(&input.unique_swap_data);
+    let htlc_keypair = try_f!(coin.derive_htlc_key_pair(&input.unique_swap_data));
     let other_pub =
         &try_f!(Public::from_slice(&input.other_pub)
             .map_to_mm(|err| ValidatePaymentError::InvalidParameter(err.to_string())));
@@ -2578,7 +2624,7 @@ pub fn validate_taker_payment<T: UtxoCommonOps + SwapOps>(
     let mut tx: UtxoTx = try_f!(deserialize(input.payment_tx.as_slice()));
     tx.tx_hash_algo = coin.as_ref().tx_hash_algo;
 
-    let htlc_keypair = coin.derive_htlc_key_pair(&input.unique_swap_data);
+    let htlc_keypair = try_f!(coin.derive_htlc_key_pair(&input.unique_swap_data));
     let other_pub =
         &try_f!(Public::from_slice(&input.other_pub)
             .map_to_mm(|err| ValidatePaymentError::InvalidParameter(err.to_string())));
@@ -2635,7 +2681,7 @@ pub fn check_if_my_payment_sent<T: UtxoCommonOps + SwapOps>(
     secret_hash: &[u8],
     swap_unique_data: &[u8],
 ) -> Box<dyn Future<Item = Option<TransactionEnum>, Error = String> + Send> {
-    let my_htlc_keypair = coin.derive_htlc_key_pair(swap_unique_data);
+    let my_htlc_keypair = try_fus!(coin.derive_htlc_key_pair(swap_unique_data));
     let script = payment_script(
         time_lock,
         secret_hash,
@@ -2715,7 +2761,7 @@ pub async fn search_for_swap_tx_spend_my<T: AsRef<UtxoCoinFields> + SwapOps>(
     search_for_swap_output_spend(
         coin.as_ref(),
         try_s!(input.time_lock.try_into()),
-        coin.derive_htlc_key_pair(input.swap_unique_data).public(),
+        try_s!(coin.derive_htlc_key_pair(input.swap_unique_data)).public(),
         &try_s!(Public::from_slice(input.other_pub)),
         input.secret_hash,
         input.tx,
@@ -2734,7 +2780,7 @@ pub async fn search_for_swap_tx_spend_other<T: AsRef<UtxoCoinFields> + SwapOps>(
         coin.as_ref(),
         try_s!(input.time_lock.try_into()),
         &try_s!(Public::from_slice(input.other_pub)),
-        coin.derive_htlc_key_pair(input.swap_unique_data).public(),
+        try_s!(coin.derive_htlc_key_pair(input.swap_unique_data)).public(),
         input.secret_hash,
         input.tx,
         output_index,
@@ -3040,6 +3086,14 @@ pub async fn get_tx_hex_by_hash(coin: &UtxoCoinFields, tx_hash: Vec<u8>) -> RawT
 }
 
 pub async fn withdraw<T>(coin: T, req: WithdrawRequest) -> WithdrawResult
+where
+    T: UtxoCommonOps + GetUtxoListOps + MarketCoinOps,
+{
+    StandardUtxoWithdraw::new(coin, req.into())?.build().await
+}
+
+/// Pays to all of the `req.outputs` within one transaction.
+pub async fn withdraw_many<T>(coin: T, req: WithdrawManyRequest) -> WithdrawResult
 where
     T: UtxoCommonOps + GetUtxoListOps + MarketCoinOps,
 {
@@ -3049,7 +3103,7 @@ where
 pub async fn init_withdraw<T>(
     ctx: MmArc,
     coin: T,
-    req: WithdrawRequest,
+    req: WithdrawManyRequest,
     task_handle: &WithdrawTaskHandle,
 ) -> WithdrawResult
 where
@@ -3064,7 +3118,7 @@ where
 
 pub async fn get_withdraw_from_address<T>(
     coin: &T,
-    req: &WithdrawRequest,
+    from: Option<&WithdrawFrom>,
 ) -> MmResult<WithdrawSenderAddress<Address, Public>, WithdrawError>
 where
     T: CoinWithDerivationMethod<Address = Address, HDWallet = <T as HDWalletCoinOps>::HDWallet>
@@ -3072,18 +3126,18 @@ where
         + UtxoCommonOps,
 {
     match coin.derivation_method() {
-        DerivationMethod::SingleAddress(my_address) => get_withdraw_iguana_sender(coin, req, my_address),
-        DerivationMethod::HDWallet(hd_wallet) => get_withdraw_hd_sender(coin, req, hd_wallet).await,
+        DerivationMethod::SingleAddress(my_address) => get_withdraw_iguana_sender(coin, from, my_address),
+        DerivationMethod::HDWallet(hd_wallet) => get_withdraw_hd_sender(coin, from, hd_wallet).await,
     }
 }
 
 #[allow(clippy::result_large_err)]
 pub fn get_withdraw_iguana_sender<T: UtxoCommonOps>(
     coin: &T,
-    req: &WithdrawRequest,
+    from: Option<&WithdrawFrom>,
     my_address: &Address,
 ) -> MmResult<WithdrawSenderAddress<Address, Public>, WithdrawError> {
-    if req.from.is_some() {
+    if from.is_some() {
         let error = "'from' is not supported if the coin is initialized with an Iguana private key";
         return MmError::err(WithdrawError::UnexpectedFromAddress(error.to_owned()));
     }
@@ -3099,7 +3153,7 @@ pub fn get_withdraw_iguana_sender<T: UtxoCommonOps>(
 
 pub async fn get_withdraw_hd_sender<T>(
     coin: &T,
-    req: &WithdrawRequest,
+    from: Option<&WithdrawFrom>,
     hd_wallet: &T::HDWallet,
 ) -> MmResult<WithdrawSenderAddress<Address, Public>, WithdrawError>
 where
@@ -3109,7 +3163,7 @@ where
         account_id,
         chain,
         address_id,
-    } = match req.from.clone().or_mm_err(|| WithdrawError::FromAddressNotFound)? {
+    } = match from.cloned().or_mm_err(|| WithdrawError::FromAddressNotFound)? {
         WithdrawFrom::AddressId(id) => id,
         WithdrawFrom::DerivationPath { derivation_path } => {
             let derivation_path = StandardHDPath::from_str(&derivation_path)
@@ -4633,14 +4687,16 @@ pub async fn get_unspent_ordered_list<'a, T>(
 where
     T: UtxoCommonOps + GetUtxoListOps,
 {
-    if coin.as_ref().check_utxo_maturity {
+    let (unspents, recently_spent) = if coin.as_ref().check_utxo_maturity {
         coin.get_mature_unspent_ordered_list(address)
             .await
             // Convert `MatureUnspentList` into `Vec<UnspentInfo>` by discarding immature unspents.
-            .map(|(mature_unspents, recently_spent)| (mature_unspents.only_mature(), recently_spent))
+            .map(|(mature_unspents, recently_spent)| (mature_unspents.only_mature(), recently_spent))?
     } else {
-        coin.get_all_unspent_ordered_list(address).await
-    }
+        coin.get_all_unspent_ordered_list(address).await?
+    };
+    let frozen_outpoints = get_frozen_outpoints(coin);
+    Ok((exclude_frozen_unspents(unspents, &frozen_outpoints), recently_spent))
 }
 
 /// [`GetUtxoMapOps::get_unspent_ordered_map`] implementation.
@@ -4653,22 +4709,55 @@ pub async fn get_unspent_ordered_map<T>(
 where
     T: UtxoCommonOps + GetUtxoMapOps,
 {
-    if coin.as_ref().check_utxo_maturity {
+    let (unspents_map, recently_spent) = if coin.as_ref().check_utxo_maturity {
         coin.get_mature_unspent_ordered_map(addresses)
             .await
             // Convert `MatureUnspentMap` into `UnspentMap` by discarding immature unspents.
             .map(|(mature_unspents_map, recently_spent)| {
-                let unspents_map = mature_unspents_map
+                let unspents_map: UnspentMap = mature_unspents_map
                     .into_iter()
                     .map(|(address, unspents)| (address, unspents.only_mature()))
                     .collect();
                 (unspents_map, recently_spent)
-            })
+            })?
     } else {
-        coin.get_all_unspent_ordered_map(addresses).await
+        coin.get_all_unspent_ordered_map(addresses).await?
+    };
+    let frozen_outpoints = get_frozen_outpoints(coin);
+    let unspents_map = unspents_map
+        .into_iter()
+        .map(|(address, unspents)| (address, exclude_frozen_unspents(unspents, &frozen_outpoints)))
+        .collect();
+    Ok((unspents_map, recently_spent))
+}
+
+/// Returns the outputs of the coin frozen by the user.
+/// These outputs are excluded from [`get_unspent_ordered_list`] and [`get_unspent_ordered_map`],
+/// so they are never selected to fund swaps or withdrawals automatically.
+pub fn get_frozen_outpoints<T: AsRef<UtxoCoinFields>>(coin: &T) -> FrozenOutPoints {
+    let ctx = match MmArc::from_weak(&coin.as_ref().ctx) {
+        Some(ctx) => ctx,
+        None => return FrozenOutPoints::new(),
+    };
+    match CoinsContext::from_ctx(&ctx) {
+        Ok(coins_ctx) => coins_ctx.frozen_outpoints(&coin.as_ref().conf.ticker),
+        Err(e) => {
+            error!("Error getting CoinsContext: {}", e);
+            FrozenOutPoints::new()
+        },
     }
 }
 
+fn exclude_frozen_unspents(unspents: Vec<UnspentInfo>, frozen_outpoints: &FrozenOutPoints) -> Vec<UnspentInfo> {
+    if frozen_outpoints.is_empty() {
+        return unspents;
+    }
+    unspents
+        .into_iter()
+        .filter(|unspent| !frozen_outpoints.contains_key(&unspent.outpoint))
+        .collect()
+}
+
 /// [`GetUtxoListOps::get_all_unspent_ordered_list`] implementation.
 /// Returns available mature and immature unspents in ascending
 /// + `RecentlySpentOutPoints` MutexGuard for further interaction (e.g. to add new transaction to it).
@@ -4847,22 +4936,22 @@ where
 }
 
 #[inline]
-pub fn derive_htlc_key_pair(coin: &UtxoCoinFields, _swap_unique_data: &[u8]) -> KeyPair {
+pub fn derive_htlc_key_pair(coin: &UtxoCoinFields, _swap_unique_data: &[u8]) -> DeriveHtlcResult<KeyPair> {
     match coin.priv_key_policy {
-        PrivKeyPolicy::Iguana(k) => k,
+        PrivKeyPolicy::Iguana(k) => Ok(k),
         PrivKeyPolicy::HDWallet {
             activated_key: activated_key_pair,
             ..
-        } => activated_key_pair,
-        PrivKeyPolicy::Trezor => todo!(),
+        } => Ok(activated_key_pair),
+        PrivKeyPolicy::Trezor => MmError::err(UnexpectedDerivationMethod::Trezor),
         #[cfg(target_arch = "wasm32")]
         PrivKeyPolicy::Metamask(_) => panic!("`PrivKeyPolicy::Metamask` is not supported for UTXO coins"),
     }
 }
 
 #[inline]
-pub fn derive_htlc_pubkey(coin: &dyn SwapOps, swap_unique_data: &[u8]) -> Vec<u8> {
-    coin.derive_htlc_key_pair(swap_unique_data).public_slice().to_vec()
+pub fn derive_htlc_pubkey(coin: &dyn SwapOps, swap_unique_data: &[u8]) -> DeriveHtlcResult<Vec<u8>> {
+    Ok(coin.derive_htlc_key_pair(swap_unique_data)?.public_slice().to_vec())
 }
 
 pub fn validate_other_pubkey(raw_pubkey: &[u8]) -> MmResult<(), ValidateOtherPubKeyErr> {
@@ -4896,7 +4985,7 @@ pub async fn send_taker_funding<T>(coin: T, args: SendTakerFundingArgs<'_>) -> R
 where
     T: UtxoCommonOps + GetUtxoListOps + SwapOps,
 {
-    let taker_htlc_key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let taker_htlc_key_pair = try_tx_s!(coin.derive_htlc_key_pair(args.swap_unique_data));
     let total_amount = &args.dex_fee_amount + &args.premium_amount + &args.trading_amount;
 
     let SwapPaymentOutputsResult {
@@ -4941,7 +5030,7 @@ where
     let my_address = try_tx_s!(coin.as_ref().derivation_method.single_addr_or_err()).clone();
     let payment_value = try_tx_s!(args.funding_tx.first_output()).value;
 
-    let key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let key_pair = try_tx_s!(coin.derive_htlc_key_pair(args.swap_unique_data));
     let script_data = Builder::default()
         .push_data(args.taker_secret)
         .push_opcode(Opcode::OP_0)
@@ -4995,7 +5084,7 @@ pub async fn validate_taker_funding<T>(coin: &T, args: ValidateTakerFundingArgs<
 where
     T: UtxoCommonOps + SwapOps,
 {
-    let maker_htlc_key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let maker_htlc_key_pair = coin.derive_htlc_key_pair(args.swap_unique_data)?;
     let total_expected_amount = &args.dex_fee_amount + &args.premium_amount + &args.trading_amount;
 
     let expected_amount_sat = sat_from_big_decimal(&total_expected_amount, coin.as_ref().decimals)?;
@@ -115,6 +115,7 @@ pub(super) fn utxo_coin_fields_for_test(
             signature_version: SignatureVersion::Base,
             required_confirmations: 1.into(),
             force_min_relay_fee: false,
+            signal_rbf: false,
             mtp_block_count: NonZeroU64::new(11).unwrap(),
             estimate_fee_mode: None,
             mature_confirmations: MATURE_CONFIRMATIONS_DEFAULT,
@@ -138,6 +139,7 @@ pub(super) fn utxo_coin_fields_for_test(
         block_headers_status_notifier: None,
         block_headers_status_watcher: None,
         abortable_system: AbortableQueue::default(),
+        ctx: MmWeak::default(),
     }
 }
 
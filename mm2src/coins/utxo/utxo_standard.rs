@@ -17,7 +17,7 @@ use crate::rpc_command::init_create_account::{self, CreateAccountRpcError, Creat
                                               InitCreateAccountRpcOps};
 use crate::rpc_command::init_scan_for_new_addresses::{self, InitScanAddressesRpcOps, ScanAddressesParams,
                                                       ScanAddressesResponse};
-use crate::rpc_command::init_withdraw::{InitWithdrawCoin, WithdrawTaskHandle};
+use crate::rpc_command::init_withdraw::{InitWithdrawCoin, InitWithdrawManyCoin, WithdrawTaskHandle};
 use crate::tx_history_storage::{GetTxHistoryFilters, WalletId};
 use crate::utxo::utxo_builder::{UtxoArcBuilder, UtxoCoinBuilder};
 use crate::utxo::utxo_tx_history_v2::{UtxoMyAddressesHistoryError, UtxoTxDetailsError, UtxoTxDetailsParams,
@@ -34,7 +34,8 @@ use crate::{CanRefundHtlc, CheckIfMyPaymentSentArgs, CoinBalance, CoinWithDeriva
             ValidateTakerFundingArgs, ValidateTakerFundingResult, ValidateTakerFundingSpendPreimageResult,
             ValidateTakerPaymentSpendPreimageResult, ValidateWatcherSpendInput, VerificationResult,
             WaitForHTLCTxSpendArgs, WatcherOps, WatcherReward, WatcherRewardError, WatcherSearchForSwapTxSpendInput,
-            WatcherValidatePaymentInput, WatcherValidateTakerFeeInput, WithdrawFut, WithdrawSenderAddress};
+            WatcherValidatePaymentInput, WatcherValidateTakerFeeInput, WithdrawFrom, WithdrawFut, WithdrawManyDetails,
+            WithdrawManyFut, WithdrawManyRequest, WithdrawSenderAddress};
 use common::executor::{AbortableSystem, AbortedError};
 use crypto::Bip44Chain;
 use futures::{FutureExt, TryFutureExt};
@@ -425,11 +426,11 @@ impl SwapOps for UtxoStandardCoin {
         Ok(None)
     }
 
-    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> KeyPair {
+    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<KeyPair> {
         utxo_common::derive_htlc_key_pair(self.as_ref(), swap_unique_data)
     }
 
-    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> Vec<u8> {
+    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Vec<u8>> {
         utxo_common::derive_htlc_pubkey(self, swap_unique_data)
     }
 
@@ -618,7 +619,7 @@ impl SwapOpsV2 for UtxoStandardCoin {
         args: &GenTakerFundingSpendArgs<'_, Self>,
         swap_unique_data: &[u8],
     ) -> GenPreimageResult<Self> {
-        let htlc_keypair = self.derive_htlc_key_pair(swap_unique_data);
+        let htlc_keypair = self.derive_htlc_key_pair(swap_unique_data)?;
         utxo_common::gen_and_sign_taker_funding_spend_preimage(self, args, &htlc_keypair).await
     }
 
@@ -636,7 +637,7 @@ impl SwapOpsV2 for UtxoStandardCoin {
         args: &GenTakerFundingSpendArgs<'_, Self>,
         swap_unique_data: &[u8],
     ) -> Result<Self::Tx, TransactionErr> {
-        let htlc_keypair = self.derive_htlc_key_pair(swap_unique_data);
+        let htlc_keypair = try_tx_s!(self.derive_htlc_key_pair(swap_unique_data));
         utxo_common::sign_and_send_taker_funding_spend(self, preimage, args, &htlc_keypair).await
     }
 
@@ -649,7 +650,7 @@ impl SwapOpsV2 for UtxoStandardCoin {
         args: &GenTakerPaymentSpendArgs<'_, Self>,
         swap_unique_data: &[u8],
     ) -> GenPreimageResult<Self> {
-        let key_pair = self.derive_htlc_key_pair(swap_unique_data);
+        let key_pair = self.derive_htlc_key_pair(swap_unique_data)?;
         utxo_common::gen_and_sign_taker_payment_spend_preimage(self, args, &key_pair).await
     }
 
@@ -668,12 +669,12 @@ impl SwapOpsV2 for UtxoStandardCoin {
         secret: &[u8],
         swap_unique_data: &[u8],
     ) -> TransactionResult {
-        let htlc_keypair = self.derive_htlc_key_pair(swap_unique_data);
+        let htlc_keypair = try_tx_s!(self.derive_htlc_key_pair(swap_unique_data));
         utxo_common::sign_and_broadcast_taker_payment_spend(self, preimage, gen_args, secret, &htlc_keypair).await
     }
 
-    fn derive_htlc_pubkey_v2(&self, swap_unique_data: &[u8]) -> Self::Pubkey {
-        *self.derive_htlc_key_pair(swap_unique_data).public()
+    fn derive_htlc_pubkey_v2(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Self::Pubkey> {
+        Ok(*self.derive_htlc_key_pair(swap_unique_data)?.public())
     }
 }
 
@@ -767,6 +768,11 @@ impl MmCoin for UtxoStandardCoin {
         Box::new(utxo_common::withdraw(self.clone(), req).boxed().compat())
     }
 
+    fn withdraw_many(&self, req: WithdrawManyRequest) -> WithdrawManyFut {
+        let fut = utxo_common::withdraw_many(self.clone(), req).map_ok(WithdrawManyDetails::from);
+        Box::new(fut.boxed().compat())
+    }
+
     fn decimals(&self) -> u8 { utxo_common::decimals(&self.utxo_arc) }
 
     fn convert_to_address(&self, from: &str, to_address_format: Json) -> Result<String, String> {
@@ -854,9 +860,9 @@ impl GetWithdrawSenderAddress for UtxoStandardCoin {
 
     async fn get_withdraw_sender_address(
         &self,
-        req: &WithdrawRequest,
+        from: Option<&WithdrawFrom>,
     ) -> MmResult<WithdrawSenderAddress<Self::Address, Self::Pubkey>, WithdrawError> {
-        utxo_common::get_withdraw_from_address(self, req).await
+        utxo_common::get_withdraw_from_address(self, from).await
     }
 }
 
@@ -867,6 +873,18 @@ impl InitWithdrawCoin for UtxoStandardCoin {
         ctx: MmArc,
         req: WithdrawRequest,
         task_handle: &WithdrawTaskHandle,
+    ) -> Result<TransactionDetails, MmError<WithdrawError>> {
+        utxo_common::init_withdraw(ctx, self.clone(), req.into(), task_handle).await
+    }
+}
+
+#[async_trait]
+impl InitWithdrawManyCoin for UtxoStandardCoin {
+    async fn init_withdraw_many(
+        &self,
+        ctx: MmArc,
+        req: WithdrawManyRequest,
+        task_handle: &WithdrawTaskHandle,
     ) -> Result<TransactionDetails, MmError<WithdrawError>> {
         utxo_common::init_withdraw(ctx, self.clone(), req, task_handle).await
     }
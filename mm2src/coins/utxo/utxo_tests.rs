@@ -31,6 +31,9 @@ use crate::utxo::utxo_tx_history_v2::{UtxoTxDetailsParams, UtxoTxHistoryOps};
 use crate::{BlockHeightAndTime, CoinBalance, ConfirmPaymentInput, DexFee, IguanaPrivKey, PrivKeyBuildPolicy,
             SearchForSwapTxSpendInput, SpendPaymentArgs, StakingInfosDetails, SwapOps, TradePreimageValue,
             TxFeeDetails, TxMarshalingErr, ValidateFeeArgs, WaitForHTLCTxSpendArgs, INVALID_SENDER_ERR_LOG};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{WithdrawManyRequest, WithdrawOutput};
+use chain::constants::{SEQUENCE_FINAL, SEQUENCE_RBF};
 use chain::{BlockHeader, BlockHeaderBits, OutPoint};
 use common::executor::Timer;
 use common::{block_on, wait_until_sec, OrdRange, PagingOptionsEnum, DEX_FEE_ADDR_RAW_PUBKEY};
@@ -277,6 +280,69 @@ fn test_generate_transaction() {
     block_on(builder.build()).unwrap_err();
 }
 
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_generate_transaction_with_required_inputs() {
+    let coin = utxo_coin_for_test(UtxoRpcClientEnum::Native(native_client_for_test()), None, false);
+    let unspent = |hash: u64, value: u64| UnspentInfo {
+        outpoint: OutPoint {
+            hash: hash.into(),
+            index: 0,
+        },
+        value,
+        height: None,
+    };
+    let outputs = vec![TransactionOutput {
+        script_pubkey: vec![].into(),
+        value: 50000,
+    }];
+
+    // The required input covers the output and the fee, so no available inputs are added.
+    let builder = UtxoTxBuilder::new(&coin)
+        .add_required_inputs(vec![unspent(1, 100000)])
+        .fund_from_required_inputs()
+        .add_available_inputs(vec![unspent(2, 200000)])
+        .add_outputs(outputs.clone());
+    let (unsigned, data) = block_on(builder.build()).unwrap();
+    assert_eq!(unsigned.inputs.len(), 1);
+    assert_eq!(unsigned.inputs[0].previous_output.hash, 1.into());
+    assert_eq!(unsigned.outputs.len(), 2);
+    assert_eq!(unsigned.outputs[1].value, 49000);
+    assert_eq!(data.fee_amount, 1000);
+    assert_eq!(data.spent_by_me, 100000);
+    assert_eq!(data.received_by_me, 49000);
+
+    // The required input is not enough, so it's topped up from the available inputs.
+    let builder = UtxoTxBuilder::new(&coin)
+        .add_required_inputs(vec![unspent(1, 30000)])
+        .fund_from_required_inputs()
+        .add_available_inputs(vec![unspent(2, 200000)])
+        .add_outputs(outputs.clone());
+    let (unsigned, data) = block_on(builder.build()).unwrap();
+    assert_eq!(unsigned.inputs.len(), 2);
+    assert_eq!(unsigned.outputs[1].value, 179000);
+    assert_eq!(data.spent_by_me, 230000);
+
+    // By default (e.g. SLP transactions), the required inputs don't fund the outputs and the fee,
+    // so the available inputs are added even though the required input would cover them.
+    let builder = UtxoTxBuilder::new(&coin)
+        .add_required_inputs(vec![unspent(1, 100000)])
+        .add_available_inputs(vec![unspent(2, 200000)])
+        .add_outputs(outputs.clone());
+    let (unsigned, data) = block_on(builder.build()).unwrap();
+    assert_eq!(unsigned.inputs.len(), 2);
+    assert_eq!(unsigned.inputs[0].previous_output.hash, 1.into());
+    assert_eq!(unsigned.outputs[1].value, 149000);
+    assert_eq!(data.spent_by_me, 200000);
+
+    // The required inputs that are not funding the transaction can't cover it without the available inputs.
+    let builder = UtxoTxBuilder::new(&coin)
+        .add_required_inputs(vec![unspent(1, 100000)])
+        .add_outputs(outputs);
+    let err = block_on(builder.build()).unwrap_err().into_inner();
+    assert!(matches!(err, GenerateTxError::NotEnoughUtxos { .. }), "{:?}", err);
+}
+
 #[test]
 fn test_addresses_from_script() {
     let client = electrum_client_for_test(DOC_ELECTRUM_ADDRS);
@@ -592,6 +658,7 @@ fn test_withdraw_impl_set_fixed_fee() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        inputs: None,
     };
     let expected = Some(
         UtxoFeeDetails {
@@ -634,6 +701,7 @@ fn test_withdraw_impl_sat_per_kb_fee() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        inputs: None,
     };
     // The resulting transaction size might be 244 or 245 bytes depending on signature size
     // MM2 always expects the worst case during fee calculation
@@ -649,6 +717,130 @@ fn test_withdraw_impl_sat_per_kb_fee() {
     assert_eq!(expected, tx_details.fee_details);
 }
 
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_withdraw_many_impl_with_max_output() {
+    UtxoStandardCoin::get_unspent_ordered_list.mock_safe(|coin, _| {
+        let cache = block_on(coin.as_ref().recently_spent_outpoints.lock());
+        let unspents = vec![UnspentInfo {
+            outpoint: OutPoint {
+                hash: 1.into(),
+                index: 0,
+            },
+            value: 1000000000,
+            height: Default::default(),
+        }];
+        MockResult::Return(Box::pin(futures::future::ok((unspents, cache))))
+    });
+
+    let client = NativeClient(Arc::new(NativeClientImpl::default()));
+
+    let coin = utxo_coin_for_test(UtxoRpcClientEnum::Native(client), None, false);
+
+    let withdraw_req = WithdrawManyRequest {
+        coin: TEST_COIN_NAME.into(),
+        from: None,
+        outputs: vec![
+            WithdrawOutput {
+                to: "RQq6fWoy8aGGMLjvRfMY5mBNVm2RQxJyLa".to_string(),
+                amount: 1u64.into(),
+                max: false,
+            },
+            WithdrawOutput {
+                to: "RJTYiYeJ8eVvJ53n2YbrVmxWNNMVZjDGLh".to_string(),
+                amount: 0.into(),
+                max: true,
+            },
+        ],
+        fee: Some(WithdrawFee::UtxoFixed {
+            amount: "0.1".parse().unwrap(),
+        }),
+        memo: None,
+        inputs: None,
+    };
+    let tx_details = coin.withdraw_many(withdraw_req).wait().unwrap().details;
+    assert_eq!(tx_details.to, vec![
+        "RQq6fWoy8aGGMLjvRfMY5mBNVm2RQxJyLa".to_string(),
+        "RJTYiYeJ8eVvJ53n2YbrVmxWNNMVZjDGLh".to_string()
+    ]);
+    assert_eq!(tx_details.spent_by_me, BigDecimal::from(10));
+
+    // The fee is deducted from the `max` output, so there is no change output.
+    let tx: UtxoTx = deserialize(tx_details.tx_hex.as_slice()).unwrap();
+    let output_values: Vec<_> = tx.outputs.iter().map(|output| output.value).collect();
+    assert_eq!(output_values, vec![100000000, 890000000]);
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_withdraw_impl_with_explicit_inputs() {
+    UtxoStandardCoin::get_unspent_ordered_list.mock_safe(|coin, _| {
+        let cache = block_on(coin.as_ref().recently_spent_outpoints.lock());
+        let unspents = vec![
+            UnspentInfo {
+                outpoint: OutPoint {
+                    hash: 1.into(),
+                    index: 0,
+                },
+                value: 1000000000,
+                height: Default::default(),
+            },
+            UnspentInfo {
+                outpoint: OutPoint {
+                    hash: 2.into(),
+                    index: 1,
+                },
+                value: 1000000000,
+                height: Default::default(),
+            },
+        ];
+        MockResult::Return(Box::pin(futures::future::ok((unspents, cache))))
+    });
+
+    let client = NativeClient(Arc::new(NativeClientImpl::default()));
+
+    let coin = utxo_coin_for_test(UtxoRpcClientEnum::Native(client), None, false);
+
+    let chosen_input = OutPoint {
+        hash: 2.into(),
+        index: 1,
+    };
+    let withdraw_req = WithdrawRequest {
+        amount: 1u64.into(),
+        from: None,
+        to: "RQq6fWoy8aGGMLjvRfMY5mBNVm2RQxJyLa".to_string(),
+        coin: TEST_COIN_NAME.into(),
+        max: false,
+        fee: Some(WithdrawFee::UtxoFixed {
+            amount: "0.1".parse().unwrap(),
+        }),
+        memo: None,
+        inputs: Some(vec![chosen_input.into()]),
+    };
+    let tx_details = coin.withdraw(withdraw_req.clone()).wait().unwrap();
+    let tx: UtxoTx = deserialize(tx_details.tx_hex.as_slice()).unwrap();
+    let previous_outputs: Vec<_> = tx.inputs.iter().map(|input| input.previous_output).collect();
+    assert_eq!(previous_outputs, vec![chosen_input]);
+
+    // The input that isn't among the spendable outputs is rejected.
+    let unknown_input = UtxoOutPoint::from(OutPoint {
+        hash: 3.into(),
+        index: 0,
+    });
+    let withdraw_req = WithdrawRequest {
+        inputs: Some(vec![unknown_input]),
+        ..withdraw_req
+    };
+    let error = coin.withdraw(withdraw_req).wait().unwrap_err().into_inner();
+    match error {
+        WithdrawError::InputNotSpendable { tx_hash, index } => {
+            assert_eq!(tx_hash, unknown_input.tx_hash);
+            assert_eq!(index, unknown_input.index);
+        },
+        e => panic!("Expected 'InputNotSpendable', found {:?}", e),
+    }
+}
+
 #[test]
 #[cfg(not(target_arch = "wasm32"))]
 fn test_withdraw_impl_sat_per_kb_fee_amount_equal_to_max() {
@@ -679,6 +871,7 @@ fn test_withdraw_impl_sat_per_kb_fee_amount_equal_to_max() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        inputs: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     // The resulting transaction size might be 210 or 211 bytes depending on signature size
@@ -726,6 +919,7 @@ fn test_withdraw_impl_sat_per_kb_fee_amount_equal_to_max_dust_included_to_fee()
             amount: "0.09999999".parse().unwrap(),
         }),
         memo: None,
+        inputs: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     // The resulting transaction size might be 210 or 211 bytes depending on signature size
@@ -773,6 +967,7 @@ fn test_withdraw_impl_sat_per_kb_fee_amount_over_max() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        inputs: None,
     };
     coin.withdraw(withdraw_req).wait().unwrap_err();
 }
@@ -807,6 +1002,7 @@ fn test_withdraw_impl_sat_per_kb_fee_max() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        inputs: None,
     };
     // The resulting transaction size might be 210 or 211 bytes depending on signature size
     // MM2 always expects the worst case during fee calculation
@@ -867,6 +1063,7 @@ fn test_withdraw_kmd_rewards_impl(
         max: false,
         fee: None,
         memo: None,
+        inputs: None,
     };
     let expected_fee = TxFeeDetails::Utxo(UtxoFeeDetails {
         coin: Some("KMD".into()),
@@ -942,6 +1139,7 @@ fn test_withdraw_rick_rewards_none() {
         max: false,
         fee: None,
         memo: None,
+        inputs: None,
     };
     let expected_fee = TxFeeDetails::Utxo(UtxoFeeDetails {
         coin: Some(TEST_COIN_NAME.into()),
@@ -1160,6 +1358,42 @@ fn test_generate_transaction_relay_fee_is_used_when_dynamic_fee_is_lower() {
     assert!(unsafe { GET_RELAY_FEE_CALLED });
 }
 
+#[test]
+fn test_generate_transaction_signals_rbf() {
+    let client = UtxoRpcClientEnum::Native(NativeClient(Arc::new(NativeClientImpl::default())));
+    let mut coin = utxo_coin_fields_for_test(client, None, false);
+    coin.conf.signal_rbf = true;
+    let coin = utxo_coin_from_fields(coin);
+    let unspents = vec![UnspentInfo {
+        value: 1000000000,
+        outpoint: OutPoint::default(),
+        height: Default::default(),
+    }];
+
+    let outputs = vec![TransactionOutput {
+        script_pubkey: vec![].into(),
+        value: 900000000,
+    }];
+
+    let builder = UtxoTxBuilder::new(&coin)
+        .add_available_inputs(unspents.clone())
+        .add_outputs(outputs.clone())
+        .with_fee(ActualTxFee::FixedPerKb(1000))
+        .replaceable();
+
+    let generated = block_on(builder.build()).unwrap();
+    assert!(generated.0.inputs.iter().all(|input| input.sequence == SEQUENCE_RBF));
+
+    // The transactions are not replaceable by default, e.g. the swap payments.
+    let builder = UtxoTxBuilder::new(&coin)
+        .add_available_inputs(unspents)
+        .add_outputs(outputs)
+        .with_fee(ActualTxFee::FixedPerKb(1000));
+
+    let generated = block_on(builder.build()).unwrap();
+    assert!(generated.0.inputs.iter().all(|input| input.sequence == SEQUENCE_FINAL));
+}
+
 #[test]
 #[cfg(not(target_arch = "wasm32"))]
 // https://github.com/KomodoPlatform/atomicDEX-API/issues/1037
@@ -3071,6 +3305,7 @@ fn test_withdraw_to_p2pkh() {
         max: false,
         fee: None,
         memo: None,
+        inputs: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     let transaction: UtxoTx = deserialize(tx_details.tx_hex.as_slice()).unwrap();
@@ -3119,6 +3354,7 @@ fn test_withdraw_to_p2sh() {
         max: false,
         fee: None,
         memo: None,
+        inputs: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     let transaction: UtxoTx = deserialize(tx_details.tx_hex.as_slice()).unwrap();
@@ -3167,6 +3403,7 @@ fn test_withdraw_to_p2wpkh() {
         max: false,
         fee: None,
         memo: None,
+        inputs: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     let transaction: UtxoTx = deserialize(tx_details.tx_hex.as_slice()).unwrap();
@@ -3177,6 +3414,109 @@ fn test_withdraw_to_p2wpkh() {
     assert_eq!(output_script, expected_script);
 }
 
+/// Withdraws 1 coin from the only 10 coins output of the wallet address as PSBT,
+/// signs and finalizes it, and checks that it's the same transaction as the one signed directly.
+#[cfg(not(target_arch = "wasm32"))]
+fn withdraw_psbt_sign_and_finalize(is_segwit: bool) {
+    use crate::utxo::utxo_withdraw::{StandardUtxoWithdraw, UtxoWithdraw};
+    use chain::Psbt;
+    use script::UnsignedTransactionInput;
+    use utxo_signer::with_psbt::{finalize_psbt, sign_psbt};
+
+    let client = NativeClient(Arc::new(NativeClientImpl::default()));
+    let mut fields = utxo_coin_fields_for_test(UtxoRpcClientEnum::Native(client), None, is_segwit);
+    // PSBT is supported by the plain Bitcoin transaction format only.
+    fields.conf.overwintered = false;
+    fields.conf.zcash = false;
+    fields.conf.tx_version = 2;
+    let coin = utxo_coin_from_fields(fields);
+    let my_address = coin.as_ref().derivation_method.unwrap_single_addr().clone();
+
+    let mut prev_tx = coin.as_ref().transaction_preimage();
+    prev_tx.inputs.push(UnsignedTransactionInput {
+        previous_output: OutPoint {
+            hash: 1.into(),
+            index: 0,
+        },
+        sequence: SEQUENCE_FINAL,
+        amount: 0,
+        witness: vec![],
+    });
+    prev_tx.outputs.push(TransactionOutput {
+        value: 1000000000,
+        script_pubkey: output_script(&my_address, ScriptType::P2PKH).to_bytes(),
+    });
+    let prev_tx = UtxoTx::from(prev_tx);
+    let prev_hash = prev_tx.hash();
+
+    UtxoStandardCoin::get_unspent_ordered_list.mock_safe(move |coin, _| {
+        let cache = coin.as_ref().recently_spent_outpoints.try_lock().unwrap();
+        let unspents = vec![UnspentInfo {
+            outpoint: OutPoint {
+                hash: prev_hash,
+                index: 0,
+            },
+            value: 1000000000,
+            height: Default::default(),
+        }];
+        MockResult::Return(Box::pin(futures::future::ok((unspents, cache))))
+    });
+    let prev_tx_bytes: BytesJson = serialize(&prev_tx).into();
+    NativeClient::get_transaction_bytes.mock_safe(move |_, txid| {
+        assert_eq!(*txid, H256Json::from(prev_hash.reversed()));
+        MockResult::Return(Box::new(futures01::future::ok(prev_tx_bytes.clone())))
+    });
+
+    let withdraw_req = WithdrawRequest {
+        amount: 1.into(),
+        from: None,
+        to: my_address.to_string(),
+        coin: TEST_COIN_NAME.into(),
+        max: false,
+        fee: None,
+        memo: None,
+        inputs: None,
+    };
+    let withdraw = StandardUtxoWithdraw::new(coin.clone(), withdraw_req.into()).unwrap();
+    let (psbt, _) = block_on(withdraw.build_psbt()).unwrap();
+    // The whole previous transaction is provided for both the segwit and non-segwit inputs.
+    let non_witness_utxo = psbt.inputs[0].non_witness_utxo.as_ref().map(|prev_tx| prev_tx.hash());
+    assert_eq!(non_witness_utxo, Some(prev_hash));
+    assert_eq!(psbt.inputs[0].witness_utxo.is_some(), is_segwit);
+    assert_eq!(psbt.spent_output(0).unwrap().value, 1000000000);
+
+    let mut psbt: Psbt = deserialize(serialize(&psbt).as_slice()).unwrap();
+    let key_pair = coin.as_ref().priv_key_policy.activated_key_or_err().unwrap();
+    assert_eq!(sign_psbt(&mut psbt, key_pair, SignatureVersion::Base, 0).unwrap(), 1);
+    let signed = finalize_psbt(psbt.clone()).unwrap();
+    if is_segwit {
+        assert!(signed.inputs[0].script_sig.is_empty());
+        assert_eq!(signed.inputs[0].script_witness.len(), 2);
+    } else {
+        assert!(!signed.inputs[0].script_sig.is_empty());
+        assert!(signed.inputs[0].script_witness.is_empty());
+    }
+
+    let mut unsigned = TransactionInputSigner::from(psbt.unsigned_tx);
+    unsigned.inputs[0].amount = 1000000000;
+    let prev_script = Builder::build_p2pkh(&my_address.hash);
+    let signature_version = if is_segwit {
+        SignatureVersion::WitnessV0
+    } else {
+        SignatureVersion::Base
+    };
+    let expected = sign_tx(unsigned, key_pair, prev_script, signature_version, 0).unwrap();
+    assert_eq!(signed, expected);
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_withdraw_psbt_sign_and_finalize() { withdraw_psbt_sign_and_finalize(true) }
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_withdraw_psbt_p2pkh_sign_and_finalize() { withdraw_psbt_sign_and_finalize(false) }
+
 /// `UtxoStandardCoin` has to check UTXO maturity if `check_utxo_maturity` is `true`.
 /// https://github.com/KomodoPlatform/atomicDEX-API/issues/1181
 #[test]
@@ -4617,3 +4957,555 @@ fn test_block_header_utxo_loop_with_reorg() {
         panic!("Loop shouldn't stop")
     };
 }
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_freeze_unfreeze_list_unspent() {
+    use crate::rpc_command::utxo_coin_control::{freeze_outpoints, list_unspent, prune_frozen_outpoints,
+                                                unfreeze_outpoints, CoinControlError};
+    use crate::utxo::frozen_outpoints_storage::load_frozen_outpoints;
+    use crate::MmCoinEnum;
+    use std::sync::atomic::Ordering;
+
+    let first = OutPoint {
+        hash: 1.into(),
+        index: 0,
+    };
+    let second = OutPoint {
+        hash: 2.into(),
+        index: 1,
+    };
+    let first_json = json::to_value(UtxoOutPoint::from(first)).unwrap();
+    let second_json = json::to_value(UtxoOutPoint::from(second)).unwrap();
+
+    // Whether the `first` output is spent already.
+    let first_spent = Arc::new(AtomicBool::new(false));
+    let first_spent_mock = first_spent.clone();
+    UtxoStandardCoin::get_all_unspent_ordered_map.mock_safe(move |coin, addresses| {
+        let cache = coin.as_ref().recently_spent_outpoints.try_lock().unwrap();
+        let mut unspents = vec![UnspentInfo {
+            outpoint: second,
+            value: 50000000,
+            height: None,
+        }];
+        if !first_spent_mock.load(Ordering::Relaxed) {
+            unspents.push(UnspentInfo {
+                outpoint: first,
+                value: 100000000,
+                height: Some(10),
+            });
+        }
+        let unspents_map = addresses
+            .into_iter()
+            .map(|address| (address, unspents.clone()))
+            .collect();
+        MockResult::Return(Box::pin(futures::future::ok((unspents_map, cache))))
+    });
+    NativeClient::get_block_count.mock_safe(|_| MockResult::Return(Box::new(futures01::future::ok(20))));
+
+    let ctx = mm_ctx_with_custom_db();
+    let mut fields = utxo_coin_fields_for_test(UtxoRpcClientEnum::Native(native_client_for_test()), None, false);
+    fields.ctx = ctx.weak();
+    let coin = utxo_coin_from_fields(fields);
+    let coins_ctx = CoinsContext::from_ctx(&ctx).unwrap();
+    block_on(coins_ctx.add_token(MmCoinEnum::UtxoCoin(coin.clone()))).unwrap();
+
+    let coin_req = json!({ "coin": TEST_COIN_NAME });
+    let list = block_on(list_unspent(ctx.clone(), json::from_value(coin_req.clone()).unwrap())).unwrap();
+    let list = json::to_value(list).unwrap();
+    let unspents = list["unspents"].as_array().unwrap();
+    assert_eq!(unspents.len(), 2);
+    assert!(unspents.iter().all(|unspent| unspent["is_frozen"] == false));
+    let first_unspent = unspents.iter().find(|unspent| unspent["index"] == 0).unwrap();
+    assert_eq!(first_unspent["tx_hash"], first_json["tx_hash"]);
+    assert_eq!(first_unspent["confirmations"], 11);
+    let first_value: BigDecimal = json::from_value(first_unspent["value"].clone()).unwrap();
+    assert_eq!(first_value, BigDecimal::from(1));
+
+    let freeze_req = json!({ "coin": TEST_COIN_NAME, "outpoints": [first_json] });
+    let frozen = block_on(freeze_outpoints(ctx.clone(), json::from_value(freeze_req).unwrap())).unwrap();
+    assert_eq!(json::to_value(frozen).unwrap(), json!({ "frozen": [first_json] }));
+    assert_eq!(coins_ctx.frozen_amount(TEST_COIN_NAME), BigDecimal::from(1));
+    // The frozen outputs are persisted.
+    let stored = block_on(load_frozen_outpoints(&ctx, TEST_COIN_NAME)).unwrap();
+    assert_eq!(stored.keys().collect::<Vec<_>>(), vec![&first]);
+
+    let list = block_on(list_unspent(ctx.clone(), json::from_value(coin_req.clone()).unwrap())).unwrap();
+    let list = json::to_value(list).unwrap();
+    for unspent in list["unspents"].as_array().unwrap() {
+        assert_eq!(unspent["is_frozen"], unspent["index"] == 0);
+    }
+
+    // The frozen outputs are excluded from the automatic coin selection.
+    let my_address = coin.as_ref().derivation_method.unwrap_single_addr().clone();
+    let (unspents_map, _) = block_on(coin.get_unspent_ordered_map(vec![my_address])).unwrap();
+    let selectable: Vec<_> = unspents_map
+        .into_values()
+        .flatten()
+        .map(|unspent| unspent.outpoint)
+        .collect();
+    assert_eq!(selectable, vec![second]);
+
+    // Only the unspent outputs of the wallet can be frozen.
+    let unknown = OutPoint {
+        hash: 3.into(),
+        index: 0,
+    };
+    let unknown_req = json!({ "coin": TEST_COIN_NAME, "outpoints": [UtxoOutPoint::from(unknown)] });
+    let err = block_on(freeze_outpoints(ctx.clone(), json::from_value(unknown_req).unwrap()))
+        .unwrap_err()
+        .into_inner();
+    assert!(matches!(err, CoinControlError::UnknownOutPoint { .. }), "{:?}", err);
+
+    // The spent frozen outputs are not forgotten by `list_unspent`, only by `prune_frozen_outpoints`.
+    first_spent.store(true, Ordering::Relaxed);
+    let list = block_on(list_unspent(ctx.clone(), json::from_value(coin_req.clone()).unwrap())).unwrap();
+    assert_eq!(json::to_value(list).unwrap()["unspents"].as_array().unwrap().len(), 1);
+    assert_eq!(coins_ctx.frozen_outpoints(TEST_COIN_NAME).len(), 1);
+
+    let pruned = block_on(prune_frozen_outpoints(ctx.clone(), json::from_value(coin_req).unwrap())).unwrap();
+    assert_eq!(
+        json::to_value(pruned).unwrap(),
+        json!({ "pruned": [first_json], "frozen": [] })
+    );
+    assert!(block_on(load_frozen_outpoints(&ctx, TEST_COIN_NAME))
+        .unwrap()
+        .is_empty());
+
+    let freeze_req = json!({ "coin": TEST_COIN_NAME, "outpoints": [second_json] });
+    block_on(freeze_outpoints(
+        ctx.clone(),
+        json::from_value(freeze_req.clone()).unwrap(),
+    ))
+    .unwrap();
+    let unfrozen = block_on(unfreeze_outpoints(ctx.clone(), json::from_value(freeze_req).unwrap())).unwrap();
+    assert_eq!(json::to_value(unfrozen).unwrap(), json!({ "frozen": [] }));
+    assert!(coins_ctx.frozen_outpoints(TEST_COIN_NAME).is_empty());
+    assert!(block_on(load_frozen_outpoints(&ctx, TEST_COIN_NAME))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_frozen_outpoints_reloaded_on_activation() {
+    use crate::utxo::frozen_outpoints_storage::save_frozen_outpoints;
+    use crate::utxo::utxo_common::get_frozen_outpoints;
+    use crate::{lp_register_coin, MmCoinEnum, RegisterCoinParams};
+
+    let ctx = mm_ctx_with_custom_db();
+    let mut frozen = FrozenOutPoints::new();
+    frozen.insert(
+        OutPoint {
+            hash: 1.into(),
+            index: 0,
+        },
+        "0.5".parse().unwrap(),
+    );
+    block_on(save_frozen_outpoints(&ctx, TEST_COIN_NAME, &frozen)).unwrap();
+
+    let mut fields = utxo_coin_fields_for_test(UtxoRpcClientEnum::Native(native_client_for_test()), None, false);
+    fields.ctx = ctx.weak();
+    let coin = utxo_coin_from_fields(fields);
+    let params = RegisterCoinParams {
+        ticker: TEST_COIN_NAME.to_owned(),
+    };
+    block_on(lp_register_coin(&ctx, MmCoinEnum::UtxoCoin(coin.clone()), params)).unwrap();
+
+    let coins_ctx = CoinsContext::from_ctx(&ctx).unwrap();
+    assert_eq!(coins_ctx.frozen_outpoints(TEST_COIN_NAME), frozen);
+    assert_eq!(get_frozen_outpoints(&coin), frozen);
+}
+
+/// Registers the RBF signalling coin and mocks the RPCs [`crate::rpc_command::bump_fee::bump_fee`] relies on.
+/// Returns the unconfirmed transaction paying 0.0001 fee from the only 1 coin output of the wallet,
+/// and sending `change` back to the wallet address as the last output.
+#[cfg(not(target_arch = "wasm32"))]
+fn bump_fee_test_setup(sequence: u32, change: u64) -> (MmArc, UtxoStandardCoin, UtxoTx) {
+    use crate::MmCoinEnum;
+    use script::UnsignedTransactionInput;
+
+    let ctx = mm_ctx_with_custom_db();
+    let mut fields = utxo_coin_fields_for_test(UtxoRpcClientEnum::Native(native_client_for_test()), None, false);
+    fields.conf.signal_rbf = true;
+    fields.ctx = ctx.weak();
+    let coin = utxo_coin_from_fields(fields);
+    let coins_ctx = CoinsContext::from_ctx(&ctx).unwrap();
+    block_on(coins_ctx.add_token(MmCoinEnum::UtxoCoin(coin.clone()))).unwrap();
+
+    let my_address = coin.as_ref().derivation_method.unwrap_single_addr().clone();
+    let my_script = output_script(&my_address, ScriptType::P2PKH).to_bytes();
+    let other_script = Builder::build_p2pkh(&AddressHashEnum::AddressHash(H160::from([1; 20]))).to_bytes();
+
+    let mut prev_tx = coin.as_ref().transaction_preimage();
+    prev_tx.inputs.push(UnsignedTransactionInput {
+        previous_output: OutPoint {
+            hash: 1.into(),
+            index: 0,
+        },
+        sequence: SEQUENCE_FINAL,
+        amount: 200000000,
+        witness: vec![],
+    });
+    prev_tx.outputs.push(TransactionOutput {
+        value: 100000000,
+        script_pubkey: my_script.clone(),
+    });
+    let prev_tx = UtxoTx::from(prev_tx);
+
+    let mut tx = coin.as_ref().transaction_preimage();
+    tx.inputs.push(UnsignedTransactionInput {
+        previous_output: OutPoint {
+            hash: prev_tx.hash(),
+            index: 0,
+        },
+        sequence,
+        amount: 100000000,
+        witness: vec![],
+    });
+    tx.outputs.push(TransactionOutput {
+        value: 100000000 - 10000 - change,
+        script_pubkey: other_script,
+    });
+    tx.outputs.push(TransactionOutput {
+        value: change,
+        script_pubkey: my_script,
+    });
+    let tx = UtxoTx::from(tx);
+
+    let verbose = RpcTransaction {
+        hex: serialize(&tx).into(),
+        txid: tx.hash().reversed().into(),
+        hash: None,
+        size: None,
+        vsize: None,
+        version: tx.version,
+        locktime: tx.lock_time,
+        vin: vec![],
+        vout: vec![],
+        blockhash: H256Json::default(),
+        confirmations: 0,
+        rawconfirmations: None,
+        time: 0,
+        blocktime: 0,
+        height: None,
+    };
+    NativeClient::get_verbose_transaction
+        .mock_safe(move |_, _| MockResult::Return(Box::new(futures01::future::ok(verbose.clone()))));
+    let prev_tx_bytes: BytesJson = serialize(&prev_tx).into();
+    NativeClient::get_transaction_bytes
+        .mock_safe(move |_, _| MockResult::Return(Box::new(futures01::future::ok(prev_tx_bytes.clone()))));
+    NativeClient::get_relay_fee
+        .mock_safe(|_| MockResult::Return(Box::new(futures01::future::ok("0.00001".parse().unwrap()))));
+    // The parent output is spent by the transaction already, so only the change is listed.
+    let change_unspent = UnspentInfo {
+        outpoint: OutPoint {
+            hash: tx.hash(),
+            index: 1,
+        },
+        value: change,
+        height: None,
+    };
+    UtxoStandardCoin::get_unspent_ordered_list.mock_safe(move |coin, _| {
+        let cache = coin.as_ref().recently_spent_outpoints.try_lock().unwrap();
+        MockResult::Return(Box::pin(futures::future::ok((vec![change_unspent.clone()], cache))))
+    });
+    UtxoStandardCoin::broadcast_tx
+        .mock_safe(|_, tx| MockResult::Return(Box::pin(futures::future::ok(tx.hash().reversed().into()))));
+
+    (ctx, coin, tx)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn bump_fee_req(method: &str, fixed_fee: &str) -> crate::rpc_command::bump_fee::BumpFeeRequest {
+    json::from_value(json!({
+        "coin": TEST_COIN_NAME,
+        "tx_hash": "0000000000000000000000000000000000000000000000000000000000000000",
+        "method": method,
+        "fee": { "type": "UtxoFixed", "amount": fixed_fee },
+    }))
+    .unwrap()
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_bump_fee_rbf_not_signalling() {
+    use crate::rpc_command::bump_fee::{bump_fee, BumpFeeError};
+
+    let (ctx, _coin, _tx) = bump_fee_test_setup(SEQUENCE_FINAL, 49990000);
+    let error = block_on(bump_fee(ctx, bump_fee_req("Rbf", "0.0002")))
+        .unwrap_err()
+        .into_inner();
+    assert!(matches!(error, BumpFeeError::TxNotReplaceable(_)), "{:?}", error);
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_bump_fee_rbf_recomputes_change() {
+    use crate::rpc_command::bump_fee::bump_fee;
+
+    let (ctx, coin, tx) = bump_fee_test_setup(SEQUENCE_RBF, 49990000);
+    let my_address = coin.as_ref().derivation_method.unwrap_single_addr().clone();
+    // The replaced transaction was sent from the wallet, so its outputs are cached.
+    let spent = vec![UnspentInfo {
+        outpoint: tx.inputs[0].previous_output,
+        value: 100000000,
+        height: None,
+    }];
+    block_on(coin.as_ref().recently_spent_outpoints.lock()).add_spent(spent, tx.hash(), tx.outputs.clone());
+
+    let details = block_on(bump_fee(ctx, bump_fee_req("Rbf", "0.0002"))).unwrap();
+    let expected_fee = TxFeeDetails::Utxo(UtxoFeeDetails {
+        coin: Some(TEST_COIN_NAME.into()),
+        amount: "0.0002".parse().unwrap(),
+    });
+    assert_eq!(details.fee_details, Some(expected_fee));
+
+    let replacement: UtxoTx = deserialize(details.tx_hex.as_slice()).unwrap();
+    assert_eq!(replacement.inputs.len(), 1);
+    assert_eq!(replacement.inputs[0].previous_output, tx.inputs[0].previous_output);
+    assert_eq!(replacement.inputs[0].sequence, SEQUENCE_RBF);
+    assert_eq!(replacement.outputs.len(), 2);
+    assert_eq!(replacement.outputs[0], tx.outputs[0]);
+    assert_eq!(replacement.outputs[1].value, 49980000);
+    assert_eq!(
+        replacement.outputs[1].script_pubkey,
+        output_script(&my_address, ScriptType::P2PKH).to_bytes()
+    );
+
+    // The outputs of the replaced transaction are not spendable anymore.
+    let cache = block_on(coin.as_ref().recently_spent_outpoints.lock());
+    assert!(cache
+        .output_to_input_map
+        .keys()
+        .all(|output| output.outpoint.hash != tx.hash()));
+    assert!(cache
+        .input_to_output_map
+        .values()
+        .flatten()
+        .all(|output| output.outpoint.hash != tx.hash()));
+    assert!(cache
+        .output_to_input_map
+        .keys()
+        .any(|output| output.outpoint.hash == replacement.hash()));
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_bump_fee_rbf_fee_not_increased() {
+    use crate::rpc_command::bump_fee::{bump_fee, BumpFeeError};
+
+    let (ctx, _coin, _tx) = bump_fee_test_setup(SEQUENCE_RBF, 49990000);
+    let error = block_on(bump_fee(ctx, bump_fee_req("Rbf", "0.0001")))
+        .unwrap_err()
+        .into_inner();
+    assert!(matches!(error, BumpFeeError::FeeNotIncreased { .. }), "{:?}", error);
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_bump_fee_rbf_relay_fee_not_paid() {
+    use crate::rpc_command::bump_fee::{bump_fee, BumpFeeError};
+
+    let (ctx, _coin, _tx) = bump_fee_test_setup(SEQUENCE_RBF, 49990000);
+    // The fee is increased by 100 satoshis only, while the replacement relay costs about 0.00001 per kB.
+    let error = block_on(bump_fee(ctx, bump_fee_req("Rbf", "0.000101")))
+        .unwrap_err()
+        .into_inner();
+    match error {
+        BumpFeeError::ReplacementFeeTooLow { new_fee, required_fee } => {
+            assert_eq!(new_fee, "0.000101".parse().unwrap());
+            assert!(required_fee > new_fee);
+        },
+        e => panic!("Expected ReplacementFeeTooLow, found {:?}", e),
+    }
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_bump_fee_cpfp() {
+    use crate::rpc_command::bump_fee::bump_fee;
+
+    let (ctx, coin, tx) = bump_fee_test_setup(SEQUENCE_FINAL, 49990000);
+    // The package of the parent and the child is less than 1 kB, so it should pay 0.0005 in total.
+    let details = block_on(bump_fee(ctx, bump_fee_req("Cpfp", "0.0005"))).unwrap();
+    let expected_fee = TxFeeDetails::Utxo(UtxoFeeDetails {
+        coin: Some(TEST_COIN_NAME.into()),
+        amount: "0.0004".parse().unwrap(),
+    });
+    assert_eq!(details.fee_details, Some(expected_fee));
+
+    let child: UtxoTx = deserialize(details.tx_hex.as_slice()).unwrap();
+    assert_eq!(child.inputs.len(), 1);
+    assert_eq!(child.inputs[0].previous_output, OutPoint {
+        hash: tx.hash(),
+        index: 1,
+    });
+    assert_eq!(child.inputs[0].sequence, SEQUENCE_RBF);
+    assert_eq!(child.outputs.len(), 1);
+    assert_eq!(child.outputs[0].value, 49990000 - 40000);
+    let my_address = coin.as_ref().derivation_method.unwrap_single_addr().clone();
+    assert_eq!(
+        child.outputs[0].script_pubkey,
+        output_script(&my_address, ScriptType::P2PKH).to_bytes()
+    );
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_bump_fee_cpfp_insufficient_change() {
+    use crate::rpc_command::bump_fee::{bump_fee, BumpFeeError};
+
+    let (ctx, _coin, _tx) = bump_fee_test_setup(SEQUENCE_FINAL, 20000);
+    let error = block_on(bump_fee(ctx, bump_fee_req("Cpfp", "0.0005")))
+        .unwrap_err()
+        .into_inner();
+    match error {
+        BumpFeeError::NotSufficientBalance {
+            available, required, ..
+        } => {
+            assert_eq!(available, "0.0002".parse().unwrap());
+            // The child fee 0.0004 and the dust left in the output.
+            assert_eq!(required, "0.00041".parse().unwrap());
+        },
+        e => panic!("Expected NotSufficientBalance, found {:?}", e),
+    }
+}
+
+/// Registers the coin having the outputs of the given `values` and returns the consolidation request for it.
+#[cfg(not(target_arch = "wasm32"))]
+fn consolidate_utxos_test_setup(
+    mut fields: UtxoCoinFields,
+    values: Vec<u64>,
+    mut req: Json,
+) -> (
+    MmArc,
+    UtxoStandardCoin,
+    crate::rpc_command::consolidate_utxos::ConsolidateUtxosRequest,
+) {
+    use crate::MmCoinEnum;
+
+    let ctx = mm_ctx_with_custom_db();
+    fields.ctx = ctx.weak();
+    let coin = utxo_coin_from_fields(fields);
+    let coins_ctx = CoinsContext::from_ctx(&ctx).unwrap();
+    block_on(coins_ctx.add_token(MmCoinEnum::UtxoCoin(coin.clone()))).unwrap();
+
+    UtxoStandardCoin::get_unspent_ordered_list.mock_safe(move |coin, _| {
+        let cache = coin.as_ref().recently_spent_outpoints.try_lock().unwrap();
+        let unspents = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| UnspentInfo {
+                outpoint: OutPoint {
+                    hash: (i as u8 + 1).into(),
+                    index: 0,
+                },
+                value: *value,
+                height: None,
+            })
+            .collect();
+        MockResult::Return(Box::pin(futures::future::ok((unspents, cache))))
+    });
+
+    req["coin"] = TEST_COIN_NAME.into();
+    (ctx, coin, json::from_value(req).unwrap())
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_consolidate_utxos_fee_rate_too_high() {
+    use crate::rpc_command::consolidate_utxos::{consolidate_utxos, ConsolidateUtxosError};
+
+    let fields = utxo_coin_fields_for_test(UtxoRpcClientEnum::Native(native_client_for_test()), None, false);
+    let req = json!({ "max_fee_per_kbyte": "0.000005" });
+    let (ctx, _coin, req) = consolidate_utxos_test_setup(fields, vec![10000, 20000], req);
+    UtxoStandardCoin::broadcast_tx.mock_safe(|_, _| panic!("The consolidation shouldn't be broadcasted"));
+
+    let error = block_on(consolidate_utxos(ctx, req, 0.into()))
+        .unwrap_err()
+        .into_inner();
+    match error {
+        ConsolidateUtxosError::FeeRateTooHigh {
+            fee_per_kbyte,
+            max_fee_per_kbyte,
+        } => {
+            assert_eq!(fee_per_kbyte, "0.00001".parse().unwrap());
+            assert_eq!(max_fee_per_kbyte, "0.000005".parse().unwrap());
+        },
+        e => panic!("Expected FeeRateTooHigh, found {:?}", e),
+    }
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_consolidate_utxos_preview_skips_dust_and_reserves_for_swaps() {
+    use crate::rpc_command::consolidate_utxos::consolidate_utxos;
+
+    let fields = utxo_coin_fields_for_test(UtxoRpcClientEnum::Native(native_client_for_test()), None, false);
+    let values = vec![100000000, 50000000, 20000, 10000, 1000, 500];
+    let req = json!({ "preview": true });
+    let (ctx, coin, req) = consolidate_utxos_test_setup(fields, values, req);
+    UtxoStandardCoin::broadcast_tx.mock_safe(|_, _| panic!("The preview shouldn't be broadcasted"));
+
+    // The largest output covers the amount locked by the swaps.
+    let res = block_on(consolidate_utxos(ctx, req, 1.into())).unwrap();
+    let res = json::to_value(res).unwrap();
+    let fee_per_kbyte: BigDecimal = json::from_value(res["fee_per_kbyte"].clone()).unwrap();
+    assert_eq!(fee_per_kbyte, "0.00001".parse().unwrap());
+    let consolidation = &res["consolidations"][0];
+    assert_eq!(consolidation["reserved_for_swaps"], 1);
+    // The fixed fee per started kbyte exceeds the values of the smallest outputs.
+    assert_eq!(consolidation["dust_skipped"], 2);
+    let merged = consolidation["merged"].as_array().unwrap();
+    assert_eq!(merged.len(), 3);
+
+    let tx_hex: BytesJson = json::from_value(consolidation["tx"]["tx_hex"].clone()).unwrap();
+    let tx: UtxoTx = deserialize(tx_hex.as_slice()).unwrap();
+    assert_eq!(tx.inputs.len(), 3);
+    assert_eq!(tx.outputs.len(), 1);
+    assert_eq!(tx.outputs[0].value, 50000000 + 20000 + 10000 - 1000);
+
+    // Nothing is recorded as spent since the transaction isn't broadcasted.
+    let cache = block_on(coin.as_ref().recently_spent_outpoints.lock());
+    assert!(cache.input_to_output_map.is_empty());
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_consolidate_utxos_segwit() {
+    use crate::rpc_command::consolidate_utxos::consolidate_utxos;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static BROADCASTED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut fields = utxo_coin_fields_for_test(UtxoRpcClientEnum::Native(native_client_for_test()), None, true);
+    fields.conf.overwintered = false;
+    fields.conf.zcash = false;
+    fields.conf.tx_version = 2;
+    let my_address = fields.derivation_method.unwrap_single_addr().clone();
+    let my_script = output_script(&my_address, ScriptType::P2PKH).to_bytes();
+    block_on(fields.recently_spent_outpoints.lock()).for_script_pubkey = my_script.clone();
+    let (ctx, coin, req) = consolidate_utxos_test_setup(fields, vec![30000, 20000], json!({}));
+    UtxoStandardCoin::broadcast_tx.mock_safe(|_, tx| {
+        BROADCASTED.fetch_add(1, Ordering::Relaxed);
+        MockResult::Return(Box::pin(futures::future::ok(tx.hash().reversed().into())))
+    });
+
+    let res = block_on(consolidate_utxos(ctx, req, 0.into())).unwrap();
+    assert_eq!(BROADCASTED.load(Ordering::Relaxed), 1);
+    let res = json::to_value(res).unwrap();
+    let tx_hex: BytesJson = json::from_value(res["consolidations"][0]["tx"]["tx_hex"].clone()).unwrap();
+    let tx: UtxoTx = deserialize(tx_hex.as_slice()).unwrap();
+    // The outputs are merged to the P2WPKH output of the segwit address.
+    assert_eq!(tx.outputs.len(), 1);
+    assert_eq!(tx.outputs[0].script_pubkey, my_script);
+    assert_eq!(&my_script[..2], &[0x00, 0x14]);
+
+    // The merged output replaces the spent ones in the cache.
+    let cache = block_on(coin.as_ref().recently_spent_outpoints.lock());
+    assert!(cache
+        .output_to_input_map
+        .keys()
+        .any(|output| output.outpoint.hash == tx.hash()));
+}
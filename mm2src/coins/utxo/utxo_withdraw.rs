@@ -1,22 +1,32 @@
 use crate::rpc_command::init_withdraw::{WithdrawInProgressStatus, WithdrawTaskHandle};
-use crate::utxo::utxo_common::{big_decimal_from_sat, UtxoTxBuilder};
-use crate::utxo::{output_script, sat_from_big_decimal, ActualTxFee, Address, FeePolicy, GetUtxoListOps, PrivKeyPolicy,
-                  UtxoAddressFormat, UtxoCoinFields, UtxoCommonOps, UtxoFeeDetails, UtxoTx, UTXO_LOCK};
+use crate::rpc_command::psbt::is_psbt_supported;
+use crate::utxo::rpc_clients::UnspentInfo;
+use crate::utxo::utxo_common::{big_decimal_from_sat, big_decimal_from_sat_unsigned, UtxoTxBuilder};
+use crate::utxo::{output_script, sat_from_big_decimal, ActualTxFee, AdditionalTxData, Address, FeePolicy,
+                  GetUtxoListOps, PrivKeyPolicy, UtxoAddressFormat, UtxoCoinFields, UtxoCommonOps, UtxoFeeDetails,
+                  UtxoOutPoint, UtxoTx, UTXO_LOCK};
 use crate::{CoinWithDerivationMethod, GetWithdrawSenderAddress, MarketCoinOps, TransactionDetails, WithdrawError,
-            WithdrawFee, WithdrawFrom, WithdrawRequest, WithdrawResult};
+            WithdrawFee, WithdrawFrom, WithdrawManyRequest, WithdrawResult};
 use async_trait::async_trait;
-use chain::TransactionOutput;
+use bitcrypto::dhash160;
+use chain::{KeySource, OutPoint, Psbt, TransactionOutput};
 use common::log::info;
 use common::now_sec;
 use crypto::trezor::{TrezorError, TrezorProcessingError};
-use crypto::{from_hw_error, CryptoCtx, CryptoCtxError, DerivationPath, HwError, HwProcessingError, HwRpcError};
+use crypto::{from_hw_error, Bip32DerPathOps, ChildNumber, CryptoCtx, CryptoCtxError, DerivationPath, HwError,
+             HwProcessingError, HwRpcError, StandardHDCoinAddress};
+use futures::compat::Future01CompatExt;
+use itertools::Itertools;
+use keys::bytes::Bytes;
 use keys::{AddressHashEnum, KeyPair, Private, Public as PublicKey, Type as ScriptType};
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
-use rpc::v1::types::ToTxHash;
+use primitives::hash::H256;
+use rpc::v1::types::{ToTxHash, H256 as H256Json};
 use rpc_task::RpcTaskError;
 use script::{Builder, Script, SignatureVersion, TransactionInputSigner};
-use serialization::{serialize, serialize_with_flags, SERIALIZE_TRANSACTION_WITNESS};
+use serialization::{deserialize, serialize, serialize_with_flags, SERIALIZE_TRANSACTION_WITNESS};
+use std::collections::HashMap;
 use std::iter::once;
 use utxo_signer::sign_params::{OutputDestination, SendingOutputInfo, SpendingInputInfo, UtxoSignTxParamsBuilder};
 use utxo_signer::{with_key_pair, UtxoSignTxError};
@@ -95,7 +105,7 @@ where
 
     fn sender_address_string(&self) -> String;
 
-    fn request(&self) -> &WithdrawRequest;
+    fn request(&self) -> &WithdrawManyRequest;
 
     fn signature_version(&self) -> SignatureVersion {
         match self.sender_address().addr_format {
@@ -114,49 +124,86 @@ where
 
     async fn sign_tx(&self, unsigned_tx: TransactionInputSigner) -> Result<UtxoTx, MmError<WithdrawError>>;
 
-    async fn build(self) -> WithdrawResult {
+    /// Returns the sender public key and its BIP32 derivation if they are known.
+    fn sender_bip32_derivation(&self) -> Option<(PublicKey, KeySource)> { None }
+
+    /// Generates the unsigned transaction paying to the requested outputs.
+    /// [`UTXO_LOCK`] is expected to be held by the caller.
+    async fn generate_tx(&self) -> Result<(TransactionInputSigner, AdditionalTxData), MmError<WithdrawError>> {
         let coin = self.coin();
         let ticker = coin.as_ref().conf.ticker.clone();
         let decimals = coin.as_ref().decimals;
         let conf = &self.coin().as_ref().conf;
         let req = self.request();
 
-        let to = coin.address_from_str(&req.to)?;
-
-        let is_p2pkh = to.prefix == conf.pub_addr_prefix && to.t_addr_prefix == conf.pub_t_addr_prefix;
-        let is_p2sh = to.prefix == conf.p2sh_addr_prefix && to.t_addr_prefix == conf.p2sh_t_addr_prefix;
+        let mut max_outputs = req.outputs.iter().positions(|output| output.max);
+        let max_output = max_outputs.next();
+        if max_outputs.next().is_some() {
+            let error = "Only one output can send the rest of the balance".to_owned();
+            return MmError::err(WithdrawError::UnsupportedError(error));
+        }
 
-        let script_type = if is_p2pkh {
-            ScriptType::P2PKH
-        } else if is_p2sh {
-            ScriptType::P2SH
-        } else {
-            return MmError::err(WithdrawError::InvalidAddress("Expected either P2PKH or P2SH".into()));
-        };
+        let mut outputs = Vec::with_capacity(req.outputs.len());
+        for output in req.outputs.iter() {
+            let to = coin.address_from_str(&output.to)?;
+
+            let is_p2pkh = to.prefix == conf.pub_addr_prefix && to.t_addr_prefix == conf.pub_t_addr_prefix;
+            let is_p2sh = to.prefix == conf.p2sh_addr_prefix && to.t_addr_prefix == conf.p2sh_t_addr_prefix;
+
+            let script_type = if is_p2pkh {
+                ScriptType::P2PKH
+            } else if is_p2sh {
+                ScriptType::P2SH
+            } else {
+                return MmError::err(WithdrawError::InvalidAddress("Expected either P2PKH or P2SH".into()));
+            };
+
+            let value = if output.max {
+                // The value is set once the unspents are known.
+                0
+            } else {
+                sat_from_big_decimal(&output.amount, decimals)?
+            };
+            let script_pubkey = output_script(&to, script_type).to_bytes();
+            outputs.push(TransactionOutput { value, script_pubkey });
+        }
 
         // Generate unsigned transaction.
         self.on_generating_transaction()?;
 
-        let script_pubkey = output_script(&to, script_type).to_bytes();
-
-        let _utxo_lock = UTXO_LOCK.lock().await;
         let (unspents, _) = coin.get_unspent_ordered_list(&self.sender_address()).await?;
-        let (value, fee_policy) = if req.max {
-            (
-                unspents.iter().fold(0, |sum, unspent| sum + unspent.value),
-                FeePolicy::DeductFromOutput(0),
-            )
-        } else {
-            let value = sat_from_big_decimal(&req.amount, decimals)?;
-            (value, FeePolicy::SendExact)
+        // Spend exactly the chosen inputs if any.
+        let (unspents, has_explicit_inputs) = match req.inputs {
+            Some(ref inputs) => (select_explicit_inputs(unspents, inputs)?, true),
+            None => (unspents, false),
+        };
+        let fee_policy = match max_output {
+            Some(max_output) => {
+                let available = unspents.iter().fold(0, |sum, unspent| sum + unspent.value);
+                let required = outputs.iter().fold(0, |sum, output| sum + output.value);
+                outputs[max_output].value =
+                    available
+                        .checked_sub(required)
+                        .or_mm_err(|| WithdrawError::NotSufficientBalance {
+                            coin: ticker.clone(),
+                            available: big_decimal_from_sat_unsigned(available, decimals),
+                            required: big_decimal_from_sat_unsigned(required, decimals),
+                        })?;
+                FeePolicy::DeductFromOutput(max_output)
+            },
+            None => FeePolicy::SendExact,
         };
-        let outputs = vec![TransactionOutput { value, script_pubkey }];
 
-        let mut tx_builder = UtxoTxBuilder::new(coin)
-            .with_from_address(self.sender_address())
-            .add_available_inputs(unspents)
+        let tx_builder = UtxoTxBuilder::new(coin).with_from_address(self.sender_address());
+        let tx_builder = if has_explicit_inputs {
+            tx_builder.add_required_inputs(unspents).fund_from_required_inputs()
+        } else {
+            tx_builder.add_available_inputs(unspents)
+        };
+        let mut tx_builder = tx_builder
             .add_outputs(outputs)
-            .with_fee_policy(fee_policy);
+            .with_fee_policy(fee_policy)
+            .replaceable();
 
         match req.fee {
             Some(WithdrawFee::UtxoFixed { ref amount }) => {
@@ -176,10 +223,20 @@ where
             },
             None => (),
         };
-        let (unsigned, data) = tx_builder
+        tx_builder
             .build()
             .await
-            .mm_err(|gen_tx_error| WithdrawError::from_generate_tx_error(gen_tx_error, ticker.clone(), decimals))?;
+            .mm_err(|gen_tx_error| WithdrawError::from_generate_tx_error(gen_tx_error, ticker, decimals))
+    }
+
+    async fn build(self) -> WithdrawResult {
+        let coin = self.coin();
+        let ticker = coin.as_ref().conf.ticker.clone();
+        let decimals = coin.as_ref().decimals;
+        let req = self.request();
+
+        let _utxo_lock = UTXO_LOCK.lock().await;
+        let (unsigned, data) = self.generate_tx().await?;
 
         // Sign the `unsigned` transaction.
         let signed = self.sign_tx(unsigned).await?;
@@ -198,7 +255,7 @@ where
         };
         Ok(TransactionDetails {
             from: vec![self.sender_address_string()],
-            to: vec![req.to.clone()],
+            to: req.outputs.iter().map(|output| output.to.clone()).collect(),
             total_amount: big_decimal_from_sat(data.spent_by_me as i64, decimals),
             spent_by_me: big_decimal_from_sat(data.spent_by_me as i64, decimals),
             received_by_me: big_decimal_from_sat(data.received_by_me as i64, decimals),
@@ -215,13 +272,107 @@ where
             memo: None,
         })
     }
+
+    /// Generates the unsigned transaction as PSBT to be signed by the sender key holder.
+    /// The transactions spent by the inputs, the spent outputs of the segwit inputs
+    /// and the sender BIP32 derivation, if known, are included.
+    async fn build_psbt(self) -> Result<(Psbt, AdditionalTxData), MmError<WithdrawError>> {
+        let coin = self.coin();
+        if !is_psbt_supported(&coin.as_ref().conf) {
+            let error = format!("PSBT is not supported by {}", coin.as_ref().conf.ticker);
+            return MmError::err(WithdrawError::UnsupportedError(error));
+        }
+
+        let (unsigned, data) = {
+            let _utxo_lock = UTXO_LOCK.lock().await;
+            self.generate_tx().await?
+        };
+        let spent_amounts: Vec<u64> = unsigned.inputs.iter().map(|input| input.amount).collect();
+        let mut psbt = Psbt::new(unsigned.into()).map_to_mm(|e| WithdrawError::InternalError(format!("{:?}", e)))?;
+
+        let sender_address = self.sender_address();
+        let sender_script = output_script(&sender_address, ScriptType::P2PKH).to_bytes();
+        let bip32_derivation = self
+            .sender_bip32_derivation()
+            .map(|(pubkey, key_source)| (Bytes::from(pubkey.to_vec()), key_source));
+
+        // The previous transactions are required to check the spent amounts. They are included for the segwit inputs too,
+        // since the hardware wallets require them after the fee attack on the segwit inputs (CVE-2020-14199).
+        let mut prev_txs: HashMap<H256, UtxoTx> = HashMap::new();
+        for (index, amount) in spent_amounts.into_iter().enumerate() {
+            let prev_hash = psbt.unsigned_tx.inputs[index].previous_output.hash;
+            let prev_tx = match prev_txs.get(&prev_hash) {
+                Some(prev_tx) => prev_tx.clone(),
+                None => {
+                    let prev_tx = get_prev_tx(coin.as_ref(), prev_hash).await?;
+                    prev_txs.insert(prev_hash, prev_tx.clone());
+                    prev_tx
+                },
+            };
+            let input = &mut psbt.inputs[index];
+            input.non_witness_utxo = Some(prev_tx);
+            if let UtxoAddressFormat::Segwit = sender_address.addr_format {
+                input.witness_utxo = Some(TransactionOutput {
+                    value: amount,
+                    script_pubkey: sender_script.clone(),
+                });
+            }
+            if let Some((ref pubkey, ref key_source)) = bip32_derivation {
+                input.bip32_derivation.insert(pubkey.clone(), key_source.clone());
+            }
+        }
+
+        // Let the signer recognize the change output.
+        if let Some((pubkey, key_source)) = bip32_derivation {
+            for (output, psbt_output) in psbt.unsigned_tx.outputs.iter().zip(psbt.outputs.iter_mut()) {
+                if output.script_pubkey == sender_script {
+                    psbt_output.bip32_derivation.insert(pubkey.clone(), key_source.clone());
+                }
+            }
+        }
+
+        Ok((psbt, data))
+    }
+}
+
+async fn get_prev_tx(coin: &UtxoCoinFields, tx_hash: H256) -> Result<UtxoTx, MmError<WithdrawError>> {
+    let tx_hash = H256Json::from(tx_hash.reversed());
+    let tx_bytes = coin.rpc_client.get_transaction_bytes(&tx_hash).compat().await?;
+    let mut tx: UtxoTx = deserialize(tx_bytes.0.as_slice())
+        .map_to_mm(|e| WithdrawError::InternalError(format!("Error deserializing tx {}: {:?}", tx_hash, e)))?;
+    tx.tx_hash_algo = coin.tx_hash_algo;
+    Ok(tx)
+}
+
+/// Picks the `inputs` chosen by the user from the spendable `unspents`.
+/// Returns an error if any of the `inputs` is not spendable or chosen twice.
+#[allow(clippy::result_large_err)]
+fn select_explicit_inputs(
+    unspents: Vec<UnspentInfo>,
+    inputs: &[UtxoOutPoint],
+) -> Result<Vec<UnspentInfo>, MmError<WithdrawError>> {
+    let mut unspents: HashMap<OutPoint, UnspentInfo> = unspents
+        .into_iter()
+        .map(|unspent| (unspent.outpoint, unspent))
+        .collect();
+    inputs
+        .iter()
+        .map(|input| {
+            unspents
+                .remove(&OutPoint::from(*input))
+                .or_mm_err(|| WithdrawError::InputNotSpendable {
+                    tx_hash: input.tx_hash,
+                    index: input.index,
+                })
+        })
+        .collect()
 }
 
 pub struct InitUtxoWithdraw<'a, Coin> {
     ctx: MmArc,
     coin: Coin,
     task_handle: &'a WithdrawTaskHandle,
-    req: WithdrawRequest,
+    req: WithdrawManyRequest,
     from_address: Address,
     /// Displayed [`InitUtxoWithdraw::from_address`].
     from_address_string: String,
@@ -242,19 +393,27 @@ where
 
     fn sender_address_string(&self) -> String { self.from_address_string.clone() }
 
-    fn request(&self) -> &WithdrawRequest { &self.req }
+    fn request(&self) -> &WithdrawManyRequest { &self.req }
 
     fn on_generating_transaction(&self) -> Result<(), MmError<WithdrawError>> {
-        let amount_display = if self.req.max {
-            "MAX".to_owned()
-        } else {
-            self.req.amount.to_string()
-        };
+        let outputs_display = self
+            .req
+            .outputs
+            .iter()
+            .map(|output| {
+                let amount_display = if output.max {
+                    "MAX".to_owned()
+                } else {
+                    output.amount.to_string()
+                };
+                format!("{} {} to {}", amount_display, self.req.coin, output.to)
+            })
+            .join(", ");
 
         // Display the address from which we are trying to withdraw funds.
         info!(
-            "Trying to withdraw {} {} from {} to {}",
-            amount_display, self.req.coin, self.from_address_string, self.req.to,
+            "Trying to withdraw {} from {}",
+            outputs_display, self.from_address_string
         );
 
         Ok(self
@@ -280,14 +439,15 @@ where
             address_pubkey: self.from_pubkey,
         }));
 
-        sign_params.add_outputs_infos(once(SendingOutputInfo {
-            destination_address: OutputDestination::plain(self.req.to.clone()),
+        sign_params.add_outputs_infos(self.req.outputs.iter().map(|output| SendingOutputInfo {
+            destination_address: OutputDestination::plain(output.to.clone()),
         }));
+        let requested_outputs = self.req.outputs.len();
         match unsigned_tx.outputs.len() {
             // There is no change output.
-            1 => (),
+            n if n == requested_outputs => (),
             // There is a change output.
-            2 => {
+            n if n == requested_outputs + 1 => {
                 sign_params.add_outputs_infos(once(SendingOutputInfo {
                     destination_address: OutputDestination::change(self.from_derivation_path.clone()),
                 }));
@@ -340,13 +500,13 @@ impl<'a, Coin> InitUtxoWithdraw<'a, Coin> {
     pub async fn new(
         ctx: MmArc,
         coin: Coin,
-        req: WithdrawRequest,
+        req: WithdrawManyRequest,
         task_handle: &'a WithdrawTaskHandle,
     ) -> Result<InitUtxoWithdraw<'a, Coin>, MmError<WithdrawError>>
     where
         Coin: CoinWithDerivationMethod + GetWithdrawSenderAddress<Address = Address, Pubkey = PublicKey>,
     {
-        let from = coin.get_withdraw_sender_address(&req).await?;
+        let from = coin.get_withdraw_sender_address(req.from.as_ref()).await?;
         let from_address_string = from.address.display_address().map_to_mm(WithdrawError::InternalError)?;
 
         let from_derivation_path = match from.derivation_path {
@@ -375,10 +535,12 @@ impl<'a, Coin> InitUtxoWithdraw<'a, Coin> {
 
 pub struct StandardUtxoWithdraw<Coin> {
     coin: Coin,
-    req: WithdrawRequest,
+    req: WithdrawManyRequest,
     key_pair: KeyPair,
     my_address: Address,
     my_address_string: String,
+    /// The derivation of [`StandardUtxoWithdraw::key_pair`] if it's derived from the HD wallet.
+    key_source: Option<KeySource>,
 }
 
 #[async_trait]
@@ -392,12 +554,17 @@ where
 
     fn sender_address_string(&self) -> String { self.my_address_string.clone() }
 
-    fn request(&self) -> &WithdrawRequest { &self.req }
+    fn request(&self) -> &WithdrawManyRequest { &self.req }
 
     fn on_generating_transaction(&self) -> Result<(), MmError<WithdrawError>> { Ok(()) }
 
     fn on_finishing(&self) -> Result<(), MmError<WithdrawError>> { Ok(()) }
 
+    fn sender_bip32_derivation(&self) -> Option<(PublicKey, KeySource)> {
+        let key_source = self.key_source.clone()?;
+        Some((*self.key_pair.public(), key_source))
+    }
+
     async fn sign_tx(&self, unsigned_tx: TransactionInputSigner) -> Result<UtxoTx, MmError<WithdrawError>> {
         Ok(with_key_pair::sign_tx(
             unsigned_tx,
@@ -414,8 +581,8 @@ where
     Coin: AsRef<UtxoCoinFields> + MarketCoinOps,
 {
     #[allow(clippy::result_large_err)]
-    pub fn new(coin: Coin, req: WithdrawRequest) -> Result<Self, MmError<WithdrawError>> {
-        let (key_pair, my_address) = match req.from {
+    pub fn new(coin: Coin, req: WithdrawManyRequest) -> Result<Self, MmError<WithdrawError>> {
+        let (key_pair, my_address, key_source) = match req.from {
             Some(WithdrawFrom::HDWalletAddress(ref path_to_address)) => {
                 let secret = coin
                     .as_ref()
@@ -443,7 +610,8 @@ where
                     hrp: coin.as_ref().conf.bech32_hrp.clone(),
                     addr_format,
                 };
-                (key_pair, my_address)
+                let key_source = hd_wallet_key_source(&coin.as_ref().priv_key_policy, path_to_address)?;
+                (key_pair, my_address, Some(key_source))
             },
             Some(WithdrawFrom::AddressId(_)) | Some(WithdrawFrom::DerivationPath { .. }) => {
                 return MmError::err(WithdrawError::UnsupportedError(
@@ -453,7 +621,7 @@ where
             None => {
                 let key_pair = coin.as_ref().priv_key_policy.activated_key_or_err()?;
                 let my_address = coin.as_ref().derivation_method.single_addr_or_err()?.clone();
-                (*key_pair, my_address)
+                (*key_pair, my_address, None)
             },
         };
         let my_address_string = my_address.display_address().map_to_mm(WithdrawError::InternalError)?;
@@ -463,6 +631,27 @@ where
             key_pair,
             my_address,
             my_address_string,
+            key_source,
         })
     }
 }
+
+/// Returns the master key fingerprint and the full derivation path of the HD wallet address.
+#[allow(clippy::result_large_err)]
+fn hd_wallet_key_source(
+    priv_key_policy: &PrivKeyPolicy<KeyPair>,
+    path_to_address: &StandardHDCoinAddress,
+) -> Result<KeySource, MmError<WithdrawError>> {
+    let master_pubkey = priv_key_policy.bip39_secp_priv_key_or_err()?.public_key();
+    let master_pubkey_hash = dhash160(&master_pubkey.public_key().serialize());
+    let mut fingerprint = [0; 4];
+    fingerprint.copy_from_slice(&master_pubkey_hash[..4]);
+
+    let mut der_path = priv_key_policy.derivation_path_or_err()?.to_derivation_path();
+    der_path.push(ChildNumber::new(path_to_address.account, true)?);
+    der_path.push(ChildNumber::new(path_to_address.is_change as u32, false)?);
+    der_path.push(ChildNumber::new(path_to_address.address_index, false)?);
+    let path = der_path.into_iter().map(|child| child.0).collect();
+
+    Ok(KeySource { fingerprint, path })
+}
@@ -11,6 +11,7 @@ use script::Script;
 mod sign_common;
 pub mod sign_params;
 pub mod with_key_pair;
+pub mod with_psbt;
 pub mod with_trezor;
 
 use crate::with_key_pair::UtxoSignWithKeyPairError;
@@ -0,0 +1,146 @@
+//! Signing and finalizing the PSBT inputs spending P2PKH and P2WPKH outputs.
+
+use crate::with_key_pair::{calc_and_sign_sighash, UtxoSignWithKeyPairError, SIGHASH_ALL};
+use chain::{Psbt, Transaction as UtxoTx};
+use derive_more::Display;
+use keys::bytes::Bytes;
+use keys::{AddressHashEnum, KeyPair, Public};
+use mm2_err_handle::prelude::*;
+use script::{Builder, Script, SignatureVersion, TransactionInputSigner};
+
+pub type UtxoSignPsbtResult<T> = Result<T, MmError<UtxoSignPsbtError>>;
+
+#[derive(Debug, Display)]
+pub enum UtxoSignPsbtError {
+    #[display(fmt = "Output spent by the input '{}' is unknown", index)]
+    UnknownSpentOutput { index: usize },
+    #[display(fmt = "Input '{}' requires unsupported sighash type '{}'", index, sighash_type)]
+    UnsupportedSighashType { index: usize, sighash_type: u32 },
+    #[display(fmt = "Input '{}' is not signed", index)]
+    InputNotSigned { index: usize },
+    #[display(fmt = "Error signing: {}", _0)]
+    ErrorSigning(UtxoSignWithKeyPairError),
+}
+
+impl From<UtxoSignWithKeyPairError> for UtxoSignPsbtError {
+    fn from(e: UtxoSignWithKeyPairError) -> Self { UtxoSignPsbtError::ErrorSigning(e) }
+}
+
+/// Signs the not finalized inputs of the `psbt` spending P2PKH and P2WPKH outputs of the `key_pair`.
+/// The other inputs are left as is. Returns the number of the signed inputs.
+pub fn sign_psbt(
+    psbt: &mut Psbt,
+    key_pair: &KeyPair,
+    signature_version: SignatureVersion,
+    fork_id: u32,
+) -> UtxoSignPsbtResult<usize> {
+    let signer = input_signer(psbt)?;
+    let pubkey = Bytes::from(key_pair.public().to_vec());
+    let address_hash = AddressHashEnum::AddressHash(key_pair.public().address_hash());
+    let p2pkh_script = Builder::build_p2pkh(&address_hash);
+    let p2wpkh_script = Builder::build_witness_script(&address_hash);
+    let sighash_type = SIGHASH_ALL | fork_id;
+
+    let mut signed = 0;
+    for index in 0..psbt.inputs.len() {
+        if psbt.inputs[index].is_finalized() {
+            continue;
+        }
+        let prev_script = spent_script(psbt, index)?;
+        let input_signature_version = if prev_script == p2pkh_script {
+            signature_version
+        } else if prev_script == p2wpkh_script {
+            SignatureVersion::WitnessV0
+        } else {
+            continue;
+        };
+        if let Some(requested) = psbt.inputs[index].sighash_type {
+            if requested != sighash_type {
+                return MmError::err(UtxoSignPsbtError::UnsupportedSighashType {
+                    index,
+                    sighash_type: requested,
+                });
+            }
+        }
+
+        // The script code of P2WPKH is the P2PKH script.
+        let mut signature = calc_and_sign_sighash(
+            &signer,
+            index,
+            &p2pkh_script,
+            key_pair,
+            input_signature_version,
+            SIGHASH_ALL,
+            fork_id,
+        )?;
+        signature.append(&mut Bytes::from(vec![sighash_type as u8]));
+        psbt.inputs[index].partial_sigs.insert(pubkey.clone(), signature);
+        signed += 1;
+    }
+    Ok(signed)
+}
+
+/// Finalizes the signed inputs of the `psbt` and extracts the signed transaction.
+/// Returns an error if any of the inputs is not signed.
+pub fn finalize_psbt(mut psbt: Psbt) -> UtxoSignPsbtResult<UtxoTx> {
+    for index in 0..psbt.inputs.len() {
+        if psbt.inputs[index].is_finalized() {
+            continue;
+        }
+        let prev_script = spent_script(&psbt, index)?;
+        let input = &mut psbt.inputs[index];
+
+        let mut is_witness = false;
+        let (pubkey, signature) = input
+            .partial_sigs
+            .iter()
+            .find(|(pubkey, _)| {
+                let address_hash = match Public::from_slice(pubkey) {
+                    Ok(pubkey) => AddressHashEnum::AddressHash(pubkey.address_hash()),
+                    Err(_) => return false,
+                };
+                if Builder::build_p2pkh(&address_hash) == prev_script {
+                    return true;
+                }
+                is_witness = Builder::build_witness_script(&address_hash) == prev_script;
+                is_witness
+            })
+            .map(|(pubkey, signature)| (pubkey.clone(), signature.clone()))
+            .or_mm_err(|| UtxoSignPsbtError::InputNotSigned { index })?;
+
+        if is_witness {
+            input.final_script_witness = Some(vec![signature, pubkey]);
+        } else {
+            let script_sig = Builder::default().push_data(&signature).push_data(&pubkey).into_bytes();
+            input.final_script_sig = Some(script_sig);
+        }
+        input.partial_sigs.clear();
+        input.sighash_type = None;
+        input.bip32_derivation.clear();
+    }
+
+    let mut tx = psbt.unsigned_tx;
+    for (tx_input, input) in tx.inputs.iter_mut().zip(psbt.inputs) {
+        tx_input.script_sig = input.final_script_sig.unwrap_or_default();
+        tx_input.script_witness = input.final_script_witness.unwrap_or_default();
+    }
+    Ok(tx)
+}
+
+fn spent_script(psbt: &Psbt, index: usize) -> UtxoSignPsbtResult<Script> {
+    psbt.spent_output(index)
+        .map(|output| output.script_pubkey.clone().into())
+        .or_mm_err(|| UtxoSignPsbtError::UnknownSpentOutput { index })
+}
+
+/// Returns the signer of the unsigned transaction with the spent amounts set.
+fn input_signer(psbt: &Psbt) -> UtxoSignPsbtResult<TransactionInputSigner> {
+    let mut signer = TransactionInputSigner::from(psbt.unsigned_tx.clone());
+    for (index, input) in signer.inputs.iter_mut().enumerate() {
+        input.amount = psbt
+            .spent_output(index)
+            .or_mm_err(|| UtxoSignPsbtError::UnknownSpentOutput { index })?
+            .value;
+    }
+    Ok(signer)
+}
@@ -14,14 +14,14 @@ use crate::utxo::{utxo_common, ActualTxFee, AdditionalTxData, AddrFromStrError,
                   UtxoActivationParams, UtxoAddressFormat, UtxoArc, UtxoCoinFields, UtxoCommonOps, UtxoRpcMode,
                   UtxoTxBroadcastOps, UtxoTxGenerationOps, VerboseTransactionFrom};
 use crate::{BalanceError, BalanceFut, CheckIfMyPaymentSentArgs, CoinBalance, CoinFutSpawner, ConfirmPaymentInput,
-            DexFee, FeeApproxStage, FoundSwapTxSpend, HistorySyncState, MakerSwapTakerCoin, MarketCoinOps, MmCoin,
-            MmCoinEnum, NegotiateSwapContractAddrErr, PaymentInstructionArgs, PaymentInstructions,
-            PaymentInstructionsErr, PrivKeyActivationPolicy, PrivKeyBuildPolicy, PrivKeyPolicyNotAllowed,
-            RawTransactionFut, RawTransactionRequest, RefundError, RefundPaymentArgs, RefundResult,
-            SearchForSwapTxSpendInput, SendMakerPaymentSpendPreimageInput, SendPaymentArgs, SignatureError,
-            SignatureResult, SpendPaymentArgs, SwapOps, TakerSwapMakerCoin, TradeFee, TradePreimageFut,
-            TradePreimageResult, TradePreimageValue, TransactionEnum, TransactionFut, TransactionResult,
-            TxMarshalingErr, UnexpectedDerivationMethod, ValidateAddressResult, ValidateFeeArgs,
+            DeriveHtlcResult, DexFee, FeeApproxStage, FoundSwapTxSpend, HistorySyncState, MakerSwapTakerCoin,
+            MarketCoinOps, MmCoin, MmCoinEnum, NegotiateSwapContractAddrErr, PaymentInstructionArgs,
+            PaymentInstructions, PaymentInstructionsErr, PrivKeyActivationPolicy, PrivKeyBuildPolicy,
+            PrivKeyPolicyNotAllowed, RawTransactionFut, RawTransactionRequest, RefundError, RefundPaymentArgs,
+            RefundResult, SearchForSwapTxSpendInput, SendMakerPaymentSpendPreimageInput, SendPaymentArgs,
+            SignatureError, SignatureResult, SpendPaymentArgs, SwapOps, TakerSwapMakerCoin, TradeFee,
+            TradePreimageFut, TradePreimageResult, TradePreimageValue, TransactionEnum, TransactionFut,
+            TransactionResult, TxMarshalingErr, UnexpectedDerivationMethod, ValidateAddressResult, ValidateFeeArgs,
             ValidateInstructionsErr, ValidateOtherPubKeyErr, ValidatePaymentError, ValidatePaymentFut,
             ValidatePaymentInput, ValidateWatcherSpendInput, VerificationError, VerificationResult,
             WaitForHTLCTxSpendArgs, WatcherOps, WatcherReward, WatcherRewardError, WatcherSearchForSwapTxSpendInput,
@@ -1192,7 +1192,7 @@ impl SwapOps for ZCoin {
 
     fn send_maker_payment(&self, maker_payment_args: SendPaymentArgs<'_>) -> TransactionFut {
         let selfi = self.clone();
-        let maker_key_pair = self.derive_htlc_key_pair(maker_payment_args.swap_unique_data);
+        let maker_key_pair = try_tx_fus!(self.derive_htlc_key_pair(maker_payment_args.swap_unique_data));
         let taker_pub = try_tx_fus!(Public::from_slice(maker_payment_args.other_pubkey));
         let secret_hash = maker_payment_args.secret_hash.to_vec();
         let time_lock = try_tx_fus!(maker_payment_args.time_lock.try_into());
@@ -1216,7 +1216,7 @@ impl SwapOps for ZCoin {
 
     fn send_taker_payment(&self, taker_payment_args: SendPaymentArgs<'_>) -> TransactionFut {
         let selfi = self.clone();
-        let taker_keypair = self.derive_htlc_key_pair(taker_payment_args.swap_unique_data);
+        let taker_keypair = try_tx_fus!(self.derive_htlc_key_pair(taker_payment_args.swap_unique_data));
         let maker_pub = try_tx_fus!(Public::from_slice(taker_payment_args.other_pubkey));
         let secret_hash = taker_payment_args.secret_hash.to_vec();
         let time_lock = try_tx_fus!(taker_payment_args.time_lock.try_into());
@@ -1240,7 +1240,7 @@ impl SwapOps for ZCoin {
 
     fn send_maker_spends_taker_payment(&self, maker_spends_payment_args: SpendPaymentArgs<'_>) -> TransactionFut {
         let tx = try_tx_fus!(ZTransaction::read(maker_spends_payment_args.other_payment_tx));
-        let key_pair = self.derive_htlc_key_pair(maker_spends_payment_args.swap_unique_data);
+        let key_pair = try_tx_fus!(self.derive_htlc_key_pair(maker_spends_payment_args.swap_unique_data));
         let time_lock = try_tx_fus!(maker_spends_payment_args.time_lock.try_into());
         let redeem_script = payment_script(
             time_lock,
@@ -1271,7 +1271,7 @@ impl SwapOps for ZCoin {
 
     fn send_taker_spends_maker_payment(&self, taker_spends_payment_args: SpendPaymentArgs<'_>) -> TransactionFut {
         let tx = try_tx_fus!(ZTransaction::read(taker_spends_payment_args.other_payment_tx));
-        let key_pair = self.derive_htlc_key_pair(taker_spends_payment_args.swap_unique_data);
+        let key_pair = try_tx_fus!(self.derive_htlc_key_pair(taker_spends_payment_args.swap_unique_data));
         let time_lock = try_tx_fus!(taker_spends_payment_args.time_lock.try_into());
         let redeem_script = payment_script(
             time_lock,
@@ -1302,7 +1302,7 @@ impl SwapOps for ZCoin {
 
     async fn send_taker_refunds_payment(&self, taker_refunds_payment_args: RefundPaymentArgs<'_>) -> TransactionResult {
         let tx = try_tx_s!(ZTransaction::read(taker_refunds_payment_args.payment_tx));
-        let key_pair = self.derive_htlc_key_pair(taker_refunds_payment_args.swap_unique_data);
+        let key_pair = try_tx_s!(self.derive_htlc_key_pair(taker_refunds_payment_args.swap_unique_data));
         let time_lock = try_tx_s!(taker_refunds_payment_args.time_lock.try_into());
         let redeem_script = payment_script(
             time_lock,
@@ -1327,7 +1327,7 @@ impl SwapOps for ZCoin {
 
     async fn send_maker_refunds_payment(&self, maker_refunds_payment_args: RefundPaymentArgs<'_>) -> TransactionResult {
         let tx = try_tx_s!(ZTransaction::read(maker_refunds_payment_args.payment_tx));
-        let key_pair = self.derive_htlc_key_pair(maker_refunds_payment_args.swap_unique_data);
+        let key_pair = try_tx_s!(self.derive_htlc_key_pair(maker_refunds_payment_args.swap_unique_data));
         let time_lock = try_tx_s!(maker_refunds_payment_args.time_lock.try_into());
         let redeem_script = payment_script(
             time_lock,
@@ -1505,17 +1505,17 @@ impl SwapOps for ZCoin {
         Ok(None)
     }
 
-    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> KeyPair {
+    fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<KeyPair> {
         let message = Message::from(dhash256(swap_unique_data).take());
         let signature = self.secp_keypair().private().sign(&message).expect("valid privkey");
 
         let key = secp_privkey_from_hash(dhash256(&signature));
-        key_pair_from_secret(key.as_slice()).expect("valid privkey")
+        Ok(key_pair_from_secret(key.as_slice()).expect("valid privkey"))
     }
 
     #[inline]
-    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> Vec<u8> {
-        self.derive_htlc_key_pair(swap_unique_data).public_slice().to_vec()
+    fn derive_htlc_pubkey(&self, swap_unique_data: &[u8]) -> DeriveHtlcResult<Vec<u8>> {
+        Ok(self.derive_htlc_key_pair(swap_unique_data)?.public_slice().to_vec())
     }
 
     #[inline]
@@ -17,6 +17,7 @@ use common::{drop_mutability, true_f};
 use crypto::CryptoCtxError;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
+use mm2_event_stream::behaviour::{EventBehaviour, EventInitStatus};
 use mm2_event_stream::EventStreamConfiguration;
 use mm2_number::BigDecimal;
 use serde_derive::{Deserialize, Serialize};
@@ -112,6 +113,7 @@ impl From<BchWithTokensActivationError> for EnablePlatformCoinWithTokensError {
                 EnablePlatformCoinWithTokensError::UnexpectedDerivationMethod(e)
             },
             BchWithTokensActivationError::Transport(e) => EnablePlatformCoinWithTokensError::Transport(e),
+            BchWithTokensActivationError::BalanceStreamInitError(e) => EnablePlatformCoinWithTokensError::Internal(e),
             BchWithTokensActivationError::Internal(e) => EnablePlatformCoinWithTokensError::Internal(e),
         }
     }
@@ -181,6 +183,7 @@ pub enum BchWithTokensActivationError {
     PrivKeyPolicyNotAllowed(PrivKeyPolicyNotAllowed),
     UnexpectedDerivationMethod(String),
     Transport(String),
+    BalanceStreamInitError(String),
     Internal(String),
 }
 
@@ -334,8 +337,11 @@ impl PlatformWithTokensActivationOps for BchCoin {
 
     async fn handle_balance_streaming(
         &self,
-        _config: &EventStreamConfiguration,
+        config: &EventStreamConfiguration,
     ) -> Result<(), MmError<Self::ActivationError>> {
+        if let EventInitStatus::Failed(err) = EventBehaviour::spawn_if_active(self.clone(), config).await {
+            return MmError::err(BchWithTokensActivationError::BalanceStreamInitError(err));
+        }
         Ok(())
     }
 }
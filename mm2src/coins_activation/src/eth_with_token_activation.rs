@@ -15,7 +15,8 @@ use common::Future01CompatExt;
 use common::{drop_mutability, true_f};
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
-use mm2_event_stream::EventStreamConfiguration;
+use mm2_event_stream::{behaviour::{EventBehaviour, EventInitStatus},
+                       EventStreamConfiguration};
 #[cfg(target_arch = "wasm32")]
 use mm2_metamask::MetamaskRpcError;
 use mm2_number::BigDecimal;
@@ -50,6 +51,7 @@ impl From<EthActivationV2Error> for EnablePlatformCoinWithTokensError {
             EthActivationV2Error::PrivKeyPolicyNotAllowed(e) => {
                 EnablePlatformCoinWithTokensError::PrivKeyPolicyNotAllowed(e)
             },
+            EthActivationV2Error::BalanceStreamInitError(e) => EnablePlatformCoinWithTokensError::Internal(e),
             #[cfg(target_arch = "wasm32")]
             EthActivationV2Error::MetamaskError(metamask) => {
                 EnablePlatformCoinWithTokensError::Transport(metamask.to_string())
@@ -281,8 +283,11 @@ impl PlatformWithTokensActivationOps for EthCoin {
 
     async fn handle_balance_streaming(
         &self,
-        _config: &EventStreamConfiguration,
+        config: &EventStreamConfiguration,
     ) -> Result<(), MmError<Self::ActivationError>> {
+        if let EventInitStatus::Failed(err) = EventBehaviour::spawn_if_active(self.clone(), config).await {
+            return MmError::err(EthActivationV2Error::BalanceStreamInitError(err));
+        }
         Ok(())
     }
 }
@@ -6,6 +6,7 @@ mod l2;
 #[cfg(not(target_arch = "wasm32"))] mod lightning_activation;
 mod platform_coin_with_tokens;
 mod prelude;
+mod rpc_methods;
 mod slp_token_activation;
 #[cfg(all(
     feature = "enable-solana",
@@ -30,6 +31,7 @@ mod utxo_activation;
 
 pub use l2::{cancel_init_l2, init_l2, init_l2_status, init_l2_user_action};
 pub use platform_coin_with_tokens::enable_platform_coin_with_tokens;
+pub use rpc_methods::register_rpc_methods;
 pub use standalone_coin::{cancel_init_standalone_coin, init_standalone_coin, init_standalone_coin_status,
                           init_standalone_coin_user_action};
 pub use token::enable_token;
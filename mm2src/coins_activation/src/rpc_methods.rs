@@ -0,0 +1,157 @@
+use crate::{cancel_init_l2, cancel_init_standalone_coin, enable_platform_coin_with_tokens, enable_token, init_l2,
+            init_l2_status, init_l2_user_action, init_standalone_coin, init_standalone_coin_status,
+            init_standalone_coin_user_action};
+use coins::eth::EthCoin;
+use coins::tendermint::{TendermintCoin, TendermintToken};
+use coins::utxo::bch::BchCoin;
+use coins::utxo::qtum::QtumCoin;
+use coins::utxo::slp::SlpToken;
+use coins::utxo::utxo_standard::UtxoStandardCoin;
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::RpcMethodRegistry;
+
+#[cfg(all(
+    feature = "enable-solana",
+    not(target_os = "ios"),
+    not(target_os = "android"),
+    not(target_arch = "wasm32")
+))]
+use coins::{SolanaCoin, SplToken};
+
+#[cfg(not(target_arch = "wasm32"))]
+use coins::{lightning::LightningCoin, z_coin::ZCoin};
+
+/// Registers the coins activation mmrpc 2.0 methods including the `task::enable_*` ones.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "enable_bch_with_tokens",
+        "Enables BCH along with the SLP tokens.",
+        mmrpc_handler!(enable_platform_coin_with_tokens::<BchCoin>),
+    );
+    registry.register(
+        "enable_erc20",
+        "Enables an ERC20 token of the enabled platform coin.",
+        mmrpc_handler!(enable_token::<EthCoin>),
+    );
+    registry.register(
+        "enable_eth_with_tokens",
+        "Enables ETH or another EVM platform coin along with the ERC20 tokens.",
+        mmrpc_handler!(enable_platform_coin_with_tokens::<EthCoin>),
+    );
+    registry.register(
+        "enable_slp",
+        "Enables an SLP token of the enabled BCH.",
+        mmrpc_handler!(enable_token::<SlpToken>),
+    );
+    registry.register(
+        "enable_tendermint_token",
+        "Enables a token of the enabled Tendermint coin.",
+        mmrpc_handler!(enable_token::<TendermintToken>),
+    );
+    registry.register(
+        "enable_tendermint_with_assets",
+        "Enables a Tendermint coin along with the tokens.",
+        mmrpc_handler!(enable_platform_coin_with_tokens::<TendermintCoin>),
+    );
+    #[cfg(all(
+        feature = "enable-solana",
+        not(target_os = "ios"),
+        not(target_os = "android"),
+        not(target_arch = "wasm32")
+    ))]
+    {
+        registry.register(
+            "enable_solana_with_tokens",
+            "Enables SOL along with the SPL tokens.",
+            mmrpc_handler!(enable_platform_coin_with_tokens::<SolanaCoin>),
+        );
+        registry.register(
+            "enable_spl",
+            "Enables an SPL token of the enabled SOL.",
+            mmrpc_handler!(enable_token::<SplToken>),
+        );
+    }
+    registry.register(
+        "task::enable_qtum::cancel",
+        "Cancels the QTUM activation task.",
+        mmrpc_handler!(cancel_init_standalone_coin::<QtumCoin>),
+    );
+    registry.register(
+        "task::enable_qtum::init",
+        "Starts the task enabling a QTUM coin.",
+        mmrpc_handler!(init_standalone_coin::<QtumCoin>),
+    );
+    registry.register(
+        "task::enable_qtum::status",
+        "Returns the status of the QTUM activation task.",
+        mmrpc_handler!(init_standalone_coin_status::<QtumCoin>),
+    );
+    registry.register(
+        "task::enable_qtum::user_action",
+        "Passes the user action to the QTUM activation task.",
+        mmrpc_handler!(init_standalone_coin_user_action::<QtumCoin>),
+    );
+    registry.register(
+        "task::enable_utxo::cancel",
+        "Cancels the UTXO coin activation task.",
+        mmrpc_handler!(cancel_init_standalone_coin::<UtxoStandardCoin>),
+    );
+    registry.register(
+        "task::enable_utxo::init",
+        "Starts the task enabling a UTXO coin.",
+        mmrpc_handler!(init_standalone_coin::<UtxoStandardCoin>),
+    );
+    registry.register(
+        "task::enable_utxo::status",
+        "Returns the status of the UTXO coin activation task.",
+        mmrpc_handler!(init_standalone_coin_status::<UtxoStandardCoin>),
+    );
+    registry.register(
+        "task::enable_utxo::user_action",
+        "Passes the user action to the UTXO coin activation task.",
+        mmrpc_handler!(init_standalone_coin_user_action::<UtxoStandardCoin>),
+    );
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        registry.register(
+            "task::enable_lightning::cancel",
+            "Cancels the Lightning activation task.",
+            mmrpc_handler!(cancel_init_l2::<LightningCoin>),
+        );
+        registry.register(
+            "task::enable_lightning::init",
+            "Starts the task enabling the Lightning network on top of the enabled platform coin.",
+            mmrpc_handler!(init_l2::<LightningCoin>),
+        );
+        registry.register(
+            "task::enable_lightning::status",
+            "Returns the status of the Lightning activation task.",
+            mmrpc_handler!(init_l2_status::<LightningCoin>),
+        );
+        registry.register(
+            "task::enable_lightning::user_action",
+            "Passes the user action to the Lightning activation task.",
+            mmrpc_handler!(init_l2_user_action::<LightningCoin>),
+        );
+        registry.register(
+            "task::enable_z_coin::cancel",
+            "Cancels the Z coin activation task.",
+            mmrpc_handler!(cancel_init_standalone_coin::<ZCoin>),
+        );
+        registry.register(
+            "task::enable_z_coin::init",
+            "Starts the task enabling a Z coin.",
+            mmrpc_handler!(init_standalone_coin::<ZCoin>),
+        );
+        registry.register(
+            "task::enable_z_coin::status",
+            "Returns the status of the Z coin activation task.",
+            mmrpc_handler!(init_standalone_coin_status::<ZCoin>),
+        );
+        registry.register(
+            "task::enable_z_coin::user_action",
+            "Passes the user action to the Z coin activation task.",
+            mmrpc_handler!(init_standalone_coin_user_action::<ZCoin>),
+        );
+    }
+}
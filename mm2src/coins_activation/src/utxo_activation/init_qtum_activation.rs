@@ -16,6 +16,7 @@ use coins::utxo::UtxoActivationParams;
 use coins::CoinProtocol;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
+use mm2_event_stream::behaviour::{EventBehaviour, EventInitStatus};
 use mm2_metrics::MetricsArc;
 use mm2_number::BigDecimal;
 use serde_json::Value as Json;
@@ -67,6 +68,16 @@ impl InitStandaloneCoinActivationOps for QtumCoin {
             .build()
             .await
             .mm_err(|e| InitUtxoStandardError::from_build_err(e, ticker.clone()))?;
+
+        if let Some(config) = &ctx.event_stream_configuration {
+            if let EventInitStatus::Failed(err) = EventBehaviour::spawn_if_active(coin.clone(), config).await {
+                return MmError::err(InitUtxoStandardError::CoinCreationError {
+                    ticker,
+                    error: format!("Failed to initialize the balance streaming: {}", err),
+                });
+            }
+        }
+
         Ok(coin)
     }
 
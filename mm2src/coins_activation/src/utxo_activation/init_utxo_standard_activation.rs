@@ -17,6 +17,7 @@ use coins::CoinProtocol;
 use futures::StreamExt;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
+use mm2_event_stream::behaviour::{EventBehaviour, EventInitStatus};
 use mm2_metrics::MetricsArc;
 use mm2_number::BigDecimal;
 use serde_json::Value as Json;
@@ -108,6 +109,15 @@ impl InitStandaloneCoinActivationOps for UtxoStandardCoin {
             }
         }
 
+        if let Some(config) = &ctx.event_stream_configuration {
+            if let EventInitStatus::Failed(err) = EventBehaviour::spawn_if_active(coin.clone(), config).await {
+                return MmError::err(InitUtxoStandardError::CoinCreationError {
+                    ticker,
+                    error: format!("Failed to initialize the balance streaming: {}", err),
+                });
+            }
+        }
+
         Ok(coin)
     }
 
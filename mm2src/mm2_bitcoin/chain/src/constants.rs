@@ -7,6 +7,10 @@ pub const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1u32 << 31;
 // disables nLockTime.
 pub const SEQUENCE_FINAL: u32 = 0xffffffff;
 
+// Setting nSequence to this value or lower for any input in a transaction
+// signals that the transaction can be replaced by one paying a higher fee (BIP 125).
+pub const SEQUENCE_RBF: u32 = 0xfffffffd;
+
 // If CTxIn::nSequence encodes a relative lock-time and this flag
 // is set, the relative lock-time has units of 512 seconds,
 // otherwise it specifies blocks with a granularity of 1.
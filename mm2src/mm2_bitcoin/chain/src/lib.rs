@@ -11,6 +11,8 @@ pub mod constants;
 mod block;
 mod block_header;
 mod merkle_root;
+mod psbt;
+pub use psbt::{KeySource, Psbt, PsbtInput, PsbtOutput};
 mod raw_block;
 pub use raw_block::{RawBlockHeader, RawHeaderError};
 mod transaction;
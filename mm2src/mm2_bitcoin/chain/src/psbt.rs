@@ -0,0 +1,436 @@
+//! Partially Signed Bitcoin Transaction.
+//! https://github.com/bitcoin/bips/blob/master/bip-0174.mediawiki
+//!
+//! Only the version 0 fields required to sign P2PKH and P2WPKH inputs are parsed,
+//! the other ones are kept as unknown and serialized back as is.
+
+use bytes::Bytes;
+use ser::{deserialize, serialize, serialize_with_flags, CompactInteger, Deserializable, Error, Reader, Serializable,
+          Stream, SERIALIZE_TRANSACTION_WITNESS};
+use std::collections::BTreeMap;
+use std::io;
+use transaction::{Transaction, TransactionOutput};
+
+/// `psbt` followed by `0xff` separator.
+const PSBT_MAGIC: [u8; 5] = [0x70, 0x73, 0x62, 0x74, 0xff];
+/// The maximum length of a key or a value. The previous transactions may be as large as a block.
+const MAX_KEY_VALUE_LEN: usize = 4_000_000;
+
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+
+const PSBT_IN_NON_WITNESS_UTXO: u8 = 0x00;
+const PSBT_IN_WITNESS_UTXO: u8 = 0x01;
+const PSBT_IN_PARTIAL_SIG: u8 = 0x02;
+const PSBT_IN_SIGHASH_TYPE: u8 = 0x03;
+const PSBT_IN_BIP32_DERIVATION: u8 = 0x06;
+const PSBT_IN_FINAL_SCRIPTSIG: u8 = 0x07;
+const PSBT_IN_FINAL_SCRIPTWITNESS: u8 = 0x08;
+
+const PSBT_OUT_BIP32_DERIVATION: u8 = 0x02;
+
+/// The master key fingerprint and the derivation path of a public key.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KeySource {
+    pub fingerprint: [u8; 4],
+    pub path: Vec<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PsbtInput {
+    /// The transaction which output is spent by the input.
+    pub non_witness_utxo: Option<Transaction>,
+    /// The output spent by the segwit input.
+    pub witness_utxo: Option<TransactionOutput>,
+    /// The signatures (including the sighash type byte) by the public keys.
+    pub partial_sigs: BTreeMap<Bytes, Bytes>,
+    pub sighash_type: Option<u32>,
+    pub bip32_derivation: BTreeMap<Bytes, KeySource>,
+    pub final_script_sig: Option<Bytes>,
+    pub final_script_witness: Option<Vec<Bytes>>,
+    /// The fields that are not parsed by the full key.
+    pub unknown: BTreeMap<Bytes, Bytes>,
+}
+
+impl PsbtInput {
+    pub fn is_finalized(&self) -> bool { self.final_script_sig.is_some() || self.final_script_witness.is_some() }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PsbtOutput {
+    pub bip32_derivation: BTreeMap<Bytes, KeySource>,
+    /// The fields that are not parsed by the full key.
+    pub unknown: BTreeMap<Bytes, Bytes>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Psbt {
+    /// The transaction with empty script sigs and witnesses.
+    pub unsigned_tx: Transaction,
+    /// The global fields that are not parsed by the full key.
+    pub unknown: BTreeMap<Bytes, Bytes>,
+    pub inputs: Vec<PsbtInput>,
+    pub outputs: Vec<PsbtOutput>,
+}
+
+impl Psbt {
+    /// Creates PSBT with empty input and output maps.
+    /// Returns an error if any of the inputs is signed.
+    pub fn new(unsigned_tx: Transaction) -> Result<Psbt, Error> {
+        check_unsigned(&unsigned_tx)?;
+        Ok(Psbt {
+            inputs: vec![PsbtInput::default(); unsigned_tx.inputs.len()],
+            outputs: vec![PsbtOutput::default(); unsigned_tx.outputs.len()],
+            unsigned_tx,
+            unknown: BTreeMap::new(),
+        })
+    }
+
+    /// Returns the output spent by the `input_index` input if it's known.
+    pub fn spent_output(&self, input_index: usize) -> Option<&TransactionOutput> {
+        let input = self.inputs.get(input_index)?;
+        if let Some(ref witness_utxo) = input.witness_utxo {
+            return Some(witness_utxo);
+        }
+        let prev_output = &self.unsigned_tx.inputs.get(input_index)?.previous_output;
+        input
+            .non_witness_utxo
+            .as_ref()
+            .filter(|prev_tx| prev_tx.hash() == prev_output.hash)
+            .and_then(|prev_tx| prev_tx.outputs.get(prev_output.index as usize))
+    }
+}
+
+fn check_unsigned(tx: &Transaction) -> Result<(), Error> {
+    if tx
+        .inputs
+        .iter()
+        .any(|input| !input.script_sig.is_empty() || input.has_witness())
+    {
+        return Err(Error::MalformedData);
+    }
+    Ok(())
+}
+
+fn key(key_type: u8, key_data: &[u8]) -> Bytes {
+    let mut key = Vec::with_capacity(key_data.len() + 1);
+    key.push(key_type);
+    key.extend_from_slice(key_data);
+    key.into()
+}
+
+fn append_key_value(stream: &mut Stream, key: &Bytes, value: &Bytes) { stream.append(key).append(value); }
+
+fn append_derivations(stream: &mut Stream, key_type: u8, derivations: &BTreeMap<Bytes, KeySource>) {
+    for (pubkey, key_source) in derivations {
+        let mut value = Stream::new();
+        value.append_slice(&key_source.fingerprint);
+        for child in key_source.path.iter() {
+            value.append(child);
+        }
+        append_key_value(stream, &key(key_type, pubkey), &value.out());
+    }
+}
+
+fn append_unknown(stream: &mut Stream, unknown: &BTreeMap<Bytes, Bytes>) {
+    for (key, value) in unknown {
+        append_key_value(stream, key, value);
+    }
+}
+
+/// Reads the length prefixed key or value.
+fn read_var_bytes<R: io::Read>(reader: &mut Reader<R>) -> Result<Bytes, Error> {
+    let len: usize = reader.read::<CompactInteger>()?.into();
+    if len > MAX_KEY_VALUE_LEN {
+        return Err(Error::MalformedData);
+    }
+    let mut bytes = Bytes::new_with_len(len);
+    reader.read_slice(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads the key-value pairs of a map until the separator.
+fn read_map<R: io::Read>(reader: &mut Reader<R>) -> Result<Vec<(Bytes, Bytes)>, Error> {
+    let mut pairs: Vec<(Bytes, Bytes)> = Vec::new();
+    loop {
+        let key = read_var_bytes(reader)?;
+        if key.is_empty() {
+            return Ok(pairs);
+        }
+        if pairs.iter().any(|(existing, _)| *existing == key) {
+            return Err(Error::MalformedData);
+        }
+        let value = read_var_bytes(reader)?;
+        pairs.push((key, value));
+    }
+}
+
+/// Checks that the key consists of the key type only.
+fn expect_key_type_only(key: &Bytes) -> Result<(), Error> {
+    if key.len() != 1 {
+        return Err(Error::MalformedData);
+    }
+    Ok(())
+}
+
+fn parse_key_source(value: &Bytes) -> Result<KeySource, Error> {
+    if value.len() < 4 || value.len() % 4 != 0 {
+        return Err(Error::MalformedData);
+    }
+    let mut fingerprint = [0; 4];
+    fingerprint.copy_from_slice(&value[..4]);
+    let path = value[4..]
+        .chunks(4)
+        .map(|child| u32::from_le_bytes([child[0], child[1], child[2], child[3]]))
+        .collect();
+    Ok(KeySource { fingerprint, path })
+}
+
+fn parse_input(pairs: Vec<(Bytes, Bytes)>) -> Result<PsbtInput, Error> {
+    let mut input = PsbtInput::default();
+    for (key, value) in pairs {
+        match key[0] {
+            PSBT_IN_NON_WITNESS_UTXO => {
+                expect_key_type_only(&key)?;
+                input.non_witness_utxo = Some(deserialize(value.as_slice())?);
+            },
+            PSBT_IN_WITNESS_UTXO => {
+                expect_key_type_only(&key)?;
+                input.witness_utxo = Some(deserialize(value.as_slice())?);
+            },
+            PSBT_IN_PARTIAL_SIG => {
+                input.partial_sigs.insert(key[1..].into(), value);
+            },
+            PSBT_IN_SIGHASH_TYPE => {
+                expect_key_type_only(&key)?;
+                input.sighash_type = Some(deserialize(value.as_slice())?);
+            },
+            PSBT_IN_BIP32_DERIVATION => {
+                input
+                    .bip32_derivation
+                    .insert(key[1..].into(), parse_key_source(&value)?);
+            },
+            PSBT_IN_FINAL_SCRIPTSIG => {
+                expect_key_type_only(&key)?;
+                input.final_script_sig = Some(value);
+            },
+            PSBT_IN_FINAL_SCRIPTWITNESS => {
+                expect_key_type_only(&key)?;
+                input.final_script_witness = Some(Reader::new(value.as_slice()).read_list()?);
+            },
+            _ => {
+                input.unknown.insert(key, value);
+            },
+        }
+    }
+    Ok(input)
+}
+
+fn parse_output(pairs: Vec<(Bytes, Bytes)>) -> Result<PsbtOutput, Error> {
+    let mut output = PsbtOutput::default();
+    for (key, value) in pairs {
+        match key[0] {
+            PSBT_OUT_BIP32_DERIVATION => {
+                output
+                    .bip32_derivation
+                    .insert(key[1..].into(), parse_key_source(&value)?);
+            },
+            _ => {
+                output.unknown.insert(key, value);
+            },
+        }
+    }
+    Ok(output)
+}
+
+impl Serializable for Psbt {
+    fn serialize(&self, stream: &mut Stream) {
+        stream.append_slice(&PSBT_MAGIC);
+
+        append_key_value(
+            stream,
+            &key(PSBT_GLOBAL_UNSIGNED_TX, &[]),
+            &serialize(&self.unsigned_tx),
+        );
+        append_unknown(stream, &self.unknown);
+        stream.append(&CompactInteger::from(0u8));
+
+        for input in self.inputs.iter() {
+            // The previous transaction is kept as is, including its witnesses.
+            if let Some(ref non_witness_utxo) = input.non_witness_utxo {
+                append_key_value(
+                    stream,
+                    &key(PSBT_IN_NON_WITNESS_UTXO, &[]),
+                    &serialize_with_flags(non_witness_utxo, SERIALIZE_TRANSACTION_WITNESS),
+                );
+            }
+            if let Some(ref witness_utxo) = input.witness_utxo {
+                append_key_value(stream, &key(PSBT_IN_WITNESS_UTXO, &[]), &serialize(witness_utxo));
+            }
+            for (pubkey, signature) in input.partial_sigs.iter() {
+                append_key_value(stream, &key(PSBT_IN_PARTIAL_SIG, pubkey), signature);
+            }
+            if let Some(ref sighash_type) = input.sighash_type {
+                append_key_value(stream, &key(PSBT_IN_SIGHASH_TYPE, &[]), &serialize(sighash_type));
+            }
+            append_derivations(stream, PSBT_IN_BIP32_DERIVATION, &input.bip32_derivation);
+            if let Some(ref final_script_sig) = input.final_script_sig {
+                append_key_value(stream, &key(PSBT_IN_FINAL_SCRIPTSIG, &[]), final_script_sig);
+            }
+            if let Some(ref final_script_witness) = input.final_script_witness {
+                let mut value = Stream::new();
+                value.append_list::<Bytes, Bytes>(final_script_witness);
+                append_key_value(stream, &key(PSBT_IN_FINAL_SCRIPTWITNESS, &[]), &value.out());
+            }
+            append_unknown(stream, &input.unknown);
+            stream.append(&CompactInteger::from(0u8));
+        }
+
+        for output in self.outputs.iter() {
+            append_derivations(stream, PSBT_OUT_BIP32_DERIVATION, &output.bip32_derivation);
+            append_unknown(stream, &output.unknown);
+            stream.append(&CompactInteger::from(0u8));
+        }
+    }
+}
+
+impl Deserializable for Psbt {
+    fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, Error>
+    where
+        Self: Sized,
+        T: io::Read,
+    {
+        let mut magic = [0; 5];
+        reader.read_slice(&mut magic)?;
+        if magic != PSBT_MAGIC {
+            return Err(Error::MalformedData);
+        }
+
+        let mut unsigned_tx = None;
+        let mut unknown = BTreeMap::new();
+        for (key, value) in read_map(reader)? {
+            match key[0] {
+                PSBT_GLOBAL_UNSIGNED_TX => {
+                    expect_key_type_only(&key)?;
+                    let tx: Transaction = deserialize(value.as_slice())?;
+                    check_unsigned(&tx)?;
+                    unsigned_tx = Some(tx);
+                },
+                _ => {
+                    unknown.insert(key, value);
+                },
+            }
+        }
+        let unsigned_tx = unsigned_tx.ok_or(Error::MalformedData)?;
+
+        let inputs = (0..unsigned_tx.inputs.len())
+            .map(|_| parse_input(read_map(reader)?))
+            .collect::<Result<_, _>>()?;
+        let outputs = (0..unsigned_tx.outputs.len())
+            .map(|_| parse_output(read_map(reader)?))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Psbt {
+            unsigned_tx,
+            unknown,
+            inputs,
+            outputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unsigned_tx() -> Transaction {
+        let mut tx: Transaction = "0100000001a6b97044d03da79c005b20ea9c0e1a6d9dc12d9f7b91a5911c9030a439eed8f5000000004948304502206e21798a42fae0e854281abd38bacd1aeed3ee3738d9e1446618c4571d1090db022100e2ac980643b0b82c0e88ffdfec6b64e3e6ba35e7ba5fdd7d5d6cc8d25c6b241501ffffffff0100f2052a010000001976a914404371705fa9bd789a2fcd52d2c580b65d35549d88ac00000000".into();
+        tx.inputs[0].script_sig = Bytes::new();
+        tx
+    }
+
+    #[test]
+    fn test_psbt_serde() {
+        let mut psbt = Psbt::new(unsigned_tx()).unwrap();
+        let pubkey: Bytes = "03d2e15674941bad4a996372cb87e1856d3652606d98562fe39c5e9e7e413f2105".into();
+        psbt.inputs[0].witness_utxo = Some(TransactionOutput {
+            value: 5000000000,
+            script_pubkey: "76a914404371705fa9bd789a2fcd52d2c580b65d35549d88ac".into(),
+        });
+        psbt.inputs[0].partial_sigs.insert(pubkey.clone(), "3044022001".into());
+        psbt.inputs[0].bip32_derivation.insert(pubkey.clone(), KeySource {
+            fingerprint: [0xd9, 0x0c, 0x6a, 0x4f],
+            path: vec![0x8000002c, 0x80000000, 0x80000000, 0, 1],
+        });
+        psbt.outputs[0].unknown.insert("fc01".into(), "00".into());
+
+        let serialized = serialize(&psbt);
+        assert_eq!(&serialized[..5], &PSBT_MAGIC);
+        let deserialized: Psbt = deserialize(serialized.as_slice()).unwrap();
+        assert_eq!(deserialized, psbt);
+    }
+
+    /// Checks that the `psbt_hex` is parsed and serialized back byte to byte.
+    fn parse_vector(psbt_hex: &'static str) -> Psbt {
+        let psbt_bytes: Bytes = psbt_hex.into();
+        let psbt: Psbt = deserialize(psbt_bytes.as_slice()).unwrap();
+        assert_eq!(serialize(&psbt), psbt_bytes);
+        psbt
+    }
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0174.mediawiki#test-vectors
+    #[test]
+    fn test_psbt_bip174_one_p2pkh_input() {
+        // PSBT with one P2PKH input. Outputs are empty.
+        let psbt = parse_vector("70736274ff0100750200000001268171371edff285e937adeea4b37b78000c0566cbb3ad64641713ca42171bf60000000000feffffff02d3dff505000000001976a914d0c59903c5bac2868760e90fd521a4665aa7652088ac00e1f5050000000017a9143545e6e33b832c47050f24d3eeb93c9c03948bc787b32e1300000100fda5010100000000010289a3c71eab4d20e0371bbba4cc698fa295c9463afa2e397f8533ccb62f9567e50100000017160014be18d152a9b012039daf3da7de4f53349eecb985ffffffff86f8aa43a71dff1448893a530a7237ef6b4608bbb2dd2d0171e63aec6a4890b40100000017160014fe3e9ef1a745e974d902c4355943abcb34bd5353ffffffff0200c2eb0b000000001976a91485cff1097fd9e008bb34af709c62197b38978a4888ac72fef84e2c00000017a914339725ba21efd62ac753a9bcd067d6c7a6a39d05870247304402202712be22e0270f394f568311dc7ca9a68970b8025fdd3b240229f07f8a5f3a240220018b38d7dcd314e734c9276bd6fb40f673325bc4baa144c800d2f2f02db2765c012103d2e15674941bad4a996372cb87e1856d3652606d98562fe39c5e9e7e413f210502483045022100d12b852d85dcd961d2f5f4ab660654df6eedcc794c0c33ce5cc309ffb5fce58d022067338a8e0e1725c197fb1a88af59f51e44e4255b20167c8684031c05d1f2592a01210223b72beef0965d10be0778efecd61fcac6f79a4ea169393380734464f84f2ab300000000000000");
+        assert_eq!(psbt.inputs.len(), 1);
+        assert_eq!(psbt.outputs, vec![PsbtOutput::default(); 2]);
+        // The previous transaction is segwit, its witnesses are kept.
+        let prev_tx = psbt.inputs[0].non_witness_utxo.as_ref().unwrap();
+        assert!(prev_tx.has_witness());
+        assert_eq!(prev_tx.hash(), psbt.unsigned_tx.inputs[0].previous_output.hash);
+        assert_eq!(psbt.spent_output(0).unwrap().value, 200000000);
+        assert!(!psbt.inputs[0].is_finalized());
+    }
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0174.mediawiki#test-vectors
+    #[test]
+    fn test_psbt_bip174_finalized_p2pkh_and_p2sh_p2wpkh_inputs() {
+        // PSBT with one P2PKH input and one P2SH-P2WPKH input.
+        // First input is signed and finalized. Outputs are empty.
+        let psbt = parse_vector("70736274ff0100a00200000002ab0949a08c5af7c49b8212f417e2f15ab3f5c33dcf153821a8139f877a5b7be40000000000feffffffab0949a08c5af7c49b8212f417e2f15ab3f5c33dcf153821a8139f877a5b7be40100000000feffffff02603bea0b000000001976a914768a40bbd740cbe81d988e71de2a4d5c71396b1d88ac8e240000000000001976a9146f4620b553fa095e721b9ee0efe9fa039cca459788ac000000000001076a47304402204759661797c01b036b25928948686218347d89864b719e1f7fcf57d1e511658702205309eabf56aa4d8891ffd111fdf1336f3a29da866d7f8486d75546ceedaf93190121035cdc61fc7ba971c0b501a646a2a83b102cb43881217ca682dc86e2d73fa882920001012000e1f5050000000017a9143545e6e33b832c47050f24d3eeb93c9c03948bc787010416001485d13537f2e265405a34dbafa9e3dda01fb82308000000");
+        assert_eq!(psbt.inputs.len(), 2);
+        assert!(psbt.inputs[0].is_finalized());
+        assert_eq!(psbt.inputs[0].final_script_sig.as_ref().unwrap().len(), 106);
+        assert!(!psbt.inputs[1].is_finalized());
+        assert_eq!(psbt.spent_output(1).unwrap().value, 100000000);
+        // The redeem script is not parsed.
+        let redeem_script: Bytes = "001485d13537f2e265405a34dbafa9e3dda01fb82308".into();
+        assert_eq!(psbt.inputs[1].unknown.get(&key(0x04, &[])), Some(&redeem_script));
+    }
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0174.mediawiki#test-vectors
+    #[test]
+    fn test_psbt_bip174_p2pkh_input_with_sighash_type() {
+        // PSBT with one P2PKH input which has a non-final scriptSig and has a sighash type specified.
+        // Outputs are empty.
+        let psbt = parse_vector("70736274ff0100750200000001268171371edff285e937adeea4b37b78000c0566cbb3ad64641713ca42171bf60000000000feffffff02d3dff505000000001976a914d0c59903c5bac2868760e90fd521a4665aa7652088ac00e1f5050000000017a9143545e6e33b832c47050f24d3eeb93c9c03948bc787b32e1300000100fda5010100000000010289a3c71eab4d20e0371bbba4cc698fa295c9463afa2e397f8533ccb62f9567e50100000017160014be18d152a9b012039daf3da7de4f53349eecb985ffffffff86f8aa43a71dff1448893a530a7237ef6b4608bbb2dd2d0171e63aec6a4890b40100000017160014fe3e9ef1a745e974d902c4355943abcb34bd5353ffffffff0200c2eb0b000000001976a91485cff1097fd9e008bb34af709c62197b38978a4888ac72fef84e2c00000017a914339725ba21efd62ac753a9bcd067d6c7a6a39d05870247304402202712be22e0270f394f568311dc7ca9a68970b8025fdd3b240229f07f8a5f3a240220018b38d7dcd314e734c9276bd6fb40f673325bc4baa144c800d2f2f02db2765c012103d2e15674941bad4a996372cb87e1856d3652606d98562fe39c5e9e7e413f210502483045022100d12b852d85dcd961d2f5f4ab660654df6eedcc794c0c33ce5cc309ffb5fce58d022067338a8e0e1725c197fb1a88af59f51e44e4255b20167c8684031c05d1f2592a01210223b72beef0965d10be0778efecd61fcac6f79a4ea169393380734464f84f2ab30000000001030401000000000000");
+        assert_eq!(psbt.inputs[0].sighash_type, Some(1));
+        assert!(psbt.inputs[0].non_witness_utxo.is_some());
+        assert!(psbt.inputs[0].witness_utxo.is_none());
+    }
+
+    #[test]
+    fn test_psbt_signed_tx_rejected() {
+        let mut tx = unsigned_tx();
+        tx.inputs[0].script_sig = "00".into();
+        assert_eq!(Psbt::new(tx).unwrap_err(), Error::MalformedData);
+
+        // The global map without the unsigned transaction.
+        let mut psbt_bytes = PSBT_MAGIC.to_vec();
+        psbt_bytes.push(0);
+        assert_eq!(
+            deserialize::<_, Psbt>(psbt_bytes.as_slice()).unwrap_err(),
+            Error::MalformedData
+        );
+    }
+}
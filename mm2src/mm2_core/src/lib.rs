@@ -3,6 +3,7 @@ use rand::{thread_rng, Rng};
 
 pub mod event_dispatcher;
 pub mod mm_ctx;
+pub mod rpc_registry;
 
 #[derive(Clone, Copy, Display, PartialEq)]
 pub enum DbNamespaceId {
@@ -87,6 +87,7 @@ pub struct MmCtx {
     /// The context belonging to the `ordermatch` mod: `OrdermatchContext`.
     pub ordermatch_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     pub rate_limit_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    pub rpc_permissions_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     pub simple_market_maker_bot_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     pub dispatcher_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     pub message_service_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
@@ -151,6 +152,7 @@ impl MmCtx {
             ffi_handle: Constructible::default(),
             ordermatch_ctx: Mutex::new(None),
             rate_limit_ctx: Mutex::new(None),
+            rpc_permissions_ctx: Mutex::new(None),
             simple_market_maker_bot_ctx: Mutex::new(None),
             dispatcher_ctx: Mutex::new(None),
             message_service_ctx: Mutex::new(None),
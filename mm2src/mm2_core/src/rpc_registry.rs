@@ -0,0 +1,243 @@
+//! The registry of the RPC methods.
+//!
+//! Every subsystem registers its methods along with the metadata in its own `register_rpc_methods` function
+//! taking the [`RpcMethodRegistry`], so the RPC dispatcher only assembles the registry from them.
+//! The mmrpc 2.0 handlers are usually wrapped with the [`mmrpc_handler`] macro.
+
+use crate::mm_ctx::MmArc;
+use common::log::error;
+use common::{HttpStatusCode, HyRes};
+use derive_more::Display;
+use futures::Future;
+use http::Response;
+use mm2_err_handle::prelude::*;
+use mm2_rpc::mm_protocol::{MmRpcBuilder, MmRpcRequest};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+
+/// The result of an mmrpc 2.0 handler.
+pub type MmRpcHandlerResult = Result<Response<Vec<u8>>, MmError<RpcHandlerError>>;
+#[cfg(not(target_arch = "wasm32"))]
+pub type MmRpcHandlerFut = futures::future::BoxFuture<'static, MmRpcHandlerResult>;
+#[cfg(target_arch = "wasm32")]
+pub type MmRpcHandlerFut = futures::future::LocalBoxFuture<'static, MmRpcHandlerResult>;
+
+/// The handler of an mmrpc 2.0 method.
+pub type MmRpcHandler = fn(MmArc, MmRpcRequest) -> MmRpcHandlerFut;
+/// The handler of a legacy method.
+pub type LegacyHandler = fn(MmArc, Json) -> HyRes;
+/// Registers the methods of a subsystem.
+pub type MethodsRegistrar = fn(&mut RpcMethodRegistry);
+
+/// The error returned by the mmrpc 2.0 handler before the method itself is called.
+/// The errors of the method are serialized into the response instead.
+#[derive(Debug, Display)]
+pub enum RpcHandlerError {
+    #[display(fmt = "Error parsing request: {}", _0)]
+    InvalidRequest(String),
+}
+
+impl From<json::Error> for RpcHandlerError {
+    fn from(e: json::Error) -> Self { RpcHandlerError::InvalidRequest(e.to_string()) }
+}
+
+/// Wraps the typed handler (see [`handle_mmrpc`]) into [`MmRpcHandler`].
+#[macro_export]
+macro_rules! mmrpc_handler {
+    ($handler:expr) => {
+        |ctx, request| {
+            Box::pin($crate::rpc_registry::handle_mmrpc(ctx, request, $handler))
+                as $crate::rpc_registry::MmRpcHandlerFut
+        }
+    };
+}
+
+/// # Examples
+///
+/// ```rust
+/// async fn withdraw(request: WithdrawRequest) -> Result<TransactionDetails, MmError<WithdrawError>>
+/// ```
+///
+/// where
+///     `Request` = `WithdrawRequest`,
+///     `T` = `TransactionDetails`,
+///     `E` = `WithdrawError`
+pub async fn handle_mmrpc<Handler, Fut, Request, T, E>(
+    ctx: MmArc,
+    request: MmRpcRequest,
+    handler: Handler,
+) -> MmRpcHandlerResult
+where
+    Handler: FnOnce(MmArc, Request) -> Fut,
+    Fut: Future<Output = Result<T, MmError<E>>>,
+    Request: DeserializeOwned,
+    T: serde::Serialize + 'static,
+    E: SerMmErrorType + HttpStatusCode + 'static,
+{
+    let params = json::from_value(request.params)?;
+    let result = handler(ctx, params).await;
+    if let Err(ref e) = result {
+        error!("RPC error response: {}", e);
+    }
+
+    let response = MmRpcBuilder::from_result(result)
+        .version(request.mmrpc)
+        .id(request.id)
+        .build();
+    Ok(response.serialize_http_response())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum AuthLevel {
+    /// Can be called without the `userpass` and from any IP even if `rpc_local_only` is set.
+    Public,
+    /// Requires the `rpc_password` or an API key allowed to call the method.
+    Userpass,
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub enum RpcMethodVersion {
+    #[serde(rename = "legacy")]
+    Legacy,
+    #[serde(rename = "2.0")]
+    V2,
+}
+
+impl RpcMethodVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RpcMethodVersion::Legacy => "legacy",
+            RpcMethodVersion::V2 => "2.0",
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct RpcMethodMeta {
+    pub name: &'static str,
+    pub version: RpcMethodVersion,
+    pub auth: AuthLevel,
+    pub description: &'static str,
+}
+
+impl RpcMethodMeta {
+    fn new(name: &'static str, version: RpcMethodVersion, auth: AuthLevel, description: &'static str) -> Self {
+        RpcMethodMeta {
+            name,
+            version,
+            auth,
+            description,
+        }
+    }
+}
+
+pub struct RpcMethod<Handler> {
+    pub meta: RpcMethodMeta,
+    pub handler: Handler,
+}
+
+#[derive(Default)]
+pub struct RpcMethodRegistry {
+    mmrpc: HashMap<&'static str, RpcMethod<MmRpcHandler>>,
+    legacy: HashMap<&'static str, RpcMethod<LegacyHandler>>,
+    /// The methods registered more than once.
+    duplicates: Vec<String>,
+}
+
+impl RpcMethodRegistry {
+    /// Registers the mmrpc 2.0 method requiring the `userpass`.
+    ///
+    /// If the method is registered already, the first handler is kept and the method is reported as a duplicate.
+    pub fn register(&mut self, name: &'static str, description: &'static str, handler: MmRpcHandler) {
+        self.register_with_auth(name, AuthLevel::Userpass, description, handler)
+    }
+
+    /// Registers the mmrpc 2.0 method with the given `auth` level.
+    pub fn register_with_auth(
+        &mut self,
+        name: &'static str,
+        auth: AuthLevel,
+        description: &'static str,
+        handler: MmRpcHandler,
+    ) {
+        if self.mmrpc.contains_key(name) {
+            self.duplicates.push(format!("'{}' mmrpc 2.0", name));
+            return;
+        }
+        let meta = RpcMethodMeta::new(name, RpcMethodVersion::V2, auth, description);
+        self.mmrpc.insert(name, RpcMethod { meta, handler });
+    }
+
+    /// Registers the legacy method with the given `auth` level.
+    pub fn register_legacy(
+        &mut self,
+        name: &'static str,
+        auth: AuthLevel,
+        description: &'static str,
+        handler: LegacyHandler,
+    ) {
+        if self.legacy.contains_key(name) {
+            self.duplicates.push(format!("'{}' legacy", name));
+            return;
+        }
+        let meta = RpcMethodMeta::new(name, RpcMethodVersion::Legacy, auth, description);
+        self.legacy.insert(name, RpcMethod { meta, handler });
+    }
+
+    pub fn mmrpc_method(&self, name: &str) -> Option<&RpcMethod<MmRpcHandler>> { self.mmrpc.get(name) }
+
+    pub fn legacy_method(&self, name: &str) -> Option<&RpcMethod<LegacyHandler>> { self.legacy.get(name) }
+
+    /// Returns the methods registered more than once, e.g. `'withdraw' mmrpc 2.0`.
+    pub fn duplicates(&self) -> &[String] { &self.duplicates }
+
+    /// Returns the metadata of all the methods sorted by the version and the name.
+    pub fn methods_meta(&self) -> Vec<RpcMethodMeta> {
+        let mut methods: Vec<_> = self
+            .legacy
+            .values()
+            .map(|method| method.meta.clone())
+            .chain(self.mmrpc.values().map(|method| method.meta.clone()))
+            .collect();
+        methods.sort_by_key(|meta| (meta.version as u8, meta.name));
+        methods
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_handler(_ctx: MmArc, _req: MmRpcRequest) -> MmRpcHandlerFut {
+        Box::pin(async { Ok(Response::new(b"first".to_vec())) })
+    }
+
+    fn other_test_handler(_ctx: MmArc, _req: MmRpcRequest) -> MmRpcHandlerFut {
+        Box::pin(async { Ok(Response::new(b"second".to_vec())) })
+    }
+
+    #[test]
+    fn test_duplicate_registration_is_reported() {
+        let mut registry = RpcMethodRegistry::default();
+        registry.register("test::method", "The first registration.", test_handler);
+        registry.register("test::method", "The second registration.", other_test_handler);
+        registry.register_legacy("test_method", AuthLevel::Userpass, "The first registration.", |_, _| {
+            Box::new(futures01::future::ok(Response::new(Vec::new())))
+        });
+        registry.register_legacy("test_method", AuthLevel::Public, "The second registration.", |_, _| {
+            Box::new(futures01::future::ok(Response::new(Vec::new())))
+        });
+
+        assert_eq!(registry.duplicates(), [
+            "'test::method' mmrpc 2.0",
+            "'test_method' legacy"
+        ]);
+        // The first registration is kept.
+        let method = registry.mmrpc_method("test::method").unwrap();
+        assert_eq!(method.meta.description, "The first registration.");
+        let legacy = registry.legacy_method("test_method").unwrap();
+        assert_eq!(legacy.meta.auth, AuthLevel::Userpass);
+    }
+}
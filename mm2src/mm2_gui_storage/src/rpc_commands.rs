@@ -5,6 +5,8 @@ use crate::context::AccountContext;
 use common::{HttpStatusCode, StatusCode, SuccessResponse};
 use derive_more::Display;
 use mm2_core::mm_ctx::MmArc;
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::RpcMethodRegistry;
 use mm2_err_handle::prelude::*;
 use mm2_number::BigDecimal;
 use ser_error_derive::SerializeErrorType;
@@ -378,3 +380,62 @@ fn validate_tickers(tickers: &[String]) -> MmResult<(), AccountRpcError> {
     }
     Ok(())
 }
+
+/// Registers the `gui_storage::*` mmrpc 2.0 methods.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "gui_storage::activate_coins",
+        "Adds the coins to the GUI account.",
+        mmrpc_handler!(activate_coins),
+    );
+    registry.register(
+        "gui_storage::add_account",
+        "Adds a new GUI account.",
+        mmrpc_handler!(add_account),
+    );
+    registry.register(
+        "gui_storage::deactivate_coins",
+        "Removes the coins from the GUI account.",
+        mmrpc_handler!(deactivate_coins),
+    );
+    registry.register(
+        "gui_storage::delete_account",
+        "Deletes the GUI account.",
+        mmrpc_handler!(delete_account),
+    );
+    registry.register(
+        "gui_storage::enable_account",
+        "Sets the enabled GUI account.",
+        mmrpc_handler!(enable_account),
+    );
+    registry.register(
+        "gui_storage::get_account_coins",
+        "Lists the coins of the GUI account.",
+        mmrpc_handler!(get_account_coins),
+    );
+    registry.register(
+        "gui_storage::get_accounts",
+        "Lists the GUI accounts.",
+        mmrpc_handler!(get_accounts),
+    );
+    registry.register(
+        "gui_storage::get_enabled_account",
+        "Returns the enabled GUI account.",
+        mmrpc_handler!(get_enabled_account),
+    );
+    registry.register(
+        "gui_storage::set_account_balance",
+        "Sets the balance of the GUI account.",
+        mmrpc_handler!(set_account_balance),
+    );
+    registry.register(
+        "gui_storage::set_account_description",
+        "Sets the description of the GUI account.",
+        mmrpc_handler!(set_account_description),
+    );
+    registry.register(
+        "gui_storage::set_account_name",
+        "Sets the name of the GUI account.",
+        mmrpc_handler!(set_account_name),
+    );
+}
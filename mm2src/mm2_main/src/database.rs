@@ -105,6 +105,12 @@ fn migration_9() -> Vec<(&'static str, Vec<String>)> {
     db_common::sqlite::execute_batch(my_swaps::TRADING_PROTO_UPGRADE_MIGRATION)
 }
 
+fn migration_10() -> Vec<(&'static str, Vec<String>)> {
+    db_common::sqlite::execute_batch(stats_swaps::ADD_SWAP_OUTCOME)
+}
+
+fn migration_11() -> Vec<(&'static str, Vec<String>)> { vec![(my_swaps::CREATE_SWAP_V2_MSGS_TABLE, vec![])] }
+
 async fn statements_for_migration(ctx: &MmArc, current_migration: i64) -> Option<Vec<(&'static str, Vec<String>)>> {
     match current_migration {
         1 => Some(migration_1(ctx).await),
@@ -116,6 +122,8 @@ async fn statements_for_migration(ctx: &MmArc, current_migration: i64) -> Option
         7 => Some(migration_7()),
         8 => Some(migration_8()),
         9 => Some(migration_9()),
+        10 => Some(migration_10()),
+        11 => Some(migration_11()),
         _ => None,
     }
 }
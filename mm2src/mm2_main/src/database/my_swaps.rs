@@ -1,15 +1,16 @@
 #![allow(deprecated)] // TODO: remove this once rusqlite is >= 0.29
 
 /// This module contains code to work with my_swaps table in MM2 SQLite DB
-use crate::mm2::lp_swap::{MyRecentSwapsUuids, MySwapsFilter, SavedSwap, SavedSwapIo};
+use crate::mm2::lp_swap::{MyRecentSwapsUuids, MySwapV2Data, MySwapsFilter, SavedSwap, SavedSwapIo, MAKER_SWAP_V2_TYPE,
+                          TAKER_SWAP_V2_TYPE};
 use common::log::debug;
 use common::PagingOptions;
 use db_common::sqlite::offset_by_uuid;
-use db_common::sqlite::rusqlite::{Connection, Error as SqlError, Result as SqlResult, Row, ToSql};
+use db_common::sqlite::rusqlite::{named_params, Connection, Error as SqlError, Result as SqlResult, Row, ToSql};
 use db_common::sqlite::sql_builder::SqlBuilder;
 use mm2_core::mm_ctx::MmArc;
 use std::convert::TryInto;
-use uuid::Error as UuidError;
+use uuid::{Error as UuidError, Uuid};
 
 const MY_SWAPS_TABLE: &str = "my_swaps";
 
@@ -101,8 +102,34 @@ const INSERT_MY_SWAP_V2: &str = r#"INSERT INTO my_swaps (
     :taker_coin_nota
 );"#;
 
-pub fn insert_new_swap_v2(ctx: &MmArc, params: &[(&str, &dyn ToSql)]) -> SqlResult<()> {
-    let conn = ctx.sqlite_connection();
+pub fn insert_new_swap_v2(
+    conn: &Connection,
+    uuid: &str,
+    swap_type: u8,
+    secret_hash: &[u8],
+    data: &MySwapV2Data,
+) -> SqlResult<()> {
+    debug!("Inserting new swap v2 {} to the SQLite database", uuid);
+    let params = named_params! {
+        ":my_coin": data.my_coin,
+        ":other_coin": data.other_coin,
+        ":uuid": uuid,
+        ":started_at": data.started_at,
+        ":swap_type": swap_type,
+        ":maker_volume": data.maker_volume,
+        ":taker_volume": data.taker_volume,
+        ":premium": data.premium,
+        ":dex_fee": data.dex_fee,
+        ":secret": data.secret,
+        ":secret_hash": secret_hash,
+        ":secret_hash_algo": data.secret_hash_algo,
+        ":p2p_privkey": data.p2p_privkey,
+        ":lock_duration": data.lock_duration,
+        ":maker_coin_confs": data.maker_coin_confs,
+        ":maker_coin_nota": data.maker_coin_nota,
+        ":taker_coin_confs": data.taker_coin_confs,
+        ":taker_coin_nota": data.taker_coin_nota
+    };
     conn.execute(INSERT_MY_SWAP_V2, params).map(|_| ())
 }
 
@@ -176,7 +203,65 @@ pub fn select_uuids_by_my_swaps_filter(
     let mut query_builder = SqlBuilder::select_from(MY_SWAPS_TABLE);
     let mut params = vec![];
     apply_my_swaps_filter(&mut query_builder, &mut params, filter);
+    select_uuids_with_paging(conn, query_builder, params, paging_options)
+}
+
+/// Adds where clauses selecting only the swaps v2.
+fn apply_swaps_v2_filter(builder: &mut SqlBuilder, unfinished_only: bool) {
+    builder.and_where(format!("swap_type IN ({}, {})", MAKER_SWAP_V2_TYPE, TAKER_SWAP_V2_TYPE));
+    if unfinished_only {
+        builder.and_where("is_finished = 0");
+    }
+}
+
+fn select_uuids_with_paging(
+    conn: &Connection,
+    query_builder: SqlBuilder,
+    params: Vec<(&str, String)>,
+    paging_options: Option<&PagingOptions>,
+) -> SqlResult<MyRecentSwapsUuids, SelectRecentSwapsUuidsErr> {
+    let selected = select_with_paging(conn, query_builder, params, paging_options, &["uuid"], |row| {
+        row.get::<_, String>(0)
+    })?;
+    let uuids: SqlResult<Vec<_>, _> = selected.records.into_iter().map(|uuid| uuid.parse()).collect();
+    let uuids = uuids?;
+
+    Ok(MyRecentSwapsUuids {
+        uuids,
+        total_count: selected.total_count,
+        skipped: selected.skipped,
+    })
+}
+
+/// The page of the records selected by [`select_with_paging`].
+struct SelectedRecords<T> {
+    records: Vec<T>,
+    total_count: usize,
+    skipped: usize,
+}
+
+impl<T> Default for SelectedRecords<T> {
+    fn default() -> Self {
+        SelectedRecords {
+            records: Vec::new(),
+            total_count: 0,
+            skipped: 0,
+        }
+    }
+}
 
+/// Selects the `fields` of the swaps matching the query, the most recent ones first.
+fn select_with_paging<T, F>(
+    conn: &Connection,
+    mut query_builder: SqlBuilder,
+    params: Vec<(&str, String)>,
+    paging_options: Option<&PagingOptions>,
+    fields: &[&str],
+    from_row: F,
+) -> SqlResult<SelectedRecords<T>>
+where
+    F: FnMut(&Row) -> SqlResult<T>,
+{
     // count total records matching the filter
     let mut count_builder = query_builder.clone();
     count_builder.count("id");
@@ -188,13 +273,9 @@ pub fn select_uuids_by_my_swaps_filter(
     let total_count: isize = conn.query_row_named(&count_query, params_as_trait.as_slice(), |row| row.get(0))?;
     let total_count = total_count.try_into().expect("COUNT should always be >= 0");
     if total_count == 0 {
-        return Ok(MyRecentSwapsUuids::default());
+        return Ok(SelectedRecords::default());
     }
 
-    // query the uuids finally
-    query_builder.field("uuid");
-    query_builder.order_desc("started_at");
-
     let skipped = match paging_options {
         Some(paging) => {
             // calculate offset, page_number is ignored if from_uuid is set
@@ -209,17 +290,19 @@ pub fn select_uuids_by_my_swaps_filter(
         None => 0,
     };
 
-    let uuids_query = query_builder.sql().expect("SQL query builder should never fail here");
-    debug!("Trying to execute SQL query {} with params {:?}", uuids_query, params);
-    let mut stmt = conn.prepare(&uuids_query)?;
-    let uuids = stmt
-        .query_map_named(params_as_trait.as_slice(), |row| row.get(0))?
-        .collect::<SqlResult<Vec<String>>>()?;
-    let uuids: SqlResult<Vec<_>, _> = uuids.into_iter().map(|uuid| uuid.parse()).collect();
-    let uuids = uuids?;
+    // query the records finally
+    query_builder.fields(fields);
+    query_builder.order_desc("started_at");
 
-    Ok(MyRecentSwapsUuids {
-        uuids,
+    let records_query = query_builder.sql().expect("SQL query builder should never fail here");
+    debug!("Trying to execute SQL query {} with params {:?}", records_query, params);
+    let mut stmt = conn.prepare(&records_query)?;
+    let records = stmt
+        .query_map_named(params_as_trait.as_slice(), from_row)?
+        .collect::<SqlResult<Vec<T>>>()?;
+
+    Ok(SelectedRecords {
+        records,
         total_count,
         skipped,
     })
@@ -249,12 +332,116 @@ pub fn update_swap_events(conn: &Connection, uuid: &str, events_json: &str) -> S
         .map(|_| ())
 }
 
+/// Marks swap as finished by uuid
 pub fn set_swap_is_finished(conn: &Connection, uuid: &str) -> SqlResult<()> {
     const UPDATE_SWAP_IS_FINISHED_BY_UUID: &str = "UPDATE my_swaps SET is_finished = 1 WHERE uuid = :uuid;";
     let mut stmt = conn.prepare(UPDATE_SWAP_IS_FINISHED_BY_UUID)?;
     stmt.execute(&[(":uuid", uuid)]).map(|_| ())
 }
 
+/// Queries uuids of the swaps of the given type that are not finished yet
+pub fn select_unfinished_swaps_uuids(
+    conn: &Connection,
+    swap_type: u8,
+) -> SqlResult<Vec<Uuid>, SelectRecentSwapsUuidsErr> {
+    const SELECT_UNFINISHED_SWAPS_UUIDS_BY_TYPE: &str =
+        "SELECT uuid FROM my_swaps WHERE is_finished = 0 AND swap_type = :swap_type;";
+    let mut stmt = conn.prepare(SELECT_UNFINISHED_SWAPS_UUIDS_BY_TYPE)?;
+    let uuids = stmt
+        .query_map(&[(":swap_type", &swap_type)], |row| row.get(0))?
+        .collect::<SqlResult<Vec<String>>>()?;
+    let uuids: SqlResult<Vec<_>, _> = uuids.into_iter().map(|uuid| uuid.parse()).collect();
+    Ok(uuids?)
+}
+
+/// Stores the p2p messages received for swaps v2, so they can be processed after the swap is restored
+pub const CREATE_SWAP_V2_MSGS_TABLE: &str = "CREATE TABLE IF NOT EXISTS swap_v2_msgs (
+    id INTEGER NOT NULL PRIMARY KEY,
+    uuid VARCHAR(255) NOT NULL,
+    msg_kind VARCHAR(255) NOT NULL,
+    msg BLOB NOT NULL,
+    UNIQUE(uuid, msg_kind)
+);";
+
+/// Inserts the swap v2 p2p message replacing the previously received one of the same kind
+pub fn insert_swap_v2_msg(conn: &Connection, uuid: &str, msg_kind: &str, msg: &[u8]) -> SqlResult<()> {
+    const INSERT_SWAP_V2_MSG: &str =
+        "INSERT OR REPLACE INTO swap_v2_msgs (uuid, msg_kind, msg) VALUES (:uuid, :msg_kind, :msg);";
+    let mut stmt = conn.prepare(INSERT_SWAP_V2_MSG)?;
+    stmt.execute(named_params! {
+        ":uuid": uuid,
+        ":msg_kind": msg_kind,
+        ":msg": msg,
+    })
+    .map(|_| ())
+}
+
+/// Queries swap v2 p2p messages by uuid
+pub fn select_swap_v2_msgs(conn: &Connection, uuid: &str) -> SqlResult<Vec<Vec<u8>>> {
+    const SELECT_SWAP_V2_MSGS_BY_UUID: &str = "SELECT msg FROM swap_v2_msgs WHERE uuid = :uuid;";
+    let mut stmt = conn.prepare(SELECT_SWAP_V2_MSGS_BY_UUID)?;
+    let msgs = stmt
+        .query_map(&[(":uuid", uuid)], |row| row.get(0))?
+        .collect::<SqlResult<Vec<Vec<u8>>>>()?;
+    Ok(msgs)
+}
+
+/// Deletes swap v2 p2p messages by uuid
+pub fn delete_swap_v2_msgs(conn: &Connection, uuid: &str) -> SqlResult<()> {
+    const DELETE_SWAP_V2_MSGS_BY_UUID: &str = "DELETE FROM swap_v2_msgs WHERE uuid = :uuid;";
+    let mut stmt = conn.prepare(DELETE_SWAP_V2_MSGS_BY_UUID)?;
+    stmt.execute(&[(":uuid", uuid)]).map(|_| ())
+}
+
+const SELECT_MY_SWAP_V2_BY_UUID: &str = r#"SELECT
+    my_coin,
+    other_coin,
+    started_at,
+    events_json,
+    maker_volume,
+    taker_volume,
+    premium,
+    dex_fee,
+    secret,
+    secret_hash_algo,
+    p2p_privkey,
+    lock_duration,
+    maker_coin_confs,
+    maker_coin_nota,
+    taker_coin_confs,
+    taker_coin_nota
+FROM my_swaps
+WHERE uuid = :uuid;
+"#;
+
+fn my_swap_v2_data_from_row(row: &Row) -> SqlResult<MySwapV2Data> {
+    Ok(MySwapV2Data {
+        my_coin: row.get(0)?,
+        other_coin: row.get(1)?,
+        started_at: row.get(2)?,
+        events_json: row.get(3)?,
+        maker_volume: row.get(4)?,
+        taker_volume: row.get(5)?,
+        premium: row.get(6)?,
+        dex_fee: row.get(7)?,
+        secret: row.get(8)?,
+        secret_hash_algo: row.get(9)?,
+        p2p_privkey: row.get(10)?,
+        lock_duration: row.get(11)?,
+        maker_coin_confs: row.get(12)?,
+        maker_coin_nota: row.get(13)?,
+        taker_coin_confs: row.get(14)?,
+        taker_coin_nota: row.get(15)?,
+    })
+}
+
+/// Queries `MySwapV2Data` by uuid
+pub fn get_swap_v2_data(conn: &Connection, uuid: &str) -> SqlResult<MySwapV2Data> {
+    let mut stmt = conn.prepare(SELECT_MY_SWAP_V2_BY_UUID)?;
+    let swap_data = stmt.query_row(&[(":uuid", uuid)], my_swap_v2_data_from_row)?;
+    Ok(swap_data)
+}
+
 const SELECT_MY_SWAP_V2_FOR_RPC_BY_UUID: &str = r#"SELECT
     my_coin,
     other_coin,
@@ -280,23 +467,23 @@ WHERE uuid = :uuid;
 /// Represents data of the swap used for RPC, omits fields that should be kept in secret
 #[derive(Debug, Serialize)]
 pub struct MySwapForRpc {
-    my_coin: String,
-    other_coin: String,
-    uuid: String,
-    started_at: i64,
-    is_finished: bool,
-    events_json: String,
-    maker_volume: String,
-    taker_volume: String,
-    premium: String,
-    dex_fee: String,
-    secret_hash: Vec<u8>,
-    secret_hash_algo: i64,
-    lock_duration: i64,
-    maker_coin_confs: i64,
-    maker_coin_nota: bool,
-    taker_coin_confs: i64,
-    taker_coin_nota: bool,
+    pub my_coin: String,
+    pub other_coin: String,
+    pub uuid: String,
+    pub started_at: i64,
+    pub is_finished: bool,
+    pub events_json: String,
+    pub maker_volume: String,
+    pub taker_volume: String,
+    pub premium: String,
+    pub dex_fee: String,
+    pub secret_hash: Vec<u8>,
+    pub secret_hash_algo: i64,
+    pub lock_duration: i64,
+    pub maker_coin_confs: i64,
+    pub maker_coin_nota: bool,
+    pub taker_coin_confs: i64,
+    pub taker_coin_nota: bool,
 }
 
 impl MySwapForRpc {
@@ -329,3 +516,61 @@ pub fn get_swap_data_for_rpc(conn: &Connection, uuid: &str) -> SqlResult<MySwapF
     let swap_data = stmt.query_row(&[(":uuid", uuid)], MySwapForRpc::from_row)?;
     Ok(swap_data)
 }
+
+/// The columns of [`MySwapForRpc`] followed by the swap type.
+const MY_SWAP_V2_FOR_RPC_FIELDS: &[&str] = &[
+    "my_coin",
+    "other_coin",
+    "uuid",
+    "started_at",
+    "is_finished",
+    "events_json",
+    "maker_volume",
+    "taker_volume",
+    "premium",
+    "dex_fee",
+    "secret_hash",
+    "secret_hash_algo",
+    "lock_duration",
+    "maker_coin_confs",
+    "maker_coin_nota",
+    "taker_coin_confs",
+    "taker_coin_nota",
+    "swap_type",
+];
+
+/// The page of the swaps v2 selected by [`select_swaps_v2_for_rpc_by_filter`].
+pub struct MySwapsV2ForRpc {
+    /// The swap types and data.
+    pub swaps: Vec<(u8, MySwapForRpc)>,
+    pub total_count: usize,
+    pub skipped: usize,
+}
+
+/// Queries `MySwapForRpc` of the swaps v2 matching the filter with a single query.
+/// If `unfinished_only` is set, only the swaps that are not finished yet are selected.
+pub fn select_swaps_v2_for_rpc_by_filter(
+    conn: &Connection,
+    filter: &MySwapsFilter,
+    unfinished_only: bool,
+    paging_options: Option<&PagingOptions>,
+) -> SqlResult<MySwapsV2ForRpc> {
+    let mut query_builder = SqlBuilder::select_from(MY_SWAPS_TABLE);
+    let mut params = vec![];
+    apply_my_swaps_filter(&mut query_builder, &mut params, filter);
+    apply_swaps_v2_filter(&mut query_builder, unfinished_only);
+
+    let selected = select_with_paging(
+        conn,
+        query_builder,
+        params,
+        paging_options,
+        MY_SWAP_V2_FOR_RPC_FIELDS,
+        |row| Ok((row.get(17)?, MySwapForRpc::from_row(row)?)),
+    )?;
+    Ok(MySwapsV2ForRpc {
+        swaps: selected.records,
+        total_count: selected.total_count,
+        skipped: selected.skipped,
+    })
+}
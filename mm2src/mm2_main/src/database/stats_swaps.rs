@@ -3,7 +3,8 @@
 use crate::mm2::lp_swap::{MakerSavedSwap, SavedSwap, SavedSwapIo, TakerSavedSwap};
 use common::log::{debug, error};
 use db_common::{owned_named_params,
-                sqlite::{rusqlite::{params_from_iter, Connection, OptionalExtension},
+                sqlite::{rusqlite::{named_params, params_from_iter, Connection, OptionalExtension,
+                                    Result as SqlResult, Row},
                          AsSqlNamedParams, OwnedSqlNamedParams}};
 use mm2_core::mm_ctx::MmArc;
 use std::collections::HashSet;
@@ -47,10 +48,12 @@ const INSERT_STATS_SWAP: &str = "INSERT INTO stats_swaps (
     maker_coin_usd_price,
     taker_coin_usd_price,
     maker_pubkey,
-    taker_pubkey
+    taker_pubkey,
+    is_refunded,
+    at_fault_pubkey
 ) VALUES (:maker_coin, :maker_coin_ticker, :maker_coin_platform, :taker_coin, :taker_coin_ticker, 
 :taker_coin_platform, :uuid, :started_at, :finished_at, :maker_amount, :taker_amount, :is_success, 
-:maker_coin_usd_price, :taker_coin_usd_price, :maker_pubkey, :taker_pubkey)";
+:maker_coin_usd_price, :taker_coin_usd_price, :maker_pubkey, :taker_pubkey, :is_refunded, :at_fault_pubkey)";
 
 pub const ADD_COINS_PRICE_INFOMATION: &[&str] = &[
     "ALTER TABLE stats_swaps ADD COLUMN maker_coin_usd_price DECIMAL;",
@@ -85,10 +88,70 @@ pub const ADD_SPLIT_TICKERS: &[&str] = &[
         END;",
 ];
 
+/// `is_refunded` is set if a payment of the failed swap was refunded.
+/// `at_fault_pubkey` is the pubkey of the party that caused the swap to fail if it's known.
+pub const ADD_SWAP_OUTCOME: &[&str] = &[
+    "ALTER TABLE stats_swaps ADD COLUMN is_refunded INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE stats_swaps ADD COLUMN at_fault_pubkey VARCHAR(255);",
+    "CREATE INDEX maker_pubkey_index ON stats_swaps (maker_pubkey);",
+    "CREATE INDEX taker_pubkey_index ON stats_swaps (taker_pubkey);",
+];
+
 pub const ADD_STARTED_AT_INDEX: &str = "CREATE INDEX timestamp_index ON stats_swaps (started_at);";
 
 pub const SELECT_ID_BY_UUID: &str = "SELECT id FROM stats_swaps WHERE uuid = ?1";
 
+/// The amounts are casted to TEXT since the DECIMAL column affinity stores them as INTEGER or REAL.
+const SELECT_SUCCESSFUL_SWAPS_FOR_PAIR: &str = "SELECT maker_coin, CAST(maker_amount AS TEXT), \
+    CAST(taker_amount AS TEXT), finished_at FROM stats_swaps \
+    WHERE is_success = 1 \
+    AND ((maker_coin = :base AND taker_coin = :rel) OR (maker_coin = :rel AND taker_coin = :base)) \
+    AND finished_at >= :from AND finished_at <= :to \
+    ORDER BY finished_at, id;";
+
+/// Only the swaps of this node are selected since the swaps broadcasted by the other nodes can be forged.
+/// A failed swap is counted against the pubkey only if it's known to cause the failure.
+const SELECT_PUBKEY_SWAP_OUTCOMES: &str = "SELECT \
+    COALESCE(SUM(is_success = 1), 0), \
+    COALESCE(SUM(is_success = 0 AND is_refunded = 0 AND at_fault_pubkey IN (:even_pubkey, :odd_pubkey)), 0), \
+    COALESCE(SUM(is_success = 0 AND is_refunded = 1 AND at_fault_pubkey IN (:even_pubkey, :odd_pubkey)), 0), \
+    AVG(CASE WHEN is_success = 1 THEN finished_at - started_at END) \
+    FROM stats_swaps \
+    WHERE (maker_pubkey IN (:even_pubkey, :odd_pubkey) OR taker_pubkey IN (:even_pubkey, :odd_pubkey)) \
+    AND uuid IN (SELECT uuid FROM my_swaps);";
+
+/// The successful swap of the pair observed by the node.
+#[derive(Debug)]
+pub struct StatsSwapTrade {
+    pub maker_coin: String,
+    pub maker_amount: String,
+    pub taker_amount: String,
+    pub finished_at: u64,
+}
+
+impl StatsSwapTrade {
+    fn from_row(row: &Row<'_>) -> SqlResult<Self> {
+        Ok(StatsSwapTrade {
+            maker_coin: row.get(0)?,
+            maker_amount: row.get(1)?,
+            taker_amount: row.get(2)?,
+            finished_at: row.get::<_, i64>(3)? as u64,
+        })
+    }
+}
+
+/// The outcomes of the swaps of this node with a pubkey.
+#[derive(Debug, Default, PartialEq)]
+pub struct PubkeySwapOutcomes {
+    pub completed: u64,
+    /// The swaps failed before any payment had to be refunded.
+    pub aborted: u64,
+    /// The swaps failed after a payment was sent, so it had to be refunded.
+    pub refunded: u64,
+    /// The average duration of the completed swaps in seconds.
+    pub avg_swap_duration: Option<u64>,
+}
+
 /// Returns SQL statements to initially fill stats_swaps table using existing DB with JSON files
 pub async fn create_and_fill_stats_swaps_from_json_statements(ctx: &MmArc) -> Vec<(&'static str, Vec<String>)> {
     let maker_swaps = SavedSwap::load_all_from_maker_stats_db(ctx).await.unwrap_or_default();
@@ -168,6 +231,8 @@ fn insert_stats_maker_swap_sql(swap: &MakerSavedSwap) -> Option<(&'static str, O
         ":is_success": (is_success as u32).to_string(),
         ":maker_coin_usd_price": swap.maker_coin_usd_price.as_ref().map(|p| p.to_string()),
         ":taker_coin_usd_price": swap.taker_coin_usd_price.as_ref().map(|p| p.to_string()),
+        ":is_refunded": (swap.is_refunded() as u32).to_string(),
+        ":at_fault_pubkey": swap.taker_caused_failure().then(|| pubkeys.taker.clone()),
         ":maker_pubkey": pubkeys.maker,
         ":taker_pubkey": pubkeys.taker,
     };
@@ -253,6 +318,8 @@ fn insert_stats_taker_swap_sql(swap: &TakerSavedSwap) -> Option<(&'static str, O
         ":is_success": (is_success as u32).to_string(),
         ":maker_coin_usd_price": swap.maker_coin_usd_price.as_ref().map(|p| p.to_string()),
         ":taker_coin_usd_price": swap.taker_coin_usd_price.as_ref().map(|p| p.to_string()),
+        ":is_refunded": (swap.is_refunded() as u32).to_string(),
+        ":at_fault_pubkey": swap.maker_caused_failure().then(|| pubkeys.maker.clone()),
         ":maker_pubkey": pubkeys.maker,
         ":taker_pubkey": pubkeys.taker,
     };
@@ -323,6 +390,89 @@ pub fn add_swap_to_index(conn: &Connection, swap: &SavedSwap) {
     };
 }
 
+/// Selects the successful swaps of the pair in any direction finished within the `[from, to]` range.
+pub fn select_successful_swaps_for_pair(
+    conn: &Connection,
+    base: &str,
+    rel: &str,
+    from: u64,
+    to: u64,
+) -> SqlResult<Vec<StatsSwapTrade>> {
+    let mut stmt = conn.prepare(SELECT_SUCCESSFUL_SWAPS_FOR_PAIR)?;
+    let trades = stmt
+        .query_map(
+            named_params! {
+                ":base": base,
+                ":rel": rel,
+                ":from": (from as i64),
+                ":to": (to as i64),
+            },
+            StatsSwapTrade::from_row,
+        )?
+        .collect::<SqlResult<Vec<_>>>()?;
+    Ok(trades)
+}
+
+/// Selects the outcomes of the swaps of this node with the pubkey given without the parity prefix as a hex string.
+pub fn select_pubkey_swap_outcomes(conn: &Connection, unprefixed_pubkey: &str) -> SqlResult<PubkeySwapOutcomes> {
+    // The pubkeys are stored compressed, so both parities of the pubkey are checked.
+    let even_pubkey = format!("02{}", unprefixed_pubkey);
+    let odd_pubkey = format!("03{}", unprefixed_pubkey);
+    conn.query_row(
+        SELECT_PUBKEY_SWAP_OUTCOMES,
+        named_params! {
+            ":even_pubkey": even_pubkey,
+            ":odd_pubkey": odd_pubkey,
+        },
+        |row| {
+            Ok(PubkeySwapOutcomes {
+                completed: row.get::<_, i64>(0)? as u64,
+                aborted: row.get::<_, i64>(1)? as u64,
+                refunded: row.get::<_, i64>(2)? as u64,
+                avg_swap_duration: row.get::<_, Option<f64>>(3)?.map(|duration| duration.round() as u64),
+            })
+        },
+    )
+}
+
+/// `(finished_at, is_success, maker_pubkey, taker_pubkey, is_refunded, at_fault_pubkey, is_my_swap)`
+#[cfg(test)]
+pub(crate) type TestSwapOutcome<'a> = (u64, bool, &'a str, &'a str, bool, Option<&'a str>, bool);
+
+/// Creates the `stats_swaps` and `my_swaps` tables and inserts the swaps with the given outcomes.
+#[cfg(test)]
+pub(crate) fn insert_swap_outcomes_for_tests(conn: &Connection, swaps: &[TestSwapOutcome<'_>]) {
+    const INSERT_SWAP: &str = "INSERT INTO stats_swaps (maker_coin, taker_coin, uuid, started_at, finished_at, \
+        maker_amount, taker_amount, is_success, maker_pubkey, taker_pubkey, is_refunded, at_fault_pubkey) \
+        VALUES ('RICK', 'MORTY', ?1, 100, ?2, 1, 1, ?3, ?4, ?5, ?6, ?7);";
+    const INSERT_MY_SWAP: &str =
+        "INSERT INTO my_swaps (my_coin, other_coin, uuid, started_at) VALUES ('RICK', 'MORTY', ?1, 100);";
+
+    conn.execute(CREATE_STATS_SWAPS_TABLE, []).unwrap();
+    for sql in ADD_MAKER_TAKER_PUBKEYS.iter().chain(ADD_SWAP_OUTCOME) {
+        conn.execute(sql, []).unwrap();
+    }
+    conn.execute(crate::CREATE_MY_SWAPS_TABLE!(), []).unwrap();
+
+    for (i, (finished_at, is_success, maker, taker, is_refunded, at_fault, is_my_swap)) in swaps.iter().enumerate() {
+        let uuid = format!("swap-{}", i);
+        conn.execute(INSERT_SWAP, db_common::sqlite::rusqlite::params![
+            uuid,
+            *finished_at as i64,
+            *is_success as i64,
+            maker,
+            taker,
+            *is_refunded as i64,
+            at_fault,
+        ])
+        .unwrap();
+        if *is_my_swap {
+            conn.execute(INSERT_MY_SWAP, db_common::sqlite::rusqlite::params![uuid])
+                .unwrap();
+        }
+    }
+}
+
 #[test]
 fn test_split_coin() {
     let input = "";
@@ -345,3 +495,40 @@ fn test_split_coin() {
     let actual = split_coin(input);
     assert_eq!(expected, actual);
 }
+
+#[test]
+fn test_select_pubkey_swap_outcomes() {
+    let pubkey = "a".repeat(64);
+    let me = format!("03{}", pubkey);
+    let me = me.as_str();
+    let other = format!("02{}", "b".repeat(64));
+    let other = other.as_str();
+    let swaps: &[TestSwapOutcome<'_>] = &[
+        (200, true, me, other, false, None, true),
+        (300, true, other, me, false, None, true),
+        // The failure with unknown cause isn't counted against any party.
+        (400, false, me, other, false, None, true),
+        (500, false, other, me, true, Some(me), true),
+        (600, false, other, me, false, Some(me), true),
+        // The failure caused by the counterparty isn't counted against the pubkey.
+        (700, false, me, other, true, Some(other), true),
+        (800, true, other, other, false, None, true),
+        // The swaps broadcasted by the other nodes are ignored.
+        (900, true, me, other, false, None, false),
+        (1000, false, me, other, true, Some(me), false),
+    ];
+    let conn = Connection::open_in_memory().unwrap();
+    insert_swap_outcomes_for_tests(&conn, swaps);
+
+    let expected = PubkeySwapOutcomes {
+        completed: 2,
+        aborted: 1,
+        refunded: 1,
+        avg_swap_duration: Some(150),
+    };
+    assert_eq!(select_pubkey_swap_outcomes(&conn, &pubkey).unwrap(), expected);
+    assert_eq!(
+        select_pubkey_swap_outcomes(&conn, &"c".repeat(64)).unwrap(),
+        PubkeySwapOutcomes::default()
+    );
+}
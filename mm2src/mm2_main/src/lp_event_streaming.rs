@@ -0,0 +1,212 @@
+//! The helpers shared by the streamers of the events produced by MM2 itself, like the swap and order status changes.
+//!
+//! Unlike the polled events, these are pushed by the producer to a channel stored in its context,
+//! so `stream_interval_seconds` has no effect on them.
+//! The channel is bounded by [`STREAM_CHANNEL_CAPACITY`], and the messages are dropped if the streamer falls behind,
+//! so the producers never wait for the clients.
+
+use common::executor::SpawnFuture;
+use common::log::{info, warn};
+use futures::channel::mpsc::{channel, Sender};
+use futures::channel::oneshot::{self, Receiver, Sender};
+use futures::StreamExt;
+use mm2_core::mm_ctx::MmArc;
+use mm2_event_stream::{behaviour::{EventBehaviour, EventInitStatus},
+                       Event, EventStreamConfiguration};
+
+/// The max number of the messages waiting to be converted to the events by a streamer.
+pub(crate) const STREAM_CHANNEL_CAPACITY: usize = 1024;
+
+/// Sends the message to the streamer of the `event_name` events without waiting.
+/// Returns `false` if the message is dropped since the streamer is stopped or falls behind.
+pub(crate) fn try_send_stream_message<Msg>(tx: &mut Sender<Msg>, event_name: &str, msg: Msg) -> bool {
+    match tx.try_send(msg) {
+        Ok(()) => true,
+        Err(e) => {
+            // The receiver is dropped only if the streamer is stopped.
+            if e.is_full() {
+                warn!("{} streamer falls behind, the message is dropped", event_name);
+            }
+            false
+        },
+    }
+}
+
+/// Spawns [`EventBehaviour::handle`] of the streamer if its event is active and waits for the initialization status.
+pub(crate) async fn spawn_streamer_if_active<Streamer>(
+    ctx: &MmArc,
+    streamer: Streamer,
+    config: &EventStreamConfiguration,
+) -> EventInitStatus
+where
+    Streamer: EventBehaviour + Send + 'static,
+{
+    let event = match config.get_event(Streamer::EVENT_NAME) {
+        Some(event) => event,
+        None => return EventInitStatus::Inactive,
+    };
+
+    info!(
+        "{} event is activated. `stream_interval_seconds`({}) has no effect on this.",
+        Streamer::EVENT_NAME,
+        event.stream_interval_seconds
+    );
+
+    let (tx, rx): (Sender<EventInitStatus>, Receiver<EventInitStatus>) = oneshot::channel();
+    ctx.spawner().spawn(streamer.handle(event.stream_interval_seconds, tx));
+
+    rx.await
+        .unwrap_or_else(|e| EventInitStatus::Failed(format!("Event initialization status must be received: {}", e)))
+}
+
+/// Registers the sender of the messages with `set_sender` and broadcasts the events every received message is
+/// converted to by `to_events`. Runs until all the senders are dropped.
+///
+/// If `set_sender` fails, the error is reported as the initialization status.
+pub(crate) async fn stream_channel_events<Msg, SetSender, ToEvents>(
+    ctx: &MmArc,
+    tx: oneshot::Sender<EventInitStatus>,
+    set_sender: SetSender,
+    mut to_events: ToEvents,
+) where
+    SetSender: FnOnce(Sender<Msg>) -> Result<(), String>,
+    ToEvents: FnMut(Msg) -> Vec<Event>,
+{
+    let (msg_tx, mut msg_rx) = channel(STREAM_CHANNEL_CAPACITY);
+    if let Err(e) = set_sender(msg_tx) {
+        tx.send(EventInitStatus::Failed(e)).ok();
+        return;
+    }
+
+    tx.send(EventInitStatus::Success).ok();
+
+    while let Some(msg) = msg_rx.next().await {
+        for event in to_events(msg) {
+            ctx.stream_channel_controller.broadcast(event).await;
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use common::block_on;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use parking_lot::Mutex as PaMutex;
+    use std::sync::Arc;
+
+    type TestSender = Arc<PaMutex<Option<Sender<u32>>>>;
+
+    struct TestStreamer {
+        ctx: MmArc,
+        sender: TestSender,
+        fail_with: Option<String>,
+    }
+
+    #[async_trait]
+    impl EventBehaviour for TestStreamer {
+        const EVENT_NAME: &'static str = "TEST";
+
+        async fn handle(self, _interval: f64, tx: oneshot::Sender<EventInitStatus>) {
+            let (sender, fail_with) = (self.sender, self.fail_with);
+            let set_sender = move |msg_tx: Sender<u32>| match fail_with {
+                Some(e) => Err(e),
+                None => {
+                    *sender.lock() = Some(msg_tx);
+                    Ok(())
+                },
+            };
+            let to_events = |num: u32| -> Vec<Event> {
+                (0..num)
+                    .map(|i| Event::new(Self::EVENT_NAME.to_string(), i.to_string()))
+                    .collect()
+            };
+            stream_channel_events(&self.ctx, tx, set_sender, to_events).await
+        }
+
+        async fn spawn_if_active(self, config: &EventStreamConfiguration) -> EventInitStatus {
+            let ctx = self.ctx.clone();
+            spawn_streamer_if_active(&ctx, self, config).await
+        }
+    }
+
+    fn config_with_events(events: &[&str]) -> EventStreamConfiguration {
+        let active_events: serde_json::Map<_, _> = events.iter().map(|e| (e.to_string(), json!({}))).collect();
+        serde_json::from_value(json!({ "active_events": active_events })).unwrap()
+    }
+
+    fn test_streamer(ctx: &MmArc, fail_with: Option<&str>) -> (TestStreamer, TestSender) {
+        let sender = TestSender::default();
+        let streamer = TestStreamer {
+            ctx: ctx.clone(),
+            sender: sender.clone(),
+            fail_with: fail_with.map(String::from),
+        };
+        (streamer, sender)
+    }
+
+    #[test]
+    fn test_inactive_streamer_is_not_spawned() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let (streamer, sender) = test_streamer(&ctx, None);
+
+        let status = block_on(streamer.spawn_if_active(&config_with_events(&["OTHER"])));
+        assert!(matches!(status, EventInitStatus::Inactive));
+        assert!(sender.lock().is_none());
+    }
+
+    #[test]
+    fn test_streamer_init_failure() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let (streamer, sender) = test_streamer(&ctx, Some("no context"));
+
+        let status = block_on(streamer.spawn_if_active(&config_with_events(&["TEST"])));
+        match status {
+            EventInitStatus::Failed(e) => assert_eq!(e, "no context"),
+            status => panic!("Expected Failed, found {:?}", status),
+        }
+        assert!(sender.lock().is_none());
+    }
+
+    #[test]
+    fn test_streamer_broadcasts_converted_messages() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let mut rx = ctx.stream_channel_controller.clone().create_channel(4);
+        let (streamer, sender) = test_streamer(&ctx, None);
+
+        let status = block_on(streamer.spawn_if_active(&config_with_events(&["TEST"])));
+        assert!(matches!(status, EventInitStatus::Success));
+
+        let mut msg_tx = sender.lock().clone().expect("The sender must be set on init");
+        // No events are produced for zero, so the next received events must belong to the second message.
+        assert!(try_send_stream_message(&mut msg_tx, "TEST", 0));
+        assert!(try_send_stream_message(&mut msg_tx, "TEST", 2));
+
+        let messages: Vec<_> = block_on(async {
+            let mut messages = Vec::new();
+            for _ in 0..2 {
+                let event = rx.recv().await.unwrap();
+                assert_eq!(event.event_type(), "TEST");
+                messages.push(event.message().to_owned());
+            }
+            messages
+        });
+        assert_eq!(messages, ["0", "1"]);
+    }
+
+    #[test]
+    fn test_try_send_stream_message_drops_when_full() {
+        // The capacity of the channel is the buffer size plus one slot per sender.
+        let (mut tx, mut rx) = channel(0);
+        assert!(try_send_stream_message(&mut tx, "TEST", 1));
+        assert!(!try_send_stream_message(&mut tx, "TEST", 2));
+
+        assert_eq!(rx.try_next().unwrap(), Some(1));
+        assert!(try_send_stream_message(&mut tx, "TEST", 3));
+        assert_eq!(rx.try_next().unwrap(), Some(3));
+
+        drop(rx);
+        assert!(!try_send_stream_message(&mut tx, "TEST", 4));
+    }
+}
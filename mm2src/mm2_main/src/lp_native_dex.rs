@@ -26,6 +26,8 @@ use crypto::{from_hw_error, CryptoCtx, CryptoInitError, HwError, HwProcessingErr
 use derive_more::Display;
 use enum_from::EnumFromTrait;
 use mm2_core::mm_ctx::{MmArc, MmCtx};
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::RpcMethodRegistry;
 use mm2_err_handle::common_errors::InternalError;
 use mm2_err_handle::prelude::*;
 use mm2_event_stream::behaviour::{EventBehaviour, EventInitStatus};
@@ -47,10 +49,11 @@ use std::time::Duration;
 use crate::mm2::database::init_and_migrate_db;
 use crate::mm2::lp_message_service::{init_message_service, InitMessageServiceError};
 use crate::mm2::lp_network::{lp_network_ports, p2p_event_process_loop, NetIdError};
-use crate::mm2::lp_ordermatch::{broadcast_maker_orders_keep_alive_loop, clean_memory_loop, init_ordermatch_context,
-                                lp_ordermatch_loop, orders_kick_start, BalanceUpdateOrdermatchHandler,
+use crate::mm2::lp_ordermatch::{broadcast_maker_orders_keep_alive_loop, clean_memory_loop, conditional_orders_loop,
+                                init_ordermatch_context, lp_ordermatch_loop, orders_kick_start,
+                                BalanceUpdateOrdermatchHandler, OrderStatusStreamer, OrderbookStreamer,
                                 OrdermatchInitError};
-use crate::mm2::lp_swap::{running_swaps_num, swap_kick_starts};
+use crate::mm2::lp_swap::{running_swaps_num, swap_kick_starts, SwapStatusStreamer};
 use crate::mm2::rpc::spawn_rpc;
 
 cfg_native! {
@@ -205,6 +208,12 @@ pub enum MmInitError {
     InvalidPassphrase(String),
     #[display(fmt = "NETWORK event initialization failed: {}", _0)]
     NetworkEventInitFailed(String),
+    #[display(fmt = "SWAP_STATUS event initialization failed: {}", _0)]
+    SwapStatusEventInitFailed(String),
+    #[display(fmt = "ORDER_STATUS event initialization failed: {}", _0)]
+    OrderStatusEventInitFailed(String),
+    #[display(fmt = "ORDERBOOK event initialization failed: {}", _0)]
+    OrderbookEventInitFailed(String),
     #[from_trait(WithHwRpcError::hw_rpc_error)]
     #[display(fmt = "{}", _0)]
     HwError(HwRpcError),
@@ -380,6 +389,9 @@ pub fn fix_directories(ctx: &MmCtx) -> MmInitResult<()> {
     if !ensure_dir_is_writable(&dbdir.join("ORDERS").join("MY").join("HISTORY")) {
         return MmError::err(MmInitError::db_directory_is_not_writable("ORDERS/MY/HISTORY"));
     }
+    if !ensure_dir_is_writable(&dbdir.join("ORDERS").join("MY").join("CONDITIONAL")) {
+        return MmError::err(MmInitError::db_directory_is_not_writable("ORDERS/MY/CONDITIONAL"));
+    }
     if !ensure_dir_is_writable(&dbdir.join("TX_CACHE")) {
         return MmError::err(MmInitError::db_directory_is_not_writable("TX_CACHE"));
     }
@@ -434,6 +446,18 @@ async fn init_event_streaming(ctx: &MmArc) -> MmInitResult<()> {
         if let EventInitStatus::Failed(err) = NetworkEvent::new(ctx.clone()).spawn_if_active(config).await {
             return MmError::err(MmInitError::NetworkEventInitFailed(err));
         }
+
+        if let EventInitStatus::Failed(err) = SwapStatusStreamer::new(ctx.clone()).spawn_if_active(config).await {
+            return MmError::err(MmInitError::SwapStatusEventInitFailed(err));
+        }
+
+        if let EventInitStatus::Failed(err) = OrderStatusStreamer::new(ctx.clone()).spawn_if_active(config).await {
+            return MmError::err(MmInitError::OrderStatusEventInitFailed(err));
+        }
+
+        if let EventInitStatus::Failed(err) = OrderbookStreamer::new(ctx.clone()).spawn_if_active(config).await {
+            return MmError::err(MmInitError::OrderbookEventInitFailed(err));
+        }
     }
 
     Ok(())
@@ -477,14 +501,17 @@ pub async fn lp_init_continue(ctx: MmArc) -> MmInitResult<()> {
 
     ctx.initialized.pin(true).map_to_mm(MmInitError::Internal)?;
 
+    // init the event streaming before kick-start, so the status events of the restored swaps and orders are streamed too
+    init_event_streaming(&ctx).await?;
+
     // launch kickstart threads before RPC is available, this will prevent the API user to place
     // an order and start new swap that might get started 2 times because of kick-start
     kick_start(ctx.clone()).await?;
 
-    init_event_streaming(&ctx).await?;
-
     ctx.spawner().spawn(lp_ordermatch_loop(ctx.clone()));
 
+    ctx.spawner().spawn(conditional_orders_loop(ctx.clone()));
+
     ctx.spawner().spawn(broadcast_maker_orders_keep_alive_loop(ctx.clone()));
 
     #[cfg(target_arch = "wasm32")]
@@ -758,3 +785,45 @@ fn wss_certs(ctx: &MmArc) -> P2PResult<Option<WssCerts>> {
     )?;
     Ok(Some(WssCerts { server_priv_key, certs }))
 }
+
+/// Registers the `task::init_trezor::*` and `task::connect_metamask::*` mmrpc 2.0 methods.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "task::init_trezor::cancel",
+        "Cancels the Trezor initialization task.",
+        mmrpc_handler!(init_hw::cancel_init_trezor),
+    );
+    registry.register(
+        "task::init_trezor::init",
+        "Starts the task initializing the Trezor device.",
+        mmrpc_handler!(init_hw::init_trezor),
+    );
+    registry.register(
+        "task::init_trezor::status",
+        "Returns the status of the Trezor initialization task.",
+        mmrpc_handler!(init_hw::init_trezor_status),
+    );
+    registry.register(
+        "task::init_trezor::user_action",
+        "Passes the user action to the Trezor initialization task.",
+        mmrpc_handler!(init_hw::init_trezor_user_action),
+    );
+    #[cfg(target_arch = "wasm32")]
+    {
+        registry.register(
+            "task::connect_metamask::cancel",
+            "Cancels the MetaMask connection task.",
+            mmrpc_handler!(init_metamask::cancel_connect_metamask),
+        );
+        registry.register(
+            "task::connect_metamask::init",
+            "Starts the task connecting to MetaMask.",
+            mmrpc_handler!(init_metamask::connect_metamask),
+        );
+        registry.register(
+            "task::connect_metamask::status",
+            "Returns the status of the MetaMask connection task.",
+            mmrpc_handler!(init_metamask::connect_metamask_status),
+        );
+    }
+}
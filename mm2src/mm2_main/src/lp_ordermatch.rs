@@ -27,6 +27,7 @@ use blake2::Blake2bVar;
 use coins::utxo::{compressed_pub_key_from_priv_raw, ChecksumType, UtxoAddressFormat};
 use coins::{coin_conf, find_pair, lp_coinfind, BalanceTradeFeeUpdatedHandler, CoinProtocol, CoinsContext,
             FeeApproxStage, MarketCoinOps, MmCoinEnum};
+use coins::{CoinAssocTypes, MmCoin, SwapOpsV2};
 use common::executor::{simple_map::AbortableSimpleMap, AbortSettings, AbortableSystem, AbortedError, SpawnAbortable,
                        SpawnFuture, Timer};
 use common::log::{error, warn, LogOnError};
@@ -41,6 +42,8 @@ use hash_db::Hasher;
 use http::Response;
 use keys::{AddressFormat, KeyPair};
 use mm2_core::mm_ctx::{from_ctx, MmArc, MmWeak};
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::{AuthLevel, RpcMethodRegistry};
 use mm2_err_handle::prelude::*;
 use mm2_libp2p::{decode_signed, encode_and_sign, encode_message, pub_sub_topic, TopicHash, TopicPrefix,
                  TOPIC_SEPARATOR};
@@ -55,6 +58,7 @@ use my_orders_storage::{delete_my_maker_order, delete_my_taker_order, save_maker
                         MyOrdersHistory, MyOrdersStorage};
 use num_traits::identities::Zero;
 use parking_lot::Mutex as PaMutex;
+use primitives::hash::H256;
 use rpc::v1::types::H256 as H256Json;
 use serde_json::{self as json, Value as Json};
 use sp_trie::{delta_trie_root, MemoryDB, Trie, TrieConfiguration, TrieDB, TrieDBMut, TrieHash, TrieMut};
@@ -71,19 +75,16 @@ use uuid::Uuid;
 
 use crate::mm2::lp_network::{broadcast_p2p_msg, request_any_relay, request_one_peer, subscribe_to_topic, P2PRequest,
                              P2PRequestError};
-#[cfg(not(target_arch = "wasm32"))]
-use crate::mm2::lp_swap::detect_secret_hash_algo;
-#[cfg(not(target_arch = "wasm32"))]
-use crate::mm2::lp_swap::maker_swap_v2::{self, DummyMakerSwapStorage, MakerSwapStateMachine};
-#[cfg(not(target_arch = "wasm32"))]
-use crate::mm2::lp_swap::taker_swap_v2::{self, DummyTakerSwapStorage, TakerSwapStateMachine};
+use crate::mm2::lp_swap::maker_swap_v2::{self, MakerSwapStateMachine, MakerSwapStorage};
+use crate::mm2::lp_swap::taker_swap_v2::{self, TakerSwapStateMachine, TakerSwapStorage};
 use crate::mm2::lp_swap::{calc_max_maker_vol, check_balance_for_maker_swap, check_balance_for_taker_swap,
                           check_other_coin_balance_for_swap, dex_fee_amount_from_taker_coin, generate_secret,
-                          get_max_maker_vol, insert_new_swap_to_db, is_pubkey_banned, lp_atomic_locktime,
-                          p2p_keypair_and_peer_id_to_broadcast, p2p_private_and_peer_id_to_broadcast, run_maker_swap,
-                          run_taker_swap, swap_v2_topic, AtomicLocktimeVersion, CheckBalanceError, CheckBalanceResult,
-                          CoinVolumeInfo, MakerSwap, RunMakerSwapInput, RunTakerSwapInput, SwapConfirmationsSettings,
-                          TakerSwap};
+                          get_max_maker_vol, has_min_reputation, insert_new_swap_to_db, is_pubkey_banned,
+                          lp_atomic_locktime, p2p_keypair_and_peer_id_to_broadcast,
+                          p2p_private_and_peer_id_to_broadcast, run_maker_swap, run_taker_swap, swap_v2_topic,
+                          AtomicLocktimeVersion, CheckBalanceError, CheckBalanceResult, CoinVolumeInfo, MakerSwap,
+                          RunMakerSwapInput, RunTakerSwapInput, SwapConfirmationsSettings, TakerSwap};
+use crate::mm2::lp_swap::{detect_secret_hash_algo, is_swap_v2_pair_supported, spawn_swap_v2_machine, SecretHashAlgo};
 
 #[cfg(any(test, feature = "run-docker-tests"))]
 use crate::mm2::lp_swap::taker_swap::FailAt;
@@ -99,17 +100,31 @@ cfg_wasm32! {
     pub type OrdermatchDbLocked<'a> = DbLocked<'a, OrdermatchDb>;
 }
 
+#[path = "lp_ordermatch/batch_orders.rs"] mod batch_orders;
+pub use batch_orders::{batch_cancel, batch_setprice, replace_orders};
 #[path = "lp_ordermatch/best_orders.rs"] mod best_orders;
+#[path = "lp_ordermatch/conditional_orders.rs"]
+mod conditional_orders;
+pub use conditional_orders::{cancel_conditional_order, conditional_orders_loop, create_conditional_order,
+                             my_conditional_orders};
+use conditional_orders::{conditional_orders_kick_start, ConditionalOrder};
 #[path = "lp_ordermatch/lp_bot.rs"] mod lp_bot;
-pub use lp_bot::{start_simple_market_maker_bot, stop_simple_market_maker_bot, StartSimpleMakerBotRequest,
-                 TradingBotEvent, KMD_PRICE_ENDPOINT};
+pub use lp_bot::{get_simple_market_maker_bot_status, start_simple_market_maker_bot, stop_simple_market_maker_bot,
+                 StartSimpleMakerBotRequest, TradingBotEvent, KMD_PRICE_ENDPOINT};
 
 #[path = "lp_ordermatch/my_orders_storage.rs"]
 mod my_orders_storage;
 #[path = "lp_ordermatch/new_protocol.rs"] mod new_protocol;
+#[path = "lp_ordermatch/order_events.rs"] mod order_events;
+pub use order_events::OrderStatusStreamer;
+use order_events::{broadcast_order_status_event, OrderStatusEvent, OrderStatusEventSender};
 #[path = "lp_ordermatch/order_requests_tracker.rs"]
 mod order_requests_tracker;
 #[path = "lp_ordermatch/orderbook_depth.rs"] mod orderbook_depth;
+#[path = "lp_ordermatch/orderbook_events.rs"]
+mod orderbook_events;
+use orderbook_events::OrderbookStreamState;
+pub use orderbook_events::{disable_orderbook_stream, enable_orderbook_stream, OrderbookStreamer};
 #[path = "lp_ordermatch/orderbook_rpc.rs"] mod orderbook_rpc;
 #[cfg(all(test, not(target_arch = "wasm32")))]
 #[path = "ordermatch_tests.rs"]
@@ -127,6 +142,8 @@ pub const MIN_ORDER_KEEP_ALIVE_INTERVAL: u64 = 5;
 const BALANCE_REQUEST_INTERVAL: f64 = 30.;
 const MAKER_ORDER_TIMEOUT: u64 = MIN_ORDER_KEEP_ALIVE_INTERVAL * 3;
 const TAKER_ORDER_TIMEOUT: u64 = 30;
+/// The max time an [`OrderType::ImmediateOrCancel`] taker order waits for maker reservations.
+const IMMEDIATE_OR_CANCEL_TIMEOUT: u64 = 5;
 const ORDER_MATCH_TIMEOUT: u64 = 30;
 const ORDERBOOK_REQUESTING_TIMEOUT: u64 = MIN_ORDER_KEEP_ALIVE_INTERVAL * 2;
 const MAX_ORDERS_NUMBER_IN_ORDERBOOK_RESPONSE: usize = 1000;
@@ -458,10 +475,11 @@ fn insert_or_update_order(ctx: &MmArc, item: OrderbookItem) {
 fn insert_or_update_my_order(ctx: &MmArc, item: OrderbookItem, my_order: &MakerOrder) {
     let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).expect("from_ctx failed");
     let mut orderbook = ordermatch_ctx.orderbook.lock();
-    orderbook.insert_or_update_order_update_trie(item);
+    // The key must be known before the order is inserted, so that the order is streamed as mine.
     if let Some(key) = my_order.p2p_privkey {
         orderbook.my_p2p_pubkeys.insert(hex::encode(key.public_slice()));
     }
+    orderbook.insert_or_update_order_update_trie(item);
 }
 
 fn delete_order(ctx: &MmArc, pubkey: &str, uuid: Uuid) {
@@ -1036,7 +1054,7 @@ fn maker_order_created_p2p_notify(
         base: order.base_orderbook_ticker().to_owned(),
         rel: order.rel_orderbook_ticker().to_owned(),
         price: order.price.to_ratio(),
-        max_volume: order.available_amount().to_ratio(),
+        max_volume: order.visible_amount().to_ratio(),
         min_volume: order.min_base_vol.to_ratio(),
         conf_settings: order.conf_settings.clone().unwrap(),
         created_at: now_sec(),
@@ -1153,7 +1171,7 @@ impl BalanceTradeFeeUpdatedHandler for BalanceUpdateOrdermatchHandler {
             if new_volume < order.available_amount() {
                 order.max_base_vol = &order.reserved_amount() + &new_volume;
                 let mut update_msg = new_protocol::MakerOrderUpdated::new(order.uuid);
-                update_msg.with_new_max_volume(order.available_amount().into());
+                update_msg.with_new_max_volume(order.visible_amount().into());
                 maker_order_updated_p2p_notify(ctx.clone(), order.orderbook_topic(), update_msg, order.p2p_keypair());
             }
         }
@@ -1269,6 +1287,7 @@ pub struct TakerOrderBuilder<'a> {
     min_volume: Option<MmNumber>,
     timeout: u64,
     save_in_history: bool,
+    min_maker_reputation: Option<MmNumber>,
 }
 
 pub enum TakerOrderBuildError {
@@ -1295,6 +1314,12 @@ pub enum TakerOrderBuildError {
     },
     SenderPubkeyIsZero,
     ConfsSettingsNotSet,
+    ExpiresAtInPast {
+        expires_at: u64,
+        now: u64,
+    },
+    /// The min maker reputation requires the swaps stats that are only available in native mode
+    MinReputationNotSupported,
 }
 
 impl fmt::Display for TakerOrderBuildError {
@@ -1327,6 +1352,14 @@ impl fmt::Display for TakerOrderBuildError {
             ),
             TakerOrderBuildError::SenderPubkeyIsZero => write!(f, "Sender pubkey can not be zero"),
             TakerOrderBuildError::ConfsSettingsNotSet => write!(f, "Confirmation settings must be set"),
+            TakerOrderBuildError::ExpiresAtInPast { expires_at, now } => write!(
+                f,
+                "Expiration time {} must be later than the current time {}",
+                expires_at, now
+            ),
+            TakerOrderBuildError::MinReputationNotSupported => {
+                write!(f, "Min maker reputation is only supported in native mode")
+            },
         }
     }
 }
@@ -1348,6 +1381,7 @@ impl<'a> TakerOrderBuilder<'a> {
             order_type: OrderType::GoodTillCancelled,
             timeout: TAKER_ORDER_TIMEOUT,
             save_in_history: true,
+            min_maker_reputation: None,
         }
     }
 
@@ -1376,6 +1410,11 @@ impl<'a> TakerOrderBuilder<'a> {
         self
     }
 
+    pub fn with_min_maker_reputation(mut self, min_maker_reputation: Option<MmNumber>) -> Self {
+        self.min_maker_reputation = min_maker_reputation;
+        self
+    }
+
     fn with_order_type(mut self, order_type: OrderType) -> Self {
         self.order_type = order_type;
         self
@@ -1443,6 +1482,18 @@ impl<'a> TakerOrderBuilder<'a> {
             return Err(TakerOrderBuildError::ConfsSettingsNotSet);
         }
 
+        if let Some(expires_at) = self.order_type.expires_at() {
+            let now = now_sec();
+            if expires_at <= now {
+                return Err(TakerOrderBuildError::ExpiresAtInPast { expires_at, now });
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        if self.min_maker_reputation.is_some() {
+            return Err(TakerOrderBuildError::MinReputationNotSupported);
+        }
+
         let price = &self.rel_amount / &self.base_amount;
         let base_min_by_rel = &min_rel_amount / &price;
         let base_min_vol_threshold = min_base_amount.max(base_min_by_rel);
@@ -1502,12 +1553,16 @@ impl<'a> TakerOrderBuilder<'a> {
             },
             matches: Default::default(),
             min_volume,
+            timeout: match self.order_type {
+                OrderType::ImmediateOrCancel => self.timeout.min(IMMEDIATE_OR_CANCEL_TIMEOUT),
+                _ => self.timeout,
+            },
             order_type: self.order_type,
-            timeout: self.timeout,
             save_in_history: self.save_in_history,
             base_orderbook_ticker: self.base_orderbook_ticker,
             rel_orderbook_ticker: self.rel_orderbook_ticker,
             p2p_privkey,
+            min_maker_reputation: self.min_maker_reputation,
         })
     }
 
@@ -1548,6 +1603,7 @@ impl<'a> TakerOrderBuilder<'a> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            min_maker_reputation: self.min_maker_reputation,
         }
     }
 }
@@ -1569,6 +1625,10 @@ pub struct TakerOrder {
     /// A custom priv key for more privacy to prevent linking orders of the same node between each other
     /// Commonly used with privacy coins (ARRR, ZCash, etc.)
     p2p_privkey: Option<SerializableSecp256k1Keypair>,
+    /// Only the makers with at least this reputation score are matched.
+    /// Unlike `request.match_by`, it isn't sent to the makers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_maker_reputation: Option<MmNumber>,
 }
 
 /// Result of match_reserved function
@@ -1700,6 +1760,16 @@ pub struct MakerOrder {
     /// A custom priv key for more privacy to prevent linking orders of the same node between each other
     /// Commonly used with privacy coins (ARRR, ZCash, etc.)
     p2p_privkey: Option<SerializableSecp256k1Keypair>,
+    /// The order is cancelled automatically after this timestamp (in seconds) if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+    /// Makes the order an iceberg: only this slice of the available volume is advertised to the orderbook.
+    /// The slice is replenished from the hidden remainder after each started swap.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    visible_volume: Option<MmNumber>,
+    /// Only the takers with at least this reputation score are matched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_taker_reputation: Option<MmNumber>,
 }
 
 pub struct MakerOrderBuilder<'a> {
@@ -1712,6 +1782,9 @@ pub struct MakerOrderBuilder<'a> {
     rel_orderbook_ticker: Option<String>,
     conf_settings: Option<OrderConfirmationsSettings>,
     save_in_history: bool,
+    expires_at: Option<u64>,
+    visible_volume: Option<MmNumber>,
+    min_taker_reputation: Option<MmNumber>,
 }
 
 pub enum MakerOrderBuildError {
@@ -1741,6 +1814,17 @@ pub enum MakerOrderBuildError {
         min: MmNumber,
         max: MmNumber,
     },
+    ExpiresAtInPast {
+        expires_at: u64,
+        now: u64,
+    },
+    /// Visible volume of the iceberg order is below the min base vol
+    VisibleVolumeTooLow {
+        actual: MmNumber,
+        threshold: MmNumber,
+    },
+    /// The min taker reputation requires the swaps stats that are only available in native mode
+    MinReputationNotSupported,
 }
 
 impl fmt::Display for MakerOrderBuildError {
@@ -1778,6 +1862,20 @@ impl fmt::Display for MakerOrderBuildError {
                 max.to_decimal(),
                 min.to_decimal()
             ),
+            MakerOrderBuildError::ExpiresAtInPast { expires_at, now } => write!(
+                f,
+                "Expiration time {} must be later than the current time {}",
+                expires_at, now
+            ),
+            MakerOrderBuildError::VisibleVolumeTooLow { actual, threshold } => write!(
+                f,
+                "Visible volume {} is too low, required: {}",
+                actual.to_decimal(),
+                threshold.to_decimal()
+            ),
+            MakerOrderBuildError::MinReputationNotSupported => {
+                write!(f, "Min taker reputation is only supported in native mode")
+            },
         }
     }
 }
@@ -1861,6 +1959,9 @@ impl<'a> MakerOrderBuilder<'a> {
             price: 0.into(),
             conf_settings: None,
             save_in_history: true,
+            expires_at: None,
+            visible_volume: None,
+            min_taker_reputation: None,
         }
     }
 
@@ -1899,6 +2000,21 @@ impl<'a> MakerOrderBuilder<'a> {
         self
     }
 
+    pub fn with_expires_at(mut self, expires_at: Option<u64>) -> Self {
+        self.expires_at = expires_at;
+        self
+    }
+
+    pub fn with_visible_volume(mut self, visible_volume: Option<MmNumber>) -> Self {
+        self.visible_volume = visible_volume;
+        self
+    }
+
+    pub fn with_min_taker_reputation(mut self, min_taker_reputation: Option<MmNumber>) -> Self {
+        self.min_taker_reputation = min_taker_reputation;
+        self
+    }
+
     /// Build MakerOrder
     #[allow(clippy::result_large_err)]
     pub fn build(self) -> Result<MakerOrder, MakerOrderBuildError> {
@@ -1930,6 +2046,27 @@ impl<'a> MakerOrderBuilder<'a> {
             self.price.clone(),
         )?;
 
+        if let Some(expires_at) = self.expires_at {
+            let now = now_sec();
+            if expires_at <= now {
+                return Err(MakerOrderBuildError::ExpiresAtInPast { expires_at, now });
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        if self.min_taker_reputation.is_some() {
+            return Err(MakerOrderBuildError::MinReputationNotSupported);
+        }
+
+        if let Some(visible_volume) = &self.visible_volume {
+            if visible_volume < &actual_min_base_vol {
+                return Err(MakerOrderBuildError::VisibleVolumeTooLow {
+                    actual: visible_volume.clone(),
+                    threshold: actual_min_base_vol,
+                });
+            }
+        }
+
         let created_at = now_ms();
 
         let p2p_privkey = if self.base_coin.is_privacy() {
@@ -1955,6 +2092,9 @@ impl<'a> MakerOrderBuilder<'a> {
             base_orderbook_ticker: self.base_orderbook_ticker,
             rel_orderbook_ticker: self.rel_orderbook_ticker,
             p2p_privkey,
+            expires_at: self.expires_at,
+            visible_volume: self.visible_volume,
+            min_taker_reputation: self.min_taker_reputation,
         })
     }
 
@@ -1979,6 +2119,9 @@ impl<'a> MakerOrderBuilder<'a> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            expires_at: self.expires_at,
+            visible_volume: self.visible_volume,
+            min_taker_reputation: self.min_taker_reputation,
         }
     }
 }
@@ -1989,6 +2132,21 @@ fn zero_rat() -> BigRational { BigRational::zero() }
 impl MakerOrder {
     fn available_amount(&self) -> MmNumber { &self.max_base_vol - &self.reserved_amount() }
 
+    fn is_expired(&self, now_sec: u64) -> bool { self.expires_at.map_or(false, |expires_at| expires_at <= now_sec) }
+
+    /// The volume advertised to the orderbook and available for matching.
+    /// Equals to [`MakerOrder::available_amount`] if the order is not an iceberg.
+    fn visible_amount(&self) -> MmNumber {
+        let available = self.available_amount();
+        match &self.visible_volume {
+            Some(visible_volume) if visible_volume < &available => visible_volume.clone(),
+            _ => available,
+        }
+    }
+
+    /// The part of the available volume not advertised to the orderbook.
+    fn hidden_amount(&self) -> MmNumber { self.available_amount() - self.visible_amount() }
+
     fn reserved_amount(&self) -> MmNumber {
         self.matches.iter().fold(
             MmNumber::from(BigRational::from_integer(0.into())),
@@ -2024,7 +2182,7 @@ impl MakerOrder {
                     && (self.rel == taker.rel || self.rel_orderbook_ticker.as_ref() == Some(&taker.rel));
                 let taker_price = taker_rel_amount / taker_base_amount;
                 if ticker_match
-                    && taker_base_amount <= &self.available_amount()
+                    && taker_base_amount <= &self.visible_amount()
                     && taker_base_amount >= &self.min_base_vol
                     && taker_price >= self.price
                 {
@@ -2043,7 +2201,7 @@ impl MakerOrder {
                 let matched_rel_amount = taker_base_amount.clone();
 
                 if ticker_match
-                    && matched_base_amount <= self.available_amount()
+                    && matched_base_amount <= self.visible_amount()
                     && matched_base_amount >= self.min_base_vol
                     && taker_price >= self.price
                 {
@@ -2091,6 +2249,7 @@ impl MakerOrder {
 impl From<TakerOrder> for MakerOrder {
     fn from(taker_order: TakerOrder) -> Self {
         let created_at = now_ms();
+        let expires_at = taker_order.order_type.expires_at();
         match taker_order.request.action {
             TakerAction::Sell => MakerOrder {
                 price: (taker_order.request.get_rel_amount() / taker_order.request.get_base_amount()),
@@ -2109,6 +2268,9 @@ impl From<TakerOrder> for MakerOrder {
                 base_orderbook_ticker: taker_order.base_orderbook_ticker,
                 rel_orderbook_ticker: taker_order.rel_orderbook_ticker,
                 p2p_privkey: taker_order.p2p_privkey,
+                expires_at,
+                visible_volume: None,
+                min_taker_reputation: taker_order.min_maker_reputation,
             },
             // The "buy" taker order is recreated with reversed pair as Maker order is always considered as "sell"
             TakerAction::Buy => {
@@ -2131,6 +2293,9 @@ impl From<TakerOrder> for MakerOrder {
                     base_orderbook_ticker: taker_order.rel_orderbook_ticker,
                     rel_orderbook_ticker: taker_order.base_orderbook_ticker,
                     p2p_privkey: taker_order.p2p_privkey,
+                    expires_at,
+                    visible_volume: None,
+                    min_taker_reputation: taker_order.min_maker_reputation,
                 }
             },
         }
@@ -2488,6 +2653,8 @@ struct Orderbook {
     /// MemoryDB instance to store Patricia Tries data
     memory_db: MemoryDB<Blake2Hasher64>,
     my_p2p_pubkeys: HashSet<String>,
+    /// The state of the `ORDERBOOK` event streaming.
+    stream: OrderbookStreamState,
 }
 
 fn hashed_null_node<T: TrieConfiguration>() -> TrieHash<T> { <T::Codec as NodeCodecT>::hashed_null_node() }
@@ -2590,6 +2757,10 @@ impl Orderbook {
             .or_insert_with(HashSet::new)
             .insert(order.uuid);
 
+        let is_new = !self.order_set.contains_key(&order.uuid);
+        let is_my_p2p_pubkey = self.my_p2p_pubkeys.contains(&order.pubkey);
+        self.stream.order_upserted(&order, is_new, is_my_p2p_pubkey);
+
         self.order_set.insert(order.uuid, order);
     }
 
@@ -2598,6 +2769,7 @@ impl Orderbook {
             Some(order) => order,
             None => return None,
         };
+        self.stream.order_removed(&order);
         let base_rel = (order.base.clone(), order.rel.clone());
 
         // create an `order_to_delete` that allows to find and remove an element from `self.ordered` by hash
@@ -2716,6 +2888,10 @@ struct OrdermatchContext {
     /// Pending MakerReserved messages for a specific TakerOrder UUID
     /// Used to select a trade with the best price upon matching
     pending_maker_reserved: AsyncMutex<HashMap<Uuid, Vec<MakerReserved>>>,
+    /// The sender of the `ORDER_STATUS` streamer, set only if the streaming of this event is active.
+    order_status_event_tx: PaMutex<Option<OrderStatusEventSender>>,
+    /// Stop-loss and take-profit orders waiting for their trigger condition.
+    my_conditional_orders: AsyncMutex<HashMap<Uuid, ConditionalOrder>>,
     #[cfg(target_arch = "wasm32")]
     ordermatch_db: ConstructibleDb<OrdermatchDb>,
 }
@@ -2752,6 +2928,8 @@ pub fn init_ordermatch_context(ctx: &MmArc) -> OrdermatchInitResult<()> {
         pending_maker_reserved: Default::default(),
         orderbook_tickers,
         original_tickers,
+        order_status_event_tx: Default::default(),
+        my_conditional_orders: Default::default(),
         #[cfg(target_arch = "wasm32")]
         ordermatch_db: ConstructibleDb::new(ctx),
     };
@@ -2782,6 +2960,8 @@ impl OrdermatchContext {
                 pending_maker_reserved: Default::default(),
                 orderbook_tickers: Default::default(),
                 original_tickers: Default::default(),
+                order_status_event_tx: Default::default(),
+                my_conditional_orders: Default::default(),
                 #[cfg(target_arch = "wasm32")]
                 ordermatch_db: ConstructibleDb::new(ctx),
             })
@@ -2840,6 +3020,16 @@ impl MakerOrdersContext {
     fn get_order(&self, uuid: &Uuid) -> Option<&Arc<AsyncMutex<MakerOrder>>> { self.orders.get(uuid) }
 
     fn remove_order(&mut self, uuid: &Uuid) -> Option<Arc<AsyncMutex<MakerOrder>>> {
+        let ticker = self.order_tickers.get(uuid)?.clone();
+        let order = self.remove_order_keeping_balance_loop(uuid)?;
+        self.stop_balance_loop_if_unused(&ticker);
+        Some(order)
+    }
+
+    /// Removes the order but keeps the balance loop of its base coin running with the last known balance,
+    /// so the order can be put back without losing it.
+    /// [`MakerOrdersContext::stop_balance_loop_if_unused`] should be called once the removal is final.
+    fn remove_order_keeping_balance_loop(&mut self, uuid: &Uuid) -> Option<Arc<AsyncMutex<MakerOrder>>> {
         let order = self.orders.remove(uuid)?;
         let ticker = self.order_tickers.remove(uuid)?;
         if let Some(count) = self.count_by_tickers.get_mut(&ticker) {
@@ -2847,12 +3037,13 @@ impl MakerOrdersContext {
                 *count -= 1;
             }
         }
+        Some(order)
+    }
 
-        if !self.coin_has_active_maker_orders(&ticker) {
-            self.stop_balance_loop(&ticker);
+    fn stop_balance_loop_if_unused(&mut self, ticker: &str) {
+        if !self.coin_has_active_maker_orders(ticker) {
+            self.stop_balance_loop(ticker);
         }
-
-        Some(order)
     }
 
     fn coin_has_active_maker_orders(&self, ticker: &str) -> bool {
@@ -2875,10 +3066,124 @@ impl MakerOrdersContext {
     fn balance_loop_exists(&mut self, ticker: &str) -> bool { self.balance_loops.lock().contains(ticker).unwrap() }
 }
 
+/// Starts the maker state machine of the upgraded swap protocol (swap v2) for the given pair of coins.
+#[allow(clippy::too_many_arguments)]
+fn start_maker_swap_state_machine<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2>(
+    ctx: MmArc,
+    uuid: Uuid,
+    maker_coin: MakerCoin,
+    taker_coin: TakerCoin,
+    maker_volume: MmNumber,
+    taker_volume: MmNumber,
+    secret: H256,
+    conf_settings: SwapConfirmationsSettings,
+    p2p_keypair: Option<KeyPair>,
+    secret_hash_algo: SecretHashAlgo,
+    lock_duration: u64,
+) {
+    // TODO:
+    // Support KMD burning for v2
+    let dex_fee_amount =
+        dex_fee_amount_from_taker_coin(&taker_coin, maker_coin.ticker(), &taker_volume).total_spend_amount();
+    let mut maker_swap_state_machine = MakerSwapStateMachine {
+        storage: MakerSwapStorage::new(ctx.clone()),
+        ctx: ctx.clone(),
+        started_at: now_sec(),
+        maker_coin,
+        maker_volume,
+        secret,
+        taker_coin,
+        dex_fee_amount,
+        taker_volume,
+        taker_premium: Default::default(),
+        conf_settings,
+        p2p_topic: swap_v2_topic(&uuid),
+        uuid,
+        p2p_keypair,
+        secret_hash_algo,
+        lock_duration,
+    };
+    spawn_swap_v2_machine(&ctx, uuid, async move {
+        #[allow(clippy::box_default)]
+        maker_swap_state_machine
+            .run(Box::new(maker_swap_v2::Initialize::default()))
+            .await
+            .error_log();
+    });
+}
+
+/// Starts the taker state machine of the upgraded swap protocol (swap v2) for the given pair of coins.
+#[allow(clippy::too_many_arguments)]
+fn start_taker_swap_state_machine<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2>(
+    ctx: MmArc,
+    uuid: Uuid,
+    maker_coin: MakerCoin,
+    taker_coin: TakerCoin,
+    maker_volume: MmNumber,
+    taker_volume: MmNumber,
+    taker_secret: H256,
+    conf_settings: SwapConfirmationsSettings,
+    p2p_keypair: Option<KeyPair>,
+    secret_hash_algo: SecretHashAlgo,
+    lock_duration: u64,
+) {
+    // TODO:
+    // Support KMD burning for v2
+    let dex_fee = dex_fee_amount_from_taker_coin(&taker_coin, maker_coin.ticker(), &taker_volume).total_spend_amount();
+    let mut taker_swap_state_machine = TakerSwapStateMachine {
+        storage: TakerSwapStorage::new(ctx.clone()),
+        ctx: ctx.clone(),
+        started_at: now_sec(),
+        lock_duration,
+        maker_coin,
+        maker_volume,
+        taker_coin,
+        dex_fee,
+        taker_volume,
+        taker_premium: Default::default(),
+        secret_hash_algo,
+        conf_settings,
+        p2p_topic: swap_v2_topic(&uuid),
+        uuid,
+        p2p_keypair,
+        taker_secret,
+    };
+    spawn_swap_v2_machine(&ctx, uuid, async move {
+        #[allow(clippy::box_default)]
+        taker_swap_state_machine
+            .run(Box::new(taker_swap_v2::Initialize::default()))
+            .await
+            .error_log();
+    });
+}
+
+/// Whether the swap should be started with the swap v2 protocol.
+/// The pairs that aren't supported by it yet fall back to the legacy protocol.
+/// Both sides come to the same decision since it depends on the coin types only.
+fn use_swap_v2(ctx: &MmArc, maker_coin: &MmCoinEnum, taker_coin: &MmCoinEnum, uuid: &Uuid) -> bool {
+    if !ctx.use_trading_proto_v2() {
+        return false;
+    }
+    if !is_swap_v2_pair_supported(maker_coin, taker_coin) {
+        warn!(
+            "{}/{} pair is not supported by the swap v2 protocol, starting the swap {} with the legacy one",
+            maker_coin.ticker(),
+            taker_coin.ticker(),
+            uuid
+        );
+        return false;
+    }
+    true
+}
+
 #[cfg_attr(test, mockable)]
 fn lp_connect_start_bob(ctx: MmArc, maker_match: MakerMatch, maker_order: MakerOrder) {
     let spawner = ctx.spawner();
     let uuid = maker_match.request.uuid;
+    broadcast_order_status_event(&ctx, OrderStatusEvent::Matched {
+        uuid: maker_order.uuid,
+        swap_uuid: uuid,
+    });
 
     let fut = async move {
         // aka "maker_loop"
@@ -2960,42 +3265,35 @@ fn lp_connect_start_bob(ctx: MmArc, maker_match: MakerMatch, maker_order: MakerO
             },
         };
 
-        if ctx.use_trading_proto_v2() {
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                let secret_hash_algo = detect_secret_hash_algo(&maker_coin, &taker_coin);
-                match (maker_coin, taker_coin) {
-                    (MmCoinEnum::UtxoCoin(m), MmCoinEnum::UtxoCoin(t)) => {
-                        let mut maker_swap_state_machine = MakerSwapStateMachine {
-                            storage: DummyMakerSwapStorage::new(ctx.clone()),
-                            ctx,
-                            started_at: now_sec(),
-                            maker_coin: m.clone(),
-                            maker_volume: maker_amount,
-                            secret,
-                            taker_coin: t.clone(),
-                            // TODO:
-                            // Support KMD burning for v2
-                            dex_fee_amount: dex_fee_amount_from_taker_coin(&t, m.ticker(), &taker_amount)
-                                .total_spend_amount(),
-                            taker_volume: taker_amount,
-                            taker_premium: Default::default(),
-                            conf_settings: my_conf_settings,
-                            p2p_topic: swap_v2_topic(&uuid),
-                            uuid,
-                            p2p_keypair: maker_order.p2p_privkey.map(SerializableSecp256k1Keypair::into_inner),
-                            secret_hash_algo,
-                            lock_duration: lock_time,
-                        };
-                        #[allow(clippy::box_default)]
-                        maker_swap_state_machine
-                            .run(Box::new(maker_swap_v2::Initialize::default()))
-                            .await
-                            .error_log();
-                    },
-                    _ => todo!("implement fallback to the old protocol here"),
-                }
+        if use_swap_v2(&ctx, &maker_coin, &taker_coin, &uuid) {
+            let secret_hash_algo = detect_secret_hash_algo(&maker_coin, &taker_coin);
+            let p2p_keypair = maker_order.p2p_privkey.map(SerializableSecp256k1Keypair::into_inner);
+            macro_rules! start_maker_swap_v2 {
+                ($maker_coin: expr, $taker_coin: expr) => {
+                    start_maker_swap_state_machine(
+                        ctx,
+                        uuid,
+                        $maker_coin,
+                        $taker_coin,
+                        maker_amount,
+                        taker_amount,
+                        secret,
+                        my_conf_settings,
+                        p2p_keypair,
+                        secret_hash_algo,
+                        lock_time,
+                    )
+                };
             }
+            crate::dispatch_swap_v2_coins_pair!(
+                maker_coin,
+                taker_coin,
+                start_maker_swap_v2,
+                error!(
+                    "Swap {} can't be started: the pair is not supported by the swap v2 protocol",
+                    uuid
+                )
+            )
         } else {
             if let Err(e) =
                 insert_new_swap_to_db(ctx.clone(), maker_coin.ticker(), taker_coin.ticker(), uuid, now).await
@@ -3028,6 +3326,10 @@ fn lp_connect_start_bob(ctx: MmArc, maker_match: MakerMatch, maker_order: MakerO
 fn lp_connected_alice(ctx: MmArc, taker_order: TakerOrder, taker_match: TakerMatch) {
     let spawner = ctx.spawner();
     let uuid = taker_match.reserved.taker_order_uuid;
+    broadcast_order_status_event(&ctx, OrderStatusEvent::Matched {
+        uuid: taker_order.request.uuid,
+        swap_uuid: uuid,
+    });
 
     let fut = async move {
         // aka "taker_loop"
@@ -3103,49 +3405,42 @@ fn lp_connected_alice(ctx: MmArc, taker_order: TakerOrder, taker_match: TakerMat
         );
 
         let now = now_sec();
-        if ctx.use_trading_proto_v2() {
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                let taker_secret = match generate_secret() {
-                    Ok(s) => s.into(),
-                    Err(e) => {
-                        error!("Error {} on secret generation", e);
-                        return;
-                    },
+        if use_swap_v2(&ctx, &maker_coin, &taker_coin, &uuid) {
+            let taker_secret = match generate_secret() {
+                Ok(s) => s.into(),
+                Err(e) => {
+                    error!("Error {} on secret generation", e);
+                    return;
+                },
+            };
+            let secret_hash_algo = detect_secret_hash_algo(&maker_coin, &taker_coin);
+            let p2p_keypair = taker_order.p2p_privkey.map(SerializableSecp256k1Keypair::into_inner);
+            macro_rules! start_taker_swap_v2 {
+                ($maker_coin: expr, $taker_coin: expr) => {
+                    start_taker_swap_state_machine(
+                        ctx,
+                        uuid,
+                        $maker_coin,
+                        $taker_coin,
+                        maker_amount,
+                        taker_amount,
+                        taker_secret,
+                        my_conf_settings,
+                        p2p_keypair,
+                        secret_hash_algo,
+                        locktime,
+                    )
                 };
-                let secret_hash_algo = detect_secret_hash_algo(&maker_coin, &taker_coin);
-                match (maker_coin, taker_coin) {
-                    (MmCoinEnum::UtxoCoin(m), MmCoinEnum::UtxoCoin(t)) => {
-                        let mut taker_swap_state_machine = TakerSwapStateMachine {
-                            storage: DummyTakerSwapStorage::new(ctx.clone()),
-                            ctx,
-                            started_at: now,
-                            lock_duration: locktime,
-                            maker_coin: m.clone(),
-                            maker_volume: maker_amount,
-                            taker_coin: t.clone(),
-                            // TODO:
-                            // Support KMD burning for v2
-                            dex_fee: dex_fee_amount_from_taker_coin(&t, maker_coin_ticker, &taker_amount)
-                                .total_spend_amount(),
-                            taker_volume: taker_amount,
-                            taker_premium: Default::default(),
-                            secret_hash_algo,
-                            conf_settings: my_conf_settings,
-                            p2p_topic: swap_v2_topic(&uuid),
-                            uuid,
-                            p2p_keypair: taker_order.p2p_privkey.map(SerializableSecp256k1Keypair::into_inner),
-                            taker_secret,
-                        };
-                        #[allow(clippy::box_default)]
-                        taker_swap_state_machine
-                            .run(Box::new(taker_swap_v2::Initialize::default()))
-                            .await
-                            .error_log();
-                    },
-                    _ => todo!("implement fallback to the old protocol here"),
-                }
             }
+            crate::dispatch_swap_v2_coins_pair!(
+                maker_coin,
+                taker_coin,
+                start_taker_swap_v2,
+                error!(
+                    "Swap {} can't be started: the pair is not supported by the swap v2 protocol",
+                    uuid
+                )
+            )
         } else {
             #[cfg(any(test, feature = "run-docker-tests"))]
             let fail_at = std::env::var("TAKER_FAIL_AT").map(FailAt::from).ok();
@@ -3195,6 +3490,7 @@ pub async fn lp_ordermatch_loop(ctx: MmArc) {
 
         handle_timed_out_taker_orders(ctx.clone(), &ordermatch_ctx).await;
         handle_timed_out_maker_matches(ctx.clone(), &ordermatch_ctx).await;
+        handle_expired_maker_orders(ctx.clone(), &ordermatch_ctx).await;
         check_balance_for_maker_orders(ctx.clone(), &ordermatch_ctx).await;
 
         {
@@ -3326,7 +3622,8 @@ pub async fn clean_memory_loop(ctx_weak: MmWeak) {
     }
 }
 
-/// Transforms the timed out and unmatched GTC taker orders to maker.
+/// Transforms the timed out and unmatched GTC and GTT taker orders to maker,
+/// cancels the expired unmatched GTT taker orders.
 ///
 /// # Safety
 ///
@@ -3337,12 +3634,28 @@ async fn handle_timed_out_taker_orders(ctx: MmArc, ordermatch_ctx: &OrdermatchCo
     let mut my_actual_taker_orders = HashMap::with_capacity(my_taker_orders.len());
 
     for (uuid, order) in my_taker_orders.drain() {
+        let is_expired = order
+            .order_type
+            .expires_at()
+            .map_or(false, |expires_at| expires_at <= now_sec());
+        if is_expired && order.matches.is_empty() {
+            delete_my_taker_order(ctx.clone(), order, TakerOrderCancellationReason::Expired)
+                .compat()
+                .await
+                .ok();
+            continue;
+        }
+
         if order.created_at + order.timeout * 1000 >= now_ms() {
             my_actual_taker_orders.insert(uuid, order);
             continue;
         }
 
-        if !order.matches.is_empty() || order.order_type != OrderType::GoodTillCancelled {
+        let can_become_maker = matches!(
+            order.order_type,
+            OrderType::GoodTillCancelled | OrderType::GoodTillTime { .. }
+        );
+        if !order.matches.is_empty() || !can_become_maker {
             delete_my_taker_order(ctx.clone(), order, TakerOrderCancellationReason::TimedOut)
                 .compat()
                 .await
@@ -3366,6 +3679,7 @@ async fn handle_timed_out_taker_orders(ctx: MmArc, ordermatch_ctx: &OrdermatchCo
             .save_new_active_maker_order(&maker_order)
             .await
             .error_log_with_msg("!save_new_active_maker_order");
+        broadcast_order_status_event(&ctx, OrderStatusEvent::Created(Order::Maker(maker_order.clone())));
         if maker_order.save_in_history {
             storage
                 .update_was_taker_in_filtering_history(uuid)
@@ -3416,6 +3730,34 @@ async fn check_balance_for_maker_orders(ctx: MmArc, ordermatch_ctx: &OrdermatchC
     }
 }
 
+/// Cancels the maker orders that reached their expiration time and notifies other peers.
+///
+/// # Safety
+///
+/// The function locks the [`OrdermatchContext::my_maker_orders`] mutex.
+async fn handle_expired_maker_orders(ctx: MmArc, ordermatch_ctx: &OrdermatchContext) {
+    let now = now_sec();
+    let my_maker_orders = ordermatch_ctx.maker_orders_ctx.lock().orders.clone();
+
+    for (uuid, order) in my_maker_orders {
+        let order = order.lock().await;
+        // Let the ongoing swaps start, the order will be cancelled on the next iteration.
+        if !order.is_expired(now) || order.has_ongoing_matches() {
+            continue;
+        }
+
+        let removed_order_mutex = ordermatch_ctx.maker_orders_ctx.lock().remove_order(&uuid);
+        // This checks that the order hasn't been removed by another process
+        if removed_order_mutex.is_some() {
+            maker_order_cancelled_p2p_notify(ctx.clone(), &order);
+            delete_my_maker_order(ctx.clone(), order.clone(), MakerOrderCancellationReason::Expired)
+                .compat()
+                .await
+                .ok();
+        }
+    }
+}
+
 /// Removes timed out unfinished matches to unlock the reserved amount.
 ///
 /// # Safety
@@ -3441,6 +3783,14 @@ async fn handle_timed_out_maker_matches(ctx: MmArc, ordermatch_ctx: &OrdermatchC
     }
 }
 
+/// Whether the counterparty reputation satisfies the minimum required by my order if any.
+async fn counterparty_has_min_reputation(ctx: &MmArc, pubkey: &H256Json, min_reputation: Option<&MmNumber>) -> bool {
+    match min_reputation {
+        Some(min_reputation) => has_min_reputation(ctx, pubkey, min_reputation).await,
+        None => true,
+    }
+}
+
 async fn process_maker_reserved(ctx: MmArc, from_pubkey: H256Json, reserved_msg: MakerReserved) {
     log::debug!("Processing MakerReserved {:?}", reserved_msg);
     let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).unwrap();
@@ -3492,6 +3842,20 @@ async fn process_maker_reserved(ctx: MmArc, from_pubkey: H256Json, reserved_msg:
         reserved_messages.sort_unstable_by_key(|r| r.price());
 
         for reserved_msg in reserved_messages {
+            if !counterparty_has_min_reputation(
+                &ctx,
+                &reserved_msg.sender_pubkey,
+                my_order.min_maker_reputation.as_ref(),
+            )
+            .await
+            {
+                log::debug!(
+                    "Maker {} reputation is below the minimum of the order {}",
+                    reserved_msg.sender_pubkey,
+                    uuid
+                );
+                continue;
+            }
             let my_conf_settings = choose_maker_confs_and_notas(
                 reserved_msg.conf_settings.clone(),
                 &my_order.request,
@@ -3617,6 +3981,14 @@ async fn process_taker_request(ctx: MmArc, from_pubkey: H256Json, taker_request:
     for (uuid, order) in filtered {
         let mut order = order.lock().await;
         if let OrderMatchResult::Matched((base_amount, rel_amount)) = order.match_with_request(&taker_request) {
+            if !counterparty_has_min_reputation(&ctx, &from_pubkey, order.min_taker_reputation.as_ref()).await {
+                log::debug!(
+                    "Taker {} reputation is below the minimum of the order {}",
+                    from_pubkey,
+                    uuid
+                );
+                continue;
+            }
             let (base_coin, rel_coin) = match find_pair(&ctx, &order.base, &order.rel).await {
                 Ok(Some(c)) => c,
                 _ => return, // attempt to match with deactivated coin
@@ -3750,10 +4122,11 @@ async fn process_taker_connect(ctx: MmArc, sender_pubkey: H256Json, connect_msg:
         let topic = my_order.orderbook_topic();
         broadcast_ordermatch_message(&ctx, topic.clone(), connected.into(), my_order.p2p_keypair());
 
-        // If volume is less order will be cancelled a bit later
+        // If volume is less order will be cancelled a bit later.
+        // The visible volume of an iceberg order is replenished from the hidden remainder here.
         if my_order.available_amount() >= my_order.min_base_vol {
             let mut updated_msg = new_protocol::MakerOrderUpdated::new(my_order.uuid);
-            updated_msg.with_new_max_volume(my_order.available_amount().into());
+            updated_msg.with_new_max_volume(my_order.visible_amount().into());
             maker_order_updated_p2p_notify(ctx.clone(), topic, updated_msg, my_order.p2p_keypair());
         }
         MyOrdersStorage::new(ctx)
@@ -3765,23 +4138,29 @@ async fn process_taker_connect(ctx: MmArc, sender_pubkey: H256Json, connect_msg:
 
 pub async fn buy(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
     let input: SellBuyRequest = try_s!(json::from_value(req));
+    let res = try_s!(lp_buy(&ctx, input).await);
+    Ok(try_s!(Response::builder().body(res)))
+}
+
+/// Checks the coins and the balance, then creates a taker order with the `buy` action.
+pub async fn lp_buy(ctx: &MmArc, input: SellBuyRequest) -> Result<Vec<u8>, String> {
     if input.base == input.rel {
         return ERR!("Base and rel must be different coins");
     }
-    let rel_coin = try_s!(lp_coinfind(&ctx, &input.rel).await);
+    let rel_coin = try_s!(lp_coinfind(ctx, &input.rel).await);
     let rel_coin = try_s!(rel_coin.ok_or("Rel coin is not found or inactive"));
-    let base_coin = try_s!(lp_coinfind(&ctx, &input.base).await);
+    let base_coin = try_s!(lp_coinfind(ctx, &input.base).await);
     let base_coin: MmCoinEnum = try_s!(base_coin.ok_or("Base coin is not found or inactive"));
-    if base_coin.wallet_only(&ctx) {
+    if base_coin.wallet_only(ctx) {
         return ERR!("Base coin {} is wallet only", input.base);
     }
-    if rel_coin.wallet_only(&ctx) {
+    if rel_coin.wallet_only(ctx) {
         return ERR!("Rel coin {} is wallet only", input.rel);
     }
     let my_amount = &input.volume * &input.price;
     try_s!(
         check_balance_for_taker_swap(
-            &ctx,
+            ctx,
             rel_coin.deref(),
             base_coin.deref(),
             my_amount,
@@ -3791,28 +4170,33 @@ pub async fn buy(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
         )
         .await
     );
-    let res = try_s!(lp_auto_buy(&ctx, &base_coin, &rel_coin, input).await);
-    Ok(try_s!(Response::builder().body(res)))
+    lp_auto_buy(ctx, &base_coin, &rel_coin, input).await
 }
 
 pub async fn sell(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
     let input: SellBuyRequest = try_s!(json::from_value(req));
+    let res = try_s!(lp_sell(&ctx, input).await);
+    Ok(try_s!(Response::builder().body(res)))
+}
+
+/// Checks the coins and the balance, then creates a taker order with the `sell` action.
+pub async fn lp_sell(ctx: &MmArc, input: SellBuyRequest) -> Result<Vec<u8>, String> {
     if input.base == input.rel {
         return ERR!("Base and rel must be different coins");
     }
-    let base_coin = try_s!(lp_coinfind(&ctx, &input.base).await);
+    let base_coin = try_s!(lp_coinfind(ctx, &input.base).await);
     let base_coin = try_s!(base_coin.ok_or("Base coin is not found or inactive"));
-    let rel_coin = try_s!(lp_coinfind(&ctx, &input.rel).await);
+    let rel_coin = try_s!(lp_coinfind(ctx, &input.rel).await);
     let rel_coin = try_s!(rel_coin.ok_or("Rel coin is not found or inactive"));
-    if base_coin.wallet_only(&ctx) {
+    if base_coin.wallet_only(ctx) {
         return ERR!("Base coin {} is wallet only", input.base);
     }
-    if rel_coin.wallet_only(&ctx) {
+    if rel_coin.wallet_only(ctx) {
         return ERR!("Rel coin {} is wallet only", input.rel);
     }
     try_s!(
         check_balance_for_taker_swap(
-            &ctx,
+            ctx,
             base_coin.deref(),
             rel_coin.deref(),
             input.volume.clone(),
@@ -3822,8 +4206,7 @@ pub async fn sell(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
         )
         .await
     );
-    let res = try_s!(lp_auto_buy(&ctx, &base_coin, &rel_coin, input).await);
-    Ok(try_s!(Response::builder().body(res)))
+    lp_auto_buy(ctx, &base_coin, &rel_coin, input).await
 }
 
 /// Created when maker order is matched with taker request
@@ -3896,6 +4279,7 @@ pub async fn lp_auto_buy(
         .with_rel_amount(rel_volume)
         .with_action(action)
         .with_match_by(input.match_by)
+        .with_min_maker_reputation(input.min_maker_reputation)
         .with_min_volume(input.min_volume)
         .with_order_type(input.order_type)
         .with_conf_settings(conf_settings)
@@ -4380,6 +4764,13 @@ pub struct SetPriceReq {
     rel_nota: Option<bool>,
     #[serde(default = "get_true")]
     save_in_history: bool,
+    /// Only [`OrderType::GoodTillCancelled`] and [`OrderType::GoodTillTime`] are supported for maker orders.
+    #[serde(default)]
+    order_type: OrderType,
+    /// Creates an iceberg order advertising only this part of the volume.
+    visible_volume: Option<MmNumber>,
+    /// Only the takers with at least this reputation score, see the `pubkey_reputation` RPC, are matched.
+    min_taker_reputation: Option<MmNumber>,
 }
 
 #[derive(Deserialize)]
@@ -4514,6 +4905,14 @@ struct MakerOrderForRpc<'a> {
     changes_history: &'a Option<Vec<HistoricalOrder>>,
     base_orderbook_ticker: &'a Option<String>,
     rel_orderbook_ticker: &'a Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visible_volume: Option<BigDecimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visible_volume_rat: Option<&'a MmNumber>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_taker_reputation: Option<BigDecimal>,
 }
 
 impl<'a> From<&'a MakerOrder> for MakerOrderForRpc<'a> {
@@ -4540,6 +4939,10 @@ impl<'a> From<&'a MakerOrder> for MakerOrderForRpc<'a> {
             changes_history: &order.changes_history,
             base_orderbook_ticker: &order.base_orderbook_ticker,
             rel_orderbook_ticker: &order.rel_orderbook_ticker,
+            expires_at: order.expires_at,
+            visible_volume: order.visible_volume.as_ref().map(MmNumber::to_decimal),
+            visible_volume_rat: order.visible_volume.as_ref(),
+            min_taker_reputation: order.min_taker_reputation.as_ref().map(MmNumber::to_decimal),
         }
     }
 }
@@ -4586,6 +4989,44 @@ pub async fn check_balance_update_loop(ctx: MmWeak, ticker: String, balance: Opt
     }
 }
 
+/// Returns the expiration time of a maker order of the given `order_type`.
+/// Only good-till-cancelled and good-till-time maker orders are supported.
+fn maker_order_expires_at(order_type: &OrderType) -> Result<Option<u64>, String> {
+    match order_type {
+        OrderType::GoodTillCancelled => Ok(None),
+        OrderType::GoodTillTime { expires_at } => Ok(Some(*expires_at)),
+        order_type => ERR!("{:?} order type is not supported for maker orders", order_type),
+    }
+}
+
+fn build_maker_order(
+    ordermatch_ctx: &OrdermatchContext,
+    base_coin: &MmCoinEnum,
+    rel_coin: &MmCoinEnum,
+    req: &SetPriceReq,
+    volume: MmNumber,
+    expires_at: Option<u64>,
+) -> Result<MakerOrder, MakerOrderBuildError> {
+    let conf_settings = OrderConfirmationsSettings {
+        base_confs: req.base_confs.unwrap_or_else(|| base_coin.required_confirmations()),
+        base_nota: req.base_nota.unwrap_or_else(|| base_coin.requires_notarization()),
+        rel_confs: req.rel_confs.unwrap_or_else(|| rel_coin.required_confirmations()),
+        rel_nota: req.rel_nota.unwrap_or_else(|| rel_coin.requires_notarization()),
+    };
+    let builder = MakerOrderBuilder::new(base_coin, rel_coin)
+        .with_max_base_vol(volume)
+        .with_min_base_vol(req.min_volume.clone())
+        .with_price(req.price.clone())
+        .with_conf_settings(conf_settings)
+        .with_save_in_history(req.save_in_history)
+        .with_base_orderbook_ticker(ordermatch_ctx.orderbook_ticker(base_coin.ticker()))
+        .with_rel_orderbook_ticker(ordermatch_ctx.orderbook_ticker(rel_coin.ticker()))
+        .with_expires_at(expires_at)
+        .with_visible_volume(req.visible_volume.clone())
+        .with_min_taker_reputation(req.min_taker_reputation.clone());
+    builder.build()
+}
+
 pub async fn create_maker_order(ctx: &MmArc, req: SetPriceReq) -> Result<MakerOrder, String> {
     let base_coin: MmCoinEnum = match try_s!(lp_coinfind(ctx, &req.base).await) {
         Some(coin) => coin,
@@ -4604,6 +5045,8 @@ pub async fn create_maker_order(ctx: &MmArc, req: SetPriceReq) -> Result<MakerOr
         return ERR!("Rel coin {} is wallet only", req.rel);
     }
 
+    let expires_at = try_s!(maker_order_expires_at(&req.order_type));
+
     let (volume, balance) = if req.max {
         let CoinVolumeInfo { volume, balance, .. } = try_s!(
             get_max_maker_vol(ctx, &base_coin)
@@ -4634,22 +5077,14 @@ pub async fn create_maker_order(ctx: &MmArc, req: SetPriceReq) -> Result<MakerOr
         cancel_previous_maker_orders(ctx, &ordermatch_ctx, &req.base, &req.rel).await;
     }
 
-    let conf_settings = OrderConfirmationsSettings {
-        base_confs: req.base_confs.unwrap_or_else(|| base_coin.required_confirmations()),
-        base_nota: req.base_nota.unwrap_or_else(|| base_coin.requires_notarization()),
-        rel_confs: req.rel_confs.unwrap_or_else(|| rel_coin.required_confirmations()),
-        rel_nota: req.rel_nota.unwrap_or_else(|| rel_coin.requires_notarization()),
-    };
-    let builder = MakerOrderBuilder::new(&base_coin, &rel_coin)
-        .with_max_base_vol(volume.clone())
-        .with_min_base_vol(req.min_volume)
-        .with_price(req.price.clone())
-        .with_conf_settings(conf_settings)
-        .with_save_in_history(req.save_in_history)
-        .with_base_orderbook_ticker(ordermatch_ctx.orderbook_ticker(base_coin.ticker()))
-        .with_rel_orderbook_ticker(ordermatch_ctx.orderbook_ticker(rel_coin.ticker()));
-
-    let new_order = try_s!(builder.build());
+    let new_order = try_s!(build_maker_order(
+        &ordermatch_ctx,
+        &base_coin,
+        &rel_coin,
+        &req,
+        volume.clone(),
+        expires_at
+    ));
 
     let request_orderbook = false;
     try_s!(
@@ -4844,7 +5279,7 @@ pub async fn update_maker_order(ctx: &MmArc, req: MakerOrderUpdateReq) -> Result
         *order = order_before_update;
         return ERR!("Error on saving updated order state to database:{}", e);
     }
-    update_msg.with_new_max_volume((new_volume - reserved_amount).into());
+    update_msg.with_new_max_volume(order.visible_amount().into());
     maker_order_updated_p2p_notify(ctx.clone(), order.orderbook_topic(), update_msg, order.p2p_keypair());
     Ok(order.clone())
 }
@@ -4925,6 +5360,7 @@ pub enum MakerOrderCancellationReason {
     Fulfilled,
     InsufficientBalance,
     Cancelled,
+    Expired,
 }
 
 #[derive(Display)]
@@ -4933,6 +5369,7 @@ pub enum TakerOrderCancellationReason {
     ToMaker,
     TimedOut,
     Cancelled,
+    Expired,
 }
 
 #[derive(Debug, Deserialize)]
@@ -5207,6 +5644,9 @@ struct MakerOrderForMyOrdersRpc<'a> {
     order: MakerOrderForRpc<'a>,
     cancellable: bool,
     available_amount: BigDecimal,
+    /// The part of `available_amount` not advertised to the orderbook, set for iceberg orders only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hidden_amount: Option<BigDecimal>,
 }
 
 impl<'a> From<&'a MakerOrder> for MakerOrderForMyOrdersRpc<'a> {
@@ -5215,6 +5655,7 @@ impl<'a> From<&'a MakerOrder> for MakerOrderForMyOrdersRpc<'a> {
             order: order.into(),
             cancellable: order.is_cancellable(),
             available_amount: order.available_amount().into(),
+            hidden_amount: order.visible_volume.as_ref().map(|_| order.hidden_amount().into()),
         }
     }
 }
@@ -5330,6 +5771,14 @@ fn my_order_history_file_path(ctx: &MmArc, uuid: &Uuid) -> PathBuf {
     my_orders_history_dir(ctx).join(format!("{}.json", uuid))
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn my_conditional_orders_dir(ctx: &MmArc) -> PathBuf { ctx.dbdir().join("ORDERS").join("MY").join("CONDITIONAL") }
+
+#[cfg(not(target_arch = "wasm32"))]
+fn my_conditional_order_file_path(ctx: &MmArc, uuid: &Uuid) -> PathBuf {
+    my_conditional_orders_dir(ctx).join(format!("{}.json", uuid))
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct HistoricalOrder {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -5361,12 +5810,16 @@ pub async fn orders_kick_start(ctx: &MmArc) -> Result<HashSet<String>, String> {
         }
     }
 
-    let mut taker_orders = ordermatch_ctx.my_taker_orders.lock().await;
-    for order in saved_taker_orders {
-        coins.insert(order.request.base.clone());
-        coins.insert(order.request.rel.clone());
-        taker_orders.insert(order.request.uuid, order);
+    {
+        let mut taker_orders = ordermatch_ctx.my_taker_orders.lock().await;
+        for order in saved_taker_orders {
+            coins.insert(order.request.base.clone());
+            coins.insert(order.request.rel.clone());
+            taker_orders.insert(order.request.uuid, order);
+        }
     }
+
+    coins.extend(try_s!(conditional_orders_kick_start(ctx, &ordermatch_ctx).await));
     Ok(coins)
 }
 
@@ -5824,3 +6277,73 @@ fn orderbook_address(
         CoinProtocol::LIGHTNING { .. } => Ok(OrderbookAddress::Shielded),
     }
 }
+
+/// Registers the ordermatch mmrpc 2.0 methods.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "best_orders",
+        "Returns the best orders to fill the given volume or number of orders.",
+        mmrpc_handler!(best_orders_rpc_v2),
+    );
+    registry.register_with_auth(
+        "orderbook",
+        AuthLevel::Public,
+        "Returns the orderbook of the given pair.",
+        mmrpc_handler!(orderbook_rpc_v2),
+    );
+    registry.register(
+        "start_simple_market_maker_bot",
+        "Starts the simple market maker bot with the given config.",
+        mmrpc_handler!(start_simple_market_maker_bot),
+    );
+    registry.register(
+        "stop_simple_market_maker_bot",
+        "Stops the simple market maker bot.",
+        mmrpc_handler!(stop_simple_market_maker_bot),
+    );
+    registry.register(
+        "get_simple_market_maker_bot_status",
+        "Returns the simple market maker bot state, inventory, PnL and the state of every pair.",
+        mmrpc_handler!(get_simple_market_maker_bot_status),
+    );
+    registry.register(
+        "create_conditional_order",
+        "Creates a stop-loss or take-profit order placed when the market price reaches the trigger price.",
+        mmrpc_handler!(create_conditional_order),
+    );
+    registry.register(
+        "my_conditional_orders",
+        "Returns the conditional orders waiting for their trigger price.",
+        mmrpc_handler!(my_conditional_orders),
+    );
+    registry.register(
+        "cancel_conditional_order",
+        "Cancels the conditional order by uuid.",
+        mmrpc_handler!(cancel_conditional_order),
+    );
+    registry.register(
+        "batch_setprice",
+        "Places several maker orders at once checking the balances for the whole batch.",
+        mmrpc_handler!(batch_setprice),
+    );
+    registry.register(
+        "batch_cancel",
+        "Cancels several orders at once returning the result for each order.",
+        mmrpc_handler!(batch_cancel),
+    );
+    registry.register(
+        "replace_orders",
+        "Atomically cancels the given maker orders and places the new ones.",
+        mmrpc_handler!(replace_orders),
+    );
+    registry.register(
+        "orderbook_stream::enable",
+        "Streams the orderbook snapshot of the given pair followed by the order deltas as the ORDERBOOK event.",
+        mmrpc_handler!(enable_orderbook_stream),
+    );
+    registry.register(
+        "orderbook_stream::disable",
+        "Removes a subscriber of the orderbook stream of the given pair.",
+        mmrpc_handler!(disable_orderbook_stream),
+    );
+}
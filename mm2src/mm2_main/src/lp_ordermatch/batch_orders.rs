@@ -0,0 +1,799 @@
+//! Batch placement, cancellation and replacement of maker orders.
+//!
+//! Issuing a separate `setprice` or `cancel_order` call per order of a ladder requests the balances,
+//! locks [`MakerOrdersContext`] and persists the order once per call.
+//! The RPCs of this module request each coin balance once for the whole batch (the amounts locked by ongoing swaps
+//! are taken into account), remove the cancelled orders from [`MakerOrdersContext`] under a single lock
+//! and add the new ones once they're persisted,
+//! so `batch_setprice` and `replace_orders` either apply the whole batch or nothing.
+
+use super::my_orders_storage::{delete_my_maker_order, persist_my_new_maker_order, MyActiveOrders, MyOrdersStorage};
+use super::order_events::{broadcast_order_status_event, OrderStatusEvent};
+use super::{build_maker_order, cancel_order, check_other_coin_balance_for_order_issue,
+            maker_order_cancelled_p2p_notify, maker_order_created_p2p_notify, maker_order_expires_at,
+            subscribe_to_orderbook_topic, CancelOrderError, CancelOrderReq, MakerOrder, MakerOrderCancellationReason,
+            MakerOrdersContext, Order, OrdermatchContext, SetPriceReq};
+use crate::mm2::lp_swap::{get_max_maker_vol, CheckBalanceError, CoinVolumeInfo};
+use coins::{lp_coinfind, MarketCoinOps, MmCoin, MmCoinEnum};
+use common::log::LogOnError;
+use common::{BoxFut, HttpStatusCode, StatusCode};
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use futures::{FutureExt, TryFutureExt};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigDecimal, MmNumber, MmNumberMultiRepr};
+#[cfg(test)] use mocktopus::macros::*;
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+use uuid::Uuid;
+
+pub type BatchOrdersResult<T> = Result<T, MmError<BatchOrdersError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum BatchOrdersError {
+    #[display(fmt = "The request contains no orders")]
+    EmptyRequest,
+    #[display(fmt = "Order {} is listed more than once", uuid)]
+    DuplicateUuid { uuid: Uuid },
+    #[display(fmt = "Order #{} is invalid: {}", index, reason)]
+    InvalidOrder { index: usize, reason: String },
+    #[display(
+        fmt = "Not enough {} for the batch: available {}, required {}, locked by swaps {:?}",
+        coin,
+        available,
+        required,
+        locked_by_swaps
+    )]
+    NotSufficientBalance {
+        coin: String,
+        available: BigDecimal,
+        required: BigDecimal,
+        locked_by_swaps: Option<BigDecimal>,
+    },
+    #[display(
+        fmt = "Not enough base coin {} balance for the batch: available {}, required {}, locked by swaps {:?}",
+        coin,
+        available,
+        required,
+        locked_by_swaps
+    )]
+    NotSufficientBaseCoinBalance {
+        coin: String,
+        available: BigDecimal,
+        required: BigDecimal,
+        locked_by_swaps: Option<BigDecimal>,
+    },
+    #[display(
+        fmt = "The volume {} of the {} coin less than minimum transaction amount {}",
+        volume,
+        coin,
+        threshold
+    )]
+    VolumeTooLow {
+        coin: String,
+        volume: BigDecimal,
+        threshold: BigDecimal,
+    },
+    #[display(fmt = "Order {} is not found", uuid)]
+    NoSuchOrder { uuid: Uuid },
+    #[display(fmt = "Order {} is being matched now, can't cancel", uuid)]
+    OrderBeingMatched { uuid: Uuid },
+    #[display(fmt = "Transport error: {}", _0)]
+    Transport(String),
+    #[display(fmt = "Storage error: {}", _0)]
+    StorageError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    InternalError(String),
+}
+
+impl HttpStatusCode for BatchOrdersError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BatchOrdersError::EmptyRequest
+            | BatchOrdersError::DuplicateUuid { .. }
+            | BatchOrdersError::InvalidOrder { .. }
+            | BatchOrdersError::NotSufficientBalance { .. }
+            | BatchOrdersError::NotSufficientBaseCoinBalance { .. }
+            | BatchOrdersError::VolumeTooLow { .. }
+            | BatchOrdersError::NoSuchOrder { .. }
+            | BatchOrdersError::OrderBeingMatched { .. } => StatusCode::BAD_REQUEST,
+            BatchOrdersError::Transport(_) => StatusCode::BAD_GATEWAY,
+            BatchOrdersError::StorageError(_) | BatchOrdersError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<CheckBalanceError> for BatchOrdersError {
+    fn from(e: CheckBalanceError) -> Self {
+        match e {
+            CheckBalanceError::NotSufficientBalance {
+                coin,
+                available,
+                required,
+                locked_by_swaps,
+            } => BatchOrdersError::NotSufficientBalance {
+                coin,
+                available,
+                required,
+                locked_by_swaps,
+            },
+            CheckBalanceError::NotSufficientBaseCoinBalance {
+                coin,
+                available,
+                required,
+                locked_by_swaps,
+            } => BatchOrdersError::NotSufficientBaseCoinBalance {
+                coin,
+                available,
+                required,
+                locked_by_swaps,
+            },
+            CheckBalanceError::VolumeTooLow {
+                coin,
+                volume,
+                threshold,
+            } => BatchOrdersError::VolumeTooLow {
+                coin,
+                volume,
+                threshold,
+            },
+            CheckBalanceError::Transport(transport) => BatchOrdersError::Transport(transport),
+            CheckBalanceError::InternalError(internal) => BatchOrdersError::InternalError(internal),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BatchSetPriceRequest {
+    orders: Vec<SetPriceReq>,
+}
+
+#[derive(Serialize)]
+pub struct BatchSetPriceResponse {
+    /// The created orders in the order of the request.
+    orders: Vec<BatchOrderInfo>,
+    /// The orders cancelled due to `cancel_previous`.
+    cancelled: Vec<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct BatchCancelRequest {
+    uuids: Vec<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct BatchCancelResponse {
+    /// The cancellation results in the order of the request.
+    results: Vec<BatchCancelResult>,
+}
+
+#[derive(Serialize)]
+pub struct BatchCancelResult {
+    uuid: Uuid,
+    cancelled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<CancelOrderError>,
+}
+
+#[derive(Deserialize)]
+pub struct ReplaceOrdersRequest {
+    /// The maker orders to cancel.
+    cancel: Vec<Uuid>,
+    /// The maker orders to place instead.
+    orders: Vec<SetPriceReq>,
+}
+
+#[derive(Serialize)]
+pub struct ReplaceOrdersResponse {
+    /// The created orders in the order of the request.
+    orders: Vec<BatchOrderInfo>,
+    /// The cancelled orders including the ones cancelled due to `cancel_previous`.
+    cancelled: Vec<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct BatchOrderInfo {
+    uuid: Uuid,
+    base: String,
+    rel: String,
+    price: MmNumberMultiRepr,
+    max_base_vol: MmNumberMultiRepr,
+    min_base_vol: MmNumberMultiRepr,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+}
+
+impl From<&MakerOrder> for BatchOrderInfo {
+    fn from(order: &MakerOrder) -> Self {
+        BatchOrderInfo {
+            uuid: order.uuid,
+            base: order.base.clone(),
+            rel: order.rel.clone(),
+            price: order.price.clone().into(),
+            max_base_vol: order.max_base_vol.clone().into(),
+            min_base_vol: order.min_base_vol.clone().into(),
+            expires_at: order.expires_at,
+        }
+    }
+}
+
+/// A maker order that passed the validation but isn't published yet.
+struct PreparedOrder {
+    order: MakerOrder,
+    base_coin: MmCoinEnum,
+    rel_coin: MmCoinEnum,
+    /// The base coin balance the order is tracked with in [`MakerOrdersContext`].
+    balance: BigDecimal,
+}
+
+pub async fn batch_setprice(ctx: MmArc, req: BatchSetPriceRequest) -> BatchOrdersResult<BatchSetPriceResponse> {
+    let (orders, cancelled) = apply_maker_orders_batch(&ctx, Vec::new(), req.orders).await?;
+    Ok(BatchSetPriceResponse {
+        orders: orders.iter().map(BatchOrderInfo::from).collect(),
+        cancelled,
+    })
+}
+
+pub async fn replace_orders(ctx: MmArc, req: ReplaceOrdersRequest) -> BatchOrdersResult<ReplaceOrdersResponse> {
+    let (orders, cancelled) = apply_maker_orders_batch(&ctx, req.cancel, req.orders).await?;
+    Ok(ReplaceOrdersResponse {
+        orders: orders.iter().map(BatchOrderInfo::from).collect(),
+        cancelled,
+    })
+}
+
+/// Cancels the maker orders removing them from [`MakerOrdersContext`] at once.
+/// The uuids that don't belong to maker orders are cancelled as taker orders by [`cancel_order`].
+/// Unlike [`replace_orders`], the orders that can be cancelled are cancelled even if others can't.
+pub async fn batch_cancel(ctx: MmArc, req: BatchCancelRequest) -> BatchOrdersResult<BatchCancelResponse> {
+    if req.uuids.is_empty() {
+        return MmError::err(BatchOrdersError::EmptyRequest);
+    }
+    check_unique_uuids(&req.uuids)?;
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(BatchOrdersError::InternalError)?;
+
+    let mut maker_orders: Vec<_> = {
+        let maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+        req.uuids
+            .iter()
+            .filter_map(|uuid| maker_orders_ctx.get_order(uuid).map(|order| (*uuid, order.clone())))
+            .collect()
+    };
+    // Lock the orders in the same order to avoid deadlocks with the concurrent batches.
+    maker_orders.sort_by_key(|(uuid, _)| *uuid);
+
+    let mut results = HashMap::with_capacity(req.uuids.len());
+    let mut to_cancel = Vec::with_capacity(maker_orders.len());
+    for (uuid, order_mutex) in maker_orders.iter() {
+        let order = order_mutex.lock().await;
+        if order.is_cancellable() {
+            to_cancel.push(order);
+        } else {
+            results.insert(*uuid, Err(CancelOrderError::OrderBeingMatched { uuid: *uuid }));
+        }
+    }
+
+    let cancelled = {
+        let mut maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+        remove_maker_orders(&mut maker_orders_ctx, &to_cancel)
+    };
+    for order in to_cancel.iter() {
+        results.insert(order.uuid, Ok(()));
+    }
+    drop(to_cancel);
+    notify_cancelled_maker_orders(&ctx, cancelled).await;
+
+    for uuid in req.uuids.iter() {
+        if !results.contains_key(uuid) {
+            let result = cancel_order(ctx.clone(), CancelOrderReq { uuid: *uuid })
+                .await
+                .map(|_| ())
+                .map_err(|e| e.into_inner());
+            results.insert(*uuid, result);
+        }
+    }
+
+    let results = req
+        .uuids
+        .into_iter()
+        .map(|uuid| match results.remove(&uuid) {
+            Some(Err(error)) => BatchCancelResult {
+                uuid,
+                cancelled: false,
+                error: Some(error),
+            },
+            _ => BatchCancelResult {
+                uuid,
+                cancelled: true,
+                error: None,
+            },
+        })
+        .collect();
+    Ok(BatchCancelResponse { results })
+}
+
+/// Validates the `orders` and the cancellation of the `to_cancel` maker orders,
+/// then cancels the orders and places the new ones at once.
+/// Nothing is changed if any order is invalid, any `to_cancel` order is being matched or the balances are insufficient.
+///
+/// Returns the created orders and the uuids of the cancelled ones.
+async fn apply_maker_orders_batch(
+    ctx: &MmArc,
+    to_cancel: Vec<Uuid>,
+    orders: Vec<SetPriceReq>,
+) -> BatchOrdersResult<(Vec<MakerOrder>, Vec<Uuid>)> {
+    check_unique_uuids(&to_cancel)?;
+    let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).map_to_mm(BatchOrdersError::InternalError)?;
+    let prepared = prepare_maker_orders(ctx, &ordermatch_ctx, &orders).await?;
+
+    let request_orderbook = false;
+    for PreparedOrder { order, .. } in prepared.iter() {
+        subscribe_to_orderbook_topic(
+            ctx,
+            order.base_orderbook_ticker(),
+            order.rel_orderbook_ticker(),
+            request_orderbook,
+        )
+        .await
+        .map_to_mm(BatchOrdersError::InternalError)?;
+    }
+
+    let previous_pairs: HashSet<(&str, &str)> = orders
+        .iter()
+        .filter(|req| req.cancel_previous)
+        .map(|req| (req.base.as_str(), req.rel.as_str()))
+        .collect();
+    apply_prepared_orders(ctx, &ordermatch_ctx, &to_cancel, &previous_pairs, prepared).await
+}
+
+/// Cancels the `to_cancel` orders and the orders of the `previous_pairs`, then places the `prepared` orders.
+///
+/// The cancelled orders are removed from [`MakerOrdersContext`] while they're locked, so they can't be matched anymore.
+/// The new orders are persisted after the locks are released and only then added to [`MakerOrdersContext`].
+/// If the new orders can't be saved, the cancelled orders are put back as nothing is published yet.
+/// The balance loops of the cancelled orders are kept until then, so the orders are put back with the last known balance.
+async fn apply_prepared_orders(
+    ctx: &MmArc,
+    ordermatch_ctx: &OrdermatchContext,
+    to_cancel: &[Uuid],
+    previous_pairs: &HashSet<(&str, &str)>,
+    prepared: Vec<PreparedOrder>,
+) -> BatchOrdersResult<(Vec<MakerOrder>, Vec<Uuid>)> {
+    let mut candidates = {
+        let maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+        let mut candidates = Vec::with_capacity(to_cancel.len());
+        for uuid in to_cancel.iter() {
+            match maker_orders_ctx.get_order(uuid) {
+                Some(order) => candidates.push((*uuid, order.clone())),
+                None => return MmError::err(BatchOrdersError::NoSuchOrder { uuid: *uuid }),
+            }
+        }
+        if !previous_pairs.is_empty() {
+            candidates.extend(
+                maker_orders_ctx
+                    .orders
+                    .iter()
+                    .filter(|(uuid, _)| !to_cancel.contains(uuid))
+                    .map(|(uuid, order)| (*uuid, order.clone())),
+            );
+        }
+        candidates
+    };
+    // Lock the orders in the same order to avoid deadlocks with the concurrent batches.
+    candidates.sort_by_key(|(uuid, _)| *uuid);
+
+    let cancelled = {
+        let mut orders_to_cancel = Vec::with_capacity(candidates.len());
+        for (uuid, order_mutex) in candidates.iter() {
+            let order = order_mutex.lock().await;
+            if to_cancel.contains(uuid) {
+                if !order.is_cancellable() {
+                    return MmError::err(BatchOrdersError::OrderBeingMatched { uuid: *uuid });
+                }
+                orders_to_cancel.push(order);
+            } else if previous_pairs.contains(&(order.base.as_str(), order.rel.as_str())) {
+                orders_to_cancel.push(order);
+            }
+        }
+        let mut maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+        detach_maker_orders(&mut maker_orders_ctx, &orders_to_cancel)
+    };
+
+    let new_orders = prepared.iter().map(|prepared| prepared.order.clone()).collect();
+    if let Err(e) = save_new_maker_orders(ctx.clone(), new_orders).compat().await {
+        let mut maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+        for order in cancelled {
+            // The balance loop of the order base coin is still running, so the balance isn't requested again.
+            maker_orders_ctx.add_order(ctx.weak(), order, None);
+        }
+        return Err(e);
+    }
+
+    {
+        let mut maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+        for PreparedOrder { order, balance, .. } in prepared.iter() {
+            maker_orders_ctx.add_order(ctx.weak(), order.clone(), Some(balance.clone()));
+        }
+        stop_unused_balance_loops(&mut maker_orders_ctx, &cancelled);
+    }
+
+    let cancelled_uuids = cancelled.iter().map(|order| order.uuid).collect();
+    notify_cancelled_maker_orders(ctx, cancelled).await;
+
+    let mut created = Vec::with_capacity(prepared.len());
+    for PreparedOrder {
+        order,
+        base_coin,
+        rel_coin,
+        ..
+    } in prepared
+    {
+        broadcast_order_status_event(ctx, OrderStatusEvent::Created(Order::Maker(order.clone())));
+        let rel_volume = order.max_base_vol.clone() * order.price.clone();
+        maker_order_created_p2p_notify(
+            ctx.clone(),
+            &order,
+            base_coin.coin_protocol_info(None),
+            rel_coin.coin_protocol_info(Some(rel_volume)),
+        );
+        created.push(order);
+    }
+    Ok((created, cancelled_uuids))
+}
+
+/// Builds the maker orders checking that every coin balance is enough for all the orders of the batch using it.
+/// Each balance is requested once no matter how many orders use the coin.
+async fn prepare_maker_orders(
+    ctx: &MmArc,
+    ordermatch_ctx: &OrdermatchContext,
+    orders: &[SetPriceReq],
+) -> BatchOrdersResult<Vec<PreparedOrder>> {
+    if orders.is_empty() {
+        return MmError::err(BatchOrdersError::EmptyRequest);
+    }
+
+    let mut order_coins = Vec::with_capacity(orders.len());
+    for (index, req) in orders.iter().enumerate() {
+        let base_coin = find_order_coin(ctx, index, &req.base).await?;
+        let rel_coin = find_order_coin(ctx, index, &req.rel).await?;
+        let expires_at = maker_order_expires_at(&req.order_type)
+            .map_to_mm(|reason| BatchOrdersError::InvalidOrder { index, reason })?;
+        order_coins.push((base_coin, rel_coin, expires_at));
+    }
+
+    // `get_max_maker_vol` subtracts the amount locked by the ongoing swaps and the trade fee from the balance.
+    let mut max_volumes: HashMap<String, CoinVolumeInfo> = HashMap::new();
+    let mut checked_rel_coins = HashSet::new();
+    for (base_coin, rel_coin, _) in order_coins.iter() {
+        if !max_volumes.contains_key(base_coin.ticker()) {
+            let volume_info = get_max_maker_vol(ctx, base_coin).await?;
+            max_volumes.insert(base_coin.ticker().to_owned(), volume_info);
+        }
+        if checked_rel_coins.insert(rel_coin.ticker()) {
+            check_other_coin_balance_for_order_issue(ctx, rel_coin).await?;
+        }
+    }
+
+    let mut required_volumes: HashMap<&str, MmNumber> = HashMap::new();
+    let mut volumes = Vec::with_capacity(orders.len());
+    for ((base_coin, ..), req) in order_coins.iter().zip(orders) {
+        let volume = if req.max {
+            max_volumes[base_coin.ticker()].volume.clone()
+        } else {
+            req.volume.clone()
+        };
+        *required_volumes.entry(base_coin.ticker()).or_default() += &volume;
+        volumes.push(volume);
+    }
+    for (ticker, required) in required_volumes {
+        let volume_info = &max_volumes[ticker];
+        if required > volume_info.volume {
+            return MmError::err(BatchOrdersError::NotSufficientBalance {
+                coin: ticker.to_owned(),
+                available: volume_info.volume.to_decimal(),
+                required: required.to_decimal(),
+                locked_by_swaps: Some(volume_info.locked_by_swaps.to_decimal()),
+            });
+        }
+    }
+
+    let mut prepared = Vec::with_capacity(orders.len());
+    for (index, (((base_coin, rel_coin, expires_at), req), volume)) in
+        order_coins.into_iter().zip(orders).zip(volumes).enumerate()
+    {
+        let order =
+            build_maker_order(ordermatch_ctx, &base_coin, &rel_coin, req, volume, expires_at).map_to_mm(|e| {
+                BatchOrdersError::InvalidOrder {
+                    index,
+                    reason: e.to_string(),
+                }
+            })?;
+        let balance = max_volumes[base_coin.ticker()].balance.to_decimal();
+        prepared.push(PreparedOrder {
+            order,
+            base_coin,
+            rel_coin,
+            balance,
+        });
+    }
+    Ok(prepared)
+}
+
+async fn find_order_coin(ctx: &MmArc, index: usize, ticker: &str) -> BatchOrdersResult<MmCoinEnum> {
+    let coin = match lp_coinfind(ctx, ticker).await {
+        Ok(Some(coin)) => coin,
+        Ok(None) => {
+            return MmError::err(BatchOrdersError::InvalidOrder {
+                index,
+                reason: format!("Coin {} is not found", ticker),
+            })
+        },
+        Err(e) => return MmError::err(BatchOrdersError::InternalError(e)),
+    };
+    if coin.wallet_only(ctx) {
+        return MmError::err(BatchOrdersError::InvalidOrder {
+            index,
+            reason: format!("Coin {} is wallet only", ticker),
+        });
+    }
+    Ok(coin)
+}
+
+fn check_unique_uuids(uuids: &[Uuid]) -> BatchOrdersResult<()> {
+    let mut unique = HashSet::with_capacity(uuids.len());
+    match uuids.iter().find(|uuid| !unique.insert(**uuid)) {
+        Some(uuid) => MmError::err(BatchOrdersError::DuplicateUuid { uuid: *uuid }),
+        None => Ok(()),
+    }
+}
+
+/// Saves the new orders to the storage.
+/// If any order fails to be saved, the already saved ones are deleted.
+/// [`OrderStatusEvent::Created`] isn't broadcast here as the batch can still be rolled back.
+#[cfg_attr(test, mockable)]
+fn save_new_maker_orders(ctx: MmArc, orders: Vec<MakerOrder>) -> BoxFut<(), MmError<BatchOrdersError>> {
+    let fut = async move {
+        for (saved, order) in orders.iter().enumerate() {
+            if let Err(e) = persist_my_new_maker_order(&ctx, order).await {
+                let storage = MyOrdersStorage::new(ctx.clone());
+                for order in orders.iter().take(saved + 1) {
+                    storage
+                        .delete_active_maker_order(order.uuid)
+                        .await
+                        .error_log_with_msg("!delete_active_maker_order");
+                }
+                return MmError::err(BatchOrdersError::StorageError(e.to_string()));
+            }
+        }
+        Ok(())
+    };
+    Box::new(fut.boxed().compat())
+}
+
+/// Removes the orders from the context returning the ones that haven't been removed by another process yet.
+fn remove_maker_orders<O>(maker_orders_ctx: &mut MakerOrdersContext, orders: &[O]) -> Vec<MakerOrder>
+where
+    O: Deref<Target = MakerOrder>,
+{
+    let removed = detach_maker_orders(maker_orders_ctx, orders);
+    stop_unused_balance_loops(maker_orders_ctx, &removed);
+    removed
+}
+
+/// Same as [`remove_maker_orders`] but keeps the balance loops running
+/// until [`stop_unused_balance_loops`] is called for the removed orders.
+fn detach_maker_orders<O>(maker_orders_ctx: &mut MakerOrdersContext, orders: &[O]) -> Vec<MakerOrder>
+where
+    O: Deref<Target = MakerOrder>,
+{
+    orders
+        .iter()
+        .filter(|order| {
+            maker_orders_ctx
+                .remove_order_keeping_balance_loop(&order.uuid)
+                .is_some()
+        })
+        .map(|order| MakerOrder::clone(order))
+        .collect()
+}
+
+fn stop_unused_balance_loops(maker_orders_ctx: &mut MakerOrdersContext, removed: &[MakerOrder]) {
+    for order in removed {
+        maker_orders_ctx.stop_balance_loop_if_unused(&order.base);
+    }
+}
+
+async fn notify_cancelled_maker_orders(ctx: &MmArc, cancelled: Vec<MakerOrder>) {
+    for order in cancelled {
+        maker_order_cancelled_p2p_notify(ctx.clone(), &order);
+        delete_my_maker_order(ctx.clone(), order, MakerOrderCancellationReason::Cancelled)
+            .compat()
+            .await
+            .ok();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::MakerOrderBuilder;
+    use super::*;
+    use coins::TestCoin;
+    use common::{block_on, new_uuid};
+    use futures::channel::mpsc;
+    use mm2_libp2p::AdexBehaviourCmd;
+    use mm2_net::p2p::P2PContext;
+    use mm2_test_helpers::for_tests::mm_ctx_with_iguana;
+    use mocktopus::mocking::*;
+    use std::iter::FromIterator;
+
+    fn prepare_ctx() -> (MmArc, mpsc::Receiver<AdexBehaviourCmd>) {
+        let ctx = mm_ctx_with_iguana(None);
+        let (tx, rx) = mpsc::channel(10);
+        P2PContext::new(tx).store_to_mm_arc(&ctx);
+        delete_my_maker_order.mock_safe(|_, _, _| MockResult::Return(Box::new(futures01::future::ok(()))));
+        (ctx, rx)
+    }
+
+    fn maker_order(base: &str, rel: &str) -> MakerOrder {
+        let base_coin = MmCoinEnum::Test(TestCoin::new(base));
+        let rel_coin = MmCoinEnum::Test(TestCoin::new(rel));
+        MakerOrderBuilder::new(&base_coin, &rel_coin)
+            .with_max_base_vol(1.into())
+            .with_price(2.into())
+            .build_unchecked()
+    }
+
+    fn add_maker_order(ctx: &MmArc, order: &MakerOrder) {
+        let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).unwrap();
+        let mut maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+        maker_orders_ctx.add_order(ctx.weak(), order.clone(), None);
+    }
+
+    fn prepared_order(base: &str, rel: &str) -> PreparedOrder {
+        PreparedOrder {
+            order: maker_order(base, rel),
+            base_coin: MmCoinEnum::Test(TestCoin::new(base)),
+            rel_coin: MmCoinEnum::Test(TestCoin::new(rel)),
+            balance: 10.into(),
+        }
+    }
+
+    fn subscribe_to_order_status_events(ctx: &MmArc) -> mpsc::Receiver<OrderStatusEvent> {
+        let (tx, rx) = mpsc::channel(10);
+        let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).unwrap();
+        *ordermatch_ctx.order_status_event_tx.lock() = Some(tx);
+        rx
+    }
+
+    fn maker_order_uuids(ctx: &MmArc) -> HashSet<Uuid> {
+        let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).unwrap();
+        let maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+        maker_orders_ctx.orders.keys().copied().collect()
+    }
+
+    #[test]
+    fn test_batch_setprice_cancels_previous_orders() {
+        let (ctx, _rx) = prepare_ctx();
+        save_new_maker_orders.mock_safe(|_, _| MockResult::Return(Box::new(futures01::future::ok(()))));
+        let rick_morty = maker_order("RICK", "MORTY");
+        let morty_rick = maker_order("MORTY", "RICK");
+        add_maker_order(&ctx, &rick_morty);
+        add_maker_order(&ctx, &morty_rick);
+
+        let prepared = vec![prepared_order("RICK", "MORTY"), prepared_order("RICK", "MORTY")];
+        let new_uuids: HashSet<_> = prepared.iter().map(|prepared| prepared.order.uuid).collect();
+        let previous_pairs = HashSet::from_iter([("RICK", "MORTY")]);
+        let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).unwrap();
+
+        let (created, cancelled) = block_on(apply_prepared_orders(
+            &ctx,
+            &ordermatch_ctx,
+            &[],
+            &previous_pairs,
+            prepared,
+        ))
+        .unwrap();
+        assert_eq!(
+            created.iter().map(|order| order.uuid).collect::<HashSet<_>>(),
+            new_uuids
+        );
+        assert_eq!(cancelled, vec![rick_morty.uuid]);
+
+        let mut expected = new_uuids;
+        expected.insert(morty_rick.uuid);
+        assert_eq!(maker_order_uuids(&ctx), expected);
+    }
+
+    #[test]
+    fn test_replace_orders() {
+        let (ctx, _rx) = prepare_ctx();
+        save_new_maker_orders.mock_safe(|_, _| MockResult::Return(Box::new(futures01::future::ok(()))));
+        let to_replace = maker_order("RICK", "MORTY");
+        let to_keep = maker_order("RICK", "MORTY");
+        add_maker_order(&ctx, &to_replace);
+        add_maker_order(&ctx, &to_keep);
+
+        let prepared = vec![prepared_order("RICK", "MORTY")];
+        let new_uuid = prepared[0].order.uuid;
+        let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).unwrap();
+        let mut events = subscribe_to_order_status_events(&ctx);
+
+        let (created, cancelled) = block_on(apply_prepared_orders(
+            &ctx,
+            &ordermatch_ctx,
+            &[to_replace.uuid],
+            &HashSet::new(),
+            prepared,
+        ))
+        .unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].uuid, new_uuid);
+        assert_eq!(cancelled, vec![to_replace.uuid]);
+        assert_eq!(maker_order_uuids(&ctx), HashSet::from_iter([to_keep.uuid, new_uuid]));
+        match events.try_next() {
+            Ok(Some(OrderStatusEvent::Created(Order::Maker(order)))) => assert_eq!(order.uuid, new_uuid),
+            _ => panic!("Expected the created order event"),
+        }
+    }
+
+    #[test]
+    fn test_replace_orders_unknown_uuid() {
+        let (ctx, _rx) = prepare_ctx();
+        save_new_maker_orders.mock_safe(|_, _| panic!("Nothing must be saved"));
+        let existing = maker_order("RICK", "MORTY");
+        add_maker_order(&ctx, &existing);
+
+        let unknown = new_uuid();
+        let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).unwrap();
+        let err = block_on(apply_prepared_orders(
+            &ctx,
+            &ordermatch_ctx,
+            &[existing.uuid, unknown],
+            &HashSet::new(),
+            vec![prepared_order("RICK", "MORTY")],
+        ))
+        .unwrap_err();
+        match err.into_inner() {
+            BatchOrdersError::NoSuchOrder { uuid } => assert_eq!(uuid, unknown),
+            e => panic!("Expected NoSuchOrder, found {:?}", e),
+        }
+        assert_eq!(maker_order_uuids(&ctx), HashSet::from_iter([existing.uuid]));
+    }
+
+    #[test]
+    fn test_replace_orders_restores_cancelled_if_not_saved() {
+        let (ctx, _rx) = prepare_ctx();
+        save_new_maker_orders.mock_safe(|_, _| {
+            let error = MmError::new(BatchOrdersError::StorageError("disk is full".to_owned()));
+            MockResult::Return(Box::new(futures01::future::err(error)))
+        });
+        let to_replace = maker_order("RICK", "MORTY");
+        let previous = maker_order("MORTY", "RICK");
+        add_maker_order(&ctx, &to_replace);
+        add_maker_order(&ctx, &previous);
+
+        let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).unwrap();
+        let mut events = subscribe_to_order_status_events(&ctx);
+        let err = block_on(apply_prepared_orders(
+            &ctx,
+            &ordermatch_ctx,
+            &[to_replace.uuid],
+            &HashSet::from_iter([("MORTY", "RICK")]),
+            vec![prepared_order("RICK", "MORTY")],
+        ))
+        .unwrap_err();
+        assert!(matches!(err.into_inner(), BatchOrdersError::StorageError(_)));
+        assert_eq!(
+            maker_order_uuids(&ctx),
+            HashSet::from_iter([to_replace.uuid, previous.uuid])
+        );
+        // Nothing is reported about the orders that have never been published.
+        assert!(events.try_next().is_err());
+        // The balance loops have never been stopped, so they keep the last known balances.
+        let mut maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+        assert!(maker_orders_ctx.balance_loop_exists("RICK"));
+        assert!(maker_orders_ctx.balance_loop_exists("MORTY"));
+    }
+}
@@ -0,0 +1,699 @@
+//! Stop-loss and take-profit conditional orders.
+//!
+//! A conditional order is stored locally and doesn't enter the orderbook until its trigger condition is met.
+//! [`conditional_orders_loop`] periodically compares the trigger price with the current market price
+//! and converts the triggered orders to regular maker or taker orders
+//! using [`create_maker_order`] or [`lp_buy`]/[`lp_sell`].
+
+use super::my_orders_storage::{MyConditionalOrders, MyOrdersStorage};
+use super::{create_maker_order, lp_buy, lp_sell, subscribe_to_orderbook_topic, Orderbook, OrdermatchContext,
+            SetPriceReq, KMD_PRICE_ENDPOINT};
+use coins::lp_coinfind;
+use coins::lp_price::{fetch_price_tickers, TickerInfosRegistry};
+use common::executor::Timer;
+use common::log::{debug, error, info, LogOnError};
+use common::{new_uuid, now_ms, now_sec, HttpStatusCode, StatusCode};
+use derive_more::Display;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::MmNumber;
+use mm2_rpc::data::legacy::{Mm2RpcResult, OrderType, SellBuyRequest, SellBuyResponse, TakerAction};
+use serde_json as json;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// How often (in seconds) the trigger conditions are checked.
+const CONDITIONAL_ORDERS_CHECK_INTERVAL: f64 = 10.;
+/// The triggered order is not placed anymore after this number of failed attempts.
+const MAX_PLACEMENT_ATTEMPTS: u32 = 5;
+
+pub type ConditionalOrderResult<T> = Result<T, MmError<ConditionalOrderError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum ConditionalOrderError {
+    #[display(fmt = "Base and rel must be different coins")]
+    BaseEqualRel,
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Coin {} is wallet only", coin)]
+    CoinIsWalletOnly { coin: String },
+    #[display(fmt = "Invalid request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Conditional order {} is not found", uuid)]
+    NoSuchOrder { uuid: Uuid },
+    #[display(fmt = "Storage error: {}", _0)]
+    StorageError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    InternalError(String),
+}
+
+impl HttpStatusCode for ConditionalOrderError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ConditionalOrderError::BaseEqualRel
+            | ConditionalOrderError::NoSuchCoin { .. }
+            | ConditionalOrderError::CoinIsWalletOnly { .. }
+            | ConditionalOrderError::InvalidRequest(_)
+            | ConditionalOrderError::NoSuchOrder { .. } => StatusCode::BAD_REQUEST,
+            ConditionalOrderError::StorageError(_) | ConditionalOrderError::InternalError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionalOrderKind {
+    /// Limits the loss: a sell order is triggered when the price falls to the trigger price,
+    /// a buy order is triggered when the price rises to the trigger price.
+    StopLoss,
+    /// Locks in the profit: a sell order is triggered when the price rises to the trigger price,
+    /// a buy order is triggered when the price falls to the trigger price.
+    TakeProfit,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerPriceSource {
+    /// The `base/rel` price is calculated from the USD prices of the price service.
+    /// [`KMD_PRICE_ENDPOINT`] is used if `url` is not set.
+    PriceService { url: Option<String> },
+    /// The middle between the best ask and the best bid of the `base/rel` orderbook.
+    OrderbookMid,
+}
+
+/// Whether the triggered order is placed as a maker or a taker order.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderPlacement {
+    Maker,
+    Taker,
+}
+
+impl Default for OrderPlacement {
+    fn default() -> Self { OrderPlacement::Taker }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConditionalOrder {
+    pub uuid: Uuid,
+    pub base: String,
+    pub rel: String,
+    pub action: TakerAction,
+    pub kind: ConditionalOrderKind,
+    /// The `base/rel` market price triggering the order.
+    pub trigger_price: MmNumber,
+    pub price_source: TriggerPriceSource,
+    /// The price of the order placed on trigger.
+    pub price: MmNumber,
+    /// The base coin volume of the order placed on trigger.
+    pub volume: MmNumber,
+    pub min_volume: Option<MmNumber>,
+    pub placement: OrderPlacement,
+    pub order_type: OrderType,
+    pub created_at: u64,
+    /// The number of the failed attempts to place the triggered order.
+    #[serde(default)]
+    pub failed_placements: u32,
+    /// The error of the last failed attempt to place the triggered order.
+    #[serde(default)]
+    pub placement_error: Option<String>,
+}
+
+impl ConditionalOrder {
+    /// The order is failed once [`MAX_PLACEMENT_ATTEMPTS`] attempts to place it fail.
+    /// Failed orders are kept until they are cancelled, so that the error can be seen in `my_conditional_orders`.
+    pub fn is_failed(&self) -> bool { self.failed_placements >= MAX_PLACEMENT_ATTEMPTS }
+
+    fn is_expired(&self, now: u64) -> bool {
+        match self.order_type {
+            OrderType::GoodTillTime { expires_at } => expires_at <= now,
+            _ => false,
+        }
+    }
+
+    fn placement_failed(&mut self, error: String) {
+        self.failed_placements += 1;
+        self.placement_error = Some(error);
+    }
+
+    /// Checks if the trigger condition is met at the given `base/rel` market price.
+    fn is_triggered(&self, market_price: &MmNumber) -> bool {
+        let triggers_on_fall = match (&self.action, self.kind) {
+            (TakerAction::Sell, ConditionalOrderKind::StopLoss)
+            | (TakerAction::Buy, ConditionalOrderKind::TakeProfit) => true,
+            (TakerAction::Sell, ConditionalOrderKind::TakeProfit)
+            | (TakerAction::Buy, ConditionalOrderKind::StopLoss) => false,
+        };
+        if triggers_on_fall {
+            market_price <= &self.trigger_price
+        } else {
+            market_price >= &self.trigger_price
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateConditionalOrderRequest {
+    base: String,
+    rel: String,
+    action: TakerAction,
+    kind: ConditionalOrderKind,
+    trigger_price: MmNumber,
+    price_source: TriggerPriceSource,
+    price: MmNumber,
+    volume: MmNumber,
+    min_volume: Option<MmNumber>,
+    #[serde(default)]
+    placement: OrderPlacement,
+    #[serde(default)]
+    order_type: OrderType,
+}
+
+pub async fn create_conditional_order(
+    ctx: MmArc,
+    req: CreateConditionalOrderRequest,
+) -> ConditionalOrderResult<ConditionalOrder> {
+    if req.base == req.rel {
+        return MmError::err(ConditionalOrderError::BaseEqualRel);
+    }
+    let zero = MmNumber::from(0);
+    if req.trigger_price <= zero || req.price <= zero || req.volume <= zero {
+        let error = "'trigger_price', 'price' and 'volume' must be greater than 0".to_owned();
+        return MmError::err(ConditionalOrderError::InvalidRequest(error));
+    }
+    match (req.placement, &req.order_type) {
+        (OrderPlacement::Maker, OrderType::FillOrKill | OrderType::ImmediateOrCancel) => {
+            let error = format!("{:?} order type is not supported for maker orders", req.order_type);
+            return MmError::err(ConditionalOrderError::InvalidRequest(error));
+        },
+        (_, OrderType::GoodTillTime { expires_at }) if *expires_at <= now_sec() => {
+            let error = format!("Expiration time {} is in the past", expires_at);
+            return MmError::err(ConditionalOrderError::InvalidRequest(error));
+        },
+        _ => (),
+    }
+
+    for ticker in [&req.base, &req.rel] {
+        let coin = lp_coinfind(&ctx, ticker)
+            .await
+            .map_to_mm(ConditionalOrderError::InternalError)?
+            .or_mm_err(|| ConditionalOrderError::NoSuchCoin { coin: ticker.clone() })?;
+        if coin.wallet_only(&ctx) {
+            return MmError::err(ConditionalOrderError::CoinIsWalletOnly { coin: ticker.clone() });
+        }
+    }
+
+    let order = ConditionalOrder {
+        uuid: new_uuid(),
+        base: req.base,
+        rel: req.rel,
+        action: req.action,
+        kind: req.kind,
+        trigger_price: req.trigger_price,
+        price_source: req.price_source,
+        price: req.price,
+        volume: req.volume,
+        min_volume: req.min_volume,
+        placement: req.placement,
+        order_type: req.order_type,
+        created_at: now_ms(),
+        failed_placements: 0,
+        placement_error: None,
+    };
+
+    if order.price_source == TriggerPriceSource::OrderbookMid {
+        subscribe_to_pair_orderbook(&ctx, &order)
+            .await
+            .map_to_mm(ConditionalOrderError::InternalError)?;
+    }
+
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(ConditionalOrderError::InternalError)?;
+    MyOrdersStorage::new(ctx.clone())
+        .save_conditional_order(&order)
+        .await
+        .mm_err(|e| ConditionalOrderError::StorageError(e.to_string()))?;
+    ordermatch_ctx
+        .my_conditional_orders
+        .lock()
+        .await
+        .insert(order.uuid, order.clone());
+    Ok(order)
+}
+
+#[derive(Deserialize)]
+pub struct MyConditionalOrdersRequest {}
+
+#[derive(Serialize)]
+pub struct MyConditionalOrdersResponse {
+    orders: Vec<ConditionalOrder>,
+}
+
+pub async fn my_conditional_orders(
+    ctx: MmArc,
+    _req: MyConditionalOrdersRequest,
+) -> ConditionalOrderResult<MyConditionalOrdersResponse> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(ConditionalOrderError::InternalError)?;
+    let mut orders: Vec<_> = ordermatch_ctx
+        .my_conditional_orders
+        .lock()
+        .await
+        .values()
+        .cloned()
+        .collect();
+    orders.sort_by_key(|order| order.created_at);
+    Ok(MyConditionalOrdersResponse { orders })
+}
+
+#[derive(Deserialize)]
+pub struct CancelConditionalOrderRequest {
+    uuid: Uuid,
+}
+
+#[derive(Serialize)]
+pub struct CancelConditionalOrderResponse {
+    uuid: Uuid,
+}
+
+pub async fn cancel_conditional_order(
+    ctx: MmArc,
+    req: CancelConditionalOrderRequest,
+) -> ConditionalOrderResult<CancelConditionalOrderResponse> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(ConditionalOrderError::InternalError)?;
+    ordermatch_ctx
+        .my_conditional_orders
+        .lock()
+        .await
+        .remove(&req.uuid)
+        .or_mm_err(|| ConditionalOrderError::NoSuchOrder { uuid: req.uuid })?;
+    MyOrdersStorage::new(ctx)
+        .delete_conditional_order(req.uuid)
+        .await
+        .mm_err(|e| ConditionalOrderError::StorageError(e.to_string()))?;
+    Ok(CancelConditionalOrderResponse { uuid: req.uuid })
+}
+
+/// Loads the saved conditional orders on the start.
+/// Returns the coins required by the orders.
+pub(super) async fn conditional_orders_kick_start(
+    ctx: &MmArc,
+    ordermatch_ctx: &OrdermatchContext,
+) -> Result<Vec<String>, String> {
+    let saved_orders = try_s!(MyOrdersStorage::new(ctx.clone()).load_conditional_orders().await);
+    let mut coins = Vec::with_capacity(saved_orders.len() * 2);
+    let mut conditional_orders = ordermatch_ctx.my_conditional_orders.lock().await;
+    for order in saved_orders {
+        coins.push(order.base.clone());
+        coins.push(order.rel.clone());
+        conditional_orders.insert(order.uuid, order);
+    }
+    Ok(coins)
+}
+
+pub async fn conditional_orders_loop(ctx: MmArc) {
+    loop {
+        if ctx.is_stopping() {
+            break;
+        }
+        let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).unwrap();
+        process_conditional_orders(&ctx, &ordermatch_ctx).await;
+        Timer::sleep(CONDITIONAL_ORDERS_CHECK_INTERVAL).await;
+    }
+}
+
+/// Removes the expired orders from the `orders` and returns them.
+fn take_expired_orders(orders: &mut HashMap<Uuid, ConditionalOrder>, now: u64) -> Vec<ConditionalOrder> {
+    let expired: Vec<_> = orders
+        .values()
+        .filter(|order| order.is_expired(now))
+        .map(|order| order.uuid)
+        .collect();
+    expired.iter().filter_map(|uuid| orders.remove(uuid)).collect()
+}
+
+async fn process_conditional_orders(ctx: &MmArc, ordermatch_ctx: &OrdermatchContext) {
+    let (expired, orders) = {
+        let mut conditional_orders = ordermatch_ctx.my_conditional_orders.lock().await;
+        let expired = take_expired_orders(&mut conditional_orders, now_sec());
+        let orders: Vec<_> = conditional_orders
+            .values()
+            .filter(|order| !order.is_failed())
+            .cloned()
+            .collect();
+        (expired, orders)
+    };
+    for order in expired {
+        info!("Conditional order {} is expired before it's triggered", order.uuid);
+        MyOrdersStorage::new(ctx.clone())
+            .delete_conditional_order(order.uuid)
+            .await
+            .error_log_with_msg("!delete_conditional_order");
+    }
+    // The price service responses are shared between the orders within one iteration.
+    let mut price_tickers: HashMap<String, Option<TickerInfosRegistry>> = HashMap::new();
+
+    for mut order in orders {
+        let market_price = match &order.price_source {
+            TriggerPriceSource::PriceService { url } => {
+                let url = url.as_deref().unwrap_or(KMD_PRICE_ENDPOINT);
+                if !price_tickers.contains_key(url) {
+                    let tickers = fetch_price_tickers(url)
+                        .await
+                        .map_err(|e| error!("Error fetching the prices from {}: {:?}", url, e))
+                        .ok();
+                    price_tickers.insert(url.to_owned(), tickers);
+                }
+                price_tickers[url]
+                    .as_ref()
+                    .and_then(|tickers| tickers.get_cex_rates(&order.base, &order.rel))
+                    .map(|rates| rates.price)
+            },
+            TriggerPriceSource::OrderbookMid => {
+                subscribe_to_pair_orderbook(ctx, &order).await.error_log();
+                let orderbook = ordermatch_ctx.orderbook.lock();
+                orderbook_mid_price(
+                    &orderbook,
+                    &ordermatch_ctx.orderbook_ticker_bypass(&order.base),
+                    &ordermatch_ctx.orderbook_ticker_bypass(&order.rel),
+                )
+            },
+        };
+
+        let market_price = match market_price {
+            Some(price) if price > MmNumber::from(0) => price,
+            _ => {
+                debug!(
+                    "No {}/{} market price for the conditional order {}",
+                    order.base, order.rel, order.uuid
+                );
+                continue;
+            },
+        };
+        if !order.is_triggered(&market_price) {
+            continue;
+        }
+
+        // Remove the order before placing to avoid races with `cancel_conditional_order`.
+        if ordermatch_ctx
+            .my_conditional_orders
+            .lock()
+            .await
+            .remove(&order.uuid)
+            .is_none()
+        {
+            continue;
+        }
+        info!(
+            "Conditional order {} is triggered at the {}/{} price {}",
+            order.uuid,
+            order.base,
+            order.rel,
+            market_price.to_decimal()
+        );
+
+        match place_triggered_order(ctx, &order).await {
+            Ok(new_order_uuid) => {
+                info!("Conditional order {} is placed as {}", order.uuid, new_order_uuid);
+                MyOrdersStorage::new(ctx.clone())
+                    .delete_conditional_order(order.uuid)
+                    .await
+                    .error_log_with_msg("!delete_conditional_order");
+            },
+            Err(e) => {
+                order.placement_failed(e);
+                if order.is_failed() {
+                    error!(
+                        "Conditional order {} is failed after {} placement attempts: {:?}",
+                        order.uuid, order.failed_placements, order.placement_error
+                    );
+                } else {
+                    // Keep the order to try again on the next iteration.
+                    error!(
+                        "Error placing the triggered conditional order {}: {:?}",
+                        order.uuid, order.placement_error
+                    );
+                }
+                MyOrdersStorage::new(ctx.clone())
+                    .save_conditional_order(&order)
+                    .await
+                    .error_log_with_msg("!save_conditional_order");
+                ordermatch_ctx
+                    .my_conditional_orders
+                    .lock()
+                    .await
+                    .insert(order.uuid, order);
+            },
+        }
+    }
+}
+
+/// Converts the triggered order to a regular order and returns the uuid of the new order.
+async fn place_triggered_order(ctx: &MmArc, order: &ConditionalOrder) -> Result<Uuid, String> {
+    match order.placement {
+        OrderPlacement::Maker => {
+            let maker_order = create_maker_order(ctx, maker_order_request(order)).await?;
+            Ok(maker_order.uuid)
+        },
+        OrderPlacement::Taker => {
+            let req = taker_order_request(order);
+            let res = match order.action {
+                TakerAction::Buy => lp_buy(ctx, req).await?,
+                TakerAction::Sell => lp_sell(ctx, req).await?,
+            };
+            let res: Mm2RpcResult<SellBuyResponse> = try_s!(json::from_slice(&res));
+            Ok(res.result.request.uuid)
+        },
+    }
+}
+
+/// Maker orders are always "sell", so "buy" is placed as "sell" of the reversed pair.
+fn maker_order_request(order: &ConditionalOrder) -> SetPriceReq {
+    let (base, rel, price, volume, min_volume) = match order.action {
+        TakerAction::Sell => (
+            order.base.clone(),
+            order.rel.clone(),
+            order.price.clone(),
+            order.volume.clone(),
+            order.min_volume.clone(),
+        ),
+        TakerAction::Buy => (
+            order.rel.clone(),
+            order.base.clone(),
+            MmNumber::from(1) / order.price.clone(),
+            &order.volume * &order.price,
+            order.min_volume.as_ref().map(|min_volume| min_volume * &order.price),
+        ),
+    };
+    SetPriceReq {
+        base,
+        rel,
+        price,
+        max: false,
+        volume,
+        min_volume,
+        cancel_previous: false,
+        base_confs: None,
+        base_nota: None,
+        rel_confs: None,
+        rel_nota: None,
+        save_in_history: true,
+        order_type: order.order_type.clone(),
+        visible_volume: None,
+        min_taker_reputation: None,
+    }
+}
+
+fn taker_order_request(order: &ConditionalOrder) -> SellBuyRequest {
+    let method = match order.action {
+        TakerAction::Buy => "buy",
+        TakerAction::Sell => "sell",
+    };
+    SellBuyRequest {
+        base: order.base.clone(),
+        rel: order.rel.clone(),
+        price: order.price.clone(),
+        volume: order.volume.clone(),
+        timeout: None,
+        duration: None,
+        method: method.to_owned(),
+        gui: None,
+        dest_pub_key: Default::default(),
+        match_by: Default::default(),
+        min_maker_reputation: None,
+        order_type: order.order_type.clone(),
+        base_confs: None,
+        base_nota: None,
+        rel_confs: None,
+        rel_nota: None,
+        min_volume: order.min_volume.clone(),
+        save_in_history: true,
+    }
+}
+
+async fn subscribe_to_pair_orderbook(ctx: &MmArc, order: &ConditionalOrder) -> Result<(), String> {
+    let ordermatch_ctx = try_s!(OrdermatchContext::from_ctx(ctx));
+    let base = ordermatch_ctx.orderbook_ticker_bypass(&order.base);
+    let rel = ordermatch_ctx.orderbook_ticker_bypass(&order.rel);
+    let request_orderbook = true;
+    subscribe_to_orderbook_topic(ctx, &base, &rel, request_orderbook).await
+}
+
+/// Returns the middle between the best ask and the best bid of the `base/rel` orderbook.
+/// Both sides must have orders.
+fn orderbook_mid_price(orderbook: &Orderbook, base: &str, rel: &str) -> Option<MmNumber> {
+    let best_ask = orderbook
+        .ordered
+        .get(&(base.to_owned(), rel.to_owned()))
+        .and_then(|asks| asks.iter().next())
+        .map(|ask| ask.price.clone())?;
+    // The bids are the `rel/base` asks, so the best bid is the inverted lowest `rel/base` price.
+    let best_bid = orderbook
+        .ordered
+        .get(&(rel.to_owned(), base.to_owned()))
+        .and_then(|bids| bids.iter().next())
+        .map(|bid| MmNumber::from(1) / bid.price.clone())?;
+    Some((best_ask + best_bid) / MmNumber::from(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditional_order(action: TakerAction, kind: ConditionalOrderKind) -> ConditionalOrder {
+        ConditionalOrder {
+            uuid: new_uuid(),
+            base: "RICK".to_owned(),
+            rel: "MORTY".to_owned(),
+            action,
+            kind,
+            trigger_price: 10.into(),
+            price_source: TriggerPriceSource::OrderbookMid,
+            price: 10.into(),
+            volume: 1.into(),
+            min_volume: None,
+            placement: OrderPlacement::Taker,
+            order_type: OrderType::GoodTillCancelled,
+            created_at: now_ms(),
+            failed_placements: 0,
+            placement_error: None,
+        }
+    }
+
+    #[test]
+    fn test_conditional_order_is_triggered() {
+        let sell_stop_loss = conditional_order(TakerAction::Sell, ConditionalOrderKind::StopLoss);
+        assert!(sell_stop_loss.is_triggered(&9.into()));
+        assert!(sell_stop_loss.is_triggered(&10.into()));
+        assert!(!sell_stop_loss.is_triggered(&11.into()));
+
+        let sell_take_profit = conditional_order(TakerAction::Sell, ConditionalOrderKind::TakeProfit);
+        assert!(!sell_take_profit.is_triggered(&9.into()));
+        assert!(sell_take_profit.is_triggered(&11.into()));
+
+        let buy_stop_loss = conditional_order(TakerAction::Buy, ConditionalOrderKind::StopLoss);
+        assert!(!buy_stop_loss.is_triggered(&9.into()));
+        assert!(buy_stop_loss.is_triggered(&11.into()));
+
+        let buy_take_profit = conditional_order(TakerAction::Buy, ConditionalOrderKind::TakeProfit);
+        assert!(buy_take_profit.is_triggered(&9.into()));
+        assert!(!buy_take_profit.is_triggered(&11.into()));
+    }
+
+    #[test]
+    fn test_triggered_order_placement_requests() {
+        let mut sell = conditional_order(TakerAction::Sell, ConditionalOrderKind::StopLoss);
+        sell.price = 4.into();
+        sell.volume = 2.into();
+        sell.min_volume = Some(1.into());
+        sell.order_type = OrderType::FillOrKill;
+
+        let taker = taker_order_request(&sell);
+        assert_eq!((taker.base.as_str(), taker.rel.as_str()), ("RICK", "MORTY"));
+        assert_eq!(taker.method, "sell");
+        assert_eq!(taker.price, 4.into());
+        assert_eq!(taker.volume, 2.into());
+        assert_eq!(taker.min_volume, Some(1.into()));
+        assert_eq!(taker.order_type, OrderType::FillOrKill);
+
+        let maker = maker_order_request(&sell);
+        assert_eq!((maker.base.as_str(), maker.rel.as_str()), ("RICK", "MORTY"));
+        assert_eq!(maker.price, 4.into());
+        assert_eq!(maker.volume, 2.into());
+        assert_eq!(maker.min_volume, Some(1.into()));
+
+        // Buying 2 RICK at 4 MORTY is placed as selling 8 MORTY at 1/4 RICK as a maker.
+        let mut buy = sell.clone();
+        buy.action = TakerAction::Buy;
+        buy.order_type = OrderType::GoodTillCancelled;
+
+        let taker = taker_order_request(&buy);
+        assert_eq!((taker.base.as_str(), taker.rel.as_str()), ("RICK", "MORTY"));
+        assert_eq!(taker.method, "buy");
+
+        let maker = maker_order_request(&buy);
+        assert_eq!((maker.base.as_str(), maker.rel.as_str()), ("MORTY", "RICK"));
+        assert_eq!(maker.price, MmNumber::from((1, 4)));
+        assert_eq!(maker.volume, 8.into());
+        assert_eq!(maker.min_volume, Some(4.into()));
+        assert_eq!(maker.order_type, OrderType::GoodTillCancelled);
+    }
+
+    #[test]
+    fn test_take_expired_orders() {
+        let now = now_sec();
+        let mut expired = conditional_order(TakerAction::Sell, ConditionalOrderKind::StopLoss);
+        expired.order_type = OrderType::GoodTillTime { expires_at: now - 1 };
+        let mut expires_now = conditional_order(TakerAction::Buy, ConditionalOrderKind::StopLoss);
+        expires_now.order_type = OrderType::GoodTillTime { expires_at: now };
+        let mut not_expired = conditional_order(TakerAction::Sell, ConditionalOrderKind::TakeProfit);
+        not_expired.order_type = OrderType::GoodTillTime { expires_at: now + 60 };
+        let good_till_cancelled = conditional_order(TakerAction::Buy, ConditionalOrderKind::TakeProfit);
+
+        let mut orders: HashMap<_, _> = vec![
+            expired.clone(),
+            expires_now.clone(),
+            not_expired.clone(),
+            good_till_cancelled.clone(),
+        ]
+        .into_iter()
+        .map(|order| (order.uuid, order))
+        .collect();
+
+        let mut taken: Vec<_> = take_expired_orders(&mut orders, now)
+            .into_iter()
+            .map(|order| order.uuid)
+            .collect();
+        taken.sort();
+        let mut expected = vec![expired.uuid, expires_now.uuid];
+        expected.sort();
+        assert_eq!(taken, expected);
+
+        assert_eq!(orders.len(), 2);
+        assert!(orders.contains_key(&not_expired.uuid));
+        assert!(orders.contains_key(&good_till_cancelled.uuid));
+    }
+
+    #[test]
+    fn test_placement_attempts_are_capped() {
+        let mut order = conditional_order(TakerAction::Sell, ConditionalOrderKind::StopLoss);
+        for attempt in 1..MAX_PLACEMENT_ATTEMPTS {
+            order.placement_failed(format!("error {}", attempt));
+            assert!(!order.is_failed());
+        }
+        order.placement_failed("last error".to_owned());
+        assert!(order.is_failed());
+        assert_eq!(order.failed_placements, MAX_PLACEMENT_ATTEMPTS);
+        assert_eq!(order.placement_error.as_deref(), Some("last error"));
+
+        // The orders saved before the attempts were counted are not failed.
+        let mut json = json::to_value(&order).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        fields.remove("failed_placements");
+        fields.remove("placement_error");
+        let saved: ConditionalOrder = json::from_value(json).unwrap();
+        assert!(!saved.is_failed());
+        assert_eq!(saved.placement_error, None);
+    }
+}
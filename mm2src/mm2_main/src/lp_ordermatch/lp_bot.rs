@@ -9,20 +9,23 @@ use derive_more::Display;
 use futures::lock::Mutex as AsyncMutex;
 use mm2_core::{event_dispatcher::{EventListener, EventUniqueId},
                mm_ctx::{from_ctx, MmArc}};
-use mm2_number::MmNumber;
+use mm2_number::{BigDecimal, MmNumber};
 #[cfg(test)] use mocktopus::macros::*;
 use std::any::TypeId;
 use std::ops::Deref;
 use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
 
+#[path = "price_oracle.rs"] mod price_oracle;
 #[path = "simple_market_maker.rs"] mod simple_market_maker_bot;
 use crate::mm2::lp_dispatcher::{LpEvents, StopCtxEvent};
 use crate::mm2::lp_message_service::{MessageServiceContext, MAKER_BOT_ROOM_ID};
 use crate::mm2::lp_ordermatch::lp_bot::simple_market_maker_bot::{tear_down_bot, BOT_DEFAULT_REFRESH_RATE,
                                                                  PRECISION_FOR_NOTIFICATION};
 use crate::mm2::lp_swap::MakerSwapStatusChanged;
-pub use simple_market_maker_bot::{start_simple_market_maker_bot, stop_simple_market_maker_bot,
-                                  StartSimpleMakerBotRequest, KMD_PRICE_ENDPOINT};
+use price_oracle::PriceOracleRegistry;
+pub use simple_market_maker_bot::{get_simple_market_maker_bot_status, start_simple_market_maker_bot,
+                                  stop_simple_market_maker_bot, StartSimpleMakerBotRequest, KMD_PRICE_ENDPOINT};
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 #[path = "simple_market_maker_tests.rs"]
@@ -93,7 +96,8 @@ impl From<TradingBotStarted> for TradingBotEvent {
 pub struct RunningState {
     trading_bot_cfg: SimpleMakerBotRegistry,
     bot_refresh_rate: f64,
-    price_url: String,
+    price_oracle: Arc<PriceOracleRegistry>,
+    inventory_cfg: CoinInventoryRegistry,
 }
 
 pub struct StoppingState {
@@ -137,6 +141,35 @@ pub enum VolumeSettings {
     Usd(MmNumber),
 }
 
+/// How the total volume of a ladder is distributed between the levels.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum VolumeDistribution {
+    /// Every level gets the same volume.
+    Flat,
+    /// The n-th level (starting from 1) gets n parts of the volume.
+    Linear,
+    /// The volume of every next level is multiplied by the given factor.
+    Exponential(MmNumber),
+}
+
+impl Default for VolumeDistribution {
+    fn default() -> Self { VolumeDistribution::Flat }
+}
+
+/// Places several orders at the increasing price levels instead of a single order.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LadderCfg {
+    /// The number of price levels.
+    pub levels: usize,
+    /// The first level is placed at `price * spread`, every next level is placed at the previous level price
+    /// multiplied by `level_spread`, e.g. `1.01` places the levels 1% apart.
+    pub level_spread: MmNumber,
+    /// How the pair volume is distributed between the levels.
+    #[serde(default)]
+    pub volume_distribution: VolumeDistribution,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SimpleCoinMarketMakerCfg {
     pub base: String,
@@ -155,11 +188,81 @@ pub struct SimpleCoinMarketMakerCfg {
     pub min_base_price: Option<MmNumber>,
     pub min_rel_price: Option<MmNumber>,
     pub min_pair_price: Option<MmNumber>,
+    /// The names of the `price_oracle` providers used for the pair, all the providers are used by default.
+    pub price_providers: Option<Vec<String>>,
+    /// Places a ladder of orders for the pair instead of a single order.
+    pub ladder: Option<LadderCfg>,
+    /// Only the takers with at least this reputation score are matched by the orders of the pair.
+    pub min_taker_reputation: Option<MmNumber>,
+}
+
+pub type CoinInventoryRegistry = HashMap<String, CoinInventoryCfg>;
+
+/// The inventory controls of a coin traded by the bot.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CoinInventoryCfg {
+    /// The target share of the coin in the USD value of the bot inventory, from 0 to 1.
+    pub target_ratio: Option<MmNumber>,
+    /// How strongly the prices are skewed when the coin share deviates from `target_ratio`, from 0 to 1.
+    /// The price of the orders selling the coin is multiplied by `1 - skew_factor * (share - target_ratio)`,
+    /// the price of the orders buying the coin is multiplied by `1 + skew_factor * (share - target_ratio)`.
+    pub skew_factor: Option<MmNumber>,
+    /// The orders selling the coin never bring its balance below this value.
+    pub min_balance: Option<MmNumber>,
+    /// The orders buying the coin never bring its balance above this value.
+    pub max_balance: Option<MmNumber>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CoinInventoryStatus {
+    pub balance: BigDecimal,
+    pub usd_price: Option<BigDecimal>,
+    pub usd_value: Option<BigDecimal>,
+    /// The share of the coin in the USD value of the bot inventory.
+    pub share: Option<BigDecimal>,
+    pub target_ratio: Option<BigDecimal>,
+}
+
+/// The profit and loss of the bot compared to holding the balances it started with.
+#[derive(Clone, Debug, Serialize)]
+pub struct BotPnl {
+    /// The USD value of the initial balances at the initial prices.
+    pub initial_value_usd: BigDecimal,
+    /// The USD value of the initial balances at the current prices.
+    pub hold_value_usd: BigDecimal,
+    /// The USD value of the current balances at the current prices.
+    pub current_value_usd: BigDecimal,
+    /// `current_value_usd - hold_value_usd`.
+    pub pnl_usd: BigDecimal,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PairStatus {
+    pub enabled: bool,
+    /// The market price of the pair.
+    pub price: Option<BigDecimal>,
+    /// The price multiplier applied due to the inventory deviation.
+    pub skew: BigDecimal,
+    pub orders: Vec<Uuid>,
+    /// Whether all the orders of the pair have been placed or updated during the last bot cycle.
+    pub last_cycle_succeeded: bool,
+}
+
+/// The bot state updated every bot cycle.
+#[derive(Default)]
+pub struct BotStatus {
+    /// The balances and USD prices of the first bot cycle used as the PnL baseline.
+    initial_inventory: Option<HashMap<String, (MmNumber, MmNumber)>>,
+    inventory: HashMap<String, CoinInventoryStatus>,
+    pnl: Option<BotPnl>,
+    pairs: HashMap<String, PairStatus>,
+    updated_at: Option<u64>,
 }
 
 #[derive(Default)]
 pub struct TradingBotContext {
     trading_bot_states: AsyncMutex<TradingBotState>,
+    bot_status: AsyncMutex<BotStatus>,
 }
 
 impl TradingBotContext {
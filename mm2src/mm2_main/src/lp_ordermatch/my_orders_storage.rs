@@ -1,3 +1,5 @@
+use super::conditional_orders::ConditionalOrder;
+use super::order_events::{broadcast_order_status_event, OrderStatusEvent};
 use super::{MakerOrder, MakerOrderCancellationReason, MyOrdersFilter, Order, RecentOrdersSelectResult, TakerOrder,
             TakerOrderCancellationReason};
 use async_trait::async_trait;
@@ -36,12 +38,20 @@ pub enum MyOrdersError {
 }
 
 pub async fn save_my_new_maker_order(ctx: MmArc, order: &MakerOrder) -> MyOrdersResult<()> {
-    let storage = MyOrdersStorage::new(ctx);
+    let result = persist_my_new_maker_order(&ctx, order).await;
+    // The order is broadcast only after it's persisted, so the clients can't see an order that is lost on restart.
+    broadcast_order_status_event(&ctx, OrderStatusEvent::Created(Order::Maker(order.clone())));
+    result
+}
+
+/// Saves the new maker order without broadcasting [`OrderStatusEvent::Created`].
+/// The caller broadcasts the event once the order can't be rolled back anymore.
+pub async fn persist_my_new_maker_order(ctx: &MmArc, order: &MakerOrder) -> MyOrdersResult<()> {
+    let storage = MyOrdersStorage::new(ctx.clone());
     storage
         .save_new_active_maker_order(order)
         .await
         .error_log_with_msg("!save_new_active_maker_order");
-
     if order.save_in_history {
         storage.save_maker_order_in_filtering_history(order).await?;
     }
@@ -49,11 +59,12 @@ pub async fn save_my_new_maker_order(ctx: MmArc, order: &MakerOrder) -> MyOrders
 }
 
 pub async fn save_my_new_taker_order(ctx: MmArc, order: &TakerOrder) -> MyOrdersResult<()> {
-    let storage = MyOrdersStorage::new(ctx);
+    let storage = MyOrdersStorage::new(ctx.clone());
     storage
         .save_new_active_taker_order(order)
         .await
         .error_log_with_msg("!save_new_active_taker_order");
+    broadcast_order_status_event(&ctx, OrderStatusEvent::Created(Order::Taker(order.clone())));
 
     if order.save_in_history {
         storage.save_taker_order_in_filtering_history(order).await?;
@@ -77,6 +88,7 @@ pub fn delete_my_taker_order(ctx: MmArc, order: TakerOrder, reason: TakerOrderCa
         let uuid = order.request.uuid;
         let save_in_history = order.save_in_history;
 
+        broadcast_order_status_event(&ctx, OrderStatusEvent::taker_order_deleted(uuid, &reason));
         let storage = MyOrdersStorage::new(ctx);
         storage
             .delete_active_taker_order(uuid)
@@ -113,6 +125,7 @@ pub fn delete_my_maker_order(ctx: MmArc, order: MakerOrder, reason: MakerOrderCa
         let uuid = order_to_save.uuid;
         let save_in_history = order_to_save.save_in_history;
 
+        broadcast_order_status_event(&ctx, OrderStatusEvent::maker_order_deleted(uuid, &reason));
         let storage = MyOrdersStorage::new(ctx);
         if order_to_save.was_updated() {
             if let Ok(order_from_file) = storage.load_active_maker_order(order_to_save.uuid).await {
@@ -202,13 +215,23 @@ pub trait MyOrdersFilteringHistory {
     async fn update_was_taker_in_filtering_history(&self, uuid: Uuid) -> MyOrdersResult<()>;
 }
 
+#[async_trait]
+pub trait MyConditionalOrders {
+    async fn load_conditional_orders(&self) -> MyOrdersResult<Vec<ConditionalOrder>>;
+
+    async fn save_conditional_order(&self, order: &ConditionalOrder) -> MyOrdersResult<()>;
+
+    async fn delete_conditional_order(&self, uuid: Uuid) -> MyOrdersResult<()>;
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 mod native_impl {
     use super::*;
     use crate::mm2::database::my_orders::{insert_maker_order, insert_taker_order, select_orders_by_filter,
                                           select_status_by_uuid, update_maker_order, update_order_status,
                                           update_was_taker};
-    use crate::mm2::lp_ordermatch::{my_maker_order_file_path, my_maker_orders_dir, my_order_history_file_path,
+    use crate::mm2::lp_ordermatch::{my_conditional_order_file_path, my_conditional_orders_dir,
+                                    my_maker_order_file_path, my_maker_orders_dir, my_order_history_file_path,
                                     my_taker_order_file_path, my_taker_orders_dir};
     use mm2_io::fs::{read_dir_json, read_json, remove_file_async, write_json, FsJsonError};
 
@@ -345,14 +368,36 @@ mod native_impl {
             update_was_taker(&self.ctx, uuid).map_to_mm(|e| MyOrdersError::ErrorSaving(e.to_string()))
         }
     }
+
+    #[async_trait]
+    impl MyConditionalOrders for MyOrdersStorage {
+        async fn load_conditional_orders(&self) -> MyOrdersResult<Vec<ConditionalOrder>> {
+            let dir_path = my_conditional_orders_dir(&self.ctx);
+            Ok(read_dir_json(&dir_path).await?)
+        }
+
+        async fn save_conditional_order(&self, order: &ConditionalOrder) -> MyOrdersResult<()> {
+            let path = my_conditional_order_file_path(&self.ctx, &order.uuid);
+            write_json(order, &path, USE_TMP_FILE).await?;
+            Ok(())
+        }
+
+        async fn delete_conditional_order(&self, uuid: Uuid) -> MyOrdersResult<()> {
+            let path = my_conditional_order_file_path(&self.ctx, &uuid);
+            remove_file_async(&path)
+                .await
+                .mm_err(|e| MyOrdersError::ErrorSaving(e.to_string()))?;
+            Ok(())
+        }
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
 mod wasm_impl {
     use super::*;
     use crate::mm2::lp_ordermatch::ordermatch_wasm_db::{DbTransactionError, InitDbError, MyActiveMakerOrdersTable,
-                                                        MyActiveTakerOrdersTable, MyFilteringHistoryOrdersTable,
-                                                        MyHistoryOrdersTable};
+                                                        MyActiveTakerOrdersTable, MyConditionalOrdersTable,
+                                                        MyFilteringHistoryOrdersTable, MyHistoryOrdersTable};
     use crate::mm2::lp_ordermatch::OrdermatchContext;
     use common::log::warn;
     use mm2_rpc::data::legacy::TakerAction;
@@ -626,6 +671,41 @@ mod wasm_impl {
         }
     }
 
+    #[async_trait]
+    impl MyConditionalOrders for MyOrdersStorage {
+        async fn load_conditional_orders(&self) -> MyOrdersResult<Vec<ConditionalOrder>> {
+            let db = self.ctx.ordermatch_db().await?;
+            let transaction = db.transaction().await?;
+            let table = transaction.table::<MyConditionalOrdersTable>().await?;
+            let conditional_orders = table.get_all_items().await?;
+            Ok(conditional_orders
+                .into_iter()
+                .map(|(_item_id, MyConditionalOrdersTable { order_payload, .. })| order_payload)
+                .collect())
+        }
+
+        async fn save_conditional_order(&self, order: &ConditionalOrder) -> MyOrdersResult<()> {
+            let db = self.ctx.ordermatch_db().await?;
+            let transaction = db.transaction().await?;
+            let table = transaction.table::<MyConditionalOrdersTable>().await?;
+
+            let item = MyConditionalOrdersTable {
+                uuid: order.uuid,
+                order_payload: order.clone(),
+            };
+            table.replace_item_by_unique_index("uuid", order.uuid, &item).await?;
+            Ok(())
+        }
+
+        async fn delete_conditional_order(&self, uuid: Uuid) -> MyOrdersResult<()> {
+            let db = self.ctx.ordermatch_db().await?;
+            let transaction = db.transaction().await?;
+            let table = transaction.table::<MyConditionalOrdersTable>().await?;
+            table.delete_item_by_unique_index("uuid", uuid).await?;
+            Ok(())
+        }
+    }
+
     pub(super) fn maker_order_to_filtering_history_item(
         order: &MakerOrder,
         status: String,
@@ -725,6 +805,9 @@ mod tests {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            expires_at: None,
+            visible_volume: None,
+            min_taker_reputation: None,
         }
     }
 
@@ -753,6 +836,7 @@ mod tests {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            min_maker_reputation: None,
         }
     }
 
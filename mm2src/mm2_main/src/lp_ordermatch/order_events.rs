@@ -0,0 +1,147 @@
+use super::{MakerOrderCancellationReason, Order, OrderForRpc, OrdermatchContext, TakerOrderCancellationReason};
+use crate::mm2::lp_event_streaming::{spawn_streamer_if_active, stream_channel_events, try_send_stream_message};
+use async_trait::async_trait;
+use common::log::error;
+use futures::channel::mpsc::Sender;
+use futures::channel::oneshot;
+use mm2_core::mm_ctx::MmArc;
+use mm2_event_stream::{behaviour::{EventBehaviour, EventInitStatus},
+                       Event, EventStreamConfiguration};
+use serde_json::Value as Json;
+use uuid::Uuid;
+
+pub type OrderStatusEventSender = Sender<OrderStatusEvent>;
+
+/// The lifecycle change of one of my orders streamed to the clients.
+pub enum OrderStatusEvent {
+    /// The order has been placed. Taker orders converted to maker ones are reported as created too.
+    Created(Order),
+    /// The order has been matched, and the swap with the given uuid is started.
+    Matched { uuid: Uuid, swap_uuid: Uuid },
+    /// The order has been removed before its volume was fully used.
+    Cancelled { uuid: Uuid, reason: String },
+    /// The whole volume of the order has been used by swaps.
+    Filled { uuid: Uuid },
+}
+
+impl OrderStatusEvent {
+    pub(super) fn maker_order_deleted(uuid: Uuid, reason: &MakerOrderCancellationReason) -> Self {
+        match reason {
+            MakerOrderCancellationReason::Fulfilled => OrderStatusEvent::Filled { uuid },
+            reason => OrderStatusEvent::Cancelled {
+                uuid,
+                reason: reason.to_string(),
+            },
+        }
+    }
+
+    pub(super) fn taker_order_deleted(uuid: Uuid, reason: &TakerOrderCancellationReason) -> Self {
+        match reason {
+            TakerOrderCancellationReason::Fulfilled => OrderStatusEvent::Filled { uuid },
+            reason => OrderStatusEvent::Cancelled {
+                uuid,
+                reason: reason.to_string(),
+            },
+        }
+    }
+
+    /// Serializes the event, so that the created orders have the same format as in `my_orders` response.
+    fn to_json(&self) -> Json {
+        match self {
+            OrderStatusEvent::Created(order) => json!({
+                "status": "Created",
+                "data": OrderForRpc::from(order),
+            }),
+            OrderStatusEvent::Matched { uuid, swap_uuid } => json!({
+                "status": "Matched",
+                "data": { "uuid": uuid, "swap_uuid": swap_uuid },
+            }),
+            OrderStatusEvent::Cancelled { uuid, reason } => json!({
+                "status": "Cancelled",
+                "data": { "uuid": uuid, "reason": reason },
+            }),
+            OrderStatusEvent::Filled { uuid } => json!({
+                "status": "Filled",
+                "data": { "uuid": uuid },
+            }),
+        }
+    }
+}
+
+/// Forwards the event to the `ORDER_STATUS` streamer if the streaming is active.
+pub(super) fn broadcast_order_status_event(ctx: &MmArc, event: OrderStatusEvent) {
+    let ordermatch_ctx = match OrdermatchContext::from_ctx(ctx) {
+        Ok(ordermatch_ctx) => ordermatch_ctx,
+        Err(e) => {
+            error!("Failed to get OrdermatchContext: {}", e);
+            return;
+        },
+    };
+
+    if let Some(tx) = &mut *ordermatch_ctx.order_status_event_tx.lock() {
+        try_send_stream_message(tx, OrderStatusStreamer::EVENT_NAME, event);
+    }
+}
+
+pub struct OrderStatusStreamer {
+    ctx: MmArc,
+}
+
+impl OrderStatusStreamer {
+    pub fn new(ctx: MmArc) -> Self { Self { ctx } }
+}
+
+#[async_trait]
+impl EventBehaviour for OrderStatusStreamer {
+    const EVENT_NAME: &'static str = "ORDER_STATUS";
+
+    async fn handle(self, _interval: f64, tx: oneshot::Sender<EventInitStatus>) {
+        let set_sender = |event_tx: OrderStatusEventSender| -> Result<(), String> {
+            let ordermatch_ctx = OrdermatchContext::from_ctx(&self.ctx)?;
+            *ordermatch_ctx.order_status_event_tx.lock() = Some(event_tx);
+            Ok(())
+        };
+        let to_events =
+            |event: OrderStatusEvent| vec![Event::new(Self::EVENT_NAME.to_string(), event.to_json().to_string())];
+        stream_channel_events(&self.ctx, tx, set_sender, to_events).await
+    }
+
+    async fn spawn_if_active(self, config: &EventStreamConfiguration) -> EventInitStatus {
+        let ctx = self.ctx.clone();
+        spawn_streamer_if_active(&ctx, self, config).await
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use common::block_on;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    #[test]
+    fn test_order_status_streamer() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let mut rx = ctx.stream_channel_controller.clone().create_channel(4);
+        let config: EventStreamConfiguration =
+            serde_json::from_value(json!({ "active_events": { "ORDER_STATUS": {} } })).unwrap();
+
+        // The events are dropped until the streamer is spawned.
+        broadcast_order_status_event(&ctx, OrderStatusEvent::Filled { uuid: Uuid::new_v4() });
+
+        let status = block_on(OrderStatusStreamer::new(ctx.clone()).spawn_if_active(&config));
+        assert!(matches!(status, EventInitStatus::Success));
+
+        let uuid = Uuid::new_v4();
+        let event = OrderStatusEvent::maker_order_deleted(uuid, &MakerOrderCancellationReason::Cancelled);
+        broadcast_order_status_event(&ctx, event);
+
+        let event = block_on(rx.recv()).unwrap();
+        assert_eq!(event.event_type(), "ORDER_STATUS");
+        let actual: Json = serde_json::from_str(event.message()).unwrap();
+        let expected = json!({
+            "status": "Cancelled",
+            "data": { "uuid": uuid, "reason": MakerOrderCancellationReason::Cancelled.to_string() },
+        });
+        assert_eq!(actual, expected);
+    }
+}
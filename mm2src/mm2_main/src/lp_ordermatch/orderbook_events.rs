@@ -0,0 +1,779 @@
+//! Streaming of the orderbook of the enabled pairs.
+//!
+//! Once a pair is enabled with the `orderbook_stream::enable` RPC, the `ORDERBOOK:{base}:{rel}` events are streamed:
+//! a snapshot of the whole orderbook first and the `add`, `update` and `remove` deltas then.
+//! Every event of the pair has a sequence number increased by one, so a client applies the deltas following
+//! the snapshot and enables the pair again to get a new snapshot if a gap is detected.
+//! A gap appears if the changes are produced faster than they are streamed, since the exceeding changes are dropped.
+//!
+//! The pair is streamed until it's disabled as many times as it has been enabled,
+//! so every client enabling the pair is expected to disable it once it's not needed.
+
+use super::orderbook_rpc::{get_tradeable_coin_conf, GetTradeableCoinConfErr};
+use super::{addr_format_from_protocol_info, mm2_internal_pubkey_hex, orderbook_address, subscribe_to_orderbook_topic,
+            OrderbookItem, OrdermatchContext, RpcOrderbookEntryV2};
+use crate::mm2::lp_event_streaming::{spawn_streamer_if_active, stream_channel_events, try_send_stream_message};
+use async_trait::async_trait;
+use common::log::{error, warn};
+use common::HttpStatusCode;
+use derive_more::Display;
+use futures::channel::mpsc::Sender;
+use futures::channel::oneshot;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_event_stream::{behaviour::{EventBehaviour, EventInitStatus},
+                       Event, EventStreamConfiguration};
+use serde_json::Value as Json;
+use std::collections::hash_map::{Entry, HashMap};
+use uuid::Uuid;
+
+pub type OrderbookStreamSender = Sender<OrderbookStreamMessage>;
+
+/// The enabled pairs affected by the orderbook change with the sequence numbers of their events.
+type PairSeqs = Vec<((String, String), u64)>;
+
+/// The orderbook change sent to the `ORDERBOOK` streamer.
+pub enum OrderbookStreamMessage {
+    /// The pair is enabled, the orders are taken at the moment of enabling.
+    Snapshot {
+        base: String,
+        rel: String,
+        seq: u64,
+        base_conf: Json,
+        rel_conf: Json,
+        asks: Vec<StreamedOrder>,
+        bids: Vec<StreamedOrder>,
+    },
+    Upserted {
+        order: StreamedOrder,
+        is_new: bool,
+        pairs: PairSeqs,
+    },
+    Removed {
+        order: OrderbookItem,
+        pairs: PairSeqs,
+    },
+    Disabled {
+        base: String,
+        rel: String,
+    },
+}
+
+pub struct StreamedOrder {
+    order: OrderbookItem,
+    /// Whether the order is signed by one of the P2P keys of this node.
+    is_my_p2p_pubkey: bool,
+}
+
+struct EnabledPair {
+    /// The orderbook tickers of the pair.
+    tickers: (String, String),
+    /// The number of the `orderbook_stream::enable` requests not followed by `orderbook_stream::disable` yet.
+    subscribers: usize,
+    /// The sequence number of the last event of the pair, including the dropped ones.
+    seq: u64,
+}
+
+impl EnabledPair {
+    fn contains(&self, order: &OrderbookItem) -> bool {
+        let (base, rel) = &self.tickers;
+        (*base == order.base && *rel == order.rel) || (*base == order.rel && *rel == order.base)
+    }
+}
+
+/// The orderbook streaming state kept by [`super::Orderbook`], so that the changes are sent in the order they happen.
+/// The sequence numbers are assigned here, so the dropped changes leave a gap visible to the clients.
+#[derive(Default)]
+pub(super) struct OrderbookStreamState {
+    /// Set only if the streaming of the `ORDERBOOK` event is active.
+    tx: Option<OrderbookStreamSender>,
+    /// The enabled `(base, rel)` pairs.
+    pairs: HashMap<(String, String), EnabledPair>,
+}
+
+impl OrderbookStreamState {
+    fn next_seqs(&mut self, order: &OrderbookItem) -> PairSeqs {
+        self.pairs
+            .iter_mut()
+            .filter(|(_pair, enabled)| enabled.contains(order))
+            .map(|(pair, enabled)| {
+                enabled.seq += 1;
+                (pair.clone(), enabled.seq)
+            })
+            .collect()
+    }
+
+    fn send(&mut self, msg: OrderbookStreamMessage) -> bool {
+        match &mut self.tx {
+            Some(tx) => try_send_stream_message(tx, OrderbookStreamer::EVENT_NAME, msg),
+            None => false,
+        }
+    }
+
+    pub(super) fn order_upserted(&mut self, order: &OrderbookItem, is_new: bool, is_my_p2p_pubkey: bool) {
+        let pairs = self.next_seqs(order);
+        if !pairs.is_empty() {
+            let order = StreamedOrder {
+                order: order.clone(),
+                is_my_p2p_pubkey,
+            };
+            self.send(OrderbookStreamMessage::Upserted { order, is_new, pairs });
+        }
+    }
+
+    pub(super) fn order_removed(&mut self, order: &OrderbookItem) {
+        let pairs = self.next_seqs(order);
+        if !pairs.is_empty() {
+            let order = order.clone();
+            self.send(OrderbookStreamMessage::Removed { order, pairs });
+        }
+    }
+
+    /// Adds a subscriber of the pair and sends the snapshot of the pair built by `snapshot` from its sequence number.
+    /// Returns the number of the pair subscribers.
+    fn subscribe<F>(
+        &mut self,
+        pair: (String, String),
+        tickers: (String, String),
+        snapshot: F,
+    ) -> MmResult<usize, OrderbookStreamError>
+    where
+        F: FnOnce(u64) -> OrderbookStreamMessage,
+    {
+        if self.tx.is_none() {
+            return MmError::err(OrderbookStreamError::StreamingIsNotActive);
+        }
+        let seq = {
+            let enabled = self.pairs.entry(pair.clone()).or_insert(EnabledPair {
+                tickers,
+                subscribers: 0,
+                seq: 0,
+            });
+            enabled.seq += 1;
+            enabled.seq
+        };
+
+        // The snapshot is sent while the orderbook is locked, so no delta can precede it.
+        let is_sent = self.send(snapshot(seq));
+        match self.pairs.entry(pair) {
+            Entry::Occupied(mut enabled) if is_sent => {
+                enabled.get_mut().subscribers += 1;
+                Ok(enabled.get().subscribers)
+            },
+            // The pair isn't streamed if the snapshot for its first subscriber is dropped.
+            Entry::Occupied(enabled) if enabled.get().subscribers == 0 => {
+                enabled.remove();
+                MmError::err(OrderbookStreamError::StreamIsOverloaded)
+            },
+            _ => MmError::err(OrderbookStreamError::StreamIsOverloaded),
+        }
+    }
+
+    /// Removes a subscriber of the pair, the pair isn't streamed anymore once there are no subscribers left.
+    /// Returns the number of the pair subscribers left.
+    fn unsubscribe(&mut self, pair: (String, String)) -> MmResult<usize, OrderbookStreamError> {
+        if self.tx.is_none() {
+            return MmError::err(OrderbookStreamError::StreamingIsNotActive);
+        }
+        let subscribers = match self.pairs.get_mut(&pair) {
+            Some(enabled) => {
+                enabled.subscribers -= 1;
+                enabled.subscribers
+            },
+            None => return Ok(0),
+        };
+        if subscribers == 0 {
+            self.pairs.remove(&pair);
+            let (base, rel) = pair;
+            self.send(OrderbookStreamMessage::Disabled { base, rel });
+        }
+        Ok(subscribers)
+    }
+}
+
+fn orderbook_event_type(base: &str, rel: &str) -> String {
+    format!("{}:{}:{}", OrderbookStreamer::EVENT_NAME, base, rel)
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum OrderSide {
+    Ask,
+    Bid,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum OrderbookStreamEvent {
+    Snapshot {
+        seq: u64,
+        base: String,
+        rel: String,
+        asks: Vec<RpcOrderbookEntryV2>,
+        bids: Vec<RpcOrderbookEntryV2>,
+    },
+    Add {
+        seq: u64,
+        side: OrderSide,
+        entry: RpcOrderbookEntryV2,
+    },
+    Update {
+        seq: u64,
+        side: OrderSide,
+        entry: RpcOrderbookEntryV2,
+    },
+    Remove {
+        seq: u64,
+        side: OrderSide,
+        uuid: Uuid,
+    },
+}
+
+/// The enabled pair as seen by the streamer.
+struct StreamedPair {
+    base_ticker: String,
+    rel_ticker: String,
+    base_conf: Json,
+    rel_conf: Json,
+}
+
+impl StreamedPair {
+    fn side(&self, order: &OrderbookItem) -> Option<OrderSide> {
+        if order.base == self.base_ticker && order.rel == self.rel_ticker {
+            Some(OrderSide::Ask)
+        } else if order.base == self.rel_ticker && order.rel == self.base_ticker {
+            Some(OrderSide::Bid)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct OrderbookStreamer {
+    ctx: MmArc,
+}
+
+impl OrderbookStreamer {
+    pub fn new(ctx: MmArc) -> Self { Self { ctx } }
+
+    fn entry(
+        &self,
+        pair: &(String, String),
+        streamed: &StreamedPair,
+        side: OrderSide,
+        order: &StreamedOrder,
+        my_pubsecp: &Option<String>,
+    ) -> Option<RpcOrderbookEntryV2> {
+        let (coin, conf) = match side {
+            OrderSide::Ask => (&pair.0, &streamed.base_conf),
+            OrderSide::Bid => (&pair.1, &streamed.rel_conf),
+        };
+        let order_item = &order.order;
+        let address_format = addr_format_from_protocol_info(&order_item.base_protocol_info);
+        let address = match orderbook_address(&self.ctx, coin, conf, &order_item.pubkey, address_format) {
+            Ok(address) => address,
+            Err(e) => {
+                warn!("Error {} on getting address for order {}", e, order_item.uuid);
+                return None;
+            },
+        };
+        let is_mine = order.is_my_p2p_pubkey || my_pubsecp.as_deref() == Some(order_item.pubkey.as_str());
+        Some(match side {
+            OrderSide::Ask => order_item.as_rpc_v2_entry_ask(address, is_mine),
+            OrderSide::Bid => order_item.as_rpc_v2_entry_bid(address, is_mine),
+        })
+    }
+
+    /// Returns the events to stream for every affected pair.
+    fn process_message(
+        &self,
+        pairs: &mut HashMap<(String, String), StreamedPair>,
+        msg: OrderbookStreamMessage,
+    ) -> Vec<(String, OrderbookStreamEvent)> {
+        let my_pubsecp = mm2_internal_pubkey_hex(&self.ctx, String::from).unwrap_or_default();
+        match msg {
+            OrderbookStreamMessage::Snapshot {
+                base,
+                rel,
+                seq,
+                base_conf,
+                rel_conf,
+                asks,
+                bids,
+            } => {
+                let pair = (base, rel);
+                let ordermatch_ctx = OrdermatchContext::from_ctx(&self.ctx).expect("ctx is available");
+                let (base_ticker, rel_ticker) = ordermatch_ctx.orderbook_pair_bypass(&pair);
+                let streamed = StreamedPair {
+                    base_ticker,
+                    rel_ticker,
+                    base_conf,
+                    rel_conf,
+                };
+
+                let mut asks: Vec<_> = asks
+                    .iter()
+                    .filter_map(|order| self.entry(&pair, &streamed, OrderSide::Ask, order, &my_pubsecp))
+                    .collect();
+                asks.sort_unstable_by(|ask1, ask2| ask1.price.rational.cmp(&ask2.price.rational));
+                let mut bids: Vec<_> = bids
+                    .iter()
+                    .filter_map(|order| self.entry(&pair, &streamed, OrderSide::Bid, order, &my_pubsecp))
+                    .collect();
+                bids.sort_unstable_by(|bid1, bid2| bid2.price.rational.cmp(&bid1.price.rational));
+
+                let event = OrderbookStreamEvent::Snapshot {
+                    seq,
+                    base: pair.0.clone(),
+                    rel: pair.1.clone(),
+                    asks,
+                    bids,
+                };
+                let event_type = orderbook_event_type(&pair.0, &pair.1);
+                pairs.insert(pair, streamed);
+                vec![(event_type, event)]
+            },
+            OrderbookStreamMessage::Upserted {
+                order,
+                is_new,
+                pairs: seqs,
+            } => seqs
+                .into_iter()
+                .filter_map(|(pair, seq)| {
+                    // The pair is unknown if its snapshot has been dropped.
+                    let streamed = pairs.get(&pair)?;
+                    let side = streamed.side(&order.order)?;
+                    let event = match self.entry(&pair, streamed, side, &order, &my_pubsecp) {
+                        Some(entry) if is_new => OrderbookStreamEvent::Add { seq, side, entry },
+                        Some(entry) => OrderbookStreamEvent::Update { seq, side, entry },
+                        // The order isn't included into the snapshot either, so it's removed to keep the sequence.
+                        None => OrderbookStreamEvent::Remove {
+                            seq,
+                            side,
+                            uuid: order.order.uuid,
+                        },
+                    };
+                    Some((orderbook_event_type(&pair.0, &pair.1), event))
+                })
+                .collect(),
+            OrderbookStreamMessage::Removed { order, pairs: seqs } => seqs
+                .into_iter()
+                .filter_map(|(pair, seq)| {
+                    let side = pairs.get(&pair)?.side(&order)?;
+                    let event = OrderbookStreamEvent::Remove {
+                        seq,
+                        side,
+                        uuid: order.uuid,
+                    };
+                    Some((orderbook_event_type(&pair.0, &pair.1), event))
+                })
+                .collect(),
+            OrderbookStreamMessage::Disabled { base, rel } => {
+                pairs.remove(&(base, rel));
+                Vec::new()
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl EventBehaviour for OrderbookStreamer {
+    const EVENT_NAME: &'static str = "ORDERBOOK";
+
+    async fn handle(self, _interval: f64, tx: oneshot::Sender<EventInitStatus>) {
+        let set_sender = |msg_tx: OrderbookStreamSender| -> Result<(), String> {
+            let ordermatch_ctx = OrdermatchContext::from_ctx(&self.ctx)?;
+            ordermatch_ctx.orderbook.lock().stream.tx = Some(msg_tx);
+            Ok(())
+        };
+
+        let mut pairs = HashMap::new();
+        let to_events = |msg: OrderbookStreamMessage| -> Vec<Event> {
+            self.process_message(&mut pairs, msg)
+                .into_iter()
+                .filter_map(|(event_type, event)| match serde_json::to_string(&event) {
+                    Ok(message) => Some(Event::new(event_type, message)),
+                    Err(e) => {
+                        error!("Error serializing {} event: {}", event_type, e);
+                        None
+                    },
+                })
+                .collect()
+        };
+        stream_channel_events(&self.ctx, tx, set_sender, to_events).await
+    }
+
+    async fn spawn_if_active(self, config: &EventStreamConfiguration) -> EventInitStatus {
+        let ctx = self.ctx.clone();
+        spawn_streamer_if_active(&ctx, self, config).await
+    }
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum OrderbookStreamError {
+    #[display(fmt = "'base' and 'rel' must differ")]
+    BaseRelSame,
+    #[display(fmt = "'base' and 'rel' share the same orderbook ticker and protocol")]
+    BaseRelSameOrderbookTickersAndProtocols,
+    #[display(fmt = "No such coin {} in config", _0)]
+    CoinConfigNotFound(String),
+    #[display(fmt = "Coin {} is wallet only", _0)]
+    CoinIsWalletOnly(String),
+    #[display(fmt = "ORDERBOOK event streaming is not active")]
+    StreamingIsNotActive,
+    #[display(fmt = "ORDERBOOK event streaming falls behind the orderbook changes, try again later")]
+    StreamIsOverloaded,
+    #[display(fmt = "Error subscribing to the orderbook: {}", _0)]
+    P2PSubscribeError(String),
+}
+
+impl HttpStatusCode for OrderbookStreamError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            OrderbookStreamError::BaseRelSame
+            | OrderbookStreamError::BaseRelSameOrderbookTickersAndProtocols
+            | OrderbookStreamError::CoinConfigNotFound(_)
+            | OrderbookStreamError::CoinIsWalletOnly(_) => StatusCode::BAD_REQUEST,
+            OrderbookStreamError::StreamingIsNotActive => StatusCode::METHOD_NOT_ALLOWED,
+            OrderbookStreamError::StreamIsOverloaded => StatusCode::SERVICE_UNAVAILABLE,
+            OrderbookStreamError::P2PSubscribeError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<GetTradeableCoinConfErr> for OrderbookStreamError {
+    fn from(err: GetTradeableCoinConfErr) -> Self {
+        match err {
+            GetTradeableCoinConfErr::CoinConfigNotFound(ticker) => OrderbookStreamError::CoinConfigNotFound(ticker),
+            GetTradeableCoinConfErr::CoinIsWalletOnly(ticker) => OrderbookStreamError::CoinIsWalletOnly(ticker),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct OrderbookStreamRequest {
+    base: String,
+    rel: String,
+}
+
+#[derive(Serialize)]
+pub struct OrderbookStreamResponse {
+    /// The type of the events streamed for the pair.
+    event: String,
+    /// The number of the pair subscribers after the request is applied.
+    subscribers: usize,
+}
+
+/// Starts streaming the orderbook of the pair or adds a subscriber if the pair is streamed already.
+/// The snapshot is streamed every time the pair is enabled.
+pub async fn enable_orderbook_stream(
+    ctx: MmArc,
+    req: OrderbookStreamRequest,
+) -> MmResult<OrderbookStreamResponse, OrderbookStreamError> {
+    if req.base == req.rel {
+        return MmError::err(OrderbookStreamError::BaseRelSame);
+    }
+    let base_conf = get_tradeable_coin_conf(&ctx, &req.base)?;
+    let rel_conf = get_tradeable_coin_conf(&ctx, &req.rel)?;
+
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).expect("ctx is available");
+    let (base_ticker, rel_ticker) = ordermatch_ctx.orderbook_pair_bypass(&(req.base.clone(), req.rel.clone()));
+    if base_ticker == rel_ticker && base_conf["protocol"] == rel_conf["protocol"] {
+        return MmError::err(OrderbookStreamError::BaseRelSameOrderbookTickersAndProtocols);
+    }
+    if ordermatch_ctx.orderbook.lock().stream.tx.is_none() {
+        return MmError::err(OrderbookStreamError::StreamingIsNotActive);
+    }
+
+    let request_orderbook = true;
+    subscribe_to_orderbook_topic(&ctx, &base_ticker, &rel_ticker, request_orderbook)
+        .await
+        .map_to_mm(OrderbookStreamError::P2PSubscribeError)?;
+
+    let mut orderbook = ordermatch_ctx.orderbook.lock();
+    let collect_orders = |base: &str, rel: &str| -> Vec<StreamedOrder> {
+        orderbook
+            .unordered
+            .get(&(base.to_owned(), rel.to_owned()))
+            .into_iter()
+            .flatten()
+            .filter_map(|uuid| orderbook.order_set.get(uuid))
+            .map(|order| StreamedOrder {
+                order: order.clone(),
+                is_my_p2p_pubkey: orderbook.my_p2p_pubkeys.contains(&order.pubkey),
+            })
+            .collect()
+    };
+    let asks = collect_orders(&base_ticker, &rel_ticker);
+    let bids = collect_orders(&rel_ticker, &base_ticker);
+
+    let pair = (req.base.clone(), req.rel.clone());
+    let subscribers = orderbook.stream.subscribe(pair, (base_ticker, rel_ticker), |seq| {
+        OrderbookStreamMessage::Snapshot {
+            base: req.base.clone(),
+            rel: req.rel.clone(),
+            seq,
+            base_conf,
+            rel_conf,
+            asks,
+            bids,
+        }
+    })?;
+
+    Ok(OrderbookStreamResponse {
+        event: orderbook_event_type(&req.base, &req.rel),
+        subscribers,
+    })
+}
+
+/// Removes a subscriber of the pair, the orderbook of the pair isn't streamed anymore once no subscribers are left.
+pub async fn disable_orderbook_stream(
+    ctx: MmArc,
+    req: OrderbookStreamRequest,
+) -> MmResult<OrderbookStreamResponse, OrderbookStreamError> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).expect("ctx is available");
+    let subscribers = ordermatch_ctx
+        .orderbook
+        .lock()
+        .stream
+        .unsubscribe((req.base.clone(), req.rel.clone()))?;
+
+    Ok(OrderbookStreamResponse {
+        event: orderbook_event_type(&req.base, &req.rel),
+        subscribers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mm2::lp_ordermatch::{insert_or_update_my_order, MakerOrder, Orderbook};
+    use crypto::privkey::SerializableSecp256k1Keypair;
+    use futures::channel::mpsc::{channel, Receiver};
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use mm2_number::BigRational;
+
+    fn orderbook_item(base: &str, rel: &str, max_volume: i64) -> OrderbookItem {
+        OrderbookItem {
+            pubkey: "02f3578fbc0fc76056eae34180a71e9190ee08ad05d40947aab7a286666e2ce798".to_owned(),
+            base: base.to_owned(),
+            rel: rel.to_owned(),
+            price: BigRational::from_integer(2.into()),
+            max_volume: BigRational::from_integer(max_volume.into()),
+            min_volume: BigRational::from_integer(0.into()),
+            uuid: Uuid::new_v4(),
+            created_at: 0,
+            base_protocol_info: Vec::new(),
+            rel_protocol_info: Vec::new(),
+            conf_settings: None,
+        }
+    }
+
+    fn kmd_btc() -> (String, String) { ("KMD".to_owned(), "BTC".to_owned()) }
+
+    fn snapshot(seq: u64) -> OrderbookStreamMessage {
+        OrderbookStreamMessage::Snapshot {
+            base: "KMD".to_owned(),
+            rel: "BTC".to_owned(),
+            seq,
+            base_conf: Json::Null,
+            rel_conf: Json::Null,
+            asks: Vec::new(),
+            bids: Vec::new(),
+        }
+    }
+
+    /// Creates the streaming state with the KMD/BTC pair enabled by one subscriber.
+    fn stream_state(buffer: usize) -> (OrderbookStreamState, Receiver<OrderbookStreamMessage>) {
+        let (tx, mut rx) = channel(buffer);
+        let mut state = OrderbookStreamState {
+            tx: Some(tx),
+            pairs: HashMap::new(),
+        };
+        assert_eq!(state.subscribe(kmd_btc(), kmd_btc(), snapshot).unwrap(), 1);
+        assert!(matches!(
+            rx.try_next(),
+            Ok(Some(OrderbookStreamMessage::Snapshot { seq: 1, .. }))
+        ));
+        (state, rx)
+    }
+
+    fn received(rx: &mut Receiver<OrderbookStreamMessage>) -> Vec<OrderbookStreamMessage> {
+        let mut messages = Vec::new();
+        while let Ok(Some(msg)) = rx.try_next() {
+            messages.push(msg);
+        }
+        messages
+    }
+
+    #[test]
+    fn test_orderbook_stream_messages() {
+        let (stream, mut rx) = stream_state(10);
+        let mut orderbook = Orderbook {
+            stream,
+            ..Orderbook::default()
+        };
+
+        let ask = orderbook_item("KMD", "BTC", 10);
+        let bid = orderbook_item("BTC", "KMD", 1);
+        orderbook.insert_or_update_order(ask.clone());
+        orderbook.insert_or_update_order(bid.clone());
+        // The order of another pair mustn't be streamed.
+        orderbook.insert_or_update_order(orderbook_item("KMD", "LTC", 10));
+        let mut updated_ask = ask.clone();
+        updated_ask.max_volume = BigRational::from_integer(5.into());
+        orderbook.insert_or_update_order(updated_ask);
+        orderbook.remove_order_trie_update(bid.uuid);
+
+        let messages = received(&mut rx);
+        assert_eq!(messages.len(), 4);
+        let seqs = |seq: u64| vec![(kmd_btc(), seq)];
+        match &messages[0] {
+            OrderbookStreamMessage::Upserted {
+                order,
+                is_new: true,
+                pairs,
+            } => {
+                assert_eq!(order.order.uuid, ask.uuid);
+                assert_eq!(*pairs, seqs(2));
+            },
+            _ => panic!("Expected the ask to be added"),
+        }
+        match &messages[1] {
+            OrderbookStreamMessage::Upserted {
+                order,
+                is_new: true,
+                pairs,
+            } => {
+                assert_eq!(order.order.uuid, bid.uuid);
+                assert_eq!(*pairs, seqs(3));
+            },
+            _ => panic!("Expected the bid to be added"),
+        }
+        match &messages[2] {
+            OrderbookStreamMessage::Upserted {
+                order,
+                is_new: false,
+                pairs,
+            } => {
+                assert_eq!(order.order.uuid, ask.uuid);
+                assert_eq!(*pairs, seqs(4));
+            },
+            _ => panic!("Expected the ask to be updated"),
+        }
+        match &messages[3] {
+            OrderbookStreamMessage::Removed { order, pairs } => {
+                assert_eq!(order.uuid, bid.uuid);
+                assert_eq!(*pairs, seqs(5));
+            },
+            _ => panic!("Expected the bid to be removed"),
+        }
+
+        let streamed = StreamedPair {
+            base_ticker: "KMD".to_owned(),
+            rel_ticker: "BTC".to_owned(),
+            base_conf: Json::Null,
+            rel_conf: Json::Null,
+        };
+        assert!(matches!(streamed.side(&ask), Some(OrderSide::Ask)));
+        assert!(matches!(streamed.side(&bid), Some(OrderSide::Bid)));
+        assert!(streamed.side(&orderbook_item("KMD", "LTC", 1)).is_none());
+    }
+
+    #[test]
+    fn test_orderbook_stream_dropped_changes_leave_gap() {
+        // The capacity of the channel is the buffer size plus one slot per sender.
+        let (mut state, mut rx) = stream_state(0);
+
+        state.order_upserted(&orderbook_item("KMD", "BTC", 1), true, false);
+        // The streamer falls behind, so the change is dropped.
+        state.order_upserted(&orderbook_item("KMD", "BTC", 2), true, false);
+        assert_eq!(received(&mut rx).len(), 1);
+
+        state.order_removed(&orderbook_item("KMD", "BTC", 3));
+        match received(&mut rx).as_slice() {
+            [OrderbookStreamMessage::Removed { pairs, .. }] => assert_eq!(*pairs, vec![(kmd_btc(), 4)]),
+            _ => panic!("Expected the order to be removed"),
+        }
+
+        // The snapshot for the new subscriber can't be dropped silently.
+        state.order_upserted(&orderbook_item("KMD", "BTC", 4), true, false);
+        let err = state.subscribe(kmd_btc(), kmd_btc(), snapshot).unwrap_err();
+        assert!(matches!(err.get_inner(), OrderbookStreamError::StreamIsOverloaded));
+        assert_eq!(state.pairs[&kmd_btc()].subscribers, 1);
+    }
+
+    #[test]
+    fn test_orderbook_stream_subscribers() {
+        let (mut state, mut rx) = stream_state(10);
+
+        // Every subscriber gets its own snapshot.
+        assert_eq!(state.subscribe(kmd_btc(), kmd_btc(), snapshot).unwrap(), 2);
+        assert!(matches!(received(&mut rx).as_slice(), [
+            OrderbookStreamMessage::Snapshot { seq: 2, .. }
+        ]));
+
+        // The pair is still streamed to the other subscriber.
+        assert_eq!(state.unsubscribe(kmd_btc()).unwrap(), 1);
+        state.order_upserted(&orderbook_item("KMD", "BTC", 1), true, false);
+        assert!(matches!(received(&mut rx).as_slice(), [
+            OrderbookStreamMessage::Upserted { .. }
+        ]));
+
+        assert_eq!(state.unsubscribe(kmd_btc()).unwrap(), 0);
+        assert!(matches!(received(&mut rx).as_slice(), [
+            OrderbookStreamMessage::Disabled { .. }
+        ]));
+        state.order_upserted(&orderbook_item("KMD", "BTC", 1), true, false);
+        assert!(received(&mut rx).is_empty());
+
+        // Disabling the pair that isn't enabled has no effect.
+        assert_eq!(state.unsubscribe(kmd_btc()).unwrap(), 0);
+        assert!(received(&mut rx).is_empty());
+
+        state.tx = None;
+        let err = state.subscribe(kmd_btc(), kmd_btc(), snapshot).unwrap_err();
+        assert!(matches!(err.get_inner(), OrderbookStreamError::StreamingIsNotActive));
+    }
+
+    #[test]
+    fn test_my_order_is_streamed_as_mine() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).unwrap();
+        let (stream, mut rx) = stream_state(10);
+        ordermatch_ctx.orderbook.lock().stream = stream;
+
+        let p2p_privkey = SerializableSecp256k1Keypair::random();
+        let mut item = orderbook_item("KMD", "BTC", 10);
+        item.pubkey = hex::encode(p2p_privkey.public_slice());
+        let order = MakerOrder {
+            base: "KMD".to_owned(),
+            rel: "BTC".to_owned(),
+            created_at: 0,
+            updated_at: None,
+            max_base_vol: 10.into(),
+            min_base_vol: 0.into(),
+            price: 2.into(),
+            matches: HashMap::new(),
+            started_swaps: Vec::new(),
+            uuid: item.uuid,
+            conf_settings: None,
+            changes_history: None,
+            save_in_history: false,
+            base_orderbook_ticker: None,
+            rel_orderbook_ticker: None,
+            p2p_privkey: Some(p2p_privkey),
+            expires_at: None,
+            visible_volume: None,
+            min_taker_reputation: None,
+        };
+        insert_or_update_my_order(&ctx, item, &order);
+
+        match received(&mut rx).as_slice() {
+            [OrderbookStreamMessage::Upserted { order, .. }] => assert!(order.is_my_p2p_pubkey),
+            _ => panic!("Expected the order to be added"),
+        }
+    }
+}
@@ -207,12 +207,12 @@ pub struct OrderbookV2Response {
     total_bids_rel_vol: MmNumberMultiRepr,
 }
 
-enum GetTradeableCoinConfErr {
+pub(super) enum GetTradeableCoinConfErr {
     CoinConfigNotFound(String),
     CoinIsWalletOnly(String),
 }
 
-fn get_tradeable_coin_conf(ctx: &MmArc, ticker: &str) -> MmResult<Json, GetTradeableCoinConfErr> {
+pub(super) fn get_tradeable_coin_conf(ctx: &MmArc, ticker: &str) -> MmResult<Json, GetTradeableCoinConfErr> {
     let conf = coin_conf(ctx, ticker);
     if conf.is_null() {
         return MmError::err(GetTradeableCoinConfErr::CoinConfigNotFound(ticker.to_owned()));
@@ -6,10 +6,10 @@ use uuid::Uuid;
 
 pub use mm2_db::indexed_db::{cursor_prelude, DbTransactionError, DbTransactionResult, InitDbError, InitDbResult,
                              ItemId};
-pub use tables::{MyActiveMakerOrdersTable, MyActiveTakerOrdersTable, MyFilteringHistoryOrdersTable,
-                 MyHistoryOrdersTable};
+pub use tables::{MyActiveMakerOrdersTable, MyActiveTakerOrdersTable, MyConditionalOrdersTable,
+                 MyFilteringHistoryOrdersTable, MyHistoryOrdersTable};
 
-const DB_VERSION: u32 = 1;
+const DB_VERSION: u32 = 2;
 
 pub struct OrdermatchDb {
     inner: IndexedDb,
@@ -26,6 +26,7 @@ impl DbInstance for OrdermatchDb {
             .with_table::<MyActiveTakerOrdersTable>()
             .with_table::<MyHistoryOrdersTable>()
             .with_table::<MyFilteringHistoryOrdersTable>()
+            .with_table::<MyConditionalOrdersTable>()
             .build()
             .await?;
         Ok(OrdermatchDb { inner })
@@ -40,6 +41,7 @@ impl Deref for OrdermatchDb {
 
 pub mod tables {
     use super::*;
+    use crate::mm2::lp_ordermatch::conditional_orders::ConditionalOrder;
     use crate::mm2::lp_ordermatch::{MakerOrder, Order, TakerOrder};
     use serde_json::Value as Json;
 
@@ -104,7 +106,7 @@ pub mod tables {
         fn table_name() -> &'static str { "my_filtering_history_orders" }
 
         fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
-            if let (0, 1) = (old_version, new_version) {
+            if old_version < 1 && new_version >= 1 {
                 let table = upgrader.create_table(Self::table_name())?;
                 table.create_index("uuid", true)?;
                 // TODO add other indexes during [`MyOrdersStorage::select_orders_by_filter`] implementation.
@@ -113,6 +115,25 @@ pub mod tables {
         }
     }
 
+    /// Conditional orders waiting for their trigger condition, added in the DB version 2.
+    #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+    pub struct MyConditionalOrdersTable {
+        pub uuid: Uuid,
+        pub order_payload: ConditionalOrder,
+    }
+
+    impl TableSignature for MyConditionalOrdersTable {
+        fn table_name() -> &'static str { "my_conditional_orders" }
+
+        fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+            if old_version < 2 && new_version >= 2 {
+                let table = upgrader.create_table(Self::table_name())?;
+                table.create_index("uuid", true)?;
+            }
+            Ok(())
+        }
+    }
+
     /// [`TableSignature::on_upgrade_needed`] implementation common for the most tables with the only `uuid` unique index.
     fn on_upgrade_swap_table_by_uuid_v1(
         upgrader: &DbUpgrader,
@@ -120,7 +141,7 @@ pub mod tables {
         new_version: u32,
         table_name: &'static str,
     ) -> OnUpgradeResult<()> {
-        if let (0, 1) = (old_version, new_version) {
+        if old_version < 1 && new_version >= 1 {
             let table = upgrader.create_table(table_name)?;
             table.create_index("uuid", true)?;
         }
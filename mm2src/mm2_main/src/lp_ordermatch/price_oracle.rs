@@ -0,0 +1,682 @@
+//! Price oracles of the simple market maker bot.
+//!
+//! The bot fetches the USD prices from all the configured providers on every tick.
+//! The rates of a pair are then aggregated from the provider quotes that are fresh enough
+//! and don't deviate from the median of the quotes too much, so a single bad feed can't move the order price.
+//!
+//! ```json
+//! "price_oracle": {
+//!     "providers": [
+//!         { "name": "komodo", "source": { "type": "tickers_url", "url": "https://prices.komodian.info/api/v2/tickers" } },
+//!         { "name": "own_feed", "source": { "type": "usd_prices_url", "url": "http://127.0.0.1:8080/prices" }, "weight": 2 },
+//!         { "name": "manual", "source": { "type": "static_file", "path": "/home/user/prices.json" } }
+//!     ],
+//!     "aggregation": "median",
+//!     "max_deviation": "0.05",
+//!     "min_providers": 2
+//! }
+//! ```
+
+use async_trait::async_trait;
+use coins::lp_price::{fetch_price_tickers, fetch_usd_prices, PriceServiceRequestError, UsdPrice};
+use common::log::{debug, error, warn};
+use common::now_sec;
+use derive_more::Display;
+use futures::future::join_all;
+use mm2_err_handle::prelude::*;
+use mm2_number::MmNumber;
+use std::collections::{HashMap, HashSet};
+#[cfg(not(target_arch = "wasm32"))] use std::path::PathBuf;
+#[cfg(not(target_arch = "wasm32"))] use std::time::UNIX_EPOCH;
+
+pub type PriceOracleResult<T> = Result<T, MmError<PriceOracleError>>;
+type UsdPrices = HashMap<String, UsdPrice>;
+
+/// The name of the provider used if `price_oracle` is not set.
+const DEFAULT_PROVIDER_NAME: &str = "default";
+
+#[derive(Debug, Display)]
+pub enum PriceOracleError {
+    #[display(fmt = "Invalid price oracle configuration: {}", _0)]
+    InvalidConfiguration(String),
+    #[display(
+        fmt = "Only {} valid price quotes while {} are required - skipping for {}",
+        available,
+        required,
+        pair
+    )]
+    NotEnoughQuotes {
+        pair: String,
+        available: usize,
+        required: usize,
+    },
+}
+
+/// Fetches the USD prices of the coins.
+#[async_trait]
+pub trait PriceOracle: Send + Sync {
+    async fn fetch_usd_prices(&self) -> Result<UsdPrices, MmError<PriceServiceRequestError>>;
+}
+
+/// The tickers format of the Komodo price services, see [`super::KMD_PRICE_ENDPOINT`].
+struct TickersUrlOracle {
+    url: String,
+}
+
+#[async_trait]
+impl PriceOracle for TickersUrlOracle {
+    async fn fetch_usd_prices(&self) -> Result<UsdPrices, MmError<PriceServiceRequestError>> {
+        Ok(fetch_price_tickers(&self.url).await?.usd_prices())
+    }
+}
+
+/// The [`UsdPrice`] format served by an HTTP endpoint, e.g. by a self-hosted feed.
+struct UsdPricesUrlOracle {
+    url: String,
+}
+
+#[async_trait]
+impl PriceOracle for UsdPricesUrlOracle {
+    async fn fetch_usd_prices(&self) -> Result<UsdPrices, MmError<PriceServiceRequestError>> {
+        fetch_usd_prices(&self.url).await
+    }
+}
+
+/// A local JSON file in the [`UsdPrice`] format. The file is re-read on every tick, so it can be edited on the fly.
+/// The prices without `last_updated_timestamp` are considered updated when the file was modified last time.
+#[cfg(not(target_arch = "wasm32"))]
+struct StaticFileOracle {
+    path: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl PriceOracle for StaticFileOracle {
+    async fn fetch_usd_prices(&self) -> Result<UsdPrices, MmError<PriceServiceRequestError>> {
+        let mut prices: UsdPrices = match mm2_io::fs::read_json(&self.path).await {
+            Ok(Some(prices)) => prices,
+            Ok(None) => {
+                return MmError::err(PriceServiceRequestError::Internal(format!(
+                    "'{}' file not found",
+                    self.path.display()
+                )))
+            },
+            Err(e) => return MmError::err(PriceServiceRequestError::ParsingAnswerError(e.to_string())),
+        };
+
+        let modified_at = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .map_to_mm(|e| PriceServiceRequestError::Internal(e.to_string()))?
+            .duration_since(UNIX_EPOCH)
+            .map_to_mm(|e| PriceServiceRequestError::Internal(e.to_string()))?
+            .as_secs();
+        for price in prices.values_mut() {
+            price.last_updated_timestamp.get_or_insert(modified_at);
+        }
+        Ok(prices)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PriceSourceConf {
+    TickersUrl {
+        url: String,
+    },
+    UsdPricesUrl {
+        url: String,
+    },
+    #[cfg(not(target_arch = "wasm32"))]
+    StaticFile {
+        path: PathBuf,
+    },
+}
+
+impl PriceSourceConf {
+    fn into_oracle(self) -> Box<dyn PriceOracle> {
+        match self {
+            PriceSourceConf::TickersUrl { url } => Box::new(TickersUrlOracle { url }),
+            PriceSourceConf::UsdPricesUrl { url } => Box::new(UsdPricesUrlOracle { url }),
+            #[cfg(not(target_arch = "wasm32"))]
+            PriceSourceConf::StaticFile { path } => Box::new(StaticFileOracle { path }),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PriceProviderConf {
+    pub name: String,
+    pub source: PriceSourceConf,
+    /// Used by [`PriceAggregation::WeightedAverage`] only, 1 by default.
+    pub weight: Option<MmNumber>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceAggregation {
+    Median,
+    WeightedAverage,
+}
+
+impl Default for PriceAggregation {
+    fn default() -> Self { PriceAggregation::Median }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PriceOracleConf {
+    pub providers: Vec<PriceProviderConf>,
+    #[serde(default)]
+    pub aggregation: PriceAggregation,
+    /// The maximum relative deviation of a quote from the median of the pair quotes, e.g. `0.05` for 5%.
+    /// The deviating quotes are ignored.
+    pub max_deviation: Option<MmNumber>,
+    /// The minimum number of the valid quotes required to price a pair, 1 by default.
+    pub min_providers: Option<usize>,
+}
+
+struct PriceProvider {
+    name: String,
+    weight: MmNumber,
+    oracle: Box<dyn PriceOracle>,
+}
+
+/// The USD prices fetched from every provider that responded successfully.
+#[derive(Default)]
+pub struct PriceQuotes(HashMap<String, UsdPrices>);
+
+impl PriceQuotes {
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+}
+
+/// The rates of a pair aggregated from the quotes of several providers.
+#[derive(Clone, Debug)]
+pub struct PairRates {
+    pub base_price: MmNumber,
+    pub rel_price: MmNumber,
+    /// The price of the base coin in the rel coin.
+    pub price: MmNumber,
+    /// The timestamp of the oldest quote the rates are aggregated from.
+    pub last_updated_timestamp: u64,
+    pub providers: Vec<String>,
+}
+
+struct PairQuote<'a> {
+    provider: &'a PriceProvider,
+    base_price: MmNumber,
+    rel_price: MmNumber,
+    price: MmNumber,
+    timestamp: u64,
+}
+
+pub struct PriceOracleRegistry {
+    providers: Vec<PriceProvider>,
+    aggregation: PriceAggregation,
+    max_deviation: Option<MmNumber>,
+    min_providers: usize,
+}
+
+impl PriceOracleRegistry {
+    pub fn from_conf(conf: PriceOracleConf) -> PriceOracleResult<PriceOracleRegistry> {
+        if conf.providers.is_empty() {
+            return MmError::err(PriceOracleError::InvalidConfiguration(
+                "at least one provider is required".to_owned(),
+            ));
+        }
+        let min_providers = conf.min_providers.unwrap_or(1);
+        if min_providers == 0 || min_providers > conf.providers.len() {
+            let error = format!(
+                "'min_providers' must be between 1 and the number of providers {}",
+                conf.providers.len()
+            );
+            return MmError::err(PriceOracleError::InvalidConfiguration(error));
+        }
+        if let Some(max_deviation) = &conf.max_deviation {
+            if *max_deviation <= MmNumber::from(0) {
+                return MmError::err(PriceOracleError::InvalidConfiguration(
+                    "'max_deviation' must be positive".to_owned(),
+                ));
+            }
+        }
+
+        let mut names = HashSet::with_capacity(conf.providers.len());
+        let mut providers = Vec::with_capacity(conf.providers.len());
+        for provider_conf in conf.providers {
+            if !names.insert(provider_conf.name.clone()) {
+                let error = format!("'{}' provider is duplicated", provider_conf.name);
+                return MmError::err(PriceOracleError::InvalidConfiguration(error));
+            }
+            let weight = provider_conf.weight.unwrap_or_else(|| MmNumber::from(1));
+            if weight <= MmNumber::from(0) {
+                let error = format!("'{}' provider weight must be positive", provider_conf.name);
+                return MmError::err(PriceOracleError::InvalidConfiguration(error));
+            }
+            providers.push(PriceProvider {
+                name: provider_conf.name,
+                weight,
+                oracle: provider_conf.source.into_oracle(),
+            });
+        }
+
+        Ok(PriceOracleRegistry {
+            providers,
+            aggregation: conf.aggregation,
+            max_deviation: conf.max_deviation,
+            min_providers,
+        })
+    }
+
+    /// The single provider of the Komodo tickers format, used if `price_oracle` is not set.
+    pub fn with_tickers_url(url: String) -> PriceOracleRegistry {
+        PriceOracleRegistry {
+            providers: vec![PriceProvider {
+                name: DEFAULT_PROVIDER_NAME.to_owned(),
+                weight: MmNumber::from(1),
+                oracle: Box::new(TickersUrlOracle { url }),
+            }],
+            aggregation: PriceAggregation::Median,
+            max_deviation: None,
+            min_providers: 1,
+        }
+    }
+
+    /// Checks that the per-pair `price_providers` override refers to the known providers only
+    /// and that there are enough of them.
+    pub fn validate_pair_providers(&self, pair: &str, provider_names: &[String]) -> PriceOracleResult<()> {
+        if let Some(unknown) = provider_names
+            .iter()
+            .find(|name| !self.providers.iter().any(|provider| &provider.name == *name))
+        {
+            let error = format!("unknown '{}' price provider for {}", unknown, pair);
+            return MmError::err(PriceOracleError::InvalidConfiguration(error));
+        }
+        if provider_names.len() < self.min_providers {
+            let error = format!(
+                "{} requires at least {} price providers, but only {} are set",
+                pair,
+                self.min_providers,
+                provider_names.len()
+            );
+            return MmError::err(PriceOracleError::InvalidConfiguration(error));
+        }
+        Ok(())
+    }
+
+    /// Fetches the prices from all the providers concurrently.
+    /// The providers that failed are logged and skipped.
+    /// The prices still having no `last_updated_timestamp` are considered updated at the fetch time.
+    pub async fn fetch_quotes(&self) -> PriceQuotes {
+        let futures = self.providers.iter().map(|provider| async move {
+            let result = provider.oracle.fetch_usd_prices().await;
+            (provider.name.clone(), result)
+        });
+
+        let mut quotes = PriceQuotes::default();
+        for (name, result) in join_all(futures).await {
+            match result {
+                Ok(mut prices) => {
+                    debug!("'{}' price provider returned {} prices", name, prices.len());
+                    let fetched_at = now_sec();
+                    for price in prices.values_mut() {
+                        price.last_updated_timestamp.get_or_insert(fetched_at);
+                    }
+                    quotes.0.insert(name, prices);
+                },
+                Err(e) => error!("Error fetching prices from '{}' provider: {:?}", name, e),
+            }
+        }
+        quotes
+    }
+
+    /// Aggregates the rates of the `base/rel` pair from the `quotes` of the given providers,
+    /// or of all the providers if `provider_names` is `None`.
+    /// The quotes older than `max_price_age` seconds or without the timestamp are ignored.
+    pub fn pair_rates(
+        &self,
+        quotes: &PriceQuotes,
+        base: &str,
+        rel: &str,
+        provider_names: Option<&[String]>,
+        max_price_age: f64,
+    ) -> PriceOracleResult<PairRates> {
+        let pair = format!("{}/{}", base, rel);
+        let now = now_sec();
+
+        let mut pair_quotes = Vec::with_capacity(self.providers.len());
+        for provider in self.providers.iter() {
+            if provider_names.map_or(false, |names| !names.contains(&provider.name)) {
+                continue;
+            }
+            let prices = match quotes.0.get(&provider.name) {
+                Some(prices) => prices,
+                None => continue,
+            };
+            let (base_price, rel_price) = match find_usd_price(prices, base).zip(find_usd_price(prices, rel)) {
+                Some(prices) => prices,
+                None => {
+                    debug!("'{}' price provider has no prices for {}", provider.name, pair);
+                    continue;
+                },
+            };
+            if base_price.price.is_zero() || rel_price.price.is_zero() {
+                debug!("'{}' price provider has zero prices for {}", provider.name, pair);
+                continue;
+            }
+
+            let timestamp = match base_price.last_updated_timestamp.zip(rel_price.last_updated_timestamp) {
+                Some((base_timestamp, rel_timestamp)) => std::cmp::min(base_timestamp, rel_timestamp),
+                None => {
+                    warn!(
+                        "'{}' prices for {} have no timestamp - skipping the provider",
+                        provider.name, pair
+                    );
+                    continue;
+                },
+            };
+            let elapsed = now.saturating_sub(timestamp) as f64;
+            if elapsed > max_price_age {
+                warn!(
+                    "'{}' prices for {} are {} seconds old, more than {} - skipping the provider",
+                    provider.name, pair, elapsed, max_price_age
+                );
+                continue;
+            }
+
+            pair_quotes.push(PairQuote {
+                provider,
+                price: &base_price.price / &rel_price.price,
+                base_price: base_price.price.clone(),
+                rel_price: rel_price.price.clone(),
+                timestamp,
+            });
+        }
+
+        let pair_quotes = self.reject_outliers(pair_quotes, &pair);
+        if pair_quotes.len() < self.min_providers {
+            return MmError::err(PriceOracleError::NotEnoughQuotes {
+                pair,
+                available: pair_quotes.len(),
+                required: self.min_providers,
+            });
+        }
+        Ok(self.aggregate(pair_quotes))
+    }
+
+    /// Drops the quotes deviating from the median of the quotes more than `max_deviation`.
+    fn reject_outliers<'a>(&self, quotes: Vec<PairQuote<'a>>, pair: &str) -> Vec<PairQuote<'a>> {
+        let max_deviation = match &self.max_deviation {
+            Some(max_deviation) if !quotes.is_empty() => max_deviation,
+            _ => return quotes,
+        };
+
+        let median_price = median(quotes.iter().map(|quote| quote.price.clone()).collect());
+        let max_diff = max_deviation * &median_price;
+        quotes
+            .into_iter()
+            .filter(|quote| {
+                let diff = if quote.price > median_price {
+                    &quote.price - &median_price
+                } else {
+                    &median_price - &quote.price
+                };
+                if diff > max_diff {
+                    warn!(
+                        "'{}' price {} of {} deviates from the median price {} more than {} - skipping the provider",
+                        quote.provider.name,
+                        quote.price.to_decimal(),
+                        pair,
+                        median_price.to_decimal(),
+                        max_deviation
+                    );
+                    return false;
+                }
+                true
+            })
+            .collect()
+    }
+
+    /// `quotes` must not be empty.
+    fn aggregate(&self, quotes: Vec<PairQuote>) -> PairRates {
+        let last_updated_timestamp = quotes.iter().map(|quote| quote.timestamp).min().unwrap_or_default();
+        let providers = quotes.iter().map(|quote| quote.provider.name.clone()).collect();
+        match self.aggregation {
+            PriceAggregation::Median => PairRates {
+                base_price: median(quotes.iter().map(|quote| quote.base_price.clone()).collect()),
+                rel_price: median(quotes.iter().map(|quote| quote.rel_price.clone()).collect()),
+                price: median(quotes.iter().map(|quote| quote.price.clone()).collect()),
+                last_updated_timestamp,
+                providers,
+            },
+            PriceAggregation::WeightedAverage => PairRates {
+                base_price: weighted_average(&quotes, |quote| &quote.base_price),
+                rel_price: weighted_average(&quotes, |quote| &quote.rel_price),
+                price: weighted_average(&quotes, |quote| &quote.price),
+                last_updated_timestamp,
+                providers,
+            },
+        }
+    }
+}
+
+/// Looks for the price of the `ticker` or of the ticker without the protocol suffix, e.g. `USDT` for `USDT-PLG20`.
+fn find_usd_price<'a>(prices: &'a UsdPrices, ticker: &str) -> Option<&'a UsdPrice> {
+    prices.get(ticker).or_else(|| {
+        let (without_suffix, _) = ticker.split_once('-')?;
+        prices.get(without_suffix)
+    })
+}
+
+/// `values` must not be empty.
+fn median(mut values: Vec<MmNumber>) -> MmNumber {
+    values.sort();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (&values[mid - 1] + &values[mid]) / MmNumber::from(2)
+    } else {
+        values.swap_remove(mid)
+    }
+}
+
+/// `quotes` must not be empty.
+fn weighted_average<F>(quotes: &[PairQuote], value: F) -> MmNumber
+where
+    F: Fn(&PairQuote) -> &MmNumber,
+{
+    let mut weighted_sum = MmNumber::default();
+    let mut total_weight = MmNumber::default();
+    for quote in quotes {
+        weighted_sum += value(quote) * &quote.provider.weight;
+        total_weight += &quote.provider.weight;
+    }
+    weighted_sum / total_weight
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Always returns the same prices.
+    struct FixedOracle(UsdPrices);
+
+    #[async_trait]
+    impl PriceOracle for FixedOracle {
+        async fn fetch_usd_prices(&self) -> Result<UsdPrices, MmError<PriceServiceRequestError>> { Ok(self.0.clone()) }
+    }
+
+    fn usd_prices(prices: &[(&str, &'static str, Option<u64>)]) -> UsdPrices {
+        prices
+            .iter()
+            .map(|(ticker, price, last_updated_timestamp)| {
+                let price = UsdPrice {
+                    price: MmNumber::from(*price),
+                    last_updated_timestamp: *last_updated_timestamp,
+                };
+                (ticker.to_string(), price)
+            })
+            .collect()
+    }
+
+    fn registry(
+        providers: Vec<(&str, &'static str, UsdPrices)>,
+        aggregation: PriceAggregation,
+        max_deviation: Option<&'static str>,
+        min_providers: usize,
+    ) -> PriceOracleRegistry {
+        PriceOracleRegistry {
+            providers: providers
+                .into_iter()
+                .map(|(name, weight, prices)| PriceProvider {
+                    name: name.to_owned(),
+                    weight: MmNumber::from(weight),
+                    oracle: Box::new(FixedOracle(prices)),
+                })
+                .collect(),
+            aggregation,
+            max_deviation: max_deviation.map(MmNumber::from),
+            min_providers,
+        }
+    }
+
+    #[test]
+    fn test_median_rejects_bad_feed() {
+        let now = now_sec();
+        let registry = registry(
+            vec![
+                (
+                    "a",
+                    "1",
+                    usd_prices(&[("KMD", "1", Some(now)), ("LTC", "100", Some(now))]),
+                ),
+                (
+                    "b",
+                    "1",
+                    usd_prices(&[("KMD", "1.02", Some(now)), ("LTC", "100", Some(now))]),
+                ),
+                // The bad feed reports the KMD price 10 times higher.
+                (
+                    "c",
+                    "1",
+                    usd_prices(&[("KMD", "10", Some(now)), ("LTC", "100", Some(now))]),
+                ),
+            ],
+            PriceAggregation::Median,
+            Some("0.05"),
+            2,
+        );
+        let quotes = common::block_on(registry.fetch_quotes());
+
+        let rates = registry.pair_rates(&quotes, "KMD", "LTC", None, 300.).unwrap();
+        assert_eq!(rates.providers, vec!["a".to_owned(), "b".to_owned()]);
+        assert_eq!(rates.price, MmNumber::from("0.0101"));
+
+        // There is only one valid quote if the pair is limited to the `a` and `c` providers.
+        let provider_names = vec!["a".to_owned(), "c".to_owned()];
+        let err = registry
+            .pair_rates(&quotes, "KMD", "LTC", Some(&provider_names), 300.)
+            .unwrap_err();
+        assert!(matches!(err.into_inner(), PriceOracleError::NotEnoughQuotes {
+            available: 0,
+            required: 2,
+            ..
+        }));
+    }
+
+    #[test]
+    fn test_stale_quotes_are_skipped() {
+        let now = now_sec();
+        let registry = registry(
+            vec![
+                (
+                    "fresh",
+                    "1",
+                    usd_prices(&[("KMD", "1", None), ("USDT", "1", Some(now))]),
+                ),
+                (
+                    "stale",
+                    "1",
+                    usd_prices(&[("KMD", "2", Some(now - 600)), ("USDT", "1", Some(now))]),
+                ),
+            ],
+            PriceAggregation::Median,
+            None,
+            1,
+        );
+        let quotes = common::block_on(registry.fetch_quotes());
+
+        // The ticker suffix is ignored if there is no price of the exact ticker.
+        let rates = registry.pair_rates(&quotes, "KMD", "USDT-ERC20", None, 300.).unwrap();
+        assert_eq!(rates.providers, vec!["fresh".to_owned()]);
+        assert_eq!(rates.price, MmNumber::from(1));
+    }
+
+    #[test]
+    fn test_weighted_average() {
+        let now = now_sec();
+        let registry = registry(
+            vec![
+                (
+                    "a",
+                    "3",
+                    usd_prices(&[("KMD", "1", Some(now)), ("LTC", "100", Some(now))]),
+                ),
+                (
+                    "b",
+                    "1",
+                    usd_prices(&[("KMD", "2", Some(now)), ("LTC", "100", Some(now))]),
+                ),
+            ],
+            PriceAggregation::WeightedAverage,
+            None,
+            1,
+        );
+        let quotes = common::block_on(registry.fetch_quotes());
+
+        let rates = registry.pair_rates(&quotes, "KMD", "LTC", None, 300.).unwrap();
+        assert_eq!(rates.base_price, MmNumber::from("1.25"));
+        assert_eq!(rates.rel_price, MmNumber::from(100));
+        assert_eq!(rates.price, MmNumber::from("0.0125"));
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_static_file_prices_are_stamped_with_mtime() {
+        let path = std::env::temp_dir().join(format!("price_oracle_{}.json", common::new_uuid()));
+        std::fs::write(
+            &path,
+            r#"{"KMD": {"price": "1"}, "LTC": {"price": "100", "last_updated_timestamp": 1}}"#,
+        )
+        .unwrap();
+        let modified_at = std::fs::metadata(&path)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let oracle = StaticFileOracle { path: path.clone() };
+        let prices = common::block_on(oracle.fetch_usd_prices()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(prices["KMD"].last_updated_timestamp, Some(modified_at));
+        assert_eq!(prices["LTC"].last_updated_timestamp, Some(1));
+    }
+
+    #[test]
+    fn test_invalid_conf() {
+        let conf: PriceOracleConf = serde_json::from_value(json!({
+            "providers": [
+                { "name": "a", "source": { "type": "tickers_url", "url": "http://127.0.0.1:8080" } },
+                { "name": "a", "source": { "type": "usd_prices_url", "url": "http://127.0.0.1:8081" } }
+            ]
+        }))
+        .unwrap();
+        assert!(PriceOracleRegistry::from_conf(conf).is_err());
+
+        let conf: PriceOracleConf = serde_json::from_value(json!({
+            "providers": [{ "name": "a", "source": { "type": "tickers_url", "url": "http://127.0.0.1:8080" } }],
+            "min_providers": 2
+        }))
+        .unwrap();
+        assert!(PriceOracleRegistry::from_conf(conf).is_err());
+    }
+}
@@ -1,60 +1,56 @@
 use crate::mm2::lp_dispatcher::{dispatch_lp_event, DispatcherContext};
-use crate::mm2::lp_ordermatch::lp_bot::{RunningState, StoppedState, StoppingState, TradingBotStarted,
-                                        TradingBotStopped, TradingBotStopping, VolumeSettings};
+use crate::mm2::lp_ordermatch::lp_bot::price_oracle::{PairRates, PriceOracleConf, PriceOracleRegistry,
+                                                      PriceOracleResult, PriceQuotes};
+use crate::mm2::lp_ordermatch::lp_bot::{BotPnl, BotStatus, CoinInventoryRegistry, CoinInventoryStatus, LadderCfg,
+                                        PairStatus, RunningState, StoppedState, StoppingState, TradingBotStarted,
+                                        TradingBotStopped, TradingBotStopping, VolumeDistribution, VolumeSettings};
 use crate::mm2::lp_ordermatch::{cancel_all_orders, CancelBy, TradingBotEvent};
-use crate::mm2::lp_swap::SavedSwap;
+use crate::mm2::lp_swap::{get_max_maker_vol, SavedSwap};
 use crate::mm2::{lp_ordermatch::{cancel_order, create_maker_order,
                                  lp_bot::{SimpleCoinMarketMakerCfg, SimpleMakerBotRegistry, TradingBotContext,
                                           TradingBotState},
                                  update_maker_order, CancelOrderReq, MakerOrder, MakerOrderUpdateReq,
                                  OrdermatchContext, SetPriceReq},
                  lp_swap::{latest_swaps_for_pair, LatestSwapsErr}};
-use coins::lp_price::{fetch_price_tickers, Provider, RateInfos};
-use coins::{lp_coinfind, GetNonZeroBalance};
+use coins::{lp_coinfind, GetNonZeroBalance, MarketCoinOps};
 use common::{executor::{SpawnFuture, Timer},
              log::{debug, error, info, warn},
-             Future01CompatExt, HttpStatusCode, StatusCode};
+             now_sec, Future01CompatExt, HttpStatusCode, StatusCode};
 use derive_more::Display;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
-use mm2_number::MmNumber;
+use mm2_number::{BigDecimal, MmNumber};
+use mm2_rpc::data::legacy::OrderType;
+use parking_lot::Mutex as PaMutex;
 use serde_json::Value as Json;
 use std::collections::{HashMap, HashSet};
+use std::iter;
+use std::sync::Arc;
 use uuid::Uuid;
 
 // !< constants
 pub const KMD_PRICE_ENDPOINT: &str = "https://prices.komodian.info/api/v2/tickers";
 pub const BOT_DEFAULT_REFRESH_RATE: f64 = 30.0;
 pub const PRECISION_FOR_NOTIFICATION: u64 = 8;
+/// The default `price_elapsed_validity`, 5 min.
+const DEFAULT_PRICE_ELAPSED_VALIDITY: f64 = 300.0;
 const LATEST_SWAPS_LIMIT: usize = 1000;
 
 // !< Type definitions
 pub type StartSimpleMakerBotResult = Result<StartSimpleMakerBotRes, MmError<StartSimpleMakerBotError>>;
 pub type StopSimpleMakerBotResult = Result<StopSimpleMakerBotRes, MmError<StopSimpleMakerBotError>>;
+pub type SimpleMakerBotStatusResult = Result<SimpleMakerBotStatusResponse, MmError<SimpleMakerBotStatusError>>;
 pub type OrderProcessingResult = Result<bool, MmError<OrderProcessingError>>;
 pub type VwapProcessingResult = Result<MmNumber, MmError<OrderProcessingError>>;
-pub type OrderPreparationResult = Result<(Option<MmNumber>, MmNumber, MmNumber, bool), MmError<OrderProcessingError>>;
+/// The min volume, the volume, the price and whether the max volume is used.
+pub type OrderPreparation = (Option<MmNumber>, MmNumber, MmNumber, bool);
+pub type OrderPreparationResult = Result<OrderPreparation, MmError<OrderProcessingError>>;
 
 #[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
 #[serde(tag = "error_type", content = "error_data")]
 pub enum OrderProcessingError {
-    #[display(fmt = "Rates from provider are Unknown - skipping for {}", key_trade_pair)]
-    ProviderUnknown { key_trade_pair: String },
     #[display(fmt = "Price from provider is zero - skipping for {}", key_trade_pair)]
     PriceIsZero { key_trade_pair: String },
-    #[display(fmt = "Last updated price timestamp is invalid - skipping for {}", key_trade_pair)]
-    LastUpdatedTimestampInvalid { key_trade_pair: String },
-    #[display(
-        fmt = "Last updated price timestamp elapsed {} is more than the elapsed validity {} - skipping for {}",
-        elapsed,
-        elapsed_validity,
-        key_trade_pair
-    )]
-    PriceElapsedValidityExpired {
-        elapsed: f64,
-        elapsed_validity: f64,
-        key_trade_pair: String,
-    },
     #[display(fmt = "Unable to parse/treat elapsed time {} - skipping", _0)]
     PriceElapsedValidityUntreatable(String),
     #[display(fmt = "Price of base coin {} is below min_base_price {}", base_price, min_base_price)]
@@ -89,6 +85,8 @@ pub enum OrderProcessingError {
     MyRecentSwapsError(String),
     #[display(fmt = "Base balance is less than the min_vol_usd - skipping")]
     MinVolUsdAboveBalanceUsd,
+    #[display(fmt = "{} balance limit is reached: {} - skipping", coin, reason)]
+    BalanceLimitReached { coin: String, reason: String },
     #[display(fmt = "Legacy error - skipping")]
     LegacyError(String),
 }
@@ -114,7 +112,12 @@ impl From<std::string::String> for OrderProcessingError {
 pub struct StartSimpleMakerBotRequest {
     cfg: SimpleMakerBotRegistry,
     price_url: Option<String>,
+    /// Replaces the single `price_url` with several aggregated price providers.
+    price_oracle: Option<PriceOracleConf>,
     bot_refresh_rate: Option<f64>,
+    /// The inventory controls per coin.
+    #[serde(default)]
+    inventory: CoinInventoryRegistry,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -162,6 +165,12 @@ pub enum StartSimpleMakerBotError {
     AlreadyStarted,
     #[display(fmt = "Invalid bot configuration")]
     InvalidBotConfiguration,
+    #[display(fmt = "Invalid price oracle configuration: {}", _0)]
+    InvalidPriceOracleConfiguration(String),
+    #[display(fmt = "Invalid ladder configuration for {}: {}", pair, reason)]
+    InvalidLadderConfiguration { pair: String, reason: String },
+    #[display(fmt = "Invalid inventory configuration for {}: {}", coin, reason)]
+    InvalidInventoryConfiguration { coin: String, reason: String },
     #[display(fmt = "Transport error: {}", _0)]
     Transport(String),
     #[display(fmt = "Cannot start the bot if it's currently stopping")]
@@ -170,6 +179,39 @@ pub enum StartSimpleMakerBotError {
     InternalError(String),
 }
 
+#[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum SimpleMakerBotStatusError {
+    #[display(fmt = "Internal error: {}", _0)]
+    InternalError(String),
+}
+
+impl HttpStatusCode for SimpleMakerBotStatusError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SimpleMakerBotStatusError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimpleMakerBotState {
+    Running,
+    Stopping,
+    Stopped,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimpleMakerBotStatusResponse {
+    state: SimpleMakerBotState,
+    inventory: HashMap<String, CoinInventoryStatus>,
+    pnl: Option<BotPnl>,
+    pairs: HashMap<String, PairStatus>,
+    /// The time of the last bot cycle.
+    updated_at: Option<u64>,
+}
+
 #[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
 #[serde(tag = "error_type", content = "error_data")]
 pub enum SwapUpdateNotificationError {
@@ -188,6 +230,9 @@ impl HttpStatusCode for StartSimpleMakerBotError {
         match self {
             StartSimpleMakerBotError::AlreadyStarted
             | StartSimpleMakerBotError::InvalidBotConfiguration
+            | StartSimpleMakerBotError::InvalidPriceOracleConfiguration(_)
+            | StartSimpleMakerBotError::InvalidLadderConfiguration { .. }
+            | StartSimpleMakerBotError::InvalidInventoryConfiguration { .. }
             | StartSimpleMakerBotError::CannotStartFromStopping => StatusCode::BAD_REQUEST,
             StartSimpleMakerBotError::Transport(_) | StartSimpleMakerBotError::InternalError(_) => {
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -222,6 +267,197 @@ impl TradingPair {
     pub fn as_combination(&self) -> String { self.base.clone() + "/" + self.rel.clone().as_str() }
 }
 
+/// The volumes of the coins the bot orders prepared in the current cycle may sell or buy.
+#[derive(Default)]
+struct CommittedVolumes {
+    sold: HashMap<String, MmNumber>,
+    bought: HashMap<String, MmNumber>,
+}
+
+/// The balances and the USD prices of the coins traded by the bot fetched at the beginning of the bot cycle.
+pub struct InventorySnapshot<'a> {
+    cfg: &'a CoinInventoryRegistry,
+    balances: HashMap<String, MmNumber>,
+    usd_prices: HashMap<String, MmNumber>,
+    /// The orders are prepared concurrently, so every order commits its volume to limit the next ones.
+    committed: PaMutex<CommittedVolumes>,
+}
+
+impl<'a> InventorySnapshot<'a> {
+    pub fn new(
+        cfg: &'a CoinInventoryRegistry,
+        balances: HashMap<String, MmNumber>,
+        usd_prices: HashMap<String, MmNumber>,
+    ) -> Self {
+        InventorySnapshot {
+            cfg,
+            balances,
+            usd_prices,
+            committed: PaMutex::new(CommittedVolumes::default()),
+        }
+    }
+
+    fn usd_value(&self, coin: &str) -> Option<MmNumber> {
+        let balance = self.balances.get(coin)?;
+        let usd_price = self.usd_prices.get(coin)?;
+        Some(balance * usd_price)
+    }
+
+    /// The USD value of the coins having both the balance and the price.
+    fn total_usd_value(&self) -> MmNumber {
+        self.balances
+            .keys()
+            .filter_map(|coin| self.usd_value(coin))
+            .fold(MmNumber::default(), |total, value| total + value)
+    }
+
+    /// Returns the share of the coin in the USD value of the inventory.
+    fn share(&self, coin: &str) -> Option<MmNumber> {
+        let total = self.total_usd_value();
+        if total.is_zero() {
+            return None;
+        }
+        Some(self.usd_value(coin)? / total)
+    }
+
+    /// Returns `skew_factor * (share - target_ratio)` if the coin has the inventory target.
+    fn coin_skew(&self, coin: &str) -> Option<MmNumber> {
+        let coin_cfg = self.cfg.get(coin)?;
+        let target_ratio = coin_cfg.target_ratio.as_ref()?;
+        let skew_factor = coin_cfg.skew_factor.as_ref()?;
+        let share = self.share(coin)?;
+        Some(skew_factor * &(&share - target_ratio))
+    }
+
+    /// Returns the multiplier of the price of the orders selling `base` for `rel`.
+    /// The price is decreased when there is too much `base` and increased when there is too much `rel`.
+    pub fn price_skew(&self, base: &str, rel: &str) -> MmNumber {
+        let one = MmNumber::from(1);
+        let mut skew = one.clone();
+        if let Some(base_skew) = self.coin_skew(base) {
+            skew = &skew * &(&one - &base_skew);
+        }
+        if let Some(rel_skew) = self.coin_skew(rel) {
+            skew = &skew * &(&one + &rel_skew);
+        }
+        skew
+    }
+
+    /// Limits the volume of the order selling `base` for `rel` so that the `base` balance doesn't fall
+    /// below its `min_balance` and the `rel` balance doesn't exceed its `max_balance`
+    /// even if this order and the other bot orders prepared in this cycle are all filled.
+    /// The resulting volume is committed and limits the orders prepared after this one.
+    pub fn apply_balance_limits(
+        &self,
+        base: &str,
+        rel: &str,
+        base_balance: &MmNumber,
+        price: &MmNumber,
+        volume: MmNumber,
+        is_max: bool,
+    ) -> Result<(MmNumber, bool), MmError<OrderProcessingError>> {
+        let mut committed = self.committed.lock();
+        let (volume, is_max) = self.limit_volume(&committed, base, rel, base_balance, price, volume, is_max)?;
+
+        let sold = if is_max { base_balance.clone() } else { volume.clone() };
+        let bought = &sold * price;
+        *committed.sold.entry(base.to_owned()).or_default() += &sold;
+        *committed.bought.entry(rel.to_owned()).or_default() += &bought;
+        Ok((volume, is_max))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn limit_volume(
+        &self,
+        committed: &CommittedVolumes,
+        base: &str,
+        rel: &str,
+        base_balance: &MmNumber,
+        price: &MmNumber,
+        volume: MmNumber,
+        is_max: bool,
+    ) -> Result<(MmNumber, bool), MmError<OrderProcessingError>> {
+        let mut limit: Option<MmNumber> = None;
+        if let Some(min_balance) = self.cfg.get(base).and_then(|coin_cfg| coin_cfg.min_balance.as_ref()) {
+            let sold = committed.sold.get(base).cloned().unwrap_or_default();
+            let available = &(base_balance - min_balance) - &sold;
+            if available <= MmNumber::default() {
+                return MmError::err(OrderProcessingError::BalanceLimitReached {
+                    coin: base.to_owned(),
+                    reason: format!(
+                        "the balance {} minus {} committed to the other bot orders is not above min_balance {}",
+                        base_balance, sold, min_balance
+                    ),
+                });
+            }
+            limit = Some(available);
+        }
+        if let Some(max_balance) = self.cfg.get(rel).and_then(|coin_cfg| coin_cfg.max_balance.as_ref()) {
+            let rel_balance = self
+                .balances
+                .get(rel)
+                .ok_or_else(|| MmError::new(OrderProcessingError::BalanceInternalError))?;
+            let bought = committed.bought.get(rel).cloned().unwrap_or_default();
+            let room = &(max_balance - rel_balance) - &bought;
+            if room <= MmNumber::default() {
+                return MmError::err(OrderProcessingError::BalanceLimitReached {
+                    coin: rel.to_owned(),
+                    reason: format!(
+                        "the balance {} plus {} committed to the other bot orders is not below max_balance {}",
+                        rel_balance, bought, max_balance
+                    ),
+                });
+            }
+            let max_base_volume = &room / price;
+            limit = Some(match limit {
+                Some(limit) if limit < max_base_volume => limit,
+                _ => max_base_volume,
+            });
+        }
+
+        match limit {
+            // The max volume is within the limit already.
+            Some(ref limit) if is_max && limit >= base_balance => Ok((volume, is_max)),
+            Some(limit) if is_max || volume > limit => Ok((limit, false)),
+            _ => Ok((volume, is_max)),
+        }
+    }
+}
+
+/// Requests the balances of the coins traded by the bot and takes their USD prices from the price quotes.
+async fn fetch_inventory<'a>(
+    ctx: &MmArc,
+    cfg: &SimpleMakerBotRegistry,
+    inventory_cfg: &'a CoinInventoryRegistry,
+    price_oracle: &PriceOracleRegistry,
+    price_quotes: &PriceQuotes,
+) -> InventorySnapshot<'a> {
+    let mut balances = HashMap::new();
+    let mut usd_prices = HashMap::new();
+    for pair_cfg in cfg.values().filter(|pair_cfg| pair_cfg.enable) {
+        if let Ok(rates) = pair_rates(price_oracle, price_quotes, pair_cfg) {
+            usd_prices.entry(pair_cfg.base.clone()).or_insert(rates.base_price);
+            usd_prices.entry(pair_cfg.rel.clone()).or_insert(rates.rel_price);
+        }
+        for ticker in [&pair_cfg.base, &pair_cfg.rel] {
+            if balances.contains_key(ticker) {
+                continue;
+            }
+            let coin = match lp_coinfind(ctx, ticker).await {
+                Ok(Some(coin)) => coin,
+                _ => continue,
+            };
+            match coin.my_spendable_balance().compat().await {
+                Ok(balance) => {
+                    balances.insert(ticker.clone(), MmNumber::from(balance));
+                },
+                Err(e) => warn!("Couldn't get {} balance for the inventory: {}", ticker, e),
+            }
+        }
+    }
+    InventorySnapshot::new(inventory_cfg, balances, usd_prices)
+}
+
 pub async fn tear_down_bot(ctx: MmArc) {
     let simple_market_maker_bot_ctx = TradingBotContext::from_ctx(&ctx).unwrap();
     let mut state = simple_market_maker_bot_ctx.trading_bot_states.lock().await;
@@ -364,16 +600,10 @@ async fn cancel_single_order(ctx: &MmArc, uuid: Uuid) {
 }
 
 async fn checks_order_prerequisites(
-    rates: &RateInfos,
+    rates: &PairRates,
     cfg: &SimpleCoinMarketMakerCfg,
     key_trade_pair: &str,
 ) -> OrderProcessingResult {
-    if rates.base_provider == Provider::Unknown || rates.rel_provider == Provider::Unknown {
-        return MmError::err(OrderProcessingError::ProviderUnknown {
-            key_trade_pair: key_trade_pair.to_string(),
-        });
-    }
-
     if rates.price.is_zero() {
         return MmError::err(OrderProcessingError::PriceIsZero {
             key_trade_pair: key_trade_pair.to_string(),
@@ -408,31 +638,19 @@ async fn checks_order_prerequisites(
         }
     }
 
-    if rates.last_updated_timestamp.is_none() {
-        return MmError::err(OrderProcessingError::LastUpdatedTimestampInvalid {
-            key_trade_pair: key_trade_pair.to_string(),
-        });
-    }
-
-    // Elapsed validity is the field defined in the cfg or 5 min by default (300 sec)
-    let elapsed = rates.retrieve_elapsed_times();
-    let elapsed_validity = cfg.price_elapsed_validity.unwrap_or(300.0);
-
-    if elapsed > elapsed_validity {
-        return MmError::err(OrderProcessingError::PriceElapsedValidityExpired {
-            elapsed,
-            elapsed_validity,
-            key_trade_pair: key_trade_pair.to_string(),
-        });
-    }
-    debug!("elapsed since last price update: {} secs", elapsed);
+    // The quotes older than `price_elapsed_validity` are already skipped by the price oracle.
+    debug!(
+        "{} price is aggregated from {:?} - last updated at {}",
+        key_trade_pair, rates.providers, rates.last_updated_timestamp
+    );
     Ok(true)
 }
 
 async fn prepare_order(
-    rates: &RateInfos,
+    rates: &PairRates,
     cfg: &SimpleCoinMarketMakerCfg,
     key_trade_pair: &str,
+    inventory: &InventorySnapshot<'_>,
     ctx: &MmArc,
 ) -> OrderPreparationResult {
     checks_order_prerequisites(rates, cfg, key_trade_pair).await?;
@@ -446,8 +664,9 @@ async fn prepare_order(
 
     debug!("balance for {} is {}", cfg.base, base_balance);
 
-    let mut calculated_price = &rates.price * &cfg.spread;
-    debug!("calculated price is: {}", calculated_price);
+    let skew = inventory.price_skew(&cfg.base, &cfg.rel);
+    let mut calculated_price = &(&rates.price * &cfg.spread) * &skew;
+    debug!("calculated price is: {} (inventory skew {})", calculated_price, skew);
     if cfg.check_last_bidirectional_trade_thresh_hold.unwrap_or(false) {
         calculated_price = vwap_calculator(calculated_price.clone(), ctx, cfg).await?;
     }
@@ -472,6 +691,8 @@ async fn prepare_order(
         },
         _ => MmNumber::default(),
     };
+    let (volume, is_max) =
+        inventory.apply_balance_limits(&cfg.base, &cfg.rel, &base_balance, &calculated_price, volume, is_max)?;
 
     let min_vol = match &cfg.min_volume {
         Some(VolumeSettings::Percentage(min_volume_percentage)) => {
@@ -493,19 +714,36 @@ async fn prepare_order(
     Ok((min_vol, volume, calculated_price, is_max))
 }
 
+/// Returns the change of `max_base_vol` making the amount available for matching equal to `volume`
+/// or `None` if it's equal already.
+/// The order volume reserved by the matches isn't available anymore, so the filled order is topped up.
+pub fn order_volume_delta(order: &MakerOrder, volume: &MmNumber) -> Option<MmNumber> {
+    let delta = volume - &order.available_amount();
+    if delta.is_zero() {
+        None
+    } else {
+        Some(delta)
+    }
+}
+
 async fn update_single_order(
-    rates: &RateInfos,
-    cfg: SimpleCoinMarketMakerCfg,
-    uuid: Uuid,
-    key_trade_pair: String,
+    order_params: OrderPreparation,
+    cfg: &SimpleCoinMarketMakerCfg,
+    order: &MakerOrder,
+    key_trade_pair: &str,
     ctx: &MmArc,
 ) -> OrderProcessingResult {
-    let (min_vol, volume, calculated_price, is_max) = prepare_order(rates, &cfg, &key_trade_pair, ctx).await?;
+    let (min_vol, volume, calculated_price, is_max) = order_params;
+    let volume_delta = if is_max {
+        None
+    } else {
+        order_volume_delta(order, &volume)
+    };
     let req = MakerOrderUpdateReq {
-        uuid,
+        uuid: order.uuid,
         new_price: Some(calculated_price.clone()),
         max: is_max.into(),
-        volume_delta: None,
+        volume_delta,
         min_volume: min_vol,
         base_confs: cfg.base_confs,
         base_nota: cfg.base_nota,
@@ -533,15 +771,36 @@ async fn update_single_order(
 }
 
 async fn execute_update_order(
-    uuid: Uuid,
     order: MakerOrder,
-    cloned_infos: (MmArc, RateInfos, TradingPair, SimpleCoinMarketMakerCfg),
+    cloned_infos: (
+        MmArc,
+        PriceOracleResult<PairRates>,
+        TradingPair,
+        SimpleCoinMarketMakerCfg,
+    ),
+    inventory: &InventorySnapshot<'_>,
 ) -> bool {
     let (ctx, rates, key_trade_pair, cfg) = cloned_infos;
-    match update_single_order(&rates, cfg, uuid, key_trade_pair.as_combination(), &ctx).await {
+    let rates = match rates {
+        Ok(rates) => rates,
+        Err(err) => {
+            error!(
+                "Order with uuid: {} for {} cannot be updated - err: {err}",
+                order.uuid,
+                key_trade_pair.as_combination()
+            );
+            cancel_single_order(&ctx, order.uuid).await;
+            return false;
+        },
+    };
+    let pair = key_trade_pair.as_combination();
+    let update_res = match prepare_order(&rates, &cfg, &pair, inventory, &ctx).await {
+        Ok(order_params) => update_single_order(order_params, &cfg, &order, &pair, &ctx).await,
+        Err(err) => Err(err),
+    };
+    match update_res {
         Ok(resp) => resp,
         Err(err) => {
-            let pair = key_trade_pair.as_combination();
             error!(
                 "Order with uuid: {} for {pair} cannot be updated - rate: ({:.8} {pair}) - err: {err:?}",
                 order.uuid,
@@ -554,12 +813,13 @@ async fn execute_update_order(
 }
 
 async fn create_single_order(
-    rates: &RateInfos,
-    cfg: SimpleCoinMarketMakerCfg,
-    key_trade_pair: String,
-    ctx: MmArc,
+    order_params: OrderPreparation,
+    cfg: &SimpleCoinMarketMakerCfg,
+    key_trade_pair: &str,
+    cancel_previous: bool,
+    ctx: &MmArc,
 ) -> OrderProcessingResult {
-    let (min_vol, volume, calculated_price, is_max) = prepare_order(rates, &cfg, &key_trade_pair, &ctx).await?;
+    let (min_vol, volume, calculated_price, is_max) = order_params;
 
     let req = SetPriceReq {
         base: cfg.base.clone(),
@@ -568,15 +828,18 @@ async fn create_single_order(
         max: is_max,
         volume: volume.clone(),
         min_volume: min_vol,
-        cancel_previous: true,
+        cancel_previous,
         base_confs: cfg.base_confs,
         base_nota: cfg.base_nota,
         rel_confs: cfg.rel_confs,
         rel_nota: cfg.rel_nota,
         save_in_history: true,
+        order_type: OrderType::GoodTillCancelled,
+        visible_volume: None,
+        min_taker_reputation: cfg.min_taker_reputation.clone(),
     };
 
-    let resp = create_maker_order(&ctx, req)
+    let resp = create_maker_order(ctx, req)
         .await
         .map_to_mm(OrderProcessingError::OrderUpdateError)?;
     let vol_info = if is_max {
@@ -595,12 +858,25 @@ async fn create_single_order(
 }
 
 async fn execute_create_single_order(
-    rates: RateInfos,
+    rates: PriceOracleResult<PairRates>,
     cfg: SimpleCoinMarketMakerCfg,
     key_trade_pair: String,
+    inventory: &InventorySnapshot<'_>,
     ctx: &MmArc,
 ) -> bool {
-    match create_single_order(&rates, cfg, key_trade_pair.clone(), ctx.clone()).await {
+    let rates = match rates {
+        Ok(rates) => rates,
+        Err(err) => {
+            error!("{err} - order cannot be created for: {key_trade_pair}.");
+            return false;
+        },
+    };
+    let cancel_previous = true;
+    let create_res = match prepare_order(&rates, &cfg, &key_trade_pair, inventory, ctx).await {
+        Ok(order_params) => create_single_order(order_params, &cfg, &key_trade_pair, cancel_previous, ctx).await,
+        Err(err) => Err(err),
+    };
+    match create_res {
         Ok(resp) => resp,
         Err(err) => {
             error!(
@@ -612,35 +888,262 @@ async fn execute_create_single_order(
     }
 }
 
+/// Returns the price and the volume of every ladder level.
+/// The first level is placed at `price`, every next level price is multiplied by `level_spread`.
+pub fn ladder_levels(ladder: &LadderCfg, price: &MmNumber, total_volume: &MmNumber) -> Vec<(MmNumber, MmNumber)> {
+    let weights: Vec<MmNumber> = match &ladder.volume_distribution {
+        VolumeDistribution::Flat => vec![MmNumber::from(1); ladder.levels],
+        VolumeDistribution::Linear => (1..=ladder.levels as u64).map(MmNumber::from).collect(),
+        VolumeDistribution::Exponential(factor) => {
+            iter::successors(Some(MmNumber::from(1)), |weight| Some(weight * factor))
+                .take(ladder.levels)
+                .collect()
+        },
+    };
+    let total_weight = weights
+        .iter()
+        .fold(MmNumber::default(), |total, weight| total + weight.clone());
+
+    let mut level_price = price.clone();
+    weights
+        .into_iter()
+        .map(|weight| {
+            let level = (level_price.clone(), total_volume * &weight / total_weight.clone());
+            level_price = &level_price * &ladder.level_spread;
+            level
+        })
+        .collect()
+}
+
+/// Splits the order prepared for the pair between the ladder levels.
+async fn prepare_ladder_orders(
+    rates: &PairRates,
+    cfg: &SimpleCoinMarketMakerCfg,
+    ladder: &LadderCfg,
+    key_trade_pair: &str,
+    inventory: &InventorySnapshot<'_>,
+    ctx: &MmArc,
+) -> Result<Vec<OrderPreparation>, MmError<OrderProcessingError>> {
+    let (min_vol, volume, calculated_price, is_max) = prepare_order(rates, cfg, key_trade_pair, inventory, ctx).await?;
+    // Every level needs an explicit volume, so the max volume is split between the levels.
+    let total_volume = if is_max {
+        let base_coin = lp_coinfind(ctx, cfg.base.as_str())
+            .await?
+            .ok_or_else(|| MmError::new(OrderProcessingError::AssetNotEnabled))?;
+        get_max_maker_vol(ctx, &base_coin)
+            .await
+            .map_to_mm(|e| OrderProcessingError::OrderCreationError(e.to_string()))?
+            .volume
+    } else {
+        volume
+    };
+
+    let levels = ladder_levels(ladder, &calculated_price, &total_volume)
+        .into_iter()
+        .map(|(level_price, level_volume)| {
+            let level_min_vol = min_vol.as_ref().map(|min_vol| {
+                if *min_vol > level_volume {
+                    level_volume.clone()
+                } else {
+                    min_vol.clone()
+                }
+            });
+            (level_min_vol, level_volume, level_price, false)
+        })
+        .collect();
+    Ok(levels)
+}
+
+/// Rebalances the ladder of the pair: the existing orders sorted by price are updated to the levels,
+/// the missing levels are created and the extra orders are cancelled.
+async fn execute_ladder_orders(
+    rates: PriceOracleResult<PairRates>,
+    cfg: SimpleCoinMarketMakerCfg,
+    key_trade_pair: String,
+    mut orders: Vec<MakerOrder>,
+    inventory: &InventorySnapshot<'_>,
+    ctx: &MmArc,
+) -> bool {
+    let ladder = match &cfg.ladder {
+        Some(ladder) => ladder,
+        None => return false,
+    };
+    let prepared = match rates {
+        Ok(rates) => prepare_ladder_orders(&rates, &cfg, ladder, &key_trade_pair, inventory, ctx).await,
+        Err(err) => MmError::err(OrderProcessingError::OrderUpdateError(err.to_string())),
+    };
+    let levels = match prepared {
+        Ok(levels) => levels,
+        Err(err) => {
+            error!(
+                "{err} - ladder cannot be placed for: {key_trade_pair} - cancel {} orders",
+                orders.len()
+            );
+            for order in orders {
+                cancel_single_order(ctx, order.uuid).await;
+            }
+            return false;
+        },
+    };
+
+    orders.sort_by(|a, b| a.price.cmp(&b.price));
+    let mut orders = orders.into_iter();
+    let mut all_placed = true;
+    for (level, order_params) in levels.into_iter().enumerate() {
+        match orders.next() {
+            Some(order) => {
+                if let Err(err) = update_single_order(order_params, &cfg, &order, &key_trade_pair, ctx).await {
+                    error!("Ladder level {level} order with uuid: {} for {key_trade_pair} cannot be updated - err: {err:?}", order.uuid);
+                    cancel_single_order(ctx, order.uuid).await;
+                    all_placed = false;
+                }
+            },
+            None => {
+                let cancel_previous = false;
+                if let Err(err) = create_single_order(order_params, &cfg, &key_trade_pair, cancel_previous, ctx).await {
+                    error!("{err} - ladder level {level} order cannot be created for: {key_trade_pair}.");
+                    all_placed = false;
+                }
+            },
+        }
+    }
+    // The ladder has been shrunk.
+    for order in orders {
+        cancel_single_order(ctx, order.uuid).await;
+    }
+    all_placed
+}
+
+/// Checks the ladder configurations of the pairs.
+fn validate_ladders(cfg: &SimpleMakerBotRegistry) -> Result<(), MmError<StartSimpleMakerBotError>> {
+    for (trading_pair, cfg) in cfg.iter() {
+        let ladder = match &cfg.ladder {
+            Some(ladder) => ladder,
+            None => continue,
+        };
+        let invalid_ladder = |reason: &str| {
+            MmError::err(StartSimpleMakerBotError::InvalidLadderConfiguration {
+                pair: trading_pair.clone(),
+                reason: reason.to_owned(),
+            })
+        };
+        if ladder.levels == 0 {
+            return invalid_ladder("'levels' must be positive");
+        }
+        if ladder.level_spread <= MmNumber::default() {
+            return invalid_ladder("'level_spread' must be positive");
+        }
+        if let VolumeDistribution::Exponential(factor) = &ladder.volume_distribution {
+            if *factor <= MmNumber::default() {
+                return invalid_ladder("the exponential distribution factor must be positive");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the inventory controls of the coins.
+fn validate_inventory(inventory_cfg: &CoinInventoryRegistry) -> Result<(), MmError<StartSimpleMakerBotError>> {
+    let zero = MmNumber::default();
+    let one = MmNumber::from(1);
+    for (coin, coin_cfg) in inventory_cfg.iter() {
+        let invalid_inventory = |reason: &str| {
+            MmError::err(StartSimpleMakerBotError::InvalidInventoryConfiguration {
+                coin: coin.clone(),
+                reason: reason.to_owned(),
+            })
+        };
+        if let Some(target_ratio) = &coin_cfg.target_ratio {
+            if *target_ratio < zero || *target_ratio > one {
+                return invalid_inventory("'target_ratio' must be from 0 to 1");
+            }
+        }
+        if let Some(skew_factor) = &coin_cfg.skew_factor {
+            if *skew_factor < zero || *skew_factor >= one {
+                return invalid_inventory("'skew_factor' must be from 0 to 1 (exclusive)");
+            }
+        }
+        if let (Some(min_balance), Some(max_balance)) = (&coin_cfg.min_balance, &coin_cfg.max_balance) {
+            if min_balance > max_balance {
+                return invalid_inventory("'min_balance' must not exceed 'max_balance'");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn pair_rates(
+    price_oracle: &PriceOracleRegistry,
+    price_quotes: &PriceQuotes,
+    cfg: &SimpleCoinMarketMakerCfg,
+) -> PriceOracleResult<PairRates> {
+    price_oracle.pair_rates(
+        price_quotes,
+        &cfg.base,
+        &cfg.rel,
+        cfg.price_providers.as_deref(),
+        cfg.price_elapsed_validity.unwrap_or(DEFAULT_PRICE_ELAPSED_VALIDITY),
+    )
+}
+
+/// Builds the price oracle from `price_oracle` or from the single `price_url` and checks the per-pair overrides.
+fn price_oracle_from_request(
+    req: &StartSimpleMakerBotRequest,
+) -> Result<PriceOracleRegistry, MmError<StartSimpleMakerBotError>> {
+    let price_oracle = match (&req.price_oracle, &req.price_url) {
+        (Some(_), Some(_)) => {
+            return MmError::err(StartSimpleMakerBotError::InvalidPriceOracleConfiguration(
+                "'price_url' and 'price_oracle' can't be used together".to_owned(),
+            ))
+        },
+        (Some(conf), None) => PriceOracleRegistry::from_conf(conf.clone())
+            .map_to_mm(|e| StartSimpleMakerBotError::InvalidPriceOracleConfiguration(e.to_string()))?,
+        (None, price_url) => {
+            PriceOracleRegistry::with_tickers_url(price_url.clone().unwrap_or_else(|| KMD_PRICE_ENDPOINT.to_string()))
+        },
+    };
+
+    for (trading_pair, cfg) in req.cfg.iter() {
+        if let Some(provider_names) = &cfg.price_providers {
+            price_oracle
+                .validate_pair_providers(trading_pair, provider_names)
+                .map_to_mm(|e| StartSimpleMakerBotError::InvalidPriceOracleConfiguration(e.to_string()))?;
+        }
+    }
+    Ok(price_oracle)
+}
+
 async fn process_bot_logic(ctx: &MmArc) {
     let simple_market_maker_bot_ctx = TradingBotContext::from_ctx(ctx).unwrap();
     let state = simple_market_maker_bot_ctx.trading_bot_states.lock().await;
-    let (cfg, price_url) = if let TradingBotState::Running(running_state) = &*state {
-        let res = (running_state.trading_bot_cfg.clone(), running_state.price_url.clone());
+    let (cfg, price_oracle, inventory_cfg) = if let TradingBotState::Running(running_state) = &*state {
+        let res = (
+            running_state.trading_bot_cfg.clone(),
+            running_state.price_oracle.clone(),
+            running_state.inventory_cfg.clone(),
+        );
         drop(state);
         res
     } else {
         drop(state);
         return;
     };
-    let rates_registry = match fetch_price_tickers(price_url.as_str()).await {
-        Ok(model) => {
-            info!("price successfully fetched from {price_url}");
-            model
-        },
-        Err(err) => {
-            let nb_orders = cancel_pending_orders(ctx, &cfg).await;
-            error!("error fetching price: {err:?} - cancel {nb_orders} orders");
-            return;
-        },
-    };
+    let price_quotes = price_oracle.fetch_quotes().await;
+    if price_quotes.is_empty() {
+        let nb_orders = cancel_pending_orders(ctx, &cfg).await;
+        error!("error fetching price from all the providers - cancel {nb_orders} orders");
+        return;
+    }
+    let inventory = fetch_inventory(ctx, &cfg, &inventory_cfg, &price_oracle, &price_quotes).await;
 
     let mut memoization_pair_registry: HashSet<String> = HashSet::new();
     let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).unwrap();
     let maker_orders = ordermatch_ctx.maker_orders_ctx.lock().orders.clone();
     let mut futures_order_update = Vec::with_capacity(0);
+    let mut updated_pairs = Vec::with_capacity(0);
+    let mut ladder_orders: HashMap<String, Vec<MakerOrder>> = HashMap::new();
     // Iterating over maker orders and update order that are present in cfg as the key_trade_pair e.g KMD/LTC
-    for (uuid, order_mutex) in maker_orders.into_iter() {
+    for (_uuid, order_mutex) in maker_orders.into_iter() {
         let order = order_mutex.lock().await;
         let key_trade_pair = TradingPair::new(order.base.clone(), order.rel.clone());
         match cfg.get(&key_trade_pair.as_combination()) {
@@ -648,15 +1151,22 @@ async fn process_bot_logic(ctx: &MmArc) {
                 if !coin_cfg.enable {
                     continue;
                 }
+                // The ladders are rebalanced as a whole below.
+                if coin_cfg.ladder.is_some() {
+                    ladder_orders
+                        .entry(key_trade_pair.as_combination())
+                        .or_default()
+                        .push(order.clone());
+                    continue;
+                }
                 let cloned_infos = (
                     ctx.clone(),
-                    rates_registry
-                        .get_cex_rates(&coin_cfg.base, &coin_cfg.rel)
-                        .unwrap_or_default(),
+                    pair_rates(&price_oracle, &price_quotes, coin_cfg),
                     key_trade_pair.clone(),
                     coin_cfg.clone(),
                 );
-                futures_order_update.push(execute_update_order(uuid, order.clone(), cloned_infos));
+                futures_order_update.push(execute_update_order(order.clone(), cloned_infos, &inventory));
+                updated_pairs.push(key_trade_pair.as_combination());
                 memoization_pair_registry.insert(key_trade_pair.as_combination());
             },
             _ => continue,
@@ -664,31 +1174,157 @@ async fn process_bot_logic(ctx: &MmArc) {
     }
 
     let all_updated_orders_tasks = futures::future::join_all(futures_order_update);
-    let _results_order_updates = all_updated_orders_tasks.await;
+    let results_order_updates = all_updated_orders_tasks.await;
 
     let mut futures_order_creation = Vec::with_capacity(0);
+    let mut created_pairs = Vec::with_capacity(0);
+    let mut futures_ladder = Vec::with_capacity(0);
+    let mut ladder_pairs = Vec::with_capacity(0);
     // Now iterate over the registry and for every pairs that are not hit let's create an order
-    for (trading_pair, cur_cfg) in cfg.into_iter() {
-        match memoization_pair_registry.get(&trading_pair) {
+    for (trading_pair, cur_cfg) in cfg.iter() {
+        match memoization_pair_registry.get(trading_pair) {
             Some(_) => continue,
             None => {
                 if !cur_cfg.enable {
                     continue;
                 }
-                let rates_infos = rates_registry
-                    .get_cex_rates(&cur_cfg.base, &cur_cfg.rel)
-                    .unwrap_or_default();
+                let rates_infos = pair_rates(&price_oracle, &price_quotes, cur_cfg);
+                if cur_cfg.ladder.is_some() {
+                    let orders = ladder_orders.remove(trading_pair).unwrap_or_default();
+                    futures_ladder.push(execute_ladder_orders(
+                        rates_infos,
+                        cur_cfg.clone(),
+                        trading_pair.clone(),
+                        orders,
+                        &inventory,
+                        ctx,
+                    ));
+                    ladder_pairs.push(trading_pair.clone());
+                    continue;
+                }
                 futures_order_creation.push(execute_create_single_order(
                     rates_infos,
-                    cur_cfg,
+                    cur_cfg.clone(),
                     trading_pair.clone(),
+                    &inventory,
                     ctx,
                 ));
+                created_pairs.push(trading_pair.clone());
             },
         };
     }
     let all_created_orders_tasks = futures::future::join_all(futures_order_creation);
-    let _results_order_creations = all_created_orders_tasks.await;
+    let all_ladders_tasks = futures::future::join_all(futures_ladder);
+    let (results_order_creations, results_ladders) =
+        futures::future::join(all_created_orders_tasks, all_ladders_tasks).await;
+
+    let mut pair_results: HashMap<String, bool> = HashMap::new();
+    let results = updated_pairs
+        .into_iter()
+        .zip(results_order_updates)
+        .chain(created_pairs.into_iter().zip(results_order_creations))
+        .chain(ladder_pairs.into_iter().zip(results_ladders));
+    for (trading_pair, succeeded) in results {
+        *pair_results.entry(trading_pair).or_insert(true) &= succeeded;
+    }
+    update_bot_status(ctx, &cfg, &inventory, &price_oracle, &price_quotes, pair_results).await;
+}
+
+/// Updates the inventory, the PnL and the pairs state reported by `get_simple_market_maker_bot_status`.
+async fn update_bot_status(
+    ctx: &MmArc,
+    cfg: &SimpleMakerBotRegistry,
+    inventory: &InventorySnapshot<'_>,
+    price_oracle: &PriceOracleRegistry,
+    price_quotes: &PriceQuotes,
+    pair_results: HashMap<String, bool>,
+) {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).unwrap();
+    let maker_orders = ordermatch_ctx.maker_orders_ctx.lock().orders.clone();
+    let mut pair_orders: HashMap<String, Vec<Uuid>> = HashMap::new();
+    for (uuid, order_mutex) in maker_orders {
+        let order = order_mutex.lock().await;
+        let key_trade_pair = TradingPair::new(order.base.clone(), order.rel.clone()).as_combination();
+        if cfg.contains_key(&key_trade_pair) {
+            pair_orders.entry(key_trade_pair).or_default().push(uuid);
+        }
+    }
+
+    let pairs = cfg
+        .iter()
+        .map(|(trading_pair, pair_cfg)| {
+            let pair_status = PairStatus {
+                enabled: pair_cfg.enable,
+                price: pair_rates(price_oracle, price_quotes, pair_cfg)
+                    .ok()
+                    .map(|rates| rates.price.to_decimal()),
+                skew: inventory.price_skew(&pair_cfg.base, &pair_cfg.rel).to_decimal(),
+                orders: pair_orders.remove(trading_pair).unwrap_or_default(),
+                last_cycle_succeeded: pair_results.get(trading_pair).copied().unwrap_or(false),
+            };
+            (trading_pair.clone(), pair_status)
+        })
+        .collect();
+
+    let coins = inventory
+        .balances
+        .iter()
+        .map(|(ticker, balance)| {
+            let coin_status = CoinInventoryStatus {
+                balance: balance.to_decimal(),
+                usd_price: inventory.usd_prices.get(ticker).map(MmNumber::to_decimal),
+                usd_value: inventory.usd_value(ticker).map(|value| value.to_decimal()),
+                share: inventory.share(ticker).map(|share| share.to_decimal()),
+                target_ratio: inventory
+                    .cfg
+                    .get(ticker)
+                    .and_then(|coin_cfg| coin_cfg.target_ratio.as_ref())
+                    .map(MmNumber::to_decimal),
+            };
+            (ticker.clone(), coin_status)
+        })
+        .collect();
+
+    let simple_market_maker_bot_ctx = TradingBotContext::from_ctx(ctx).unwrap();
+    let mut bot_status_guard = simple_market_maker_bot_ctx.bot_status.lock().await;
+    let bot_status = &mut *bot_status_guard;
+    let initial_inventory = bot_status.initial_inventory.get_or_insert_with(|| {
+        inventory
+            .balances
+            .iter()
+            .filter_map(|(ticker, balance)| {
+                let usd_price = inventory.usd_prices.get(ticker)?;
+                Some((ticker.clone(), (balance.clone(), usd_price.clone())))
+            })
+            .collect()
+    });
+    bot_status.pnl = bot_pnl(initial_inventory, inventory);
+    bot_status.inventory = coins;
+    bot_status.pairs = pairs;
+    bot_status.updated_at = Some(now_sec());
+}
+
+/// Compares the current inventory value with the value of the initial balances at the current prices.
+/// Returns `None` if any of the initially priced coins has no current balance or price.
+fn bot_pnl(
+    initial_inventory: &HashMap<String, (MmNumber, MmNumber)>,
+    inventory: &InventorySnapshot<'_>,
+) -> Option<BotPnl> {
+    let mut initial_value = MmNumber::default();
+    let mut hold_value = MmNumber::default();
+    let mut current_value = MmNumber::default();
+    for (ticker, (initial_balance, initial_price)) in initial_inventory.iter() {
+        let usd_price = inventory.usd_prices.get(ticker)?;
+        initial_value += initial_balance * initial_price;
+        hold_value += initial_balance * usd_price;
+        current_value += inventory.usd_value(ticker)?;
+    }
+    Some(BotPnl {
+        initial_value_usd: initial_value.to_decimal(),
+        hold_value_usd: hold_value.to_decimal(),
+        pnl_usd: (&current_value - &hold_value).to_decimal(),
+        current_value_usd: current_value.to_decimal(),
+    })
 }
 
 pub async fn lp_bot_loop(ctx: MmArc) {
@@ -727,6 +1363,9 @@ pub async fn start_simple_market_maker_bot(ctx: MmArc, req: StartSimpleMakerBotR
         TradingBotState::Running { .. } => MmError::err(StartSimpleMakerBotError::AlreadyStarted),
         TradingBotState::Stopping(_) => MmError::err(StartSimpleMakerBotError::CannotStartFromStopping),
         TradingBotState::Stopped(_) => {
+            let price_oracle = price_oracle_from_request(&req)?;
+            validate_ladders(&req.cfg)?;
+            validate_inventory(&req.inventory)?;
             let dispatcher_ctx = DispatcherContext::from_ctx(&ctx).unwrap();
             let mut dispatcher = dispatcher_ctx.dispatcher.write().await;
             dispatcher.add_listener(simple_market_maker_bot_ctx.clone());
@@ -738,9 +1377,12 @@ pub async fn start_simple_market_maker_bot(ctx: MmArc, req: StartSimpleMakerBotR
             *state = RunningState {
                 trading_bot_cfg: req.cfg,
                 bot_refresh_rate: refresh_rate,
-                price_url: req.price_url.unwrap_or_else(|| KMD_PRICE_ENDPOINT.to_string()),
+                price_oracle: Arc::new(price_oracle),
+                inventory_cfg: req.inventory,
             }
             .into();
+            // The PnL is calculated from the first cycle of the new run.
+            *simple_market_maker_bot_ctx.bot_status.lock().await = BotStatus::default();
             drop(state);
             let event: TradingBotEvent = TradingBotStarted { nb_pairs }.into();
             dispatcher.dispatch_async(ctx.clone(), event.into()).await;
@@ -752,6 +1394,24 @@ pub async fn start_simple_market_maker_bot(ctx: MmArc, req: StartSimpleMakerBotR
     }
 }
 
+pub async fn get_simple_market_maker_bot_status(ctx: MmArc, _req: Json) -> SimpleMakerBotStatusResult {
+    let simple_market_maker_bot_ctx =
+        TradingBotContext::from_ctx(&ctx).map_to_mm(SimpleMakerBotStatusError::InternalError)?;
+    let state = match &*simple_market_maker_bot_ctx.trading_bot_states.lock().await {
+        TradingBotState::Running(_) => SimpleMakerBotState::Running,
+        TradingBotState::Stopping(_) => SimpleMakerBotState::Stopping,
+        TradingBotState::Stopped(_) => SimpleMakerBotState::Stopped,
+    };
+    let bot_status = simple_market_maker_bot_ctx.bot_status.lock().await;
+    Ok(SimpleMakerBotStatusResponse {
+        state,
+        inventory: bot_status.inventory.clone(),
+        pnl: bot_status.pnl.clone(),
+        pairs: bot_status.pairs.clone(),
+        updated_at: bot_status.updated_at,
+    })
+}
+
 pub async fn stop_simple_market_maker_bot(ctx: MmArc, _req: Json) -> StopSimpleMakerBotResult {
     let simple_market_maker_bot_ctx = TradingBotContext::from_ctx(&ctx).unwrap();
     let mut state = simple_market_maker_bot_ctx.trading_bot_states.lock().await;
@@ -778,10 +1438,14 @@ pub async fn stop_simple_market_maker_bot(ctx: MmArc, _req: Json) -> StopSimpleM
 
 #[cfg(test)]
 mod tests {
-    use super::{start_simple_market_maker_bot, stop_simple_market_maker_bot, StartSimpleMakerBotRequest};
+    use super::{get_simple_market_maker_bot_status, start_simple_market_maker_bot, stop_simple_market_maker_bot,
+                update_bot_status, InventorySnapshot, PriceOracleRegistry, PriceQuotes, SimpleCoinMarketMakerCfg,
+                SimpleMakerBotRegistry, SimpleMakerBotState, StartSimpleMakerBotRequest, KMD_PRICE_ENDPOINT};
     use common::block_on;
+    use mm2_number::MmNumber;
     use mm2_test_helpers::for_tests::mm_ctx_with_iguana;
     use serde_json::Value as Json;
+    use std::collections::HashMap;
 
     #[test]
     fn test_start_and_stop_simple_market_maker_bot_from_ctx() {
@@ -794,7 +1458,9 @@ mod tests {
         let req = StartSimpleMakerBotRequest {
             cfg: Default::default(),
             price_url: None,
+            price_oracle: None,
             bot_refresh_rate: None,
+            inventory: Default::default(),
         };
         let answer = block_on(start_simple_market_maker_bot(ctx, req)).unwrap();
         assert_eq!(answer.get_result(), "Success");
@@ -802,11 +1468,115 @@ mod tests {
         let req = StartSimpleMakerBotRequest {
             cfg: Default::default(),
             price_url: None,
+            price_oracle: None,
             bot_refresh_rate: None,
+            inventory: Default::default(),
         };
         let answer = block_on(start_simple_market_maker_bot(cloned_ctx, req));
         assert!(answer.is_err());
         let answer = block_on(stop_simple_market_maker_bot(another_cloned_ctx, Json::default())).unwrap();
         assert_eq!(answer.get_result(), "Success");
     }
+
+    #[test]
+    fn test_simple_market_maker_bot_status() {
+        let ctx = mm_ctx_with_iguana(None);
+        let status = block_on(get_simple_market_maker_bot_status(ctx.clone(), Json::Null)).unwrap();
+        assert!(matches!(status.state, SimpleMakerBotState::Stopped));
+        assert!(status.inventory.is_empty());
+        assert!(status.pnl.is_none());
+        assert!(status.pairs.is_empty());
+        assert_eq!(status.updated_at, None);
+
+        let pair_cfg = SimpleCoinMarketMakerCfg {
+            base: "KMD".to_string(),
+            rel: "LTC".to_string(),
+            min_volume: None,
+            max_volume: None,
+            spread: MmNumber::from("1.02"),
+            base_confs: None,
+            base_nota: None,
+            rel_confs: None,
+            rel_nota: None,
+            enable: true,
+            price_elapsed_validity: None,
+            check_last_bidirectional_trade_thresh_hold: None,
+            max: Some(true),
+            min_base_price: None,
+            min_rel_price: None,
+            min_pair_price: None,
+            price_providers: None,
+            ladder: None,
+            min_taker_reputation: None,
+        };
+        let cfg: SimpleMakerBotRegistry = HashMap::from([("KMD/LTC".to_string(), pair_cfg)]);
+        let inventory_cfg = HashMap::new();
+        let price_oracle = PriceOracleRegistry::with_tickers_url(KMD_PRICE_ENDPOINT.to_string());
+        let pair_results = HashMap::from([("KMD/LTC".to_string(), true)]);
+
+        let inventory = InventorySnapshot::new(
+            &inventory_cfg,
+            HashMap::from([
+                ("KMD".to_string(), MmNumber::from(150)),
+                ("LTC".to_string(), MmNumber::from(1)),
+            ]),
+            HashMap::from([
+                ("KMD".to_string(), MmNumber::from(1)),
+                ("LTC".to_string(), MmNumber::from(50)),
+            ]),
+        );
+        block_on(update_bot_status(
+            &ctx,
+            &cfg,
+            &inventory,
+            &price_oracle,
+            &PriceQuotes::default(),
+            pair_results.clone(),
+        ));
+
+        let status = block_on(get_simple_market_maker_bot_status(ctx.clone(), Json::Null)).unwrap();
+        assert!(status.updated_at.is_some());
+        let kmd = &status.inventory["KMD"];
+        assert_eq!(kmd.balance, MmNumber::from(150).to_decimal());
+        assert_eq!(kmd.usd_value, Some(MmNumber::from(150).to_decimal()));
+        assert_eq!(kmd.share, Some(MmNumber::from("0.75").to_decimal()));
+        let pnl = status.pnl.expect("The PnL must be known if all the coins are priced");
+        assert_eq!(pnl.initial_value_usd, MmNumber::from(200).to_decimal());
+        assert_eq!(pnl.pnl_usd, MmNumber::default().to_decimal());
+        let pair = &status.pairs["KMD/LTC"];
+        assert!(pair.enabled);
+        assert!(pair.last_cycle_succeeded);
+        assert!(pair.orders.is_empty());
+        // No price quotes have been fetched.
+        assert_eq!(pair.price, None);
+
+        // 10 KMD are sold for 0.2 LTC, then LTC doubles in price.
+        let inventory = InventorySnapshot::new(
+            &inventory_cfg,
+            HashMap::from([
+                ("KMD".to_string(), MmNumber::from(140)),
+                ("LTC".to_string(), MmNumber::from("1.2")),
+            ]),
+            HashMap::from([
+                ("KMD".to_string(), MmNumber::from(1)),
+                ("LTC".to_string(), MmNumber::from(100)),
+            ]),
+        );
+        block_on(update_bot_status(
+            &ctx,
+            &cfg,
+            &inventory,
+            &price_oracle,
+            &PriceQuotes::default(),
+            pair_results,
+        ));
+
+        let status = block_on(get_simple_market_maker_bot_status(ctx, Json::Null)).unwrap();
+        assert_eq!(status.inventory["LTC"].balance, MmNumber::from("1.2").to_decimal());
+        let pnl = status.pnl.unwrap();
+        assert_eq!(pnl.initial_value_usd, MmNumber::from(200).to_decimal());
+        assert_eq!(pnl.hold_value_usd, MmNumber::from(250).to_decimal());
+        assert_eq!(pnl.current_value_usd, MmNumber::from(260).to_decimal());
+        assert_eq!(pnl.pnl_usd, MmNumber::from(10).to_decimal());
+    }
 }
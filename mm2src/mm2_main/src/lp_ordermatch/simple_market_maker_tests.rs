@@ -1,8 +1,12 @@
-use crate::mm2::{lp_ordermatch::lp_bot::simple_market_maker_bot::vwap,
-                 lp_ordermatch::lp_bot::SimpleCoinMarketMakerCfg,
+use crate::mm2::{lp_ordermatch::lp_bot::simple_market_maker_bot::{ladder_levels, order_volume_delta, vwap,
+                                                                  InventorySnapshot},
+                 lp_ordermatch::lp_bot::{CoinInventoryCfg, LadderCfg, SimpleCoinMarketMakerCfg, VolumeDistribution},
+                 lp_ordermatch::MakerOrderBuilder,
                  lp_swap::{MakerSavedSwap, SavedSwap}};
+use coins::{MmCoinEnum, TestCoin};
 use common::{block_on, log::UnifiedLoggerBuilder};
 use mm2_number::MmNumber;
+use std::collections::HashMap;
 
 fn generate_swaps_from_values(swaps_value: Vec<(MmNumber, MmNumber)>) -> Vec<SavedSwap> {
     swaps_value
@@ -29,6 +33,9 @@ fn generate_cfg_from_params(base: String, rel: String, spread: MmNumber) -> Simp
         min_base_price: None,
         min_rel_price: None,
         min_pair_price: None,
+        price_providers: None,
+        ladder: None,
+        min_taker_reputation: None,
     }
 }
 
@@ -192,4 +199,179 @@ mod tests {
         );
         assert_eq!(calculated_price.to_decimal(), expected_price.to_decimal());
     }
+
+    #[test]
+    fn test_ladder_levels() {
+        let mut ladder = LadderCfg {
+            levels: 3,
+            level_spread: MmNumber::from("1.01"),
+            volume_distribution: VolumeDistribution::Flat,
+        };
+        let price = MmNumber::from(10);
+        let levels = ladder_levels(&ladder, &price, &MmNumber::from(6));
+        let expected = vec![
+            (MmNumber::from(10), MmNumber::from(2)),
+            (MmNumber::from("10.1"), MmNumber::from(2)),
+            (MmNumber::from("10.201"), MmNumber::from(2)),
+        ];
+        assert_eq!(levels, expected);
+
+        ladder.volume_distribution = VolumeDistribution::Linear;
+        let volumes: Vec<_> = ladder_levels(&ladder, &price, &MmNumber::from(6))
+            .into_iter()
+            .map(|(_, volume)| volume)
+            .collect();
+        assert_eq!(volumes, vec![MmNumber::from(1), MmNumber::from(2), MmNumber::from(3)]);
+
+        ladder.volume_distribution = VolumeDistribution::Exponential(MmNumber::from(2));
+        let volumes: Vec<_> = ladder_levels(&ladder, &price, &MmNumber::from(7))
+            .into_iter()
+            .map(|(_, volume)| volume)
+            .collect();
+        assert_eq!(volumes, vec![MmNumber::from(1), MmNumber::from(2), MmNumber::from(4)]);
+    }
+
+    #[test]
+    fn test_ladder_rebalance_after_fill() {
+        let base_coin = MmCoinEnum::Test(TestCoin::new("KMD"));
+        let rel_coin = MmCoinEnum::Test(TestCoin::new("LTC"));
+        let ladder = LadderCfg {
+            levels: 2,
+            level_spread: MmNumber::from("1.01"),
+            volume_distribution: VolumeDistribution::Flat,
+        };
+        let levels = ladder_levels(&ladder, &MmNumber::from(10), &MmNumber::from(4));
+        let mut orders: Vec<_> = levels
+            .iter()
+            .map(|(price, volume)| {
+                MakerOrderBuilder::new(&base_coin, &rel_coin)
+                    .with_price(price.clone())
+                    .with_max_base_vol(volume.clone())
+                    .build_unchecked()
+            })
+            .collect();
+        for (order, (_, volume)) in orders.iter().zip(levels.iter()) {
+            assert_eq!(order_volume_delta(order, volume), None);
+        }
+
+        // The first level is partially filled and the balance left is split between the levels again.
+        orders[0].max_base_vol = MmNumber::from("0.5");
+        let levels = ladder_levels(&ladder, &MmNumber::from(10), &MmNumber::from(3));
+        assert_eq!(order_volume_delta(&orders[0], &levels[0].1), Some(MmNumber::from(1)));
+        assert_eq!(
+            order_volume_delta(&orders[1], &levels[1].1),
+            Some(MmNumber::from("-0.5"))
+        );
+    }
+
+    #[test]
+    fn test_inventory_skew_and_balance_limits() {
+        let mut inventory_cfg = HashMap::new();
+        inventory_cfg.insert("KMD".to_string(), CoinInventoryCfg {
+            target_ratio: Some(MmNumber::from("0.5")),
+            skew_factor: Some(MmNumber::from("0.1")),
+            min_balance: Some(MmNumber::from(10)),
+            max_balance: None,
+        });
+        inventory_cfg.insert("LTC".to_string(), CoinInventoryCfg {
+            target_ratio: None,
+            skew_factor: None,
+            min_balance: None,
+            max_balance: Some(MmNumber::from(3)),
+        });
+        let balances = HashMap::from([
+            ("KMD".to_string(), MmNumber::from(150)),
+            ("LTC".to_string(), MmNumber::from(1)),
+        ]);
+        let usd_prices = HashMap::from([
+            ("KMD".to_string(), MmNumber::from(1)),
+            ("LTC".to_string(), MmNumber::from(50)),
+        ]);
+        let new_inventory = || InventorySnapshot::new(&inventory_cfg, balances.clone(), usd_prices.clone());
+        let inventory = new_inventory();
+
+        // KMD is 75% of the inventory instead of 50%, so the KMD/LTC price is decreased by 0.1 * 0.25.
+        assert_eq!(inventory.price_skew("KMD", "LTC"), MmNumber::from("0.975"));
+        // The orders selling LTC for KMD buy KMD, so the price is increased.
+        assert_eq!(inventory.price_skew("LTC", "KMD"), MmNumber::from("1.025"));
+
+        let base_balance = MmNumber::from(150);
+        let price = MmNumber::from("0.02");
+        // The LTC balance can grow by 2 LTC only that is 100 KMD.
+        let (volume, is_max) = new_inventory()
+            .apply_balance_limits("KMD", "LTC", &base_balance, &price, MmNumber::default(), true)
+            .unwrap();
+        assert_eq!(volume, MmNumber::from(100));
+        assert!(!is_max);
+
+        // 140 KMD can be sold until min_balance is reached, but LTC max_balance limits the volume.
+        let (volume, is_max) = new_inventory()
+            .apply_balance_limits("KMD", "LTC", &base_balance, &price, MmNumber::from(120), false)
+            .unwrap();
+        assert_eq!(volume, MmNumber::from(100));
+        assert!(!is_max);
+
+        let (volume, is_max) = new_inventory()
+            .apply_balance_limits("KMD", "LTC", &base_balance, &price, MmNumber::from(50), false)
+            .unwrap();
+        assert_eq!(volume, MmNumber::from(50));
+        assert!(!is_max);
+
+        let low_balance = MmNumber::from(10);
+        new_inventory()
+            .apply_balance_limits("KMD", "LTC", &low_balance, &price, MmNumber::from(5), false)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_balance_limits_take_other_bot_orders_into_account() {
+        let mut inventory_cfg = HashMap::new();
+        inventory_cfg.insert("KMD".to_string(), CoinInventoryCfg {
+            target_ratio: None,
+            skew_factor: None,
+            min_balance: Some(MmNumber::from(10)),
+            max_balance: None,
+        });
+        inventory_cfg.insert("LTC".to_string(), CoinInventoryCfg {
+            target_ratio: None,
+            skew_factor: None,
+            min_balance: None,
+            max_balance: Some(MmNumber::from(3)),
+        });
+        let balances = HashMap::from([
+            ("KMD".to_string(), MmNumber::from(150)),
+            ("LTC".to_string(), MmNumber::from(1)),
+            ("BTC".to_string(), MmNumber::from(1)),
+        ]);
+        let inventory = InventorySnapshot::new(&inventory_cfg, balances, HashMap::new());
+        let kmd_balance = MmNumber::from(150);
+
+        // KMD/BTC commits 100 KMD of 140 KMD available above min_balance.
+        let (volume, _) = inventory
+            .apply_balance_limits("KMD", "BTC", &kmd_balance, &MmNumber::from("0.0001"), 100.into(), false)
+            .unwrap();
+        assert_eq!(volume, MmNumber::from(100));
+
+        // KMD/LTC may sell the remaining 40 KMD only.
+        let price = MmNumber::from("0.02");
+        let (volume, is_max) = inventory
+            .apply_balance_limits("KMD", "LTC", &kmd_balance, &price, MmNumber::default(), true)
+            .unwrap();
+        assert_eq!(volume, MmNumber::from(40));
+        assert!(!is_max);
+
+        // The KMD orders may receive 0.8 LTC, so BTC/LTC may buy 1.2 LTC more only.
+        let (volume, _) = inventory
+            .apply_balance_limits("BTC", "LTC", &MmNumber::from(1), &MmNumber::from(2), 1.into(), false)
+            .unwrap();
+        assert_eq!(volume, MmNumber::from("0.6"));
+
+        // Nothing is left for the next KMD order.
+        inventory
+            .apply_balance_limits("KMD", "BTC", &kmd_balance, &MmNumber::from("0.0001"), 1.into(), false)
+            .unwrap_err();
+        inventory
+            .apply_balance_limits("BTC", "LTC", &MmNumber::from(1), &MmNumber::from(2), 1.into(), false)
+            .unwrap_err();
+    }
 }
@@ -6,12 +6,17 @@ use derive_more::Display;
 use futures::lock::Mutex as AsyncMutex;
 use http::StatusCode;
 use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::RpcMethodRegistry;
 use mm2_err_handle::prelude::*;
 use mm2_libp2p::{encode_message, NetworkInfo, PeerId, RelayAddress, RelayAddressError};
 use serde_json::{self as json, Value as Json};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+#[path = "lp_stats/market_data.rs"] mod market_data;
+pub use market_data::{swaps_ohlcv, swaps_ticker};
+
 use crate::mm2::lp_network::{add_reserved_peer_addresses, lp_network_ports, request_peers, NetIdError, P2PRequest,
                              ParseAddressError, PeerDecodedResponse};
 use std::str::FromStr;
@@ -414,3 +419,42 @@ pub async fn stop_version_stat_collection(ctx: MmArc, _req: Json) -> NodeVersion
 
     Ok("success".into())
 }
+
+/// Registers the stats mmrpc 2.0 methods.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "add_node_to_version_stat",
+        "Adds the node to the version stats collection.",
+        mmrpc_handler!(add_node_to_version_stat),
+    );
+    registry.register(
+        "remove_node_from_version_stat",
+        "Removes the node from the version stats collection.",
+        mmrpc_handler!(remove_node_from_version_stat),
+    );
+    registry.register(
+        "start_version_stat_collection",
+        "Starts collecting the versions of the added nodes.",
+        mmrpc_handler!(start_version_stat_collection),
+    );
+    registry.register(
+        "stop_version_stat_collection",
+        "Stops collecting the versions of the added nodes.",
+        mmrpc_handler!(stop_version_stat_collection),
+    );
+    registry.register(
+        "swaps_ohlcv",
+        "Returns the OHLCV candles of the pair aggregated from the observed successful swaps.",
+        mmrpc_handler!(swaps_ohlcv),
+    );
+    registry.register(
+        "swaps_ticker",
+        "Returns the 24h ticker of the pair aggregated from the observed successful swaps.",
+        mmrpc_handler!(swaps_ticker),
+    );
+    registry.register(
+        "update_version_stat_collection",
+        "Updates the interval of the version stats collection.",
+        mmrpc_handler!(update_version_stat_collection),
+    );
+}
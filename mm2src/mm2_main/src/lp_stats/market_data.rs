@@ -0,0 +1,372 @@
+//! Market data aggregated from the successful swaps collected in the `stats_swaps` table.
+//!
+//! The price of a swap is the amount of `rel` coin paid per one `base` coin regardless of which side was the maker.
+
+use common::{now_sec, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigDecimal, MmNumber};
+
+/// Supported candle intervals in seconds: 1m, 5m, 15m, 30m, 1h, 4h, 1d, 1w.
+const SUPPORTED_INTERVALS: &[u64] = &[60, 300, 900, 1800, 3600, 14400, 86400, 604800];
+const MAX_CANDLES: u64 = 1000;
+const DEFAULT_CANDLES: u64 = 500;
+const TICKER_PERIOD: u64 = 24 * 60 * 60;
+
+pub type MarketDataResult<T> = Result<T, MmError<MarketDataError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum MarketDataError {
+    #[display(fmt = "Invalid request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Interval {} is not supported, expected one of {:?}", interval, supported)]
+    UnsupportedInterval { interval: u64, supported: Vec<u64> },
+    #[display(fmt = "{} is only supported in native mode", _0)]
+    UnsupportedMode(String),
+    #[display(fmt = "Database error: {}", _0)]
+    DatabaseError(String),
+}
+
+impl HttpStatusCode for MarketDataError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            MarketDataError::InvalidRequest(_) | MarketDataError::UnsupportedInterval { .. } => StatusCode::BAD_REQUEST,
+            MarketDataError::UnsupportedMode(_) => StatusCode::METHOD_NOT_ALLOWED,
+            MarketDataError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The swap of the pair converted to the `base/rel` direction.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+#[derive(Debug)]
+struct PairTrade {
+    timestamp: u64,
+    price: MmNumber,
+    base_volume: MmNumber,
+    rel_volume: MmNumber,
+}
+
+#[cfg(target_arch = "wasm32")]
+fn select_pair_trades(_ctx: &MmArc, _base: &str, _rel: &str, _from: u64, _to: u64) -> MarketDataResult<Vec<PairTrade>> {
+    MmError::err(MarketDataError::UnsupportedMode("Swaps market data".into()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn select_pair_trades(ctx: &MmArc, base: &str, rel: &str, from: u64, to: u64) -> MarketDataResult<Vec<PairTrade>> {
+    use crate::mm2::database::stats_swaps::select_successful_swaps_for_pair;
+    use common::log::warn;
+    use std::str::FromStr;
+
+    let conn = ctx.sqlite_connection();
+    let swaps = select_successful_swaps_for_pair(&conn, base, rel, from, to)
+        .map_to_mm(|e| MarketDataError::DatabaseError(e.to_string()))?;
+    drop(conn);
+
+    let parse_amount = |amount: &str| BigDecimal::from_str(amount).ok().map(MmNumber::from);
+    let trades = swaps
+        .into_iter()
+        .filter_map(|swap| {
+            let (maker_amount, taker_amount) =
+                match (parse_amount(&swap.maker_amount), parse_amount(&swap.taker_amount)) {
+                    (Some(maker_amount), Some(taker_amount)) => (maker_amount, taker_amount),
+                    _ => {
+                        warn!("Invalid amounts of the stats swap: {:?}", swap);
+                        return None;
+                    },
+                };
+            let (base_volume, rel_volume) = if swap.maker_coin == base {
+                (maker_amount, taker_amount)
+            } else {
+                (taker_amount, maker_amount)
+            };
+            // The non-positive volumes would produce a zero, negative or undefined price.
+            let zero = MmNumber::from(0);
+            if base_volume <= zero || rel_volume <= zero {
+                warn!("Non-positive volumes of the stats swap: {:?}", swap);
+                return None;
+            }
+            Some(PairTrade {
+                timestamp: swap.finished_at,
+                price: &rel_volume / &base_volume,
+                base_volume,
+                rel_volume,
+            })
+        })
+        .collect();
+    Ok(trades)
+}
+
+#[derive(Debug, Serialize)]
+pub struct Candle {
+    /// The candle open time in seconds, a multiple of the interval.
+    pub timestamp: u64,
+    pub open: BigDecimal,
+    pub high: BigDecimal,
+    pub low: BigDecimal,
+    pub close: BigDecimal,
+    pub base_volume: BigDecimal,
+    pub rel_volume: BigDecimal,
+    pub trades_count: u64,
+}
+
+struct CandleBuilder {
+    timestamp: u64,
+    open: MmNumber,
+    high: MmNumber,
+    low: MmNumber,
+    close: MmNumber,
+    base_volume: MmNumber,
+    rel_volume: MmNumber,
+    trades_count: u64,
+}
+
+impl CandleBuilder {
+    fn new(timestamp: u64, trade: &PairTrade) -> Self {
+        CandleBuilder {
+            timestamp,
+            open: trade.price.clone(),
+            high: trade.price.clone(),
+            low: trade.price.clone(),
+            close: trade.price.clone(),
+            base_volume: trade.base_volume.clone(),
+            rel_volume: trade.rel_volume.clone(),
+            trades_count: 1,
+        }
+    }
+
+    fn add_trade(&mut self, trade: &PairTrade) {
+        if trade.price > self.high {
+            self.high = trade.price.clone();
+        }
+        if trade.price < self.low {
+            self.low = trade.price.clone();
+        }
+        self.close = trade.price.clone();
+        self.base_volume += &trade.base_volume;
+        self.rel_volume += &trade.rel_volume;
+        self.trades_count += 1;
+    }
+
+    fn build(self) -> Candle {
+        Candle {
+            timestamp: self.timestamp,
+            open: self.open.to_decimal(),
+            high: self.high.to_decimal(),
+            low: self.low.to_decimal(),
+            close: self.close.to_decimal(),
+            base_volume: self.base_volume.to_decimal(),
+            rel_volume: self.rel_volume.to_decimal(),
+            trades_count: self.trades_count,
+        }
+    }
+}
+
+/// Aggregates the trades sorted by timestamp into candles.
+/// Intervals without trades are skipped.
+fn aggregate_candles(trades: &[PairTrade], interval: u64) -> Vec<Candle> {
+    let mut candles = Vec::new();
+    let mut current: Option<CandleBuilder> = None;
+    for trade in trades {
+        let timestamp = trade.timestamp - trade.timestamp % interval;
+        match current {
+            Some(ref mut candle) if candle.timestamp == timestamp => candle.add_trade(trade),
+            _ => {
+                if let Some(candle) = current.replace(CandleBuilder::new(timestamp, trade)) {
+                    candles.push(candle.build());
+                }
+            },
+        }
+    }
+    candles.extend(current.map(CandleBuilder::build));
+    candles
+}
+
+#[derive(Deserialize)]
+pub struct SwapsOhlcvRequest {
+    base: String,
+    rel: String,
+    /// The candle interval in seconds.
+    interval: u64,
+    /// Defaults to `to_timestamp - interval * 500`.
+    from_timestamp: Option<u64>,
+    /// Defaults to the current time.
+    to_timestamp: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SwapsOhlcvResponse {
+    pub base: String,
+    pub rel: String,
+    pub interval: u64,
+    pub candles: Vec<Candle>,
+}
+
+/// Returns OHLCV candles of the pair built from the successful swaps observed by the node.
+pub async fn swaps_ohlcv(ctx: MmArc, req: SwapsOhlcvRequest) -> MarketDataResult<SwapsOhlcvResponse> {
+    if req.base == req.rel {
+        return MmError::err(MarketDataError::InvalidRequest("'base' and 'rel' must differ".into()));
+    }
+    if !SUPPORTED_INTERVALS.contains(&req.interval) {
+        return MmError::err(MarketDataError::UnsupportedInterval {
+            interval: req.interval,
+            supported: SUPPORTED_INTERVALS.to_vec(),
+        });
+    }
+    let to = req.to_timestamp.unwrap_or_else(now_sec);
+    let from = req
+        .from_timestamp
+        .unwrap_or_else(|| to.saturating_sub(req.interval * DEFAULT_CANDLES));
+    if from > to {
+        return MmError::err(MarketDataError::InvalidRequest(
+            "'from_timestamp' must not be greater than 'to_timestamp'".into(),
+        ));
+    }
+    if (to - from) / req.interval >= MAX_CANDLES {
+        return MmError::err(MarketDataError::InvalidRequest(format!(
+            "The range must not exceed {} intervals",
+            MAX_CANDLES
+        )));
+    }
+
+    let trades = select_pair_trades(&ctx, &req.base, &req.rel, from, to)?;
+    Ok(SwapsOhlcvResponse {
+        candles: aggregate_candles(&trades, req.interval),
+        base: req.base,
+        rel: req.rel,
+        interval: req.interval,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct SwapsTickerRequest {
+    base: String,
+    rel: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SwapsTickerResponse {
+    pub base: String,
+    pub rel: String,
+    /// The price of the last swap within the last 24 hours.
+    pub last_price: Option<BigDecimal>,
+    pub last_trade_at: Option<u64>,
+    /// The price change between the first and the last swap within the last 24 hours.
+    pub price_change_percent: Option<BigDecimal>,
+    pub high: Option<BigDecimal>,
+    pub low: Option<BigDecimal>,
+    pub base_volume: BigDecimal,
+    pub rel_volume: BigDecimal,
+    pub trades_count: u64,
+}
+
+fn build_ticker(base: String, rel: String, trades: &[PairTrade]) -> SwapsTickerResponse {
+    // A single candle covering all the trades.
+    let candle = aggregate_candles(trades, u64::MAX).pop();
+    let price_change_percent = match (trades.first(), trades.last()) {
+        // The change relative to the zero price is undefined.
+        (Some(first), Some(last)) if !first.price.is_zero() => {
+            let change = &(&last.price - &first.price) / &first.price;
+            Some((change * MmNumber::from(100)).to_decimal())
+        },
+        _ => None,
+    };
+    SwapsTickerResponse {
+        base,
+        rel,
+        last_price: candle.as_ref().map(|candle| candle.close.clone()),
+        last_trade_at: trades.last().map(|trade| trade.timestamp),
+        price_change_percent,
+        high: candle.as_ref().map(|candle| candle.high.clone()),
+        low: candle.as_ref().map(|candle| candle.low.clone()),
+        base_volume: candle
+            .as_ref()
+            .map(|candle| candle.base_volume.clone())
+            .unwrap_or_default(),
+        rel_volume: candle
+            .as_ref()
+            .map(|candle| candle.rel_volume.clone())
+            .unwrap_or_default(),
+        trades_count: candle.map(|candle| candle.trades_count).unwrap_or_default(),
+    }
+}
+
+/// Returns the 24h ticker of the pair built from the successful swaps observed by the node.
+pub async fn swaps_ticker(ctx: MmArc, req: SwapsTickerRequest) -> MarketDataResult<SwapsTickerResponse> {
+    if req.base == req.rel {
+        return MmError::err(MarketDataError::InvalidRequest("'base' and 'rel' must differ".into()));
+    }
+    let to = now_sec();
+    let trades = select_pair_trades(&ctx, &req.base, &req.rel, to.saturating_sub(TICKER_PERIOD), to)?;
+    Ok(build_ticker(req.base, req.rel, &trades))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(timestamp: u64, base_volume: i32, rel_volume: i32) -> PairTrade {
+        let base_volume = MmNumber::from(base_volume);
+        let rel_volume = MmNumber::from(rel_volume);
+        PairTrade {
+            timestamp,
+            price: &rel_volume / &base_volume,
+            base_volume,
+            rel_volume,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_candles() {
+        let trades = vec![
+            trade(60, 1, 2),
+            trade(90, 2, 6),
+            trade(100, 1, 1),
+            trade(119, 1, 3),
+            trade(300, 4, 4),
+        ];
+        let candles = aggregate_candles(&trades, 60);
+        assert_eq!(candles.len(), 2);
+
+        let first = &candles[0];
+        assert_eq!(first.timestamp, 60);
+        assert_eq!(first.open, BigDecimal::from(2));
+        assert_eq!(first.high, BigDecimal::from(3));
+        assert_eq!(first.low, BigDecimal::from(1));
+        assert_eq!(first.close, BigDecimal::from(3));
+        assert_eq!(first.base_volume, BigDecimal::from(5));
+        assert_eq!(first.rel_volume, BigDecimal::from(12));
+        assert_eq!(first.trades_count, 4);
+
+        let second = &candles[1];
+        assert_eq!(second.timestamp, 300);
+        assert_eq!(second.open, BigDecimal::from(1));
+        assert_eq!(second.trades_count, 1);
+    }
+
+    #[test]
+    fn test_build_ticker() {
+        let ticker = build_ticker("RICK".into(), "MORTY".into(), &[]);
+        assert_eq!(ticker.last_price, None);
+        assert_eq!(ticker.trades_count, 0);
+
+        let trades = vec![trade(10, 1, 2), trade(20, 1, 4), trade(30, 2, 3)];
+        let ticker = build_ticker("RICK".into(), "MORTY".into(), &trades);
+        assert_eq!(ticker.last_price, Some("1.5".parse().unwrap()));
+        assert_eq!(ticker.last_trade_at, Some(30));
+        assert_eq!(ticker.high, Some(BigDecimal::from(4)));
+        assert_eq!(ticker.low, Some("1.5".parse().unwrap()));
+        assert_eq!(ticker.price_change_percent, Some(BigDecimal::from(-25)));
+        assert_eq!(ticker.base_volume, BigDecimal::from(4));
+        assert_eq!(ticker.rel_volume, BigDecimal::from(9));
+        assert_eq!(ticker.trades_count, 3);
+
+        let trades = vec![trade(10, 1, 0), trade(20, 1, 4)];
+        let ticker = build_ticker("RICK".into(), "MORTY".into(), &trades);
+        assert_eq!(ticker.last_price, Some(BigDecimal::from(4)));
+        assert_eq!(ticker.price_change_percent, None);
+    }
+}
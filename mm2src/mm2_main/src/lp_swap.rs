@@ -60,7 +60,10 @@
 use super::lp_network::P2PRequestResult;
 use crate::mm2::lp_network::{broadcast_p2p_msg, Libp2pPeerId, P2PProcessError, P2PProcessResult, P2PRequestError};
 use bitcrypto::{dhash160, sha256};
-use coins::{lp_coinfind, lp_coinfind_or_err, CoinFindError, DexFee, MmCoin, MmCoinEnum, TradeFee, TransactionEnum};
+use coins::rpc_command::consolidate_utxos::{consolidate_utxos, ConsolidateUtxosRequest, ConsolidateUtxosResponse,
+                                            ConsolidateUtxosResult};
+use coins::{lp_coinfind, lp_coinfind_or_err, CoinFindError, CoinsContext, DexFee, MmCoin, MmCoinEnum, TradeFee,
+            TransactionEnum};
 use common::log::{debug, warn};
 use common::now_sec;
 use common::time_cache::DuplicateCache;
@@ -71,6 +74,8 @@ use common::{bits256, calc_total_pages,
 use derive_more::Display;
 use http::Response;
 use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::RpcMethodRegistry;
 use mm2_err_handle::prelude::*;
 use mm2_libp2p::{decode_signed, encode_and_sign, pub_sub_topic, PeerId, TopicPrefix};
 use mm2_number::{BigDecimal, BigRational, MmNumber, MmNumberMultiRepr};
@@ -80,6 +85,7 @@ use secp256k1::{PublicKey, SecretKey, Signature};
 use serde::Serialize;
 use serde_json::{self as json, Value as Json};
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -92,15 +98,17 @@ use std::sync::atomic::{AtomicU64, Ordering};
 
 #[path = "lp_swap/check_balance.rs"] mod check_balance;
 #[path = "lp_swap/maker_swap.rs"] mod maker_swap;
-#[cfg(not(target_arch = "wasm32"))]
-#[path = "lp_swap/maker_swap_v2.rs"]
-pub mod maker_swap_v2;
+#[path = "lp_swap/maker_swap_v2.rs"] pub mod maker_swap_v2;
 #[path = "lp_swap/max_maker_vol_rpc.rs"] mod max_maker_vol_rpc;
 #[path = "lp_swap/my_swaps_storage.rs"] mod my_swaps_storage;
 #[path = "lp_swap/pubkey_banning.rs"] mod pubkey_banning;
+#[path = "lp_swap/pubkey_reputation.rs"] mod pubkey_reputation;
 #[path = "lp_swap/recreate_swap_data.rs"] mod recreate_swap_data;
 #[path = "lp_swap/saved_swap.rs"] mod saved_swap;
+#[path = "lp_swap/swap_events.rs"] mod swap_events;
 #[path = "lp_swap/swap_lock.rs"] mod swap_lock;
+#[path = "lp_swap/swap_v2_common.rs"] mod swap_v2_common;
+#[path = "lp_swap/swap_v2_rpcs.rs"] pub(crate) mod swap_v2_rpcs;
 #[path = "lp_swap/komodefi.swap_v2.pb.rs"]
 #[rustfmt::skip]
 mod swap_v2_pb;
@@ -108,9 +116,9 @@ mod swap_v2_pb;
 #[path = "lp_swap/taker_restart.rs"]
 pub(crate) mod taker_restart;
 #[path = "lp_swap/taker_swap.rs"] pub(crate) mod taker_swap;
-#[cfg(not(target_arch = "wasm32"))]
-#[path = "lp_swap/taker_swap_v2.rs"]
-pub mod taker_swap_v2;
+#[path = "lp_swap/taker_swap_v2.rs"] pub mod taker_swap_v2;
+#[path = "lp_swap/trade_history_export.rs"]
+mod trade_history_export;
 #[path = "lp_swap/trade_preimage.rs"] mod trade_preimage;
 
 #[cfg(target_arch = "wasm32")]
@@ -120,6 +128,7 @@ mod swap_wasm_db;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::mm2::database::my_swaps::{get_swap_data_for_rpc, get_swap_type};
 pub use check_balance::{check_other_coin_balance_for_swap, CheckBalanceError, CheckBalanceResult};
+use common::executor::{simple_map::AbortableSimpleMap, AbortableSystem};
 use crypto::CryptoCtx;
 use keys::{KeyPair, SECP_SIGN, SECP_VERIFY};
 use maker_swap::MakerSwapEvent;
@@ -130,8 +139,12 @@ pub use max_maker_vol_rpc::max_maker_vol;
 use my_swaps_storage::{MySwapsOps, MySwapsStorage};
 use pubkey_banning::BanReason;
 pub use pubkey_banning::{ban_pubkey_rpc, is_pubkey_banned, list_banned_pubkeys_rpc, unban_pubkeys_rpc};
+pub use pubkey_reputation::{has_min_reputation, pubkey_reputation_rpc};
 pub use recreate_swap_data::recreate_swap_data;
 pub use saved_swap::{SavedSwap, SavedSwapError, SavedSwapIo, SavedSwapResult};
+use swap_events::SwapStatusEventSender;
+pub use swap_events::SwapStatusStreamer;
+pub use swap_v2_common::{is_swap_v2_pair_supported, spawn_swap_v2_machine, MySwapV2Data};
 use swap_v2_pb::*;
 pub use swap_watcher::{process_watcher_msg, watcher_topic, TakerSwapWatcherData, MAKER_PAYMENT_SPEND_FOUND_LOG,
                        MAKER_PAYMENT_SPEND_SENT_LOG, TAKER_PAYMENT_REFUND_SENT_LOG, TAKER_SWAP_ENTRY_TIMEOUT_SEC,
@@ -141,6 +154,7 @@ pub use taker_swap::{calc_max_taker_vol, check_balance_for_taker_swap, max_taker
                      run_taker_swap, taker_swap_trade_preimage, RunTakerSwapInput, TakerSavedSwap, TakerSwap,
                      TakerSwapData, TakerSwapPreparedParams, TakerTradePreimage, MAKER_PAYMENT_SPENT_BY_WATCHER_LOG,
                      REFUND_TEST_FAILURE_LOG, WATCHER_MESSAGE_SENT_LOG};
+pub use trade_history_export::export_trade_history;
 pub use trade_preimage::trade_preimage_rpc;
 
 pub const SWAP_PREFIX: TopicPrefix = "swap";
@@ -149,8 +163,8 @@ pub const SWAP_FINISHED_LOG: &str = "Swap finished: ";
 pub const TX_HELPER_PREFIX: TopicPrefix = "txhlp";
 
 const LEGACY_SWAP_TYPE: u8 = 0;
-const MAKER_SWAP_V2_TYPE: u8 = 1;
-const TAKER_SWAP_V2_TYPE: u8 = 2;
+pub(crate) const MAKER_SWAP_V2_TYPE: u8 = 1;
+pub(crate) const TAKER_SWAP_V2_TYPE: u8 = 2;
 const MAX_STARTED_AT_DIFF: u64 = 60;
 
 const NEGOTIATE_SEND_INTERVAL: f64 = 30.;
@@ -217,6 +231,46 @@ impl SwapV2MsgStore {
             ..Default::default()
         }
     }
+
+    /// Puts the received message to the corresponding field replacing the previously received one.
+    fn insert_msg(&mut self, inner: swap_v2_pb::swap_message::Inner) {
+        match inner {
+            swap_v2_pb::swap_message::Inner::MakerNegotiation(maker_negotiation) => {
+                self.maker_negotiation = Some(maker_negotiation)
+            },
+            swap_v2_pb::swap_message::Inner::TakerNegotiation(taker_negotiation) => {
+                self.taker_negotiation = Some(taker_negotiation)
+            },
+            swap_v2_pb::swap_message::Inner::MakerNegotiated(maker_negotiated) => {
+                self.maker_negotiated = Some(maker_negotiated)
+            },
+            swap_v2_pb::swap_message::Inner::TakerFundingInfo(taker_funding) => {
+                self.taker_funding = Some(taker_funding)
+            },
+            swap_v2_pb::swap_message::Inner::MakerPaymentInfo(maker_payment) => {
+                self.maker_payment = Some(maker_payment)
+            },
+            swap_v2_pb::swap_message::Inner::TakerPaymentInfo(taker_payment) => {
+                self.taker_payment = Some(taker_payment)
+            },
+            swap_v2_pb::swap_message::Inner::TakerPaymentSpendPreimage(preimage) => {
+                self.taker_payment_spend_preimage = Some(preimage)
+            },
+        }
+    }
+}
+
+/// Returns the kind of the swap v2 message, which is used as a key to persist the latest message of each kind.
+fn swap_v2_msg_kind(inner: &swap_v2_pb::swap_message::Inner) -> &'static str {
+    match inner {
+        swap_v2_pb::swap_message::Inner::MakerNegotiation(_) => "maker_negotiation",
+        swap_v2_pb::swap_message::Inner::TakerNegotiation(_) => "taker_negotiation",
+        swap_v2_pb::swap_message::Inner::MakerNegotiated(_) => "maker_negotiated",
+        swap_v2_pb::swap_message::Inner::TakerFundingInfo(_) => "taker_funding",
+        swap_v2_pb::swap_message::Inner::MakerPaymentInfo(_) => "maker_payment",
+        swap_v2_pb::swap_message::Inner::TakerPaymentInfo(_) => "taker_payment",
+        swap_v2_pb::swap_message::Inner::TakerPaymentSpendPreimage(_) => "taker_payment_spend_preimage",
+    }
 }
 
 /// Returns key-pair for signing P2P messages and an optional `PeerId` if it should be used forcibly
@@ -495,6 +549,10 @@ struct SwapsContext {
     swap_msgs: Mutex<HashMap<Uuid, SwapMsgStore>>,
     swap_v2_msgs: Mutex<HashMap<Uuid, SwapV2MsgStore>>,
     taker_swap_watchers: PaMutex<DuplicateCache<Vec<u8>>>,
+    /// The running swap v2 state machines futures stored by swap uuids, so they can be aborted on demand.
+    swap_v2_machines: AbortableSimpleMap<Uuid>,
+    /// The sender of the `SWAP_STATUS` streamer, set only if the streaming of this event is active.
+    swap_status_event_tx: PaMutex<Option<SwapStatusEventSender>>,
     #[cfg(target_arch = "wasm32")]
     swap_db: ConstructibleDb<SwapDb>,
 }
@@ -511,6 +569,8 @@ impl SwapsContext {
                 taker_swap_watchers: PaMutex::new(DuplicateCache::new(Duration::from_secs(
                     TAKER_SWAP_ENTRY_TIMEOUT_SEC,
                 ))),
+                swap_v2_machines: try_s!(ctx.abortable_system.create_subsystem()),
+                swap_status_event_tx: PaMutex::new(None),
                 #[cfg(target_arch = "wasm32")]
                 swap_db: ConstructibleDb::new(ctx),
             })
@@ -523,9 +583,13 @@ impl SwapsContext {
     }
 
     /// Initializes storage for the swap with specific uuid.
+    /// The existing storage is kept as is, since it might already contain the messages restored from DB.
     pub fn init_msg_v2_store(&self, uuid: Uuid, accept_only_from: bits256) {
-        let store = SwapV2MsgStore::new(accept_only_from);
-        self.swap_v2_msgs.lock().unwrap().insert(uuid, store);
+        self.swap_v2_msgs
+            .lock()
+            .unwrap()
+            .entry(uuid)
+            .or_insert_with(|| SwapV2MsgStore::new(accept_only_from));
     }
 
     #[cfg(target_arch = "wasm32")]
@@ -540,7 +604,10 @@ pub struct GetLockedAmountReq {
 #[derive(Serialize)]
 pub struct GetLockedAmountResp {
     coin: String,
+    /// The amount locked by the ongoing swaps.
     locked_amount: MmNumberMultiRepr,
+    /// The amount of the outputs frozen by the user.
+    frozen_amount: MmNumberMultiRepr,
 }
 
 #[derive(Debug, Display, Serialize, SerializeErrorType)]
@@ -571,16 +638,24 @@ pub async fn get_locked_amount_rpc(
     req: GetLockedAmountReq,
 ) -> Result<GetLockedAmountResp, MmError<GetLockedAmountRpcError>> {
     lp_coinfind_or_err(&ctx, &req.coin).await?;
-    let locked_amount = get_locked_amount(&ctx, &req.coin);
+    let locked_amount = get_swaps_locked_amount(&ctx, &req.coin);
+    let frozen_amount = get_frozen_amount(&ctx, &req.coin);
 
     Ok(GetLockedAmountResp {
         coin: req.coin,
         locked_amount: locked_amount.into(),
+        frozen_amount: frozen_amount.into(),
     })
 }
 
 /// Get total amount of selected coin locked by all currently ongoing swaps
+/// plus the amount of the outputs frozen by the user, since they can't be spent by swaps.
 pub fn get_locked_amount(ctx: &MmArc, coin: &str) -> MmNumber {
+    get_swaps_locked_amount(ctx, coin) + get_frozen_amount(ctx, coin)
+}
+
+/// Get total amount of selected coin locked by all currently ongoing swaps
+fn get_swaps_locked_amount(ctx: &MmArc, coin: &str) -> MmNumber {
     let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
     let swap_lock = swap_ctx.running_swaps.lock().unwrap();
 
@@ -601,6 +676,21 @@ pub fn get_locked_amount(ctx: &MmArc, coin: &str) -> MmNumber {
         })
 }
 
+/// Get total amount of selected coin outputs frozen by the user
+fn get_frozen_amount(ctx: &MmArc, coin: &str) -> MmNumber {
+    let coins_ctx = CoinsContext::from_ctx(ctx).unwrap();
+    coins_ctx.frozen_amount(coin).into()
+}
+
+/// Consolidates the UTXOs of the coin keeping the outputs required by the ongoing swaps untouched.
+pub async fn consolidate_utxos_rpc(
+    ctx: MmArc,
+    req: ConsolidateUtxosRequest,
+) -> ConsolidateUtxosResult<ConsolidateUtxosResponse> {
+    let swaps_locked_amount = get_swaps_locked_amount(&ctx, &req.coin).to_decimal();
+    consolidate_utxos(ctx, req, swaps_locked_amount).await
+}
+
 /// Get number of currently running swaps
 pub fn running_swaps_num(ctx: &MmArc) -> u64 {
     let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
@@ -612,6 +702,7 @@ pub fn running_swaps_num(ctx: &MmArc) -> u64 {
 }
 
 /// Get total amount of selected coin locked by all currently ongoing swaps except the one with selected uuid
+/// plus the amount of the outputs frozen by the user.
 fn get_locked_amount_by_other_swaps(ctx: &MmArc, except_uuid: &Uuid, coin: &str) -> MmNumber {
     let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
     let swap_lock = swap_ctx.running_swaps.lock().unwrap();
@@ -621,7 +712,7 @@ fn get_locked_amount_by_other_swaps(ctx: &MmArc, except_uuid: &Uuid, coin: &str)
         .filter_map(|swap| swap.upgrade())
         .filter(|swap| swap.uuid() != except_uuid)
         .flat_map(|swap| swap.locked_amount())
-        .fold(MmNumber::from(0), |mut total_amount, locked| {
+        .fold(get_frozen_amount(ctx, coin), |mut total_amount, locked| {
             if locked.coin == coin {
                 total_amount += locked.amount;
             }
@@ -1307,6 +1398,9 @@ pub async fn swap_kick_starts(ctx: MmArc) -> Result<HashSet<String>, String> {
         let fut = kickstart_thread_handler(ctx.clone(), swap, maker_coin_ticker, taker_coin_ticker);
         ctx.spawner().spawn(fut);
     }
+
+    coins.extend(try_s!(swap_v2_common::swap_v2_kick_starts(&ctx).await));
+
     Ok(coins)
 }
 
@@ -1485,6 +1579,18 @@ impl Default for SecretHashAlgo {
     fn default() -> Self { SecretHashAlgo::DHASH160 }
 }
 
+impl TryFrom<u8> for SecretHashAlgo {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(SecretHashAlgo::DHASH160),
+            2 => Ok(SecretHashAlgo::SHA256),
+            unknown => Err(format!("Unknown secret hash algo {}", unknown)),
+        }
+    }
+}
+
 impl SecretHashAlgo {
     fn hash_secret(&self, secret: &[u8]) -> Vec<u8> {
         match self {
@@ -1510,7 +1616,7 @@ pub fn detect_secret_hash_algo(maker_coin: &MmCoinEnum, taker_coin: &MmCoinEnum)
 
 /// Selects secret hash algorithm depending on types of coins being swapped
 #[cfg(target_arch = "wasm32")]
-fn detect_secret_hash_algo(maker_coin: &MmCoinEnum, taker_coin: &MmCoinEnum) -> SecretHashAlgo {
+pub fn detect_secret_hash_algo(maker_coin: &MmCoinEnum, taker_coin: &MmCoinEnum) -> SecretHashAlgo {
     match (maker_coin, taker_coin) {
         (MmCoinEnum::Tendermint(_) | MmCoinEnum::TendermintToken(_), _) => SecretHashAlgo::SHA256,
         (_, MmCoinEnum::Tendermint(_) | MmCoinEnum::TendermintToken(_)) => SecretHashAlgo::SHA256,
@@ -1595,30 +1701,21 @@ pub fn process_swap_v2_msg(ctx: MmArc, topic: &str, msg: &[u8]) -> P2PProcessRes
             .map_to_mm(|e| P2PProcessError::DecodeError(e.to_string()))?;
 
         debug!("Processing swap v2 msg {:?} for uuid {}", swap_message, uuid);
-        match swap_message.inner {
-            Some(swap_v2_pb::swap_message::Inner::MakerNegotiation(maker_negotiation)) => {
-                msg_store.maker_negotiation = Some(maker_negotiation)
-            },
-            Some(swap_v2_pb::swap_message::Inner::TakerNegotiation(taker_negotiation)) => {
-                msg_store.taker_negotiation = Some(taker_negotiation)
-            },
-            Some(swap_v2_pb::swap_message::Inner::MakerNegotiated(maker_negotiated)) => {
-                msg_store.maker_negotiated = Some(maker_negotiated)
-            },
-            Some(swap_v2_pb::swap_message::Inner::TakerFundingInfo(taker_funding)) => {
-                msg_store.taker_funding = Some(taker_funding)
-            },
-            Some(swap_v2_pb::swap_message::Inner::MakerPaymentInfo(maker_payment)) => {
-                msg_store.maker_payment = Some(maker_payment)
-            },
-            Some(swap_v2_pb::swap_message::Inner::TakerPaymentInfo(taker_payment)) => {
-                msg_store.taker_payment = Some(taker_payment)
-            },
-            Some(swap_v2_pb::swap_message::Inner::TakerPaymentSpendPreimage(preimage)) => {
-                msg_store.taker_payment_spend_preimage = Some(preimage)
-            },
-            None => return MmError::err(P2PProcessError::DecodeError("swap_message.inner is None".into())),
-        }
+        let inner = swap_message
+            .inner
+            .or_mm_err(|| P2PProcessError::DecodeError("swap_message.inner is None".into()))?;
+
+        // Persist the message, so it's not lost if the node is restarted before the swap processes it.
+        let msg_kind = swap_v2_msg_kind(&inner);
+        let payload = signed_message.payload;
+        let ctx_clone = ctx.clone();
+        ctx.spawner().spawn(async move {
+            if let Err(e) = swap_v2_common::store_swap_v2_msg(&ctx_clone, uuid, msg_kind, payload).await {
+                error!("Error {} on storing the swap {} {} message", e, uuid, msg_kind);
+            }
+        });
+
+        msg_store.insert_msg(inner);
     }
     Ok(())
 }
@@ -1654,6 +1751,65 @@ pub fn generate_secret() -> Result<[u8; 32], rand::Error> {
     Ok(sec)
 }
 
+/// Registers the swaps mmrpc 2.0 methods.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "consolidate_utxos",
+        "Merges the small outputs of the UTXO coin addresses, keeping the outputs required by the ongoing swaps.",
+        mmrpc_handler!(consolidate_utxos_rpc),
+    );
+    registry.register(
+        "export_trade_history",
+        "Exports the finished swaps with the fees, USD valuation and realized PnL per coin as JSON or CSV.",
+        mmrpc_handler!(export_trade_history),
+    );
+    registry.register(
+        "get_locked_amount",
+        "Returns the amount of the coin locked by the ongoing swaps.",
+        mmrpc_handler!(get_locked_amount_rpc),
+    );
+    registry.register(
+        "max_maker_vol",
+        "Returns the maximum volume of the coin that can be sold by a maker order.",
+        mmrpc_handler!(max_maker_vol),
+    );
+    registry.register(
+        "pubkey_reputation",
+        "Returns the reputation of the pubkeys built from the outcomes of their swaps observed by the node.",
+        mmrpc_handler!(pubkey_reputation_rpc),
+    );
+    registry.register(
+        "recreate_swap_data",
+        "Recreates the swap data of the counterparty from the given swap data.",
+        mmrpc_handler!(recreate_swap_data),
+    );
+    registry.register(
+        "trade_preimage",
+        "Returns the fees that would be paid by the trade.",
+        mmrpc_handler!(trade_preimage_rpc),
+    );
+    registry.register(
+        "swap_v2::abort",
+        "Aborts the swap v2 that hasn't sent the payment yet.",
+        mmrpc_handler!(swap_v2_rpcs::swap_v2_abort_rpc),
+    );
+    registry.register(
+        "swap_v2::list",
+        "Lists the swaps v2 by the given filter.",
+        mmrpc_handler!(swap_v2_rpcs::swaps_v2_list_rpc),
+    );
+    registry.register(
+        "swap_v2::refund",
+        "Refunds the payment of the failed swap v2.",
+        mmrpc_handler!(swap_v2_rpcs::swap_v2_refund_rpc),
+    );
+    registry.register(
+        "swap_v2::status",
+        "Returns the status of the swap v2.",
+        mmrpc_handler!(swap_v2_rpcs::swap_v2_status_rpc),
+    );
+}
+
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod lp_swap_tests {
     use super::*;
@@ -2274,4 +2430,26 @@ mod lp_swap_tests {
 
         assert_eq!(testcoin_taker_fee * MmNumber::from("0.90"), mycoin_taker_fee);
     }
+
+    #[test]
+    fn test_get_locked_amount_rpc_frozen_amount() {
+        use chain::OutPoint;
+        use coins::utxo::FrozenOutPoints;
+
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let coins_ctx = CoinsContext::from_ctx(&ctx).unwrap();
+        block_on(coins_ctx.add_token(MmCoinEnum::Test(coins::TestCoin::new("RICK")))).unwrap();
+
+        let mut frozen = FrozenOutPoints::new();
+        frozen.insert(OutPoint::default(), "1.5".parse().unwrap());
+        coins_ctx.set_frozen_outpoints("RICK", frozen);
+
+        let req = GetLockedAmountReq { coin: "RICK".into() };
+        let resp = block_on(get_locked_amount_rpc(ctx.clone(), req)).unwrap();
+        assert_eq!(resp.locked_amount.decimal, BigDecimal::from(0));
+        assert_eq!(resp.frozen_amount.decimal, "1.5".parse::<BigDecimal>().unwrap());
+
+        // The frozen outputs can't be spent by swaps, so they're still counted as locked internally.
+        assert_eq!(get_locked_amount(&ctx, "RICK"), MmNumber::from("1.5"));
+    }
 }
@@ -1,6 +1,7 @@
 use super::check_balance::{check_base_coin_balance_for_swap, check_my_coin_balance_for_swap, CheckBalanceError,
                            CheckBalanceResult};
 use super::pubkey_banning::ban_pubkey_on_failed_swap;
+use super::swap_events::{broadcast_swap_status_event, SwapStatusEvent};
 use super::swap_lock::{SwapLock, SwapLockOps};
 use super::trade_preimage::{TradePreimageRequest, TradePreimageRpcError, TradePreimageRpcResult};
 use super::{broadcast_my_swap_status, broadcast_p2p_tx_msg, broadcast_swap_msg_every,
@@ -538,8 +539,22 @@ impl MakerSwap {
         let taker_coin_swap_contract_address = self.taker_coin.swap_contract_address();
 
         let unique_data = self.unique_swap_data();
-        let maker_coin_htlc_pubkey = self.maker_coin.derive_htlc_pubkey(&unique_data);
-        let taker_coin_htlc_pubkey = self.taker_coin.derive_htlc_pubkey(&unique_data);
+        let maker_coin_htlc_pubkey = match self.maker_coin.derive_htlc_pubkey(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                return Ok((Some(MakerSwapCommand::Finish), vec![MakerSwapEvent::StartFailed(
+                    ERRL!("!maker_coin.derive_htlc_pubkey {}", e).into(),
+                )]))
+            },
+        };
+        let taker_coin_htlc_pubkey = match self.taker_coin.derive_htlc_pubkey(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                return Ok((Some(MakerSwapCommand::Finish), vec![MakerSwapEvent::StartFailed(
+                    ERRL!("!taker_coin.derive_htlc_pubkey {}", e).into(),
+                )]))
+            },
+        };
 
         let data = MakerSwapData {
             taker_coin: self.taker_coin.ticker().to_owned(),
@@ -1951,6 +1966,16 @@ impl MakerSavedSwap {
         Ok(true)
     }
 
+    /// Whether the maker payment was refunded.
+    pub fn is_refunded(&self) -> bool {
+        self.events
+            .iter()
+            .any(|event| matches!(event.event, MakerSwapEvent::MakerPaymentRefunded(_)))
+    }
+
+    /// Whether the swap failed because of the taker, the same events lead to the taker pubkey ban.
+    pub fn taker_caused_failure(&self) -> bool { self.events.iter().any(|event| event.event.should_ban_taker()) }
+
     pub async fn fetch_and_set_usd_prices(&mut self) {
         if let Some(rates) = fetch_swap_coins_price(self.maker_coin.clone(), self.taker_coin.clone()).await {
             self.maker_coin_usd_price = Some(rates.base);
@@ -2105,9 +2130,13 @@ pub async fn run_maker_swap(swap: RunMakerSwapInput, ctx: MmArc) {
                         .dispatch_async(ctx.clone(), LpEvents::MakerSwapStatusChanged(event_to_send))
                         .await;
                     drop(dispatcher);
-                    save_my_maker_swap_event(&ctx, &running_swap, to_save)
+                    save_my_maker_swap_event(&ctx, &running_swap, to_save.clone())
                         .await
                         .expect("!save_my_maker_swap_event");
+                    broadcast_swap_status_event(&ctx, SwapStatusEvent::MakerV1 {
+                        uuid: running_swap.uuid,
+                        event: to_save,
+                    });
                     if event.should_ban_taker() {
                         ban_pubkey_on_failed_swap(
                             &ctx,
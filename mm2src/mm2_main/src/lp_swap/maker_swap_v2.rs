@@ -1,18 +1,20 @@
+use super::swap_events::{broadcast_swap_status_event, SwapStatusEvent};
 use super::{NEGOTIATE_SEND_INTERVAL, NEGOTIATION_TIMEOUT_SEC};
-use crate::mm2::database::my_swaps::{get_swap_events, insert_new_swap_v2, set_swap_is_finished, update_swap_events};
-use crate::mm2::lp_network::subscribe_to_topic;
+use crate::mm2::lp_swap::swap_v2_common::{init_swap_v2_p2p, load_swap_v2_data, load_swap_v2_events,
+                                          load_unfinished_swaps_v2, mark_swap_v2_finished, store_swap_v2_data,
+                                          store_swap_v2_event, ManualRefundError, MySwapV2Data, StoredTxPreimage,
+                                          SwapRecreateCtx, SwapV2Params};
 use crate::mm2::lp_swap::swap_v2_pb::*;
-use crate::mm2::lp_swap::{broadcast_swap_v2_msg_every, check_balance_for_maker_swap, recv_swap_v2_msg, SecretHashAlgo,
-                          SwapConfirmationsSettings, SwapsContext, TransactionIdentifier, MAKER_SWAP_V2_TYPE,
+use crate::mm2::lp_swap::{broadcast_swap_v2_msg_every, check_balance_for_maker_swap, recv_swap_v2_msg, swap_v2_topic,
+                          SecretHashAlgo, SwapConfirmationsSettings, TransactionIdentifier, MAKER_SWAP_V2_TYPE,
                           MAX_STARTED_AT_DIFF};
 use async_trait::async_trait;
 use bitcrypto::{dhash160, sha256};
 use coins::{CoinAssocTypes, ConfirmPaymentInput, FeeApproxStage, GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs,
-            MarketCoinOps, MmCoin, SendPaymentArgs, SwapOpsV2, ToBytes, Transaction, TxPreimageWithSig,
-            ValidateTakerFundingArgs};
+            MarketCoinOps, MmCoin, RefundPaymentArgs, SendPaymentArgs, SwapOpsV2, ToBytes, Transaction,
+            TxPreimageWithSig, ValidateTakerFundingArgs};
 use common::log::{debug, info, warn};
-use common::{bits256, Future01CompatExt, DEX_FEE_ADDR_RAW_PUBKEY};
-use db_common::sqlite::rusqlite::named_params;
+use common::{now_sec, Future01CompatExt, DEX_FEE_ADDR_RAW_PUBKEY};
 use keys::KeyPair;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
@@ -21,6 +23,7 @@ use mm2_state_machine::prelude::*;
 use mm2_state_machine::storable_state_machine::*;
 use primitives::hash::H256;
 use rpc::v1::types::Bytes as BytesJson;
+use std::convert::TryFrom;
 use std::marker::PhantomData;
 use uuid::Uuid;
 
@@ -31,6 +34,9 @@ use uuid::Uuid;
 #[derive(Debug, Deserialize, Serialize)]
 pub struct StoredNegotiationData {
     taker_payment_locktime: u64,
+    /// Is not stored by the previous versions, so it's recalculated on restore if it's zero.
+    #[serde(default)]
+    taker_funding_locktime: u64,
     maker_coin_htlc_pub_from_taker: BytesJson,
     taker_coin_htlc_pub_from_taker: BytesJson,
     maker_coin_swap_contract: Option<BytesJson>,
@@ -59,14 +65,18 @@ pub enum MakerSwapEvent {
         negotiation_data: StoredNegotiationData,
         taker_funding: TransactionIdentifier,
     },
-    /// Sent maker payment.
+    /// Sent maker payment and generated funding spend preimage.
     MakerPaymentSent {
         maker_coin_start_block: u64,
         taker_coin_start_block: u64,
         negotiation_data: StoredNegotiationData,
+        /// Is not stored by the previous versions, which sent maker payment before receiving the preimage.
+        #[serde(default)]
+        funding_spend_preimage: Option<StoredTxPreimage>,
         maker_payment: TransactionIdentifier,
     },
     /// Received funding spend preimage.
+    /// Is not produced anymore, kept to read the events stored by the previous versions.
     TakerFundingSpendReceived {
         maker_coin_start_block: u64,
         taker_coin_start_block: u64,
@@ -82,6 +92,14 @@ pub enum MakerSwapEvent {
         taker_coin_start_block: u64,
         negotiation_data: StoredNegotiationData,
         maker_payment: TransactionIdentifier,
+        #[serde(default)]
+        reason: MakerPaymentRefundReason,
+    },
+    /// Maker payment has been refunded manually.
+    MakerPaymentRefunded {
+        maker_payment: TransactionIdentifier,
+        maker_payment_refund: TransactionIdentifier,
+        reason: MakerPaymentRefundReason,
     },
     /// Taker payment has been confirmed on-chain.
     TakerPaymentConfirmed {
@@ -110,43 +128,41 @@ pub enum MakerSwapEvent {
 pub enum MakerSwapStateMachineError {
     StorageError(String),
     SerdeError(String),
+    RestoreError(String),
 }
 
-/// Dummy storage for maker swap events (used temporary).
-pub struct DummyMakerSwapStorage {
+/// Storage for maker swaps, which keeps the swap data and events in `my_swaps` table on native
+/// and in `my_swaps_v2` IndexedDB table on WASM.
+///
+/// The events are kept as a single JSON array rather than as a row per event:
+/// a swap produces about 10 events, each swap has a single writer (its state machine),
+/// and the array is read as is by the swap status RPCs.
+pub struct MakerSwapStorage {
     ctx: MmArc,
 }
 
-impl DummyMakerSwapStorage {
-    pub fn new(ctx: MmArc) -> Self { DummyMakerSwapStorage { ctx } }
+impl MakerSwapStorage {
+    pub fn new(ctx: MmArc) -> Self { MakerSwapStorage { ctx } }
 }
 
 #[async_trait]
-impl StateMachineStorage for DummyMakerSwapStorage {
+impl StateMachineStorage for MakerSwapStorage {
     type MachineId = Uuid;
     type Event = MakerSwapEvent;
     type Error = MmError<MakerSwapStateMachineError>;
 
     async fn store_event(&mut self, id: Self::MachineId, event: Self::Event) -> Result<(), Self::Error> {
-        let id_str = id.to_string();
-        let events_json = get_swap_events(&self.ctx.sqlite_connection(), &id_str)
-            .map_to_mm(|e| MakerSwapStateMachineError::StorageError(e.to_string()))?;
-        let mut events: Vec<MakerSwapEvent> =
-            serde_json::from_str(&events_json).map_to_mm(|e| MakerSwapStateMachineError::SerdeError(e.to_string()))?;
-        events.push(event);
-        drop_mutability!(events);
-        let serialized_events =
-            serde_json::to_string(&events).map_to_mm(|e| MakerSwapStateMachineError::SerdeError(e.to_string()))?;
-        update_swap_events(&self.ctx.sqlite_connection(), &id_str, &serialized_events)
-            .map_to_mm(|e| MakerSwapStateMachineError::StorageError(e.to_string()))?;
+        store_swap_v2_event(&self.ctx, id, &event).await?;
+        broadcast_swap_status_event(&self.ctx, SwapStatusEvent::MakerV2 { uuid: id, event });
         Ok(())
     }
 
-    async fn get_unfinished(&self) -> Result<Vec<Self::MachineId>, Self::Error> { todo!() }
+    async fn get_unfinished(&self) -> Result<Vec<Self::MachineId>, Self::Error> {
+        Ok(load_unfinished_swaps_v2(&self.ctx, MAKER_SWAP_V2_TYPE).await?)
+    }
 
     async fn mark_finished(&mut self, id: Self::MachineId) -> Result<(), Self::Error> {
-        set_swap_is_finished(&self.ctx.sqlite_connection(), &id.to_string())
-            .map_to_mm(|e| MakerSwapStateMachineError::StorageError(e.to_string()))
+        Ok(mark_swap_v2_finished(&self.ctx, id).await?)
     }
 }
 
@@ -155,7 +171,7 @@ pub struct MakerSwapStateMachine<MakerCoin, TakerCoin> {
     /// MM2 context
     pub ctx: MmArc,
     /// Storage
-    pub storage: DummyMakerSwapStorage,
+    pub storage: MakerSwapStorage,
     /// Maker coin
     pub maker_coin: MakerCoin,
     /// The amount swapped by maker.
@@ -208,21 +224,277 @@ impl<MakerCoin, TakerCoin> MakerSwapStateMachine<MakerCoin, TakerCoin> {
     fn unique_data(&self) -> Vec<u8> { self.secret_hash() }
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableStateMachine
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> MakerSwapStateMachine<MakerCoin, TakerCoin> {
+    /// Recreates the state the swap should continue from using the last stored event.
+    fn state_from_event(
+        &self,
+        event: MakerSwapEvent,
+    ) -> MmResult<Box<dyn State<StateMachine = Self>>, MakerSwapStateMachineError> {
+        let state: Box<dyn State<StateMachine = Self>> = match event {
+            MakerSwapEvent::Initialized {
+                maker_coin_start_block,
+                taker_coin_start_block,
+            } => Box::new(Initialized {
+                maker_coin: Default::default(),
+                taker_coin: Default::default(),
+                maker_coin_start_block,
+                taker_coin_start_block,
+            }),
+            MakerSwapEvent::WaitingForTakerFunding {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+            } => Box::new(WaitingForTakerFunding {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+            }),
+            MakerSwapEvent::TakerFundingReceived {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                taker_funding,
+            } => Box::new(TakerFundingReceived {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+                taker_funding: self.parse_taker_tx(&taker_funding)?,
+            }),
+            MakerSwapEvent::MakerPaymentSent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                funding_spend_preimage: Some(funding_spend_preimage),
+                maker_payment,
+            } => Box::new(MakerPaymentSentFundingSpendGenerated {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+                funding_spend_preimage: self.parse_funding_spend_preimage(
+                    &funding_spend_preimage.preimage,
+                    &funding_spend_preimage.signature,
+                )?,
+                maker_payment,
+            }),
+            // The previous versions sent maker payment before the funding spend preimage was received,
+            // so the swap can't continue without it and maker payment must be refunded.
+            MakerSwapEvent::MakerPaymentSent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                funding_spend_preimage: None,
+                maker_payment,
+            } => Box::new(MakerPaymentRefundRequired {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+                maker_payment,
+                reason: MakerPaymentRefundReason::Unknown,
+            }),
+            MakerSwapEvent::TakerFundingSpendReceived {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                maker_payment,
+                taker_funding_preimage,
+                taker_funding_spend_signature,
+                ..
+            } => Box::new(MakerPaymentSentFundingSpendGenerated {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+                funding_spend_preimage: self
+                    .parse_funding_spend_preimage(&taker_funding_preimage, &taker_funding_spend_signature)?,
+                maker_payment,
+            }),
+            MakerSwapEvent::MakerPaymentRefundRequired {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                maker_payment,
+                reason,
+            } => Box::new(MakerPaymentRefundRequired {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+                maker_payment,
+                reason,
+            }),
+            MakerSwapEvent::TakerPaymentConfirmed {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                maker_payment,
+                taker_payment,
+            } => Box::new(TakerPaymentConfirmed {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                maker_payment,
+                taker_payment: self.parse_taker_tx(&taker_payment)?,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+            }),
+            MakerSwapEvent::TakerPaymentSpent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                maker_payment,
+                taker_payment,
+                taker_payment_spend,
+            } => Box::new(TakerPaymentSpent {
+                maker_coin: Default::default(),
+                maker_coin_start_block,
+                taker_coin_start_block,
+                maker_payment,
+                taker_payment: self.parse_taker_tx(&taker_payment)?,
+                taker_payment_spend,
+            }),
+            // The final event might be stored while the swap isn't marked as finished yet.
+            // Restoring the last state will simply mark it as finished.
+            MakerSwapEvent::Aborted { reason } => Box::new(Aborted::new(reason)),
+            MakerSwapEvent::MakerPaymentRefunded { .. } => Box::new(Refunded::new()),
+            MakerSwapEvent::Completed => Box::new(Completed::new()),
+        };
+        Ok(state)
+    }
+
+    fn parse_taker_tx(&self, tx: &TransactionIdentifier) -> MmResult<TakerCoin::Tx, MakerSwapStateMachineError> {
+        self.taker_coin
+            .parse_tx(&tx.tx_hex.0)
+            .map_to_mm(|e| MakerSwapStateMachineError::RestoreError(e.to_string()))
+    }
+
+    fn parse_funding_spend_preimage(
+        &self,
+        preimage: &BytesJson,
+        signature: &BytesJson,
+    ) -> MmResult<TxPreimageWithSig<TakerCoin>, MakerSwapStateMachineError> {
+        Ok(TxPreimageWithSig {
+            preimage: self
+                .taker_coin
+                .parse_preimage(&preimage.0)
+                .map_to_mm(|e| MakerSwapStateMachineError::RestoreError(e.to_string()))?,
+            signature: self
+                .taker_coin
+                .parse_signature(&signature.0)
+                .map_to_mm(|e| MakerSwapStateMachineError::RestoreError(e.to_string()))?,
+        })
+    }
+
+    /// Recreates the negotiation data from the stored one.
+    /// Taker funding locktime isn't stored by the previous versions, so it's recalculated the way taker does it.
+    fn negotiation_data_from_stored(
+        &self,
+        stored: StoredNegotiationData,
+    ) -> MmResult<NegotiationData<MakerCoin, TakerCoin>, MakerSwapStateMachineError> {
+        let mut negotiation_data = NegotiationData::from_stored_data(stored, &self.maker_coin, &self.taker_coin)?;
+        if negotiation_data.taker_funding_locktime == 0 {
+            negotiation_data.taker_funding_locktime = negotiation_data.taker_payment_locktime + 2 * self.lock_duration;
+        }
+        Ok(negotiation_data)
+    }
+
+    /// Refunds maker payment of the swap that stopped in [`MakerPaymentRefundRequired`] state.
+    /// Stores the [`MakerSwapEvent::MakerPaymentRefunded`] event and marks the swap as finished on success.
+    pub(super) async fn refund_manually(&mut self) -> MmResult<TransactionIdentifier, ManualRefundError> {
+        let mut events: Vec<MakerSwapEvent> = load_swap_v2_events(&self.ctx, self.uuid).await?;
+        let (maker_payment, negotiation_data, reason) = match events.pop() {
+            Some(MakerSwapEvent::MakerPaymentRefundRequired {
+                maker_payment,
+                negotiation_data,
+                reason,
+                ..
+            }) => (maker_payment, negotiation_data, reason),
+            Some(event) => return MmError::err(ManualRefundError::RefundIsNotRequired(format!("{:?}", event))),
+            None => return MmError::err(ManualRefundError::RefundIsNotRequired("no events".to_owned())),
+        };
+
+        let locktime = self.maker_payment_locktime();
+        let now = now_sec();
+        if now < locktime {
+            return MmError::err(ManualRefundError::LocktimeNotExpired { locktime, now });
+        }
+
+        let negotiation_data = self.negotiation_data_from_stored(negotiation_data)?;
+        let args = RefundPaymentArgs {
+            payment_tx: &maker_payment.tx_hex.0,
+            time_lock: locktime,
+            other_pubkey: &negotiation_data.maker_coin_htlc_pub_from_taker.to_bytes(),
+            secret_hash: &self.secret_hash(),
+            swap_contract_address: &negotiation_data.maker_coin_swap_contract.map(|bytes| bytes.into()),
+            swap_unique_data: &self.unique_data(),
+            watcher_reward: false,
+        };
+        let refund_tx = self
+            .maker_coin
+            .send_maker_refunds_payment(args)
+            .await
+            .map_to_mm(|e| ManualRefundError::TransactionError(format!("{:?}", e)))?;
+        info!(
+            "Refunded maker payment {} tx {:02x} during swap {}",
+            self.maker_coin.ticker(),
+            refund_tx.tx_hash(),
+            self.uuid
+        );
+
+        let maker_payment_refund = TransactionIdentifier {
+            tx_hex: refund_tx.tx_hex().into(),
+            tx_hash: refund_tx.tx_hash(),
+        };
+        let event = MakerSwapEvent::MakerPaymentRefunded {
+            maker_payment,
+            maker_payment_refund: maker_payment_refund.clone(),
+            reason,
+        };
+        self.store_event(event).await?;
+        self.mark_finished().await?;
+        Ok(maker_payment_refund)
+    }
+}
+
+#[async_trait]
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableStateMachine
     for MakerSwapStateMachine<MakerCoin, TakerCoin>
 {
-    type Storage = DummyMakerSwapStorage;
+    type Storage = MakerSwapStorage;
     type Result = ();
+    type RecreateCtx = SwapRecreateCtx<MakerCoin, TakerCoin>;
 
     fn storage(&mut self) -> &mut Self::Storage { &mut self.storage }
 
     fn id(&self) -> <Self::Storage as StateMachineStorage>::MachineId { self.uuid }
 
-    fn restore_from_storage(
-        _id: <Self::Storage as StateMachineStorage>::MachineId,
-        _storage: Self::Storage,
+    async fn restore_from_storage(
+        id: <Self::Storage as StateMachineStorage>::MachineId,
+        storage: Self::Storage,
+        recreate_ctx: Self::RecreateCtx,
     ) -> Result<RestoredMachine<Self>, <Self::Storage as StateMachineStorage>::Error> {
-        todo!()
+        let swap_data = load_swap_v2_data(&storage.ctx, id).await?;
+        let params = SwapV2Params::try_from(&swap_data).map_to_mm(MakerSwapStateMachineError::RestoreError)?;
+        let mut events: Vec<MakerSwapEvent> = serde_json::from_str(&swap_data.events_json)
+            .map_to_mm(|e| MakerSwapStateMachineError::SerdeError(e.to_string()))?;
+        let last_event = events
+            .pop()
+            .or_mm_err(|| MakerSwapStateMachineError::RestoreError(format!("Swap {} has no stored events", id)))?;
+
+        let machine = MakerSwapStateMachine {
+            ctx: storage.ctx.clone(),
+            storage,
+            maker_coin: recreate_ctx.maker_coin,
+            maker_volume: params.maker_volume,
+            secret: params.secret.into(),
+            secret_hash_algo: params.secret_hash_algo,
+            started_at: params.started_at,
+            lock_duration: params.lock_duration,
+            taker_coin: recreate_ctx.taker_coin,
+            taker_volume: params.taker_volume,
+            taker_premium: params.premium,
+            dex_fee_amount: params.dex_fee,
+            conf_settings: params.conf_settings,
+            uuid: id,
+            p2p_topic: swap_v2_topic(&id),
+            p2p_keypair: params.p2p_keypair,
+        };
+        let current_state = machine.state_from_event(last_event)?;
+        Ok(RestoredMachine { machine, current_state })
     }
 }
 
@@ -241,7 +513,9 @@ impl<MakerCoin, TakerCoin> Default for Initialize<MakerCoin, TakerCoin> {
     }
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> InitialState for Initialize<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> InitialState
+    for Initialize<MakerCoin, TakerCoin>
+{
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
 }
 
@@ -250,33 +524,43 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State fo
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     async fn on_changed(self: Box<Self>, state_machine: &mut Self::StateMachine) -> StateResult<Self::StateMachine> {
+        let swap_data = MySwapV2Data {
+            my_coin: state_machine.maker_coin.ticker().to_owned(),
+            other_coin: state_machine.taker_coin.ticker().to_owned(),
+            started_at: state_machine.started_at as i64,
+            events_json: "[]".to_owned(),
+            maker_volume: state_machine.maker_volume.to_fraction_string(),
+            taker_volume: state_machine.taker_volume.to_fraction_string(),
+            premium: state_machine.taker_premium.to_fraction_string(),
+            dex_fee: state_machine.dex_fee_amount.to_fraction_string(),
+            secret: state_machine.secret.take().to_vec(),
+            secret_hash_algo: state_machine.secret_hash_algo as u8,
+            p2p_privkey: state_machine
+                .p2p_keypair
+                .map(|k| k.private_bytes())
+                .unwrap_or_default()
+                .to_vec(),
+            lock_duration: state_machine.lock_duration as i64,
+            maker_coin_confs: state_machine.conf_settings.maker_coin_confs as i64,
+            maker_coin_nota: state_machine.conf_settings.maker_coin_nota,
+            taker_coin_confs: state_machine.conf_settings.taker_coin_confs as i64,
+            taker_coin_nota: state_machine.conf_settings.taker_coin_nota,
+        };
+        let secret_hash = state_machine.secret_hash();
+        if let Err(e) = store_swap_v2_data(
+            &state_machine.ctx,
+            state_machine.uuid,
+            MAKER_SWAP_V2_TYPE,
+            &secret_hash,
+            swap_data,
+        )
+        .await
         {
-            let sql_params = named_params! {
-                ":my_coin": state_machine.maker_coin.ticker(),
-                ":other_coin": state_machine.taker_coin.ticker(),
-                ":uuid": state_machine.uuid.to_string(),
-                ":started_at": state_machine.started_at,
-                ":swap_type": MAKER_SWAP_V2_TYPE,
-                ":maker_volume": state_machine.maker_volume.to_fraction_string(),
-                ":taker_volume": state_machine.taker_volume.to_fraction_string(),
-                ":premium": state_machine.taker_premium.to_fraction_string(),
-                ":dex_fee": state_machine.dex_fee_amount.to_fraction_string(),
-                ":secret": state_machine.secret.take(),
-                ":secret_hash": state_machine.secret_hash(),
-                ":secret_hash_algo": state_machine.secret_hash_algo as u8,
-                ":p2p_privkey": state_machine.p2p_keypair.map(|k| k.private_bytes()).unwrap_or_default(),
-                ":lock_duration": state_machine.lock_duration,
-                ":maker_coin_confs": state_machine.conf_settings.maker_coin_confs,
-                ":maker_coin_nota": state_machine.conf_settings.maker_coin_nota,
-                ":taker_coin_confs": state_machine.conf_settings.taker_coin_confs,
-                ":taker_coin_nota": state_machine.conf_settings.taker_coin_nota
-            };
-            insert_new_swap_v2(&state_machine.ctx, sql_params).unwrap();
+            let reason = AbortReason::FailedToStoreSwapData(e.to_string());
+            return Self::change_state(Aborted::new(reason), state_machine).await;
         }
 
-        subscribe_to_topic(&state_machine.ctx, state_machine.p2p_topic.clone());
-        let swap_ctx = SwapsContext::from_ctx(&state_machine.ctx).expect("SwapsContext::from_ctx should not fail");
-        swap_ctx.init_msg_v2_store(state_machine.uuid, bits256::default());
+        init_swap_v2_p2p(&state_machine.ctx, state_machine.uuid);
 
         let maker_coin_start_block = match state_machine.maker_coin.current_block().compat().await {
             Ok(b) => b,
@@ -329,7 +613,9 @@ struct Initialized<MakerCoin, TakerCoin> {
 
 impl<MakerCoin, TakerCoin> TransitionFrom<Initialize<MakerCoin, TakerCoin>> for Initialized<MakerCoin, TakerCoin> {}
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableState for Initialized<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
+    for Initialized<MakerCoin, TakerCoin>
+{
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     fn get_event(&self) -> <<Self::StateMachine as StorableStateMachine>::Storage as StateMachineStorage>::Event {
@@ -347,12 +633,27 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State fo
     async fn on_changed(self: Box<Self>, state_machine: &mut Self::StateMachine) -> StateResult<Self::StateMachine> {
         let unique_data = state_machine.unique_data();
 
+        let maker_coin_htlc_pub = match state_machine.maker_coin.derive_htlc_pubkey(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                let reason = AbortReason::FailedToDeriveHtlcPubkey(e.to_string());
+                return Self::change_state(Aborted::new(reason), state_machine).await;
+            },
+        };
+        let taker_coin_htlc_pub = match state_machine.taker_coin.derive_htlc_pubkey(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                let reason = AbortReason::FailedToDeriveHtlcPubkey(e.to_string());
+                return Self::change_state(Aborted::new(reason), state_machine).await;
+            },
+        };
+
         let maker_negotiation_msg = MakerNegotiation {
             started_at: state_machine.started_at,
             payment_locktime: state_machine.maker_payment_locktime(),
             secret_hash: state_machine.secret_hash(),
-            maker_coin_htlc_pub: state_machine.maker_coin.derive_htlc_pubkey(&unique_data),
-            taker_coin_htlc_pub: state_machine.taker_coin.derive_htlc_pubkey(&unique_data),
+            maker_coin_htlc_pub,
+            taker_coin_htlc_pub,
             maker_coin_swap_contract: state_machine.maker_coin.swap_contract_address().map(|bytes| bytes.0),
             taker_coin_swap_contract: state_machine.taker_coin.swap_contract_address().map(|bytes| bytes.0),
         };
@@ -463,6 +764,7 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> NegotiationData<Maker
     fn to_stored_data(&self) -> StoredNegotiationData {
         StoredNegotiationData {
             taker_payment_locktime: self.taker_payment_locktime,
+            taker_funding_locktime: self.taker_funding_locktime,
             maker_coin_htlc_pub_from_taker: self.maker_coin_htlc_pub_from_taker.to_bytes().into(),
             taker_coin_htlc_pub_from_taker: self.taker_coin_htlc_pub_from_taker.to_bytes().into(),
             maker_coin_swap_contract: self.maker_coin_swap_contract.clone().map(|b| b.into()),
@@ -470,6 +772,26 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> NegotiationData<Maker
             taker_secret_hash: self.taker_secret_hash.clone().into(),
         }
     }
+
+    fn from_stored_data(
+        stored: StoredNegotiationData,
+        maker_coin: &MakerCoin,
+        taker_coin: &TakerCoin,
+    ) -> MmResult<Self, MakerSwapStateMachineError> {
+        Ok(NegotiationData {
+            taker_payment_locktime: stored.taker_payment_locktime,
+            taker_funding_locktime: stored.taker_funding_locktime,
+            maker_coin_htlc_pub_from_taker: maker_coin
+                .parse_pubkey(&stored.maker_coin_htlc_pub_from_taker.0)
+                .map_to_mm(|e| MakerSwapStateMachineError::RestoreError(e.to_string()))?,
+            taker_coin_htlc_pub_from_taker: taker_coin
+                .parse_pubkey(&stored.taker_coin_htlc_pub_from_taker.0)
+                .map_to_mm(|e| MakerSwapStateMachineError::RestoreError(e.to_string()))?,
+            maker_coin_swap_contract: stored.maker_coin_swap_contract.map(|b| b.0),
+            taker_coin_swap_contract: stored.taker_coin_swap_contract.map(|b| b.0),
+            taker_secret_hash: stored.taker_secret_hash.0,
+        })
+    }
 }
 
 struct WaitingForTakerFunding<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> {
@@ -539,7 +861,7 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for WaitingForTakerFunding<MakerCoin, TakerCoin>
 {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -578,6 +900,8 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
             funding_tx: &self.taker_funding,
             time_lock: self.negotiation_data.taker_funding_locktime,
             taker_secret_hash: &self.negotiation_data.taker_secret_hash,
+            maker_secret_hash: &state_machine.secret_hash(),
+            payment_time_lock: self.negotiation_data.taker_payment_locktime,
             other_pub: &self.negotiation_data.taker_coin_htlc_pub_from_taker,
             dex_fee_amount: state_machine.dex_fee_amount.to_decimal(),
             premium_amount: state_machine.taker_premium.to_decimal(),
@@ -590,9 +914,17 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
             return Self::change_state(Aborted::new(reason), state_machine).await;
         }
 
+        let maker_pub = match state_machine.taker_coin.derive_htlc_pubkey_v2(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                let reason = AbortReason::FailedToDeriveHtlcPubkey(e.to_string());
+                return Self::change_state(Aborted::new(reason), state_machine).await;
+            },
+        };
+
         let args = GenTakerFundingSpendArgs {
             funding_tx: &self.taker_funding,
-            maker_pub: &state_machine.taker_coin.derive_htlc_pubkey_v2(&unique_data),
+            maker_pub: &maker_pub,
             taker_pub: &self.negotiation_data.taker_coin_htlc_pub_from_taker,
             funding_time_lock: self.negotiation_data.taker_funding_locktime,
             taker_secret_hash: &self.negotiation_data.taker_secret_hash,
@@ -617,7 +949,11 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
             other_pubkey: &self.negotiation_data.maker_coin_htlc_pub_from_taker.to_bytes(),
             secret_hash: &state_machine.secret_hash(),
             amount: state_machine.maker_volume.to_decimal(),
-            swap_contract_address: &None,
+            swap_contract_address: &self
+                .negotiation_data
+                .maker_coin_swap_contract
+                .clone()
+                .map(|bytes| bytes.into()),
             swap_unique_data: &unique_data,
             payment_instructions: &None,
             watcher_reward: None,
@@ -651,7 +987,7 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for TakerFundingReceived<MakerCoin, TakerCoin>
 {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -772,7 +1108,7 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for MakerPaymentSentFundingSpendGenerated<MakerCoin, TakerCoin>
 {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -782,13 +1118,21 @@ impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableS
             maker_coin_start_block: self.maker_coin_start_block,
             taker_coin_start_block: self.taker_coin_start_block,
             negotiation_data: self.negotiation_data.to_stored_data(),
+            funding_spend_preimage: Some(StoredTxPreimage {
+                preimage: self.funding_spend_preimage.preimage.to_bytes().into(),
+                signature: self.funding_spend_preimage.signature.to_bytes().into(),
+            }),
             maker_payment: self.maker_payment.clone(),
         }
     }
 }
 
-#[derive(Debug)]
-enum MakerPaymentRefundReason {
+/// Represents the reason of maker payment refund
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub enum MakerPaymentRefundReason {
+    /// The reason is not stored by the previous versions.
+    #[default]
+    Unknown,
     DidNotGetTakerPayment(String),
     FailedToParseTakerPayment(String),
     TakerPaymentNotConfirmedInTime(String),
@@ -797,6 +1141,7 @@ enum MakerPaymentRefundReason {
     FailedToParseTakerPreimage(String),
     FailedToParseTakerSignature(String),
     TakerPaymentSpendBroadcastFailed(String),
+    FailedToDeriveHtlcPubkey(String),
 }
 
 struct MakerPaymentRefundRequired<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> {
@@ -818,10 +1163,8 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: SwapOpsV2> TransitionFrom<TakerPaymen
 }
 
 #[async_trait]
-impl<
-        MakerCoin: CoinAssocTypes + Send + Sync + 'static,
-        TakerCoin: MarketCoinOps + CoinAssocTypes + Send + Sync + 'static,
-    > State for MakerPaymentRefundRequired<MakerCoin, TakerCoin>
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
+    for MakerPaymentRefundRequired<MakerCoin, TakerCoin>
 {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
 
@@ -834,7 +1177,7 @@ impl<
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: CoinAssocTypes + Send + 'static> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for MakerPaymentRefundRequired<MakerCoin, TakerCoin>
 {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -845,6 +1188,7 @@ impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: CoinAssocTypes + Sen
             taker_coin_start_block: self.taker_coin_start_block,
             negotiation_data: self.negotiation_data.to_stored_data(),
             maker_payment: self.maker_payment.clone(),
+            reason: self.reason.clone(),
         }
     }
 }
@@ -894,11 +1238,25 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
 
         let unique_data = state_machine.unique_data();
 
+        let maker_pub = match state_machine.taker_coin.derive_htlc_pubkey_v2(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                let next_state = MakerPaymentRefundRequired {
+                    maker_coin_start_block: self.maker_coin_start_block,
+                    taker_coin_start_block: self.taker_coin_start_block,
+                    negotiation_data: self.negotiation_data,
+                    maker_payment: self.maker_payment,
+                    reason: MakerPaymentRefundReason::FailedToDeriveHtlcPubkey(e.to_string()),
+                };
+                return Self::change_state(next_state, state_machine).await;
+            },
+        };
+
         let gen_args = GenTakerPaymentSpendArgs {
             taker_tx: &self.taker_payment,
             time_lock: self.negotiation_data.taker_payment_locktime,
             secret_hash: &state_machine.secret_hash(),
-            maker_pub: &state_machine.taker_coin.derive_htlc_pubkey_v2(&unique_data),
+            maker_pub: &maker_pub,
             taker_pub: &self.negotiation_data.taker_coin_htlc_pub_from_taker,
             dex_fee_amount: state_machine.dex_fee_amount.to_decimal(),
             premium_amount: Default::default(),
@@ -992,7 +1350,7 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for TakerPaymentConfirmed<MakerCoin, TakerCoin>
 {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -1026,7 +1384,7 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> TransitionFrom<TakerP
 }
 
 #[async_trait]
-impl<MakerCoin: Send + Sync + 'static, TakerCoin: CoinAssocTypes + Send + Sync + 'static> State
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     for TakerPaymentSpent<MakerCoin, TakerCoin>
 {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -1036,7 +1394,7 @@ impl<MakerCoin: Send + Sync + 'static, TakerCoin: CoinAssocTypes + Send + Sync +
     }
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: CoinAssocTypes + Send + 'static> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for TakerPaymentSpent<MakerCoin, TakerCoin>
 {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -1073,6 +1431,9 @@ pub enum AbortReason {
     TakerProvidedInvalidFundingLocktime(u64),
     TakerProvidedInvalidPaymentLocktime(u64),
     FailedToParsePubkey(String),
+    FailedToDeriveHtlcPubkey(String),
+    AbortedByUser,
+    FailedToStoreSwapData(String),
 }
 
 struct Aborted<MakerCoin, TakerCoin> {
@@ -1092,7 +1453,7 @@ impl<MakerCoin, TakerCoin> Aborted<MakerCoin, TakerCoin> {
 }
 
 #[async_trait]
-impl<MakerCoin: Send + Sync + 'static, TakerCoin: Send + Sync + 'static> LastState for Aborted<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> LastState for Aborted<MakerCoin, TakerCoin> {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     async fn on_changed(
@@ -1103,7 +1464,9 @@ impl<MakerCoin: Send + Sync + 'static, TakerCoin: Send + Sync + 'static> LastSta
     }
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableState for Aborted<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
+    for Aborted<MakerCoin, TakerCoin>
+{
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     fn get_event(&self) -> <<Self::StateMachine as StorableStateMachine>::Storage as StateMachineStorage>::Event {
@@ -1138,7 +1501,9 @@ impl<MakerCoin, TakerCoin> Completed<MakerCoin, TakerCoin> {
     }
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableState for Completed<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
+    for Completed<MakerCoin, TakerCoin>
+{
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     fn get_event(&self) -> <<Self::StateMachine as StorableStateMachine>::Storage as StateMachineStorage>::Event {
@@ -1147,7 +1512,7 @@ impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableState for Com
 }
 
 #[async_trait]
-impl<MakerCoin: Send + Sync + 'static, TakerCoin: Send + Sync + 'static> LastState for Completed<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> LastState for Completed<MakerCoin, TakerCoin> {
     type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     async fn on_changed(
@@ -1162,3 +1527,178 @@ impl<MakerCoin, TakerCoin: CoinAssocTypes> TransitionFrom<TakerPaymentSpent<Make
     for Completed<MakerCoin, TakerCoin>
 {
 }
+
+struct Refunded<MakerCoin, TakerCoin> {
+    maker_coin: PhantomData<MakerCoin>,
+    taker_coin: PhantomData<TakerCoin>,
+}
+
+impl<MakerCoin, TakerCoin> Refunded<MakerCoin, TakerCoin> {
+    fn new() -> Refunded<MakerCoin, TakerCoin> {
+        Refunded {
+            maker_coin: Default::default(),
+            taker_coin: Default::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> LastState for Refunded<MakerCoin, TakerCoin> {
+    type StateMachine = MakerSwapStateMachine<MakerCoin, TakerCoin>;
+
+    async fn on_changed(
+        self: Box<Self>,
+        state_machine: &mut Self::StateMachine,
+    ) -> <Self::StateMachine as StateMachineTrait>::Result {
+        info!("Swap {} maker payment has been refunded", state_machine.uuid);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::mm2::lp_swap::swap_v2_common::tests::{store_test_swap_v2, swap_v2_test_ctx};
+    use coins::TestCoin;
+    use common::block_on;
+    use serde_json::Value as Json;
+
+    type TestMakerSwap = MakerSwapStateMachine<TestCoin, TestCoin>;
+
+    /// The negotiation data stored by the previous versions, which don't have `taker_funding_locktime`.
+    fn legacy_negotiation_data() -> Json {
+        json!({
+            "taker_payment_locktime": 1700015600,
+            "maker_coin_htlc_pub_from_taker": "02",
+            "taker_coin_htlc_pub_from_taker": "03",
+            "maker_coin_swap_contract": null,
+            "taker_coin_swap_contract": null,
+            "taker_secret_hash": "0404"
+        })
+    }
+
+    fn restore_maker_swap(
+        ctx: &MmArc,
+        uuid: Uuid,
+    ) -> MmResult<RestoredMachine<TestMakerSwap>, MakerSwapStateMachineError> {
+        let recreate_ctx = SwapRecreateCtx {
+            maker_coin: TestCoin::default(),
+            taker_coin: TestCoin::default(),
+        };
+        block_on(TestMakerSwap::restore_from_storage(
+            uuid,
+            MakerSwapStorage::new(ctx.clone()),
+            recreate_ctx,
+        ))
+    }
+
+    #[test]
+    fn test_deserialize_legacy_maker_events() {
+        let event: MakerSwapEvent = serde_json::from_value(json!({
+            "MakerPaymentSent": {
+                "maker_coin_start_block": 1,
+                "taker_coin_start_block": 2,
+                "negotiation_data": legacy_negotiation_data(),
+                "maker_payment": {"tx_hex": "01", "tx_hash": "02"}
+            }
+        }))
+        .unwrap();
+        match event {
+            MakerSwapEvent::MakerPaymentSent {
+                negotiation_data,
+                funding_spend_preimage,
+                ..
+            } => {
+                assert_eq!(negotiation_data.taker_funding_locktime, 0);
+                assert!(funding_spend_preimage.is_none());
+            },
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        let event: MakerSwapEvent = serde_json::from_value(json!({
+            "MakerPaymentRefundRequired": {
+                "maker_coin_start_block": 1,
+                "taker_coin_start_block": 2,
+                "negotiation_data": legacy_negotiation_data(),
+                "maker_payment": {"tx_hex": "01", "tx_hash": "02"}
+            }
+        }))
+        .unwrap();
+        assert!(matches!(event, MakerSwapEvent::MakerPaymentRefundRequired {
+            reason: MakerPaymentRefundReason::Unknown,
+            ..
+        }));
+    }
+
+    #[test]
+    fn test_restore_maker_swap_mid_swap() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::new_v4();
+        let mut negotiation_data = legacy_negotiation_data();
+        negotiation_data["taker_funding_locktime"] = json!(1700031200);
+        let events = [
+            json!({"Initialized": {"maker_coin_start_block": 1, "taker_coin_start_block": 2}}),
+            json!({"WaitingForTakerFunding": {
+                "maker_coin_start_block": 1,
+                "taker_coin_start_block": 2,
+                "negotiation_data": negotiation_data.clone()
+            }}),
+            json!({"TakerFundingReceived": {
+                "maker_coin_start_block": 1,
+                "taker_coin_start_block": 2,
+                "negotiation_data": negotiation_data.clone(),
+                "taker_funding": {"tx_hex": "01", "tx_hash": "02"}
+            }}),
+            json!({"MakerPaymentSent": {
+                "maker_coin_start_block": 1,
+                "taker_coin_start_block": 2,
+                "negotiation_data": negotiation_data.clone(),
+                "funding_spend_preimage": {"preimage": "05", "signature": "06"},
+                "maker_payment": {"tx_hex": "03", "tx_hash": "04"}
+            }}),
+        ];
+        store_test_swap_v2(&ctx, uuid, MAKER_SWAP_V2_TYPE, &events);
+
+        let restored = restore_maker_swap(&ctx, uuid).unwrap();
+        assert_eq!(restored.machine.uuid, uuid);
+        assert_eq!(restored.machine.maker_volume, MmNumber::from(1));
+        assert_eq!(restored.machine.taker_volume, MmNumber::from(2));
+        assert_eq!(restored.machine.lock_duration, 7800);
+        assert_eq!(restored.machine.secret, H256::from([1; 32]));
+
+        let stored: StoredNegotiationData = serde_json::from_value(negotiation_data).unwrap();
+        let negotiation_data = restored.machine.negotiation_data_from_stored(stored).unwrap();
+        assert_eq!(negotiation_data.taker_funding_locktime, 1700031200);
+    }
+
+    #[test]
+    fn test_restore_legacy_maker_swap() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::new_v4();
+        let events = [json!({"TakerFundingSpendReceived": {
+            "maker_coin_start_block": 1,
+            "taker_coin_start_block": 2,
+            "negotiation_data": legacy_negotiation_data(),
+            "taker_funding": {"tx_hex": "01", "tx_hash": "02"},
+            "maker_payment": {"tx_hex": "03", "tx_hash": "04"},
+            "taker_funding_preimage": "05",
+            "taker_funding_spend_signature": "06"
+        }})];
+        store_test_swap_v2(&ctx, uuid, MAKER_SWAP_V2_TYPE, &events);
+
+        let restored = restore_maker_swap(&ctx, uuid).unwrap();
+        // The funding locktime is recalculated the way taker does it.
+        let stored: StoredNegotiationData = serde_json::from_value(legacy_negotiation_data()).unwrap();
+        let negotiation_data = restored.machine.negotiation_data_from_stored(stored).unwrap();
+        assert_eq!(negotiation_data.taker_funding_locktime, 1700015600 + 2 * 7800);
+    }
+
+    #[test]
+    fn test_restore_maker_swap_without_events() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::new_v4();
+        store_test_swap_v2(&ctx, uuid, MAKER_SWAP_V2_TYPE, &[]);
+
+        let err = restore_maker_swap(&ctx, uuid).err().unwrap();
+        assert!(matches!(err.get_inner(), MakerSwapStateMachineError::RestoreError(_)));
+    }
+}
@@ -38,6 +38,9 @@ pub trait MySwapsOps {
         filter: &MySwapsFilter,
         paging_options: Option<&PagingOptions>,
     ) -> MySwapsResult<MyRecentSwapsUuids>;
+
+    /// Returns whether the swap with the given uuid is saved by [`MySwapsOps::save_new_swap`].
+    async fn is_my_swap(&self, uuid: Uuid) -> MySwapsResult<bool>;
 }
 
 pub struct MySwapsStorage {
@@ -51,7 +54,8 @@ impl MySwapsStorage {
 #[cfg(not(target_arch = "wasm32"))]
 mod native_impl {
     use super::*;
-    use crate::mm2::database::my_swaps::{insert_new_swap, select_uuids_by_my_swaps_filter, SelectRecentSwapsUuidsErr};
+    use crate::mm2::database::my_swaps::{get_swap_type, insert_new_swap, select_uuids_by_my_swaps_filter,
+                                         SelectRecentSwapsUuidsErr};
     use db_common::sqlite::rusqlite::Error as SqlError;
 
     impl From<SelectRecentSwapsUuidsErr> for MySwapsError {
@@ -96,6 +100,14 @@ mod native_impl {
                 paging_options,
             )?)
         }
+
+        async fn is_my_swap(&self, uuid: Uuid) -> MySwapsResult<bool> {
+            match get_swap_type(&self.ctx.sqlite_connection(), &uuid.to_string()) {
+                Ok(_) => Ok(true),
+                Err(SqlError::QueryReturnedNoRows) => Ok(false),
+                Err(e) => MmError::err(e.into()),
+            }
+        }
     }
 }
 
@@ -256,6 +268,16 @@ mod wasm_impl {
                 },
             }
         }
+
+        async fn is_my_swap(&self, uuid: Uuid) -> MySwapsResult<bool> {
+            let swap_ctx = SwapsContext::from_ctx(&self.ctx).map_to_mm(MySwapsError::InternalError)?;
+            let db = swap_ctx.swap_db().await?;
+            let transaction = db.transaction().await?;
+            let my_swaps_table = transaction.table::<MySwapsFiltersTable>().await?;
+
+            let item = my_swaps_table.get_item_by_unique_index("uuid", uuid).await?;
+            Ok(item.is_some())
+        }
     }
 
     pub(super) fn take_according_to_paging_opts(
@@ -0,0 +1,162 @@
+//! The reputation of the pubkeys built from the outcomes of their swaps with this node.
+//!
+//! The outcomes are taken from the `stats_swaps` table, but only the swaps of this node are counted
+//! since the swaps broadcasted by the other nodes can be forged. A failed swap is counted against
+//! the pubkey only if the pubkey is known to cause the failure.
+
+#[cfg(not(target_arch = "wasm32"))] use common::async_blocking;
+use common::log::warn;
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigRational, MmNumber, MmNumberMultiRepr};
+use rpc::v1::types::H256 as H256Json;
+
+/// A refunded swap locks the counterparty funds until the locktime, so it weighs more than an aborted one.
+const REFUNDED_SWAP_WEIGHT: u64 = 2;
+
+pub type PubkeyReputationResult<T> = Result<T, MmError<PubkeyReputationError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum PubkeyReputationError {
+    #[display(fmt = "{} is only supported in native mode", _0)]
+    UnsupportedMode(String),
+    #[display(fmt = "Database error: {}", _0)]
+    DatabaseError(String),
+}
+
+impl HttpStatusCode for PubkeyReputationError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PubkeyReputationError::UnsupportedMode(_) => StatusCode::METHOD_NOT_ALLOWED,
+            PubkeyReputationError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PubkeyReputation {
+    pub pubkey: H256Json,
+    pub completed_swaps: u64,
+    /// The failed swaps that didn't require a refund.
+    pub aborted_swaps: u64,
+    /// The failed swaps that required a payment to be refunded.
+    pub refunded_swaps: u64,
+    /// The average duration of the completed swaps in seconds.
+    pub avg_swap_duration: Option<u64>,
+    /// The score from 0 to 1 calculated as `(completed + 1) / (completed + aborted + 2 * refunded + 2)`,
+    /// so the pubkey without the swaps with this node scores 0.5.
+    pub score: MmNumberMultiRepr,
+}
+
+fn reputation_score(completed: u64, aborted: u64, refunded: u64) -> MmNumber {
+    let failed = aborted + REFUNDED_SWAP_WEIGHT * refunded;
+    BigRational::new((completed + 1).into(), (completed + failed + 2).into()).into()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn pubkey_reputation(ctx: &MmArc, pubkey: H256Json) -> PubkeyReputationResult<PubkeyReputation> {
+    use crate::mm2::database::stats_swaps::select_pubkey_swap_outcomes;
+
+    let ctx = ctx.clone();
+    let outcomes = async_blocking(move || {
+        let conn = ctx.sqlite_connection();
+        select_pubkey_swap_outcomes(&conn, &hex::encode(pubkey.0))
+    })
+    .await
+    .map_to_mm(|e| PubkeyReputationError::DatabaseError(e.to_string()))?;
+    Ok(PubkeyReputation {
+        pubkey,
+        completed_swaps: outcomes.completed,
+        aborted_swaps: outcomes.aborted,
+        refunded_swaps: outcomes.refunded,
+        avg_swap_duration: outcomes.avg_swap_duration,
+        score: reputation_score(outcomes.completed, outcomes.aborted, outcomes.refunded).into(),
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+pub async fn pubkey_reputation(_ctx: &MmArc, _pubkey: H256Json) -> PubkeyReputationResult<PubkeyReputation> {
+    MmError::err(PubkeyReputationError::UnsupportedMode("Pubkey reputation".into()))
+}
+
+/// Whether the reputation score of the counterparty is at least `min_reputation`.
+/// The counterparty is rejected if its reputation can't be determined.
+pub async fn has_min_reputation(ctx: &MmArc, pubkey: &H256Json, min_reputation: &MmNumber) -> bool {
+    match pubkey_reputation(ctx, *pubkey).await {
+        Ok(reputation) => reputation.score.rational >= min_reputation.to_ratio(),
+        Err(e) => {
+            warn!("Error {} on getting the reputation of pubkey {}", e, pubkey);
+            false
+        },
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PubkeyReputationRequest {
+    pubkeys: Vec<H256Json>,
+}
+
+#[derive(Serialize)]
+pub struct PubkeyReputationResponse {
+    reputations: Vec<PubkeyReputation>,
+}
+
+pub async fn pubkey_reputation_rpc(
+    ctx: MmArc,
+    req: PubkeyReputationRequest,
+) -> PubkeyReputationResult<PubkeyReputationResponse> {
+    let mut reputations = Vec::with_capacity(req.pubkeys.len());
+    for pubkey in req.pubkeys {
+        reputations.push(pubkey_reputation(&ctx, pubkey).await?);
+    }
+    Ok(PubkeyReputationResponse { reputations })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reputation_score() {
+        assert_eq!(reputation_score(0, 0, 0), MmNumber::from("0.5"));
+        assert_eq!(reputation_score(8, 0, 0), MmNumber::from("0.9"));
+        assert_eq!(reputation_score(7, 1, 0), MmNumber::from("0.8"));
+        // The refunded swap weighs twice as much as the aborted one.
+        assert_eq!(reputation_score(6, 0, 1), MmNumber::from("0.7"));
+        assert_eq!(reputation_score(0, 2, 3), MmNumber::from("0.1"));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_has_min_reputation() {
+        use crate::mm2::database::stats_swaps::insert_swap_outcomes_for_tests;
+        use common::block_on;
+        use mm2_test_helpers::for_tests::mm_ctx_with_custom_db;
+
+        let ctx = mm_ctx_with_custom_db();
+        let bad = H256Json([1; 32]);
+        let bad_pubkey = format!("02{}", hex::encode(bad.0));
+        let other = format!("03{}", "b".repeat(64));
+        insert_swap_outcomes_for_tests(&ctx.sqlite_connection(), &[
+            (200, false, &bad_pubkey, &other, true, Some(&bad_pubkey), true),
+            // The forged swaps broadcasted by the other nodes don't improve the reputation.
+            (300, true, &bad_pubkey, &other, false, None, false),
+            (400, true, &bad_pubkey, &other, false, None, false),
+        ]);
+
+        let reputation = block_on(pubkey_reputation(&ctx, bad)).unwrap();
+        assert_eq!(reputation.completed_swaps, 0);
+        assert_eq!(reputation.refunded_swaps, 1);
+        assert_eq!(reputation.score.rational, MmNumber::from("0.25").to_ratio());
+
+        assert!(block_on(has_min_reputation(&ctx, &bad, &MmNumber::from("0.25"))));
+        assert!(!block_on(has_min_reputation(&ctx, &bad, &MmNumber::from("0.5"))));
+        // The pubkey without the swaps with this node scores 0.5.
+        let unknown = H256Json([2; 32]);
+        assert!(block_on(has_min_reputation(&ctx, &unknown, &MmNumber::from("0.5"))));
+    }
+}
@@ -342,6 +342,8 @@ async fn recreate_taker_swap(ctx: MmArc, maker_swap: MakerSavedSwap) -> Recreate
         taker_payment_trade_fee: None,
         // Don't set the fee since the value is used when we calculate locked by other swaps amount only.
         maker_payment_spend_trade_fee: None,
+        // The dex fee isn't known from the maker swap events.
+        dex_fee: None,
         maker_coin_swap_contract_address: negotiated_event.maker_coin_swap_contract_addr.clone(),
         taker_coin_swap_contract_address: negotiated_event.taker_coin_swap_contract_addr.clone(),
         maker_coin_htlc_pubkey: negotiated_event.maker_coin_htlc_pubkey,
@@ -0,0 +1,107 @@
+use super::maker_swap::MakerSavedEvent;
+use super::maker_swap_v2::MakerSwapEvent;
+use super::taker_swap::TakerSavedEvent;
+use super::taker_swap_v2::TakerSwapEvent;
+use super::SwapsContext;
+use crate::mm2::lp_event_streaming::{spawn_streamer_if_active, stream_channel_events, try_send_stream_message};
+use async_trait::async_trait;
+use common::log::error;
+use futures::channel::mpsc::Sender;
+use futures::channel::oneshot;
+use mm2_core::mm_ctx::MmArc;
+use mm2_event_stream::{behaviour::{EventBehaviour, EventInitStatus},
+                       Event, EventStreamConfiguration};
+use uuid::Uuid;
+
+pub type SwapStatusEventSender = Sender<SwapStatusEvent>;
+
+/// The swap state transition streamed to the clients.
+#[derive(Serialize)]
+#[serde(tag = "swap_type")]
+pub enum SwapStatusEvent {
+    MakerV1 { uuid: Uuid, event: MakerSavedEvent },
+    TakerV1 { uuid: Uuid, event: TakerSavedEvent },
+    MakerV2 { uuid: Uuid, event: MakerSwapEvent },
+    TakerV2 { uuid: Uuid, event: TakerSwapEvent },
+}
+
+/// Forwards the event to the `SWAP_STATUS` streamer if the streaming is active.
+/// Doesn't wait for the event to be delivered, so the swap is never blocked by slow clients.
+pub(super) fn broadcast_swap_status_event(ctx: &MmArc, event: SwapStatusEvent) {
+    let swap_ctx = match SwapsContext::from_ctx(ctx) {
+        Ok(swap_ctx) => swap_ctx,
+        Err(e) => {
+            error!("Failed to get SwapsContext: {}", e);
+            return;
+        },
+    };
+
+    if let Some(tx) = &mut *swap_ctx.swap_status_event_tx.lock() {
+        try_send_stream_message(tx, SwapStatusStreamer::EVENT_NAME, event);
+    }
+}
+
+pub struct SwapStatusStreamer {
+    ctx: MmArc,
+}
+
+impl SwapStatusStreamer {
+    pub fn new(ctx: MmArc) -> Self { Self { ctx } }
+}
+
+#[async_trait]
+impl EventBehaviour for SwapStatusStreamer {
+    const EVENT_NAME: &'static str = "SWAP_STATUS";
+
+    async fn handle(self, _interval: f64, tx: oneshot::Sender<EventInitStatus>) {
+        let set_sender = |event_tx: SwapStatusEventSender| -> Result<(), String> {
+            let swap_ctx = SwapsContext::from_ctx(&self.ctx)?;
+            *swap_ctx.swap_status_event_tx.lock() = Some(event_tx);
+            Ok(())
+        };
+        let to_events = |event: SwapStatusEvent| match serde_json::to_string(&event) {
+            Ok(message) => vec![Event::new(Self::EVENT_NAME.to_string(), message)],
+            Err(e) => {
+                error!("Failed to serialize the swap status event: {}", e);
+                Vec::new()
+            },
+        };
+        stream_channel_events(&self.ctx, tx, set_sender, to_events).await
+    }
+
+    async fn spawn_if_active(self, config: &EventStreamConfiguration) -> EventInitStatus {
+        let ctx = self.ctx.clone();
+        spawn_streamer_if_active(&ctx, self, config).await
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use common::block_on;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use serde_json::Value as Json;
+
+    #[test]
+    fn test_swap_status_streamer() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let mut rx = ctx.stream_channel_controller.clone().create_channel(4);
+        let config: EventStreamConfiguration =
+            serde_json::from_value(json!({ "active_events": { "SWAP_STATUS": {} } })).unwrap();
+
+        let status = block_on(SwapStatusStreamer::new(ctx.clone()).spawn_if_active(&config));
+        assert!(matches!(status, EventInitStatus::Success));
+
+        let uuid = Uuid::new_v4();
+        broadcast_swap_status_event(&ctx, SwapStatusEvent::TakerV2 {
+            uuid,
+            event: TakerSwapEvent::Completed,
+        });
+
+        let event = block_on(rx.recv()).unwrap();
+        assert_eq!(event.event_type(), "SWAP_STATUS");
+        let actual: Json = serde_json::from_str(event.message()).unwrap();
+        let expected = json!({ "swap_type": "TakerV2", "uuid": uuid, "event": "Completed" });
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,1111 @@
+use crate::mm2::lp_network::subscribe_to_topic;
+use crate::mm2::lp_swap::maker_swap_v2::{MakerSwapStateMachine, MakerSwapStateMachineError, MakerSwapStorage};
+use crate::mm2::lp_swap::swap_v2_pb::SwapMessage;
+use crate::mm2::lp_swap::taker_swap_v2::{TakerSwapStateMachine, TakerSwapStateMachineError, TakerSwapStorage};
+use crate::mm2::lp_swap::{swap_v2_topic, MySwapsFilter, SecretHashAlgo, SwapConfirmationsSettings, SwapsContext,
+                          TransactionIdentifier, MAKER_SWAP_V2_TYPE, TAKER_SWAP_V2_TYPE};
+use coins::{lp_coinfind, CoinAssocTypes, MmCoin, MmCoinEnum, SwapOpsV2};
+use common::bits256;
+use common::executor::{SpawnFuture, Timer};
+use common::log::{error, info, warn, LogOnError};
+use common::PagingOptions;
+use crypto::privkey::key_pair_from_secret;
+use derive_more::Display;
+use keys::KeyPair;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::MmNumber;
+use mm2_state_machine::prelude::*;
+use mm2_state_machine::storable_state_machine::*;
+use rpc::v1::types::Bytes as BytesJson;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value as Json;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::future::Future;
+use uuid::Uuid;
+
+/// Represents the preimage of a transaction along with the signature, as it's stored in DB.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StoredTxPreimage {
+    pub preimage: BytesJson,
+    pub signature: BytesJson,
+}
+
+/// Represents the complete data of the swap v2 stored in DB, which is required to recreate its state machine.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MySwapV2Data {
+    pub my_coin: String,
+    pub other_coin: String,
+    pub started_at: i64,
+    pub events_json: String,
+    pub maker_volume: String,
+    pub taker_volume: String,
+    pub premium: String,
+    pub dex_fee: String,
+    pub secret: Vec<u8>,
+    pub secret_hash_algo: u8,
+    pub p2p_privkey: Vec<u8>,
+    pub lock_duration: i64,
+    pub maker_coin_confs: i64,
+    pub maker_coin_nota: bool,
+    pub taker_coin_confs: i64,
+    pub taker_coin_nota: bool,
+}
+
+/// Represents the data of the swap v2 exposed by the RPCs, omits the fields that should be kept in secret.
+#[derive(Debug)]
+pub struct MySwapV2ForRpc {
+    pub uuid: Uuid,
+    pub swap_type: u8,
+    pub my_coin: String,
+    pub other_coin: String,
+    pub started_at: i64,
+    pub is_finished: bool,
+    pub events_json: String,
+    pub maker_volume: String,
+    pub taker_volume: String,
+    pub premium: String,
+    pub dex_fee: String,
+    pub secret_hash: Vec<u8>,
+    pub lock_duration: i64,
+}
+
+/// The page of the swaps v2 selected by [`load_swaps_v2_for_rpc`], the most recent ones first.
+pub struct MySwapsV2ForRpc {
+    pub swaps: Vec<MySwapV2ForRpc>,
+    pub total_count: usize,
+    pub skipped: usize,
+}
+
+/// Represents errors of the swap v2 storage: `my_swaps` SQLite table on native and IndexedDB on WASM.
+#[derive(Debug, Display)]
+pub enum SwapV2DbError {
+    StorageError(String),
+    SerdeError(String),
+}
+
+impl From<SwapV2DbError> for MakerSwapStateMachineError {
+    fn from(e: SwapV2DbError) -> Self {
+        match e {
+            SwapV2DbError::StorageError(e) => MakerSwapStateMachineError::StorageError(e),
+            SwapV2DbError::SerdeError(e) => MakerSwapStateMachineError::SerdeError(e),
+        }
+    }
+}
+
+impl From<SwapV2DbError> for TakerSwapStateMachineError {
+    fn from(e: SwapV2DbError) -> Self {
+        match e {
+            SwapV2DbError::StorageError(e) => TakerSwapStateMachineError::StorageError(e),
+            SwapV2DbError::SerdeError(e) => TakerSwapStateMachineError::SerdeError(e),
+        }
+    }
+}
+
+impl From<SwapV2DbError> for ManualRefundError {
+    fn from(e: SwapV2DbError) -> Self {
+        match e {
+            SwapV2DbError::StorageError(e) => ManualRefundError::StorageError(e),
+            SwapV2DbError::SerdeError(e) => ManualRefundError::RestoreError(e),
+        }
+    }
+}
+
+/// Represents errors that can occur on manual refund of the swap v2.
+#[derive(Debug, Display)]
+pub enum ManualRefundError {
+    #[display(fmt = "Refund is not required at the current swap state: {}", _0)]
+    RefundIsNotRequired(String),
+    #[display(fmt = "Payment locktime {} has not expired yet, current time {}", locktime, now)]
+    LocktimeNotExpired {
+        locktime: u64,
+        now: u64,
+    },
+    RestoreError(String),
+    StorageError(String),
+    TransactionError(String),
+}
+
+impl From<MakerSwapStateMachineError> for ManualRefundError {
+    fn from(e: MakerSwapStateMachineError) -> Self {
+        match e {
+            MakerSwapStateMachineError::StorageError(e) => ManualRefundError::StorageError(e),
+            MakerSwapStateMachineError::SerdeError(e) | MakerSwapStateMachineError::RestoreError(e) => {
+                ManualRefundError::RestoreError(e)
+            },
+        }
+    }
+}
+
+impl From<TakerSwapStateMachineError> for ManualRefundError {
+    fn from(e: TakerSwapStateMachineError) -> Self {
+        match e {
+            TakerSwapStateMachineError::StorageError(e) => ManualRefundError::StorageError(e),
+            TakerSwapStateMachineError::SerdeError(e) | TakerSwapStateMachineError::RestoreError(e) => {
+                ManualRefundError::RestoreError(e)
+            },
+        }
+    }
+}
+
+/// Contains the coins required to recreate the swap v2 state machine from storage.
+pub struct SwapRecreateCtx<MakerCoin, TakerCoin> {
+    pub maker_coin: MakerCoin,
+    pub taker_coin: TakerCoin,
+}
+
+/// Swap parameters decoded from [`MySwapV2Data`], which are common for both maker and taker state machines.
+pub(super) struct SwapV2Params {
+    pub started_at: u64,
+    pub maker_volume: MmNumber,
+    pub taker_volume: MmNumber,
+    pub premium: MmNumber,
+    pub dex_fee: MmNumber,
+    pub secret: [u8; 32],
+    pub secret_hash_algo: SecretHashAlgo,
+    pub p2p_keypair: Option<KeyPair>,
+    pub lock_duration: u64,
+    pub conf_settings: SwapConfirmationsSettings,
+}
+
+impl TryFrom<&MySwapV2Data> for SwapV2Params {
+    type Error = String;
+
+    fn try_from(data: &MySwapV2Data) -> Result<Self, Self::Error> {
+        let parse_number = |input: &str| MmNumber::from_fraction_string(input).map_err(|e| e.to_string());
+
+        if data.secret.len() != 32 {
+            return Err(format!("Unexpected secret length {}", data.secret.len()));
+        }
+        let mut secret = [0; 32];
+        secret.copy_from_slice(&data.secret);
+
+        // p2p_privkey is stored as zeroes if the swap is signed by the persistent key
+        let p2p_keypair = if data.p2p_privkey.iter().all(|byte| *byte == 0) {
+            None
+        } else {
+            Some(key_pair_from_secret(&data.p2p_privkey).map_err(|e| e.to_string())?)
+        };
+
+        Ok(SwapV2Params {
+            started_at: data.started_at as u64,
+            maker_volume: parse_number(&data.maker_volume)?,
+            taker_volume: parse_number(&data.taker_volume)?,
+            premium: parse_number(&data.premium)?,
+            dex_fee: parse_number(&data.dex_fee)?,
+            secret,
+            secret_hash_algo: SecretHashAlgo::try_from(data.secret_hash_algo)?,
+            p2p_keypair,
+            lock_duration: data.lock_duration as u64,
+            conf_settings: SwapConfirmationsSettings {
+                maker_coin_confs: data.maker_coin_confs as u64,
+                maker_coin_nota: data.maker_coin_nota,
+                taker_coin_confs: data.taker_coin_confs as u64,
+                taker_coin_nota: data.taker_coin_nota,
+            },
+        })
+    }
+}
+
+/// Subscribes to the swap P2P topic and initializes the messages store.
+/// Must be called before the swap state machine is started or restored.
+pub(super) fn init_swap_v2_p2p(ctx: &MmArc, uuid: Uuid) {
+    subscribe_to_topic(ctx, swap_v2_topic(&uuid));
+    let swap_ctx = SwapsContext::from_ctx(ctx).expect("SwapsContext::from_ctx should not fail");
+    swap_ctx.init_msg_v2_store(uuid, bits256::default());
+}
+
+/// Initializes the swap P2P and fills the messages store with the messages received before the restart,
+/// so the restored state machine can process them.
+pub(super) async fn restore_swap_v2_p2p(ctx: &MmArc, uuid: Uuid) -> MmResult<(), SwapV2DbError> {
+    init_swap_v2_p2p(ctx, uuid);
+    reload_swap_v2_msgs(ctx, uuid).await
+}
+
+/// Puts the persisted messages of the swap to its messages store.
+async fn reload_swap_v2_msgs(ctx: &MmArc, uuid: Uuid) -> MmResult<(), SwapV2DbError> {
+    use prost::Message;
+
+    let msgs = load_swap_v2_msgs(ctx, uuid).await?;
+    let swap_ctx = SwapsContext::from_ctx(ctx).expect("SwapsContext::from_ctx should not fail");
+    let mut stores = swap_ctx.swap_v2_msgs.lock().unwrap();
+    let store = stores.entry(uuid).or_default();
+    for msg in msgs {
+        match SwapMessage::decode(msg.as_slice()) {
+            Ok(SwapMessage { inner: Some(inner) }) => store.insert_msg(inner),
+            Ok(SwapMessage { inner: None }) => warn!("Stored swap {} message is empty", uuid),
+            Err(e) => warn!("Error {} on decoding stored swap {} message", e, uuid),
+        }
+    }
+    Ok(())
+}
+
+/// Loads unfinished swaps v2 from DB and spawns the tasks restoring them.
+/// Returns the tickers of the coins that must be enabled to kick-start these swaps.
+pub(super) async fn swap_v2_kick_starts(ctx: &MmArc) -> Result<HashSet<String>, String> {
+    let mut coins = HashSet::new();
+
+    for swap_type in [MAKER_SWAP_V2_TYPE, TAKER_SWAP_V2_TYPE] {
+        let uuids = try_s!(load_unfinished_swaps_v2(ctx, swap_type).await);
+        for uuid in uuids {
+            let swap_data = match load_swap_v2_data(ctx, uuid).await {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("Error {} on loading the swap {} data", e, uuid);
+                    continue;
+                },
+            };
+
+            info!("Kick starting the swap {}", uuid);
+            // my_coin is the maker coin for maker swap and the taker coin for taker swap
+            let (maker_coin_ticker, taker_coin_ticker) = if swap_type == MAKER_SWAP_V2_TYPE {
+                (swap_data.my_coin, swap_data.other_coin)
+            } else {
+                (swap_data.other_coin, swap_data.my_coin)
+            };
+            coins.insert(maker_coin_ticker.clone());
+            coins.insert(taker_coin_ticker.clone());
+
+            let fut = swap_v2_kickstart_handler(ctx.clone(), uuid, swap_type, maker_coin_ticker, taker_coin_ticker);
+            ctx.spawner().spawn(fut);
+        }
+    }
+    Ok(coins)
+}
+
+async fn wait_for_coin(ctx: &MmArc, uuid: &Uuid, ticker: &str) -> Option<MmCoinEnum> {
+    loop {
+        match lp_coinfind(ctx, ticker).await {
+            Ok(Some(c)) => return Some(c),
+            Ok(None) => {
+                info!(
+                    "Can't kickstart the swap {} until the coin {} is activated",
+                    uuid, ticker
+                );
+                Timer::sleep(5.).await;
+            },
+            Err(e) => {
+                error!("Error {} on {} find attempt", e, ticker);
+                return None;
+            },
+        };
+    }
+}
+
+/// Matches the maker and taker coins pair supported by the swap v2 protocol and expands to `$action!(maker, taker)`
+/// with the concrete coin types, or to `$unsupported` for the other pairs.
+#[macro_export]
+macro_rules! dispatch_swap_v2_coins_pair {
+    ($maker_coin: expr, $taker_coin: expr, $action: ident, $unsupported: expr) => {
+        match ($maker_coin, $taker_coin) {
+            (coins::MmCoinEnum::UtxoCoin(m), coins::MmCoinEnum::UtxoCoin(t)) => $action!(m, t),
+            (coins::MmCoinEnum::UtxoCoin(m), coins::MmCoinEnum::EthCoin(t)) => $action!(m, t),
+            (coins::MmCoinEnum::EthCoin(m), coins::MmCoinEnum::UtxoCoin(t)) => $action!(m, t),
+            (coins::MmCoinEnum::EthCoin(m), coins::MmCoinEnum::EthCoin(t)) => $action!(m, t),
+            (coins::MmCoinEnum::UtxoCoin(m), coins::MmCoinEnum::Tendermint(t)) => $action!(m, t),
+            (coins::MmCoinEnum::UtxoCoin(m), coins::MmCoinEnum::TendermintToken(t)) => $action!(m, t),
+            (coins::MmCoinEnum::Tendermint(m), coins::MmCoinEnum::UtxoCoin(t)) => $action!(m, t),
+            (coins::MmCoinEnum::TendermintToken(m), coins::MmCoinEnum::UtxoCoin(t)) => $action!(m, t),
+            (coins::MmCoinEnum::EthCoin(m), coins::MmCoinEnum::Tendermint(t)) => $action!(m, t),
+            (coins::MmCoinEnum::EthCoin(m), coins::MmCoinEnum::TendermintToken(t)) => $action!(m, t),
+            (coins::MmCoinEnum::Tendermint(m), coins::MmCoinEnum::EthCoin(t)) => $action!(m, t),
+            (coins::MmCoinEnum::TendermintToken(m), coins::MmCoinEnum::EthCoin(t)) => $action!(m, t),
+            (coins::MmCoinEnum::Tendermint(m), coins::MmCoinEnum::Tendermint(t)) => $action!(m, t),
+            (coins::MmCoinEnum::Tendermint(m), coins::MmCoinEnum::TendermintToken(t)) => $action!(m, t),
+            (coins::MmCoinEnum::TendermintToken(m), coins::MmCoinEnum::Tendermint(t)) => $action!(m, t),
+            (coins::MmCoinEnum::TendermintToken(m), coins::MmCoinEnum::TendermintToken(t)) => $action!(m, t),
+            _ => $unsupported,
+        }
+    };
+}
+
+/// Whether new swaps of the pair can be started using the swap v2 protocol,
+/// i.e. [`dispatch_swap_v2_coins_pair`] doesn't expand to `$unsupported` for it.
+///
+/// Tendermint taker coins are dispatched only to finish the running swaps: the taker can refund the IRIS HTLC funding
+/// without revealing the taker secret, see the limitation described in `coins::tendermint::tendermint_swap_v2`.
+pub fn is_swap_v2_pair_supported(maker_coin: &MmCoinEnum, taker_coin: &MmCoinEnum) -> bool {
+    if matches!(taker_coin, MmCoinEnum::Tendermint(_) | MmCoinEnum::TendermintToken(_)) {
+        return false;
+    }
+
+    macro_rules! supported {
+        ($maker_coin: expr, $taker_coin: expr) => {{
+            let _ = ($maker_coin, $taker_coin);
+            true
+        }};
+    }
+    crate::dispatch_swap_v2_coins_pair!(maker_coin, taker_coin, supported, false)
+}
+
+pub(super) async fn swap_v2_kickstart_handler(
+    ctx: MmArc,
+    uuid: Uuid,
+    swap_type: u8,
+    maker_coin_ticker: String,
+    taker_coin_ticker: String,
+) {
+    // Messages might be received while the coins are being activated, so restore the P2P first.
+    if let Err(e) = restore_swap_v2_p2p(&ctx, uuid).await {
+        error!("Error {} on restoring the swap {} P2P messages", e, uuid);
+    }
+
+    let maker_coin = match wait_for_coin(&ctx, &uuid, &maker_coin_ticker).await {
+        Some(c) => c,
+        None => return,
+    };
+    let taker_coin = match wait_for_coin(&ctx, &uuid, &taker_coin_ticker).await {
+        Some(c) => c,
+        None => return,
+    };
+
+    macro_rules! restore_and_run {
+        ($maker_coin: expr, $taker_coin: expr) => {
+            restore_and_run_swap_v2(ctx, uuid, swap_type, $maker_coin, $taker_coin).await
+        };
+    }
+    crate::dispatch_swap_v2_coins_pair!(
+        maker_coin,
+        taker_coin,
+        restore_and_run,
+        warn!(
+            "Swap {} can't be kick-started: {}/{} pair is not supported by the swap v2 protocol",
+            uuid, maker_coin_ticker, taker_coin_ticker
+        )
+    )
+}
+
+async fn restore_and_run_swap_v2<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2>(
+    ctx: MmArc,
+    uuid: Uuid,
+    swap_type: u8,
+    maker_coin: MakerCoin,
+    taker_coin: TakerCoin,
+) {
+    let recreate_ctx = SwapRecreateCtx { maker_coin, taker_coin };
+    if swap_type == MAKER_SWAP_V2_TYPE {
+        let storage = MakerSwapStorage::new(ctx.clone());
+        let RestoredMachine {
+            mut machine,
+            current_state,
+        } = match MakerSwapStateMachine::restore_from_storage(uuid, storage, recreate_ctx).await {
+            Ok(restored) => restored,
+            Err(e) => {
+                error!("Error {} on restoring the maker swap {}", e, uuid);
+                return;
+            },
+        };
+        spawn_swap_v2_machine(&ctx, uuid, async move {
+            if let Err(e) = machine.run(current_state).await {
+                error!("Error {} on running the maker swap {}", e, uuid);
+            }
+        });
+    } else {
+        let storage = TakerSwapStorage::new(ctx.clone());
+        let RestoredMachine {
+            mut machine,
+            current_state,
+        } = match TakerSwapStateMachine::restore_from_storage(uuid, storage, recreate_ctx).await {
+            Ok(restored) => restored,
+            Err(e) => {
+                error!("Error {} on restoring the taker swap {}", e, uuid);
+                return;
+            },
+        };
+        spawn_swap_v2_machine(&ctx, uuid, async move {
+            if let Err(e) = machine.run(current_state).await {
+                error!("Error {} on running the taker swap {}", e, uuid);
+            }
+        });
+    }
+}
+
+/// Spawns the future running the swap v2 state machine.
+/// The future is stored by the swap uuid, so it can be aborted using [`abort_swap_v2_machine`].
+pub fn spawn_swap_v2_machine<F>(ctx: &MmArc, uuid: Uuid, fut: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let swap_ctx = SwapsContext::from_ctx(ctx).expect("SwapsContext::from_ctx should not fail");
+    let mut machines = swap_ctx.swap_v2_machines.lock();
+    // The finished machine future might still be stored, so replace it.
+    machines.abort_future(&uuid).warn_log();
+    machines.spawn_or_ignore(uuid, fut).warn_log();
+}
+
+/// Aborts the future running the swap v2 state machine if it's still alive.
+/// Returns whether the future was spawned.
+pub(super) fn abort_swap_v2_machine(ctx: &MmArc, uuid: &Uuid) -> bool {
+    let swap_ctx = SwapsContext::from_ctx(ctx).expect("SwapsContext::from_ctx should not fail");
+    let aborted = swap_ctx.swap_v2_machines.lock().abort_future(uuid);
+    match aborted {
+        Ok(was_spawned) => was_spawned,
+        Err(e) => {
+            warn!("Error {} on aborting the swap {} state machine", e, uuid);
+            false
+        },
+    }
+}
+
+/// Restores the swap v2 state machine from storage and refunds the payment of the swap stopped in refund-required state.
+pub(super) async fn refund_swap_v2<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2>(
+    ctx: &MmArc,
+    uuid: Uuid,
+    swap_type: u8,
+    maker_coin: MakerCoin,
+    taker_coin: TakerCoin,
+) -> MmResult<TransactionIdentifier, ManualRefundError> {
+    let recreate_ctx = SwapRecreateCtx { maker_coin, taker_coin };
+    if swap_type == MAKER_SWAP_V2_TYPE {
+        let storage = MakerSwapStorage::new(ctx.clone());
+        let RestoredMachine { mut machine, .. } =
+            MakerSwapStateMachine::restore_from_storage(uuid, storage, recreate_ctx).await?;
+        machine.refund_manually().await
+    } else {
+        let storage = TakerSwapStorage::new(ctx.clone());
+        let RestoredMachine { mut machine, .. } =
+            TakerSwapStateMachine::restore_from_storage(uuid, storage, recreate_ctx).await?;
+        machine.refund_manually().await
+    }
+}
+
+/// Stores the data of the new swap v2, so its state machine can be restored from the stored events.
+pub(super) async fn store_swap_v2_data(
+    ctx: &MmArc,
+    uuid: Uuid,
+    swap_type: u8,
+    secret_hash: &[u8],
+    data: MySwapV2Data,
+) -> MmResult<(), SwapV2DbError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::store_swap_v2_data(ctx, uuid, swap_type, secret_hash, data);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::store_swap_v2_data(ctx, uuid, swap_type, secret_hash, data).await;
+}
+
+/// Loads the data of the swap v2 by uuid.
+pub(super) async fn load_swap_v2_data(ctx: &MmArc, uuid: Uuid) -> MmResult<MySwapV2Data, SwapV2DbError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::load_swap_v2_data(ctx, uuid);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::load_swap_v2_data(ctx, uuid).await;
+}
+
+/// Loads the type of the swap v2 by uuid.
+/// Returns `None` if there is no swap v2 with the given uuid.
+pub(super) async fn load_swap_v2_type(ctx: &MmArc, uuid: Uuid) -> MmResult<Option<u8>, SwapV2DbError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::load_swap_v2_type(ctx, uuid);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::load_swap_v2_type(ctx, uuid).await;
+}
+
+/// Loads the uuids of the swaps v2 of the given type that are not finished yet.
+pub(super) async fn load_unfinished_swaps_v2(ctx: &MmArc, swap_type: u8) -> MmResult<Vec<Uuid>, SwapV2DbError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::load_unfinished_swaps_v2(ctx, swap_type);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::load_unfinished_swaps_v2(ctx, swap_type).await;
+}
+
+/// Loads the events of the swap v2 by uuid.
+pub(super) async fn load_swap_v2_events<E: DeserializeOwned>(
+    ctx: &MmArc,
+    uuid: Uuid,
+) -> MmResult<Vec<E>, SwapV2DbError> {
+    let events_json = load_swap_v2_data(ctx, uuid).await?.events_json;
+    serde_json::from_str(&events_json).map_to_mm(|e| SwapV2DbError::SerdeError(e.to_string()))
+}
+
+/// Appends the event to the stored events of the swap v2.
+/// The events are kept as JSON values, so the stored events of previous versions are preserved as is.
+pub(super) async fn store_swap_v2_event<E: Serialize>(
+    ctx: &MmArc,
+    uuid: Uuid,
+    event: &E,
+) -> MmResult<(), SwapV2DbError> {
+    let event = serde_json::to_value(event).map_to_mm(|e| SwapV2DbError::SerdeError(e.to_string()))?;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::store_swap_v2_event(ctx, uuid, event);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::store_swap_v2_event(ctx, uuid, event).await;
+}
+
+/// Marks the swap v2 as finished and removes the P2P messages stored for it.
+pub(super) async fn mark_swap_v2_finished(ctx: &MmArc, uuid: Uuid) -> MmResult<(), SwapV2DbError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::mark_swap_v2_finished(ctx, uuid);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::mark_swap_v2_finished(ctx, uuid).await;
+}
+
+/// Stores the encoded [`SwapMessage`] received for the swap v2, replacing the previous message of the same kind.
+pub(super) async fn store_swap_v2_msg(
+    ctx: &MmArc,
+    uuid: Uuid,
+    msg_kind: &str,
+    msg: Vec<u8>,
+) -> MmResult<(), SwapV2DbError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::store_swap_v2_msg(ctx, uuid, msg_kind, msg);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::store_swap_v2_msg(ctx, uuid, msg_kind, msg).await;
+}
+
+/// Loads the encoded [`SwapMessage`]s received for the swap v2.
+pub(super) async fn load_swap_v2_msgs(ctx: &MmArc, uuid: Uuid) -> MmResult<Vec<Vec<u8>>, SwapV2DbError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::load_swap_v2_msgs(ctx, uuid);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::load_swap_v2_msgs(ctx, uuid).await;
+}
+
+/// Loads the data of the swap v2 to be returned by the RPCs.
+/// Returns `None` if there is no swap v2 with the given uuid.
+pub(super) async fn load_swap_v2_for_rpc(ctx: &MmArc, uuid: Uuid) -> MmResult<Option<MySwapV2ForRpc>, SwapV2DbError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::load_swap_v2_for_rpc(ctx, uuid);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::load_swap_v2_for_rpc(ctx, uuid).await;
+}
+
+/// Loads the data of the swaps v2 matching the filter to be returned by the RPCs.
+/// If `unfinished_only` is set, only the swaps that are not finished yet are loaded.
+pub(super) async fn load_swaps_v2_for_rpc(
+    ctx: &MmArc,
+    filter: &MySwapsFilter,
+    unfinished_only: bool,
+    paging_options: &PagingOptions,
+) -> MmResult<MySwapsV2ForRpc, SwapV2DbError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return native_impl::load_swaps_v2_for_rpc(ctx, filter, unfinished_only, paging_options);
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm_impl::load_swaps_v2_for_rpc(ctx, filter, unfinished_only, paging_options).await;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native_impl {
+    use super::*;
+    use crate::mm2::database::my_swaps::{delete_swap_v2_msgs, get_swap_data_for_rpc, get_swap_events, get_swap_type,
+                                         get_swap_v2_data, insert_new_swap_v2, insert_swap_v2_msg,
+                                         select_swap_v2_msgs, select_swaps_v2_for_rpc_by_filter,
+                                         select_unfinished_swaps_uuids, set_swap_is_finished, update_swap_events,
+                                         MySwapForRpc};
+    use db_common::sqlite::rusqlite::Error as SqlError;
+
+    impl MySwapV2ForRpc {
+        fn from_db_swap(swap_type: u8, swap: MySwapForRpc) -> MmResult<Self, SwapV2DbError> {
+            let uuid = swap
+                .uuid
+                .parse()
+                .map_to_mm(|e: uuid::Error| SwapV2DbError::StorageError(e.to_string()))?;
+            Ok(MySwapV2ForRpc {
+                uuid,
+                swap_type,
+                my_coin: swap.my_coin,
+                other_coin: swap.other_coin,
+                started_at: swap.started_at,
+                is_finished: swap.is_finished,
+                events_json: swap.events_json,
+                maker_volume: swap.maker_volume,
+                taker_volume: swap.taker_volume,
+                premium: swap.premium,
+                dex_fee: swap.dex_fee,
+                secret_hash: swap.secret_hash,
+                lock_duration: swap.lock_duration,
+            })
+        }
+    }
+
+    pub(super) fn store_swap_v2_data(
+        ctx: &MmArc,
+        uuid: Uuid,
+        swap_type: u8,
+        secret_hash: &[u8],
+        data: MySwapV2Data,
+    ) -> MmResult<(), SwapV2DbError> {
+        insert_new_swap_v2(
+            &ctx.sqlite_connection(),
+            &uuid.to_string(),
+            swap_type,
+            secret_hash,
+            &data,
+        )
+        .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))
+    }
+
+    pub(super) fn load_swap_v2_data(ctx: &MmArc, uuid: Uuid) -> MmResult<MySwapV2Data, SwapV2DbError> {
+        get_swap_v2_data(&ctx.sqlite_connection(), &uuid.to_string())
+            .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))
+    }
+
+    pub(super) fn load_swap_v2_type(ctx: &MmArc, uuid: Uuid) -> MmResult<Option<u8>, SwapV2DbError> {
+        match get_swap_type(&ctx.sqlite_connection(), &uuid.to_string()) {
+            Ok(swap_type) if swap_type == MAKER_SWAP_V2_TYPE || swap_type == TAKER_SWAP_V2_TYPE => Ok(Some(swap_type)),
+            Ok(_) | Err(SqlError::QueryReturnedNoRows) => Ok(None),
+            Err(e) => MmError::err(SwapV2DbError::StorageError(e.to_string())),
+        }
+    }
+
+    pub(super) fn load_unfinished_swaps_v2(ctx: &MmArc, swap_type: u8) -> MmResult<Vec<Uuid>, SwapV2DbError> {
+        select_unfinished_swaps_uuids(&ctx.sqlite_connection(), swap_type)
+            .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))
+    }
+
+    pub(super) fn load_swap_v2_for_rpc(ctx: &MmArc, uuid: Uuid) -> MmResult<Option<MySwapV2ForRpc>, SwapV2DbError> {
+        let swap_type = match load_swap_v2_type(ctx, uuid)? {
+            Some(swap_type) => swap_type,
+            None => return Ok(None),
+        };
+        let swap = get_swap_data_for_rpc(&ctx.sqlite_connection(), &uuid.to_string())
+            .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))?;
+        MySwapV2ForRpc::from_db_swap(swap_type, swap).map(Some)
+    }
+
+    pub(super) fn load_swaps_v2_for_rpc(
+        ctx: &MmArc,
+        filter: &MySwapsFilter,
+        unfinished_only: bool,
+        paging_options: &PagingOptions,
+    ) -> MmResult<MySwapsV2ForRpc, SwapV2DbError> {
+        let selected =
+            select_swaps_v2_for_rpc_by_filter(&ctx.sqlite_connection(), filter, unfinished_only, Some(paging_options))
+                .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))?;
+        let swaps = selected
+            .swaps
+            .into_iter()
+            .map(|(swap_type, swap)| MySwapV2ForRpc::from_db_swap(swap_type, swap))
+            .collect::<MmResult<_, _>>()?;
+        Ok(MySwapsV2ForRpc {
+            swaps,
+            total_count: selected.total_count,
+            skipped: selected.skipped,
+        })
+    }
+
+    pub(super) fn store_swap_v2_event(ctx: &MmArc, uuid: Uuid, event: Json) -> MmResult<(), SwapV2DbError> {
+        let uuid = uuid.to_string();
+        let mut conn = ctx.sqlite_connection();
+        // Read and rewrite the events within one transaction so that concurrent writers can't lose an event.
+        let sql_transaction = conn
+            .transaction()
+            .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))?;
+        let events_json =
+            get_swap_events(&sql_transaction, &uuid).map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))?;
+        let mut events: Vec<Json> =
+            serde_json::from_str(&events_json).map_to_mm(|e| SwapV2DbError::SerdeError(e.to_string()))?;
+        events.push(event);
+        let events_json = serde_json::to_string(&events).map_to_mm(|e| SwapV2DbError::SerdeError(e.to_string()))?;
+        update_swap_events(&sql_transaction, &uuid, &events_json)
+            .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))?;
+        sql_transaction
+            .commit()
+            .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub(super) fn mark_swap_v2_finished(ctx: &MmArc, uuid: Uuid) -> MmResult<(), SwapV2DbError> {
+        let uuid = uuid.to_string();
+        let conn = ctx.sqlite_connection();
+        set_swap_is_finished(&conn, &uuid).map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))?;
+        delete_swap_v2_msgs(&conn, &uuid).map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))
+    }
+
+    pub(super) fn store_swap_v2_msg(
+        ctx: &MmArc,
+        uuid: Uuid,
+        msg_kind: &str,
+        msg: Vec<u8>,
+    ) -> MmResult<(), SwapV2DbError> {
+        insert_swap_v2_msg(&ctx.sqlite_connection(), &uuid.to_string(), msg_kind, &msg)
+            .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))
+    }
+
+    pub(super) fn load_swap_v2_msgs(ctx: &MmArc, uuid: Uuid) -> MmResult<Vec<Vec<u8>>, SwapV2DbError> {
+        select_swap_v2_msgs(&ctx.sqlite_connection(), &uuid.to_string())
+            .map_to_mm(|e| SwapV2DbError::StorageError(e.to_string()))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod wasm_impl {
+    use super::*;
+    use crate::mm2::lp_swap::swap_wasm_db::{DbTransactionError, InitDbError, MySwapsV2Table, SwapV2MsgsTable};
+    use mm2_db::indexed_db::MultiIndex;
+
+    impl From<DbTransactionError> for SwapV2DbError {
+        fn from(e: DbTransactionError) -> Self {
+            let desc = e.to_string();
+            match e {
+                DbTransactionError::ErrorSerializingItem(_) | DbTransactionError::ErrorDeserializingItem(_) => {
+                    SwapV2DbError::SerdeError(desc)
+                },
+                _ => SwapV2DbError::StorageError(desc),
+            }
+        }
+    }
+
+    impl From<InitDbError> for SwapV2DbError {
+        fn from(e: InitDbError) -> Self { SwapV2DbError::StorageError(e.to_string()) }
+    }
+
+    pub(super) async fn store_swap_v2_data(
+        ctx: &MmArc,
+        uuid: Uuid,
+        swap_type: u8,
+        secret_hash: &[u8],
+        data: MySwapV2Data,
+    ) -> MmResult<(), SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<MySwapsV2Table>().await?;
+
+        let item = MySwapsV2Table {
+            uuid,
+            swap_type,
+            is_finished: 0,
+            secret_hash: secret_hash.to_vec(),
+            data,
+        };
+        table.add_item(&item).await?;
+        Ok(())
+    }
+
+    pub(super) async fn load_swap_v2_data(ctx: &MmArc, uuid: Uuid) -> MmResult<MySwapV2Data, SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<MySwapsV2Table>().await?;
+
+        match table.get_item_by_unique_index("uuid", uuid).await? {
+            Some((_item_id, item)) => Ok(item.data),
+            None => MmError::err(SwapV2DbError::StorageError(format!("Swap {} is not found", uuid))),
+        }
+    }
+
+    pub(super) async fn load_swap_v2_type(ctx: &MmArc, uuid: Uuid) -> MmResult<Option<u8>, SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<MySwapsV2Table>().await?;
+
+        let item = table.get_item_by_unique_index("uuid", uuid).await?;
+        Ok(item.map(|(_item_id, item)| item.swap_type))
+    }
+
+    pub(super) async fn load_unfinished_swaps_v2(ctx: &MmArc, swap_type: u8) -> MmResult<Vec<Uuid>, SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<MySwapsV2Table>().await?;
+
+        let index = MultiIndex::new(MySwapsV2Table::SWAP_TYPE_IS_FINISHED_INDEX)
+            .with_value(swap_type)?
+            .with_value(0u8)?;
+        let items = table.get_items_by_multi_index(index).await?;
+        Ok(items.into_iter().map(|(_item_id, item)| item.uuid).collect())
+    }
+
+    pub(super) async fn load_swap_v2_for_rpc(
+        ctx: &MmArc,
+        uuid: Uuid,
+    ) -> MmResult<Option<MySwapV2ForRpc>, SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<MySwapsV2Table>().await?;
+
+        let item = table.get_item_by_unique_index("uuid", uuid).await?;
+        Ok(item.map(|(_item_id, item)| MySwapV2ForRpc::from(item)))
+    }
+
+    pub(super) async fn load_swaps_v2_for_rpc(
+        ctx: &MmArc,
+        filter: &MySwapsFilter,
+        unfinished_only: bool,
+        paging_options: &PagingOptions,
+    ) -> MmResult<MySwapsV2ForRpc, SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<MySwapsV2Table>().await?;
+
+        let mut swaps: Vec<_> = table
+            .get_all_items()
+            .await?
+            .into_iter()
+            .map(|(_item_id, item)| MySwapV2ForRpc::from(item))
+            .filter(|swap| !(unfinished_only && swap.is_finished) && is_applied(filter, swap))
+            .collect();
+        // The most recent swaps first, the same as on native.
+        swaps.sort_by(|a, b| b.started_at.cmp(&a.started_at).then_with(|| a.uuid.cmp(&b.uuid)));
+
+        let total_count = swaps.len();
+        let skipped = match paging_options.from_uuid {
+            // `page_number` is ignored if `from_uuid` is set
+            Some(from_uuid) => {
+                swaps
+                    .iter()
+                    .position(|swap| swap.uuid == from_uuid)
+                    .or_mm_err(|| SwapV2DbError::StorageError(format!("'from_uuid' {} is not found", from_uuid)))?
+                    + 1
+            },
+            None => (paging_options.page_number.get() - 1) * paging_options.limit,
+        };
+        let swaps = swaps.into_iter().skip(skipped).take(paging_options.limit).collect();
+        Ok(MySwapsV2ForRpc {
+            swaps,
+            total_count,
+            skipped,
+        })
+    }
+
+    /// Checks the swap against the filter the same way as the `my_swaps` SQL query does on native.
+    fn is_applied(filter: &MySwapsFilter, swap: &MySwapV2ForRpc) -> bool {
+        let started_at = swap.started_at as u64;
+        filter.my_coin.as_ref().map_or(true, |my_coin| *my_coin == swap.my_coin)
+            && filter
+                .other_coin
+                .as_ref()
+                .map_or(true, |other_coin| *other_coin == swap.other_coin)
+            && filter.from_timestamp.map_or(true, |from| started_at >= from)
+            && filter.to_timestamp.map_or(true, |to| started_at < to)
+    }
+
+    impl From<MySwapsV2Table> for MySwapV2ForRpc {
+        fn from(item: MySwapsV2Table) -> Self {
+            MySwapV2ForRpc {
+                uuid: item.uuid,
+                swap_type: item.swap_type,
+                my_coin: item.data.my_coin,
+                other_coin: item.data.other_coin,
+                started_at: item.data.started_at,
+                is_finished: item.is_finished != 0,
+                events_json: item.data.events_json,
+                maker_volume: item.data.maker_volume,
+                taker_volume: item.data.taker_volume,
+                premium: item.data.premium,
+                dex_fee: item.data.dex_fee,
+                secret_hash: item.secret_hash,
+                lock_duration: item.data.lock_duration,
+            }
+        }
+    }
+
+    pub(super) async fn store_swap_v2_event(ctx: &MmArc, uuid: Uuid, event: Json) -> MmResult<(), SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<MySwapsV2Table>().await?;
+
+        let (item_id, mut item) = table
+            .get_item_by_unique_index("uuid", uuid)
+            .await?
+            .or_mm_err(|| SwapV2DbError::StorageError(format!("Swap {} is not found", uuid)))?;
+        let mut events: Vec<Json> =
+            serde_json::from_str(&item.data.events_json).map_to_mm(|e| SwapV2DbError::SerdeError(e.to_string()))?;
+        events.push(event);
+        item.data.events_json =
+            serde_json::to_string(&events).map_to_mm(|e| SwapV2DbError::SerdeError(e.to_string()))?;
+        table.replace_item(item_id, &item).await?;
+        Ok(())
+    }
+
+    pub(super) async fn mark_swap_v2_finished(ctx: &MmArc, uuid: Uuid) -> MmResult<(), SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+
+        let swaps_table = transaction.table::<MySwapsV2Table>().await?;
+        let (item_id, mut item) = swaps_table
+            .get_item_by_unique_index("uuid", uuid)
+            .await?
+            .or_mm_err(|| SwapV2DbError::StorageError(format!("Swap {} is not found", uuid)))?;
+        item.is_finished = 1;
+        swaps_table.replace_item(item_id, &item).await?;
+
+        let msgs_table = transaction.table::<SwapV2MsgsTable>().await?;
+        msgs_table.delete_items_by_index("uuid", uuid).await?;
+        Ok(())
+    }
+
+    pub(super) async fn store_swap_v2_msg(
+        ctx: &MmArc,
+        uuid: Uuid,
+        msg_kind: &str,
+        msg: Vec<u8>,
+    ) -> MmResult<(), SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<SwapV2MsgsTable>().await?;
+
+        let index = MultiIndex::new(SwapV2MsgsTable::UUID_MSG_KIND_INDEX)
+            .with_value(uuid)?
+            .with_value(msg_kind)?;
+        let item = SwapV2MsgsTable {
+            uuid,
+            msg_kind: msg_kind.to_owned(),
+            msg,
+        };
+        table.replace_item_by_unique_multi_index(index, &item).await?;
+        Ok(())
+    }
+
+    pub(super) async fn load_swap_v2_msgs(ctx: &MmArc, uuid: Uuid) -> MmResult<Vec<Vec<u8>>, SwapV2DbError> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SwapV2DbError::StorageError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<SwapV2MsgsTable>().await?;
+
+        let items = table.get_items("uuid", uuid).await?;
+        Ok(items.into_iter().map(|(_item_id, item)| item.msg).collect())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(super) mod tests {
+    use super::*;
+    use crate::mm2::database::my_swaps::{CREATE_SWAP_V2_MSGS_TABLE, TRADING_PROTO_UPGRADE_MIGRATION};
+    use crate::mm2::lp_swap::swap_v2_msg_kind;
+    use crate::mm2::lp_swap::swap_v2_pb::{swap_message, MakerPaymentInfo, TakerFundingInfo};
+    use crate::CREATE_MY_SWAPS_TABLE;
+    use common::block_on;
+    use db_common::sqlite::rusqlite::Connection;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use prost::Message;
+    use std::sync::{Arc, Mutex};
+
+    /// Creates the context with in-memory SQLite database containing the swaps v2 tables.
+    pub(in crate::mm2::lp_swap) fn swap_v2_test_ctx() -> MmArc {
+        let ctx = MmCtxBuilder::new().into_mm_arc();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(CREATE_MY_SWAPS_TABLE!()).unwrap();
+        for statement in TRADING_PROTO_UPGRADE_MIGRATION {
+            conn.execute(statement, []).unwrap();
+        }
+        conn.execute(CREATE_SWAP_V2_MSGS_TABLE, []).unwrap();
+        let _ = ctx.sqlite_connection.pin(Arc::new(Mutex::new(conn)));
+        ctx
+    }
+
+    /// Stores the swap with the given events the way the running state machine does it.
+    pub(in crate::mm2::lp_swap) fn store_test_swap_v2(ctx: &MmArc, uuid: Uuid, swap_type: u8, events: &[Json]) {
+        let data = MySwapV2Data {
+            my_coin: "RICK".to_owned(),
+            other_coin: "MORTY".to_owned(),
+            started_at: 1_700_000_000,
+            events_json: "[]".to_owned(),
+            maker_volume: "1".to_owned(),
+            taker_volume: "2".to_owned(),
+            premium: "0".to_owned(),
+            dex_fee: "1/777".to_owned(),
+            secret: vec![1; 32],
+            secret_hash_algo: 1,
+            p2p_privkey: vec![0; 32],
+            lock_duration: 7800,
+            maker_coin_confs: 1,
+            maker_coin_nota: false,
+            taker_coin_confs: 1,
+            taker_coin_nota: false,
+        };
+        block_on(store_swap_v2_data(ctx, uuid, swap_type, &[2; 20], data)).unwrap();
+        for event in events {
+            block_on(store_swap_v2_event(ctx, uuid, event)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_swap_v2_storage() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::new_v4();
+        store_test_swap_v2(&ctx, uuid, MAKER_SWAP_V2_TYPE, &[json!("Completed")]);
+
+        let unfinished = block_on(load_unfinished_swaps_v2(&ctx, MAKER_SWAP_V2_TYPE)).unwrap();
+        assert_eq!(unfinished, vec![uuid]);
+        assert!(block_on(load_unfinished_swaps_v2(&ctx, TAKER_SWAP_V2_TYPE))
+            .unwrap()
+            .is_empty());
+
+        let data = block_on(load_swap_v2_data(&ctx, uuid)).unwrap();
+        let params = SwapV2Params::try_from(&data).unwrap();
+        assert_eq!(params.maker_volume, MmNumber::from(1));
+        assert_eq!(params.lock_duration, 7800);
+        assert!(params.p2p_keypair.is_none());
+
+        let events: Vec<Json> = block_on(load_swap_v2_events(&ctx, uuid)).unwrap();
+        assert_eq!(events, vec![json!("Completed")]);
+
+        block_on(mark_swap_v2_finished(&ctx, uuid)).unwrap();
+        assert!(block_on(load_unfinished_swaps_v2(&ctx, MAKER_SWAP_V2_TYPE))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_reload_swap_v2_msgs() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::new_v4();
+        store_test_swap_v2(&ctx, uuid, TAKER_SWAP_V2_TYPE, &[]);
+
+        let taker_funding = swap_message::Inner::TakerFundingInfo(TakerFundingInfo {
+            tx_bytes: vec![1],
+            next_step_instructions: None,
+        });
+        let maker_payment = swap_message::Inner::MakerPaymentInfo(MakerPaymentInfo {
+            tx_bytes: vec![2],
+            next_step_instructions: None,
+            funding_preimage_sig: vec![3],
+            funding_preimage_tx: vec![4],
+        });
+        for inner in [taker_funding, maker_payment] {
+            let msg_kind = swap_v2_msg_kind(&inner);
+            let msg = SwapMessage { inner: Some(inner) }.encode_to_vec();
+            block_on(store_swap_v2_msg(&ctx, uuid, msg_kind, msg)).unwrap();
+        }
+        // The message of the same kind replaces the previous one.
+        let maker_payment = SwapMessage {
+            inner: Some(swap_message::Inner::MakerPaymentInfo(MakerPaymentInfo {
+                tx_bytes: vec![5],
+                next_step_instructions: None,
+                funding_preimage_sig: vec![3],
+                funding_preimage_tx: vec![4],
+            })),
+        };
+        block_on(store_swap_v2_msg(
+            &ctx,
+            uuid,
+            "maker_payment",
+            maker_payment.encode_to_vec(),
+        ))
+        .unwrap();
+        assert_eq!(block_on(load_swap_v2_msgs(&ctx, uuid)).unwrap().len(), 2);
+
+        // Restart: the messages store is empty until the persisted messages are reloaded.
+        block_on(reload_swap_v2_msgs(&ctx, uuid)).unwrap();
+        let swap_ctx = SwapsContext::from_ctx(&ctx).unwrap();
+        let stores = swap_ctx.swap_v2_msgs.lock().unwrap();
+        let store = stores.get(&uuid).unwrap();
+        assert_eq!(store.taker_funding.as_ref().unwrap().tx_bytes, vec![1]);
+        assert_eq!(store.maker_payment.as_ref().unwrap().tx_bytes, vec![5]);
+        assert!(store.taker_payment.is_none());
+        drop(stores);
+
+        block_on(mark_swap_v2_finished(&ctx, uuid)).unwrap();
+        assert!(block_on(load_swap_v2_msgs(&ctx, uuid)).unwrap().is_empty());
+    }
+}
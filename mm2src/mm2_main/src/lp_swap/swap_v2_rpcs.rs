@@ -0,0 +1,619 @@
+//! RPCs exposing the state of the swaps v2 (Trading Protocol Upgrade) and the manual actions available for them.
+
+use super::maker_swap_v2::{self, MakerSwapEvent, MakerSwapStateMachineError, MakerSwapStorage};
+use super::my_swaps_storage::{MySwapsError, MySwapsOps, MySwapsStorage};
+use super::swap_v2_common::{abort_swap_v2_machine, load_swap_v2_for_rpc, load_swaps_v2_for_rpc, refund_swap_v2,
+                            swap_v2_kickstart_handler, ManualRefundError, MySwapV2ForRpc, SwapV2DbError};
+use super::taker_swap_v2::{self, TakerSwapEvent, TakerSwapStateMachineError, TakerSwapStorage};
+use super::{MySwapsFilter, TransactionIdentifier, MAKER_SWAP_V2_TYPE, TAKER_SWAP_V2_TYPE};
+use coins::{lp_coinfind_or_err, CoinFindError};
+use common::executor::SpawnFuture;
+use common::{calc_total_pages, HttpStatusCode, PagingOptions};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{MmNumber, MmNumberMultiRepr};
+use mm2_state_machine::storable_state_machine::StateMachineStorage;
+use rpc::v1::types::Bytes as BytesJson;
+use ser_error_derive::SerializeErrorType;
+use serde::Serialize;
+use serde_json::Value as Json;
+use std::num::NonZeroUsize;
+use uuid::Uuid;
+
+pub type SwapV2RpcResult<T> = Result<T, MmError<SwapV2RpcError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum SwapV2RpcError {
+    #[display(fmt = "No such swap with the uuid '{}'", _0)]
+    NoSuchSwap(Uuid),
+    #[display(fmt = "Swap with the uuid '{}' is not a swap v2", _0)]
+    NotSwapV2(Uuid),
+    #[display(fmt = "Action {} is not available at the swap state {:?}", action, state)]
+    ActionIsNotAvailable {
+        action: SwapV2Action,
+        state: Option<String>,
+    },
+    #[display(fmt = "Payment locktime {} has not expired yet, current time {}", locktime, now)]
+    LocktimeNotExpired { locktime: u64, now: u64 },
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "{}/{} pair is not supported by the swap v2 protocol", maker_coin, taker_coin)]
+    PairIsNotSupported { maker_coin: String, taker_coin: String },
+    #[display(fmt = "Error on refund transaction: {}", _0)]
+    RefundTransactionError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    InternalError(String),
+}
+
+impl HttpStatusCode for SwapV2RpcError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SwapV2RpcError::NoSuchSwap(_) | SwapV2RpcError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            SwapV2RpcError::NotSwapV2(_)
+            | SwapV2RpcError::ActionIsNotAvailable { .. }
+            | SwapV2RpcError::LocktimeNotExpired { .. }
+            | SwapV2RpcError::PairIsNotSupported { .. } => StatusCode::BAD_REQUEST,
+            SwapV2RpcError::RefundTransactionError(_) | SwapV2RpcError::InternalError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+impl From<SwapV2DbError> for SwapV2RpcError {
+    fn from(e: SwapV2DbError) -> Self { SwapV2RpcError::InternalError(e.to_string()) }
+}
+
+impl From<MySwapsError> for SwapV2RpcError {
+    fn from(e: MySwapsError) -> Self { SwapV2RpcError::InternalError(e.to_string()) }
+}
+
+impl From<CoinFindError> for SwapV2RpcError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => SwapV2RpcError::NoSuchCoin { coin },
+        }
+    }
+}
+
+impl From<MakerSwapStateMachineError> for SwapV2RpcError {
+    fn from(e: MakerSwapStateMachineError) -> Self { SwapV2RpcError::InternalError(e.to_string()) }
+}
+
+impl From<TakerSwapStateMachineError> for SwapV2RpcError {
+    fn from(e: TakerSwapStateMachineError) -> Self { SwapV2RpcError::InternalError(e.to_string()) }
+}
+
+impl From<ManualRefundError> for SwapV2RpcError {
+    fn from(e: ManualRefundError) -> Self {
+        match e {
+            ManualRefundError::RefundIsNotRequired(state) => SwapV2RpcError::ActionIsNotAvailable {
+                action: SwapV2Action::Refund,
+                state: Some(state),
+            },
+            ManualRefundError::LocktimeNotExpired { locktime, now } => {
+                SwapV2RpcError::LocktimeNotExpired { locktime, now }
+            },
+            ManualRefundError::TransactionError(e) => SwapV2RpcError::RefundTransactionError(e),
+            ManualRefundError::RestoreError(e) | ManualRefundError::StorageError(e) => SwapV2RpcError::InternalError(e),
+        }
+    }
+}
+
+/// Manual actions that can be performed on the swap v2 depending on its current state.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Serialize)]
+pub enum SwapV2Action {
+    /// Stops the swap and marks it as aborted. Available only until any payment of this node is sent.
+    Abort,
+    /// Refunds the payment of this node if the swap stopped in refund-required state.
+    Refund,
+}
+
+/// The locktimes of the swap v2 payments.
+/// They are calculated from the swap start time the same way as the state machines do it.
+#[derive(Debug, Serialize)]
+pub struct SwapV2Timelocks {
+    pub maker_payment_locktime: u64,
+    pub taker_payment_locktime: u64,
+    pub taker_funding_locktime: u64,
+}
+
+impl SwapV2Timelocks {
+    fn new(started_at: u64, lock_duration: u64) -> Self {
+        SwapV2Timelocks {
+            maker_payment_locktime: started_at + 2 * lock_duration,
+            taker_payment_locktime: started_at + lock_duration,
+            taker_funding_locktime: started_at + 3 * lock_duration,
+        }
+    }
+}
+
+/// The events history of the swap v2.
+#[derive(Debug, Serialize)]
+#[serde(tag = "swap_type", content = "events")]
+pub enum SwapV2Events {
+    Maker(Vec<MakerSwapEvent>),
+    Taker(Vec<TakerSwapEvent>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct SwapV2Status {
+    pub uuid: Uuid,
+    pub my_coin: String,
+    pub other_coin: String,
+    pub started_at: u64,
+    pub is_finished: bool,
+    pub maker_volume: MmNumberMultiRepr,
+    pub taker_volume: MmNumberMultiRepr,
+    pub premium: MmNumberMultiRepr,
+    pub dex_fee: MmNumberMultiRepr,
+    pub secret_hash: BytesJson,
+    pub lock_duration: u64,
+    pub timelocks: SwapV2Timelocks,
+    /// The name of the last stored event, which the swap continues from.
+    pub current_state: Option<String>,
+    pub available_actions: Vec<SwapV2Action>,
+    #[serde(flatten)]
+    pub events: SwapV2Events,
+}
+
+impl SwapV2Status {
+    fn swap_type(&self) -> u8 {
+        match self.events {
+            SwapV2Events::Maker(_) => MAKER_SWAP_V2_TYPE,
+            SwapV2Events::Taker(_) => TAKER_SWAP_V2_TYPE,
+        }
+    }
+
+    /// Returns the tickers of the maker and taker coins.
+    fn maker_and_taker_coins(&self) -> (&str, &str) {
+        match self.events {
+            SwapV2Events::Maker(_) => (&self.my_coin, &self.other_coin),
+            SwapV2Events::Taker(_) => (&self.other_coin, &self.my_coin),
+        }
+    }
+
+    fn ensure_action_is_available(&self, action: SwapV2Action) -> SwapV2RpcResult<()> {
+        if self.available_actions.contains(&action) {
+            return Ok(());
+        }
+        MmError::err(SwapV2RpcError::ActionIsNotAvailable {
+            action,
+            state: self.current_state.clone(),
+        })
+    }
+}
+
+/// Returns the name of the event variant.
+/// Events are serialized either as `"Variant"` or as `{"Variant": {...}}`.
+fn event_name<Event: Serialize>(event: &Event) -> Option<String> {
+    match serde_json::to_value(event).ok()? {
+        Json::String(name) => Some(name),
+        Json::Object(map) => map.into_iter().next().map(|(name, _)| name),
+        _ => None,
+    }
+}
+
+fn maker_swap_actions(last_event: Option<&MakerSwapEvent>) -> Vec<SwapV2Action> {
+    match last_event {
+        // Maker payment is sent on `TakerFundingReceived` state, so it's safe to abort the swap before it.
+        Some(MakerSwapEvent::Initialized { .. }) | Some(MakerSwapEvent::WaitingForTakerFunding { .. }) => {
+            vec![SwapV2Action::Abort]
+        },
+        Some(MakerSwapEvent::MakerPaymentRefundRequired { .. }) => vec![SwapV2Action::Refund],
+        _ => Vec::new(),
+    }
+}
+
+fn taker_swap_actions(last_event: Option<&TakerSwapEvent>) -> Vec<SwapV2Action> {
+    match last_event {
+        // Taker funding is sent on `Negotiated` state, so it's safe to abort the swap before it.
+        Some(TakerSwapEvent::Initialized { .. }) => vec![SwapV2Action::Abort],
+        Some(TakerSwapEvent::TakerFundingRefundRequired { .. })
+        | Some(TakerSwapEvent::TakerPaymentRefundRequired { .. }) => vec![SwapV2Action::Refund],
+        _ => Vec::new(),
+    }
+}
+
+async fn load_swap_v2_status(ctx: &MmArc, uuid: Uuid) -> SwapV2RpcResult<SwapV2Status> {
+    match load_swap_v2_for_rpc(ctx, uuid).await? {
+        Some(swap_data) => swap_v2_status_from_data(swap_data),
+        None if MySwapsStorage::new(ctx.clone()).is_my_swap(uuid).await? => {
+            MmError::err(SwapV2RpcError::NotSwapV2(uuid))
+        },
+        None => MmError::err(SwapV2RpcError::NoSuchSwap(uuid)),
+    }
+}
+
+fn swap_v2_status_from_data(swap_data: MySwapV2ForRpc) -> SwapV2RpcResult<SwapV2Status> {
+    let parse_number = |input: &str| -> SwapV2RpcResult<MmNumberMultiRepr> {
+        let number =
+            MmNumber::from_fraction_string(input).map_to_mm(|e| SwapV2RpcError::InternalError(e.to_string()))?;
+        Ok(number.into())
+    };
+    let deserialize_error = |e: serde_json::Error| SwapV2RpcError::InternalError(e.to_string());
+
+    let (events, current_state, available_actions) = if swap_data.swap_type == MAKER_SWAP_V2_TYPE {
+        let events: Vec<MakerSwapEvent> = serde_json::from_str(&swap_data.events_json).map_to_mm(deserialize_error)?;
+        let current_state = events.last().and_then(event_name);
+        let actions = if swap_data.is_finished {
+            Vec::new()
+        } else {
+            maker_swap_actions(events.last())
+        };
+        (SwapV2Events::Maker(events), current_state, actions)
+    } else {
+        let events: Vec<TakerSwapEvent> = serde_json::from_str(&swap_data.events_json).map_to_mm(deserialize_error)?;
+        let current_state = events.last().and_then(event_name);
+        let actions = if swap_data.is_finished {
+            Vec::new()
+        } else {
+            taker_swap_actions(events.last())
+        };
+        (SwapV2Events::Taker(events), current_state, actions)
+    };
+
+    let started_at = swap_data.started_at as u64;
+    let lock_duration = swap_data.lock_duration as u64;
+    Ok(SwapV2Status {
+        uuid: swap_data.uuid,
+        my_coin: swap_data.my_coin,
+        other_coin: swap_data.other_coin,
+        started_at,
+        is_finished: swap_data.is_finished,
+        maker_volume: parse_number(&swap_data.maker_volume)?,
+        taker_volume: parse_number(&swap_data.taker_volume)?,
+        premium: parse_number(&swap_data.premium)?,
+        dex_fee: parse_number(&swap_data.dex_fee)?,
+        secret_hash: swap_data.secret_hash.into(),
+        lock_duration,
+        timelocks: SwapV2Timelocks::new(started_at, lock_duration),
+        current_state,
+        available_actions,
+        events,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct SwapV2ByUuidRequest {
+    uuid: Uuid,
+}
+
+/// Returns the current state, events history, timelocks and available manual actions of the swap v2.
+pub async fn swap_v2_status_rpc(ctx: MmArc, req: SwapV2ByUuidRequest) -> SwapV2RpcResult<SwapV2Status> {
+    load_swap_v2_status(&ctx, req.uuid).await
+}
+
+#[derive(Deserialize)]
+pub struct SwapsV2ListRequest {
+    #[serde(flatten)]
+    filter: MySwapsFilter,
+    /// Whether to return only the swaps that are not finished yet.
+    #[serde(default)]
+    unfinished_only: bool,
+    #[serde(flatten)]
+    paging_options: PagingOptions,
+}
+
+#[derive(Serialize)]
+pub struct SwapsV2ListResponse {
+    swaps: Vec<SwapV2Status>,
+    from_uuid: Option<Uuid>,
+    skipped: usize,
+    limit: usize,
+    total: usize,
+    page_number: NonZeroUsize,
+    total_pages: usize,
+    found_records: usize,
+}
+
+/// Returns the swaps v2 of `my` node matching the filter, the most recent ones first.
+pub async fn swaps_v2_list_rpc(ctx: MmArc, req: SwapsV2ListRequest) -> SwapV2RpcResult<SwapsV2ListResponse> {
+    let db_result = load_swaps_v2_for_rpc(&ctx, &req.filter, req.unfinished_only, &req.paging_options).await?;
+
+    let swaps = db_result
+        .swaps
+        .into_iter()
+        .map(swap_v2_status_from_data)
+        .collect::<SwapV2RpcResult<Vec<_>>>()?;
+
+    Ok(SwapsV2ListResponse {
+        found_records: swaps.len(),
+        swaps,
+        from_uuid: req.paging_options.from_uuid,
+        skipped: db_result.skipped,
+        limit: req.paging_options.limit,
+        total: db_result.total_count,
+        page_number: req.paging_options.page_number,
+        total_pages: calc_total_pages(db_result.total_count, req.paging_options.limit),
+    })
+}
+
+#[derive(Serialize)]
+pub struct SwapV2AbortResponse {
+    uuid: Uuid,
+}
+
+/// Stops the swap v2 that hasn't sent any payment of `my` node yet and marks it as aborted.
+pub async fn swap_v2_abort_rpc(ctx: MmArc, req: SwapV2ByUuidRequest) -> SwapV2RpcResult<SwapV2AbortResponse> {
+    // Stop the state machine first, so it can't move to the next state while the swap is being aborted.
+    let was_running = abort_swap_v2_machine(&ctx, &req.uuid);
+    let status = load_swap_v2_status(&ctx, req.uuid).await?;
+    if let Err(e) = status.ensure_action_is_available(SwapV2Action::Abort) {
+        if was_running {
+            resume_swap_v2_machine(&ctx, &status);
+        }
+        return Err(e);
+    }
+
+    if let Err(e) = store_aborted_by_user(&ctx, &status).await {
+        if was_running {
+            resume_swap_v2_machine(&ctx, &status);
+        }
+        return Err(e);
+    }
+
+    Ok(SwapV2AbortResponse { uuid: req.uuid })
+}
+
+async fn store_aborted_by_user(ctx: &MmArc, status: &SwapV2Status) -> SwapV2RpcResult<()> {
+    match status.events {
+        SwapV2Events::Maker(_) => {
+            let mut storage = MakerSwapStorage::new(ctx.clone());
+            let reason = maker_swap_v2::AbortReason::AbortedByUser;
+            storage
+                .store_event(status.uuid, MakerSwapEvent::Aborted { reason })
+                .await?;
+            storage.mark_finished(status.uuid).await?;
+        },
+        SwapV2Events::Taker(_) => {
+            let mut storage = TakerSwapStorage::new(ctx.clone());
+            let reason = taker_swap_v2::AbortReason::AbortedByUser;
+            storage
+                .store_event(status.uuid, TakerSwapEvent::Aborted { reason })
+                .await?;
+            storage.mark_finished(status.uuid).await?;
+        },
+    }
+    Ok(())
+}
+
+/// Resumes the state machine stopped by the RPC from the last stored event.
+fn resume_swap_v2_machine(ctx: &MmArc, status: &SwapV2Status) {
+    if status.is_finished {
+        return;
+    }
+    let (maker_coin, taker_coin) = status.maker_and_taker_coins();
+    let fut = swap_v2_kickstart_handler(
+        ctx.clone(),
+        status.uuid,
+        status.swap_type(),
+        maker_coin.to_owned(),
+        taker_coin.to_owned(),
+    );
+    ctx.spawner().spawn(fut);
+}
+
+#[derive(Serialize)]
+pub struct SwapV2RefundResponse {
+    uuid: Uuid,
+    refund_tx: TransactionIdentifier,
+}
+
+/// Refunds the payment of `my` node if the swap v2 stopped in refund-required state.
+pub async fn swap_v2_refund_rpc(ctx: MmArc, req: SwapV2ByUuidRequest) -> SwapV2RpcResult<SwapV2RefundResponse> {
+    // The state machine can't proceed from refund-required state, so stop it before the refund is sent manually.
+    let was_running = abort_swap_v2_machine(&ctx, &req.uuid);
+    let status = load_swap_v2_status(&ctx, req.uuid).await?;
+    if let Err(e) = status.ensure_action_is_available(SwapV2Action::Refund) {
+        if was_running {
+            resume_swap_v2_machine(&ctx, &status);
+        }
+        return Err(e);
+    }
+
+    match refund_by_status(&ctx, &status).await {
+        Ok(refund_tx) => Ok(SwapV2RefundResponse {
+            uuid: req.uuid,
+            refund_tx,
+        }),
+        Err(e) => {
+            if was_running {
+                resume_swap_v2_machine(&ctx, &status);
+            }
+            Err(e)
+        },
+    }
+}
+
+async fn refund_by_status(ctx: &MmArc, status: &SwapV2Status) -> SwapV2RpcResult<TransactionIdentifier> {
+    let swap_type = status.swap_type();
+    let (maker_coin_ticker, taker_coin_ticker) = status.maker_and_taker_coins();
+    let maker_coin = lp_coinfind_or_err(ctx, maker_coin_ticker).await?;
+    let taker_coin = lp_coinfind_or_err(ctx, taker_coin_ticker).await?;
+
+    macro_rules! refund {
+        ($maker_coin: expr, $taker_coin: expr) => {
+            refund_swap_v2(ctx, status.uuid, swap_type, $maker_coin, $taker_coin).await?
+        };
+    }
+    let refund_tx = crate::dispatch_swap_v2_coins_pair!(
+        maker_coin,
+        taker_coin,
+        refund,
+        return MmError::err(SwapV2RpcError::PairIsNotSupported {
+            maker_coin: maker_coin_ticker.to_owned(),
+            taker_coin: taker_coin_ticker.to_owned(),
+        })
+    );
+    Ok(refund_tx)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::mm2::database::my_swaps::insert_new_swap;
+    use crate::mm2::lp_swap::swap_v2_common::spawn_swap_v2_machine;
+    use crate::mm2::lp_swap::swap_v2_common::tests::{store_test_swap_v2, swap_v2_test_ctx};
+    use crate::mm2::lp_swap::SwapsContext;
+    use common::block_on;
+
+    fn initialized_event() -> Json {
+        json!({"Initialized": {"maker_coin_start_block": 1, "taker_coin_start_block": 2}})
+    }
+
+    fn maker_refund_required_event() -> Json {
+        json!({"MakerPaymentRefundRequired": {
+            "maker_coin_start_block": 1,
+            "taker_coin_start_block": 2,
+            "negotiation_data": {
+                "taker_payment_locktime": 1_700_007_800,
+                "maker_coin_htlc_pub_from_taker": "03",
+                "taker_coin_htlc_pub_from_taker": "03",
+                "maker_coin_swap_contract": null,
+                "taker_coin_swap_contract": null,
+                "taker_secret_hash": "01"
+            },
+            "maker_payment": {"tx_hex": "02", "tx_hash": "03"}
+        }})
+    }
+
+    fn by_uuid(uuid: Uuid) -> SwapV2ByUuidRequest { SwapV2ByUuidRequest { uuid } }
+
+    fn is_machine_running(ctx: &MmArc, uuid: &Uuid) -> bool {
+        let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
+        let machines = swap_ctx.swap_v2_machines.lock();
+        machines.contains(uuid).unwrap()
+    }
+
+    #[test]
+    fn test_swap_v2_status() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::new_v4();
+        store_test_swap_v2(&ctx, uuid, TAKER_SWAP_V2_TYPE, &[initialized_event()]);
+
+        let status = block_on(swap_v2_status_rpc(ctx.clone(), by_uuid(uuid))).unwrap();
+        assert_eq!(status.uuid, uuid);
+        assert!(!status.is_finished);
+        assert_eq!(status.current_state.as_deref(), Some("Initialized"));
+        assert_eq!(status.available_actions, vec![SwapV2Action::Abort]);
+        assert_eq!(status.timelocks.taker_payment_locktime, 1_700_000_000 + 7800);
+        assert_eq!(status.timelocks.maker_payment_locktime, 1_700_000_000 + 2 * 7800);
+        assert_eq!(status.timelocks.taker_funding_locktime, 1_700_000_000 + 3 * 7800);
+        assert_eq!(status.maker_and_taker_coins(), ("MORTY", "RICK"));
+
+        let unknown = Uuid::new_v4();
+        let err = block_on(swap_v2_status_rpc(ctx.clone(), by_uuid(unknown)))
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(err, SwapV2RpcError::NoSuchSwap(u) if u == unknown));
+
+        let legacy = Uuid::new_v4();
+        insert_new_swap(&ctx, "RICK", "MORTY", &legacy.to_string(), "1700000000").unwrap();
+        let err = block_on(swap_v2_status_rpc(ctx, by_uuid(legacy)))
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(err, SwapV2RpcError::NotSwapV2(u) if u == legacy));
+    }
+
+    #[test]
+    fn test_swaps_v2_list() {
+        let ctx = swap_v2_test_ctx();
+        let finished = Uuid::new_v4();
+        store_test_swap_v2(&ctx, finished, MAKER_SWAP_V2_TYPE, &[json!("Completed")]);
+        block_on(MakerSwapStorage::new(ctx.clone()).mark_finished(finished)).unwrap();
+        let unfinished = Uuid::new_v4();
+        store_test_swap_v2(&ctx, unfinished, TAKER_SWAP_V2_TYPE, &[initialized_event()]);
+        insert_new_swap(&ctx, "RICK", "MORTY", &Uuid::new_v4().to_string(), "1700000000").unwrap();
+
+        let req: SwapsV2ListRequest = serde_json::from_value(json!({"my_coin": "RICK"})).unwrap();
+        let response = block_on(swaps_v2_list_rpc(ctx.clone(), req)).unwrap();
+        assert_eq!(response.total, 2);
+        assert_eq!(response.found_records, 2);
+        let mut uuids: Vec<_> = response.swaps.iter().map(|swap| swap.uuid).collect();
+        uuids.sort();
+        let mut expected = vec![finished, unfinished];
+        expected.sort();
+        assert_eq!(uuids, expected);
+
+        let req: SwapsV2ListRequest = serde_json::from_value(json!({"unfinished_only": true})).unwrap();
+        let response = block_on(swaps_v2_list_rpc(ctx.clone(), req)).unwrap();
+        assert_eq!(response.total, 1);
+        assert_eq!(response.swaps[0].uuid, unfinished);
+        assert!(matches!(response.swaps[0].events, SwapV2Events::Taker(_)));
+
+        let req: SwapsV2ListRequest = serde_json::from_value(json!({"limit": 1, "page_number": 2})).unwrap();
+        let response = block_on(swaps_v2_list_rpc(ctx.clone(), req)).unwrap();
+        assert_eq!(response.total, 2);
+        assert_eq!(response.skipped, 1);
+        assert_eq!(response.total_pages, 2);
+        assert_eq!(response.found_records, 1);
+
+        let req: SwapsV2ListRequest = serde_json::from_value(json!({"other_coin": "RICK"})).unwrap();
+        let response = block_on(swaps_v2_list_rpc(ctx, req)).unwrap();
+        assert_eq!(response.total, 0);
+        assert!(response.swaps.is_empty());
+    }
+
+    #[test]
+    fn test_swap_v2_abort() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::new_v4();
+        store_test_swap_v2(&ctx, uuid, MAKER_SWAP_V2_TYPE, &[initialized_event()]);
+        spawn_swap_v2_machine(&ctx, uuid, futures::future::pending::<()>());
+        assert!(is_machine_running(&ctx, &uuid));
+
+        block_on(swap_v2_abort_rpc(ctx.clone(), by_uuid(uuid))).unwrap();
+        assert!(!is_machine_running(&ctx, &uuid));
+
+        let status = block_on(swap_v2_status_rpc(ctx.clone(), by_uuid(uuid))).unwrap();
+        assert!(status.is_finished);
+        assert_eq!(status.current_state.as_deref(), Some("Aborted"));
+        assert!(status.available_actions.is_empty());
+
+        // The swap can't be aborted once the payment might be sent
+        let refund_required = Uuid::new_v4();
+        store_test_swap_v2(&ctx, refund_required, MAKER_SWAP_V2_TYPE, &[
+            maker_refund_required_event(),
+        ]);
+        let err = block_on(swap_v2_abort_rpc(ctx.clone(), by_uuid(refund_required)))
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(err, SwapV2RpcError::ActionIsNotAvailable {
+            action: SwapV2Action::Abort,
+            ..
+        }));
+        let status = block_on(swap_v2_status_rpc(ctx, by_uuid(refund_required))).unwrap();
+        assert!(!status.is_finished);
+    }
+
+    #[test]
+    fn test_swap_v2_refund() {
+        let ctx = swap_v2_test_ctx();
+        let initialized = Uuid::new_v4();
+        store_test_swap_v2(&ctx, initialized, TAKER_SWAP_V2_TYPE, &[initialized_event()]);
+        let err = block_on(swap_v2_refund_rpc(ctx.clone(), by_uuid(initialized)))
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(err, SwapV2RpcError::ActionIsNotAvailable {
+            action: SwapV2Action::Refund,
+            ..
+        }));
+
+        let refund_required = Uuid::new_v4();
+        store_test_swap_v2(&ctx, refund_required, MAKER_SWAP_V2_TYPE, &[
+            maker_refund_required_event(),
+        ]);
+        let status = block_on(swap_v2_status_rpc(ctx.clone(), by_uuid(refund_required))).unwrap();
+        assert_eq!(status.available_actions, vec![SwapV2Action::Refund]);
+
+        // The coins of the swap have to be enabled to refund the payment
+        let err = block_on(swap_v2_refund_rpc(ctx, by_uuid(refund_required)))
+            .unwrap_err()
+            .into_inner();
+        assert!(matches!(err, SwapV2RpcError::NoSuchCoin { coin } if coin == "RICK"));
+    }
+}
@@ -6,9 +6,9 @@ use uuid::Uuid;
 
 pub use mm2_db::indexed_db::{cursor_prelude, DbTransactionError, DbTransactionResult, InitDbError, InitDbResult,
                              ItemId};
-pub use tables::{MySwapsFiltersTable, SavedSwapTable, SwapLockTable};
+pub use tables::{MySwapsFiltersTable, MySwapsV2Table, SavedSwapTable, SwapLockTable, SwapV2MsgsTable};
 
-const DB_VERSION: u32 = 1;
+const DB_VERSION: u32 = 2;
 
 pub struct SwapDb {
     inner: IndexedDb,
@@ -24,6 +24,8 @@ impl DbInstance for SwapDb {
             .with_table::<SwapLockTable>()
             .with_table::<SavedSwapTable>()
             .with_table::<MySwapsFiltersTable>()
+            .with_table::<MySwapsV2Table>()
+            .with_table::<SwapV2MsgsTable>()
             .build()
             .await?;
         Ok(SwapDb { inner })
@@ -38,6 +40,7 @@ impl Deref for SwapDb {
 
 pub mod tables {
     use super::*;
+    use crate::mm2::lp_swap::MySwapV2Data;
     use serde_json::Value as Json;
 
     #[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
@@ -83,7 +86,7 @@ pub mod tables {
         fn table_name() -> &'static str { "my_swaps" }
 
         fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
-            if let (0, 1) = (old_version, new_version) {
+            if let (0, 1) | (0, 2) = (old_version, new_version) {
                 let table = upgrader.create_table(Self::table_name())?;
                 table.create_index("uuid", true)?;
                 table.create_index("started_at", false)?;
@@ -95,6 +98,59 @@ pub mod tables {
         }
     }
 
+    /// Keeps the swaps v2 data and events, which are stored in `my_swaps` SQLite table on native.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct MySwapsV2Table {
+        pub uuid: Uuid,
+        pub swap_type: u8,
+        /// `1` if the swap is finished, `0` otherwise. Booleans can't be used as IndexedDB keys.
+        pub is_finished: u8,
+        pub secret_hash: Vec<u8>,
+        pub data: MySwapV2Data,
+    }
+
+    impl MySwapsV2Table {
+        pub const SWAP_TYPE_IS_FINISHED_INDEX: &'static str = "swap_type_is_finished";
+    }
+
+    impl TableSignature for MySwapsV2Table {
+        fn table_name() -> &'static str { "my_swaps_v2" }
+
+        fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+            if let (0, 2) | (1, 2) = (old_version, new_version) {
+                let table = upgrader.create_table(Self::table_name())?;
+                table.create_index("uuid", true)?;
+                table.create_multi_index(Self::SWAP_TYPE_IS_FINISHED_INDEX, &["swap_type", "is_finished"], false)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Keeps the p2p messages received during the swaps v2, so they can be processed after the swap is restored.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct SwapV2MsgsTable {
+        pub uuid: Uuid,
+        pub msg_kind: String,
+        pub msg: Vec<u8>,
+    }
+
+    impl SwapV2MsgsTable {
+        pub const UUID_MSG_KIND_INDEX: &'static str = "uuid_msg_kind";
+    }
+
+    impl TableSignature for SwapV2MsgsTable {
+        fn table_name() -> &'static str { "swap_v2_msgs" }
+
+        fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+            if let (0, 2) | (1, 2) = (old_version, new_version) {
+                let table = upgrader.create_table(Self::table_name())?;
+                table.create_index("uuid", false)?;
+                table.create_multi_index(Self::UUID_MSG_KIND_INDEX, &["uuid", "msg_kind"], true)?;
+            }
+            Ok(())
+        }
+    }
+
     /// [`TableSignature::on_upgrade_needed`] implementation common for the most tables with the only `uuid` unique index.
     fn on_upgrade_swap_table_by_uuid_v1(
         upgrader: &DbUpgrader,
@@ -102,7 +158,7 @@ pub mod tables {
         new_version: u32,
         table_name: &'static str,
     ) -> OnUpgradeResult<()> {
-        if let (0, 1) = (old_version, new_version) {
+        if let (0, 1) | (0, 2) = (old_version, new_version) {
             let table = upgrader.create_table(table_name)?;
             table.create_index("uuid", true)?;
         }
@@ -1,6 +1,7 @@
 use super::check_balance::{check_my_coin_balance_for_swap, CheckBalanceError, CheckBalanceResult,
                            TakerFeeAdditionalInfo};
 use super::pubkey_banning::ban_pubkey_on_failed_swap;
+use super::swap_events::{broadcast_swap_status_event, SwapStatusEvent};
 use super::swap_lock::{SwapLock, SwapLockOps};
 use super::swap_watcher::{watcher_topic, SwapWatcherMsg};
 use super::trade_preimage::{TradePreimageRequest, TradePreimageRpcError, TradePreimageRpcResult};
@@ -148,7 +149,7 @@ async fn save_my_taker_swap_event(ctx: &MmArc, swap: &TakerSwap, event: TakerSav
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct TakerSavedEvent {
     pub timestamp: u64,
     pub event: TakerSwapEvent,
@@ -309,6 +310,19 @@ impl TakerSavedSwap {
         Ok(true)
     }
 
+    /// Whether the taker payment was refunded.
+    pub fn is_refunded(&self) -> bool {
+        self.events.iter().any(|event| {
+            matches!(
+                event.event,
+                TakerSwapEvent::TakerPaymentRefunded(_) | TakerSwapEvent::TakerPaymentRefundedByWatcher(_)
+            )
+        })
+    }
+
+    /// Whether the swap failed because of the maker, the same events lead to the maker pubkey ban.
+    pub fn maker_caused_failure(&self) -> bool { self.events.iter().any(|event| event.event.should_ban_maker()) }
+
     pub fn watcher_message_sent(&self) -> bool {
         self.events
             .iter()
@@ -458,9 +472,13 @@ pub async fn run_taker_swap(swap: RunTakerSwapInput, ctx: MmArc) {
                         event: event.clone(),
                     };
 
-                    save_my_taker_swap_event(&ctx, &running_swap, to_save)
+                    save_my_taker_swap_event(&ctx, &running_swap, to_save.clone())
                         .await
                         .expect("!save_my_taker_swap_event");
+                    broadcast_swap_status_event(&ctx, SwapStatusEvent::TakerV1 {
+                        uuid: running_swap.uuid,
+                        event: to_save,
+                    });
                     if event.should_ban_maker() {
                         ban_pubkey_on_failed_swap(
                             &ctx,
@@ -530,6 +548,10 @@ pub struct TakerSwapData {
     /// A transaction fee that should be paid to spend a `MakerPayment`.
     /// Note this value is used to calculate locked amount only.
     pub maker_payment_spend_trade_fee: Option<SavedTradeFee>,
+    /// The dex fee sent by the `TakerFee` transaction including the burn part.
+    /// Not set for the swaps started before the value was saved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dex_fee: Option<BigDecimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub maker_coin_swap_contract_address: Option<BytesJson>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -1071,8 +1093,22 @@ impl TakerSwap {
         let taker_coin_swap_contract_address = self.taker_coin.swap_contract_address();
 
         let unique_data = self.unique_swap_data();
-        let maker_coin_htlc_pubkey = self.maker_coin.derive_htlc_pubkey(&unique_data);
-        let taker_coin_htlc_pubkey = self.taker_coin.derive_htlc_pubkey(&unique_data);
+        let maker_coin_htlc_pubkey = match self.maker_coin.derive_htlc_pubkey(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                return Ok((Some(TakerSwapCommand::Finish), vec![TakerSwapEvent::StartFailed(
+                    ERRL!("!maker_coin.derive_htlc_pubkey {}", e).into(),
+                )]))
+            },
+        };
+        let taker_coin_htlc_pubkey = match self.taker_coin.derive_htlc_pubkey(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                return Ok((Some(TakerSwapCommand::Finish), vec![TakerSwapEvent::StartFailed(
+                    ERRL!("!taker_coin.derive_htlc_pubkey {}", e).into(),
+                )]))
+            },
+        };
 
         let data = TakerSwapData {
             taker_coin: self.taker_coin.ticker().to_owned(),
@@ -1095,6 +1131,7 @@ impl TakerSwap {
             fee_to_send_taker_fee: Some(SavedTradeFee::from(fee_to_send_dex_fee)),
             taker_payment_trade_fee: Some(SavedTradeFee::from(taker_payment_trade_fee)),
             maker_payment_spend_trade_fee: Some(SavedTradeFee::from(maker_payment_spend_trade_fee)),
+            dex_fee: Some(dex_fee.total_spend_amount().to_decimal()),
             maker_coin_swap_contract_address,
             taker_coin_swap_contract_address,
             maker_coin_htlc_pubkey: Some(maker_coin_htlc_pubkey.as_slice().into()),
@@ -1615,19 +1652,23 @@ impl TakerSwap {
                     );
                     let swpmsg_watcher = SwapWatcherMsg::TakerSwapWatcherMsg(watcher_data);
 
-                    let htlc_keypair = self.taker_coin.derive_htlc_key_pair(&self.unique_swap_data());
-                    broadcast_swap_message(
-                        &self.ctx,
-                        watcher_topic(&self.r().data.taker_coin),
-                        swpmsg_watcher,
-                        &Some(htlc_keypair),
-                    );
+                    match self.taker_coin.derive_htlc_key_pair(&self.unique_swap_data()) {
+                        Ok(htlc_keypair) => {
+                            broadcast_swap_message(
+                                &self.ctx,
+                                watcher_topic(&self.r().data.taker_coin),
+                                swpmsg_watcher,
+                                &Some(htlc_keypair),
+                            );
 
-                    swap_events.push(TakerSwapEvent::WatcherMessageSent(
-                        Some(maker_payment_spend.tx_hex()),
-                        Some(taker_payment_refund.tx_hex()),
-                    ));
-                    info!("{}", WATCHER_MESSAGE_SENT_LOG);
+                            swap_events.push(TakerSwapEvent::WatcherMessageSent(
+                                Some(maker_payment_spend.tx_hex()),
+                                Some(taker_payment_refund.tx_hex()),
+                            ));
+                            info!("{}", WATCHER_MESSAGE_SENT_LOG);
+                        },
+                        Err(e) => error!("The watcher message could not be sent: {}", e),
+                    }
                 },
                 Err(e) => error!(
                     "The watcher message could not be sent, error creating at least one of the preimages: {}",
@@ -1660,14 +1701,18 @@ impl TakerSwap {
                     taker_payment_refund,
                 );
                 let swpmsg_watcher = SwapWatcherMsg::TakerSwapWatcherMsg(watcher_data);
-                let htlc_keypair = self.taker_coin.derive_htlc_key_pair(&self.unique_swap_data());
-                watcher_broadcast_abort_handle = Some(broadcast_swap_msg_every_delayed(
-                    self.ctx.clone(),
-                    watcher_topic(&self.r().data.taker_coin),
-                    swpmsg_watcher,
-                    BROADCAST_MSG_INTERVAL_SEC,
-                    Some(htlc_keypair),
-                ));
+                match self.taker_coin.derive_htlc_key_pair(&self.unique_swap_data()) {
+                    Ok(htlc_keypair) => {
+                        watcher_broadcast_abort_handle = Some(broadcast_swap_msg_every_delayed(
+                            self.ctx.clone(),
+                            watcher_topic(&self.r().data.taker_coin),
+                            swpmsg_watcher,
+                            BROADCAST_MSG_INTERVAL_SEC,
+                            Some(htlc_keypair),
+                        ))
+                    },
+                    Err(e) => error!("The watcher message could not be sent: {}", e),
+                }
             }
         }
 
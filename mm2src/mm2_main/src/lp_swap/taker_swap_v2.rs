@@ -1,18 +1,20 @@
+use super::swap_events::{broadcast_swap_status_event, SwapStatusEvent};
 use super::{NEGOTIATE_SEND_INTERVAL, NEGOTIATION_TIMEOUT_SEC};
-use crate::mm2::database::my_swaps::{get_swap_events, insert_new_swap_v2, set_swap_is_finished, update_swap_events};
-use crate::mm2::lp_network::subscribe_to_topic;
+use crate::mm2::lp_swap::swap_v2_common::{init_swap_v2_p2p, load_swap_v2_data, load_swap_v2_events,
+                                          load_unfinished_swaps_v2, mark_swap_v2_finished, store_swap_v2_data,
+                                          store_swap_v2_event, ManualRefundError, MySwapV2Data, StoredTxPreimage,
+                                          SwapRecreateCtx, SwapV2Params};
 use crate::mm2::lp_swap::swap_v2_pb::*;
-use crate::mm2::lp_swap::{broadcast_swap_v2_msg_every, check_balance_for_taker_swap, recv_swap_v2_msg, SecretHashAlgo,
-                          SwapConfirmationsSettings, SwapsContext, TransactionIdentifier, MAX_STARTED_AT_DIFF,
+use crate::mm2::lp_swap::{broadcast_swap_v2_msg_every, check_balance_for_taker_swap, recv_swap_v2_msg, swap_v2_topic,
+                          SecretHashAlgo, SwapConfirmationsSettings, TransactionIdentifier, MAX_STARTED_AT_DIFF,
                           TAKER_SWAP_V2_TYPE};
 use async_trait::async_trait;
 use bitcrypto::{dhash160, sha256};
 use coins::{CoinAssocTypes, ConfirmPaymentInput, FeeApproxStage, GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs,
-            MmCoin, SendTakerFundingArgs, SpendPaymentArgs, SwapOps, SwapOpsV2, ToBytes, Transaction,
-            TxPreimageWithSig, ValidatePaymentInput, WaitForHTLCTxSpendArgs};
+            MmCoin, RefundFundingSecretArgs, RefundPaymentArgs, SendTakerFundingArgs, SpendPaymentArgs, SwapOps,
+            SwapOpsV2, ToBytes, Transaction, TxPreimageWithSig, ValidatePaymentInput, WaitForHTLCTxSpendArgs};
 use common::log::{debug, info, warn};
-use common::{bits256, Future01CompatExt, DEX_FEE_ADDR_RAW_PUBKEY};
-use db_common::sqlite::rusqlite::named_params;
+use common::{now_sec, Future01CompatExt, DEX_FEE_ADDR_RAW_PUBKEY};
 use keys::KeyPair;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
@@ -21,6 +23,7 @@ use mm2_state_machine::prelude::*;
 use mm2_state_machine::storable_state_machine::*;
 use primitives::hash::H256;
 use rpc::v1::types::Bytes as BytesJson;
+use std::convert::TryFrom;
 use std::marker::PhantomData;
 use uuid::Uuid;
 
@@ -67,25 +70,45 @@ pub enum TakerSwapEvent {
         taker_funding: TransactionIdentifier,
         reason: TakerFundingRefundReason,
     },
-    /// Received maker payment
+    /// Taker funding tx has been refunded manually.
+    TakerFundingRefunded {
+        taker_funding: TransactionIdentifier,
+        taker_funding_refund: TransactionIdentifier,
+        reason: TakerFundingRefundReason,
+    },
+    /// Received maker payment and funding spend preimage
     MakerPaymentReceived {
         maker_coin_start_block: u64,
         taker_coin_start_block: u64,
         negotiation_data: StoredNegotiationData,
         taker_funding: TransactionIdentifier,
         maker_payment: TransactionIdentifier,
+        /// Is not stored by the previous versions, which received the preimage from maker later.
+        #[serde(default)]
+        funding_spend_preimage: Option<StoredTxPreimage>,
     },
     /// Sent taker payment.
     TakerPaymentSent {
         maker_coin_start_block: u64,
         taker_coin_start_block: u64,
         taker_payment: TransactionIdentifier,
+        /// Is not stored by the previous versions, so it's taken from [`TakerSwapEvent::MakerPaymentReceived`] on restore.
+        #[serde(default)]
+        maker_payment: Option<TransactionIdentifier>,
         negotiation_data: StoredNegotiationData,
     },
     /// Something went wrong, so taker payment refund is required.
     TakerPaymentRefundRequired {
         taker_payment: TransactionIdentifier,
         negotiation_data: StoredNegotiationData,
+        #[serde(default)]
+        reason: TakerPaymentRefundReason,
+    },
+    /// Taker payment has been refunded manually.
+    TakerPaymentRefunded {
+        taker_payment: TransactionIdentifier,
+        taker_payment_refund: TransactionIdentifier,
+        reason: TakerPaymentRefundReason,
     },
     /// Maker payment is confirmed on-chain
     MakerPaymentConfirmed {
@@ -124,43 +147,38 @@ pub enum TakerSwapEvent {
 pub enum TakerSwapStateMachineError {
     StorageError(String),
     SerdeError(String),
+    RestoreError(String),
 }
 
-/// Dummy storage for taker swap events (used temporary).
-pub struct DummyTakerSwapStorage {
+/// Storage for taker swaps, which keeps the swap data and events in `my_swaps` table on native
+/// and in `my_swaps_v2` IndexedDB table on WASM.
+/// The events are kept as a single JSON array, see [`MakerSwapStorage`](super::maker_swap_v2::MakerSwapStorage).
+pub struct TakerSwapStorage {
     ctx: MmArc,
 }
 
-impl DummyTakerSwapStorage {
-    pub fn new(ctx: MmArc) -> Self { DummyTakerSwapStorage { ctx } }
+impl TakerSwapStorage {
+    pub fn new(ctx: MmArc) -> Self { TakerSwapStorage { ctx } }
 }
 
 #[async_trait]
-impl StateMachineStorage for DummyTakerSwapStorage {
+impl StateMachineStorage for TakerSwapStorage {
     type MachineId = Uuid;
     type Event = TakerSwapEvent;
     type Error = MmError<TakerSwapStateMachineError>;
 
     async fn store_event(&mut self, id: Self::MachineId, event: Self::Event) -> Result<(), Self::Error> {
-        let id_str = id.to_string();
-        let events_json = get_swap_events(&self.ctx.sqlite_connection(), &id_str)
-            .map_to_mm(|e| TakerSwapStateMachineError::StorageError(e.to_string()))?;
-        let mut events: Vec<TakerSwapEvent> =
-            serde_json::from_str(&events_json).map_to_mm(|e| TakerSwapStateMachineError::SerdeError(e.to_string()))?;
-        events.push(event);
-        drop_mutability!(events);
-        let serialized_events =
-            serde_json::to_string(&events).map_to_mm(|e| TakerSwapStateMachineError::SerdeError(e.to_string()))?;
-        update_swap_events(&self.ctx.sqlite_connection(), &id_str, &serialized_events)
-            .map_to_mm(|e| TakerSwapStateMachineError::StorageError(e.to_string()))?;
+        store_swap_v2_event(&self.ctx, id, &event).await?;
+        broadcast_swap_status_event(&self.ctx, SwapStatusEvent::TakerV2 { uuid: id, event });
         Ok(())
     }
 
-    async fn get_unfinished(&self) -> Result<Vec<Self::MachineId>, Self::Error> { todo!() }
+    async fn get_unfinished(&self) -> Result<Vec<Self::MachineId>, Self::Error> {
+        Ok(load_unfinished_swaps_v2(&self.ctx, TAKER_SWAP_V2_TYPE).await?)
+    }
 
     async fn mark_finished(&mut self, id: Self::MachineId) -> Result<(), Self::Error> {
-        set_swap_is_finished(&self.ctx.sqlite_connection(), &id.to_string())
-            .map_to_mm(|e| TakerSwapStateMachineError::StorageError(e.to_string()))
+        Ok(mark_swap_v2_finished(&self.ctx, id).await?)
     }
 }
 
@@ -169,7 +187,7 @@ pub struct TakerSwapStateMachine<MakerCoin, TakerCoin> {
     /// MM2 context.
     pub ctx: MmArc,
     /// Storage.
-    pub storage: DummyTakerSwapStorage,
+    pub storage: TakerSwapStorage,
     /// The timestamp when the swap was started.
     pub started_at: u64,
     /// The duration of HTLC timelock in seconds.
@@ -218,21 +236,353 @@ impl<MakerCoin, TakerCoin> TakerSwapStateMachine<MakerCoin, TakerCoin> {
     }
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableStateMachine
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> TakerSwapStateMachine<MakerCoin, TakerCoin> {
+    /// Recreates the state the swap should continue from using the last stored event.
+    fn state_from_event(
+        &self,
+        event: TakerSwapEvent,
+    ) -> MmResult<Box<dyn State<StateMachine = Self>>, TakerSwapStateMachineError> {
+        let state: Box<dyn State<StateMachine = Self>> = match event {
+            TakerSwapEvent::Initialized {
+                maker_coin_start_block,
+                taker_coin_start_block,
+            } => Box::new(Initialized {
+                maker_coin: Default::default(),
+                taker_coin: Default::default(),
+                maker_coin_start_block,
+                taker_coin_start_block,
+            }),
+            TakerSwapEvent::Negotiated {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+            } => Box::new(Negotiated {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+            }),
+            TakerSwapEvent::TakerFundingSent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                taker_funding,
+            } => Box::new(TakerFundingSent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                taker_funding: self.parse_taker_tx(&taker_funding)?,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+            }),
+            TakerSwapEvent::TakerFundingRefundRequired {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                taker_funding,
+                reason,
+            } => Box::new(TakerFundingRefundRequired {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                taker_funding: self.parse_taker_tx(&taker_funding)?,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+                reason,
+            }),
+            TakerSwapEvent::MakerPaymentReceived {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                taker_funding,
+                maker_payment,
+                funding_spend_preimage: Some(funding_spend_preimage),
+            } => Box::new(MakerPaymentAndFundingSpendPreimgReceived {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+                taker_funding: self.parse_taker_tx(&taker_funding)?,
+                funding_spend_preimage: TxPreimageWithSig {
+                    preimage: self
+                        .taker_coin
+                        .parse_preimage(&funding_spend_preimage.preimage.0)
+                        .map_to_mm(|e| TakerSwapStateMachineError::RestoreError(e.to_string()))?,
+                    signature: self
+                        .taker_coin
+                        .parse_signature(&funding_spend_preimage.signature.0)
+                        .map_to_mm(|e| TakerSwapStateMachineError::RestoreError(e.to_string()))?,
+                },
+                maker_payment,
+            }),
+            // The funding spend preimage wasn't received along with maker payment by the previous versions,
+            // so wait for maker payment info again. The funding will be refunded if maker doesn't send it.
+            TakerSwapEvent::MakerPaymentReceived {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                negotiation_data,
+                taker_funding,
+                funding_spend_preimage: None,
+                ..
+            } => Box::new(TakerFundingSent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                taker_funding: self.parse_taker_tx(&taker_funding)?,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+            }),
+            TakerSwapEvent::TakerPaymentSent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                taker_payment,
+                maker_payment,
+                negotiation_data,
+            } => Box::new(TakerPaymentSent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                taker_payment: self.parse_taker_tx(&taker_payment)?,
+                maker_payment: maker_payment
+                    .or_mm_err(|| TakerSwapStateMachineError::RestoreError("Maker payment is not stored".to_owned()))?,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+            }),
+            TakerSwapEvent::TakerPaymentRefundRequired {
+                taker_payment,
+                negotiation_data,
+                reason,
+            } => Box::new(TakerPaymentRefundRequired {
+                taker_payment: self.parse_taker_tx(&taker_payment)?,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+                reason,
+            }),
+            TakerSwapEvent::MakerPaymentConfirmed {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                maker_payment,
+                taker_payment,
+                negotiation_data,
+            } => Box::new(MakerPaymentConfirmed {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                maker_payment,
+                taker_payment: self.parse_taker_tx(&taker_payment)?,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+            }),
+            TakerSwapEvent::TakerPaymentSpent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                maker_payment,
+                taker_payment,
+                taker_payment_spend,
+                negotiation_data,
+            } => Box::new(TakerPaymentSpent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                maker_payment,
+                taker_payment: self.parse_taker_tx(&taker_payment)?,
+                taker_payment_spend,
+                negotiation_data: self.negotiation_data_from_stored(negotiation_data)?,
+            }),
+            TakerSwapEvent::MakerPaymentSpent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                maker_payment,
+                taker_payment,
+                taker_payment_spend,
+                maker_payment_spend,
+            } => Box::new(MakerPaymentSpent {
+                maker_coin: Default::default(),
+                maker_coin_start_block,
+                taker_coin_start_block,
+                maker_payment,
+                taker_payment: self.parse_taker_tx(&taker_payment)?,
+                taker_payment_spend,
+                maker_payment_spend,
+            }),
+            // The final event might be stored while the swap isn't marked as finished yet.
+            // Restoring the last state will simply mark it as finished.
+            TakerSwapEvent::Aborted { reason } => Box::new(Aborted::new(reason)),
+            TakerSwapEvent::TakerFundingRefunded { .. } | TakerSwapEvent::TakerPaymentRefunded { .. } => {
+                Box::new(Refunded::new())
+            },
+            TakerSwapEvent::Completed => Box::new(Completed::new()),
+        };
+        Ok(state)
+    }
+
+    fn negotiation_data_from_stored(
+        &self,
+        stored: StoredNegotiationData,
+    ) -> MmResult<NegotiationData<MakerCoin, TakerCoin>, TakerSwapStateMachineError> {
+        NegotiationData::from_stored_data(stored, &self.maker_coin, &self.taker_coin)
+    }
+
+    fn parse_taker_tx(&self, tx: &TransactionIdentifier) -> MmResult<TakerCoin::Tx, TakerSwapStateMachineError> {
+        self.taker_coin
+            .parse_tx(&tx.tx_hex.0)
+            .map_to_mm(|e| TakerSwapStateMachineError::RestoreError(e.to_string()))
+    }
+
+    /// Refunds taker funding or taker payment of the swap that stopped in [`TakerFundingRefundRequired`]
+    /// or [`TakerPaymentRefundRequired`] state.
+    /// Stores the corresponding refund event and marks the swap as finished on success.
+    pub(super) async fn refund_manually(&mut self) -> MmResult<TransactionIdentifier, ManualRefundError> {
+        let mut events: Vec<TakerSwapEvent> = load_swap_v2_events(&self.ctx, self.uuid).await?;
+        let unique_data = self.unique_data();
+
+        let (refund_tx, event) = match events.pop() {
+            Some(TakerSwapEvent::TakerFundingRefundRequired {
+                negotiation_data,
+                taker_funding,
+                reason,
+                ..
+            }) => {
+                // Maker hasn't received the taker payment, so the funding can be reclaimed immediately
+                let negotiation_data = self.negotiation_data_from_stored(negotiation_data)?;
+                let funding_tx = self.parse_taker_tx(&taker_funding)?;
+                let args = RefundFundingSecretArgs {
+                    funding_tx: &funding_tx,
+                    time_lock: self.taker_funding_locktime(),
+                    maker_pubkey: &negotiation_data.taker_coin_htlc_pub_from_maker,
+                    taker_secret: self.taker_secret.as_slice(),
+                    taker_secret_hash: &self.taker_secret_hash(),
+                    swap_contract_address: &negotiation_data.taker_coin_swap_contract.map(|bytes| bytes.into()),
+                    swap_unique_data: &unique_data,
+                    watcher_reward: false,
+                };
+                let refund_tx = self
+                    .taker_coin
+                    .refund_taker_funding_secret(args)
+                    .await
+                    .map_to_mm(|e| ManualRefundError::TransactionError(format!("{:?}", e)))?;
+                let taker_funding_refund = TransactionIdentifier {
+                    tx_hex: refund_tx.tx_hex().into(),
+                    tx_hash: refund_tx.tx_hash(),
+                };
+                let event = TakerSwapEvent::TakerFundingRefunded {
+                    taker_funding,
+                    taker_funding_refund: taker_funding_refund.clone(),
+                    reason,
+                };
+                (taker_funding_refund, event)
+            },
+            Some(TakerSwapEvent::TakerPaymentRefundRequired {
+                taker_payment,
+                negotiation_data,
+                reason,
+            }) => {
+                let locktime = self.taker_payment_locktime();
+                let now = now_sec();
+                if now < locktime {
+                    return MmError::err(ManualRefundError::LocktimeNotExpired { locktime, now });
+                }
+
+                let negotiation_data = self.negotiation_data_from_stored(negotiation_data)?;
+                let args = RefundPaymentArgs {
+                    payment_tx: &taker_payment.tx_hex.0,
+                    time_lock: locktime,
+                    other_pubkey: &negotiation_data.taker_coin_htlc_pub_from_maker.to_bytes(),
+                    secret_hash: &negotiation_data.maker_secret_hash,
+                    swap_contract_address: &negotiation_data.taker_coin_swap_contract.map(|bytes| bytes.into()),
+                    swap_unique_data: &unique_data,
+                    watcher_reward: false,
+                };
+                let refund_tx = self
+                    .taker_coin
+                    .refund_combined_taker_payment(args)
+                    .await
+                    .map_to_mm(|e| ManualRefundError::TransactionError(format!("{:?}", e)))?;
+                let taker_payment_refund = TransactionIdentifier {
+                    tx_hex: refund_tx.tx_hex().into(),
+                    tx_hash: refund_tx.tx_hash(),
+                };
+                let event = TakerSwapEvent::TakerPaymentRefunded {
+                    taker_payment,
+                    taker_payment_refund: taker_payment_refund.clone(),
+                    reason,
+                };
+                (taker_payment_refund, event)
+            },
+            Some(event) => return MmError::err(ManualRefundError::RefundIsNotRequired(format!("{:?}", event))),
+            None => return MmError::err(ManualRefundError::RefundIsNotRequired("no events".to_owned())),
+        };
+        info!(
+            "Refunded {} tx {:02x} during swap {}",
+            self.taker_coin.ticker(),
+            refund_tx.tx_hash,
+            self.uuid
+        );
+
+        self.store_event(event).await?;
+        self.mark_finished().await?;
+        Ok(refund_tx)
+    }
+}
+
+#[async_trait]
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableStateMachine
     for TakerSwapStateMachine<MakerCoin, TakerCoin>
 {
-    type Storage = DummyTakerSwapStorage;
+    type Storage = TakerSwapStorage;
     type Result = ();
+    type RecreateCtx = SwapRecreateCtx<MakerCoin, TakerCoin>;
 
     fn storage(&mut self) -> &mut Self::Storage { &mut self.storage }
 
     fn id(&self) -> <Self::Storage as StateMachineStorage>::MachineId { self.uuid }
 
-    fn restore_from_storage(
-        _id: <Self::Storage as StateMachineStorage>::MachineId,
-        _storage: Self::Storage,
+    async fn restore_from_storage(
+        id: <Self::Storage as StateMachineStorage>::MachineId,
+        storage: Self::Storage,
+        recreate_ctx: Self::RecreateCtx,
     ) -> Result<RestoredMachine<Self>, <Self::Storage as StateMachineStorage>::Error> {
-        todo!()
+        let swap_data = load_swap_v2_data(&storage.ctx, id).await?;
+        let params = SwapV2Params::try_from(&swap_data).map_to_mm(TakerSwapStateMachineError::RestoreError)?;
+        let events: Vec<TakerSwapEvent> = serde_json::from_str(&swap_data.events_json)
+            .map_to_mm(|e| TakerSwapStateMachineError::SerdeError(e.to_string()))?;
+        let last_event = event_to_restore_from(events)
+            .or_mm_err(|| TakerSwapStateMachineError::RestoreError(format!("Swap {} has no stored events", id)))?;
+
+        let machine = TakerSwapStateMachine {
+            ctx: storage.ctx.clone(),
+            storage,
+            started_at: params.started_at,
+            lock_duration: params.lock_duration,
+            maker_coin: recreate_ctx.maker_coin,
+            maker_volume: params.maker_volume,
+            taker_coin: recreate_ctx.taker_coin,
+            taker_volume: params.taker_volume,
+            dex_fee: params.dex_fee,
+            taker_premium: params.premium,
+            secret_hash_algo: params.secret_hash_algo,
+            conf_settings: params.conf_settings,
+            uuid: id,
+            p2p_topic: swap_v2_topic(&id),
+            p2p_keypair: params.p2p_keypair,
+            taker_secret: params.secret.into(),
+        };
+        let current_state = machine.state_from_event(last_event)?;
+        Ok(RestoredMachine { machine, current_state })
+    }
+}
+
+/// Returns the last stored event to restore the swap from.
+/// [`TakerSwapEvent::TakerPaymentSent`] stored by the previous versions doesn't contain maker payment,
+/// so it's taken from the preceding [`TakerSwapEvent::MakerPaymentReceived`].
+fn event_to_restore_from(mut events: Vec<TakerSwapEvent>) -> Option<TakerSwapEvent> {
+    match events.pop()? {
+        TakerSwapEvent::TakerPaymentSent {
+            maker_coin_start_block,
+            taker_coin_start_block,
+            taker_payment,
+            maker_payment: None,
+            negotiation_data,
+        } => {
+            let maker_payment = events.into_iter().rev().find_map(|event| match event {
+                TakerSwapEvent::MakerPaymentReceived { maker_payment, .. } => Some(maker_payment),
+                _ => None,
+            });
+            Some(TakerSwapEvent::TakerPaymentSent {
+                maker_coin_start_block,
+                taker_coin_start_block,
+                taker_payment,
+                maker_payment,
+                negotiation_data,
+            })
+        },
+        event => Some(event),
     }
 }
 
@@ -251,44 +601,54 @@ impl<MakerCoin, TakerCoin> Default for Initialize<MakerCoin, TakerCoin> {
     }
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> InitialState for Initialize<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> InitialState
+    for Initialize<MakerCoin, TakerCoin>
+{
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
 }
 
 #[async_trait]
-impl<MakerCoin: MmCoin + SwapOpsV2 + Send + Sync + 'static, TakerCoin: MmCoin + SwapOpsV2 + Send + Sync + 'static> State
-    for Initialize<MakerCoin, TakerCoin>
-{
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State for Initialize<MakerCoin, TakerCoin> {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     async fn on_changed(self: Box<Self>, state_machine: &mut Self::StateMachine) -> StateResult<Self::StateMachine> {
+        let swap_data = MySwapV2Data {
+            my_coin: state_machine.taker_coin.ticker().to_owned(),
+            other_coin: state_machine.maker_coin.ticker().to_owned(),
+            started_at: state_machine.started_at as i64,
+            events_json: "[]".to_owned(),
+            maker_volume: state_machine.maker_volume.to_fraction_string(),
+            taker_volume: state_machine.taker_volume.to_fraction_string(),
+            premium: state_machine.taker_premium.to_fraction_string(),
+            dex_fee: state_machine.dex_fee.to_fraction_string(),
+            secret: state_machine.taker_secret.take().to_vec(),
+            secret_hash_algo: state_machine.secret_hash_algo as u8,
+            p2p_privkey: state_machine
+                .p2p_keypair
+                .map(|k| k.private_bytes())
+                .unwrap_or_default()
+                .to_vec(),
+            lock_duration: state_machine.lock_duration as i64,
+            maker_coin_confs: state_machine.conf_settings.maker_coin_confs as i64,
+            maker_coin_nota: state_machine.conf_settings.maker_coin_nota,
+            taker_coin_confs: state_machine.conf_settings.taker_coin_confs as i64,
+            taker_coin_nota: state_machine.conf_settings.taker_coin_nota,
+        };
+        let secret_hash = state_machine.taker_secret_hash();
+        if let Err(e) = store_swap_v2_data(
+            &state_machine.ctx,
+            state_machine.uuid,
+            TAKER_SWAP_V2_TYPE,
+            &secret_hash,
+            swap_data,
+        )
+        .await
         {
-            let sql_params = named_params! {
-                ":my_coin": state_machine.taker_coin.ticker(),
-                ":other_coin": state_machine.maker_coin.ticker(),
-                ":uuid": state_machine.uuid.to_string(),
-                ":started_at": state_machine.started_at,
-                ":swap_type": TAKER_SWAP_V2_TYPE,
-                ":maker_volume": state_machine.maker_volume.to_fraction_string(),
-                ":taker_volume": state_machine.taker_volume.to_fraction_string(),
-                ":premium": state_machine.taker_premium.to_fraction_string(),
-                ":dex_fee": state_machine.dex_fee.to_fraction_string(),
-                ":secret": state_machine.taker_secret.take(),
-                ":secret_hash": state_machine.taker_secret_hash(),
-                ":secret_hash_algo": state_machine.secret_hash_algo as u8,
-                ":p2p_privkey": state_machine.p2p_keypair.map(|k| k.private_bytes()).unwrap_or_default(),
-                ":lock_duration": state_machine.lock_duration,
-                ":maker_coin_confs": state_machine.conf_settings.maker_coin_confs,
-                ":maker_coin_nota": state_machine.conf_settings.maker_coin_nota,
-                ":taker_coin_confs": state_machine.conf_settings.taker_coin_confs,
-                ":taker_coin_nota": state_machine.conf_settings.taker_coin_nota
-            };
-            insert_new_swap_v2(&state_machine.ctx, sql_params).unwrap();
+            let reason = AbortReason::FailedToStoreSwapData(e.to_string());
+            return Self::change_state(Aborted::new(reason), state_machine).await;
         }
 
-        subscribe_to_topic(&state_machine.ctx, state_machine.p2p_topic.clone());
-        let swap_ctx = SwapsContext::from_ctx(&state_machine.ctx).expect("SwapsContext::from_ctx should not fail");
-        swap_ctx.init_msg_v2_store(state_machine.uuid, bits256::default());
+        init_swap_v2_p2p(&state_machine.ctx, state_machine.uuid);
 
         let maker_coin_start_block = match state_machine.maker_coin.current_block().compat().await {
             Ok(b) => b,
@@ -341,7 +701,9 @@ struct Initialized<MakerCoin, TakerCoin> {
 
 impl<MakerCoin, TakerCoin> TransitionFrom<Initialize<MakerCoin, TakerCoin>> for Initialized<MakerCoin, TakerCoin> {}
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableState for Initialized<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
+    for Initialized<MakerCoin, TakerCoin>
+{
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     fn get_event(&self) -> <<Self::StateMachine as StorableStateMachine>::Storage as StateMachineStorage>::Event {
@@ -414,14 +776,29 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State fo
         };
 
         let unique_data = state_machine.unique_data();
+        let maker_coin_htlc_pub = match state_machine.maker_coin.derive_htlc_pubkey(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                let reason = AbortReason::FailedToDeriveHtlcPubkey(e.to_string());
+                return Self::change_state(Aborted::new(reason), state_machine).await;
+            },
+        };
+        let taker_coin_htlc_pub = match state_machine.taker_coin.derive_htlc_pubkey(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                let reason = AbortReason::FailedToDeriveHtlcPubkey(e.to_string());
+                return Self::change_state(Aborted::new(reason), state_machine).await;
+            },
+        };
+
         let taker_negotiation = TakerNegotiation {
             action: Some(taker_negotiation::Action::Continue(TakerNegotiationData {
                 started_at: state_machine.started_at,
                 funding_locktime: state_machine.taker_funding_locktime(),
                 payment_locktime: state_machine.taker_payment_locktime(),
                 taker_secret_hash: state_machine.taker_secret_hash(),
-                maker_coin_htlc_pub: state_machine.maker_coin.derive_htlc_pubkey(&unique_data),
-                taker_coin_htlc_pub: state_machine.taker_coin.derive_htlc_pubkey(&unique_data),
+                maker_coin_htlc_pub,
+                taker_coin_htlc_pub,
                 maker_coin_swap_contract: state_machine.maker_coin.swap_contract_address().map(|bytes| bytes.0),
                 taker_coin_swap_contract: state_machine.taker_coin.swap_contract_address().map(|bytes| bytes.0),
             })),
@@ -496,6 +873,25 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> NegotiationData<Maker
             taker_coin_swap_contract: self.taker_coin_swap_contract.clone().map(|b| b.into()),
         }
     }
+
+    fn from_stored_data(
+        stored: StoredNegotiationData,
+        maker_coin: &MakerCoin,
+        taker_coin: &TakerCoin,
+    ) -> MmResult<Self, TakerSwapStateMachineError> {
+        Ok(NegotiationData {
+            maker_secret_hash: stored.maker_secret_hash.0,
+            maker_payment_locktime: stored.maker_payment_locktime,
+            maker_coin_htlc_pub_from_maker: maker_coin
+                .parse_pubkey(&stored.maker_coin_htlc_pub_from_maker.0)
+                .map_to_mm(|e| TakerSwapStateMachineError::RestoreError(e.to_string()))?,
+            taker_coin_htlc_pub_from_maker: taker_coin
+                .parse_pubkey(&stored.taker_coin_htlc_pub_from_maker.0)
+                .map_to_mm(|e| TakerSwapStateMachineError::RestoreError(e.to_string()))?,
+            maker_coin_swap_contract: stored.maker_coin_swap_contract.map(|b| b.0),
+            taker_coin_swap_contract: stored.taker_coin_swap_contract.map(|b| b.0),
+        })
+    }
 }
 
 struct Negotiated<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> {
@@ -517,6 +913,8 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State fo
         let args = SendTakerFundingArgs {
             time_lock: state_machine.taker_funding_locktime(),
             taker_secret_hash: &state_machine.taker_secret_hash(),
+            maker_secret_hash: &self.negotiation_data.maker_secret_hash,
+            payment_time_lock: state_machine.taker_payment_locktime(),
             maker_pub: &self.negotiation_data.taker_coin_htlc_pub_from_maker.to_bytes(),
             dex_fee_amount: state_machine.dex_fee.to_decimal(),
             premium_amount: state_machine.taker_premium.to_decimal(),
@@ -549,7 +947,7 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State fo
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for Negotiated<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -674,7 +1072,7 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> TransitionFrom<Negoti
 {
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for TakerFundingSent<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -706,7 +1104,7 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> TransitionFrom<TakerF
 {
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for MakerPaymentAndFundingSpendPreimgReceived<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -721,6 +1119,10 @@ impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableS
                 tx_hash: self.taker_funding.tx_hash(),
             },
             maker_payment: self.maker_payment.clone(),
+            funding_spend_preimage: StoredTxPreimage {
+                preimage: self.funding_spend_preimage.preimage.to_bytes().into(),
+                signature: self.funding_spend_preimage.signature.to_bytes().into(),
+            },
         }
     }
 }
@@ -741,7 +1143,11 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
             other_pub: self.negotiation_data.maker_coin_htlc_pub_from_maker.to_bytes(),
             secret_hash: self.negotiation_data.maker_secret_hash.clone(),
             amount: state_machine.maker_volume.to_decimal(),
-            swap_contract_address: None,
+            swap_contract_address: self
+                .negotiation_data
+                .maker_coin_swap_contract
+                .clone()
+                .map(|bytes| bytes.into()),
             try_spv_proof_until: state_machine.maker_payment_conf_timeout(),
             confirmations: state_machine.conf_settings.maker_coin_confs,
             unique_swap_data: unique_data.clone(),
@@ -758,10 +1164,24 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
             return Self::change_state(next_state, state_machine).await;
         };
 
+        let taker_pub = match state_machine.taker_coin.derive_htlc_pubkey_v2(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                let next_state = TakerFundingRefundRequired {
+                    maker_coin_start_block: self.maker_coin_start_block,
+                    taker_coin_start_block: self.taker_coin_start_block,
+                    taker_funding: self.taker_funding,
+                    negotiation_data: self.negotiation_data,
+                    reason: TakerFundingRefundReason::FailedToDeriveHtlcPubkey(e.to_string()),
+                };
+                return Self::change_state(next_state, state_machine).await;
+            },
+        };
+
         let args = GenTakerFundingSpendArgs {
             funding_tx: &self.taker_funding,
             maker_pub: &self.negotiation_data.taker_coin_htlc_pub_from_maker,
-            taker_pub: &state_machine.taker_coin.derive_htlc_pubkey_v2(&unique_data),
+            taker_pub: &taker_pub,
             funding_time_lock: state_machine.taker_funding_locktime(),
             taker_secret_hash: &state_machine.taker_secret_hash(),
             taker_payment_time_lock: state_machine.taker_payment_locktime(),
@@ -883,7 +1303,7 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for TakerPaymentSent<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -896,6 +1316,7 @@ impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableS
                 tx_hex: self.taker_payment.tx_hex().into(),
                 tx_hash: self.taker_payment.tx_hash(),
             },
+            maker_payment: Some(self.maker_payment.clone()),
             negotiation_data: self.negotiation_data.to_stored_data(),
         }
     }
@@ -910,6 +1331,7 @@ pub enum TakerFundingRefundReason {
     FailedToSendTakerPayment(String),
     MakerPaymentValidationFailed(String),
     FundingSpendPreimageValidationFailed(String),
+    FailedToDeriveHtlcPubkey(String),
 }
 
 struct TakerFundingRefundRequired<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> {
@@ -931,7 +1353,7 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes>
 }
 
 #[async_trait]
-impl<MakerCoin: SwapOps + CoinAssocTypes + Send + 'static, TakerCoin: MmCoin + SwapOpsV2> State
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     for TakerFundingRefundRequired<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -941,7 +1363,7 @@ impl<MakerCoin: SwapOps + CoinAssocTypes + Send + 'static, TakerCoin: MmCoin + S
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: CoinAssocTypes + Send + 'static> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for TakerFundingRefundRequired<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -960,11 +1382,16 @@ impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: CoinAssocTypes + Sen
     }
 }
 
-#[derive(Debug)]
-enum TakerPaymentRefundReason {
+/// Represents the reason of taker payment refund
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub enum TakerPaymentRefundReason {
+    /// The reason is not stored by the previous versions.
+    #[default]
+    Unknown,
     MakerPaymentNotConfirmedInTime(String),
     FailedToGenerateSpendPreimage(String),
     MakerDidNotSpendInTime(String),
+    FailedToDeriveHtlcPubkey(String),
 }
 
 struct TakerPaymentRefundRequired<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> {
@@ -983,7 +1410,7 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: CoinAssocTypes> TransitionFrom<MakerP
 }
 
 #[async_trait]
-impl<MakerCoin: CoinAssocTypes + Send + Sync + 'static, TakerCoin: CoinAssocTypes + Send + Sync + 'static> State
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     for TakerPaymentRefundRequired<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -997,7 +1424,7 @@ impl<MakerCoin: CoinAssocTypes + Send + Sync + 'static, TakerCoin: CoinAssocType
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: CoinAssocTypes + Send + 'static> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for TakerPaymentRefundRequired<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -1009,6 +1436,7 @@ impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: CoinAssocTypes + Sen
                 tx_hash: self.taker_payment.tx_hash(),
             },
             negotiation_data: self.negotiation_data.to_stored_data(),
+            reason: self.reason.clone(),
         }
     }
 }
@@ -1035,12 +1463,24 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     async fn on_changed(self: Box<Self>, state_machine: &mut Self::StateMachine) -> StateResult<Self::StateMachine> {
         let unique_data = state_machine.unique_data();
 
+        let taker_pub = match state_machine.taker_coin.derive_htlc_pubkey_v2(&unique_data) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                let next_state = TakerPaymentRefundRequired {
+                    taker_payment: self.taker_payment,
+                    negotiation_data: self.negotiation_data,
+                    reason: TakerPaymentRefundReason::FailedToDeriveHtlcPubkey(e.to_string()),
+                };
+                return Self::change_state(next_state, state_machine).await;
+            },
+        };
+
         let args = GenTakerPaymentSpendArgs {
             taker_tx: &self.taker_payment,
             time_lock: state_machine.taker_payment_locktime(),
             secret_hash: &self.negotiation_data.maker_secret_hash,
             maker_pub: &self.negotiation_data.taker_coin_htlc_pub_from_maker,
-            taker_pub: &state_machine.taker_coin.derive_htlc_pubkey_v2(&unique_data),
+            taker_pub: &taker_pub,
             dex_fee_pub: &DEX_FEE_ADDR_RAW_PUBKEY,
             dex_fee_amount: state_machine.dex_fee.to_decimal(),
             premium_amount: Default::default(),
@@ -1130,7 +1570,7 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for MakerPaymentConfirmed<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -1235,7 +1675,7 @@ impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     }
 }
 
-impl<MakerCoin: CoinAssocTypes + Send + 'static, TakerCoin: SwapOpsV2> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for TakerPaymentSpent<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -1270,7 +1710,7 @@ impl<MakerCoin: CoinAssocTypes, TakerCoin: SwapOpsV2> TransitionFrom<TakerPaymen
 {
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: CoinAssocTypes + Send + 'static> StorableState
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
     for MakerPaymentSpent<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -1291,7 +1731,7 @@ impl<MakerCoin: Send + 'static, TakerCoin: CoinAssocTypes + Send + 'static> Stor
 }
 
 #[async_trait]
-impl<MakerCoin: Send + Sync + 'static, TakerCoin: CoinAssocTypes + Send + Sync + 'static> State
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> State
     for MakerPaymentSpent<MakerCoin, TakerCoin>
 {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
@@ -1310,6 +1750,7 @@ pub enum AbortReason {
     DidNotReceiveMakerNegotiation(String),
     TooLargeStartedAtDiff(u64),
     FailedToParsePubkey(String),
+    FailedToDeriveHtlcPubkey(String),
     MakerProvidedInvalidLocktime(u64),
     SecretHashUnexpectedLen(usize),
     DidNotReceiveMakerNegotiated(String),
@@ -1317,6 +1758,8 @@ pub enum AbortReason {
     FailedToSendTakerFunding(String),
     CouldNotExtractSecret(String),
     FailedToSpendMakerPayment(String),
+    AbortedByUser,
+    FailedToStoreSwapData(String),
 }
 
 struct Aborted<MakerCoin, TakerCoin> {
@@ -1336,7 +1779,7 @@ impl<MakerCoin, TakerCoin> Aborted<MakerCoin, TakerCoin> {
 }
 
 #[async_trait]
-impl<MakerCoin: Send + Sync + 'static, TakerCoin: Send + Sync + 'static> LastState for Aborted<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> LastState for Aborted<MakerCoin, TakerCoin> {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     async fn on_changed(
@@ -1347,7 +1790,9 @@ impl<MakerCoin: Send + Sync + 'static, TakerCoin: Send + Sync + 'static> LastSta
     }
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableState for Aborted<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
+    for Aborted<MakerCoin, TakerCoin>
+{
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     fn get_event(&self) -> <<Self::StateMachine as StorableStateMachine>::Storage as StateMachineStorage>::Event {
@@ -1382,7 +1827,9 @@ impl<MakerCoin, TakerCoin> Completed<MakerCoin, TakerCoin> {
     }
 }
 
-impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableState for Completed<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> StorableState
+    for Completed<MakerCoin, TakerCoin>
+{
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     fn get_event(&self) -> <<Self::StateMachine as StorableStateMachine>::Storage as StateMachineStorage>::Event {
@@ -1391,7 +1838,7 @@ impl<MakerCoin: Send + 'static, TakerCoin: Send + 'static> StorableState for Com
 }
 
 #[async_trait]
-impl<MakerCoin: Send + Sync + 'static, TakerCoin: Send + Sync + 'static> LastState for Completed<MakerCoin, TakerCoin> {
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> LastState for Completed<MakerCoin, TakerCoin> {
     type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
 
     async fn on_changed(
@@ -1406,3 +1853,167 @@ impl<MakerCoin, TakerCoin: CoinAssocTypes> TransitionFrom<MakerPaymentSpent<Make
     for Completed<MakerCoin, TakerCoin>
 {
 }
+
+struct Refunded<MakerCoin, TakerCoin> {
+    maker_coin: PhantomData<MakerCoin>,
+    taker_coin: PhantomData<TakerCoin>,
+}
+
+impl<MakerCoin, TakerCoin> Refunded<MakerCoin, TakerCoin> {
+    fn new() -> Refunded<MakerCoin, TakerCoin> {
+        Refunded {
+            maker_coin: Default::default(),
+            taker_coin: Default::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl<MakerCoin: MmCoin + CoinAssocTypes, TakerCoin: MmCoin + SwapOpsV2> LastState for Refunded<MakerCoin, TakerCoin> {
+    type StateMachine = TakerSwapStateMachine<MakerCoin, TakerCoin>;
+
+    async fn on_changed(
+        self: Box<Self>,
+        state_machine: &mut Self::StateMachine,
+    ) -> <Self::StateMachine as StateMachineTrait>::Result {
+        info!("Swap {} taker funds have been refunded", state_machine.uuid);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::mm2::lp_swap::swap_v2_common::tests::{store_test_swap_v2, swap_v2_test_ctx};
+    use coins::TestCoin;
+    use common::block_on;
+    use serde_json::Value as Json;
+
+    type TestTakerSwap = TakerSwapStateMachine<TestCoin, TestCoin>;
+
+    fn negotiation_data() -> Json {
+        json!({
+            "maker_payment_locktime": 1700015600,
+            "maker_secret_hash": "0101",
+            "maker_coin_htlc_pub_from_maker": "02",
+            "taker_coin_htlc_pub_from_maker": "03",
+            "maker_coin_swap_contract": null,
+            "taker_coin_swap_contract": null
+        })
+    }
+
+    fn maker_payment_received() -> Json {
+        json!({"MakerPaymentReceived": {
+            "maker_coin_start_block": 1,
+            "taker_coin_start_block": 2,
+            "negotiation_data": negotiation_data(),
+            "taker_funding": {"tx_hex": "01", "tx_hash": "02"},
+            "maker_payment": {"tx_hex": "03", "tx_hash": "04"}
+        }})
+    }
+
+    /// [`TakerSwapEvent::TakerPaymentSent`] as it's stored by the previous versions.
+    fn legacy_taker_payment_sent() -> Json {
+        json!({"TakerPaymentSent": {
+            "maker_coin_start_block": 1,
+            "taker_coin_start_block": 2,
+            "taker_payment": {"tx_hex": "05", "tx_hash": "06"},
+            "negotiation_data": negotiation_data()
+        }})
+    }
+
+    fn restore_taker_swap(
+        ctx: &MmArc,
+        uuid: Uuid,
+    ) -> MmResult<RestoredMachine<TestTakerSwap>, TakerSwapStateMachineError> {
+        let recreate_ctx = SwapRecreateCtx {
+            maker_coin: TestCoin::default(),
+            taker_coin: TestCoin::default(),
+        };
+        block_on(TestTakerSwap::restore_from_storage(
+            uuid,
+            TakerSwapStorage::new(ctx.clone()),
+            recreate_ctx,
+        ))
+    }
+
+    #[test]
+    fn test_event_to_restore_from() {
+        let events: Vec<TakerSwapEvent> =
+            serde_json::from_value(json!([maker_payment_received(), legacy_taker_payment_sent()])).unwrap();
+        match event_to_restore_from(events) {
+            Some(TakerSwapEvent::TakerPaymentSent {
+                maker_payment: Some(maker_payment),
+                ..
+            }) => assert_eq!(maker_payment.tx_hex, BytesJson::from(vec![3])),
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        let events: Vec<TakerSwapEvent> = serde_json::from_value(json!([legacy_taker_payment_sent()])).unwrap();
+        assert!(matches!(
+            event_to_restore_from(events),
+            Some(TakerSwapEvent::TakerPaymentSent {
+                maker_payment: None,
+                ..
+            })
+        ));
+
+        assert!(event_to_restore_from(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_deserialize_legacy_taker_events() {
+        let event: TakerSwapEvent = serde_json::from_value(maker_payment_received()).unwrap();
+        assert!(matches!(event, TakerSwapEvent::MakerPaymentReceived {
+            funding_spend_preimage: None,
+            ..
+        }));
+
+        let event: TakerSwapEvent = serde_json::from_value(json!({"TakerPaymentRefundRequired": {
+            "taker_payment": {"tx_hex": "05", "tx_hash": "06"},
+            "negotiation_data": negotiation_data()
+        }}))
+        .unwrap();
+        assert!(matches!(event, TakerSwapEvent::TakerPaymentRefundRequired {
+            reason: TakerPaymentRefundReason::Unknown,
+            ..
+        }));
+    }
+
+    #[test]
+    fn test_restore_taker_swap_mid_swap() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::new_v4();
+        let events = [
+            json!({"Initialized": {"maker_coin_start_block": 1, "taker_coin_start_block": 2}}),
+            json!({"Negotiated": {
+                "maker_coin_start_block": 1,
+                "taker_coin_start_block": 2,
+                "negotiation_data": negotiation_data()
+            }}),
+            json!({"TakerFundingSent": {
+                "maker_coin_start_block": 1,
+                "taker_coin_start_block": 2,
+                "negotiation_data": negotiation_data(),
+                "taker_funding": {"tx_hex": "01", "tx_hash": "02"}
+            }}),
+            maker_payment_received(),
+            legacy_taker_payment_sent(),
+        ];
+        store_test_swap_v2(&ctx, uuid, TAKER_SWAP_V2_TYPE, &events);
+
+        let restored = restore_taker_swap(&ctx, uuid).unwrap();
+        assert_eq!(restored.machine.uuid, uuid);
+        assert_eq!(restored.machine.taker_volume, MmNumber::from(2));
+        assert_eq!(restored.machine.lock_duration, 7800);
+    }
+
+    #[test]
+    fn test_restore_taker_swap_without_maker_payment() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::new_v4();
+        store_test_swap_v2(&ctx, uuid, TAKER_SWAP_V2_TYPE, &[legacy_taker_payment_sent()]);
+
+        let err = restore_taker_swap(&ctx, uuid).err().unwrap();
+        assert!(matches!(err.get_inner(), TakerSwapStateMachineError::RestoreError(_)));
+    }
+}
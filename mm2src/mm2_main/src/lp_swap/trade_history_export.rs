@@ -0,0 +1,768 @@
+//! Accounting export of the finished swaps history.
+//!
+//! Every successfully finished swap is exported with its amounts, the dex fee, the miner fees saved at the swap start
+//! and the USD prices of the coins fetched when the swap was finished.
+//! The swaps v2 are exported with their amounts and the dex fee only since the fees and the prices aren't stored for them.
+//! The realized PnL of each coin is calculated using the average cost method.
+
+use super::maker_swap::{MakerSavedSwap, MakerSwapEvent};
+use super::maker_swap_v2::MakerSwapEvent as MakerSwapV2Event;
+use super::my_swaps_storage::{MySwapsError, MySwapsOps, MySwapsStorage};
+use super::swap_v2_common::{load_swap_v2_data, load_swap_v2_type, MySwapV2Data, SwapV2DbError, SwapV2Params};
+use super::taker_swap::{TakerSavedSwap, TakerSwapEvent};
+use super::taker_swap_v2::TakerSwapEvent as TakerSwapV2Event;
+use super::{MySwapsFilter, SavedSwap, SavedSwapIo, SavedTradeFee, MAKER_SWAP_V2_TYPE};
+use common::log::debug;
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigDecimal, MmNumber};
+use ser_error_derive::SerializeErrorType;
+use std::cmp::min;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+const CSV_HEADER: &str = "uuid,swap_type,started_at,finished_at,my_coin,my_amount,other_coin,other_amount,\
+my_coin_usd_price,other_coin_usd_price,my_amount_usd,other_amount_usd,dex_fee_coin,dex_fee_amount,miner_fees";
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum TradeHistoryExportError {
+    #[display(fmt = "Invalid timestamp range: 'from_timestamp' must not be greater than 'to_timestamp'")]
+    InvalidTimestampRange,
+    #[display(fmt = "Storage error: {}", _0)]
+    StorageError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    InternalError(String),
+}
+
+impl HttpStatusCode for TradeHistoryExportError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            TradeHistoryExportError::InvalidTimestampRange => StatusCode::BAD_REQUEST,
+            TradeHistoryExportError::StorageError(_) | TradeHistoryExportError::InternalError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+impl From<MySwapsError> for TradeHistoryExportError {
+    fn from(e: MySwapsError) -> Self {
+        match e {
+            MySwapsError::InvalidTimestampRange => TradeHistoryExportError::InvalidTimestampRange,
+            MySwapsError::InternalError(e) => TradeHistoryExportError::InternalError(e),
+            e => TradeHistoryExportError::StorageError(e.to_string()),
+        }
+    }
+}
+
+impl From<SwapV2DbError> for TradeHistoryExportError {
+    fn from(e: SwapV2DbError) -> Self {
+        match e {
+            SwapV2DbError::StorageError(e) => TradeHistoryExportError::StorageError(e),
+            SwapV2DbError::SerdeError(e) => TradeHistoryExportError::InternalError(e),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeHistoryFormat {
+    Json,
+    Csv,
+}
+
+impl Default for TradeHistoryFormat {
+    fn default() -> Self { TradeHistoryFormat::Json }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportTradeHistoryRequest {
+    #[serde(flatten)]
+    filter: MySwapsFilter,
+    #[serde(default)]
+    format: TradeHistoryFormat,
+}
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Serialize)]
+pub enum TradeSide {
+    Maker,
+    Taker,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TradeFeeRecord {
+    pub coin: String,
+    pub amount: BigDecimal,
+}
+
+impl From<SavedTradeFee> for TradeFeeRecord {
+    fn from(fee: SavedTradeFee) -> Self {
+        TradeFeeRecord {
+            coin: fee.coin,
+            amount: fee.amount,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TradeRecord {
+    pub uuid: Uuid,
+    pub swap_type: TradeSide,
+    /// The swap start time in seconds.
+    pub started_at: u64,
+    /// The time of the last swap event in seconds.
+    /// The swaps v2 events have no timestamps, so it's equal to `started_at` for them.
+    pub finished_at: u64,
+    pub my_coin: String,
+    pub my_amount: BigDecimal,
+    pub other_coin: String,
+    pub other_amount: BigDecimal,
+    pub my_coin_usd_price: Option<BigDecimal>,
+    pub other_coin_usd_price: Option<BigDecimal>,
+    /// The dex fee is paid by the taker only.
+    /// It's unknown for the taker swaps started before the dex fee was saved in the swap data.
+    pub dex_fee: Option<TradeFeeRecord>,
+    /// The miner fees estimated at the swap start.
+    pub miner_fees: Vec<TradeFeeRecord>,
+}
+
+impl TradeRecord {
+    fn usd_price(&self, coin: &str) -> Option<MmNumber> {
+        if coin == self.my_coin {
+            self.my_coin_usd_price.clone().map(MmNumber::from)
+        } else if coin == self.other_coin {
+            self.other_coin_usd_price.clone().map(MmNumber::from)
+        } else {
+            None
+        }
+    }
+
+    fn to_csv_row(&self) -> String {
+        let usd_value = |amount: &BigDecimal, price: &Option<BigDecimal>| {
+            price
+                .as_ref()
+                .map(|price| (amount * price).to_string())
+                .unwrap_or_default()
+        };
+        let optional = |value: &Option<BigDecimal>| value.as_ref().map(ToString::to_string).unwrap_or_default();
+        let miner_fees = self
+            .miner_fees
+            .iter()
+            .map(|fee| format!("{} {}", fee.amount, fee.coin))
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let fields = [
+            self.uuid.to_string(),
+            self.swap_type.to_string(),
+            self.started_at.to_string(),
+            self.finished_at.to_string(),
+            self.my_coin.clone(),
+            self.my_amount.to_string(),
+            self.other_coin.clone(),
+            self.other_amount.to_string(),
+            optional(&self.my_coin_usd_price),
+            optional(&self.other_coin_usd_price),
+            usd_value(&self.my_amount, &self.my_coin_usd_price),
+            usd_value(&self.other_amount, &self.other_coin_usd_price),
+            self.dex_fee.as_ref().map(|fee| fee.coin.clone()).unwrap_or_default(),
+            self.dex_fee
+                .as_ref()
+                .map(|fee| fee.amount.to_string())
+                .unwrap_or_default(),
+            miner_fees,
+        ];
+        fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Quotes the CSV field if it contains a delimiter, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains(|c: char| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn trades_to_csv(trades: &[TradeRecord]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for trade in trades {
+        csv.push_str(&trade.to_csv_row());
+        csv.push('\n');
+    }
+    csv
+}
+
+/// The realized PnL of the coin over the exported trades.
+#[derive(Debug, Serialize)]
+pub struct CoinRealizedPnl {
+    pub coin: String,
+    pub acquired: BigDecimal,
+    pub acquired_value_usd: BigDecimal,
+    pub disposed: BigDecimal,
+    pub disposed_value_usd: BigDecimal,
+    /// The volume disposed of without a cost basis within the exported trades, it isn't included into the PnL.
+    pub disposed_without_cost_basis: BigDecimal,
+    pub dex_fees: BigDecimal,
+    pub miner_fees: BigDecimal,
+    /// The USD value of the fees that could be valued at the trade time.
+    pub fees_value_usd: BigDecimal,
+    pub realized_pnl_usd: BigDecimal,
+}
+
+#[derive(Default)]
+struct CoinPnlAccumulator {
+    position: MmNumber,
+    position_cost: MmNumber,
+    acquired: MmNumber,
+    acquired_value: MmNumber,
+    disposed: MmNumber,
+    disposed_value: MmNumber,
+    disposed_without_cost_basis: MmNumber,
+    dex_fees: MmNumber,
+    miner_fees: MmNumber,
+    fees_value: MmNumber,
+    realized_pnl: MmNumber,
+}
+
+impl CoinPnlAccumulator {
+    fn acquire(&mut self, amount: &MmNumber, price: &MmNumber) {
+        let value = amount * price;
+        self.position += amount;
+        self.position_cost += &value;
+        self.acquired += amount;
+        self.acquired_value += &value;
+    }
+
+    fn dispose(&mut self, amount: &MmNumber, price: &MmNumber) {
+        let value = amount * price;
+        let matched = min(amount.clone(), self.position.clone());
+        if !matched.is_zero() {
+            let matched_cost = &(&self.position_cost * &matched) / &self.position;
+            self.realized_pnl += &(&(&matched * price) - &matched_cost);
+            self.position_cost = &self.position_cost - &matched_cost;
+            self.position = &self.position - &matched;
+        }
+        self.disposed_without_cost_basis += &(amount - &matched);
+        self.disposed += amount;
+        self.disposed_value += &value;
+    }
+
+    fn pay_fee(&mut self, amount: &MmNumber, price: Option<&MmNumber>, is_dex_fee: bool) {
+        if is_dex_fee {
+            self.dex_fees += amount;
+        } else {
+            self.miner_fees += amount;
+        }
+        if let Some(price) = price {
+            let value = amount * price;
+            self.fees_value += &value;
+            self.realized_pnl = &self.realized_pnl - &value;
+        }
+    }
+
+    fn into_realized_pnl(self, coin: String) -> CoinRealizedPnl {
+        CoinRealizedPnl {
+            coin,
+            acquired: self.acquired.to_decimal(),
+            acquired_value_usd: self.acquired_value.to_decimal(),
+            disposed: self.disposed.to_decimal(),
+            disposed_value_usd: self.disposed_value.to_decimal(),
+            disposed_without_cost_basis: self.disposed_without_cost_basis.to_decimal(),
+            dex_fees: self.dex_fees.to_decimal(),
+            miner_fees: self.miner_fees.to_decimal(),
+            fees_value_usd: self.fees_value.to_decimal(),
+            realized_pnl_usd: self.realized_pnl.to_decimal(),
+        }
+    }
+}
+
+/// Calculates the realized PnL of every coin traded by the given trades sorted by `started_at`.
+/// Returns the PnL per coin and the uuids of the trades that can't be valued due to missing USD prices.
+fn calc_realized_pnl(trades: &[TradeRecord]) -> (Vec<CoinRealizedPnl>, Vec<Uuid>) {
+    let mut accumulators: BTreeMap<String, CoinPnlAccumulator> = BTreeMap::new();
+    let mut unpriced = Vec::new();
+
+    for trade in trades {
+        let (my_price, other_price) = match (trade.usd_price(&trade.my_coin), trade.usd_price(&trade.other_coin)) {
+            (Some(my_price), Some(other_price)) => (my_price, other_price),
+            _ => {
+                unpriced.push(trade.uuid);
+                continue;
+            },
+        };
+
+        accumulators
+            .entry(trade.my_coin.clone())
+            .or_default()
+            .dispose(&MmNumber::from(trade.my_amount.clone()), &my_price);
+        accumulators
+            .entry(trade.other_coin.clone())
+            .or_default()
+            .acquire(&MmNumber::from(trade.other_amount.clone()), &other_price);
+
+        let fees = trade
+            .dex_fee
+            .iter()
+            .map(|fee| (fee, true))
+            .chain(trade.miner_fees.iter().map(|fee| (fee, false)));
+        for (fee, is_dex_fee) in fees {
+            let price = trade.usd_price(&fee.coin);
+            accumulators.entry(fee.coin.clone()).or_default().pay_fee(
+                &MmNumber::from(fee.amount.clone()),
+                price.as_ref(),
+                is_dex_fee,
+            );
+        }
+    }
+
+    let pnl = accumulators
+        .into_iter()
+        .map(|(coin, accumulator)| accumulator.into_realized_pnl(coin))
+        .collect();
+    (pnl, unpriced)
+}
+
+fn maker_trade_record(swap: &MakerSavedSwap) -> Option<TradeRecord> {
+    let data = match &swap.events.first()?.event {
+        MakerSwapEvent::Started(data) => data,
+        _ => return None,
+    };
+    let miner_fees = [&data.maker_payment_trade_fee, &data.taker_payment_spend_trade_fee]
+        .iter()
+        .filter_map(|fee| fee.clone().map(TradeFeeRecord::from))
+        .collect();
+
+    Some(TradeRecord {
+        uuid: swap.uuid,
+        swap_type: TradeSide::Maker,
+        started_at: data.started_at,
+        finished_at: swap
+            .events
+            .last()
+            .map(|event| event.timestamp / 1000)
+            .unwrap_or_default(),
+        my_coin: data.maker_coin.clone(),
+        my_amount: data.maker_amount.clone(),
+        other_coin: data.taker_coin.clone(),
+        other_amount: data.taker_amount.clone(),
+        my_coin_usd_price: swap.maker_coin_usd_price.clone(),
+        other_coin_usd_price: swap.taker_coin_usd_price.clone(),
+        dex_fee: None,
+        miner_fees,
+    })
+}
+
+fn taker_trade_record(swap: &TakerSavedSwap) -> Option<TradeRecord> {
+    let data = match &swap.events.first()?.event {
+        TakerSwapEvent::Started(data) => data,
+        _ => return None,
+    };
+    let miner_fees = [
+        &data.fee_to_send_taker_fee,
+        &data.taker_payment_trade_fee,
+        &data.maker_payment_spend_trade_fee,
+    ]
+    .iter()
+    .filter_map(|fee| fee.clone().map(TradeFeeRecord::from))
+    .collect();
+
+    // The dex fee depends on the coin settings at the swap start, so only the saved value is exported.
+    let dex_fee = data.dex_fee.clone().map(|amount| TradeFeeRecord {
+        coin: data.taker_coin.clone(),
+        amount,
+    });
+
+    Some(TradeRecord {
+        uuid: swap.uuid,
+        swap_type: TradeSide::Taker,
+        started_at: data.started_at,
+        finished_at: swap
+            .events
+            .last()
+            .map(|event| event.timestamp / 1000)
+            .unwrap_or_default(),
+        my_coin: data.taker_coin.clone(),
+        my_amount: data.taker_amount.clone(),
+        other_coin: data.maker_coin.clone(),
+        other_amount: data.maker_amount.clone(),
+        my_coin_usd_price: swap.taker_coin_usd_price.clone(),
+        other_coin_usd_price: swap.maker_coin_usd_price.clone(),
+        dex_fee,
+        miner_fees,
+    })
+}
+
+/// Returns `None` if the swap v2 isn't completed successfully.
+fn swap_v2_trade_record(uuid: Uuid, swap_type: u8, data: &MySwapV2Data) -> Result<Option<TradeRecord>, String> {
+    let parse_events_err = |e: serde_json::Error| format!("Error parsing the swap '{}' events: {}", uuid, e);
+    let is_completed = if swap_type == MAKER_SWAP_V2_TYPE {
+        let events: Vec<MakerSwapV2Event> = serde_json::from_str(&data.events_json).map_err(parse_events_err)?;
+        matches!(events.last(), Some(MakerSwapV2Event::Completed))
+    } else {
+        let events: Vec<TakerSwapV2Event> = serde_json::from_str(&data.events_json).map_err(parse_events_err)?;
+        matches!(events.last(), Some(TakerSwapV2Event::Completed))
+    };
+    if !is_completed {
+        return Ok(None);
+    }
+
+    let params = SwapV2Params::try_from(data)?;
+    // The premium is sent to the maker along with the taker volume.
+    let taker_spent = &params.taker_volume + &params.premium;
+    let (swap_type, my_amount, other_amount, dex_fee) = if swap_type == MAKER_SWAP_V2_TYPE {
+        (TradeSide::Maker, params.maker_volume, taker_spent, None)
+    } else {
+        let dex_fee = TradeFeeRecord {
+            coin: data.my_coin.clone(),
+            amount: params.dex_fee.to_decimal(),
+        };
+        (TradeSide::Taker, taker_spent, params.maker_volume, Some(dex_fee))
+    };
+
+    Ok(Some(TradeRecord {
+        uuid,
+        swap_type,
+        started_at: params.started_at,
+        finished_at: params.started_at,
+        my_coin: data.my_coin.clone(),
+        my_amount: my_amount.to_decimal(),
+        other_coin: data.other_coin.clone(),
+        other_amount: other_amount.to_decimal(),
+        my_coin_usd_price: None,
+        other_coin_usd_price: None,
+        dex_fee,
+        miner_fees: Vec::new(),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportTradeHistoryResponse {
+    pub format: TradeHistoryFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trades: Option<Vec<TradeRecord>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub csv: Option<String>,
+    pub realized_pnl: Vec<CoinRealizedPnl>,
+    /// The trades excluded from the PnL since the USD prices of the coins weren't fetched when the swap was finished.
+    pub unpriced_trades: Vec<Uuid>,
+    pub found_records: usize,
+}
+
+/// Exports the successfully finished swaps matching the filter with the fee accounting and the realized PnL per coin.
+pub async fn export_trade_history(
+    ctx: MmArc,
+    req: ExportTradeHistoryRequest,
+) -> Result<ExportTradeHistoryResponse, MmError<TradeHistoryExportError>> {
+    if let (Some(from), Some(to)) = (req.filter.from_timestamp, req.filter.to_timestamp) {
+        if from > to {
+            return MmError::err(TradeHistoryExportError::InvalidTimestampRange);
+        }
+    }
+
+    let db_result = MySwapsStorage::new(ctx.clone())
+        .my_recent_swaps_with_filters(&req.filter, None)
+        .await?;
+
+    let mut trades = Vec::with_capacity(db_result.uuids.len());
+    for uuid in db_result.uuids {
+        let swap = SavedSwap::load_my_swap_from_db(&ctx, uuid)
+            .await
+            .map_to_mm(|e| TradeHistoryExportError::StorageError(format!("Error loading swap '{}': {}", uuid, e)))?;
+        let swap = match swap {
+            Some(swap) => swap,
+            None => {
+                let swap_type = load_swap_v2_type(&ctx, uuid)
+                    .await?
+                    .or_mm_err(|| TradeHistoryExportError::StorageError(format!("Swap '{}' is not found", uuid)))?;
+                let data = load_swap_v2_data(&ctx, uuid).await?;
+                match swap_v2_trade_record(uuid, swap_type, &data).map_to_mm(TradeHistoryExportError::InternalError)? {
+                    Some(record) => trades.push(record),
+                    None => debug!("Swap v2 '{}' isn't completed, skipping", uuid),
+                }
+                continue;
+            },
+        };
+        if !swap.is_finished_and_success() {
+            continue;
+        }
+        let record = match &swap {
+            SavedSwap::Maker(maker) => maker_trade_record(maker),
+            SavedSwap::Taker(taker) => taker_trade_record(taker),
+        };
+        let record = record.or_mm_err(|| {
+            TradeHistoryExportError::InternalError(format!("Swap '{}' doesn't start with the Started event", uuid))
+        })?;
+        trades.push(record);
+    }
+    trades.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.uuid.cmp(&b.uuid)));
+
+    let (realized_pnl, unpriced_trades) = calc_realized_pnl(&trades);
+    let found_records = trades.len();
+    let (trades, csv) = match req.format {
+        TradeHistoryFormat::Json => (Some(trades), None),
+        TradeHistoryFormat::Csv => (None, Some(trades_to_csv(&trades))),
+    };
+
+    Ok(ExportTradeHistoryResponse {
+        format: req.format,
+        trades,
+        csv,
+        realized_pnl,
+        unpriced_trades,
+        found_records,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mm2::lp_swap::taker_swap::{TakerSavedEvent, TakerSwapData};
+
+    fn trade(
+        uuid: u128,
+        my_coin: &str,
+        my_amount: &str,
+        other_coin: &str,
+        other_amount: &str,
+        prices: Option<(&str, &str)>,
+    ) -> TradeRecord {
+        TradeRecord {
+            uuid: Uuid::from_u128(uuid),
+            swap_type: TradeSide::Taker,
+            started_at: uuid as u64,
+            finished_at: uuid as u64,
+            my_coin: my_coin.to_owned(),
+            my_amount: my_amount.parse().unwrap(),
+            other_coin: other_coin.to_owned(),
+            other_amount: other_amount.parse().unwrap(),
+            my_coin_usd_price: prices.map(|(my, _)| my.parse().unwrap()),
+            other_coin_usd_price: prices.map(|(_, other)| other.parse().unwrap()),
+            dex_fee: None,
+            miner_fees: Vec::new(),
+        }
+    }
+
+    fn decimal(value: &str) -> BigDecimal { value.parse().unwrap() }
+
+    #[test]
+    fn test_calc_realized_pnl() {
+        // Buy 10 RICK for 20 MORTY when RICK costs 2$ and MORTY 1$.
+        let buy = trade(1, "MORTY", "20", "RICK", "10", Some(("1", "2")));
+        // Sell 4 RICK for 12 MORTY when RICK costs 3$ and MORTY 1$.
+        let mut sell = trade(2, "RICK", "4", "MORTY", "12", Some(("3", "1")));
+        sell.dex_fee = Some(TradeFeeRecord {
+            coin: "RICK".to_owned(),
+            amount: decimal("0.1"),
+        });
+        sell.miner_fees = vec![TradeFeeRecord {
+            coin: "ETH".to_owned(),
+            amount: decimal("0.01"),
+        }];
+        let unpriced = trade(3, "RICK", "1", "MORTY", "3", None);
+
+        let (pnl, unpriced_trades) = calc_realized_pnl(&[buy, sell, unpriced]);
+        assert_eq!(unpriced_trades, vec![Uuid::from_u128(3)]);
+
+        let coins: Vec<_> = pnl.iter().map(|pnl| pnl.coin.as_str()).collect();
+        assert_eq!(coins, vec!["ETH", "MORTY", "RICK"]);
+
+        // ETH price is unknown, so the miner fee isn't valued.
+        assert_eq!(pnl[0].miner_fees, decimal("0.01"));
+        assert_eq!(pnl[0].fees_value_usd, decimal("0"));
+
+        // 20 MORTY are disposed without a cost basis, 12 MORTY are acquired.
+        assert_eq!(pnl[1].disposed_without_cost_basis, decimal("20"));
+        assert_eq!(pnl[1].acquired, decimal("12"));
+        assert_eq!(pnl[1].realized_pnl_usd, decimal("0"));
+
+        // 4 RICK bought for 2$ are sold for 3$, the dex fee costs 0.1 * 3$.
+        let rick = &pnl[2];
+        assert_eq!(rick.acquired, decimal("10"));
+        assert_eq!(rick.disposed, decimal("4"));
+        assert_eq!(rick.disposed_value_usd, decimal("12"));
+        assert_eq!(rick.dex_fees, decimal("0.1"));
+        assert_eq!(rick.fees_value_usd, decimal("0.3"));
+        assert_eq!(rick.realized_pnl_usd, decimal("3.7"));
+    }
+
+    #[test]
+    fn test_trades_to_csv() {
+        let mut trade = trade(1, "MORTY", "20", "RICK", "10", Some(("1", "2")));
+        trade.miner_fees = vec![
+            TradeFeeRecord {
+                coin: "MORTY".to_owned(),
+                amount: decimal("0.0001"),
+            },
+            TradeFeeRecord {
+                coin: "RICK".to_owned(),
+                amount: decimal("0.0002"),
+            },
+        ];
+        let csv = trades_to_csv(&[trade]);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some(
+                "00000000-0000-0000-0000-000000000001,Taker,1,1,MORTY,20,RICK,10,1,2,20,20,,,0.0001 MORTY;0.0002 RICK"
+            )
+        );
+        assert_eq!(lines.next(), None);
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+
+    fn taker_saved_swap(dex_fee: Option<&str>) -> TakerSavedSwap {
+        let data = TakerSwapData {
+            taker_coin: "RICK".to_owned(),
+            maker_coin: "MORTY".to_owned(),
+            maker_amount: decimal("1"),
+            taker_amount: decimal("2"),
+            started_at: 10,
+            dex_fee: dex_fee.map(decimal),
+            ..Default::default()
+        };
+        TakerSavedSwap {
+            uuid: Uuid::from_u128(1),
+            my_order_uuid: None,
+            events: vec![TakerSavedEvent {
+                timestamp: 20_000,
+                event: TakerSwapEvent::Started(data),
+            }],
+            maker_amount: None,
+            maker_coin: None,
+            maker_coin_usd_price: None,
+            taker_amount: None,
+            taker_coin: None,
+            taker_coin_usd_price: None,
+            gui: None,
+            mm_version: None,
+            success_events: Vec::new(),
+            error_events: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_taker_trade_record_saved_dex_fee() {
+        let record = taker_trade_record(&taker_saved_swap(Some("0.0031"))).unwrap();
+        assert_eq!(record.started_at, 10);
+        assert_eq!(record.finished_at, 20);
+        assert_eq!(
+            record.dex_fee,
+            Some(TradeFeeRecord {
+                coin: "RICK".to_owned(),
+                amount: decimal("0.0031"),
+            })
+        );
+
+        // The dex fee isn't recalculated for the swaps started before it was saved.
+        let record = taker_trade_record(&taker_saved_swap(None)).unwrap();
+        assert_eq!(record.dex_fee, None);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod native_tests {
+    use super::*;
+    use crate::mm2::database::my_swaps::insert_new_swap;
+    use crate::mm2::lp_swap::swap_v2_common::tests::{store_test_swap_v2, swap_v2_test_ctx};
+    use crate::mm2::lp_swap::TAKER_SWAP_V2_TYPE;
+    use common::block_on;
+    use serde_json::json;
+
+    fn export_request(format: &str) -> ExportTradeHistoryRequest {
+        serde_json::from_value(json!({ "format": format })).unwrap()
+    }
+
+    #[test]
+    fn test_export_trade_history_swaps_v2() {
+        let ctx = swap_v2_test_ctx();
+        let maker_uuid = Uuid::from_u128(1);
+        let taker_uuid = Uuid::from_u128(2);
+        store_test_swap_v2(&ctx, maker_uuid, MAKER_SWAP_V2_TYPE, &[json!("Completed")]);
+        store_test_swap_v2(&ctx, taker_uuid, TAKER_SWAP_V2_TYPE, &[json!("Completed")]);
+        // Neither the unfinished nor the aborted swaps are exported.
+        store_test_swap_v2(&ctx, Uuid::from_u128(3), TAKER_SWAP_V2_TYPE, &[]);
+        store_test_swap_v2(&ctx, Uuid::from_u128(4), MAKER_SWAP_V2_TYPE, &[
+            json!({"Aborted": {"reason": "ReceivedInvalidTakerNegotiation"}}),
+        ]);
+
+        let response = block_on(export_trade_history(ctx.clone(), export_request("json"))).unwrap();
+        assert_eq!(response.found_records, 2);
+        let trades = response.trades.unwrap();
+
+        let maker = &trades[0];
+        assert_eq!(maker.uuid, maker_uuid);
+        assert_eq!(maker.swap_type, TradeSide::Maker);
+        assert_eq!(maker.started_at, 1_700_000_000);
+        assert_eq!(maker.my_amount, decimal("1"));
+        assert_eq!(maker.other_amount, decimal("2"));
+        assert_eq!(maker.dex_fee, None);
+
+        let taker = &trades[1];
+        assert_eq!(taker.uuid, taker_uuid);
+        assert_eq!(taker.swap_type, TradeSide::Taker);
+        assert_eq!(taker.my_coin, "RICK");
+        assert_eq!(taker.my_amount, decimal("2"));
+        assert_eq!(taker.other_amount, decimal("1"));
+        assert_eq!(
+            taker.dex_fee,
+            Some(TradeFeeRecord {
+                coin: "RICK".to_owned(),
+                amount: MmNumber::from_fraction_string("1/777").unwrap().to_decimal(),
+            })
+        );
+
+        // The swaps v2 have no USD prices.
+        assert_eq!(response.unpriced_trades, vec![maker_uuid, taker_uuid]);
+        assert!(response.realized_pnl.is_empty());
+
+        let response = block_on(export_trade_history(ctx, export_request("csv"))).unwrap();
+        assert!(response.trades.is_none());
+        let csv = response.csv.unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("00000000-0000-0000-0000-000000000001,Maker,1700000000,1700000000,RICK,1,MORTY,2,,,,,,,")
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("00000000-0000-0000-0000-000000000002,Taker,"));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn test_export_trade_history_missing_swap() {
+        let ctx = swap_v2_test_ctx();
+        let uuid = Uuid::from_u128(1);
+        insert_new_swap(&ctx, "RICK", "MORTY", &uuid.to_string(), "1700000000").unwrap();
+
+        let err = block_on(export_trade_history(ctx, export_request("json"))).unwrap_err();
+        assert!(matches!(err.get_inner(), TradeHistoryExportError::StorageError(_)));
+    }
+
+    #[test]
+    fn test_export_trade_history_invalid_range() {
+        let ctx = swap_v2_test_ctx();
+        let req = serde_json::from_value(json!({ "from_timestamp": 2, "to_timestamp": 1 })).unwrap();
+
+        let err = block_on(export_trade_history(ctx, req)).unwrap_err();
+        assert!(matches!(
+            err.get_inner(),
+            TradeHistoryExportError::InvalidTimestampRange
+        ));
+    }
+}
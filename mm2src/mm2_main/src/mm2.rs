@@ -58,6 +58,7 @@ use mm2_err_handle::prelude::*;
 pub mod database;
 
 #[path = "lp_dispatcher.rs"] pub mod lp_dispatcher;
+#[path = "lp_event_streaming.rs"] mod lp_event_streaming;
 #[path = "lp_message_service.rs"] pub mod lp_message_service;
 #[path = "lp_network.rs"] pub mod lp_network;
 #[path = "lp_ordermatch.rs"] pub mod lp_ordermatch;
@@ -132,6 +133,10 @@ pub async fn lp_main(
         }
     }
 
+    try_s!(rpc::validate_rpc_permissions_conf(&conf));
+    try_s!(rpc::validate_rate_limits_conf(&conf));
+    try_s!(rpc::validate_rpc_methods());
+
     #[cfg(feature = "custom-swap-locktime")]
     initialize_payment_locktime(&conf);
 
@@ -178,6 +183,15 @@ Some (but not all) of the JSON configuration parameters (* - required):
   rpcip          ..  IP address to bind to for RPC server. Overrides the 127.0.0.1 default
   rpc_password   ..  RPC password used to authorize non-public RPC calls
                      MM generates password from passphrase if this field is not set
+  rpc_api_keys   ..  Additional RPC keys allowed to call only the listed methods, e.g.
+                     [{"name": "dashboard", "key": "...", "allowed_methods": ["my_balance", "group:read_only"]}].
+                     A method can also be a `prefix*` pattern.
+  rpc_method_groups .. Named lists of the methods to be referenced as `group:<name>` in `rpc_api_keys`,
+                     e.g. {"read_only": ["orderbook", "my_recent_swaps", "swap_v2::*"]}.
+  rpc_rate_limits .. Token-bucket request rate limits per API key or IP, e.g.
+                     {"default": {"capacity": 50, "refill_per_sec": 10},
+                      "rules": [{"methods": ["my_tx_history", "group:heavy"], "capacity": 5, "refill_per_sec": 0.5}]}.
+  rpc_ban_duration_secs .. How long an IP stays banned after too many invalid userpass attempts. Defaults to 3600.
   rpc_local_only ..  MM forbids some RPC requests from not loopback (localhost) IPs as additional security measure.
                      Defaults to `true`, set `false` to disable. `Use with caution`.
   rpcport        ..  If > 1000 overrides the 7783 default.
@@ -35,6 +35,9 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
 
     let request = TakerRequest {
@@ -73,6 +76,9 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
 
     let request = TakerRequest {
@@ -111,6 +117,9 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
 
     let request = TakerRequest {
@@ -149,6 +158,9 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
 
     let request = TakerRequest {
@@ -187,6 +199,9 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
 
     let request = TakerRequest {
@@ -225,6 +240,9 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
 
     let request = TakerRequest {
@@ -265,6 +283,9 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
     let request = TakerRequest {
         base: "KMD".to_owned(),
@@ -305,6 +326,9 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
     let request = TakerRequest {
         base: "REL".to_owned(),
@@ -376,6 +400,9 @@ fn test_maker_order_available_amount() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
     maker.matches.insert(new_uuid(), MakerMatch {
         request: TakerRequest {
@@ -447,6 +474,62 @@ fn test_maker_order_available_amount() {
     assert_eq!(MmNumber::from(expected), actual);
 }
 
+#[test]
+fn test_iceberg_maker_order_visible_amount() {
+    let mut maker = MakerOrder {
+        base: "BASE".into(),
+        rel: "REL".into(),
+        created_at: now_ms(),
+        updated_at: Some(now_ms()),
+        max_base_vol: 10.into(),
+        min_base_vol: 1.into(),
+        price: 1.into(),
+        matches: HashMap::new(),
+        started_swaps: Vec::new(),
+        uuid: new_uuid(),
+        conf_settings: None,
+        changes_history: None,
+        save_in_history: false,
+        base_orderbook_ticker: None,
+        rel_orderbook_ticker: None,
+        p2p_privkey: None,
+        expires_at: None,
+        visible_volume: Some(3.into()),
+        min_taker_reputation: None,
+    };
+    assert_eq!(maker.visible_amount(), 3.into());
+    assert_eq!(maker.hidden_amount(), 7.into());
+
+    let mut request = TakerRequest {
+        base: "BASE".into(),
+        rel: "REL".into(),
+        uuid: new_uuid(),
+        dest_pub_key: H256Json::default(),
+        sender_pubkey: H256Json::default(),
+        base_amount: 4.into(),
+        rel_amount: 4.into(),
+        action: TakerAction::Buy,
+        match_by: MatchBy::Any,
+        conf_settings: None,
+        base_protocol_info: None,
+        rel_protocol_info: None,
+    };
+    // The hidden volume can't be matched.
+    assert_eq!(maker.match_with_request(&request), OrderMatchResult::NotMatched);
+
+    request.base_amount = 3.into();
+    request.rel_amount = 3.into();
+    assert_eq!(
+        maker.match_with_request(&request),
+        OrderMatchResult::Matched((3.into(), 3.into()))
+    );
+
+    // The visible volume is limited by the available amount when the hidden remainder is exhausted.
+    maker.max_base_vol = 2.into();
+    assert_eq!(maker.visible_amount(), 2.into());
+    assert_eq!(maker.hidden_amount(), 0.into());
+}
+
 #[test]
 fn test_taker_match_reserved() {
     let uuid = new_uuid();
@@ -477,6 +560,7 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -521,6 +605,7 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -565,6 +650,7 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -609,6 +695,7 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -653,6 +740,7 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -697,6 +785,7 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -741,6 +830,7 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -785,6 +875,7 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -829,6 +920,7 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -876,6 +968,7 @@ fn test_taker_order_cancellable() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     assert!(order.is_cancellable());
@@ -906,6 +999,7 @@ fn test_taker_order_cancellable() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     order.matches.insert(new_uuid(), TakerMatch {
@@ -963,6 +1057,9 @@ fn prepare_for_cancel_by(ctx: &MmArc) -> mpsc::Receiver<AdexBehaviourCmd> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            expires_at: None,
+            visible_volume: None,
+            min_taker_reputation: None,
         },
         None,
     );
@@ -985,6 +1082,9 @@ fn prepare_for_cancel_by(ctx: &MmArc) -> mpsc::Receiver<AdexBehaviourCmd> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            expires_at: None,
+            visible_volume: None,
+            min_taker_reputation: None,
         },
         None,
     );
@@ -1007,6 +1107,9 @@ fn prepare_for_cancel_by(ctx: &MmArc) -> mpsc::Receiver<AdexBehaviourCmd> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            expires_at: None,
+            visible_volume: None,
+            min_taker_reputation: None,
         },
         None,
     );
@@ -1034,6 +1137,7 @@ fn prepare_for_cancel_by(ctx: &MmArc) -> mpsc::Receiver<AdexBehaviourCmd> {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     });
     rx
 }
@@ -1099,6 +1203,44 @@ fn test_cancel_by_all() {
     assert!(cancelled.contains(&Uuid::from_bytes([3; 16])));
 }
 
+#[test]
+fn test_batch_cancel() {
+    let ctx = mm_ctx_with_iguana(None);
+    let rx = prepare_for_cancel_by(&ctx);
+
+    let connection = Connection::open_in_memory().unwrap();
+    let _ = ctx.sqlite_connection.pin(Arc::new(Mutex::new(connection)));
+
+    delete_my_maker_order.mock_safe(|_, _, _| MockResult::Return(Box::new(futures01::future::ok(()))));
+    delete_my_taker_order.mock_safe(|_, _, _| MockResult::Return(Box::new(futures01::future::ok(()))));
+
+    let maker_uuid = Uuid::from_bytes([0; 16]);
+    let taker_uuid = Uuid::from_bytes([3; 16]);
+    let unknown_uuid = Uuid::from_bytes([4; 16]);
+    let req = json::from_value(json!({ "uuids": [maker_uuid, taker_uuid, unknown_uuid] })).unwrap();
+    let response = block_on(batch_cancel(ctx.clone(), req)).unwrap();
+    block_on(rx.take(1).collect::<Vec<_>>());
+
+    let expected = json!({
+        "results": [
+            { "uuid": maker_uuid, "cancelled": true },
+            { "uuid": taker_uuid, "cancelled": true },
+            {
+                "uuid": unknown_uuid,
+                "cancelled": false,
+                "error": { "error_type": "UUIDNotFound", "error_data": { "uuid": unknown_uuid } },
+            },
+        ]
+    });
+    assert_eq!(json::to_value(response).unwrap(), expected);
+
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).unwrap();
+    let maker_orders_ctx = ordermatch_ctx.maker_orders_ctx.lock();
+    assert!(maker_orders_ctx.get_order(&maker_uuid).is_none());
+    assert!(maker_orders_ctx.get_order(&Uuid::from_bytes([1; 16])).is_some());
+    assert!(maker_orders_ctx.get_order(&Uuid::from_bytes([2; 16])).is_some());
+}
+
 #[test]
 // https://github.com/KomodoPlatform/atomicDEX-API/issues/607
 fn test_taker_order_match_by() {
@@ -1132,6 +1274,7 @@ fn test_taker_order_match_by() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        min_maker_reputation: None,
     };
 
     let reserved = MakerReserved {
@@ -1186,6 +1329,9 @@ fn test_maker_order_was_updated() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
     let mut update_msg = MakerOrderUpdated::new(maker_order.uuid);
     update_msg.with_new_price(BigRational::from_integer(2.into()));
@@ -1495,6 +1641,30 @@ fn test_choose_taker_confs_settings_buy_action() {
     assert_eq!(settings.maker_coin_confs, 2);
 }
 
+#[test]
+fn test_good_till_time_taker_order_to_maker() {
+    let coin = TestCoin::default().into();
+    let expires_at = now_sec() + 100;
+    let mut taker_order = TakerOrderBuilder::new(&coin, &coin)
+        .with_base_amount(10.into())
+        .with_rel_amount(20.into())
+        .with_action(TakerAction::Buy)
+        .build_unchecked();
+    taker_order.order_type = OrderType::GoodTillTime { expires_at };
+
+    let maker_order: MakerOrder = taker_order.into();
+    assert_eq!(maker_order.expires_at, Some(expires_at));
+    assert!(!maker_order.is_expired(expires_at - 1));
+    assert!(maker_order.is_expired(expires_at));
+
+    let maker_order = MakerOrderBuilder::new(&coin, &coin)
+        .with_max_base_vol(10.into())
+        .with_price(1.into())
+        .build_unchecked();
+    assert_eq!(maker_order.expires_at, None);
+    assert!(!maker_order.is_expired(now_sec()));
+}
+
 #[test]
 fn test_choose_taker_confs_settings_sell_action() {
     let coin = TestCoin::default().into();
@@ -3179,6 +3349,9 @@ fn test_maker_order_balance_loops() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
 
     let morty_order = MakerOrder {
@@ -3198,6 +3371,9 @@ fn test_maker_order_balance_loops() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
 
     assert!(!maker_orders_ctx.balance_loop_exists(rick_ticker));
@@ -3230,6 +3406,9 @@ fn test_maker_order_balance_loops() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        expires_at: None,
+        visible_volume: None,
+        min_taker_reputation: None,
     };
 
     maker_orders_ctx.add_order(ctx.weak(), rick_order_2.clone(), None);
@@ -3247,3 +3426,61 @@ fn test_maker_order_balance_loops() {
     assert!(!maker_orders_ctx.balance_loop_exists(morty_ticker));
     assert_eq!(*maker_orders_ctx.count_by_tickers.get(morty_ticker).unwrap(), 0);
 }
+
+#[test]
+fn test_counterparty_has_min_reputation() {
+    use crate::mm2::database::stats_swaps::insert_swap_outcomes_for_tests;
+    use mm2_test_helpers::for_tests::mm_ctx_with_custom_db;
+
+    let ctx = mm_ctx_with_custom_db();
+    let bad = H256Json([1; 32]);
+    let bad_pubkey = format!("02{}", hex::encode(bad.0));
+    let good = H256Json([2; 32]);
+    let good_pubkey = format!("03{}", hex::encode(good.0));
+    insert_swap_outcomes_for_tests(&ctx.sqlite_connection(), &[
+        (200, false, &good_pubkey, &bad_pubkey, false, Some(&bad_pubkey), true),
+        (250, false, &bad_pubkey, &good_pubkey, false, Some(&bad_pubkey), true),
+        (300, true, &good_pubkey, &bad_pubkey, false, None, true),
+        (400, true, &bad_pubkey, &good_pubkey, false, None, true),
+    ]);
+
+    let min_reputation = MmNumber::from("0.6");
+    // The order without the min reputation matches any counterparty.
+    assert!(block_on(counterparty_has_min_reputation(&ctx, &bad, None)));
+    assert!(!block_on(counterparty_has_min_reputation(
+        &ctx,
+        &bad,
+        Some(&min_reputation)
+    )));
+    assert!(block_on(counterparty_has_min_reputation(
+        &ctx,
+        &good,
+        Some(&min_reputation)
+    )));
+}
+
+#[test]
+fn test_sell_buy_request_min_maker_reputation() {
+    let req: SellBuyRequest = json::from_value(json!({
+        "base": "RICK",
+        "rel": "MORTY",
+        "price": "1",
+        "volume": "1",
+        "method": "buy",
+        "min_maker_reputation": "0.8",
+    }))
+    .unwrap();
+    assert_eq!(req.min_maker_reputation, Some(MmNumber::from("0.8")));
+
+    let coin = TestCoin::default().into();
+    let taker_order = TakerOrderBuilder::new(&coin, &coin)
+        .with_base_amount(req.volume)
+        .with_rel_amount(req.price)
+        .with_min_maker_reputation(req.min_maker_reputation)
+        .build_unchecked();
+    assert_eq!(taker_order.min_maker_reputation, Some(MmNumber::from("0.8")));
+
+    // The taker order converted to the maker one keeps requiring the counterparty reputation.
+    let maker_order = MakerOrder::from(taker_order);
+    assert_eq!(maker_order.min_taker_reputation, Some(MmNumber::from("0.8")));
+}
@@ -30,6 +30,7 @@ use http::request::Parts;
 use http::{Method, Request, Response, StatusCode};
 use lazy_static::lazy_static;
 use mm2_core::mm_ctx::MmArc;
+use mm2_core::rpc_registry::RpcHandlerError;
 use mm2_err_handle::prelude::*;
 use mm2_rpc::mm_protocol::{MmRpcBuilder, MmRpcResponse, MmRpcVersion};
 use regex::Regex;
@@ -38,9 +39,16 @@ use serde_json::{self as json, Value as Json};
 use std::borrow::Cow;
 use std::net::SocketAddr;
 
+pub use method_registry::{add_methods_registrar, validate_rpc_methods};
+pub use mm2_core::rpc_registry::{AuthLevel, LegacyHandler, MethodsRegistrar, MmRpcHandler, MmRpcHandlerFut,
+                                 RpcMethodRegistry};
+pub use rate_limiter::validate_rate_limits_conf;
+pub use rpc_permissions::validate_rpc_permissions_conf;
+
 cfg_native! {
     use hyper::{self, Body, Server};
     use mm2_net::sse_handler::{handle_sse, SSE_ENDPOINT};
+    use ws_handler::{handle_ws, WS_ENDPOINT};
 }
 
 #[path = "rpc/dispatcher/dispatcher.rs"] mod dispatcher;
@@ -49,30 +57,13 @@ mod dispatcher_legacy;
 #[path = "rpc/lp_commands/lp_commands.rs"] pub mod lp_commands;
 #[path = "rpc/lp_commands/lp_commands_legacy.rs"]
 pub mod lp_commands_legacy;
+#[path = "rpc/dispatcher/method_registry.rs"]
+mod method_registry;
 #[path = "rpc/rate_limiter.rs"] mod rate_limiter;
-
-/// Lists the RPC method not requiring the "userpass" authentication.  
-/// None is also public to skip auth and display proper error in case of method is missing
-const PUBLIC_METHODS: &[Option<&str>] = &[
-    // Sorted alphanumerically (on the first letter) for readability.
-    Some("fundvalue"),
-    Some("getprice"),
-    Some("getpeers"),
-    Some("getcoins"),
-    Some("help"),
-    Some("metrics"),
-    Some("notify"), // Manually checks the peer's public key.
-    Some("orderbook"),
-    Some("passphrase"), // Manually checks the "passphrase".
-    Some("pricearray"),
-    Some("psock"),
-    Some("statsdisp"),
-    Some("stats_swap_status"),
-    Some("tradesarray"),
-    Some("ticker"),
-    Some("version"),
-    None,
-];
+#[path = "rpc/rpc_permissions.rs"] mod rpc_permissions;
+#[cfg(not(target_arch = "wasm32"))]
+#[path = "rpc/ws_handler.rs"]
+mod ws_handler;
 
 pub type DispatcherResult<T> = Result<T, MmError<DispatcherError>>;
 
@@ -91,6 +82,14 @@ pub enum DispatcherError {
     UserpassIsNotSet,
     #[display(fmt = "Userpass is invalid! - {}", _0)]
     UserpassIsInvalid(RateLimitError),
+    #[display(fmt = "'{}' API key is not allowed to call '{}'", key_name, method)]
+    MethodIsNotAllowed { key_name: String, method: String },
+    #[display(
+        fmt = "Rate limit of '{}' is exceeded, retry in {} seconds",
+        method,
+        retry_after_secs
+    )]
+    RateLimitExceeded { method: String, retry_after_secs: u64 },
     #[display(fmt = "Error parsing mmrpc version: {}", _0)]
     InvalidMmRpcVersion(String),
 }
@@ -104,7 +103,9 @@ impl HttpStatusCode for DispatcherError {
             DispatcherError::LocalHostOnly
             | DispatcherError::UserpassIsNotSet
             | DispatcherError::UserpassIsInvalid(_)
+            | DispatcherError::MethodIsNotAllowed { .. }
             | DispatcherError::Banned => StatusCode::FORBIDDEN,
+            DispatcherError::RateLimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -113,6 +114,14 @@ impl From<serde_json::Error> for DispatcherError {
     fn from(e: serde_json::Error) -> Self { DispatcherError::InvalidRequest(e.to_string()) }
 }
 
+impl From<RpcHandlerError> for DispatcherError {
+    fn from(e: RpcHandlerError) -> Self {
+        match e {
+            RpcHandlerError::InvalidRequest(e) => DispatcherError::InvalidRequest(e),
+        }
+    }
+}
+
 #[allow(unused_macros)]
 macro_rules! unwrap_or_err_response {
     ($e:expr, $($args:tt)*) => {
@@ -368,6 +377,11 @@ pub extern "C" fn spawn_rpc(ctx_h: u32) {
                 return Ok::<_, Infallible>(res);
             }
 
+            if req.uri().path() == WS_ENDPOINT {
+                let res = handle_ws(req, ctx_h, remote_addr).await;
+                return Ok::<_, Infallible>(res);
+            }
+
             let res = rpc_service(req, ctx_h, remote_addr).await;
             Ok::<_, Infallible>(res)
         }))
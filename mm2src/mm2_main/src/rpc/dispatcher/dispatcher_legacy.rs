@@ -1,9 +1,11 @@
-use super::PUBLIC_METHODS;
+use super::method_registry::{record_rpc_call, rpc_methods};
 use common::HyRes;
 use futures::compat::Future01CompatExt;
 use futures::{Future as Future03, FutureExt, TryFutureExt};
 use http::Response;
+use instant::Instant;
 use mm2_core::mm_ctx::MmArc;
+use mm2_core::rpc_registry::{AuthLevel, RpcMethodRegistry};
 use serde_json::{self as json, Value as Json};
 use std::net::SocketAddr;
 
@@ -14,27 +16,31 @@ use crate::mm2::lp_ordermatch::{best_orders_rpc, buy, cancel_all_orders_rpc, can
 use crate::mm2::lp_swap::{active_swaps_rpc, all_swaps_uuids_by_filter, ban_pubkey_rpc, coins_needed_for_kick_start,
                           import_swaps, list_banned_pubkeys_rpc, max_taker_vol, my_recent_swaps_rpc, my_swap_status,
                           recover_funds_of_swap, stats_swap_status, unban_pubkeys_rpc};
-use crate::mm2::rpc::rate_limiter::{process_rate_limit, RateLimitContext};
+use crate::mm2::rpc::rate_limiter::{check_request_rate, process_rate_limit, RateLimitContext};
+use crate::mm2::rpc::rpc_permissions::{Authorization, RpcPermissionsContext};
 use coins::{convert_address, convert_utxo_address, get_enabled_coins, get_trade_fee, kmd_rewards_info, my_tx_history,
             send_raw_transaction, set_required_confirmations, set_requires_notarization, show_priv_key,
             validate_address};
 
-/// Result of `fn dispatcher`.
-pub enum DispatcherRes {
-    /// `fn dispatcher` has found a Rust handler for the RPC "method".
-    Match(HyRes),
-    /// No handler found by `fn dispatcher`. Returning the `Json` request in order for it to be handled elsewhere.
-    NoMatch(Json),
-}
-
 async fn auth(json: &Json, ctx: &MmArc, client: &SocketAddr) -> Result<(), String> {
-    if !PUBLIC_METHODS.contains(&json["method"].as_str()) {
+    if !is_public_method(json["method"].as_str()) {
         if !json["userpass"].is_string() {
             return Err("Userpass is not set!".to_string());
         }
 
-        if json["userpass"] != ctx.conf["rpc_password"] {
-            return Err(format!("{}", process_rate_limit(ctx, client).await));
+        if json["userpass"] == ctx.conf["rpc_password"] {
+            return Ok(());
+        }
+
+        let permissions_ctx = RpcPermissionsContext::from_ctx(ctx).unwrap();
+        let userpass = json["userpass"].as_str().unwrap_or_default();
+        let method = json["method"].as_str().unwrap_or_default();
+        match permissions_ctx.authorize(userpass, method) {
+            Authorization::Granted => (),
+            Authorization::MethodIsNotAllowed { key_name } => {
+                return Err(format!("'{}' API key is not allowed to call '{}'", key_name, method))
+            },
+            Authorization::InvalidUserpass => return Err(format!("{}", process_rate_limit(ctx, client).await)),
         }
     }
     Ok(())
@@ -46,76 +52,322 @@ fn hyres(handler: impl Future03<Output = Result<Response<Vec<u8>>, String>> + Se
     Box::new(handler.boxed().compat())
 }
 
-/// The dispatcher, with full control over the HTTP result and the way we run the `Future` producing it.
-///
-/// Invoked both directly from the HTTP endpoint handler below and in a delayed fashion from `lp_command_q_loop`.
+/// Registers all the legacy methods.
 ///
-/// Returns `None` if the requested "method" wasn't found among the ported RPC methods and has to be handled elsewhere.
-pub fn dispatcher(req: Json, ctx: MmArc) -> DispatcherRes {
-    let method = match req["method"].clone() {
-        Json::String(method) => method,
-        _ => return DispatcherRes::NoMatch(req),
-    };
-    DispatcherRes::Match(match &method[..] {
-        // Sorted alphanumerically (on the first latter) for readability.
-        // "autoprice" => lp_autoprice (ctx, req),
-        "active_swaps" => hyres(active_swaps_rpc(ctx, req)),
-        "all_swaps_uuids_by_filter" => hyres(all_swaps_uuids_by_filter(ctx, req)),
-        "ban_pubkey" => hyres(ban_pubkey_rpc(ctx, req)),
-        "best_orders" => hyres(best_orders_rpc(ctx, req)),
-        "buy" => hyres(buy(ctx, req)),
-        "cancel_all_orders" => hyres(cancel_all_orders_rpc(ctx, req)),
-        "cancel_order" => hyres(cancel_order_rpc(ctx, req)),
-        "coins_needed_for_kick_start" => hyres(coins_needed_for_kick_start(ctx)),
-        "convertaddress" => hyres(convert_address(ctx, req)),
-        "convert_utxo_address" => hyres(convert_utxo_address(ctx, req)),
-        "disable_coin" => hyres(disable_coin(ctx, req)),
-        "electrum" => hyres(electrum(ctx, req)),
-        "enable" => hyres(enable(ctx, req)),
-        "get_enabled_coins" => hyres(get_enabled_coins(ctx)),
-        "get_gossip_mesh" => hyres(get_gossip_mesh(ctx)),
-        "get_gossip_peer_topics" => hyres(get_gossip_peer_topics(ctx)),
-        "get_gossip_topic_peers" => hyres(get_gossip_topic_peers(ctx)),
-        "get_my_peer_id" => hyres(get_my_peer_id(ctx)),
-        "get_peers_info" => hyres(get_peers_info(ctx)),
-        "get_relay_mesh" => hyres(get_relay_mesh(ctx)),
-        "get_trade_fee" => hyres(get_trade_fee(ctx, req)),
-        // "fundvalue" => lp_fundvalue (ctx, req, false),
-        "help" => help(),
-        "import_swaps" => hyres(import_swaps(ctx, req)),
-        "kmd_rewards_info" => hyres(kmd_rewards_info(ctx)),
-        // "inventory" => inventory (ctx, req),
-        "list_banned_pubkeys" => hyres(list_banned_pubkeys_rpc(ctx)),
-        "max_taker_vol" => hyres(max_taker_vol(ctx, req)),
-        "metrics" => metrics(ctx),
-        "min_trading_vol" => hyres(min_trading_vol(ctx, req)),
-        "my_balance" => hyres(my_balance(ctx, req)),
-        "my_orders" => hyres(my_orders(ctx)),
-        "my_recent_swaps" => hyres(my_recent_swaps_rpc(ctx, req)),
-        "my_swap_status" => hyres(my_swap_status(ctx, req)),
-        "my_tx_history" => hyres(my_tx_history(ctx, req)),
-        "orders_history_by_filter" => hyres(orders_history_by_filter(ctx, req)),
-        "order_status" => hyres(order_status(ctx, req)),
-        "orderbook" => hyres(orderbook_rpc(ctx, req)),
-        "orderbook_depth" => hyres(orderbook_depth_rpc(ctx, req)),
-        "sim_panic" => hyres(sim_panic(req)),
-        "recover_funds_of_swap" => hyres(recover_funds_of_swap(ctx, req)),
-        "sell" => hyres(sell(ctx, req)),
-        "show_priv_key" => hyres(show_priv_key(ctx, req)),
-        "send_raw_transaction" => hyres(send_raw_transaction(ctx, req)),
-        "set_required_confirmations" => hyres(set_required_confirmations(ctx, req)),
-        "set_requires_notarization" => hyres(set_requires_notarization(ctx, req)),
-        "setprice" => hyres(set_price(ctx, req)),
-        "stats_swap_status" => hyres(stats_swap_status(ctx, req)),
-        "stop" => hyres(stop(ctx)),
-        "trade_preimage" => hyres(into_legacy::trade_preimage(ctx, req)),
-        "unban_pubkeys" => hyres(unban_pubkeys_rpc(ctx, req)),
-        "update_maker_order" => hyres(update_maker_order_rpc(ctx, req)),
-        "validateaddress" => hyres(validate_address(ctx, req)),
-        "version" => version(ctx),
-        "withdraw" => hyres(into_legacy::withdraw(ctx, req)),
-        _ => return DispatcherRes::NoMatch(req),
-    })
+/// The handlers are invoked both directly from the HTTP endpoint handler and in a delayed fashion from `lp_command_q_loop`.
+pub(super) fn register_methods(registry: &mut RpcMethodRegistry) {
+    registry.register_legacy(
+        "help",
+        AuthLevel::Public,
+        "Returns the description of the legacy methods.",
+        |_ctx, _req| help(),
+    );
+    registry.register_legacy(
+        "metrics",
+        AuthLevel::Public,
+        "Returns the node metrics.",
+        |ctx, _req| metrics(ctx),
+    );
+    registry.register_legacy(
+        "orderbook",
+        AuthLevel::Public,
+        "Returns the orderbook of the given pair.",
+        |ctx, req| hyres(orderbook_rpc(ctx, req)),
+    );
+    registry.register_legacy(
+        "stats_swap_status",
+        AuthLevel::Public,
+        "Returns the status of the swap collected by the stats node.",
+        |ctx, req| hyres(stats_swap_status(ctx, req)),
+    );
+    registry.register_legacy(
+        "version",
+        AuthLevel::Public,
+        "Returns the version of the node.",
+        |ctx, _req| version(ctx),
+    );
+
+    registry.register_legacy(
+        "active_swaps",
+        AuthLevel::Userpass,
+        "Lists the UUIDs of the ongoing swaps.",
+        |ctx, req| hyres(active_swaps_rpc(ctx, req)),
+    );
+    registry.register_legacy(
+        "all_swaps_uuids_by_filter",
+        AuthLevel::Userpass,
+        "Lists the UUIDs of the swaps by the given filter.",
+        |ctx, req| hyres(all_swaps_uuids_by_filter(ctx, req)),
+    );
+    registry.register_legacy(
+        "ban_pubkey",
+        AuthLevel::Userpass,
+        "Bans the pubkey, so that it can't be matched with the node orders.",
+        |ctx, req| hyres(ban_pubkey_rpc(ctx, req)),
+    );
+    registry.register_legacy(
+        "best_orders",
+        AuthLevel::Userpass,
+        "Returns the best orders to fill the given volume.",
+        |ctx, req| hyres(best_orders_rpc(ctx, req)),
+    );
+    registry.register_legacy("buy", AuthLevel::Userpass, "Issues a buy request.", |ctx, req| {
+        hyres(buy(ctx, req))
+    });
+    registry.register_legacy(
+        "cancel_all_orders",
+        AuthLevel::Userpass,
+        "Cancels all the orders by the given filter.",
+        |ctx, req| hyres(cancel_all_orders_rpc(ctx, req)),
+    );
+    registry.register_legacy("cancel_order", AuthLevel::Userpass, "Cancels the order.", |ctx, req| {
+        hyres(cancel_order_rpc(ctx, req))
+    });
+    registry.register_legacy(
+        "coins_needed_for_kick_start",
+        AuthLevel::Userpass,
+        "Lists the coins needed to resume the swaps after the restart.",
+        |ctx, _req| hyres(coins_needed_for_kick_start(ctx)),
+    );
+    registry.register_legacy(
+        "convert_utxo_address",
+        AuthLevel::Userpass,
+        "Converts the UTXO address to the address of another UTXO coin.",
+        |ctx, req| hyres(convert_utxo_address(ctx, req)),
+    );
+    registry.register_legacy(
+        "convertaddress",
+        AuthLevel::Userpass,
+        "Converts the address to the given format.",
+        |ctx, req| hyres(convert_address(ctx, req)),
+    );
+    registry.register_legacy("disable_coin", AuthLevel::Userpass, "Disables the coin.", |ctx, req| {
+        hyres(disable_coin(ctx, req))
+    });
+    registry.register_legacy(
+        "electrum",
+        AuthLevel::Userpass,
+        "Enables the coin using the Electrum servers.",
+        |ctx, req| hyres(electrum(ctx, req)),
+    );
+    registry.register_legacy(
+        "enable",
+        AuthLevel::Userpass,
+        "Enables the coin using the native daemon or the Web3 nodes.",
+        |ctx, req| hyres(enable(ctx, req)),
+    );
+    registry.register_legacy(
+        "get_enabled_coins",
+        AuthLevel::Userpass,
+        "Lists the enabled coins.",
+        |ctx, _req| hyres(get_enabled_coins(ctx)),
+    );
+    registry.register_legacy(
+        "get_gossip_mesh",
+        AuthLevel::Userpass,
+        "Returns the gossipsub mesh.",
+        |ctx, _req| hyres(get_gossip_mesh(ctx)),
+    );
+    registry.register_legacy(
+        "get_gossip_peer_topics",
+        AuthLevel::Userpass,
+        "Returns the topics of the gossipsub peers.",
+        |ctx, _req| hyres(get_gossip_peer_topics(ctx)),
+    );
+    registry.register_legacy(
+        "get_gossip_topic_peers",
+        AuthLevel::Userpass,
+        "Returns the gossipsub peers of the topics.",
+        |ctx, _req| hyres(get_gossip_topic_peers(ctx)),
+    );
+    registry.register_legacy(
+        "get_my_peer_id",
+        AuthLevel::Userpass,
+        "Returns the P2P peer ID of the node.",
+        |ctx, _req| hyres(get_my_peer_id(ctx)),
+    );
+    registry.register_legacy(
+        "get_peers_info",
+        AuthLevel::Userpass,
+        "Returns the addresses of the connected peers.",
+        |ctx, _req| hyres(get_peers_info(ctx)),
+    );
+    registry.register_legacy(
+        "get_relay_mesh",
+        AuthLevel::Userpass,
+        "Returns the relay mesh.",
+        |ctx, _req| hyres(get_relay_mesh(ctx)),
+    );
+    registry.register_legacy(
+        "get_trade_fee",
+        AuthLevel::Userpass,
+        "Returns the approximate transaction fee of the coin.",
+        |ctx, req| hyres(get_trade_fee(ctx, req)),
+    );
+    registry.register_legacy(
+        "import_swaps",
+        AuthLevel::Userpass,
+        "Imports the swaps data.",
+        |ctx, req| hyres(import_swaps(ctx, req)),
+    );
+    registry.register_legacy(
+        "kmd_rewards_info",
+        AuthLevel::Userpass,
+        "Returns the KMD rewards info of the unspent outputs.",
+        |ctx, _req| hyres(kmd_rewards_info(ctx)),
+    );
+    registry.register_legacy(
+        "list_banned_pubkeys",
+        AuthLevel::Userpass,
+        "Lists the banned pubkeys.",
+        |ctx, _req| hyres(list_banned_pubkeys_rpc(ctx)),
+    );
+    registry.register_legacy(
+        "max_taker_vol",
+        AuthLevel::Userpass,
+        "Returns the maximum volume of the coin that can be sold by a taker request.",
+        |ctx, req| hyres(max_taker_vol(ctx, req)),
+    );
+    registry.register_legacy(
+        "min_trading_vol",
+        AuthLevel::Userpass,
+        "Returns the minimum trading volume of the coin.",
+        |ctx, req| hyres(min_trading_vol(ctx, req)),
+    );
+    registry.register_legacy(
+        "my_balance",
+        AuthLevel::Userpass,
+        "Returns the balance of the coin.",
+        |ctx, req| hyres(my_balance(ctx, req)),
+    );
+    registry.register_legacy(
+        "my_orders",
+        AuthLevel::Userpass,
+        "Lists the orders of the node.",
+        |ctx, _req| hyres(my_orders(ctx)),
+    );
+    registry.register_legacy(
+        "my_recent_swaps",
+        AuthLevel::Userpass,
+        "Lists the recent swaps of the node.",
+        |ctx, req| hyres(my_recent_swaps_rpc(ctx, req)),
+    );
+    registry.register_legacy(
+        "my_swap_status",
+        AuthLevel::Userpass,
+        "Returns the status of the node swap.",
+        |ctx, req| hyres(my_swap_status(ctx, req)),
+    );
+    registry.register_legacy(
+        "my_tx_history",
+        AuthLevel::Userpass,
+        "Returns the transaction history of the coin.",
+        |ctx, req| hyres(my_tx_history(ctx, req)),
+    );
+    registry.register_legacy(
+        "order_status",
+        AuthLevel::Userpass,
+        "Returns the status of the order.",
+        |ctx, req| hyres(order_status(ctx, req)),
+    );
+    registry.register_legacy(
+        "orderbook_depth",
+        AuthLevel::Userpass,
+        "Returns the number of the asks and bids of the given pairs.",
+        |ctx, req| hyres(orderbook_depth_rpc(ctx, req)),
+    );
+    registry.register_legacy(
+        "orders_history_by_filter",
+        AuthLevel::Userpass,
+        "Lists the orders history by the given filter.",
+        |ctx, req| hyres(orders_history_by_filter(ctx, req)),
+    );
+    registry.register_legacy(
+        "recover_funds_of_swap",
+        AuthLevel::Userpass,
+        "Recovers the funds locked by the failed swap.",
+        |ctx, req| hyres(recover_funds_of_swap(ctx, req)),
+    );
+    registry.register_legacy("sell", AuthLevel::Userpass, "Issues a sell request.", |ctx, req| {
+        hyres(sell(ctx, req))
+    });
+    registry.register_legacy(
+        "send_raw_transaction",
+        AuthLevel::Userpass,
+        "Broadcasts the raw transaction.",
+        |ctx, req| hyres(send_raw_transaction(ctx, req)),
+    );
+    registry.register_legacy(
+        "set_required_confirmations",
+        AuthLevel::Userpass,
+        "Sets the number of the confirmations required by the swaps.",
+        |ctx, req| hyres(set_required_confirmations(ctx, req)),
+    );
+    registry.register_legacy(
+        "set_requires_notarization",
+        AuthLevel::Userpass,
+        "Sets whether the swaps require the dPoW notarization.",
+        |ctx, req| hyres(set_requires_notarization(ctx, req)),
+    );
+    registry.register_legacy("setprice", AuthLevel::Userpass, "Places a maker order.", |ctx, req| {
+        hyres(set_price(ctx, req))
+    });
+    registry.register_legacy(
+        "show_priv_key",
+        AuthLevel::Userpass,
+        "Returns the private key of the coin.",
+        |ctx, req| hyres(show_priv_key(ctx, req)),
+    );
+    registry.register_legacy(
+        "sim_panic",
+        AuthLevel::Userpass,
+        "Panics to test the panic handling.",
+        |_ctx, req| hyres(sim_panic(req)),
+    );
+    registry.register_legacy("stop", AuthLevel::Userpass, "Stops the node.", |ctx, _req| {
+        hyres(stop(ctx))
+    });
+    registry.register_legacy(
+        "trade_preimage",
+        AuthLevel::Userpass,
+        "Returns the fees that would be paid by the trade.",
+        |ctx, req| hyres(into_legacy::trade_preimage(ctx, req)),
+    );
+    registry.register_legacy(
+        "unban_pubkeys",
+        AuthLevel::Userpass,
+        "Unbans the pubkeys.",
+        |ctx, req| hyres(unban_pubkeys_rpc(ctx, req)),
+    );
+    registry.register_legacy(
+        "update_maker_order",
+        AuthLevel::Userpass,
+        "Updates the maker order.",
+        |ctx, req| hyres(update_maker_order_rpc(ctx, req)),
+    );
+    registry.register_legacy(
+        "validateaddress",
+        AuthLevel::Userpass,
+        "Checks whether the address is valid for the coin.",
+        |ctx, req| hyres(validate_address(ctx, req)),
+    );
+    registry.register_legacy(
+        "withdraw",
+        AuthLevel::Userpass,
+        "Generates a transaction sending the coins to the given address.",
+        |ctx, req| hyres(into_legacy::withdraw(ctx, req)),
+    );
+}
+
+/// Returns `true` if the method can be called without the `userpass`.
+/// A request without the method is considered public, so that it fails with the "No such method" error.
+fn is_public_method(method: Option<&str>) -> bool {
+    match method {
+        Some(method) => rpc_methods()
+            .legacy_method(method)
+            .map_or(false, |method| method.meta.auth == AuthLevel::Public),
+        None => true,
+    }
 }
 
 pub async fn process_single_request(
@@ -125,7 +377,7 @@ pub async fn process_single_request(
     local_only: bool,
 ) -> Result<Response<Vec<u8>>, String> {
     // https://github.com/artemii235/SuperNET/issues/368
-    if local_only && !client.ip().is_loopback() && !PUBLIC_METHODS.contains(&req["method"].as_str()) {
+    if local_only && !client.ip().is_loopback() && !is_public_method(req["method"].as_str()) {
         return ERR!("Selected method can be called from localhost only!");
     }
     let rate_limit_ctx = RateLimitContext::from_ctx(&ctx).unwrap();
@@ -133,12 +385,29 @@ pub async fn process_single_request(
         return ERR!("Your ip is banned.");
     }
     try_s!(auth(&req, &ctx, &client).await);
+    try_s!(
+        check_request_rate(
+            &ctx,
+            &client,
+            req["userpass"].as_str(),
+            req["method"].as_str().unwrap_or_default()
+        )
+        .await
+    );
 
-    let handler = match dispatcher(req, ctx.clone()) {
-        DispatcherRes::Match(handler) => handler,
-        DispatcherRes::NoMatch(_) => return ERR!("No such method."),
+    let method = match req["method"]
+        .as_str()
+        .and_then(|method| rpc_methods().legacy_method(method))
+    {
+        Some(method) => method,
+        None => return ERR!("No such method."),
     };
-    Ok(try_s!(handler.compat().await))
+
+    let start = Instant::now();
+    let result = (method.handler)(ctx.clone(), req).compat().await;
+    let is_success = matches!(result, Ok(ref response) if response.status().is_success());
+    record_rpc_call(&ctx, &method.meta, start.elapsed(), is_success);
+    Ok(try_s!(result))
 }
 
 /// The set of functions that convert the result of the updated handlers into the legacy format.
@@ -0,0 +1,183 @@
+//! The registry of all the RPC methods available on this node.
+//!
+//! Every subsystem registers its methods along with the metadata in its own `register_rpc_methods` function
+//! (see [`mm2_core::rpc_registry`]), and the registry is assembled from them once on the first request.
+//! The registry is never changed after that, so the lookups don't need any locking.
+//!
+//! The methods of the subsystems the dispatcher doesn't know about, e.g. of the optional crates enabled by a feature,
+//! are registered by the [`MethodsRegistrar`] added with [`add_methods_registrar`] before MM2 is started.
+
+use super::{dispatcher, dispatcher_legacy};
+use lazy_static::lazy_static;
+use mm2_core::mm_ctx::MmArc;
+use mm2_core::rpc_registry::{MethodsRegistrar, RpcMethodMeta, RpcMethodRegistry};
+use mm2_metrics::{mm_counter, mm_label, mm_timing};
+use parking_lot::Mutex as PaMutex;
+use std::time::Duration;
+
+#[derive(Default)]
+struct ExtraRegistrars {
+    registrars: Vec<MethodsRegistrar>,
+    /// Is set once the registry is built, so the registrars added later would be ignored.
+    is_sealed: bool,
+}
+
+lazy_static! {
+    static ref EXTRA_REGISTRARS: PaMutex<ExtraRegistrars> = PaMutex::new(ExtraRegistrars::default());
+    static ref RPC_METHODS: RpcMethodRegistry = {
+        let mut extra = EXTRA_REGISTRARS.lock();
+        extra.is_sealed = true;
+        build_registry(&extra.registrars)
+    };
+}
+
+/// Returns the registry of all the RPC methods available on the current platform and with the enabled features.
+pub fn rpc_methods() -> &'static RpcMethodRegistry { &RPC_METHODS }
+
+/// Adds the registrar of the methods the dispatcher doesn't know about.
+/// Fails if the registry is built already, i.e. MM2 is started or an RPC request is handled.
+pub fn add_methods_registrar(registrar: MethodsRegistrar) -> Result<(), String> {
+    let mut extra = EXTRA_REGISTRARS.lock();
+    if extra.is_sealed {
+        return ERR!("The RPC methods are registered already");
+    }
+    extra.registrars.push(registrar);
+    Ok(())
+}
+
+fn build_registry(extra_registrars: &[MethodsRegistrar]) -> RpcMethodRegistry {
+    let mut registry = RpcMethodRegistry::default();
+    dispatcher::register_methods(&mut registry);
+    dispatcher_legacy::register_methods(&mut registry);
+    for registrar in extra_registrars {
+        registrar(&mut registry);
+    }
+    registry
+}
+
+/// Builds the registry and checks that every method is registered once,
+/// so that MM2 fails to start instead of dispatching to an unexpected handler.
+pub fn validate_rpc_methods() -> Result<(), String> {
+    let duplicates = rpc_methods().duplicates();
+    if !duplicates.is_empty() {
+        return ERR!(
+            "The RPC methods are registered more than once: {}",
+            duplicates.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Updates the per-method call counters and the timing of the RPC method.
+pub fn record_rpc_call(ctx: &MmArc, meta: &RpcMethodMeta, elapsed: Duration, is_success: bool) {
+    let version = meta.version.as_str();
+    mm_counter!(ctx.metrics, "rpc.method.calls", 1, "method" => meta.name, "version" => version);
+    mm_timing!(ctx.metrics, "rpc.method.timing", elapsed, "method" => meta.name, "version" => version);
+    if !is_success {
+        mm_counter!(ctx.metrics, "rpc.method.errors", 1, "method" => meta.name, "version" => version);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Response;
+    use mm2_core::rpc_registry::{AuthLevel, MmRpcHandlerFut, RpcMethodVersion};
+    use mm2_rpc::mm_protocol::MmRpcRequest;
+
+    fn test_handler(_ctx: MmArc, _req: MmRpcRequest) -> MmRpcHandlerFut {
+        Box::pin(async { Ok(Response::new(b"first".to_vec())) })
+    }
+
+    fn register_test_methods(registry: &mut RpcMethodRegistry) {
+        registry.register("test::method", "The method of the test registrar.", test_handler);
+        registry.register_with_auth(
+            "test::public_method",
+            AuthLevel::Public,
+            "The public method of the test registrar.",
+            test_handler,
+        );
+    }
+
+    #[test]
+    fn test_public_methods() {
+        let registry = rpc_methods();
+        let public: Vec<_> = registry
+            .methods_meta()
+            .into_iter()
+            .filter(|meta| meta.auth == AuthLevel::Public)
+            .map(|meta| meta.name)
+            .collect();
+        // The legacy methods go first.
+        assert_eq!(public, vec![
+            "help",
+            "metrics",
+            "orderbook",
+            "stats_swap_status",
+            "version",
+            "orderbook"
+        ]);
+
+        assert!(registry.mmrpc_method("withdraw").is_some());
+        assert!(registry.mmrpc_method("task::withdraw::init").is_some());
+        assert!(registry.legacy_method("withdraw").is_some());
+        assert!(registry.mmrpc_method("unknown_method").is_none());
+    }
+
+    #[test]
+    fn test_all_methods_are_registered_once() {
+        validate_rpc_methods().unwrap();
+
+        let methods = rpc_methods().methods_meta();
+        assert!(methods.iter().any(|meta| meta.name == "rpc_methods"));
+        // The same name is allowed for the methods of the different versions.
+        let orderbooks: Vec<_> = methods
+            .iter()
+            .filter(|meta| meta.name == "orderbook")
+            .map(|meta| meta.version.as_str())
+            .collect();
+        assert_eq!(orderbooks, ["legacy", "2.0"]);
+    }
+
+    #[test]
+    fn test_extra_registrar() {
+        let registry = build_registry(&[register_test_methods]);
+        assert!(registry.duplicates().is_empty());
+
+        let method = registry.mmrpc_method("test::method").unwrap();
+        assert_eq!(method.meta.auth, AuthLevel::Userpass);
+        let public = registry.mmrpc_method("test::public_method").unwrap();
+        assert_eq!(public.meta.auth, AuthLevel::Public);
+        // The subsystems methods are registered too.
+        assert!(registry.mmrpc_method("withdraw").is_some());
+
+        // Registering the same methods twice is reported.
+        let registry = build_registry(&[register_test_methods, register_test_methods]);
+        assert_eq!(registry.duplicates().len(), 2);
+    }
+
+    #[test]
+    fn test_registrar_is_rejected_once_registry_is_built() {
+        rpc_methods();
+        assert!(add_methods_registrar(register_test_methods).is_err());
+        assert!(rpc_methods().mmrpc_method("test::method").is_none());
+    }
+
+    #[test]
+    fn test_methods_meta_order() {
+        let registry = build_registry(&[register_test_methods]);
+        let methods = registry.methods_meta();
+        let legacy_count = methods
+            .iter()
+            .take_while(|meta| matches!(meta.version, RpcMethodVersion::Legacy))
+            .count();
+        assert!(legacy_count > 0);
+        assert!(methods[legacy_count..]
+            .iter()
+            .all(|meta| matches!(meta.version, RpcMethodVersion::V2)));
+
+        let is_sorted = |metas: &[RpcMethodMeta]| metas.windows(2).all(|pair| pair[0].name <= pair[1].name);
+        assert!(is_sorted(&methods[..legacy_count]));
+        assert!(is_sorted(&methods[legacy_count..]));
+    }
+}
@@ -3,6 +3,8 @@ use crypto::{CryptoCtx, CryptoCtxError, HwConnectionStatus, HwPubkey};
 use derive_more::Display;
 use http::StatusCode;
 use mm2_core::mm_ctx::MmArc;
+use mm2_core::mmrpc_handler;
+use mm2_core::rpc_registry::RpcMethodRegistry;
 use mm2_err_handle::prelude::*;
 use rpc::v1::types::H160 as H160Json;
 use serde_json::Value as Json;
@@ -114,3 +116,27 @@ pub async fn trezor_connection_status(
         status: hw_ctx.trezor_connection_status().await,
     })
 }
+
+/// Registers the node mmrpc 2.0 methods.
+pub fn register_rpc_methods(registry: &mut RpcMethodRegistry) {
+    registry.register(
+        "get_public_key",
+        "Returns the public key of the node.",
+        mmrpc_handler!(get_public_key),
+    );
+    registry.register(
+        "get_public_key_hash",
+        "Returns the RIPEMD-160 hash of the node public key.",
+        mmrpc_handler!(get_public_key_hash),
+    );
+    registry.register(
+        "get_shared_db_id",
+        "Returns the identifier of the database shared between the HD wallet accounts.",
+        mmrpc_handler!(get_shared_db_id),
+    );
+    registry.register(
+        "trezor_connection_status",
+        "Returns the connection status of the Trezor device.",
+        mmrpc_handler!(trezor_connection_status),
+    );
+}
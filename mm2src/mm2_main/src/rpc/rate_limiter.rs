@@ -1,15 +1,44 @@
+//! Limits the failed authentication attempts per IP and the request rate per client.
+//!
+//! The request rate is limited by the token buckets configured in `rpc_rate_limits`:
+//!
+//! ```json
+//! "rpc_rate_limits": {
+//!     "default": { "capacity": 50, "refill_per_sec": 10 },
+//!     "rules": [
+//!         { "methods": ["my_tx_history", "orderbook", "group:read_only"], "capacity": 5, "refill_per_sec": 0.5 }
+//!     ]
+//! }
+//! ```
+//!
+//! Every rule has a separate bucket per client, i.e. per API key if it's used or per IP address otherwise.
+//! The methods are matched against the rules in order, and the methods that don't match any rule
+//! are limited by the `default` limit if it's set.
+
+use super::rpc_permissions::{method_groups_from_conf, method_matches, resolve_groups, RpcPermissionsContext};
 use crate::mm2::rpc::DispatcherError;
+use common::log::error;
+use common::{now_float, now_sec, HttpStatusCode};
 use derive_more::Display;
 use futures::lock::Mutex as AsyncMutex;
+use http::StatusCode;
 use mm2_core::mm_ctx::from_ctx;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
-use std::collections::HashMap;
+use serde_json::{self as json, Value as Json};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
 pub const LIMIT_FAILED_REQUEST: usize = 10;
-pub type RateInfosRegistry = HashMap<IpAddr, usize>;
+/// Used if `rpc_ban_duration_secs` is not set in the config.
+const DEFAULT_BAN_DURATION_SECS: u64 = 3600;
+/// The least recently used bucket is dropped once the number of the tracked buckets reaches this value.
+const MAX_TRACKED_BUCKETS: usize = 1024;
+
+pub type RateInfosRegistry = HashMap<IpAddr, FailedAuthInfo>;
+type BucketKey = (RateLimitClient, RuleId);
 
 #[derive(Display, Serialize, SerializeErrorType)]
 #[serde(tag = "error_type", content = "error_data")]
@@ -18,44 +47,485 @@ pub enum RateLimitError {
     NbAttemptsLeft(usize),
 }
 
+pub struct FailedAuthInfo {
+    attempts: usize,
+    last_attempt: u64,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RateLimit {
+    /// The number of the requests that can be done at once.
+    capacity: u32,
+    /// The number of the requests restored every second.
+    refill_per_sec: f64,
+}
+
+#[derive(Deserialize)]
+struct RateLimitRuleConf {
+    methods: Vec<String>,
+    #[serde(flatten)]
+    limit: RateLimit,
+}
+
+#[derive(Default, Deserialize)]
+struct RateLimitsConf {
+    #[serde(default)]
+    default: Option<RateLimit>,
+    #[serde(default)]
+    rules: Vec<RateLimitRuleConf>,
+}
+
+struct RateLimitRule {
+    /// The method patterns with all the groups resolved.
+    methods: Vec<String>,
+    limit: RateLimit,
+}
+
+/// The index of the rule in `rpc_rate_limits.rules`, or `None` for the default limit.
+type RuleId = Option<usize>;
+
+/// The client the request rate is limited for.
+#[derive(Clone, Eq, Hash, PartialEq)]
+enum RateLimitClient {
+    ApiKey(String),
+    Ip(IpAddr),
+}
+
+impl fmt::Display for RateLimitClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimitClient::ApiKey(name) => write!(f, "api_key:{}", name),
+            RateLimitClient::Ip(ip) => write!(f, "ip:{}", ip),
+        }
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: f64,
+}
+
+impl TokenBucket {
+    fn full(limit: &RateLimit, now: f64) -> TokenBucket {
+        TokenBucket {
+            tokens: limit.capacity as f64,
+            last_refill: now,
+        }
+    }
+
+    fn tokens_at(&self, limit: &RateLimit, now: f64) -> f64 {
+        let refilled = self.tokens + (now - self.last_refill).max(0.) * limit.refill_per_sec;
+        refilled.min(limit.capacity as f64)
+    }
+
+    /// Takes a token, or returns the number of seconds to wait until a token is available.
+    fn try_take(&mut self, limit: &RateLimit, now: f64) -> Result<(), f64> {
+        self.tokens = self.tokens_at(limit, now);
+        self.last_refill = now;
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            Ok(())
+        } else {
+            Err((1. - self.tokens) / limit.refill_per_sec)
+        }
+    }
+}
+
+/// The token buckets ordered by their last use, so that the memory is bounded by [`MAX_TRACKED_BUCKETS`].
 #[derive(Default)]
-pub struct RateLimitContext(AsyncMutex<RateInfosRegistry>);
+struct BucketsRegistry {
+    /// The buckets with the number of their last use.
+    buckets: HashMap<BucketKey, (TokenBucket, u64)>,
+    /// The keys of `buckets` by the number of their last use.
+    by_last_use: BTreeMap<u64, BucketKey>,
+    uses: u64,
+}
+
+impl BucketsRegistry {
+    /// Takes a token from the bucket of the `key`, a full bucket is created if it's not tracked.
+    fn try_take(&mut self, key: BucketKey, limit: &RateLimit, now: f64) -> Result<(), f64> {
+        self.uses += 1;
+        let use_number = self.uses;
+
+        if let Some((bucket, last_use)) = self.buckets.get_mut(&key) {
+            self.by_last_use.remove(last_use);
+            *last_use = use_number;
+            self.by_last_use.insert(use_number, key);
+            return bucket.try_take(limit, now);
+        }
+
+        if self.buckets.len() >= MAX_TRACKED_BUCKETS {
+            self.remove_least_recently_used();
+        }
+        let mut bucket = TokenBucket::full(limit, now);
+        let result = bucket.try_take(limit, now);
+        self.by_last_use.insert(use_number, key.clone());
+        self.buckets.insert(key, (bucket, use_number));
+        result
+    }
+
+    fn remove_least_recently_used(&mut self) {
+        let least_recent = self.by_last_use.keys().next().copied();
+        if let Some(key) = least_recent.and_then(|use_number| self.by_last_use.remove(&use_number)) {
+            self.buckets.remove(&key);
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&BucketKey, &TokenBucket)> {
+        self.buckets.iter().map(|(key, (bucket, _))| (key, bucket))
+    }
+}
+
+pub struct RateLimitContext {
+    failed_auth: AsyncMutex<RateInfosRegistry>,
+    buckets: AsyncMutex<BucketsRegistry>,
+    rules: Vec<RateLimitRule>,
+    default_limit: Option<RateLimit>,
+    ban_duration_secs: u64,
+}
+
+impl Default for RateLimitContext {
+    fn default() -> Self {
+        RateLimitContext {
+            failed_auth: AsyncMutex::new(HashMap::new()),
+            buckets: AsyncMutex::new(BucketsRegistry::default()),
+            rules: Vec::new(),
+            default_limit: None,
+            ban_duration_secs: DEFAULT_BAN_DURATION_SECS,
+        }
+    }
+}
 
 impl RateLimitContext {
     pub fn from_ctx(ctx: &MmArc) -> Result<Arc<RateLimitContext>, String> {
         Ok(try_s!(from_ctx(&ctx.rate_limit_ctx, move || {
-            Ok(RateLimitContext::default())
+            // The config is validated by `validate_rate_limits_conf` on the start.
+            Ok(RateLimitContext::from_conf(&ctx.conf).unwrap_or_else(|e| {
+                error!("Invalid RPC rate limits config, the request rate is not limited: {}", e);
+                RateLimitContext::default()
+            }))
         })))
     }
 
+    fn from_conf(conf: &Json) -> Result<RateLimitContext, String> {
+        let ban_duration_secs = if conf["rpc_ban_duration_secs"].is_null() {
+            DEFAULT_BAN_DURATION_SECS
+        } else {
+            try_s!(conf["rpc_ban_duration_secs"]
+                .as_u64()
+                .filter(|secs| *secs > 0)
+                .ok_or("'rpc_ban_duration_secs' must be a positive integer"))
+        };
+
+        let limits_conf: RateLimitsConf = if conf["rpc_rate_limits"].is_null() {
+            RateLimitsConf::default()
+        } else {
+            try_s!(json::from_value(conf["rpc_rate_limits"].clone()))
+        };
+
+        let groups = try_s!(method_groups_from_conf(conf));
+        let mut rules = Vec::with_capacity(limits_conf.rules.len());
+        for rule_conf in limits_conf.rules {
+            try_s!(validate_limit(&rule_conf.limit));
+            rules.push(RateLimitRule {
+                methods: try_s!(resolve_groups(rule_conf.methods, &groups)),
+                limit: rule_conf.limit,
+            });
+        }
+        if let Some(ref default_limit) = limits_conf.default {
+            try_s!(validate_limit(default_limit));
+        }
+
+        Ok(RateLimitContext {
+            rules,
+            default_limit: limits_conf.default,
+            ban_duration_secs,
+            ..RateLimitContext::default()
+        })
+    }
+
     pub async fn is_banned(&self, client_ip: IpAddr) -> bool {
-        let rate_infos = self.0.lock().await;
-        if let Some(limit) = rate_infos.get(&client_ip) {
-            return *limit >= LIMIT_FAILED_REQUEST;
+        let mut rate_infos = self.failed_auth.lock().await;
+        match rate_infos.get(&client_ip) {
+            Some(info) if self.is_expired(info) => {
+                rate_infos.remove(&client_ip);
+                false
+            },
+            Some(info) => info.attempts >= LIMIT_FAILED_REQUEST,
+            None => false,
+        }
+    }
+
+    /// The failed attempts are forgotten if there were no new ones during the ban duration.
+    fn is_expired(&self, info: &FailedAuthInfo) -> bool {
+        now_sec() >= info.last_attempt.saturating_add(self.ban_duration_secs)
+    }
+
+    fn find_limit(&self, method: &str) -> Option<(RuleId, &RateLimit)> {
+        let rule = self
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.methods.iter().any(|pattern| method_matches(pattern, method)));
+        match rule {
+            Some((index, rule)) => Some((Some(index), &rule.limit)),
+            None => self.default_limit.as_ref().map(|limit| (None, limit)),
+        }
+    }
+
+    fn limit_by_id(&self, rule_id: RuleId) -> Option<&RateLimit> {
+        match rule_id {
+            Some(index) => self.rules.get(index).map(|rule| &rule.limit),
+            None => self.default_limit.as_ref(),
         }
-        false
     }
 }
 
+fn validate_limit(limit: &RateLimit) -> Result<(), String> {
+    if limit.capacity == 0 || limit.refill_per_sec <= 0. {
+        return ERR!("Rate limit 'capacity' and 'refill_per_sec' must be positive");
+    }
+    Ok(())
+}
+
+/// Validates `rpc_rate_limits` and `rpc_ban_duration_secs`, so that MM2 doesn't start with the limits silently ignored.
+pub fn validate_rate_limits_conf(conf: &Json) -> Result<(), String> { RateLimitContext::from_conf(conf).map(|_| ()) }
+
 pub async fn process_rate_limit(ctx: &MmArc, client: &SocketAddr) -> MmError<DispatcherError> {
     let rate_limit_ctx = RateLimitContext::from_ctx(ctx).unwrap();
-    let mut rate_limit_registry = rate_limit_ctx.0.lock().await;
+    let mut rate_limit_registry = rate_limit_ctx.failed_auth.lock().await;
 
     match rate_limit_registry.get_mut(&client.ip()) {
-        Some(limit) => {
-            if *limit >= LIMIT_FAILED_REQUEST {
+        Some(info) if !rate_limit_ctx.is_expired(info) => {
+            if info.attempts >= LIMIT_FAILED_REQUEST {
                 return MmError::new(DispatcherError::Banned);
             }
-            *limit += 1;
+            info.attempts += 1;
+            info.last_attempt = now_sec();
             MmError::new(DispatcherError::UserpassIsInvalid(RateLimitError::NbAttemptsLeft(
-                LIMIT_FAILED_REQUEST - *limit,
+                LIMIT_FAILED_REQUEST - info.attempts,
             )))
         },
-        None => {
-            rate_limit_registry.insert(client.ip(), 1);
+        _ => {
+            let info = FailedAuthInfo {
+                attempts: 1,
+                last_attempt: now_sec(),
+            };
+            rate_limit_registry.insert(client.ip(), info);
             MmError::new(DispatcherError::UserpassIsInvalid(RateLimitError::NbAttemptsLeft(
                 LIMIT_FAILED_REQUEST - 1,
             )))
         },
     }
 }
+
+/// Takes a token from the `client` bucket of the rule the `method` belongs to.
+/// Should be called after the `userpass` is authenticated, so that the API key buckets can't be drained by others.
+pub async fn check_request_rate(
+    ctx: &MmArc,
+    client: &SocketAddr,
+    userpass: Option<&str>,
+    method: &str,
+) -> Result<(), MmError<DispatcherError>> {
+    let rate_limit_ctx = RateLimitContext::from_ctx(ctx).unwrap();
+    let (rule_id, limit) = match rate_limit_ctx.find_limit(method) {
+        Some(rule) => rule,
+        None => return Ok(()),
+    };
+
+    let permissions_ctx = RpcPermissionsContext::from_ctx(ctx).unwrap();
+    let rate_limit_client = match userpass.and_then(|userpass| permissions_ctx.key_name(userpass)) {
+        Some(key_name) => RateLimitClient::ApiKey(key_name.to_owned()),
+        None => RateLimitClient::Ip(client.ip()),
+    };
+
+    let now = now_float();
+    let mut buckets = rate_limit_ctx.buckets.lock().await;
+    buckets
+        .try_take((rate_limit_client, rule_id), limit, now)
+        .map_err(|retry_after| {
+            MmError::new(DispatcherError::RateLimitExceeded {
+                method: method.to_owned(),
+                retry_after_secs: retry_after.ceil() as u64,
+            })
+        })
+}
+
+#[derive(Serialize)]
+pub struct FailedAuthStatus {
+    ip: IpAddr,
+    failed_attempts: usize,
+    /// Is set if the IP is banned.
+    banned_until: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct RateLimitBucketStatus {
+    /// `api_key:<name>` or `ip:<address>`.
+    client: String,
+    /// The methods of the rule, or `None` for the default limit.
+    methods: Option<Vec<String>>,
+    #[serde(flatten)]
+    limit: RateLimit,
+    tokens_left: f64,
+}
+
+#[derive(Serialize)]
+pub struct RateLimitStatusResponse {
+    ban_duration_secs: u64,
+    failed_auth: Vec<FailedAuthStatus>,
+    buckets: Vec<RateLimitBucketStatus>,
+}
+
+#[derive(Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum RateLimitStatusError {
+    Internal(String),
+}
+
+impl HttpStatusCode for RateLimitStatusError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RateLimitStatusError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+pub async fn rate_limit_status(ctx: MmArc, _req: Json) -> MmResult<RateLimitStatusResponse, RateLimitStatusError> {
+    let rate_limit_ctx = RateLimitContext::from_ctx(&ctx).map_to_mm(RateLimitStatusError::Internal)?;
+
+    let failed_auth = rate_limit_ctx
+        .failed_auth
+        .lock()
+        .await
+        .iter()
+        .filter(|(_, info)| !rate_limit_ctx.is_expired(info))
+        .map(|(ip, info)| FailedAuthStatus {
+            ip: *ip,
+            failed_attempts: info.attempts,
+            banned_until: (info.attempts >= LIMIT_FAILED_REQUEST)
+                .then(|| info.last_attempt.saturating_add(rate_limit_ctx.ban_duration_secs)),
+        })
+        .collect();
+
+    let now = now_float();
+    let buckets = rate_limit_ctx
+        .buckets
+        .lock()
+        .await
+        .iter()
+        .filter_map(|((client, rule_id), bucket)| {
+            let limit = rate_limit_ctx.limit_by_id(*rule_id)?;
+            Some(RateLimitBucketStatus {
+                client: client.to_string(),
+                methods: rule_id.map(|index| rate_limit_ctx.rules[index].methods.clone()),
+                limit: limit.clone(),
+                tokens_left: bucket.tokens_at(limit, now),
+            })
+        })
+        .collect();
+
+    Ok(RateLimitStatusResponse {
+        ban_duration_secs: rate_limit_ctx.ban_duration_secs,
+        failed_auth,
+        buckets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let limit = RateLimit {
+            capacity: 2,
+            refill_per_sec: 0.5,
+        };
+        let mut bucket = TokenBucket::full(&limit, 100.);
+
+        bucket.try_take(&limit, 100.).unwrap();
+        bucket.try_take(&limit, 100.).unwrap();
+        let retry_after = bucket.try_take(&limit, 100.).unwrap_err();
+        assert_eq!(retry_after, 2.);
+
+        // One token is restored in 2 seconds.
+        bucket.try_take(&limit, 102.).unwrap();
+        bucket.try_take(&limit, 102.).unwrap_err();
+
+        // The bucket is never refilled over the capacity.
+        assert_eq!(bucket.tokens_at(&limit, 1000.), 2.);
+    }
+
+    #[test]
+    fn test_find_limit() {
+        let conf = json!({
+            "rpc_method_groups": { "history": ["my_tx_history", "task::*"] },
+            "rpc_rate_limits": {
+                "default": { "capacity": 50, "refill_per_sec": 10 },
+                "rules": [
+                    { "methods": ["group:history"], "capacity": 5, "refill_per_sec": 1 },
+                    { "methods": ["orderbook"], "capacity": 10, "refill_per_sec": 2 }
+                ]
+            }
+        });
+        let rate_limit_ctx = RateLimitContext::from_conf(&conf).unwrap();
+
+        assert_eq!(rate_limit_ctx.find_limit("my_tx_history").unwrap().0, Some(0));
+        assert_eq!(rate_limit_ctx.find_limit("task::withdraw::init").unwrap().0, Some(0));
+        assert_eq!(rate_limit_ctx.find_limit("orderbook").unwrap().0, Some(1));
+        assert_eq!(rate_limit_ctx.find_limit("my_balance").unwrap().0, None);
+
+        let no_default = json!({
+            "rpc_rate_limits": { "rules": [{ "methods": ["orderbook"], "capacity": 10, "refill_per_sec": 2 }] }
+        });
+        let rate_limit_ctx = RateLimitContext::from_conf(&no_default).unwrap();
+        assert!(rate_limit_ctx.find_limit("my_balance").is_none());
+
+        let zero_refill = json!({ "rpc_rate_limits": { "default": { "capacity": 10, "refill_per_sec": 0 } } });
+        assert!(RateLimitContext::from_conf(&zero_refill).is_err());
+    }
+
+    #[test]
+    fn test_buckets_registry_drops_least_recently_used() {
+        let limit = RateLimit {
+            capacity: 2,
+            refill_per_sec: 1.,
+        };
+        let ip_key = |n: usize| {
+            (
+                RateLimitClient::Ip(IpAddr::from([10, 0, (n / 256) as u8, (n % 256) as u8])),
+                None,
+            )
+        };
+
+        let mut registry = BucketsRegistry::default();
+        for n in 0..MAX_TRACKED_BUCKETS {
+            registry.try_take(ip_key(n), &limit, 100.).unwrap();
+        }
+        // The first bucket is used again, so the second one becomes the least recently used.
+        registry.try_take(ip_key(0), &limit, 100.).unwrap();
+        registry.try_take(ip_key(0), &limit, 100.).unwrap_err();
+
+        registry.try_take(ip_key(MAX_TRACKED_BUCKETS), &limit, 100.).unwrap();
+        assert_eq!(registry.buckets.len(), MAX_TRACKED_BUCKETS);
+        assert_eq!(registry.by_last_use.len(), MAX_TRACKED_BUCKETS);
+        assert!(!registry.buckets.contains_key(&ip_key(1)));
+
+        // The recently used bucket keeps its state.
+        registry.try_take(ip_key(0), &limit, 100.).unwrap_err();
+        assert!(registry.buckets.contains_key(&ip_key(0)));
+    }
+
+    #[test]
+    fn test_ban_duration_conf() {
+        let rate_limit_ctx = RateLimitContext::from_conf(&json!({ "rpc_ban_duration_secs": 60 })).unwrap();
+        assert_eq!(rate_limit_ctx.ban_duration_secs, 60);
+
+        let rate_limit_ctx = RateLimitContext::from_conf(&json!({})).unwrap();
+        assert_eq!(rate_limit_ctx.ban_duration_secs, DEFAULT_BAN_DURATION_SECS);
+
+        assert!(RateLimitContext::from_conf(&json!({ "rpc_ban_duration_secs": 0 })).is_err());
+        assert!(RateLimitContext::from_conf(&json!({ "rpc_ban_duration_secs": -1 })).is_err());
+    }
+}
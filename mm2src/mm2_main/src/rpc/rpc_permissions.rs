@@ -0,0 +1,264 @@
+//! Config-defined API keys allowed to call a restricted set of the RPC methods.
+//!
+//! ```json
+//! "rpc_method_groups": {
+//!     "read_only": ["my_balance", "orderbook", "my_recent_swaps", "swap_v2::*"]
+//! },
+//! "rpc_api_keys": [
+//!     { "name": "dashboard", "key": "...", "allowed_methods": ["group:read_only"] },
+//!     { "name": "bot", "key": "...", "allowed_methods": ["group:read_only", "setprice", "cancel_order"] }
+//! ]
+//! ```
+//!
+//! Every allowed method is either a method name, a `prefix*` pattern or a `group:<name>` reference.
+//! The `rpc_password` still grants the access to all the methods.
+
+use bitcrypto::sha256;
+use common::log::error;
+use common::password_policy::password_policy;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use primitives::hash::H256;
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const GROUP_PREFIX: &str = "group:";
+
+#[derive(Deserialize)]
+struct ApiKeyConf {
+    name: String,
+    key: String,
+    allowed_methods: Vec<String>,
+}
+
+struct ApiKey {
+    name: String,
+    key: String,
+    /// The keys are looked up by the SHA-256 of the `userpass`,
+    /// so the lookup time doesn't tell how many leading characters of a key are guessed.
+    key_hash: H256,
+    /// The method patterns with all the groups resolved.
+    allowed_methods: Vec<String>,
+}
+
+impl ApiKey {
+    fn is_allowed(&self, method: &str) -> bool {
+        self.allowed_methods
+            .iter()
+            .any(|pattern| method_matches(pattern, method))
+    }
+}
+
+/// Checks whether the `method` matches the method name or the `prefix*` pattern.
+pub(super) fn method_matches(pattern: &str, method: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => method.starts_with(prefix),
+        None => pattern == method,
+    }
+}
+
+/// Result of the userpass check against the API keys.
+pub(super) enum Authorization {
+    Granted,
+    MethodIsNotAllowed { key_name: String },
+    InvalidUserpass,
+}
+
+#[derive(Default)]
+pub(super) struct RpcPermissionsContext {
+    api_keys: Vec<ApiKey>,
+}
+
+impl RpcPermissionsContext {
+    pub(super) fn from_ctx(ctx: &MmArc) -> Result<Arc<RpcPermissionsContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.rpc_permissions_ctx, move || {
+            // The config is validated by `validate_rpc_permissions_conf` on the start,
+            // so this can fail only if MM2 is started bypassing `lp_main`.
+            Ok(RpcPermissionsContext::from_conf(&ctx.conf).unwrap_or_else(|e| {
+                error!("Invalid RPC API keys config, only 'rpc_password' is accepted: {}", e);
+                RpcPermissionsContext::default()
+            }))
+        })))
+    }
+
+    fn from_conf(conf: &Json) -> Result<RpcPermissionsContext, String> {
+        let groups = try_s!(method_groups_from_conf(conf));
+        let keys: Vec<ApiKeyConf> = if conf["rpc_api_keys"].is_null() {
+            Vec::new()
+        } else {
+            try_s!(json::from_value(conf["rpc_api_keys"].clone()))
+        };
+
+        let mut api_keys: Vec<ApiKey> = Vec::with_capacity(keys.len());
+        for key_conf in keys {
+            if key_conf.key.is_empty() {
+                return ERR!("'{}' API key must not be empty", key_conf.name);
+            }
+            if conf["rpc_password"].as_str() == Some(key_conf.key.as_str()) {
+                return ERR!("'{}' API key must differ from 'rpc_password'", key_conf.name);
+            }
+            if api_keys
+                .iter()
+                .any(|api_key| api_key.name == key_conf.name || api_key.key == key_conf.key)
+            {
+                return ERR!("'{}' API key name or key is duplicated", key_conf.name);
+            }
+
+            let allowed_methods = try_s!(resolve_groups(key_conf.allowed_methods, &groups));
+            api_keys.push(ApiKey {
+                name: key_conf.name,
+                key_hash: sha256(key_conf.key.as_bytes()),
+                key: key_conf.key,
+                allowed_methods,
+            });
+        }
+
+        Ok(RpcPermissionsContext { api_keys })
+    }
+
+    /// Returns the name of the API key if the `userpass` is one of them.
+    pub(super) fn key_name(&self, userpass: &str) -> Option<&str> {
+        self.find_key(userpass).map(|api_key| api_key.name.as_str())
+    }
+
+    /// Checks whether the `userpass` is an API key that is allowed to call the `method`.
+    pub(super) fn authorize(&self, userpass: &str, method: &str) -> Authorization {
+        match self.find_key(userpass) {
+            Some(api_key) if api_key.is_allowed(method) => Authorization::Granted,
+            Some(api_key) => Authorization::MethodIsNotAllowed {
+                key_name: api_key.name.clone(),
+            },
+            None => Authorization::InvalidUserpass,
+        }
+    }
+
+    fn find_key(&self, userpass: &str) -> Option<&ApiKey> {
+        let userpass_hash = sha256(userpass.as_bytes());
+        self.api_keys.iter().find(|api_key| api_key.key_hash == userpass_hash)
+    }
+}
+
+pub(super) fn method_groups_from_conf(conf: &Json) -> Result<HashMap<String, Vec<String>>, String> {
+    if conf["rpc_method_groups"].is_null() {
+        return Ok(HashMap::new());
+    }
+    json::from_value(conf["rpc_method_groups"].clone()).map_err(|e| ERRL!("{}", e))
+}
+
+/// Replaces the `group:<name>` references with the methods of the groups.
+pub(super) fn resolve_groups(
+    patterns: Vec<String>,
+    groups: &HashMap<String, Vec<String>>,
+) -> Result<Vec<String>, String> {
+    let mut resolved = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+        match pattern.strip_prefix(GROUP_PREFIX) {
+            Some(group) => {
+                let methods = try_s!(groups
+                    .get(group)
+                    .ok_or_else(|| format!("Unknown RPC method group '{}'", group)));
+                // Nested groups are not supported to keep the config simple and free of cycles.
+                if methods.iter().any(|method| method.starts_with(GROUP_PREFIX)) {
+                    return ERR!("'{}' RPC method group must not reference other groups", group);
+                }
+                resolved.extend(methods.iter().cloned());
+            },
+            None => resolved.push(pattern),
+        }
+    }
+    Ok(resolved)
+}
+
+/// Validates `rpc_api_keys` and `rpc_method_groups`, so that MM2 doesn't start with the API keys silently ignored.
+pub fn validate_rpc_permissions_conf(conf: &Json) -> Result<(), String> {
+    let permissions = try_s!(RpcPermissionsContext::from_conf(conf));
+
+    let is_weak_password_accepted = conf["allow_weak_password"].as_bool() == Some(true);
+    if !is_weak_password_accepted && cfg!(not(test)) {
+        for api_key in permissions.api_keys.iter() {
+            if let Err(e) = password_policy(&api_key.key) {
+                return ERR!("'{}' API key: {}", api_key.name, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permissions_from_conf(conf: Json) -> RpcPermissionsContext { RpcPermissionsContext::from_conf(&conf).unwrap() }
+
+    #[test]
+    fn test_authorize_api_keys() {
+        let permissions = permissions_from_conf(json!({
+            "rpc_password": "admin",
+            "rpc_method_groups": {
+                "read_only": ["my_balance", "orderbook", "swap_v2::*"]
+            },
+            "rpc_api_keys": [
+                { "name": "dashboard", "key": "dashboard_key", "allowed_methods": ["group:read_only"] },
+                { "name": "bot", "key": "bot_key", "allowed_methods": ["group:read_only", "setprice"] }
+            ]
+        }));
+
+        assert!(matches!(
+            permissions.authorize("dashboard_key", "my_balance"),
+            Authorization::Granted
+        ));
+        assert!(matches!(
+            permissions.authorize("dashboard_key", "swap_v2::status"),
+            Authorization::Granted
+        ));
+        assert!(matches!(
+            permissions.authorize("dashboard_key", "setprice"),
+            Authorization::MethodIsNotAllowed { key_name } if key_name == "dashboard"
+        ));
+        assert!(matches!(
+            permissions.authorize("bot_key", "setprice"),
+            Authorization::Granted
+        ));
+        assert!(matches!(
+            permissions.authorize("bot_key", "withdraw"),
+            Authorization::MethodIsNotAllowed { .. }
+        ));
+        assert!(matches!(
+            permissions.authorize("unknown_key", "my_balance"),
+            Authorization::InvalidUserpass
+        ));
+        // `rpc_password` is checked by the dispatchers on their own.
+        assert!(matches!(
+            permissions.authorize("admin", "my_balance"),
+            Authorization::InvalidUserpass
+        ));
+    }
+
+    #[test]
+    fn test_invalid_api_keys_conf() {
+        let unknown_group = json!({
+            "rpc_api_keys": [{ "name": "bot", "key": "bot_key", "allowed_methods": ["group:trading"] }]
+        });
+        assert!(RpcPermissionsContext::from_conf(&unknown_group).is_err());
+
+        let nested_group = json!({
+            "rpc_method_groups": { "trading": ["group:read_only"], "read_only": ["my_balance"] },
+            "rpc_api_keys": [{ "name": "bot", "key": "bot_key", "allowed_methods": ["group:trading"] }]
+        });
+        assert!(RpcPermissionsContext::from_conf(&nested_group).is_err());
+
+        let same_as_password = json!({
+            "rpc_password": "bot_key",
+            "rpc_api_keys": [{ "name": "bot", "key": "bot_key", "allowed_methods": ["my_balance"] }]
+        });
+        assert!(RpcPermissionsContext::from_conf(&same_as_password).is_err());
+
+        let duplicated = json!({
+            "rpc_api_keys": [
+                { "name": "bot", "key": "bot_key", "allowed_methods": ["my_balance"] },
+                { "name": "bot", "key": "other_key", "allowed_methods": ["orderbook"] }
+            ]
+        });
+        assert!(RpcPermissionsContext::from_conf(&duplicated).is_err());
+    }
+}
@@ -0,0 +1,496 @@
+//! The WebSocket transport of the RPC and `mm2_event_stream` events.
+//!
+//! Every text frame sent by the client is handled as the body of an HTTP RPC request,
+//! i.e. it can be a legacy or an mmrpc 2.0 request, or a batch of them.
+//! The only exceptions are the [`SUBSCRIBE_METHOD`] and [`UNSUBSCRIBE_METHOD`] mmrpc 2.0 methods
+//! managing the event types that are pushed to this connection.
+//!
+//! The requests are processed concurrently, so the responses are sent in the order of completion,
+//! and the clients are expected to use the mmrpc `id` to match them with the requests.
+//! The events are sent in the same `{"_type": ..., "message": ...}` format as on the SSE endpoint.
+//!
+//! At most [`OUTGOING_QUEUE_SIZE`] messages are queued for a connection.
+//! A client that doesn't read them fast enough is disconnected once the queue is full.
+
+use super::dispatcher::check_access;
+use super::{escape_answer, process_json_request, response_from_dispatcher_error, DispatcherError, DispatcherResult};
+use common::err_to_rpc_json_string;
+use common::executor::SpawnFuture;
+use common::log::{debug, error};
+use futures::channel::mpsc::{channel, Sender};
+use futures::{select, FutureExt, SinkExt, StreamExt};
+use http::header::{CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, UPGRADE};
+use http::{Request, Response, StatusCode};
+use hyper::Body;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_rpc::mm_protocol::{MmRpcBuilder, MmRpcRequest, MmRpcVersion};
+use parking_lot::Mutex as PaMutex;
+use serde_json::{self as json, Value as Json};
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::{Message, Role};
+use tokio_tungstenite::WebSocketStream;
+
+pub const WS_ENDPOINT: &str = "/ws";
+/// Starts pushing the given event types to the connection.
+const SUBSCRIBE_METHOD: &str = "stream::subscribe";
+/// Stops pushing the given event types to the connection.
+const UNSUBSCRIBE_METHOD: &str = "stream::unsubscribe";
+/// The max number of the responses and events waiting to be sent to the client.
+const OUTGOING_QUEUE_SIZE: usize = 256;
+
+/// The responses and events are queued by several tasks via the same sender,
+/// since every clone of [`Sender`] adds a guaranteed slot to the queue.
+type OutgoingSender = Arc<PaMutex<Sender<Message>>>;
+
+/// The event types the connection is subscribed to.
+type Subscriptions = Arc<PaMutex<HashSet<String>>>;
+
+#[derive(Deserialize)]
+struct StreamSubscriptionRequest {
+    events: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct StreamSubscriptionResponse {
+    /// All the event types the connection is subscribed to after the request is applied.
+    subscribed: Vec<String>,
+}
+
+/// Completes the WebSocket handshake and spawns the connection handler.
+pub async fn handle_ws(req: Request<Body>, ctx_h: u32, client: SocketAddr) -> Response<Body> {
+    let ctx = match MmArc::from_ffi_handle(ctx_h) {
+        Ok(ctx) => ctx,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    };
+
+    let is_upgrade = req
+        .headers()
+        .get(UPGRADE)
+        .and_then(|upgrade| upgrade.to_str().ok())
+        .map_or(false, |upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+    let accept_key = match req.headers().get(SEC_WEBSOCKET_KEY) {
+        Some(key) if is_upgrade => derive_accept_key(key.as_bytes()),
+        _ => return error_response(StatusCode::BAD_REQUEST, "Expected a WebSocket upgrade request"),
+    };
+
+    let spawner = ctx.spawner();
+    spawner.spawn(async move {
+        match hyper::upgrade::on(req).await {
+            Ok(upgraded) => {
+                let ws = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                handle_connection(ctx, ws, client).await;
+            },
+            Err(e) => error!("WebSocket upgrade error: {}", e),
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(CONNECTION, "upgrade")
+        .header(UPGRADE, "websocket")
+        .header(SEC_WEBSOCKET_ACCEPT, accept_key)
+        .body(Body::empty())
+        .expect("Returning 101 should never fail.")
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(err_to_rpc_json_string(error)))
+        .expect("Returning an error should never fail.")
+}
+
+async fn handle_connection<S>(ctx: MmArc, ws: WebSocketStream<S>, client: SocketAddr)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut ws_tx, mut ws_rx) = ws.split();
+    // Both the responses and the events are sent through this channel, so there is only one writer.
+    let (outgoing_tx, mut outgoing_rx) = channel::<Message>(OUTGOING_QUEUE_SIZE);
+    let outgoing_tx = OutgoingSender::new(PaMutex::new(outgoing_tx));
+    let subscriptions = Subscriptions::default();
+
+    let send_f = async move {
+        while let Some(msg) = outgoing_rx.next().await {
+            if let Err(e) = ws_tx.send(msg).await {
+                debug!("Error sending to the WebSocket client {}: {}", client, e);
+                break;
+            }
+        }
+    };
+
+    let events_f = forward_events(ctx.clone(), client, subscriptions.clone(), outgoing_tx.clone());
+
+    let recv_f = async {
+        while let Some(msg) = ws_rx.next().await {
+            match msg {
+                Ok(Message::Text(text)) => handle_text_message(&ctx, text, client, &subscriptions, &outgoing_tx).await,
+                Ok(Message::Close(_)) => break,
+                // Pings are answered by `tungstenite`, binary frames are not supported.
+                Ok(_) => (),
+                Err(e) => {
+                    debug!("Error receiving from the WebSocket client {}: {}", client, e);
+                    break;
+                },
+            }
+        }
+    };
+
+    let mut send_f = Box::pin(send_f).fuse();
+    let mut events_f = Box::pin(events_f).fuse();
+    let mut recv_f = Box::pin(recv_f).fuse();
+    select! {
+        _ = send_f => (),
+        _ = events_f => (),
+        _ = recv_f => (),
+    }
+}
+
+/// Pushes the events the connection is subscribed to.
+/// Never completes if the event streaming is disabled, so that the RPC can still be used.
+async fn forward_events(ctx: MmArc, client: SocketAddr, subscriptions: Subscriptions, outgoing_tx: OutgoingSender) {
+    let config = match &ctx.event_stream_configuration {
+        Some(config) => config,
+        None => return futures::future::pending().await,
+    };
+
+    let mut rx = ctx
+        .stream_channel_controller
+        .clone()
+        .create_channel(config.total_active_events());
+    while let Some(event) = rx.recv().await {
+        if !subscriptions.lock().contains(event.event_type()) {
+            continue;
+        }
+
+        let data = json!({
+            "_type": event.event_type(),
+            "message": event.message(),
+        });
+        if !queue_message(&outgoing_tx, client, Message::Text(data.to_string())) {
+            break;
+        }
+    }
+}
+
+/// Queues the message to be sent to the client.
+/// Closes the connection if the queue is full, since the client doesn't keep up with the messages.
+///
+/// Returns `false` if the connection is closed.
+fn queue_message(outgoing_tx: &OutgoingSender, client: SocketAddr, msg: Message) -> bool {
+    let mut outgoing_tx = outgoing_tx.lock();
+    match outgoing_tx.try_send(msg) {
+        Ok(()) => true,
+        Err(e) if e.is_full() => {
+            debug!(
+                "WebSocket client {} doesn't read the messages, {} messages are queued. Disconnecting",
+                client, OUTGOING_QUEUE_SIZE
+            );
+            // Closing the channel makes the sending loop stop once the queued messages are sent.
+            outgoing_tx.close_channel();
+            false
+        },
+        Err(_) => false,
+    }
+}
+
+async fn handle_text_message(
+    ctx: &MmArc,
+    text: String,
+    client: SocketAddr,
+    subscriptions: &Subscriptions,
+    outgoing_tx: &OutgoingSender,
+) {
+    // The same input validation as for the HTTP requests.
+    let is_invalid_input = text.chars().any(|c| c == '<' || c == '>' || c == '&');
+    if is_invalid_input {
+        queue_message(
+            outgoing_tx,
+            client,
+            Message::Text(err_to_rpc_json_string("Invalid input")),
+        );
+        return;
+    }
+    let req_json: Json = match json::from_str(&text) {
+        Ok(req_json) => req_json,
+        Err(e) => {
+            queue_message(
+                outgoing_tx,
+                client,
+                Message::Text(err_to_rpc_json_string(&e.to_string())),
+            );
+            return;
+        },
+    };
+
+    let method = req_json["method"].as_str();
+    if method == Some(SUBSCRIBE_METHOD) || method == Some(UNSUBSCRIBE_METHOD) {
+        let response = process_subscription_request(ctx, req_json, client, subscriptions).await;
+        queue_message(outgoing_tx, client, Message::Text(response));
+        return;
+    }
+
+    // Don't block the connection while the request is processed.
+    let ctx = ctx.clone();
+    let outgoing_tx = outgoing_tx.clone();
+    let spawner = ctx.spawner();
+    spawner.spawn(async move {
+        let response = match process_json_request(ctx, req_json, client).await {
+            Ok(response) => match String::from_utf8(response.into_body()) {
+                Ok(body) => escape_answer(body).into_owned(),
+                Err(_) => err_to_rpc_json_string("Non UTF-8 output"),
+            },
+            Err(e) => {
+                error!("RPC error response: {}", e);
+                err_to_rpc_json_string(&e)
+            },
+        };
+        queue_message(&outgoing_tx, client, Message::Text(response));
+    });
+}
+
+async fn process_subscription_request(
+    ctx: &MmArc,
+    req_json: Json,
+    client: SocketAddr,
+    subscriptions: &Subscriptions,
+) -> String {
+    let id = req_json["id"].as_u64().map(|id| id as usize);
+    let request: MmRpcRequest = match json::from_value(req_json) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = MmError::new(DispatcherError::from(e));
+            return response_body(response_from_dispatcher_error(error, MmRpcVersion::V2, id).into_body());
+        },
+    };
+
+    match update_subscriptions(ctx, &request, client, subscriptions).await {
+        Ok(response) => {
+            let response = MmRpcBuilder::<_, DispatcherError>::ok(response)
+                .version(request.mmrpc)
+                .id(request.id)
+                .build();
+            response_body(response.serialize_http_response().into_body())
+        },
+        Err(e) => response_body(response_from_dispatcher_error(e, request.mmrpc, request.id).into_body()),
+    }
+}
+
+async fn update_subscriptions(
+    ctx: &MmArc,
+    request: &MmRpcRequest,
+    client: SocketAddr,
+    subscriptions: &Subscriptions,
+) -> DispatcherResult<StreamSubscriptionResponse> {
+    let local_only = ctx.conf["rpc_local_only"].as_bool().unwrap_or(true);
+    check_access(ctx, request, &client, local_only).await?;
+
+    let config = ctx
+        .event_stream_configuration
+        .as_ref()
+        .or_mm_err(|| DispatcherError::InvalidRequest("Event streaming is not enabled in the config".to_owned()))?;
+    let params: StreamSubscriptionRequest = json::from_value(request.params.clone())?;
+    // The events streamed per subject, e.g. `ORDERBOOK:KMD:BTC`, are activated by their name preceding the first `:`.
+    if let Some(inactive) = params
+        .events
+        .iter()
+        .find(|event| config.get_event(event.split(':').next().unwrap_or_default()).is_none())
+    {
+        let error = format!("'{}' event is not active", inactive);
+        return MmError::err(DispatcherError::InvalidRequest(error));
+    }
+
+    let mut subscriptions = subscriptions.lock();
+    if request.method == SUBSCRIBE_METHOD {
+        subscriptions.extend(params.events);
+    } else {
+        for event in params.events.iter() {
+            subscriptions.remove(event);
+        }
+    }
+
+    let mut subscribed: Vec<_> = subscriptions.iter().cloned().collect();
+    subscribed.sort();
+    Ok(StreamSubscriptionResponse { subscribed })
+}
+
+fn response_body(body: Vec<u8>) -> String {
+    String::from_utf8(body).unwrap_or_else(|_| err_to_rpc_json_string("Non UTF-8 output"))
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use common::block_on;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use mm2_event_stream::Event;
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio::io::{duplex, DuplexStream};
+
+    fn localhost() -> SocketAddr { SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 7783) }
+
+    fn ws_test_ctx() -> MmArc {
+        let conf = json!({
+            "rpc_password": "pass",
+            "event_stream_configuration": {
+                "active_events": { "SWAP_STATUS": {}, "ORDERBOOK": {} }
+            }
+        });
+        MmCtxBuilder::new().with_conf(conf).into_mm_arc()
+    }
+
+    fn stream_request(method: &str, events: &[&str], id: usize) -> Message {
+        let request = json!({
+            "mmrpc": "2.0",
+            "userpass": "pass",
+            "method": method,
+            "params": { "events": events },
+            "id": id,
+        });
+        Message::Text(request.to_string())
+    }
+
+    async fn next_json(client: &mut WebSocketStream<DuplexStream>) -> Json {
+        match client.next().await {
+            Some(Ok(Message::Text(text))) => json::from_str(&text).unwrap(),
+            msg => panic!("Expected a text message, found {:?}", msg),
+        }
+    }
+
+    /// Runs the connection handler until `client_f` completes.
+    fn with_connection<F, Fut>(ctx: &MmArc, client_f: F)
+    where
+        F: FnOnce(WebSocketStream<DuplexStream>) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        let (client_io, server_io) = duplex(64 * 1024);
+        block_on(async {
+            let server = WebSocketStream::from_raw_socket(server_io, Role::Server, None).await;
+            let client = WebSocketStream::from_raw_socket(client_io, Role::Client, None).await;
+            let mut server_f = Box::pin(handle_connection(ctx.clone(), server, localhost())).fuse();
+            let mut client_f = Box::pin(client_f(client)).fuse();
+            select! {
+                _ = server_f => panic!("The connection mustn't be closed by the server"),
+                _ = client_f => (),
+            }
+        });
+    }
+
+    #[test]
+    fn test_ws_subscribe_unsubscribe() {
+        let ctx = ws_test_ctx();
+        with_connection(&ctx, |mut client| async move {
+            client
+                .send(stream_request(
+                    SUBSCRIBE_METHOD,
+                    &["SWAP_STATUS", "ORDERBOOK:KMD:BTC"],
+                    1,
+                ))
+                .await
+                .unwrap();
+            let response = next_json(&mut client).await;
+            assert_eq!(response["id"], 1);
+            assert_eq!(
+                response["result"]["subscribed"],
+                json!(["ORDERBOOK:KMD:BTC", "SWAP_STATUS"])
+            );
+
+            // The event of the pair the connection isn't subscribed to must be skipped.
+            let controller = &ctx.stream_channel_controller;
+            controller
+                .broadcast(Event::new("ORDERBOOK:KMD:LTC".to_owned(), "skipped".to_owned()))
+                .await;
+            controller
+                .broadcast(Event::new("SWAP_STATUS".to_owned(), "streamed".to_owned()))
+                .await;
+            let event = next_json(&mut client).await;
+            assert_eq!(event, json!({ "_type": "SWAP_STATUS", "message": "streamed" }));
+
+            client
+                .send(stream_request(UNSUBSCRIBE_METHOD, &["SWAP_STATUS"], 2))
+                .await
+                .unwrap();
+            let response = next_json(&mut client).await;
+            assert_eq!(response["id"], 2);
+            assert_eq!(response["result"]["subscribed"], json!(["ORDERBOOK:KMD:BTC"]));
+
+            controller
+                .broadcast(Event::new("SWAP_STATUS".to_owned(), "skipped".to_owned()))
+                .await;
+            controller
+                .broadcast(Event::new("ORDERBOOK:KMD:BTC".to_owned(), "streamed".to_owned()))
+                .await;
+            let event = next_json(&mut client).await;
+            assert_eq!(event, json!({ "_type": "ORDERBOOK:KMD:BTC", "message": "streamed" }));
+        });
+    }
+
+    #[test]
+    fn test_ws_subscribe_inactive_event() {
+        let ctx = ws_test_ctx();
+        with_connection(&ctx, |mut client| async move {
+            client
+                .send(stream_request(SUBSCRIBE_METHOD, &["SWAP_STATUS", "NETWORK"], 1))
+                .await
+                .unwrap();
+            let response = next_json(&mut client).await;
+            assert_eq!(response["id"], 1);
+            assert_eq!(response["error_type"], "InvalidRequest");
+
+            // Nothing is subscribed if any of the events is inactive.
+            client.send(stream_request(SUBSCRIBE_METHOD, &[], 2)).await.unwrap();
+            let response = next_json(&mut client).await;
+            assert_eq!(response["result"]["subscribed"], json!([]));
+        });
+    }
+
+    #[test]
+    fn test_ws_subscribe_wrong_userpass() {
+        let ctx = ws_test_ctx();
+        with_connection(&ctx, |mut client| async move {
+            let request = json!({
+                "mmrpc": "2.0",
+                "userpass": "wrong",
+                "method": SUBSCRIBE_METHOD,
+                "params": { "events": ["SWAP_STATUS"] },
+                "id": 1,
+            });
+            client.send(Message::Text(request.to_string())).await.unwrap();
+            let response = next_json(&mut client).await;
+            assert!(response["error"].is_string(), "Unexpected response {}", response);
+        });
+    }
+
+    #[test]
+    fn test_queue_message_closes_full_queue() {
+        let (outgoing_tx, mut outgoing_rx) = channel::<Message>(OUTGOING_QUEUE_SIZE);
+        let outgoing_tx = OutgoingSender::new(PaMutex::new(outgoing_tx));
+
+        let mut queued = 0;
+        while queue_message(&outgoing_tx, localhost(), Message::Text(queued.to_string())) {
+            queued += 1;
+            assert!(queued <= OUTGOING_QUEUE_SIZE + 1, "The queue must be bounded");
+        }
+        assert!(queued >= OUTGOING_QUEUE_SIZE);
+
+        // The channel is closed, so nothing is queued even when there is free space again.
+        assert!(outgoing_rx.try_next().unwrap().is_some());
+        assert!(!queue_message(
+            &outgoing_tx,
+            localhost(),
+            Message::Text("closed".to_owned())
+        ));
+
+        // The queued messages are still delivered before the channel is reported as closed.
+        let mut received = 1;
+        while let Some(_msg) = block_on(outgoing_rx.next()) {
+            received += 1;
+        }
+        assert_eq!(received, queued);
+    }
+}
@@ -20,6 +20,8 @@ fn send_and_refund_taker_funding_timelock() {
     let send_args = SendTakerFundingArgs {
         time_lock,
         taker_secret_hash,
+        maker_secret_hash: &[0; 20],
+        payment_time_lock: 0,
         maker_pub,
         dex_fee_amount: "0.01".parse().unwrap(),
         premium_amount: "0.1".parse().unwrap(),
@@ -45,6 +47,8 @@ fn send_and_refund_taker_funding_timelock() {
         funding_tx: &taker_funding_utxo_tx,
         time_lock,
         taker_secret_hash,
+        maker_secret_hash: &[0; 20],
+        payment_time_lock: 0,
         other_pub: maker_pub,
         dex_fee_amount: "0.01".parse().unwrap(),
         premium_amount: "0.1".parse().unwrap(),
@@ -79,6 +83,8 @@ fn send_and_refund_taker_funding_secret() {
     let send_args = SendTakerFundingArgs {
         time_lock,
         taker_secret_hash: taker_secret_hash.as_slice(),
+        maker_secret_hash: &[0; 20],
+        payment_time_lock: 0,
         maker_pub,
         dex_fee_amount: "0.01".parse().unwrap(),
         premium_amount: "0.1".parse().unwrap(),
@@ -104,6 +110,8 @@ fn send_and_refund_taker_funding_secret() {
         funding_tx: &taker_funding_utxo_tx,
         time_lock,
         taker_secret_hash: taker_secret_hash.as_slice(),
+        maker_secret_hash: &[0; 20],
+        payment_time_lock: 0,
         other_pub: maker_pub,
         dex_fee_amount: "0.01".parse().unwrap(),
         premium_amount: "0.1".parse().unwrap(),
@@ -141,6 +149,8 @@ fn send_and_spend_taker_funding() {
     let send_args = SendTakerFundingArgs {
         time_lock: funding_time_lock,
         taker_secret_hash,
+        maker_secret_hash: &[0; 20],
+        payment_time_lock: 0,
         maker_pub,
         dex_fee_amount: "0.01".parse().unwrap(),
         premium_amount: "0.1".parse().unwrap(),
@@ -166,6 +176,8 @@ fn send_and_spend_taker_funding() {
         funding_tx: &taker_funding_utxo_tx,
         time_lock: funding_time_lock,
         taker_secret_hash,
+        maker_secret_hash: &[0; 20],
+        payment_time_lock: 0,
         other_pub: taker_pub,
         dex_fee_amount: "0.01".parse().unwrap(),
         premium_amount: "0.1".parse().unwrap(),
@@ -1247,7 +1247,7 @@ fn test_watcher_validate_taker_fee_eth() {
     let lock_duration = get_payment_locktime();
 
     let taker_coin = eth_distributor();
-    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]);
+    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]).unwrap();
     let taker_pubkey = taker_keypair.public();
 
     let taker_amount = MmNumber::from((1, 1));
@@ -1350,7 +1350,7 @@ fn test_watcher_validate_taker_fee_erc20() {
 
     let seed = get_passphrase!(".env.client", "ALICE_PASSPHRASE").unwrap();
     let taker_coin = generate_jst_with_seed(&seed);
-    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]);
+    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]).unwrap();
     let taker_pubkey = taker_keypair.public();
 
     let taker_amount = MmNumber::from((1, 1));
@@ -1662,7 +1662,7 @@ fn test_watcher_validate_taker_payment_eth() {
     let timeout = wait_until_sec(120); // timeout if test takes more than 120 seconds to run
 
     let taker_coin = eth_distributor();
-    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]);
+    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]).unwrap();
     let taker_pub = taker_keypair.public();
 
     let maker_seed = get_passphrase!(".env.seed", "BOB_PASSPHRASE").unwrap();
@@ -1906,7 +1906,7 @@ fn test_watcher_validate_taker_payment_erc20() {
 
     let seed = get_passphrase!(".env.client", "ALICE_PASSPHRASE").unwrap();
     let taker_coin = generate_jst_with_seed(&seed);
-    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]);
+    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]).unwrap();
     let taker_pub = taker_keypair.public();
 
     let maker_seed = get_passphrase!(".env.seed", "BOB_PASSPHRASE").unwrap();
@@ -2232,7 +2232,7 @@ fn test_taker_validates_taker_payment_refund_eth() {
     let timeout = wait_until_sec(120); // timeout if test takes more than 120 seconds to run
 
     let taker_coin = eth_distributor();
-    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]);
+    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]).unwrap();
     let taker_pub = taker_keypair.public();
 
     let maker_seed = get_passphrase!(".env.client", "BOB_PASSPHRASE").unwrap();
@@ -2553,7 +2553,7 @@ fn test_taker_validates_taker_payment_refund_erc20() {
 
     let seed = get_passphrase!(".env.client", "ALICE_PASSPHRASE").unwrap();
     let taker_coin = generate_jst_with_seed(&seed);
-    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]);
+    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]).unwrap();
     let taker_pub = taker_keypair.public();
 
     let maker_seed = get_passphrase!(".env.client", "BOB_PASSPHRASE").unwrap();
@@ -2760,7 +2760,7 @@ fn test_taker_validates_maker_payment_spend_eth() {
     let timeout = wait_until_sec(120); // timeout if test takes more than 120 seconds to run
 
     let taker_coin = eth_distributor();
-    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]);
+    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]).unwrap();
     let taker_pub = taker_keypair.public();
 
     let maker_seed = get_passphrase!(".env.client", "BOB_PASSPHRASE").unwrap();
@@ -3081,7 +3081,7 @@ fn test_taker_validates_maker_payment_spend_erc20() {
 
     let taker_seed = get_passphrase!(".env.client", "ALICE_PASSPHRASE").unwrap();
     let taker_coin = generate_jst_with_seed(&taker_seed);
-    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]);
+    let taker_keypair = taker_coin.derive_htlc_key_pair(&[]).unwrap();
     let taker_pub = taker_keypair.public();
 
     let maker_seed = get_passphrase!(".env.client", "BOB_PASSPHRASE").unwrap();
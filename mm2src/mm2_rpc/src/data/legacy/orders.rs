@@ -25,6 +25,8 @@ pub struct SellBuyRequest {
     pub dest_pub_key: H256Json,
     #[serde(default)]
     pub match_by: MatchBy,
+    /// Only the makers with at least this reputation score, see the `pubkey_reputation` RPC, are matched.
+    pub min_maker_reputation: Option<MmNumber>,
     #[serde(default)]
     pub order_type: OrderType,
     pub base_confs: Option<u64>,
@@ -77,12 +79,30 @@ pub enum TakerAction {
 pub enum OrderType {
     FillOrKill,
     GoodTillCancelled,
+    /// The order is cancelled automatically at `expires_at` (UNIX timestamp in seconds).
+    /// A taker order is converted to a maker order on timeout and keeps the expiration time.
+    GoodTillTime {
+        expires_at: u64,
+    },
+    /// Taker only. The order is cancelled if it is not matched within a short period,
+    /// it is never converted to a maker order.
+    ImmediateOrCancel,
 }
 
 impl Default for OrderType {
     fn default() -> Self { OrderType::GoodTillCancelled }
 }
 
+impl OrderType {
+    /// Returns the expiration timestamp (in seconds) if the order has one.
+    pub fn expires_at(&self) -> Option<u64> {
+        match self {
+            OrderType::GoodTillTime { expires_at } => Some(*expires_at),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum MatchBy {
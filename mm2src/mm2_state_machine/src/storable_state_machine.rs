@@ -65,10 +65,11 @@ pub trait StateMachineStorage: Send + Sync {
 }
 
 /// A struct representing a restored state machine.
-#[allow(dead_code)]
 pub struct RestoredMachine<M> {
-    machine: M,
-    current_state: Box<dyn State<StateMachine = M>>,
+    /// The state machine recreated from the storage.
+    pub machine: M,
+    /// The state the machine should continue its execution from.
+    pub current_state: Box<dyn State<StateMachine = M>>,
 }
 
 /// A trait for storable state machines.
@@ -78,6 +79,8 @@ pub trait StorableStateMachine: Send + Sized + 'static {
     type Storage: StateMachineStorage;
     /// The result type of the state machine.
     type Result: Send;
+    /// The type of additional context required to recreate the state machine from storage.
+    type RecreateCtx: Send;
 
     /// Gets a mutable reference to the storage for the state machine.
     fn storage(&mut self) -> &mut Self::Storage;
@@ -91,13 +94,15 @@ pub trait StorableStateMachine: Send + Sized + 'static {
     ///
     /// - `id`: The unique identifier of the state machine to be restored.
     /// - `storage`: The storage containing the state machine's data.
+    /// - `recreate_ctx`: The additional context required to recreate the state machine (e.g. coins instances).
     ///
     /// # Returns
     ///
     /// A `Result` containing a `RestoredMachine` or an error.
-    fn restore_from_storage(
+    async fn restore_from_storage(
         id: <Self::Storage as StateMachineStorage>::MachineId,
         storage: Self::Storage,
+        recreate_ctx: Self::RecreateCtx,
     ) -> Result<RestoredMachine<Self>, <Self::Storage as StateMachineStorage>::Error>;
 
     /// Stores an event for the state machine.
@@ -313,17 +318,20 @@ mod tests {
         }
     }
 
+    #[async_trait]
     impl StorableStateMachine for StorableStateMachineTest {
         type Storage = StorageTest;
         type Result = ();
+        type RecreateCtx = ();
 
         fn storage(&mut self) -> &mut Self::Storage { &mut self.storage }
 
         fn id(&self) -> <Self::Storage as StateMachineStorage>::MachineId { self.id }
 
-        fn restore_from_storage(
+        async fn restore_from_storage(
             id: <Self::Storage as StateMachineStorage>::MachineId,
             storage: Self::Storage,
+            _recreate_ctx: Self::RecreateCtx,
         ) -> Result<RestoredMachine<Self>, <Self::Storage as StateMachineStorage>::Error> {
             let events = storage.events_unfinished.get(&id).unwrap();
             let current_state: Box<dyn State<StateMachine = Self>> = match events.last() {
@@ -430,7 +438,7 @@ mod tests {
         let RestoredMachine {
             mut machine,
             current_state,
-        } = StorableStateMachineTest::restore_from_storage(id, storage).unwrap();
+        } = block_on(StorableStateMachineTest::restore_from_storage(id, storage, ())).unwrap();
 
         block_on(machine.run(current_state)).unwrap();
 